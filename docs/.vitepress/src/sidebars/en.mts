@@ -72,6 +72,49 @@ export const sidebar = [
                     { text: "LastWill Message", link: "" },
                     { text: "Exclusive Subscription", link: "" },
                     { text: "Delayed Publish", link: "/RobustMQ-MQTT/DelayMessage.md" },
+                    { text: "Duplicate Client ID", link: "/RobustMQ-MQTT/DuplicateClientId.md" },
+                    { text: "Topic Cleanup", link: "/RobustMQ-MQTT/TopicCleanup.md" },
+                    { text: "Subscribe Authorization", link: "/RobustMQ-MQTT/SubscribeAuthorization.md" },
+                    { text: "Snapshot", link: "/RobustMQ-MQTT/Snapshot.md" },
+                    { text: "Client Redirect", link: "/RobustMQ-MQTT/ClientRedirect.md" },
+                    { text: "Node Draining", link: "/RobustMQ-MQTT/NodeDraining.md" },
+                    { text: "Message Sampling", link: "/RobustMQ-MQTT/MessageSampling.md" },
+                    { text: "Rate Limit", link: "/RobustMQ-MQTT/RateLimit.md" },
+                    { text: "Session Restore", link: "/RobustMQ-MQTT/SessionRestore.md" },
+                    { text: "Session Tiering", link: "/RobustMQ-MQTT/SessionTiering.md" },
+                    { text: "Session Subscriptions", link: "/RobustMQ-MQTT/SessionSubscriptions.md" },
+                    { text: "Session Expiry", link: "/RobustMQ-MQTT/SessionExpiry.md" },
+                    { text: "Subscribe Snapshot", link: "/RobustMQ-MQTT/SubscribeSnapshot.md" },
+                    { text: "Will Grace Period", link: "/RobustMQ-MQTT/WillGracePeriod.md" },
+                    { text: "ACL Default Policy", link: "/RobustMQ-MQTT/AclDefaultPolicy.md" },
+                    { text: "ACL Backup", link: "/RobustMQ-MQTT/AclBackup.md" },
+                    { text: "In-flight QoS 2", link: "/RobustMQ-MQTT/InflightQos2.md" },
+                    { text: "Connection Limit", link: "/RobustMQ-MQTT/ConnectionLimit.md" },
+                    { text: "Certificate Username", link: "/RobustMQ-MQTT/CertUsername.md" },
+                    { text: "Connector Kafka", link: "/RobustMQ-MQTT/ConnectorKafka.md" },
+                    { text: "Connector Limit", link: "/RobustMQ-MQTT/ConnectorLimit.md" },
+                    { text: "Connector Retry", link: "/RobustMQ-MQTT/ConnectorRetry.md" },
+                    { text: "Connector Status", link: "/RobustMQ-MQTT/ConnectorStatus.md" },
+                    { text: "Connector Transform", link: "/RobustMQ-MQTT/ConnectorTransform.md" },
+                    { text: "Client Group", link: "/RobustMQ-MQTT/ClientGroup.md" },
+                    { text: "Client Pool", link: "/RobustMQ-MQTT/ClientPool.md" },
+                    { text: "Message Dedup", link: "/RobustMQ-MQTT/MessageDedup.md" },
+                    { text: "Replay To Client", link: "/RobustMQ-MQTT/ReplayToClient.md" },
+                    { text: "User Single Session", link: "/RobustMQ-MQTT/UserSingleSession.md" },
+                    { text: "Describe Subscription", link: "/RobustMQ-MQTT/DescribeSubscription.md" },
+                    { text: "Auto Subscribe Priority", link: "/RobustMQ-MQTT/AutoSubscribePriority.md" },
+                    { text: "Schema Inference", link: "/RobustMQ-MQTT/SchemaInference.md" },
+                    { text: "Schema Compatibility", link: "/RobustMQ-MQTT/SchemaCompatibility.md" },
+                    { text: "Protobuf Schema", link: "/RobustMQ-MQTT/ProtobufSchema.md" },
+                    { text: "Disconnect Client", link: "/RobustMQ-MQTT/DisconnectClient.md" },
+                    { text: "Batch Create User", link: "/RobustMQ-MQTT/BatchCreateUser.md" },
+                    { text: "Blacklist", link: "/RobustMQ-MQTT/Blacklist.md" },
+                    { text: "WebSocket Subprotocol", link: "/RobustMQ-MQTT/WebSocketSubprotocol.md" },
+                    { text: "QUIC Connection Migration", link: "/RobustMQ-MQTT/QuicMigration.md" },
+                    { text: "Top Talkers", link: "/RobustMQ-MQTT/TopTalkers.md" },
+                    { text: "gRPC Health and Reflection", link: "/RobustMQ-MQTT/GrpcHealth.md" },
+                    { text: "HTTP Authentication", link: "/RobustMQ-MQTT/HttpAuth.md" },
+                    { text: "JWT Authentication", link: "/RobustMQ-MQTT/JwtAuth.md" },
                     { text: "Automatic subscription", link: "" },
                     { text: "Topic Rewriting", link: "" },
                     { text: "Wildcard Subscription", link: "" },
@@ -18,16 +18,18 @@
 use common_base::tools::unique_id;
 use common_config::mqtt::config::BrokerMqttConfig;
 use grpc_clients::mqtt::admin::call::{
-    mqtt_broker_bind_schema, mqtt_broker_cluster_status, mqtt_broker_create_acl,
-    mqtt_broker_create_blacklist, mqtt_broker_create_connector, mqtt_broker_create_schema,
-    mqtt_broker_create_topic_rewrite_rule, mqtt_broker_create_user, mqtt_broker_delete_acl,
-    mqtt_broker_delete_auto_subscribe_rule, mqtt_broker_delete_blacklist,
+    mqtt_broker_ack_system_alarm, mqtt_broker_bind_schema, mqtt_broker_cluster_status,
+    mqtt_broker_create_acl, mqtt_broker_create_blacklist, mqtt_broker_create_connector,
+    mqtt_broker_create_schema, mqtt_broker_create_topic_rewrite_rule, mqtt_broker_create_user,
+    mqtt_broker_delete_acl, mqtt_broker_delete_auto_subscribe_rule, mqtt_broker_delete_blacklist,
     mqtt_broker_delete_connector, mqtt_broker_delete_schema, mqtt_broker_delete_topic_rewrite_rule,
     mqtt_broker_delete_user, mqtt_broker_enable_flapping_detect, mqtt_broker_get_cluster_config,
-    mqtt_broker_list_acl, mqtt_broker_list_auto_subscribe_rule, mqtt_broker_list_bind_schema,
-    mqtt_broker_list_blacklist, mqtt_broker_list_connection, mqtt_broker_list_connector,
-    mqtt_broker_list_schema, mqtt_broker_list_session, mqtt_broker_list_slow_subscribe,
-    mqtt_broker_list_system_alarm, mqtt_broker_list_topic, mqtt_broker_list_user,
+    mqtt_broker_get_system_alarm_config, mqtt_broker_list_acl,
+    mqtt_broker_list_auto_subscribe_rule, mqtt_broker_list_bind_schema, mqtt_broker_list_blacklist,
+    mqtt_broker_list_connection, mqtt_broker_list_connector, mqtt_broker_list_schema,
+    mqtt_broker_list_session, mqtt_broker_list_session_subscriptions,
+    mqtt_broker_list_slow_subscribe, mqtt_broker_list_system_alarm, mqtt_broker_list_topic,
+    mqtt_broker_list_user, mqtt_broker_pause_connector, mqtt_broker_resume_connector,
     mqtt_broker_set_auto_subscribe_rule, mqtt_broker_set_cluster_config,
     mqtt_broker_set_system_alarm_config, mqtt_broker_unbind_schema, mqtt_broker_update_connector,
     mqtt_broker_update_schema,
@@ -39,16 +41,19 @@
 use paho_mqtt::{DisconnectOptionsBuilder, MessageBuilder, Properties, PropertyCode, ReasonCode};
 use prettytable::{row, Table};
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterStatusRequest, CreateAclRequest, CreateBlacklistRequest, CreateTopicRewriteRuleRequest,
-    CreateUserRequest, DeleteAclRequest, DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest,
-    DeleteTopicRewriteRuleRequest, DeleteUserRequest, EnableFlappingDetectRequest,
-    GetClusterConfigRequest, ListAclRequest, ListAutoSubscribeRuleRequest, ListBlacklistRequest,
-    ListConnectionRequest, ListSessionRequest, ListSlowSubscribeRequest, ListSystemAlarmRequest,
+    AckSystemAlarmRequest, AlarmWebhookRaw, ClusterStatusRequest, CreateAclRequest,
+    CreateBlacklistRequest, CreateTopicRewriteRuleRequest, CreateUserRequest, DeleteAclRequest,
+    DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest, DeleteTopicRewriteRuleRequest,
+    DeleteUserRequest, EnableFlappingDetectRequest, GetClusterConfigRequest,
+    GetSystemAlarmConfigRequest, ListAclRequest, ListAutoSubscribeRuleRequest,
+    ListBlacklistRequest, ListConnectionRequest, ListSessionRequest,
+    ListSessionSubscriptionsRequest, ListSlowSubscribeRequest, ListSystemAlarmRequest,
     ListTopicRequest, ListUserRequest, MqttBindSchemaRequest, MqttCreateConnectorRequest,
     MqttCreateSchemaRequest, MqttDeleteConnectorRequest, MqttDeleteSchemaRequest,
     MqttListBindSchemaRequest, MqttListConnectorRequest, MqttListSchemaRequest,
-    MqttUnbindSchemaRequest, MqttUpdateConnectorRequest, MqttUpdateSchemaRequest,
-    SetAutoSubscribeRuleRequest, SetClusterConfigRequest, SetSystemAlarmConfigRequest,
+    MqttPauseConnectorRequest, MqttResumeConnectorRequest, MqttUnbindSchemaRequest,
+    MqttUpdateConnectorRequest, MqttUpdateSchemaRequest, SetAutoSubscribeRuleRequest,
+    SetClusterConfigRequest, SetSystemAlarmConfigRequest, SystemAlarmThresholdRaw,
 };
 use std::str::FromStr;
 use std::sync::Arc;
@@ -75,7 +80,8 @@ pub enum MqttActionType {
     GetClusterConfig,
 
     // session
-    ListSession,
+    ListSession(ListSessionRequest),
+    ListSessionSubscriptions(ListSessionSubscriptionsRequest),
 
     // user admin
     ListUser,
@@ -104,7 +110,9 @@ pub enum MqttActionType {
 
     // system alarm
     SetSystemAlarmConfig(SetSystemAlarmConfigRequest),
+    GetSystemAlarmConfig(GetSystemAlarmConfigRequest),
     ListSystemAlarm(ListSystemAlarmRequest),
+    AckSystemAlarm(AckSystemAlarmRequest),
 
     // topic rewrite rule
     CreateTopicRewriteRule(CreateTopicRewriteRuleRequest),
@@ -123,6 +131,8 @@ pub enum MqttActionType {
     CreateConnector(MqttCreateConnectorRequest),
     UpdateConnector(MqttUpdateConnectorRequest),
     DeleteConnector(MqttDeleteConnectorRequest),
+    PauseConnector(MqttPauseConnectorRequest),
+    ResumeConnector(MqttResumeConnectorRequest),
 
     // schema
     ListSchema(MqttListSchemaRequest),
@@ -156,8 +166,13 @@ pub async fn start(&self, params: MqttCliCommandParam) {
         let client_pool = Arc::new(ClientPool::new(100));
         match params.action {
             // cluster status
-            MqttActionType::ListSession => {
-                self.list_session(&client_pool, params.clone()).await;
+            MqttActionType::ListSession(ref request) => {
+                self.list_session(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::ListSessionSubscriptions(ref request) => {
+                self.list_session_subscriptions(&client_pool, params.clone(), request.clone())
+                    .await;
             }
 
             // cluster config
@@ -222,6 +237,14 @@ pub async fn start(&self, params: MqttCliCommandParam) {
                 self.delete_connector(&client_pool, params.clone(), request.clone())
                     .await;
             }
+            MqttActionType::PauseConnector(ref request) => {
+                self.pause_connector(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::ResumeConnector(ref request) => {
+                self.resume_connector(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
             MqttActionType::UpdateConnector(ref request) => {
                 self.update_connector(&client_pool, params.clone(), request.clone())
                     .await;
@@ -301,11 +324,19 @@ pub async fn start(&self, params: MqttCliCommandParam) {
                     .await;
             }
             MqttActionType::SetSystemAlarmConfig(ref request) => {
-                self.set_system_alarm_config(&client_pool, params.clone(), *request)
+                self.set_system_alarm_config(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::GetSystemAlarmConfig(ref request) => {
+                self.get_system_alarm_config(&client_pool, params.clone(), *request)
                     .await;
             }
             MqttActionType::ListSystemAlarm(ref request) => {
-                self.list_system_alarm(&client_pool, params.clone(), *request)
+                self.list_system_alarm(&client_pool, params.clone(), request.clone())
+                    .await;
+            }
+            MqttActionType::AckSystemAlarm(ref request) => {
+                self.ack_system_alarm(&client_pool, params.clone(), request.clone())
                     .await;
             }
         }
@@ -501,9 +532,13 @@ async fn get_cluster_config(&self, client_pool: &ClientPool, params: MqttCliComm
     }
 
     // ------------ list session ------------
-    async fn list_session(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
-        let request = ListSessionRequest { options: None };
-        match mqtt_broker_list_session(client_pool, &grpc_addr(params.server), request).await {
+    async fn list_session(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListSessionRequest,
+    ) {
+        match mqtt_broker_list_session(client_pool, &grpc_addr(params.server), cli_request).await {
             Ok(data) => {
                 let mut table = Table::new();
                 table.set_titles(row![
@@ -515,7 +550,9 @@ async fn list_session(&self, client_pool: &ClientPool, params: MqttCliCommandPar
                     "connection_id",
                     "broker_id",
                     "reconnect_time",
-                    "distinct_time"
+                    "distinct_time",
+                    "takeover_count",
+                    "last_takeover_at"
                 ]);
                 for blacklist in data.sessions {
                     table.add_row(row![
@@ -528,6 +565,54 @@ async fn list_session(&self, client_pool: &ClientPool, params: MqttCliCommandPar
                         blacklist.broker_id.unwrap_or_default(),
                         blacklist.reconnect_time.unwrap_or_default(),
                         blacklist.distinct_time.unwrap_or_default(),
+                        blacklist.takeover_count,
+                        blacklist.last_takeover_at.unwrap_or_default(),
+                    ]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker cluster normal exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_session_subscriptions(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: ListSessionSubscriptionsRequest,
+    ) {
+        match mqtt_broker_list_session_subscriptions(
+            client_pool,
+            &grpc_addr(params.server),
+            cli_request,
+        )
+        .await
+        {
+            Ok(data) => {
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "path",
+                    "qos",
+                    "no_local",
+                    "preserve_retain",
+                    "retain_handling",
+                    "subscription_identifier"
+                ]);
+                for subscription in data.subscriptions {
+                    table.add_row(row![
+                        subscription.path,
+                        subscription.qos,
+                        subscription.no_local,
+                        subscription.preserve_retain,
+                        subscription.retain_handling,
+                        subscription
+                            .subscription_identifier
+                            .map(|id| id.to_string())
+                            .unwrap_or_default(),
                     ]);
                 }
                 // output cmd
@@ -786,7 +871,7 @@ async fn list_blacklist(&self, client_pool: &ClientPool, params: MqttCliCommandP
 
     // -------------- list connections --------------
     async fn list_connections(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
-        let request = ListConnectionRequest {};
+        let request = ListConnectionRequest::default();
         match mqtt_broker_list_connection(client_pool, &grpc_addr(params.server), request).await {
             Ok(data) => {
                 let mut table = Table::new();
@@ -796,6 +881,7 @@ async fn list_connections(&self, client_pool: &ClientPool, params: MqttCliComman
                     "connection_id",
                     "connection_type",
                     "protocol",
+                    "mqtt_protocol_version",
                     "source_addr",
                     "info",
                 ]);
@@ -805,6 +891,7 @@ async fn list_connections(&self, client_pool: &ClientPool, params: MqttCliComman
                         raw.connection_id,
                         raw.connection_type,
                         raw.protocol,
+                        raw.mqtt_protocol_version,
                         raw.source_addr,
                         raw.info,
                     ]);
@@ -865,6 +952,7 @@ async fn list_slow_subscribe(
             client_id: cli_request.client_id,
             topic: cli_request.topic,
             sort: cli_request.sort,
+            threshold_ms: cli_request.threshold_ms,
         };
         let sort = slow_subscribe_request.sort.clone();
         match mqtt_broker_list_slow_subscribe(
@@ -913,6 +1001,8 @@ async fn list_slow_subscribe(
     async fn list_topic(&self, client_pool: &ClientPool, params: MqttCliCommandParam) {
         let request = ListTopicRequest {
             topic_name: None,
+            sort_by: None,
+            sort_order: None,
             options: None,
         };
         match mqtt_broker_list_topic(client_pool, &grpc_addr(params.server), request).await {
@@ -976,6 +1066,13 @@ async fn set_system_alarm_config(
                 if let Some(cpu_check_interval_ms) = data.os_cpu_check_interval_ms {
                     table.add_row(row!["cpu-check-interval-ms", cpu_check_interval_ms]);
                 }
+                table.add_row(row![
+                    "thresholds",
+                    format_alarm_thresholds(&data.thresholds)
+                ]);
+                if let Some(webhook) = &data.webhook {
+                    table.add_row(row!["webhook", format_alarm_webhook(webhook)]);
+                }
 
                 table.printstd()
             }
@@ -986,6 +1083,50 @@ async fn set_system_alarm_config(
         }
     }
 
+    async fn get_system_alarm_config(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: GetSystemAlarmConfigRequest,
+    ) {
+        match mqtt_broker_get_system_alarm_config(
+            client_pool,
+            &grpc_addr(params.server),
+            cli_request,
+        )
+        .await
+        {
+            Ok(data) => {
+                let mut table = Table::new();
+                table.set_titles(row!["Config Options", "Value"]);
+                table.add_row(row!["enable", data.enable]);
+                table.add_row(row!["memory-high-watermark", data.os_memory_high_watermark]);
+                table.add_row(row!["cpu-high-watermark", data.os_cpu_high_watermark]);
+                table.add_row(row!["cpu-low-watermark", data.os_cpu_low_watermark]);
+                table.add_row(row!["cpu-check-interval-ms", data.os_cpu_check_interval_ms]);
+                table.add_row(row![
+                    "thresholds",
+                    format_alarm_thresholds(&data.thresholds)
+                ]);
+                if let Some(webhook) = &data.webhook {
+                    table.add_row(row!["webhook", format_alarm_webhook(webhook)]);
+                }
+                table.add_row(row![
+                    "webhook-delivered/failed/retry",
+                    format!(
+                        "{}/{}/{}",
+                        data.webhook_delivered_num, data.webhook_failed_num, data.webhook_retry_num
+                    )
+                ]);
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker get system alarm config exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
     async fn list_system_alarm(
         &self,
         client_pool: &ClientPool,
@@ -998,13 +1139,26 @@ async fn list_system_alarm(
             Ok(data) => {
                 println!("system alarm list result:");
                 let mut table = Table::new();
-                table.set_titles(row!["name", "message", "activate_at", "activated"]);
+                table.set_titles(row![
+                    "name",
+                    "message",
+                    "activate_at",
+                    "activated",
+                    "state",
+                    "acknowledged_by",
+                    "acknowledged_at",
+                    "resolved_at"
+                ]);
                 for alarm in data.list_system_alarm_raw {
                     table.add_row(row![
                         alarm.name,
                         alarm.message,
                         alarm.activate_at,
-                        alarm.activated
+                        alarm.activated,
+                        alarm.state,
+                        alarm.acknowledged_by,
+                        alarm.acknowledged_at,
+                        alarm.resolved_at
                     ]);
                 }
                 // output cmd
@@ -1017,6 +1171,28 @@ async fn list_system_alarm(
         }
     }
 
+    async fn ack_system_alarm(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: AckSystemAlarmRequest,
+    ) {
+        match mqtt_broker_ack_system_alarm(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(data) => {
+                println!(
+                    "System alarm {} acknowledged by {} at {}",
+                    data.name, data.acknowledged_by, data.acknowledged_at
+                );
+            }
+            Err(e) => {
+                println!("MQTT broker ack system alarm exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
     // ------------------ connectors ----------------
     async fn list_connectors(
         &self,
@@ -1037,13 +1213,23 @@ async fn list_connectors(
                     "connector config",
                     "topic id",
                     "status",
+                    "paused",
                     "broker id",
                     "create time",
                     "update time",
+                    "state",
+                    "last error",
+                    "last success at",
                 ]);
 
                 for mqtt_connector in data.connectors {
                     let connector = MQTTConnector::decode(&mqtt_connector);
+                    let status = data
+                        .statuses
+                        .iter()
+                        .find(|status| status.connector_name == connector.connector_name)
+                        .cloned()
+                        .unwrap_or_default();
                     table.add_row(row![
                         connector.cluster_name,
                         connector.connector_name,
@@ -1051,9 +1237,13 @@ async fn list_connectors(
                         connector.config,
                         connector.topic_id,
                         connector.status,
+                        connector.paused,
                         connector.broker_id.unwrap_or(0),
                         connector.create_time,
-                        connector.update_time
+                        connector.update_time,
+                        status.state,
+                        status.last_error,
+                        status.last_success_at
                     ]);
                 }
 
@@ -1124,6 +1314,43 @@ async fn update_connector(
         }
     }
 
+    async fn pause_connector(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttPauseConnectorRequest,
+    ) {
+        match mqtt_broker_pause_connector(client_pool, &grpc_addr(params.server), cli_request).await
+        {
+            Ok(_) => {
+                println!("Paused successfully!")
+            }
+            Err(e) => {
+                println!("MQTT broker pause connector exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn resume_connector(
+        &self,
+        client_pool: &ClientPool,
+        params: MqttCliCommandParam,
+        cli_request: MqttResumeConnectorRequest,
+    ) {
+        match mqtt_broker_resume_connector(client_pool, &grpc_addr(params.server), cli_request)
+            .await
+        {
+            Ok(_) => {
+                println!("Resumed successfully!")
+            }
+            Err(e) => {
+                println!("MQTT broker resume connector exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
     // ------------------ topic rewrite rule ----------------
     async fn create_topic_rewrite_rule(
         &self,
@@ -1394,6 +1621,7 @@ async fn list_auto_subscribe_rule(
                     "no_local",
                     "retain_as_published",
                     "retained_handling",
+                    "priority",
                 ]);
                 for rule in data.auto_subscribe_rules {
                     let mqtt_auto_subscribe_rule =
@@ -1409,7 +1637,8 @@ async fn list_auto_subscribe_rule(
                         Into::<u8>::into(mqtt_auto_subscribe_rule.qos),
                         mqtt_auto_subscribe_rule.no_local,
                         mqtt_auto_subscribe_rule.retain_as_published,
-                        Into::<u8>::into(mqtt_auto_subscribe_rule.retained_handling)
+                        Into::<u8>::into(mqtt_auto_subscribe_rule.retained_handling),
+                        mqtt_auto_subscribe_rule.priority
                     ]);
                 }
                 // output cmd
@@ -1423,6 +1652,38 @@ async fn list_auto_subscribe_rule(
     }
 }
 
+fn format_alarm_thresholds(thresholds: &[SystemAlarmThresholdRaw]) -> String {
+    thresholds
+        .iter()
+        .map(|threshold| {
+            format!(
+                "{} {} {}",
+                threshold.metric, threshold.comparator, threshold.threshold
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_alarm_webhook(webhook: &AlarmWebhookRaw) -> String {
+    if webhook.urls.is_empty() {
+        return "disabled".to_string();
+    }
+    let mut lines = webhook.urls.clone();
+    lines.push(format!(
+        "secret: {}, retries: {}, backoff: {}ms, timeout: {}ms",
+        if webhook.secret.is_empty() {
+            "none"
+        } else {
+            &webhook.secret
+        },
+        webhook.max_retries,
+        webhook.initial_backoff_ms,
+        webhook.timeout_ms
+    ));
+    lines.join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use common_base::error::common::CommonError;
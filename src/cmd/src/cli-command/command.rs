@@ -217,6 +217,10 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
                     window_time: args.window_time.unwrap_or(1),
                     max_client_connections: args.max_client_connections.unwrap_or(15),
                     ban_time: args.ban_time.unwrap_or(5),
+                    window_seconds: args.window_seconds.unwrap_or(0),
+                    max_disconnects: args.max_disconnects.unwrap_or(0),
+                    ban_seconds: args.ban_seconds.unwrap_or(0),
+                    allowlist: args.allowlist,
                 })
             }
             // system alarm
@@ -242,6 +246,7 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
                     schema_type: args.schema_type,
                     schema: args.schema,
                     desc: args.desc,
+                    message_name: args.message_name,
                 })
             }
             MQTTAction::UpdateSchema(args) => {
@@ -250,6 +255,8 @@ async fn handle_mqtt(args: MqttArgs, cmd: MqttBrokerCommand) {
                     schema_type: args.schema_type,
                     schema: args.schema,
                     desc: args.desc,
+                    message_name: args.message_name,
+                    compatibility: args.compatibility,
                 })
             }
             MQTTAction::DeleteSchema(args) => {
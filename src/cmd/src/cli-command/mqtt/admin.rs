@@ -21,15 +21,17 @@
 use common_base::enum_type::sort_type::SortType;
 use core::option::Option::Some;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    CreateAclRequest, CreateBlacklistRequest, CreateTopicRewriteRuleRequest, CreateUserRequest,
-    DeleteAclRequest, DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest,
+    AckSystemAlarmRequest, CreateAclRequest, CreateBlacklistRequest, CreateTopicRewriteRuleRequest,
+    CreateUserRequest, DeleteAclRequest, DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest,
     DeleteTopicRewriteRuleRequest, DeleteUserRequest, ListAutoSubscribeRuleRequest,
     ListSystemAlarmRequest, MqttCreateConnectorRequest, MqttDeleteConnectorRequest,
-    MqttListConnectorRequest, MqttUpdateConnectorRequest, SetAutoSubscribeRuleRequest,
-    SetClusterConfigRequest,
+    MqttListConnectorRequest, MqttPauseConnectorRequest, MqttResumeConnectorRequest,
+    MqttUpdateConnectorRequest, SetAutoSubscribeRuleRequest, SetClusterConfigRequest,
 };
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ListSlowSubscribeRequest, SetSystemAlarmConfigRequest,
+    AlarmWebhookRaw, GetSystemAlarmConfigRequest, ListSessionRequest,
+    ListSessionSubscriptionsRequest, ListSlowSubscribeRequest, SetSystemAlarmConfigRequest,
+    SystemAlarmThresholdRaw,
 };
 
 // session
@@ -44,7 +46,26 @@ pub(crate) struct SessionArgs {
 #[derive(Debug, clap::Subcommand)]
 pub enum SessionActionType {
     #[command(author = "RobustMQ", about = "action: list sessions", long_about = None)]
-    List,
+    List(ListSessionArgs),
+    #[command(author = "RobustMQ", about = "action: list the subscriptions of a session", long_about = None)]
+    Subscriptions(ListSessionSubscriptionsArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: list sessions", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ListSessionArgs {
+    // e.g. "is_contain_last_will = false AND session_expiry > 60"
+    #[arg(long, required = false, default_value = "")]
+    pub(crate) filter: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: list the subscriptions of a session", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ListSessionSubscriptionsArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) client_id: String,
 }
 
 // connection
@@ -106,6 +127,10 @@ pub(crate) struct CreateUserArgs {
     pub(crate) password: String,
     #[arg(short, long, default_value_t = false)]
     pub(crate) is_superuser: bool,
+    #[arg(long, default_value_t = false, conflicts_with = "upsert")]
+    pub(crate) if_not_exists: bool,
+    #[arg(long, default_value_t = false)]
+    pub(crate) upsert: bool,
 }
 
 #[derive(clap::Args, Debug)]
@@ -241,6 +266,39 @@ pub(crate) struct FlappingDetectArgs {
         help = "Unit is minutes"
     )]
     pub(crate) ban_time: Option<u32>,
+    #[arg(
+        long = "window-seconds",
+        value_parser = RangedU64ValueParser::<u64>::new(),
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        help = "Detection window in seconds, overrides --window-time"
+    )]
+    pub(crate) window_seconds: Option<u64>,
+    #[arg(
+        long = "max-disconnects",
+        value_parser = RangedU64ValueParser::<u32>::new(),
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        help = "Connections within the window that trigger a ban, overrides --max-client-connections"
+    )]
+    pub(crate) max_disconnects: Option<u32>,
+    #[arg(
+        long = "ban-seconds",
+        value_parser = RangedU64ValueParser::<u64>::new(),
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        help = "Ban duration in seconds, overrides --ban-time"
+    )]
+    pub(crate) ban_seconds: Option<u64>,
+    #[arg(
+        long = "allowlist",
+        value_delimiter = ',',
+        help = "Comma separated client ids or regular expressions that are never banned"
+    )]
+    pub(crate) allowlist: Vec<String>,
 }
 
 // #### observability ####
@@ -257,7 +315,7 @@ pub(crate) struct SlowSubArgs {
         required = false,
         require_equals = true,
         exclusive = true,
-        conflicts_with_all = ["list", "sort", "topic", "sub_name", "client_id"],
+        conflicts_with_all = ["list", "sort", "topic", "sub_name", "client_id", "threshold_ms"],
         help = "Enable or disable the feature"
     )]
     pub(crate) is_enable: Option<bool>,
@@ -321,11 +379,23 @@ pub(crate) struct SlowSubArgs {
         help = "Filter the results by client ID"
     )]
     pub(crate) client_id: Option<String>,
+
+    #[arg(
+        long = "threshold-ms",
+        required = false,
+        requires = "list",
+        require_equals = true,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_parser = RangedU64ValueParser::<u64>::new(),
+        help = "Only list subscriptions slower than this many milliseconds"
+    )]
+    pub(crate) threshold_ms: Option<u64>,
 }
 
 // ---- system alarm ----
 #[derive(clap::Args, Debug)]
-#[command(author = "RobustMQ", about = "related operations of system alarm, such as setting and listing", long_about = None)]
+#[command(author = "RobustMQ", about = "related operations of system alarm, such as setting, listing and acknowledging", long_about = None)]
 #[command(next_line_help = true)]
 pub(crate) struct SystemAlarmArgs {
     #[command(subcommand)]
@@ -336,8 +406,12 @@ pub(crate) struct SystemAlarmArgs {
 pub enum SystemAlarmActionType {
     #[command(author = "RobustMQ", about = "action: set system alarm", long_about = None)]
     Set(SetSystemAlarmArgs),
+    #[command(author = "RobustMQ", about = "action: get system alarm config", long_about = None)]
+    Get,
     #[command(author = "RobustMQ", about = "action: list system alarm", long_about = None)]
-    List,
+    List(ListSystemAlarmArgs),
+    #[command(author = "RobustMQ", about = "action: acknowledge an active system alarm", long_about = None)]
+    Ack(AckSystemAlarmArgs),
 }
 
 #[derive(clap::Args, Debug)]
@@ -354,6 +428,68 @@ pub(crate) struct SetSystemAlarmArgs {
     pub(crate) memory_high_watermark: Option<f32>,
     #[arg(long, required = false)]
     pub(crate) os_cpu_check_interval_ms: Option<u64>,
+    #[arg(
+        long = "threshold",
+        required = false,
+        value_parser = parse_alarm_threshold,
+        help = "alarm on a metric, e.g. \"connection_num>10000\", can be repeated and replaces the configured thresholds"
+    )]
+    pub(crate) thresholds: Vec<SystemAlarmThresholdRaw>,
+    #[arg(long, required = false, default_value_t = false)]
+    pub(crate) clear_thresholds: bool,
+    #[arg(
+        long = "webhook-url",
+        required = false,
+        help = "post alarm fire and resolve events to this url, can be repeated and replaces the configured webhook"
+    )]
+    pub(crate) webhook_urls: Vec<String>,
+    #[arg(long, required = false, default_value = "")]
+    pub(crate) webhook_secret: String,
+    #[arg(long, required = false, default_value_t = 3)]
+    pub(crate) webhook_max_retries: u32,
+    #[arg(long, required = false, default_value_t = 500)]
+    pub(crate) webhook_initial_backoff_ms: u64,
+    #[arg(long, required = false, default_value_t = 5000)]
+    pub(crate) webhook_timeout_ms: u64,
+    #[arg(long, required = false, default_value_t = false)]
+    pub(crate) clear_webhook: bool,
+}
+
+// Splits "metric<comparator><value>", the broker validates the metric, comparator and range
+fn parse_alarm_threshold(threshold: &str) -> Result<SystemAlarmThresholdRaw, String> {
+    let start = threshold
+        .find(['>', '<'])
+        .ok_or_else(|| format!("{} has no comparator, expected >, >=, < or <=", threshold))?;
+    let (metric, rest) = threshold.split_at(start);
+    let comparator_len = if rest[1..].starts_with('=') { 2 } else { 1 };
+    let (comparator, value) = rest.split_at(comparator_len);
+    let value = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("invalid threshold value {}: {}", value.trim(), e))?;
+    Ok(SystemAlarmThresholdRaw {
+        metric: metric.trim().to_string(),
+        comparator: comparator.to_string(),
+        threshold: value,
+    })
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: list system alarm", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ListSystemAlarmArgs {
+    #[arg(long, required = false, default_value = "")]
+    pub(crate) state: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: acknowledge an active system alarm", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct AckSystemAlarmArgs {
+    #[arg(long, required = true)]
+    pub(crate) name: String,
+    #[arg(long, required = true)]
+    pub(crate) acknowledged_by: String,
 }
 
 // topic rewrite rule
@@ -415,6 +551,10 @@ pub enum ConnectorActionType {
     Delete(DeleteConnectorArgs),
     #[command(author = "RobustMQ", about = "action: update connector", long_about = None)]
     Update(UpdateConnectorArgs),
+    #[command(author = "RobustMQ", about = "action: pause connector", long_about = None)]
+    Pause(PauseConnectorArgs),
+    #[command(author = "RobustMQ", about = "action: resume connector", long_about = None)]
+    Resume(ResumeConnectorArgs),
 }
 
 #[derive(clap::Args, Debug)]
@@ -429,6 +569,18 @@ pub(crate) struct CreateConnectorArgs {
     pub(crate) config: String,
     #[arg(short, long, required = true)]
     pub(crate) topic_id: String,
+    #[arg(
+        long,
+        default_value = "",
+        help = "JSON array of transforms applied to the payload, e.g. [{\"type\":\"rename\",\"fields\":{\"t\":\"temperature\"}}]"
+    )]
+    pub(crate) transforms: String,
+    #[arg(
+        long,
+        default_value = "",
+        help = "JSON retry policy of the connector, e.g. {\"max_retries\":5,\"dead_letter_topic\":\"dlq\"}"
+    )]
+    pub(crate) retry_policy: String,
 }
 
 #[derive(clap::Args, Debug)]
@@ -455,6 +607,22 @@ pub(crate) struct UpdateConnectorArgs {
     pub(crate) connector: String,
 }
 
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: pause connector", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct PauseConnectorArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) connector_name: String,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "action: resume connector", long_about = None)]
+#[command(next_line_help = true)]
+pub(crate) struct ResumeConnectorArgs {
+    #[arg(short, long, required = true)]
+    pub(crate) connector_name: String,
+}
+
 // schema
 #[derive(Debug, Parser)]
 #[command(author="RobustMQ", about="", long_about = None)]
@@ -471,6 +639,8 @@ pub(crate) struct CreateSchemaArgs {
     pub(crate) schema_type: String,
     pub(crate) schema: String,
     pub(crate) desc: String,
+    #[arg(long, default_value = "", help = "message type of a protobuf schema")]
+    pub(crate) message_name: String,
 }
 
 #[derive(Debug, Parser)]
@@ -481,6 +651,10 @@ pub(crate) struct UpdateSchemaArgs {
     pub(crate) schema_type: String,
     pub(crate) schema: String,
     pub(crate) desc: String,
+    #[arg(long, default_value = "", help = "message type of a protobuf schema")]
+    pub(crate) message_name: String,
+    #[arg(long, default_value = "none", help = "BACKWARD, FORWARD, FULL or NONE")]
+    pub(crate) compatibility: String,
 }
 
 #[derive(Debug, Parser)]
@@ -494,7 +668,10 @@ pub(crate) struct DeleteSchemaArgs {
 #[command(author="RobustMQ", about="", long_about = None)]
 #[command(next_line_help = true)]
 pub(crate) struct ListBindSchemaArgs {
+    // Both are exact filters, leave one empty to match every value
+    #[arg(default_value = "")]
     pub(crate) schema_name: String,
+    #[arg(default_value = "")]
     pub(crate) resource_name: String,
 }
 
@@ -525,6 +702,7 @@ pub fn process_slow_sub_args(args: SlowSubArgs) -> MqttActionType {
                 topic: args.topic.unwrap_or("".to_string()),
                 client_id: args.client_id.unwrap_or("".to_string()),
                 sort: args.sort.unwrap_or(SortType::DESC).to_string(),
+                threshold_ms: args.threshold_ms.unwrap_or(0),
             })
         }
     } else {
@@ -544,15 +722,46 @@ pub fn process_system_alarm_args(args: SystemAlarmArgs) -> MqttActionType {
                 os_cpu_low_watermark: arg.cpu_low_watermark,
                 os_memory_high_watermark: arg.memory_high_watermark,
                 os_cpu_check_interval_ms: arg.os_cpu_check_interval_ms,
+                thresholds: arg.thresholds,
+                clear_thresholds: arg.clear_thresholds,
+                // Leave the configured webhook untouched unless a url is given or it is cleared
+                webhook: if arg.webhook_urls.is_empty() && !arg.clear_webhook {
+                    None
+                } else {
+                    Some(AlarmWebhookRaw {
+                        urls: arg.webhook_urls,
+                        secret: arg.webhook_secret,
+                        max_retries: arg.webhook_max_retries,
+                        initial_backoff_ms: arg.webhook_initial_backoff_ms,
+                        timeout_ms: arg.webhook_timeout_ms,
+                    })
+                },
             })
         }
-        SystemAlarmActionType::List => MqttActionType::ListSystemAlarm(ListSystemAlarmRequest {}),
+        SystemAlarmActionType::Get => {
+            MqttActionType::GetSystemAlarmConfig(GetSystemAlarmConfigRequest {})
+        }
+        SystemAlarmActionType::List(arg) => {
+            MqttActionType::ListSystemAlarm(ListSystemAlarmRequest { state: arg.state })
+        }
+        SystemAlarmActionType::Ack(arg) => MqttActionType::AckSystemAlarm(AckSystemAlarmRequest {
+            name: arg.name,
+            acknowledged_by: arg.acknowledged_by,
+        }),
     }
 }
 
 pub fn process_session_args(args: SessionArgs) -> MqttActionType {
     match args.action {
-        SessionActionType::List => MqttActionType::ListSession,
+        SessionActionType::List(arg) => MqttActionType::ListSession(ListSessionRequest {
+            options: None,
+            filter: arg.filter,
+        }),
+        SessionActionType::Subscriptions(arg) => {
+            MqttActionType::ListSessionSubscriptions(ListSessionSubscriptionsRequest {
+                client_id: arg.client_id,
+            })
+        }
     }
 }
 
@@ -569,6 +778,8 @@ pub fn process_user_args(args: UserArgs) -> MqttActionType {
             username: arg.username,
             password: arg.password,
             is_superuser: arg.is_superuser,
+            if_not_exists: arg.if_not_exists,
+            upsert: arg.upsert,
         }),
         UserActionType::Delete(arg) => MqttActionType::DeleteUser(DeleteUserRequest {
             username: arg.username,
@@ -626,6 +837,8 @@ pub fn process_connector_args(args: ConnectorArgs) -> MqttActionType {
                 connector_type: arg.connector_type.parse().unwrap(),
                 config: arg.config,
                 topic_id: arg.topic_id,
+                transforms: arg.transforms,
+                retry_policy: arg.retry_policy,
             })
         }
         ConnectorActionType::Delete(arg) => {
@@ -638,6 +851,16 @@ pub fn process_connector_args(args: ConnectorArgs) -> MqttActionType {
                 connector: Vec::from(arg.connector),
             })
         }
+        ConnectorActionType::Pause(arg) => {
+            MqttActionType::PauseConnector(MqttPauseConnectorRequest {
+                connector_name: arg.connector_name,
+            })
+        }
+        ConnectorActionType::Resume(arg) => {
+            MqttActionType::ResumeConnector(MqttResumeConnectorRequest {
+                connector_name: arg.connector_name,
+            })
+        }
     }
 }
 
@@ -692,6 +915,13 @@ pub(crate) struct SetAutoSubscribeRuleArgs {
     pub(crate) retain_as_published: bool,
     #[arg(short = 'R', long, default_value_t = 0)]
     pub(crate) retained_handling: u8,
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "rules with a higher priority are applied first"
+    )]
+    pub(crate) priority: i32,
 }
 
 #[derive(clap::Args, Debug)]
@@ -714,6 +944,7 @@ pub fn process_auto_subscribe_args(args: AutoSubscribeRuleCommand) -> MqttAction
                 no_local: arg.no_local,
                 retain_as_published: arg.retain_as_published,
                 retained_handling: arg.retained_handling as u32,
+                priority: arg.priority,
             })
         }
         AutoSubscribeRuleActionType::Delete(arg) => {
@@ -737,6 +968,7 @@ async fn test_process_slow_sub_args_function_field_is_enable_not_none() {
             topic: None,
             sub_name: None,
             client_id: None,
+            threshold_ms: None,
         };
 
         let action_type = process_slow_sub_args(args);
@@ -761,6 +993,7 @@ async fn test_process_slow_sub_args_function_filed_is_enable_none() {
             topic: None,
             sub_name: None,
             client_id: None,
+            threshold_ms: None,
         };
 
         process_slow_sub_args(args);
@@ -778,6 +1011,7 @@ async fn test_process_slow_sub_args_function_filed_is_enable_none_1() {
             topic: Some("topic_name".to_string()),
             sub_name: Some("sub_name".to_string()),
             client_id: Some("client_id".to_string()),
+            threshold_ms: None,
         };
 
         process_slow_sub_args(args);
@@ -792,6 +1026,7 @@ async fn test_process_slow_sub_args_function_filed_is_enable_none_2() {
             topic: Some("topic_name".to_string()),
             sub_name: Some("sub_name".to_string()),
             client_id: Some("client_id".to_string()),
+            threshold_ms: None,
         };
 
         let action_type = process_slow_sub_args(args);
@@ -802,8 +1037,79 @@ async fn test_process_slow_sub_args_function_filed_is_enable_none_2() {
                 topic: "topic_name".to_string(),
                 client_id: "client_id".to_string(),
                 sort: "asc".to_string(),
+                threshold_ms: 0,
             }),
             action_type
         )
     }
+
+    #[test]
+    fn test_parse_alarm_threshold() {
+        let threshold = parse_alarm_threshold("connection_num>10000").unwrap();
+        assert_eq!(threshold.metric, "connection_num");
+        assert_eq!(threshold.comparator, ">");
+        assert_eq!(threshold.threshold, 10000.0);
+
+        let threshold = parse_alarm_threshold("memory_usage >= 85.5").unwrap();
+        assert_eq!(threshold.metric, "memory_usage");
+        assert_eq!(threshold.comparator, ">=");
+        assert_eq!(threshold.threshold, 85.5);
+
+        assert!(parse_alarm_threshold("cpu_usage=5").is_err());
+        assert!(parse_alarm_threshold("cpu_usage<=").is_err());
+    }
+
+    #[test]
+    fn test_process_system_alarm_args_webhook() {
+        let set_args = |webhook_urls: Vec<String>, clear_webhook: bool| SystemAlarmArgs {
+            action: SystemAlarmActionType::Set(SetSystemAlarmArgs {
+                enable: None,
+                cpu_high_watermark: None,
+                cpu_low_watermark: None,
+                memory_high_watermark: None,
+                os_cpu_check_interval_ms: None,
+                thresholds: Vec::new(),
+                clear_thresholds: false,
+                webhook_urls,
+                webhook_secret: "s3cr3t".to_string(),
+                webhook_max_retries: 3,
+                webhook_initial_backoff_ms: 500,
+                webhook_timeout_ms: 5000,
+                clear_webhook,
+            }),
+        };
+        let webhook = |args: SystemAlarmArgs| match process_system_alarm_args(args) {
+            MqttActionType::SetSystemAlarmConfig(request) => request.webhook,
+            _ => unreachable!(),
+        };
+
+        assert!(webhook(set_args(Vec::new(), false)).is_none());
+
+        let configured = webhook(set_args(
+            vec!["http://127.0.0.1:8080/alarm".to_string()],
+            false,
+        ))
+        .unwrap();
+        assert_eq!(configured.urls, vec!["http://127.0.0.1:8080/alarm"]);
+        assert_eq!(configured.secret, "s3cr3t");
+        assert_eq!(configured.timeout_ms, 5000);
+
+        assert!(webhook(set_args(Vec::new(), true)).unwrap().urls.is_empty());
+    }
+
+    #[test]
+    fn test_process_session_args_filter() {
+        let action_type = process_session_args(SessionArgs {
+            action: SessionActionType::List(ListSessionArgs {
+                filter: "session_expiry > 60".to_string(),
+            }),
+        });
+        assert_eq!(
+            MqttActionType::ListSession(ListSessionRequest {
+                options: None,
+                filter: "session_expiry > 60".to_string(),
+            }),
+            action_type
+        );
+    }
 }
@@ -22,6 +22,7 @@
 pub enum FeatureType {
     SlowSubscribe,
     OfflineMessage,
+    CertUsername,
 }
 
 impl FromStr for FeatureType {
@@ -47,13 +48,18 @@ fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
 }
 impl ValueEnum for FeatureType {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::SlowSubscribe, Self::OfflineMessage]
+        &[
+            Self::SlowSubscribe,
+            Self::OfflineMessage,
+            Self::CertUsername,
+        ]
     }
 
     fn to_possible_value(&self) -> Option<PossibleValue> {
         Some(match self {
             FeatureType::SlowSubscribe => PossibleValue::new("SlowSubscribe"),
             FeatureType::OfflineMessage => PossibleValue::new("OfflineMessage"),
+            FeatureType::CertUsername => PossibleValue::new("CertUsername"),
         })
     }
 }
@@ -57,6 +57,79 @@ pub fn base_topic_name_regex_match(topic_name: &str, regex_topic_name: &str) ->
     false
 }
 
+// Whether the wildcards of a topic filter are in valid positions: `+` and `#` take a whole
+// level, and `#` is only allowed as the last level
+pub fn is_valid_topic_filter(filter: &str) -> bool {
+    if filter.is_empty() {
+        return false;
+    }
+    let levels: Vec<&str> = filter.split('/').collect();
+    for (i, level) in levels.iter().enumerate() {
+        if level.contains('+') && *level != "+" {
+            return false;
+        }
+        if level.contains('#') && (*level != "#" || i != levels.len() - 1) {
+            return false;
+        }
+    }
+    true
+}
+
+// Whether a topic name matches a topic filter. `+` matches exactly one level and `#` the
+// remaining levels including none, so `a/#` also matches `a`. Topics starting with `$` are
+// not matched by a wildcard in the first level.
+pub fn topic_matches_filter(filter: &str, topic_name: &str) -> bool {
+    topic_filter_covers(filter, topic_name)
+}
+
+// Whether every topic matched by `sub_filter` is also matched by `filter`. For a topic name
+// without wildcards this is the same as `topic_matches_filter`.
+pub fn topic_filter_covers(filter: &str, sub_filter: &str) -> bool {
+    if is_dollar_excluded(filter, sub_filter) {
+        return false;
+    }
+    let mut sub_levels = sub_filter.split('/');
+    for level in filter.split('/') {
+        match level {
+            "#" => return true,
+            "+" => match sub_levels.next() {
+                None | Some("#") => return false,
+                Some(_) => {}
+            },
+            level => {
+                if sub_levels.next() != Some(level) {
+                    return false;
+                }
+            }
+        }
+    }
+    sub_levels.next().is_none()
+}
+
+// Whether at least one topic name is matched by both filters
+pub fn topic_filters_overlap(filter1: &str, filter2: &str) -> bool {
+    if is_dollar_excluded(filter1, filter2) || is_dollar_excluded(filter2, filter1) {
+        return false;
+    }
+    let mut levels1 = filter1.split('/');
+    let mut levels2 = filter2.split('/');
+    loop {
+        match (levels1.next(), levels2.next()) {
+            (Some("#"), _) | (_, Some("#")) | (None, None) => return true,
+            (Some(level1), Some(level2)) => {
+                if level1 != "+" && level2 != "+" && level1 != level2 {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+fn is_dollar_excluded(filter: &str, other: &str) -> bool {
+    (filter.starts_with('+') || filter.starts_with('#')) && other.starts_with('$')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,4 +186,103 @@ fn test_base_topic_name_regex_match() {
         ));
         assert!(!base_topic_name_regex_match("topic/sub/topic", "another/#"));
     }
+
+    #[test]
+    fn test_is_valid_topic_filter() {
+        for filter in [
+            "a", "a/b", "/a", "+", "#", "a/+/b", "a/#", "+/+/#", "$SYS/#",
+        ] {
+            assert!(is_valid_topic_filter(filter), "{}", filter);
+        }
+        for filter in ["", "a+", "a/b#", "a/#/b", "#/a", "a/+b/c", "a/##"] {
+            assert!(!is_valid_topic_filter(filter), "{}", filter);
+        }
+    }
+
+    #[test]
+    fn test_topic_matches_filter() {
+        let cases = [
+            ("sensors/+/temp", "sensors/a/temp", true),
+            ("sensors/+/temp", "sensors/a/b/temp", false),
+            ("sensors/+/temp", "sensors/temp", false),
+            ("sensors/+/temp", "sensors//temp", true),
+            ("sensors/#", "sensors", true),
+            ("sensors/#", "sensors/a", true),
+            ("sensors/#", "sensors/a/b/c", true),
+            ("sensors/#", "other/a", false),
+            ("sensors/+", "sensors/a/b", false),
+            ("+/+", "/a", true),
+            ("+", "/a", false),
+            ("#", "a/b", true),
+            ("#", "$SYS/broker", false),
+            ("+/broker", "$SYS/broker", false),
+            ("$SYS/#", "$SYS/broker", true),
+            ("/a/+", "/a/b", true),
+            ("/a/+", "a/b", false),
+            ("a/b", "a/b", true),
+            ("a/b", "a/b/c", false),
+        ];
+        for (filter, topic_name, expected) in cases {
+            assert_eq!(
+                topic_matches_filter(filter, topic_name),
+                expected,
+                "{} {}",
+                filter,
+                topic_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_topic_filter_covers() {
+        let cases = [
+            ("sensors/#", "sensors/+/temp", true),
+            ("sensors/#", "sensors/#", true),
+            ("sensors/+/temp", "sensors/+/temp", true),
+            ("sensors/+/temp", "sensors/a/temp", true),
+            ("sensors/+/+", "sensors/+/temp", true),
+            ("sensors/a/temp", "sensors/+/temp", false),
+            ("sensors/+/temp", "sensors/#", false),
+            ("sensors/+", "sensors/#", false),
+            ("sensors", "sensors/#", false),
+            ("#", "+/a", true),
+            ("#", "$SYS/#", false),
+        ];
+        for (filter, sub_filter, expected) in cases {
+            assert_eq!(
+                topic_filter_covers(filter, sub_filter),
+                expected,
+                "{} {}",
+                filter,
+                sub_filter
+            );
+        }
+    }
+
+    #[test]
+    fn test_topic_filters_overlap() {
+        let cases = [
+            ("sensors/+/temp", "sensors/a/+", true),
+            ("sensors/+/temp", "sensors/#", true),
+            ("sensors/a/temp", "sensors/+/temp", true),
+            ("sensors/#", "sensors", true),
+            ("sensors/+/temp", "sensors/+/humidity", false),
+            ("sensors/+", "sensors/a/b", false),
+            ("a/b", "a/b", true),
+            ("a/b", "a/c", false),
+            ("#", "$SYS/broker", false),
+            ("$SYS/#", "#", false),
+            ("$SYS/#", "$SYS/+", true),
+        ];
+        for (filter1, filter2, expected) in cases {
+            assert_eq!(
+                topic_filters_overlap(filter1, filter2),
+                expected,
+                "{} {}",
+                filter1,
+                filter2
+            );
+            assert_eq!(topic_filters_overlap(filter2, filter1), expected);
+        }
+    }
 }
@@ -13,17 +13,24 @@
 // limitations under the License.
 
 use super::default::{
-    default_auth_storage, default_feature, default_flapping_detect, default_grpc_port,
-    default_heartbeat_timeout, default_log, default_message_storage, default_network_port,
-    default_network_quic_port, default_network_tcp_port, default_network_tcps_port,
-    default_network_thread, default_network_websocket_port, default_network_websockets_port,
-    default_offline_message, default_placement_center, default_protocol, default_schema,
-    default_security, default_slow_sub, default_system, default_system_monitor, default_telemetry,
+    default_auth_http, default_auth_jwt, default_auth_storage, default_cert_username,
+    default_client_group, default_connection_limit, default_connector_limit,
+    default_duplicate_client_id, default_feature, default_flapping_detect, default_grpc_port,
+    default_heartbeat_timeout, default_log, default_message_dedup, default_message_sampling,
+    default_message_storage, default_network_port, default_network_quic_port,
+    default_network_tcp_port, default_network_tcps_port, default_network_thread,
+    default_network_websocket_port, default_network_websockets_port, default_offline_message,
+    default_placement_center, default_protocol, default_rate_limit, default_retain_message,
+    default_schema, default_security, default_session_restore, default_session_tiering,
+    default_shared_dispatch, default_slow_sub, default_subscribe_auth, default_subscribe_snapshot,
+    default_system, default_system_monitor, default_telemetry, default_topic_cleanup,
+    default_user_message_expiry, default_user_single_session, default_will_publish,
 };
 use crate::common::{
     default_pprof, default_prometheus, AvailableFlag, Log, Pprof, Prometheus, Telemetry,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct BrokerMqttConfig {
@@ -106,6 +113,86 @@ pub struct BrokerMqttConfig {
     // system monitor
     #[serde(default = "default_system_monitor")]
     pub system_monitor: SystemMonitor,
+
+    // retain message
+    #[serde(default = "default_retain_message")]
+    pub retain_message: RetainMessage,
+
+    // user message expiry
+    #[serde(default = "default_user_message_expiry")]
+    pub user_message_expiry: UserMessageExpiry,
+
+    // duplicate client id
+    #[serde(default = "default_duplicate_client_id")]
+    pub duplicate_client_id: DuplicateClientId,
+
+    // topic cleanup
+    #[serde(default = "default_topic_cleanup")]
+    pub topic_cleanup: TopicCleanup,
+
+    // subscribe authorization
+    #[serde(default = "default_subscribe_auth")]
+    pub subscribe_auth: SubscribeAuth,
+
+    // shared subscription dispatch
+    #[serde(default = "default_shared_dispatch")]
+    pub shared_dispatch: SharedDispatch,
+
+    // message sampling
+    #[serde(default = "default_message_sampling")]
+    pub message_sampling: MessageSampling,
+
+    // publish rate limit
+    #[serde(default = "default_rate_limit")]
+    pub rate_limit: RateLimit,
+
+    // publish during session restore
+    #[serde(default = "default_session_restore")]
+    pub session_restore: SessionRestore,
+
+    // persistent session storage tiering
+    #[serde(default = "default_session_tiering")]
+    pub session_tiering: SessionTiering,
+
+    // will message publishing
+    #[serde(default = "default_will_publish")]
+    pub will_publish: WillPublish,
+
+    // connector forwarding limits
+    #[serde(default = "default_connector_limit")]
+    pub connector_limit: ConnectorLimit,
+
+    // client groups and their policies
+    #[serde(default = "default_client_group")]
+    pub client_group: ClientGroup,
+
+    // deduplication of messages by a client provided message id
+    #[serde(default = "default_message_dedup")]
+    pub message_dedup: MessageDedup,
+
+    // at most one session per user across the cluster
+    #[serde(default = "default_user_single_session")]
+    pub user_single_session: UserSingleSession,
+
+    // connections accepted from a single source IP
+    #[serde(default = "default_connection_limit")]
+    pub connection_limit: ConnectionLimit,
+
+    // client certificate CN as username
+    #[serde(default = "default_cert_username")]
+    pub cert_username: CertUsername,
+
+    // periodic snapshot of the subscribe manager
+    #[serde(default = "default_subscribe_snapshot")]
+    pub subscribe_snapshot: SubscribeSnapshot,
+
+    // CONNECT authentication delegated to an external HTTP service
+    #[serde(default = "default_auth_http")]
+    pub auth_http: AuthHttp,
+
+    // CONNECT authentication with a JWT in the password field
+    #[serde(default = "default_auth_jwt")]
+    pub auth_jwt: AuthJwt,
 }
 
 // MQTT cluster protocol related dynamic configuration
@@ -134,6 +221,16 @@ pub fn encode(&self) -> Vec<u8> {
 pub struct Security {
     pub is_self_protection_status: bool,
     pub secret_free_login: bool,
+    // What happens to a publish or subscribe that no ACL rule matches
+    #[serde(default)]
+    pub acl_default_policy: AclDefaultPolicy,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum AclDefaultPolicy {
+    #[default]
+    Allow,
+    Deny,
 }
 
 impl Security {
@@ -189,15 +286,39 @@ pub fn encode(&self) -> Vec<u8> {
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct FlappingDetect {
     pub enable: bool,
+    // minutes, used when window_seconds is 0
     pub window_time: u32,
     pub max_client_connections: u64,
+    // minutes, used when ban_seconds is 0
     pub ban_time: u32,
+    #[serde(default)]
+    pub window_seconds: u64,
+    #[serde(default)]
+    pub ban_seconds: u64,
+    // Client ids that are never banned, each entry is a regular expression matching the whole
+    // client id
+    #[serde(default)]
+    pub allowlist: Vec<String>,
 }
 
 impl FlappingDetect {
     pub fn encode(&self) -> Vec<u8> {
         serde_json::to_vec(&self).unwrap()
     }
+
+    pub fn window_sec(&self) -> u64 {
+        if self.window_seconds > 0 {
+            return self.window_seconds;
+        }
+        self.window_time as u64 * 60
+    }
+
+    pub fn ban_sec(&self) -> u64 {
+        if self.ban_seconds > 0 {
+            return self.ban_seconds;
+        }
+        self.ban_time as u64 * 60
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -214,6 +335,73 @@ pub struct SystemMonitor {
     pub os_memory_check_interval_ms: u64,
     #[serde(default)]
     pub os_memory_high_watermark: f32,
+    // Extra alarms on broker metrics, each raised while its comparison holds
+    #[serde(default)]
+    pub thresholds: Vec<SystemAlarmThreshold>,
+    // Where alarms are posted when they fire or resolve
+    #[serde(default)]
+    pub webhook: AlarmWebhook,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AlarmWebhook {
+    // No alarm is posted while the list is empty
+    pub urls: Vec<String>,
+    // Key of the HMAC-SHA256 signature of the body, the request is not signed when empty
+    pub secret: String,
+    // Retries after the first attempt, the backoff doubles from initial_backoff_ms
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    pub timeout_ms: u64,
+}
+
+impl Default for AlarmWebhook {
+    fn default() -> Self {
+        AlarmWebhook {
+            urls: Vec::new(),
+            secret: String::new(),
+            max_retries: 3,
+            initial_backoff_ms: 500,
+            timeout_ms: 5000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SystemAlarmThreshold {
+    pub metric: SystemAlarmMetric,
+    pub comparator: SystemAlarmComparator,
+    pub threshold: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum SystemAlarmMetric {
+    // Percent of the process
+    CpuUsage,
+    MemoryUsage,
+    // Number of MQTT connections and sessions on the broker
+    ConnectionNum,
+    SessionNum,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum SystemAlarmComparator {
+    GreaterThan,
+    GreaterOrEqual,
+    LessThan,
+    LessOrEqual,
+}
+
+impl SystemAlarmComparator {
+    pub fn compare(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            SystemAlarmComparator::GreaterThan => value > threshold,
+            SystemAlarmComparator::GreaterOrEqual => value >= threshold,
+            SystemAlarmComparator::LessThan => value < threshold,
+            SystemAlarmComparator::LessOrEqual => value <= threshold,
+        }
+    }
 }
 
 impl SystemMonitor {
@@ -243,6 +431,9 @@ pub struct NetworkPort {
     pub tls_cert: String,
     #[serde(default)]
     pub tls_key: String,
+    // CA bundle the TLS listener verifies client certificates against, empty disables mTLS
+    #[serde(default)]
+    pub tls_client_ca: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -321,3 +512,522 @@ pub fn encode(&self) -> Vec<u8> {
         serde_json::to_vec(&self).unwrap()
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct RetainMessage {
+    // Maximum payload size of a single retained message, 0 means unlimited
+    pub max_payload_size: u64,
+    // What to do with a retained publish whose payload exceeds max_payload_size
+    pub oversize_policy: RetainOversizePolicy,
+    // Size limits of topic filters, the first matching limit replaces max_payload_size and
+    // oversize_policy on its topics
+    #[serde(default)]
+    pub topic_size_limits: Vec<RetainTopicSizeLimit>,
+    // Topic filters on which retained messages are not kept, the first matching policy applies
+    #[serde(default)]
+    pub topic_policies: Vec<RetainTopicPolicy>,
+    // Most retained messages delivered for a single SUBSCRIBE, 0 means unlimited
+    #[serde(default)]
+    pub max_deliver_num_per_subscribe: u64,
+    // Most retained payload bytes delivered for a single SUBSCRIBE, 0 means unlimited
+    #[serde(default)]
+    pub max_deliver_bytes_per_subscribe: u64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RetainTopicSizeLimit {
+    pub topic_filter: String,
+    // Maximum payload size of a retained message on the matching topics, 0 means unlimited
+    pub max_payload_size: u64,
+    pub oversize_policy: RetainOversizePolicy,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RetainTopicPolicy {
+    pub topic_filter: String,
+    pub action: RetainTopicAction,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub enum RetainTopicAction {
+    // Reject the whole publish and report the failure to the client
+    #[default]
+    Reject,
+    // Accept the publish as a normal message, but do not retain it
+    Strip,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub enum RetainOversizePolicy {
+    // Reject the whole publish and report the failure to the client
+    #[default]
+    Reject,
+    // Accept the publish as a normal message, but do not retain it
+    AcceptWithoutRetain,
+}
+
+impl RetainMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// Default message expiry interval per user, applied when the client does not set one
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct UserMessageExpiry {
+    // (username, message expiry interval in seconds)
+    #[serde(default)]
+    pub user_defaults: HashMap<String, u64>,
+}
+
+impl UserMessageExpiry {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// How CONNECT handles a client id that already has a live connection
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct DuplicateClientId {
+    pub policy: DuplicateClientIdPolicy,
+    // Under the Grace policy, the old connection keeps the client id as long as
+    // it has sent a heartbeat within this many seconds
+    pub grace_period_sec: u64,
+}
+
+// The session is never affected by the policy: a taken over or rejected client id keeps
+// its persistent session, only the network connection is replaced or refused.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub enum DuplicateClientIdPolicy {
+    // The new connection wins, the old one is disconnected with the MQTT5
+    // Session taken over (0x8E) reason code
+    #[default]
+    Takeover,
+    // The new connection is refused with Client Identifier not valid (0x85)
+    Reject,
+    // Reject while the old connection is still alive within grace_period_sec,
+    // take over once it has gone quiet
+    Grace,
+}
+
+impl DuplicateClientId {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// Background pruning of topics without subscribers, retained message or recent publish.
+// Publish activity is tracked per broker, and a topic is only pruned once every broker of
+// the cluster has seen it idle.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct TopicCleanup {
+    pub enable: bool,
+    // A topic is idle once it has not been published to for this many seconds
+    pub idle_threshold_sec: u64,
+    pub check_interval_sec: u64,
+}
+
+impl TopicCleanup {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// How a SUBSCRIBE with several topic filters is answered when only some of them are authorized
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct SubscribeAuth {
+    pub granularity: SubscribeAuthGranularity,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub enum SubscribeAuthGranularity {
+    // The authorized filters are subscribed, the others are answered with
+    // Not authorized (0x87), or Failure (0x80) for MQTT 3.1.1 clients
+    #[default]
+    PerFilter,
+    // A single unauthorized filter rejects every filter of the packet
+    AllOrNothing,
+}
+
+impl SubscribeAuth {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// How the messages of a shared subscription group are dispatched to its consumers.
+// Groups without an entry use round-robin.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct SharedDispatch {
+    // (group name, dispatch of the group)
+    #[serde(default)]
+    pub groups: HashMap<String, SharedGroupDispatch>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SharedGroupDispatch {
+    pub mode: SharedDispatchMode,
+    // (client id, weight) overrides the receive maximum advertised by the consumer,
+    // the weight is also the number of unacknowledged messages the consumer may hold
+    #[serde(default)]
+    pub consumer_weights: HashMap<String, u32>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub enum SharedDispatchMode {
+    #[default]
+    RoundRobin,
+    // Weighted by consumer capacity, consumers with a full inflight window are skipped
+    Capacity,
+}
+
+impl SharedDispatch {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// Sampled messages are written to the message_sample log target, so tracing a
+// representative share of the traffic does not cost a log line per message.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct MessageSampling {
+    // Sample one in every N published messages, 0 disables sampling
+    pub sample_every: u64,
+    // Upper bound of sampled messages per second, 0 means no limit
+    pub max_per_second: u64,
+}
+
+impl MessageSampling {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// Publish and subscribe rate limits per user, counted across all connections of the user on a broker
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct RateLimit {
+    // Messages a user may publish per second, 0 means no limit
+    pub default_publish_rate: u64,
+    // (username, publish rate) replacing the default for a single user, 0 exempts the user
+    pub user_overrides: HashMap<String, u64>,
+    // SUBSCRIBE packets a user may send per second, 0 means no limit
+    #[serde(default)]
+    pub default_subscribe_rate: u64,
+    // (username, subscribe rate) replacing the default for a single user, 0 exempts the user
+    #[serde(default)]
+    pub user_subscribe_overrides: HashMap<String, u64>,
+}
+
+impl RateLimit {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+
+    // The override of the user wins over the rate of its group, then the default applies
+    pub fn publish_rate(&self, username: &str, group_rate: Option<u64>) -> u64 {
+        self.user_overrides
+            .get(username)
+            .copied()
+            .or(group_rate)
+            .unwrap_or(self.default_publish_rate)
+    }
+
+    pub fn subscribe_rate(&self, username: &str) -> u64 {
+        self.user_subscribe_overrides
+            .get(username)
+            .copied()
+            .unwrap_or(self.default_subscribe_rate)
+    }
+}
+
+// How a PUBLISH is handled when it arrives while the CONNECT of the same connection is still
+// being processed, which clients may do since they do not have to wait for the CONNACK.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct SessionRestore {
+    pub policy: SessionRestorePolicy,
+    // Publishes held per connection under the Buffer policy, further ones are rejected
+    pub max_buffered_messages: u64,
+    // How long a held publish waits for the session, it is rejected afterwards
+    pub max_wait_ms: u64,
+}
+
+impl SessionRestore {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub enum SessionRestorePolicy {
+    // Hold the publish until the session is ready
+    #[default]
+    Buffer,
+    // Reject the publish right away
+    Reject,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct SessionTiering {
+    // Offline sessions idle for longer than this are dropped from memory and kept only in the store, 0 keeps all sessions in memory
+    pub offload_idle_sec: u64,
+    // How often the broker looks for idle offline sessions
+    pub check_interval_sec: u64,
+}
+
+impl SessionTiering {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct WillPublish {
+    // How long an unclean disconnect is held back before the will can fire, 0 disables the grace
+    pub unclean_grace_period_sec: u64,
+    // What happens to a CONNECT whose will topic the client may not publish to
+    #[serde(default)]
+    pub unauthorized_will_action: UnauthorizedWillAction,
+}
+
+impl WillPublish {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum UnauthorizedWillAction {
+    // Refuse the connection with Not Authorized
+    #[default]
+    Reject,
+    // Accept the connection without its will
+    Strip,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct ConnectorLimit {
+    // Records a single connector may forward to its sink per second, 0 is unlimited
+    pub max_records_per_sec: u64,
+}
+
+impl ConnectorLimit {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// Named groups of users sharing quotas and policies. A setting made for a single user always
+// wins over the setting of its group, which in turn wins over the cluster default.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct ClientGroup {
+    // (group_name, ClientGroupPolicy)
+    #[serde(default)]
+    pub groups: HashMap<String, ClientGroupPolicy>,
+    // (username, group_name), a user belongs to at most one group
+    #[serde(default)]
+    pub members: HashMap<String, String>,
+    // (username, max qos) replacing the QoS cap of the group for a single user
+    #[serde(default)]
+    pub user_max_qos: HashMap<String, u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ClientGroupPolicy {
+    // Messages each member may publish per second, None falls back to the cluster default
+    #[serde(default)]
+    pub publish_rate: Option<u64>,
+    // Default message expiry interval of the members in seconds, 0 means none
+    #[serde(default)]
+    pub message_expiry_interval: u64,
+    // Highest QoS granted to the subscriptions of the members, None means no cap
+    #[serde(default)]
+    pub max_qos: Option<u8>,
+    // Topic filters the members may publish and subscribe to when none of their own ACL rules match
+    #[serde(default)]
+    pub allow_topics: Vec<String>,
+    // Topic filters the members may not publish or subscribe to when none of their own ACL rules match
+    #[serde(default)]
+    pub deny_topics: Vec<String>,
+}
+
+impl ClientGroup {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+
+    pub fn group_of(&self, username: &str) -> Option<&ClientGroupPolicy> {
+        self.members
+            .get(username)
+            .and_then(|group_name| self.groups.get(group_name))
+    }
+
+    pub fn max_qos(&self, username: &str) -> Option<u8> {
+        self.user_max_qos
+            .get(username)
+            .copied()
+            .or_else(|| self.group_of(username).and_then(|group| group.max_qos))
+    }
+}
+
+// Suppresses a message when its message id user property was already seen on the same topic
+// within the window. Messages without the property are never suppressed.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct MessageDedup {
+    #[serde(default)]
+    pub enable: bool,
+    // Name of the MQTT5 user property carrying the message id
+    pub property_name: String,
+    // Topic filters the deduplication applies to, empty means every topic
+    #[serde(default)]
+    pub topic_filters: Vec<String>,
+    // How long a message id is remembered, in seconds
+    pub window_sec: u64,
+    // Most message ids remembered per topic, the oldest are forgotten first
+    pub max_ids_per_topic: u64,
+}
+
+impl MessageDedup {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+// Allows a user at most one connected session in the whole cluster. A new connection of the user
+// disconnects the session it already has, on whichever broker that session lives.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct UserSingleSession {
+    #[serde(default)]
+    pub enable: bool,
+    // Users the restriction applies to, empty means every user
+    #[serde(default)]
+    pub usernames: Vec<String>,
+}
+
+impl UserSingleSession {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+
+    pub fn is_enforced(&self, username: &str) -> bool {
+        self.enable
+            && !username.is_empty()
+            && (self.usernames.is_empty() || self.usernames.iter().any(|user| user == username))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct ConnectionLimit {
+    // Connections a single source IP may hold on a broker, 0 is unlimited
+    #[serde(default)]
+    pub max_connections_per_ip: u64,
+}
+
+impl ConnectionLimit {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+
+    pub fn is_exceeded(&self, ip_connection_num: u64) -> bool {
+        self.max_connections_per_ip > 0 && ip_connection_num >= self.max_connections_per_ip
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct CertUsername {
+    // Log clients in with the CN of their verified client certificate as username
+    #[serde(default)]
+    pub enable: bool,
+}
+
+impl CertUsername {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AuthHttp {
+    pub enable: bool,
+    // The client id, username, password and client certificate subject are POSTed as JSON
+    pub url: String,
+    pub timeout_ms: u64,
+    // Seconds an allowed login is remembered, 0 asks the service on every CONNECT
+    pub cache_ttl_sec: u64,
+    // What happens when the service can not be reached, times out or gives an invalid answer
+    pub failure_action: AuthHttpFailureAction,
+}
+
+impl Default for AuthHttp {
+    fn default() -> Self {
+        AuthHttp {
+            enable: false,
+            url: String::new(),
+            timeout_ms: 5000,
+            cache_ttl_sec: 60,
+            failure_action: AuthHttpFailureAction::Deny,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum AuthHttpFailureAction {
+    // Reject the client
+    #[default]
+    Deny,
+    // Continue with the built-in user check, as if the service answered ignore
+    Ignore,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AuthJwt {
+    pub enable: bool,
+    // Shared secret of HS256, HS384 and HS512 signed tokens
+    pub secret: String,
+    // PEM encoded public key of RSA, ECDSA and EdDSA signed tokens
+    pub public_key: String,
+    // JWKS document, the key is picked by the kid of the token header
+    pub jwks: String,
+    // Accepted aud values, empty skips the audience check
+    pub audience: Vec<String>,
+    // Expected iss value, empty skips the issuer check
+    pub issuer: String,
+    // Seconds of clock skew tolerated when checking exp and nbf
+    pub leeway_sec: u64,
+    // Claim used as username, tokens without it are rejected
+    pub username_claim: String,
+    // Claim holding the ACL rules of the connection
+    pub acl_claim: String,
+}
+
+impl Default for AuthJwt {
+    fn default() -> Self {
+        AuthJwt {
+            enable: false,
+            secret: String::new(),
+            public_key: String::new(),
+            jwks: String::new(),
+            audience: Vec::new(),
+            issuer: String::new(),
+            leeway_sec: 0,
+            username_claim: "username".to_string(),
+            acl_claim: "acl".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct SubscribeSnapshot {
+    pub enable: bool,
+    // Seconds between two snapshots of the subscriptions of a broker
+    pub interval_sec: u64,
+    // Snapshots kept per broker, older ones are removed
+    pub retain_num: u64,
+}
+
+impl SubscribeSnapshot {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).unwrap()
+    }
+}
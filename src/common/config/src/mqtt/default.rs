@@ -13,8 +13,13 @@
 // limitations under the License.
 
 use super::config::{
-    Feature, FlappingDetect, MqttProtocolConfig, NetworkPort, NetworkThread, OfflineMessage,
-    Security, SlowSub, System, SystemMonitor,
+    AclDefaultPolicy, AlarmWebhook, AuthHttp, AuthJwt, CertUsername, ClientGroup, ConnectionLimit,
+    ConnectorLimit, DuplicateClientId, DuplicateClientIdPolicy, Feature, FlappingDetect,
+    MessageDedup, MessageSampling, MqttProtocolConfig, NetworkPort, NetworkThread, OfflineMessage,
+    RateLimit, RetainMessage, RetainOversizePolicy, Security, SessionRestore, SessionRestorePolicy,
+    SessionTiering, SharedDispatch, SlowSub, SubscribeAuth, SubscribeAuthGranularity,
+    SubscribeSnapshot, System, SystemMonitor, TopicCleanup, UnauthorizedWillAction,
+    UserMessageExpiry, UserSingleSession, WillPublish,
 };
 use crate::{
     common::{AvailableFlag, Log, Telemetry},
@@ -22,6 +27,7 @@
         AuthStorage, MessageDataStorage, Schema, SchemaFailedOperation, SchemaStrategy,
     },
 };
+use std::collections::HashMap;
 
 pub fn default_grpc_port() -> u32 {
     9981
@@ -44,6 +50,7 @@ pub fn default_network_port() -> NetworkPort {
         quic_port: default_network_quic_port(),
         tls_cert: "".to_string(),
         tls_key: "".to_string(),
+        tls_client_ca: "".to_string(),
     }
 }
 pub fn default_network_tcp_port() -> u32 {
@@ -91,6 +98,8 @@ pub fn default_system_monitor() -> SystemMonitor {
         os_cpu_low_watermark: 50.0,
         os_memory_check_interval_ms: 60,
         os_memory_high_watermark: 80.0,
+        thresholds: Vec::new(),
+        webhook: AlarmWebhook::default(),
     }
 }
 
@@ -149,6 +158,7 @@ pub fn default_security() -> Security {
     Security {
         secret_free_login: false,
         is_self_protection_status: false,
+        acl_default_policy: AclDefaultPolicy::Allow,
     }
 }
 
@@ -182,6 +192,9 @@ pub fn default_flapping_detect() -> FlappingDetect {
         window_time: 1,
         max_client_connections: 15,
         ban_time: 5,
+        window_seconds: 0,
+        ban_seconds: 0,
+        allowlist: Vec::new(),
     }
 }
 
@@ -194,3 +207,142 @@ pub fn default_schema() -> Schema {
         log_level: "info".to_string(),
     }
 }
+
+pub fn default_retain_message() -> RetainMessage {
+    RetainMessage {
+        max_payload_size: 1024 * 1024,
+        oversize_policy: RetainOversizePolicy::Reject,
+        topic_size_limits: Vec::new(),
+        topic_policies: Vec::new(),
+        max_deliver_num_per_subscribe: 0,
+        max_deliver_bytes_per_subscribe: 0,
+    }
+}
+
+pub fn default_user_message_expiry() -> UserMessageExpiry {
+    UserMessageExpiry {
+        user_defaults: HashMap::new(),
+    }
+}
+
+pub fn default_duplicate_client_id() -> DuplicateClientId {
+    DuplicateClientId {
+        policy: DuplicateClientIdPolicy::Takeover,
+        grace_period_sec: 30,
+    }
+}
+
+pub fn default_topic_cleanup() -> TopicCleanup {
+    TopicCleanup {
+        enable: false,
+        idle_threshold_sec: 86400,
+        check_interval_sec: 3600,
+    }
+}
+
+pub fn default_subscribe_auth() -> SubscribeAuth {
+    SubscribeAuth {
+        granularity: SubscribeAuthGranularity::PerFilter,
+    }
+}
+
+pub fn default_shared_dispatch() -> SharedDispatch {
+    SharedDispatch {
+        groups: HashMap::new(),
+    }
+}
+
+pub fn default_message_sampling() -> MessageSampling {
+    MessageSampling {
+        sample_every: 0,
+        max_per_second: 100,
+    }
+}
+
+pub fn default_rate_limit() -> RateLimit {
+    RateLimit {
+        default_publish_rate: 0,
+        user_overrides: HashMap::new(),
+        default_subscribe_rate: 0,
+        user_subscribe_overrides: HashMap::new(),
+    }
+}
+
+pub fn default_session_restore() -> SessionRestore {
+    SessionRestore {
+        policy: SessionRestorePolicy::Buffer,
+        max_buffered_messages: 100,
+        max_wait_ms: 5000,
+    }
+}
+
+pub fn default_session_tiering() -> SessionTiering {
+    SessionTiering {
+        offload_idle_sec: 0,
+        check_interval_sec: 60,
+    }
+}
+
+pub fn default_will_publish() -> WillPublish {
+    WillPublish {
+        unclean_grace_period_sec: 0,
+        unauthorized_will_action: UnauthorizedWillAction::Reject,
+    }
+}
+
+pub fn default_connector_limit() -> ConnectorLimit {
+    ConnectorLimit {
+        max_records_per_sec: 0,
+    }
+}
+
+pub fn default_client_group() -> ClientGroup {
+    ClientGroup {
+        groups: HashMap::new(),
+        members: HashMap::new(),
+        user_max_qos: HashMap::new(),
+    }
+}
+
+pub fn default_message_dedup() -> MessageDedup {
+    MessageDedup {
+        enable: false,
+        property_name: "message-id".to_string(),
+        topic_filters: Vec::new(),
+        window_sec: 60,
+        max_ids_per_topic: 10000,
+    }
+}
+
+pub fn default_user_single_session() -> UserSingleSession {
+    UserSingleSession {
+        enable: false,
+        usernames: Vec::new(),
+    }
+}
+
+pub fn default_connection_limit() -> ConnectionLimit {
+    ConnectionLimit {
+        max_connections_per_ip: 0,
+    }
+}
+
+pub fn default_cert_username() -> CertUsername {
+    CertUsername { enable: false }
+}
+
+pub fn default_auth_http() -> AuthHttp {
+    AuthHttp::default()
+}
+
+pub fn default_auth_jwt() -> AuthJwt {
+    AuthJwt::default()
+}
+
+pub fn default_subscribe_snapshot() -> SubscribeSnapshot {
+    SubscribeSnapshot {
+        enable: false,
+        interval_sec: 300,
+        retain_num: 12,
+    }
+}
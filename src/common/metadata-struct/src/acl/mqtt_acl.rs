@@ -17,7 +17,7 @@
 use common_base::error::common::CommonError;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Clone)]
 pub struct MqttAcl {
     pub resource_type: MqttAclResourceType,
     pub resource_name: String,
@@ -37,7 +37,7 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Clone)]
 pub enum MqttAclResourceType {
     ClientId,
     User,
@@ -56,7 +56,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Clone)]
 pub enum MqttAclAction {
     All,
     Subscribe,
@@ -83,7 +83,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Clone)]
 pub enum MqttAclPermission {
     Allow,
     Deny,
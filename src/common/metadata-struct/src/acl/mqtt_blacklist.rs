@@ -24,6 +24,10 @@ pub struct MqttAclBlackList {
     pub resource_name: String,
     pub end_time: u64,
     pub desc: String,
+    // time in seconds at which the entry is removed, it no longer bans anyone from then on.
+    // When set it replaces end_time, entries without it keep banning until end_time.
+    #[serde(default)]
+    pub expire_at: Option<u64>,
 }
 
 impl MqttAclBlackList {
@@ -34,6 +38,18 @@ pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
     pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         Ok(serde_json::from_slice(data)?)
     }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expire_at.is_some_and(|expire_at| expire_at <= now)
+    }
+
+    // Whether the entry bans at the time `now`
+    pub fn is_active(&self, now: u64) -> bool {
+        match self.expire_at {
+            Some(expire_at) => expire_at > now,
+            None => self.end_time > now,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
@@ -44,6 +60,7 @@ pub enum MqttAclBlackListType {
     ClientIdMatch,
     UserMatch,
     IPCIDR,
+    IpMatch,
 }
 
 impl fmt::Display for MqttAclBlackListType {
@@ -58,6 +75,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 MqttAclBlackListType::ClientIdMatch => "ClientIdMatch",
                 MqttAclBlackListType::UserMatch => "UserMatch",
                 MqttAclBlackListType::IPCIDR => "IPCIDR",
+                MqttAclBlackListType::IpMatch => "IpMatch",
             }
         )
     }
@@ -72,6 +90,7 @@ fn from(type_enum: MqttAclBlackListType) -> Self {
             MqttAclBlackListType::ClientIdMatch => Self::ClientIdMatch,
             MqttAclBlackListType::UserMatch => Self::Username,
             MqttAclBlackListType::IPCIDR => Self::IpCidr,
+            MqttAclBlackListType::IpMatch => Self::IpMatch,
         }
     }
 }
@@ -23,6 +23,11 @@ pub struct MqttAutoSubscribeRule {
     pub no_local: bool,
     pub retain_as_published: bool,
     pub retained_handling: RetainHandling,
+    // Rules are applied from the highest priority down, ties go to the older rule
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub create_time: u64,
 }
 
 impl MqttAutoSubscribeRule {
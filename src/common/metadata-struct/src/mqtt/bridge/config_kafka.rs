@@ -14,9 +14,56 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+pub const KAFKA_ACKS_VALUES: [&str; 3] = ["0", "1", "all"];
+pub const KAFKA_COMPRESSION_VALUES: [&str; 5] = ["none", "gzip", "snappy", "lz4", "zstd"];
+pub const KAFKA_BATCH_SIZE_MAX: u64 = 2147483647;
+pub const KAFKA_LINGER_MS_MAX: u64 = 900000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct KafkaConnectorConfig {
     pub bootstrap_servers: String,
     pub topic: String,
     pub key: String,
+    // Acknowledgements the producer waits for: "0", "1" or "all"
+    #[serde(default = "default_kafka_acks")]
+    pub acks: String,
+    // Largest batch of messages sent to a partition in one request, in bytes
+    #[serde(default = "default_kafka_batch_size")]
+    pub batch_size: u64,
+    // How long the producer waits for a batch to fill up before sending it
+    #[serde(default = "default_kafka_linger_ms")]
+    pub linger_ms: u64,
+    // One of none, gzip, snappy, lz4 and zstd
+    #[serde(default = "default_kafka_compression")]
+    pub compression: String,
+}
+
+impl Default for KafkaConnectorConfig {
+    fn default() -> Self {
+        KafkaConnectorConfig {
+            bootstrap_servers: String::new(),
+            topic: String::new(),
+            key: String::new(),
+            acks: default_kafka_acks(),
+            batch_size: default_kafka_batch_size(),
+            linger_ms: default_kafka_linger_ms(),
+            compression: default_kafka_compression(),
+        }
+    }
+}
+
+fn default_kafka_acks() -> String {
+    "all".to_string()
+}
+
+fn default_kafka_batch_size() -> u64 {
+    1000000
+}
+
+fn default_kafka_linger_ms() -> u64 {
+    5
+}
+
+fn default_kafka_compression() -> String {
+    "none".to_string()
 }
@@ -14,7 +14,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::{connector_type::ConnectorType, status::MQTTStatus};
+use super::{connector_type::ConnectorType, status::MQTTStatus, transform::ConnectorTransform};
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct MQTTConnector {
@@ -27,6 +27,40 @@ pub struct MQTTConnector {
     pub broker_id: Option<u64>,
     pub create_time: u64,
     pub update_time: u64,
+    // A paused connector keeps its config and offset but forwards nothing
+    #[serde(default)]
+    pub paused: bool,
+    // Applied in order to the payload of every message before it is written to the sink
+    #[serde(default)]
+    pub transforms: Vec<ConnectorTransform>,
+    #[serde(default)]
+    pub retry_policy: ConnectorRetryPolicy,
+}
+
+// How a connector retries a batch its sink rejected
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ConnectorRetryPolicy {
+    pub max_retries: u32,
+    // The backoff doubles after every retry, from initial_backoff_ms up to max_backoff_ms
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    // Batches still failing after max_retries are written to this topic, or dropped when empty
+    pub dead_letter_topic: String,
+}
+
+// Upper bound of max_backoff_ms, so a failing sink is retried at least every ten minutes
+pub const CONNECTOR_MAX_BACKOFF_MS_MAX: u64 = 600000;
+
+impl Default for ConnectorRetryPolicy {
+    fn default() -> Self {
+        ConnectorRetryPolicy {
+            max_retries: 3,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 10000,
+            dead_letter_topic: String::new(),
+        }
+    }
 }
 
 impl MQTTConnector {
@@ -17,3 +17,4 @@
 pub mod connector;
 pub mod connector_type;
 pub mod status;
+pub mod transform;
@@ -0,0 +1,26 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+// A step applied to the JSON payload of a message before a connector writes it to its sink
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConnectorTransform {
+    // Replaces the payload with the value at a JSONPath, e.g. $.data.temperature or $.items[0]
+    Extract { path: String },
+    // Renames fields of a JSON object payload, from the old name to the new one
+    Rename { fields: BTreeMap<String, String> },
+}
@@ -19,6 +19,8 @@
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 
+use crate::acl::mqtt_acl::MqttAcl;
+
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct MQTTConnection {
     // Connection ID
@@ -51,6 +53,9 @@ pub struct MQTTConnection {
     pub sender_qos_message: Arc<AtomicIsize>,
     // Time when the connection was created
     pub create_time: u64,
+    // ACL rules the HTTP auth service assigned to the connection when it logged in
+    #[serde(default)]
+    pub assigned_acls: Vec<MqttAcl>,
 }
 
 pub struct ConnectionConfig {
@@ -27,6 +27,12 @@ pub struct MqttSession {
     pub broker_id: Option<u64>,
     pub reconnect_time: Option<u64>,
     pub distinct_time: Option<u64>,
+
+    // How many times a reconnecting client continued this session, and when it last did
+    #[serde(default)]
+    pub takeover_count: u64,
+    #[serde(default)]
+    pub last_takeover_at: Option<u64>,
 }
 
 impl MqttSession {
@@ -66,6 +72,11 @@ pub fn update_distinct_time(&mut self) {
         self.distinct_time = Some(now_second());
     }
 
+    pub fn record_takeover(&mut self, takeover_time: u64) {
+        self.takeover_count += 1;
+        self.last_takeover_at = Some(takeover_time);
+    }
+
     pub fn encode(&self) -> String {
         serde_json::to_string(&self).unwrap()
     }
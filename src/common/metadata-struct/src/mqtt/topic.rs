@@ -50,6 +50,8 @@ fn from(topic: MqttTopic) -> Self {
             cluster_name: topic.cluster_name,
             topic_name: topic.topic_name,
             is_contain_retain_message: topic.retain_message.is_some(),
+            create_time: topic.create_time,
+            message_count: 0,
         }
     }
 }
@@ -19,6 +19,9 @@ pub struct MqttUser {
     pub username: String,
     pub password: String,
     pub is_superuser: bool,
+    // seconds since the epoch, 0 for users stored before the time was recorded
+    #[serde(default)]
+    pub create_time: u64,
 }
 
 impl MqttUser {
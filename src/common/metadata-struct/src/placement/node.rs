@@ -47,6 +47,11 @@ fn from(node: BrokerNode) -> Self {
             node_inner_addr: node.node_inner_addr,
             start_time: node.start_time,
             register_time: node.register_time,
+            // Resource usage is sampled when the cluster status is requested
+            cpu_percent: 0.0,
+            memory_bytes: 0,
+            open_fd_num: 0,
+            draining: false,
         }
     }
 }
@@ -23,6 +23,9 @@ pub struct SchemaData {
     pub schema_type: SchemaType,
     pub desc: String,
     pub schema: String,
+    // For protobuf schemas, the fully qualified message type payloads are decoded as
+    #[serde(default)]
+    pub message_name: String,
 }
 
 impl SchemaData {
@@ -19,7 +19,7 @@
     start_delay_message_pop, start_recover_delay_queue,
 };
 use metadata_struct::{adapter::record::Record, delay_info::DelayMessageInfo};
-use persist::persist_delay_info;
+use persist::{build_removed_delay_info, persist_delay_info};
 use std::{
     sync::{atomic::AtomicU64, Arc},
     time::Duration,
@@ -68,6 +68,8 @@ pub struct DelayMessageManager<S> {
     incr_no: AtomicU64,
     delay_queue_list: DashMap<u64, DelayQueue<DelayMessageInfo>>,
     delay_queue_pop_thread: DashMap<u64, broadcast::Sender<bool>>,
+    // (delay_shard_name_offset, DelayMessageInfo), messages that are waiting to be fired
+    delay_message_index: DashMap<String, DelayMessageInfo>,
 }
 
 impl<S> DelayMessageManager<S>
@@ -82,6 +84,7 @@ pub fn new(namespace: String, shard_num: u64, message_storage_adapter: Arc<S>) -
             incr_no: AtomicU64::new(0),
             delay_queue_list: DashMap::with_capacity(2),
             delay_queue_pop_thread: DashMap::with_capacity(2),
+            delay_message_index: DashMap::with_capacity(8),
         }
     }
 
@@ -134,14 +137,66 @@ pub async fn stop(&self) -> Result<(), CommonError> {
     }
 
     pub fn send_to_delay_queue(&self, shard_no: u64, delay_info: &DelayMessageInfo) {
+        self.delay_message_index
+            .insert(delay_message_index_key(delay_info), delay_info.clone());
         if let Some(mut delay_queue) = self.delay_queue_list.get_mut(&shard_no) {
             delay_queue.insert_at(
                 delay_info.clone(),
-                Instant::now() + Duration::from_secs(delay_info.delay_timestamp - now_second()),
+                Instant::now()
+                    + Duration::from_secs(delay_info.delay_timestamp.saturating_sub(now_second())),
             );
         }
     }
 
+    pub fn list_delay_message(&self) -> Vec<DelayMessageInfo> {
+        self.delay_message_index
+            .iter()
+            .map(|raw| raw.value().clone())
+            .collect()
+    }
+
+    // Purge the pending delay messages matched by the filter and return the number removed.
+    // A purged message is only dropped from the index, the pop thread skips it when it expires.
+    pub async fn purge_delay_message<F>(&self, filter: F, dry_run: bool) -> Result<u64, CommonError>
+    where
+        F: Fn(&DelayMessageInfo) -> bool,
+    {
+        let matched: Vec<DelayMessageInfo> = self
+            .delay_message_index
+            .iter()
+            .filter(|raw| filter(raw.value()))
+            .map(|raw| raw.value().clone())
+            .collect();
+
+        if dry_run {
+            return Ok(matched.len() as u64);
+        }
+
+        let mut purged_num = 0;
+        for delay_info in matched {
+            // Messages that started firing in the meantime have already left the index
+            if !self.take_delay_message(&delay_info) {
+                continue;
+            }
+
+            persist_delay_info(
+                &self.message_storage_adapter,
+                &self.namespace,
+                build_removed_delay_info(&delay_info),
+            )
+            .await?;
+            purged_num += 1;
+        }
+        Ok(purged_num)
+    }
+
+    // Removes the message from the index, returns false if it was already fired or purged
+    pub(crate) fn take_delay_message(&self, delay_info: &DelayMessageInfo) -> bool {
+        self.delay_message_index
+            .remove(&delay_message_index_key(delay_info))
+            .is_some()
+    }
+
     pub fn get_shard_num(&self) -> u64 {
         self.shard_num
     }
@@ -156,3 +211,56 @@ fn get_target_shard_no(&self) -> u64 {
             % self.shard_num
     }
 }
+
+pub(crate) fn delay_message_index_key(delay_info: &DelayMessageInfo) -> String {
+    format!("{}_{}", delay_info.delay_shard_name, delay_info.offset)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use metadata_struct::adapter::record::Record;
+    use storage_adapter::memory::MemoryStorageAdapter;
+
+    use crate::DelayMessageManager;
+
+    #[tokio::test]
+    pub async fn purge_delay_message_test() {
+        let namespace = unique_id();
+        let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let delay_message_manager =
+            DelayMessageManager::new(namespace.clone(), 1, message_storage_adapter.clone());
+        delay_message_manager.start().await;
+
+        for i in 0..10 {
+            let target_topic = if i % 2 == 0 { "t1" } else { "t2" };
+            let data = Record::build_str(format!("data{}", i));
+            let res = delay_message_manager
+                .send(target_topic, 100 + i, data)
+                .await;
+            assert!(res.is_ok());
+        }
+        assert_eq!(delay_message_manager.list_delay_message().len(), 10);
+
+        let res = delay_message_manager
+            .purge_delay_message(|info| info.target_shard_name == "t1", true)
+            .await;
+        assert_eq!(res.unwrap(), 5);
+        assert_eq!(delay_message_manager.list_delay_message().len(), 10);
+
+        let res = delay_message_manager
+            .purge_delay_message(|info| info.target_shard_name == "t1", false)
+            .await;
+        assert_eq!(res.unwrap(), 5);
+        let list = delay_message_manager.list_delay_message();
+        assert_eq!(list.len(), 5);
+        assert!(list.iter().all(|info| info.target_shard_name == "t2"));
+
+        let res = delay_message_manager
+            .purge_delay_message(|info| info.target_shard_name == "t1", false)
+            .await;
+        assert_eq!(res.unwrap(), 0);
+    }
+}
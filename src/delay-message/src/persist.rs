@@ -17,15 +17,25 @@
     adapter::{read_config::ReadConfig, record::Record},
     delay_info::DelayMessageInfo,
 };
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use storage_adapter::storage::StorageAdapter;
 use tokio::time::sleep;
 use tracing::{error, info};
 
-use crate::DelayMessageManager;
+use crate::{delay_message_index_key, DelayMessageManager};
 
 const DELAY_QUEUE_INFO_SHARD_NAME: &str = "$delay-queue-info-shard";
 
+// A delay info with this timestamp marks the message at the same offset as sent or purged
+const REMOVED_DELAY_TIMESTAMP: u64 = 0;
+
+pub(crate) fn build_removed_delay_info(delay_info: &DelayMessageInfo) -> DelayMessageInfo {
+    DelayMessageInfo {
+        delay_timestamp: REMOVED_DELAY_TIMESTAMP,
+        ..delay_info.clone()
+    }
+}
+
 pub async fn persist_delay_info<S>(
     message_storage_adapter: &Arc<S>,
     namespace: &str,
@@ -54,8 +64,10 @@ pub async fn recover_delay_queue<S>(
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
+    // Pending messages are collected first, so that messages which were sent or purged
+    // later in the shard are never put back in the delay queue
+    let mut pending: HashMap<String, (u64, DelayMessageInfo)> = HashMap::new();
     let mut offset = 0;
-    let mut total_num = 0;
     loop {
         let data = match message_storage_adapter
             .read_by_offset(
@@ -89,26 +101,36 @@ pub async fn recover_delay_queue<S>(
                 }
             };
 
-            if delay_info.delay_timestamp < now_second() {
+            let key = delay_message_index_key(&delay_info);
+            if delay_info.delay_timestamp == REMOVED_DELAY_TIMESTAMP {
+                pending.remove(&key);
                 continue;
             }
 
-            let shard_no = offset % shard_num;
-            delay_message_manager.send_to_delay_queue(shard_no, &delay_info);
-
-            total_num += 1;
+            pending.insert(key, (offset % shard_num, delay_info));
         }
 
         offset += 1;
     }
-    info!("Delay queue index was successfully constructed from the persistent store. Number of data items: {}", total_num);
+
+    // Messages that became due while the broker was down are sent right away
+    let now = now_second();
+    let total_num = pending.len();
+    let mut overdue_num = 0;
+    for (shard_no, delay_info) in pending.into_values() {
+        if delay_info.delay_timestamp <= now {
+            overdue_num += 1;
+        }
+        delay_message_manager.send_to_delay_queue(shard_no, &delay_info);
+    }
+    info!("Delay queue index was successfully constructed from the persistent store. Number of data items: {}, overdue: {}", total_num, overdue_num);
 }
 
 #[cfg(test)]
 mod test {
     use std::{sync::Arc, time::Duration};
 
-    use common_base::tools::unique_id;
+    use common_base::tools::{now_second, unique_id};
     use metadata_struct::{
         adapter::{read_config::ReadConfig, record::Record},
         delay_info::DelayMessageInfo,
@@ -117,7 +139,11 @@ mod test {
     use tokio::time::sleep;
 
     use crate::{
-        persist::{persist_delay_info, recover_delay_queue, DELAY_QUEUE_INFO_SHARD_NAME},
+        delay::persist_delay_message,
+        persist::{
+            build_removed_delay_info, persist_delay_info, recover_delay_queue,
+            DELAY_QUEUE_INFO_SHARD_NAME,
+        },
         pop::read_offset_data,
         start_delay_message_pop, DelayMessageManager,
     };
@@ -225,4 +251,112 @@ pub async fn build_delay_queue_test() {
             assert_eq!(d, format!("data{}", i));
         }
     }
+
+    #[tokio::test]
+    pub async fn recover_delay_queue_overdue_test() {
+        let namespace = unique_id();
+        let shard_num = 1;
+        let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let read_config = ReadConfig {
+            max_record_num: 100,
+            max_size: 1024 * 1024 * 1024,
+        };
+
+        let delay_shard_name = unique_id();
+        let target_topic = unique_id();
+        for i in 0..3 {
+            let data = Record::build_str(format!("data{}", i));
+            let offset = persist_delay_message(
+                &message_storage_adapter,
+                &namespace,
+                &delay_shard_name,
+                data,
+            )
+            .await
+            .unwrap();
+            // Message 0 became due while the broker was down, 1 was already sent, 2 is pending
+            let delay_timestamp = if i == 2 {
+                now_second() + 3600
+            } else {
+                now_second() - 10
+            };
+            let delay_info = DelayMessageInfo {
+                delay_shard_name: delay_shard_name.clone(),
+                target_shard_name: target_topic.clone(),
+                offset,
+                delay_timestamp,
+            };
+            persist_delay_info(&message_storage_adapter, &namespace, delay_info.clone())
+                .await
+                .unwrap();
+            if i == 1 {
+                persist_delay_info(
+                    &message_storage_adapter,
+                    &namespace,
+                    build_removed_delay_info(&delay_info),
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        let delay_message_manager = Arc::new(DelayMessageManager::new(
+            namespace.clone(),
+            shard_num,
+            message_storage_adapter.clone(),
+        ));
+        delay_message_manager.start().await;
+        recover_delay_queue(
+            &message_storage_adapter,
+            &delay_message_manager,
+            &namespace,
+            read_config.clone(),
+            shard_num,
+        )
+        .await;
+
+        let mut offsets: Vec<u64> = delay_message_manager
+            .list_delay_message()
+            .iter()
+            .map(|info| info.offset)
+            .collect();
+        offsets.sort();
+        assert_eq!(offsets, vec![0, 2]);
+
+        start_delay_message_pop(
+            &delay_message_manager,
+            &message_storage_adapter,
+            &namespace,
+            shard_num,
+        );
+        sleep(Duration::from_secs(2)).await;
+
+        let raw = read_offset_data(&message_storage_adapter, &namespace, &target_topic, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        let d: String = serde_json::from_slice(&raw.data).unwrap();
+        assert_eq!(d, "data0".to_string());
+        let res = read_offset_data(&message_storage_adapter, &namespace, &target_topic, 1).await;
+        assert!(res.unwrap().is_none());
+
+        // The overdue message is marked as sent and not recovered again after a restart
+        let delay_message_manager = Arc::new(DelayMessageManager::new(
+            namespace.clone(),
+            shard_num,
+            message_storage_adapter.clone(),
+        ));
+        delay_message_manager.start().await;
+        recover_delay_queue(
+            &message_storage_adapter,
+            &delay_message_manager,
+            &namespace,
+            read_config,
+            shard_num,
+        )
+        .await;
+        let list = delay_message_manager.list_delay_message();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.first().unwrap().offset, 2);
+    }
 }
@@ -12,11 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{sync::Arc, time::Duration};
+use std::{future::poll_fn, sync::Arc, task::Poll, time::Duration};
 
+use crate::persist::{build_removed_delay_info, persist_delay_info};
 use crate::DelayMessageManager;
 use common_base::error::common::CommonError;
-use futures::StreamExt;
 use metadata_struct::{
     adapter::{read_config::ReadConfig, record::Record},
     delay_info::DelayMessageInfo,
@@ -32,23 +32,48 @@ pub async fn pop_delay_queue<S>(
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
-    if let Some(mut delay_queue) = delay_message_manager.delay_queue_list.get_mut(&shard_no) {
-        while let Some(expired) = delay_queue.next().await {
-            let delay_message = expired.into_inner();
-            let raw_message_storage_adapter = message_storage_adapter.clone();
-            let raw_namespace = namespace.to_owned();
-            tokio::spawn(async move {
-                send_delay_message_to_shard(
-                    &raw_message_storage_adapter,
-                    &raw_namespace,
-                    delay_message,
-                )
-                .await;
-            });
+    while let Some(delay_message) =
+        next_expired_delay_message(delay_message_manager, shard_no).await
+    {
+        if !delay_message_manager.take_delay_message(&delay_message) {
+            info!(
+                "Delay message was purged and will not be sent, shard_name:{},offset:{}",
+                delay_message.delay_shard_name, delay_message.offset
+            );
+            continue;
         }
+        let raw_message_storage_adapter = message_storage_adapter.clone();
+        let raw_namespace = namespace.to_owned();
+        tokio::spawn(async move {
+            send_delay_message_to_shard(
+                &raw_message_storage_adapter,
+                &raw_namespace,
+                delay_message,
+            )
+            .await;
+        });
     }
 }
 
+// The queue is only locked while it is polled, so new delay messages can be inserted
+// while the pop thread waits for the next one to expire.
+async fn next_expired_delay_message<S>(
+    delay_message_manager: &Arc<DelayMessageManager<S>>,
+    shard_no: u64,
+) -> Option<DelayMessageInfo>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    poll_fn(
+        |cx| match delay_message_manager.delay_queue_list.get_mut(&shard_no) {
+            Some(mut delay_queue) => delay_queue.poll_expired(cx),
+            None => Poll::Ready(None),
+        },
+    )
+    .await
+    .map(|expired| expired.into_inner())
+}
+
 async fn send_delay_message_to_shard<S>(
     message_storage_adapter: &Arc<S>,
     namespace: &str,
@@ -96,6 +121,16 @@ async fn send_delay_message_to_shard<S>(
         {
             Ok(id) => {
                 info!("Delay message: message was written to {:?} successfully, offset: {:?}, delay info: {:?}",delay_message.target_shard_name,id, delay_message);
+                // Keeps the message from being sent again when the delay queue is recovered
+                if let Err(e) = persist_delay_info(
+                    message_storage_adapter,
+                    namespace,
+                    build_removed_delay_info(&delay_message),
+                )
+                .await
+                {
+                    error!("Failed to mark delay message as sent, err: {:?}", e);
+                }
                 break;
             }
             Err(e) => {
@@ -144,13 +179,21 @@ mod test {
     use common_base::tools::unique_id;
     use metadata_struct::{adapter::record::Record, delay_info::DelayMessageInfo};
     use storage_adapter::{memory::MemoryStorageAdapter, storage::StorageAdapter};
-    use tokio::time::sleep;
+    use tokio::task::yield_now;
+    use tokio::time::{advance, pause, sleep};
 
     use crate::{
         pop::{read_offset_data, send_delay_message_to_shard},
         start_delay_message_pop, DelayMessageManager,
     };
 
+    // Lets the pop thread and the send tasks it spawns run after the clock moved
+    async fn settle() {
+        for _ in 0..20 {
+            yield_now().await;
+        }
+    }
+
     #[tokio::test]
     pub async fn read_offset_data_test() {
         let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
@@ -252,4 +295,52 @@ pub async fn pop_delay_queue_test() {
             // assert_eq!(d, format!("data{}", i));
         }
     }
+
+    #[tokio::test]
+    pub async fn pop_delay_queue_mock_clock_test() {
+        pause();
+        let namespace = unique_id();
+        let shard_num = 1;
+        let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let delay_message_manager = Arc::new(DelayMessageManager::new(
+            namespace.clone(),
+            shard_num,
+            message_storage_adapter.clone(),
+        ));
+        delay_message_manager.start().await;
+
+        start_delay_message_pop(
+            &delay_message_manager,
+            &message_storage_adapter,
+            &namespace,
+            shard_num,
+        );
+
+        let target_topic = unique_id();
+        let data = Record::build_str("data".to_string());
+        let res = delay_message_manager.send(&target_topic, 10, data).await;
+        assert!(res.is_ok());
+
+        // Not delivered before the delay elapses
+        advance(Duration::from_secs(8)).await;
+        settle().await;
+        let res = read_offset_data(&message_storage_adapter, &namespace, &target_topic, 0).await;
+        assert!(res.unwrap().is_none());
+        assert_eq!(delay_message_manager.list_delay_message().len(), 1);
+
+        // A message sent while the first one waits does not block on the pop thread
+        let data = Record::build_str("data1".to_string());
+        let res = delay_message_manager.send(&target_topic, 60, data).await;
+        assert!(res.is_ok());
+
+        advance(Duration::from_secs(3)).await;
+        settle().await;
+        let raw = read_offset_data(&message_storage_adapter, &namespace, &target_topic, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        let d: String = serde_json::from_slice(&raw.data).unwrap();
+        assert_eq!(d, "data".to_string());
+        assert_eq!(delay_message_manager.list_delay_message().len(), 1);
+    }
 }
@@ -15,6 +15,7 @@
 mod macros;
 
 pub mod journal;
+pub mod metrics;
 pub mod mqtt;
 pub mod placement;
 pub mod pool;
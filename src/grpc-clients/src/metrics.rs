@@ -0,0 +1,66 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ClientPoolRequestLabel {
+    endpoint: String,
+    outcome: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ClientPoolEndpointLabel {
+    endpoint: String,
+}
+
+common_base::register_counter_metric!(
+    CLIENT_POOL_REQUEST_COUNTER,
+    "grpc_client_pool_request",
+    "Number of requests sent through the grpc client pool per endpoint",
+    ClientPoolRequestLabel
+);
+
+common_base::register_histogram_metric!(
+    CLIENT_POOL_WAIT_MS,
+    "grpc_client_pool_wait_ms",
+    "Time spent waiting for a connection from the grpc client pool per endpoint",
+    ClientPoolEndpointLabel,
+    0.5,
+    2.0,
+    12
+);
+
+pub const CLIENT_POOL_OUTCOME_SUCCESS: &str = "success";
+pub const CLIENT_POOL_OUTCOME_ERROR: &str = "error";
+
+pub fn record_client_pool_request(endpoint: &str, success: bool) {
+    let outcome = if success {
+        CLIENT_POOL_OUTCOME_SUCCESS
+    } else {
+        CLIENT_POOL_OUTCOME_ERROR
+    };
+    let label = ClientPoolRequestLabel {
+        endpoint: endpoint.to_string(),
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(CLIENT_POOL_REQUEST_COUNTER, label);
+}
+
+pub fn record_client_pool_wait_ms(endpoint: &str, ms: f64) {
+    let label = ClientPoolEndpointLabel {
+        endpoint: endpoint.to_string(),
+    };
+    common_base::histogram_metric_observe!(CLIENT_POOL_WAIT_MS, ms, label);
+}
@@ -14,27 +14,79 @@
 
 use common_base::error::common::CommonError;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateTopicRewriteRuleReply,
-    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
-    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
-    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteTopicRewriteRuleReply,
-    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, EnableFlappingDetectReply,
-    EnableFlappingDetectRequest, GetClusterConfigReply, GetClusterConfigRequest, ListAclReply,
-    ListAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
-    ListBlacklistRequest, ListConnectionReply, ListConnectionRequest, ListSessionReply,
-    ListSessionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListSystemAlarmReply,
-    ListSystemAlarmRequest, ListTopicReply, ListTopicRequest, ListUserReply, ListUserRequest,
-    MqttBindSchemaReply, MqttBindSchemaRequest, MqttCreateConnectorReply,
+    AckSystemAlarmReply, AckSystemAlarmRequest, BatchCreateUserReply, BatchCreateUserRequest,
+    BenchmarkMatcherReply, BenchmarkMatcherRequest, CancelInflightQos2Reply,
+    CancelInflightQos2Request, ClientMetricsRaw, ClusterStatusReply, ClusterStatusRequest,
+    CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
+    CreateSnapshotReply, CreateSnapshotRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclByPrefixReply,
+    DeleteAclByPrefixRequest, DeleteAclReply, DeleteAclRequest, DeleteAutoSubscribeRuleReply,
+    DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
+    DeleteClientGroupReply, DeleteClientGroupRequest, DeleteRetainedMessageReply,
+    DeleteRetainedMessageRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
+    DeleteUserRateLimitReply, DeleteUserRateLimitRequest, DeleteUserReply, DeleteUserRequest,
+    DescribeSubscriptionReply, DescribeSubscriptionRequest, DisconnectClientReply,
+    DisconnectClientRequest, EnableFlappingDetectReply, EnableFlappingDetectRequest,
+    EstimateRetainDeliveryReply, EstimateRetainDeliveryRequest, ExportAclRaw, ExportAclRequest,
+    ExportClientMetricsRequest, GcTopicsReply, GcTopicsRequest, GetAclConfigReply,
+    GetAclConfigRequest, GetClientPoolStatusReply, GetClientPoolStatusRequest,
+    GetClusterConfigReply, GetClusterConfigRequest, GetDuplicateClientIdConfigReply,
+    GetDuplicateClientIdConfigRequest, GetMessageDedupConfigReply, GetMessageDedupConfigRequest,
+    GetMessageSamplingRequest, GetRetainMessageConfigReply, GetRetainMessageConfigRequest,
+    GetSessionTieringReply, GetSessionTieringRequest, GetSubscribeAuthConfigReply,
+    GetSubscribeAuthConfigRequest, GetSubscribeSnapshotReply, GetSubscribeSnapshotRequest,
+    GetSystemAlarmConfigReply, GetSystemAlarmConfigRequest, GetUserSingleSessionReply,
+    GetUserSingleSessionRequest, GetWillPublishConfigReply, GetWillPublishConfigRequest,
+    ImportAclReply, ImportAclRequest, ListAclReply, ListAclRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListClientGroupReply,
+    ListClientGroupRequest, ListConnectionReply, ListConnectionRequest, ListDelayMessageReply,
+    ListDelayMessageRequest, ListExpiredSessionsReply, ListExpiredSessionsRequest,
+    ListFlappingDetectBanReply, ListFlappingDetectBanRequest, ListInflightQos2Reply,
+    ListInflightQos2Request, ListLargestSessionReply, ListLargestSessionRequest,
+    ListListenerMetricsReply, ListListenerMetricsRequest, ListRateLimitReply, ListRateLimitRequest,
+    ListRetainTopicPolicyReply, ListRetainTopicPolicyRequest, ListRetainedMessagesReply,
+    ListRetainedMessagesRequest, ListSessionReply, ListSessionRequest,
+    ListSessionSubscriptionsReply, ListSessionSubscriptionsRequest, ListSharedDispatchReply,
+    ListSharedDispatchRequest, ListSharedSubscriptionsReply, ListSharedSubscriptionsRequest,
+    ListSlowSubscribeReply, ListSlowSubscribeRequest, ListSubscribeDetailReply,
+    ListSubscribeDetailRequest, ListSystemAlarmReply, ListSystemAlarmRequest, ListThreadPoolReply,
+    ListThreadPoolRequest, ListTopTalkersReply, ListTopTalkersRequest, ListTopicReply,
+    ListTopicRequest, ListUserMessageExpiryReply, ListUserMessageExpiryRequest, ListUserReply,
+    ListUserRequest, MqttBindSchemaReply, MqttBindSchemaRequest, MqttCreateConnectorReply,
     MqttCreateConnectorRequest, MqttCreateSchemaReply, MqttCreateSchemaRequest,
     MqttDeleteConnectorReply, MqttDeleteConnectorRequest, MqttDeleteSchemaReply,
-    MqttDeleteSchemaRequest, MqttListBindSchemaReply, MqttListBindSchemaRequest,
-    MqttListConnectorReply, MqttListConnectorRequest, MqttListSchemaReply, MqttListSchemaRequest,
+    MqttDeleteSchemaRequest, MqttGetConnectorLimitReply, MqttGetConnectorLimitRequest,
+    MqttInferSchemaReply, MqttInferSchemaRequest, MqttListBindSchemaReply,
+    MqttListBindSchemaRequest, MqttListConnectorReply, MqttListConnectorRequest,
+    MqttListSchemaReply, MqttListSchemaRequest, MqttPauseConnectorReply, MqttPauseConnectorRequest,
+    MqttResumeConnectorReply, MqttResumeConnectorRequest, MqttSetConnectorLimitReply,
+    MqttSetConnectorLimitRequest, MqttTestConnectorRoutingReply, MqttTestConnectorRoutingRequest,
     MqttUnbindSchemaReply, MqttUnbindSchemaRequest, MqttUpdateConnectorReply,
     MqttUpdateConnectorRequest, MqttUpdateSchemaReply, MqttUpdateSchemaRequest,
-    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetClusterConfigReply,
-    SetClusterConfigRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    MqttValidateConnectorReply, MqttValidateConnectorRequest, PurgeDelayMessageReply,
+    PurgeDelayMessageRequest, PurgeExpiredSessionsReply, PurgeExpiredSessionsRequest,
+    RedirectClientReply, RedirectClientRequest, ReplayToClientReply, ReplayToClientRequest,
+    ResetTopicSequenceReply, ResetTopicSequenceRequest, RestoreSnapshotReply,
+    RestoreSnapshotRequest, RestoreSubscribeSnapshotReply, RestoreSubscribeSnapshotRequest,
+    SelfDiagnoseReply, SelfDiagnoseRequest, SetAclDefaultPolicyReply, SetAclDefaultPolicyRequest,
+    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetClientGroupMemberReply,
+    SetClientGroupMemberRequest, SetClientGroupReply, SetClientGroupRequest,
+    SetClientPoolSizeReply, SetClientPoolSizeRequest, SetClusterConfigReply,
+    SetClusterConfigRequest, SetConnectionLimitReply, SetConnectionLimitRequest,
+    SetMessageDedupConfigReply, SetMessageDedupConfigRequest, SetMessageSamplingReply,
+    SetMessageSamplingRequest, SetNodeDrainingReply, SetNodeDrainingRequest,
+    SetRetainDeliverLimitReply, SetRetainDeliverLimitRequest, SetRetainSizeLimitReply,
+    SetRetainSizeLimitRequest, SetRetainTopicPolicyReply, SetRetainTopicPolicyRequest,
+    SetSessionTieringReply, SetSessionTieringRequest, SetSharedDispatchReply,
+    SetSharedDispatchRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    SetUserMaxQosReply, SetUserMaxQosRequest, SetUserMessageExpiryReply,
+    SetUserMessageExpiryRequest, SetUserRateLimitReply, SetUserRateLimitRequest,
+    SetUserSingleSessionReply, SetUserSingleSessionRequest, StreamConnectionsReply,
+    StreamConnectionsRequest, TestAclReply, TestAclRequest, TestTopicRewriteReply,
+    TestTopicRewriteRequest, TopicCleanupReply, TopicCleanupRequest, TopicSequenceReply,
+    TopicSequenceRequest, UnbanFlappingDetectClientReply, UnbanFlappingDetectClientRequest,
 };
+use tonic::Streaming;
 
 use crate::pool::ClientPool;
 
@@ -88,6 +140,13 @@ pub async fn $fn_name(
     CreateUser
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_batch_create_user,
+    BatchCreateUserRequest,
+    BatchCreateUserReply,
+    BatchCreateUser
+);
+
 generate_mqtt_admin_service_call!(
     mqtt_broker_delete_user,
     DeleteUserRequest,
@@ -140,6 +199,21 @@ pub async fn $fn_name(
     ListConnection
 );
 
+// chunks are streamed, the retry only covers opening the stream
+generate_mqtt_admin_service_call!(
+    mqtt_broker_stream_connections,
+    StreamConnectionsRequest,
+    Streaming<StreamConnectionsReply>,
+    StreamConnections
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_top_talkers,
+    ListTopTalkersRequest,
+    ListTopTalkersReply,
+    ListTopTalkers
+);
+
 // -------flapping detect feat  -----------
 generate_mqtt_admin_service_call!(
     mqtt_broker_enable_flapping_detect,
@@ -166,6 +240,13 @@ pub async fn $fn_name(
     SetSystemAlarmConfig
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_system_alarm_config,
+    GetSystemAlarmConfigRequest,
+    GetSystemAlarmConfigReply,
+    GetSystemAlarmConfig
+);
+
 generate_mqtt_admin_service_call!(
     mqtt_broker_list_system_alarm,
     ListSystemAlarmRequest,
@@ -173,6 +254,13 @@ pub async fn $fn_name(
     ListSystemAlarm
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_ack_system_alarm,
+    AckSystemAlarmRequest,
+    AckSystemAlarmReply,
+    AckSystemAlarm
+);
+
 generate_mqtt_admin_service_call!(
     mqtt_broker_list_topic,
     ListTopicRequest,
@@ -194,6 +282,13 @@ pub async fn $fn_name(
     DeleteTopicRewriteRule
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_test_topic_rewrite,
+    TestTopicRewriteRequest,
+    TestTopicRewriteReply,
+    TestTopicRewrite
+);
+
 // connector command line CRUD
 generate_mqtt_admin_service_call!(
     mqtt_broker_list_connector,
@@ -223,6 +318,20 @@ pub async fn $fn_name(
     MqttDeleteConnector
 );
 
+generate_mqtt_admin_service_call!(
+    mqtt_broker_pause_connector,
+    MqttPauseConnectorRequest,
+    MqttPauseConnectorReply,
+    MqttPauseConnector
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_resume_connector,
+    MqttResumeConnectorRequest,
+    MqttResumeConnectorReply,
+    MqttResumeConnector
+);
+
 // schema command line CRUD
 generate_mqtt_admin_service_call!(
     mqtt_broker_list_schema,
@@ -300,3 +409,562 @@ pub async fn $fn_name(
     ListSessionReply,
     ListSession
 );
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_session_subscriptions,
+    ListSessionSubscriptionsRequest,
+    ListSessionSubscriptionsReply,
+    ListSessionSubscriptions
+);
+
+// ---- subscribe ----
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_subscribe_detail,
+    ListSubscribeDetailRequest,
+    ListSubscribeDetailReply,
+    ListSubscribeDetail
+);
+
+// ---- retain message ----
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_retain_message_config,
+    GetRetainMessageConfigRequest,
+    GetRetainMessageConfigReply,
+    GetRetainMessageConfig
+);
+
+// ---- delay message ----
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_delay_message,
+    ListDelayMessageRequest,
+    ListDelayMessageReply,
+    ListDelayMessage
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_purge_delay_message,
+    PurgeDelayMessageRequest,
+    PurgeDelayMessageReply,
+    PurgeDelayMessage
+);
+
+// ---- user message expiry ----
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_user_message_expiry,
+    SetUserMessageExpiryRequest,
+    SetUserMessageExpiryReply,
+    SetUserMessageExpiry
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_user_message_expiry,
+    ListUserMessageExpiryRequest,
+    ListUserMessageExpiryReply,
+    ListUserMessageExpiry
+);
+
+// ---- self diagnose ----
+generate_mqtt_admin_service_call!(
+    mqtt_broker_self_diagnose,
+    SelfDiagnoseRequest,
+    SelfDiagnoseReply,
+    SelfDiagnose
+);
+
+// ---- duplicate client id ----
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_duplicate_client_id_config,
+    GetDuplicateClientIdConfigRequest,
+    GetDuplicateClientIdConfigReply,
+    GetDuplicateClientIdConfig
+);
+
+// ---- thread pool ----
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_thread_pool,
+    ListThreadPoolRequest,
+    ListThreadPoolReply,
+    ListThreadPool
+);
+
+// topic cleanup
+generate_mqtt_admin_service_call!(
+    mqtt_broker_topic_cleanup,
+    TopicCleanupRequest,
+    TopicCleanupReply,
+    TopicCleanup
+);
+
+// topic gc
+generate_mqtt_admin_service_call!(
+    mqtt_broker_gc_topics,
+    GcTopicsRequest,
+    GcTopicsReply,
+    GcTopics
+);
+
+// acl entries are streamed, the retry only covers opening the stream
+generate_mqtt_admin_service_call!(
+    mqtt_broker_export_acl,
+    ExportAclRequest,
+    Streaming<ExportAclRaw>,
+    ExportAcl
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_delete_acl_by_prefix,
+    DeleteAclByPrefixRequest,
+    DeleteAclByPrefixReply,
+    DeleteAclByPrefix
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_import_acl,
+    ImportAclRequest,
+    ImportAclReply,
+    ImportAcl
+);
+
+// rows are streamed, the retry only covers opening the stream
+generate_mqtt_admin_service_call!(
+    mqtt_broker_export_client_metrics,
+    ExportClientMetricsRequest,
+    Streaming<ClientMetricsRaw>,
+    ExportClientMetrics
+);
+
+// subscribe auth
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_subscribe_auth_config,
+    GetSubscribeAuthConfigRequest,
+    GetSubscribeAuthConfigReply,
+    GetSubscribeAuthConfig
+);
+
+// snapshot
+generate_mqtt_admin_service_call!(
+    mqtt_broker_create_snapshot,
+    CreateSnapshotRequest,
+    CreateSnapshotReply,
+    CreateSnapshot
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_restore_snapshot,
+    RestoreSnapshotRequest,
+    RestoreSnapshotReply,
+    RestoreSnapshot
+);
+
+// listener
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_listener_metrics,
+    ListListenerMetricsRequest,
+    ListListenerMetricsReply,
+    ListListenerMetrics
+);
+
+// shared dispatch
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_shared_dispatch,
+    SetSharedDispatchRequest,
+    SetSharedDispatchReply,
+    SetSharedDispatch
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_shared_dispatch,
+    ListSharedDispatchRequest,
+    ListSharedDispatchReply,
+    ListSharedDispatch
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_shared_subscriptions,
+    ListSharedSubscriptionsRequest,
+    ListSharedSubscriptionsReply,
+    ListSharedSubscriptions
+);
+
+// topic sequence
+generate_mqtt_admin_service_call!(
+    mqtt_broker_topic_sequence,
+    TopicSequenceRequest,
+    TopicSequenceReply,
+    TopicSequence
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_reset_topic_sequence,
+    ResetTopicSequenceRequest,
+    ResetTopicSequenceReply,
+    ResetTopicSequence
+);
+
+// retain size limit
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_retain_size_limit,
+    SetRetainSizeLimitRequest,
+    SetRetainSizeLimitReply,
+    SetRetainSizeLimit
+);
+
+// retain topic policy
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_retain_topic_policy,
+    SetRetainTopicPolicyRequest,
+    SetRetainTopicPolicyReply,
+    SetRetainTopicPolicy
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_retain_topic_policy,
+    ListRetainTopicPolicyRequest,
+    ListRetainTopicPolicyReply,
+    ListRetainTopicPolicy
+);
+
+// largest session
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_largest_session,
+    ListLargestSessionRequest,
+    ListLargestSessionReply,
+    ListLargestSession
+);
+
+// expired sessions
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_expired_sessions,
+    ListExpiredSessionsRequest,
+    ListExpiredSessionsReply,
+    ListExpiredSessions
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_purge_expired_sessions,
+    PurgeExpiredSessionsRequest,
+    PurgeExpiredSessionsReply,
+    PurgeExpiredSessions
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_redirect_client,
+    RedirectClientRequest,
+    RedirectClientReply,
+    RedirectClient
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_validate_connector,
+    MqttValidateConnectorRequest,
+    MqttValidateConnectorReply,
+    MqttValidateConnector
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_message_sampling,
+    GetMessageSamplingRequest,
+    GetMessageSamplingReply,
+    GetMessageSampling
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_message_sampling,
+    SetMessageSamplingRequest,
+    SetMessageSamplingReply,
+    SetMessageSampling
+);
+
+// rate limit
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_rate_limits,
+    ListRateLimitRequest,
+    ListRateLimitReply,
+    ListRateLimit
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_user_rate_limit,
+    SetUserRateLimitRequest,
+    SetUserRateLimitReply,
+    SetUserRateLimit
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_delete_user_rate_limit,
+    DeleteUserRateLimitRequest,
+    DeleteUserRateLimitReply,
+    DeleteUserRateLimit
+);
+
+// flapping detect
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_flapping_detect_ban,
+    ListFlappingDetectBanRequest,
+    ListFlappingDetectBanReply,
+    ListFlappingDetectBan
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_unban_flapping_detect_client,
+    UnbanFlappingDetectClientRequest,
+    UnbanFlappingDetectClientReply,
+    UnbanFlappingDetectClient
+);
+
+// session tiering
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_session_tiering,
+    GetSessionTieringRequest,
+    GetSessionTieringReply,
+    GetSessionTiering
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_session_tiering,
+    SetSessionTieringRequest,
+    SetSessionTieringReply,
+    SetSessionTiering
+);
+
+// subscription matcher benchmark
+generate_mqtt_admin_service_call!(
+    mqtt_broker_benchmark_matcher,
+    BenchmarkMatcherRequest,
+    BenchmarkMatcherReply,
+    BenchmarkMatcher
+);
+
+// will publish
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_will_publish_config,
+    GetWillPublishConfigRequest,
+    GetWillPublishConfigReply,
+    GetWillPublishConfig
+);
+
+// connector routing
+generate_mqtt_admin_service_call!(
+    mqtt_broker_test_connector_routing,
+    MqttTestConnectorRoutingRequest,
+    MqttTestConnectorRoutingReply,
+    MqttTestConnectorRouting
+);
+
+// acl default policy
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_acl_config,
+    GetAclConfigRequest,
+    GetAclConfigReply,
+    GetAclConfig
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_acl_default_policy,
+    SetAclDefaultPolicyRequest,
+    SetAclDefaultPolicyReply,
+    SetAclDefaultPolicy
+);
+
+generate_mqtt_admin_service_call!(mqtt_broker_test_acl, TestAclRequest, TestAclReply, TestAcl);
+
+// inflight qos2
+generate_mqtt_admin_service_call!(
+    list_inflight_qos2,
+    ListInflightQos2Request,
+    ListInflightQos2Reply,
+    ListInflightQos2
+);
+
+generate_mqtt_admin_service_call!(
+    cancel_inflight_qos2,
+    CancelInflightQos2Request,
+    CancelInflightQos2Reply,
+    CancelInflightQos2
+);
+
+// connector limit
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_connector_limit,
+    MqttGetConnectorLimitRequest,
+    MqttGetConnectorLimitReply,
+    MqttGetConnectorLimit
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_connector_limit,
+    MqttSetConnectorLimitRequest,
+    MqttSetConnectorLimitReply,
+    MqttSetConnectorLimit
+);
+
+// retain delivery
+generate_mqtt_admin_service_call!(
+    set_retain_deliver_limit,
+    SetRetainDeliverLimitRequest,
+    SetRetainDeliverLimitReply,
+    SetRetainDeliverLimit
+);
+
+generate_mqtt_admin_service_call!(
+    estimate_retain_delivery,
+    EstimateRetainDeliveryRequest,
+    EstimateRetainDeliveryReply,
+    EstimateRetainDelivery
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_list_retained_messages,
+    ListRetainedMessagesRequest,
+    ListRetainedMessagesReply,
+    ListRetainedMessages
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_delete_retained_message,
+    DeleteRetainedMessageRequest,
+    DeleteRetainedMessageReply,
+    DeleteRetainedMessage
+);
+
+// client group
+generate_mqtt_admin_service_call!(
+    set_client_group,
+    SetClientGroupRequest,
+    SetClientGroupReply,
+    SetClientGroup
+);
+
+generate_mqtt_admin_service_call!(
+    delete_client_group,
+    DeleteClientGroupRequest,
+    DeleteClientGroupReply,
+    DeleteClientGroup
+);
+
+generate_mqtt_admin_service_call!(
+    set_client_group_member,
+    SetClientGroupMemberRequest,
+    SetClientGroupMemberReply,
+    SetClientGroupMember
+);
+
+generate_mqtt_admin_service_call!(
+    set_user_max_qos,
+    SetUserMaxQosRequest,
+    SetUserMaxQosReply,
+    SetUserMaxQos
+);
+
+generate_mqtt_admin_service_call!(
+    list_client_group,
+    ListClientGroupRequest,
+    ListClientGroupReply,
+    ListClientGroup
+);
+
+// client pool
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_client_pool_status,
+    GetClientPoolStatusRequest,
+    GetClientPoolStatusReply,
+    GetClientPoolStatus
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_client_pool_size,
+    SetClientPoolSizeRequest,
+    SetClientPoolSizeReply,
+    SetClientPoolSize
+);
+
+// node draining
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_node_draining,
+    SetNodeDrainingRequest,
+    SetNodeDrainingReply,
+    SetNodeDraining
+);
+
+// message dedup
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_message_dedup_config,
+    SetMessageDedupConfigRequest,
+    SetMessageDedupConfigReply,
+    SetMessageDedupConfig
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_message_dedup_config,
+    GetMessageDedupConfigRequest,
+    GetMessageDedupConfigReply,
+    GetMessageDedupConfig
+);
+
+// replay
+generate_mqtt_admin_service_call!(
+    mqtt_broker_replay_to_client,
+    ReplayToClientRequest,
+    ReplayToClientReply,
+    ReplayToClient
+);
+
+// user single session
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_user_single_session,
+    SetUserSingleSessionRequest,
+    SetUserSingleSessionReply,
+    SetUserSingleSession
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_user_single_session,
+    GetUserSingleSessionRequest,
+    GetUserSingleSessionReply,
+    GetUserSingleSession
+);
+
+// connections per source ip
+generate_mqtt_admin_service_call!(
+    mqtt_broker_set_connection_limit,
+    SetConnectionLimitRequest,
+    SetConnectionLimitReply,
+    SetConnectionLimit
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_describe_subscription,
+    DescribeSubscriptionRequest,
+    DescribeSubscriptionReply,
+    DescribeSubscription
+);
+
+// latest persisted subscribe snapshot of a broker
+generate_mqtt_admin_service_call!(
+    mqtt_broker_get_subscribe_snapshot,
+    GetSubscribeSnapshotRequest,
+    GetSubscribeSnapshotReply,
+    GetSubscribeSnapshot
+);
+
+// restore the subscriptions of a persisted subscribe snapshot of a broker
+generate_mqtt_admin_service_call!(
+    mqtt_broker_restore_subscribe_snapshot,
+    RestoreSubscribeSnapshotRequest,
+    RestoreSubscribeSnapshotReply,
+    RestoreSubscribeSnapshot
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_infer_schema,
+    MqttInferSchemaRequest,
+    MqttInferSchemaReply,
+    MqttInferSchema
+);
+
+generate_mqtt_admin_service_call!(
+    mqtt_broker_disconnect_client,
+    DisconnectClientRequest,
+    DisconnectClientReply,
+    DisconnectClient
+);
@@ -16,15 +16,66 @@
 use mobc::Manager;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_client::MqttBrokerAdminServiceClient;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterStatusReply, ClusterStatusRequest, DeleteAutoSubscribeRuleReply,
-    DeleteAutoSubscribeRuleRequest, GetClusterConfigReply, GetClusterConfigRequest,
-    ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListSessionReply, ListSessionRequest,
-    ListSystemAlarmReply, ListSystemAlarmRequest, MqttCreateConnectorReply,
-    MqttCreateConnectorRequest, MqttDeleteConnectorReply, MqttDeleteConnectorRequest,
-    MqttListConnectorReply, MqttListConnectorRequest, MqttUpdateConnectorReply,
-    MqttUpdateConnectorRequest, SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest,
-    SetClusterConfigReply, SetClusterConfigRequest, SetSystemAlarmConfigReply,
-    SetSystemAlarmConfigRequest,
+    AckSystemAlarmReply, AckSystemAlarmRequest, BatchCreateUserReply, BatchCreateUserRequest,
+    BenchmarkMatcherReply, BenchmarkMatcherRequest, CancelInflightQos2Reply,
+    CancelInflightQos2Request, ClientMetricsRaw, ClusterStatusReply, ClusterStatusRequest,
+    CreateSnapshotReply, CreateSnapshotRequest, DeleteAclByPrefixReply, DeleteAclByPrefixRequest,
+    DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest, DeleteClientGroupReply,
+    DeleteClientGroupRequest, DeleteRetainedMessageReply, DeleteRetainedMessageRequest,
+    DeleteUserRateLimitReply, DeleteUserRateLimitRequest, DescribeSubscriptionReply,
+    DescribeSubscriptionRequest, DisconnectClientReply, DisconnectClientRequest,
+    EstimateRetainDeliveryReply, EstimateRetainDeliveryRequest, ExportAclRaw, ExportAclRequest,
+    ExportClientMetricsRequest, GcTopicsReply, GcTopicsRequest, GetAclConfigReply,
+    GetAclConfigRequest, GetClientPoolStatusReply, GetClientPoolStatusRequest,
+    GetClusterConfigReply, GetClusterConfigRequest, GetDuplicateClientIdConfigReply,
+    GetDuplicateClientIdConfigRequest, GetMessageDedupConfigReply, GetMessageDedupConfigRequest,
+    GetMessageSamplingRequest, GetRetainMessageConfigReply, GetRetainMessageConfigRequest,
+    GetSessionTieringReply, GetSessionTieringRequest, GetSubscribeAuthConfigReply,
+    GetSubscribeAuthConfigRequest, GetSubscribeSnapshotReply, GetSubscribeSnapshotRequest,
+    GetSystemAlarmConfigReply, GetSystemAlarmConfigRequest, GetUserSingleSessionReply,
+    GetUserSingleSessionRequest, GetWillPublishConfigReply, GetWillPublishConfigRequest,
+    ImportAclReply, ImportAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest,
+    ListClientGroupReply, ListClientGroupRequest, ListDelayMessageReply, ListDelayMessageRequest,
+    ListExpiredSessionsReply, ListExpiredSessionsRequest, ListFlappingDetectBanReply,
+    ListFlappingDetectBanRequest, ListInflightQos2Reply, ListInflightQos2Request,
+    ListLargestSessionReply, ListLargestSessionRequest, ListListenerMetricsReply,
+    ListListenerMetricsRequest, ListRateLimitReply, ListRateLimitRequest,
+    ListRetainTopicPolicyReply, ListRetainTopicPolicyRequest, ListRetainedMessagesReply,
+    ListRetainedMessagesRequest, ListSessionReply, ListSessionRequest,
+    ListSessionSubscriptionsReply, ListSessionSubscriptionsRequest, ListSharedDispatchReply,
+    ListSharedDispatchRequest, ListSharedSubscriptionsReply, ListSharedSubscriptionsRequest,
+    ListSubscribeDetailReply, ListSubscribeDetailRequest, ListSystemAlarmReply,
+    ListSystemAlarmRequest, ListThreadPoolReply, ListThreadPoolRequest, ListTopTalkersReply,
+    ListTopTalkersRequest, ListUserMessageExpiryReply, ListUserMessageExpiryRequest,
+    MqttCreateConnectorReply, MqttCreateConnectorRequest, MqttDeleteConnectorReply,
+    MqttDeleteConnectorRequest, MqttGetConnectorLimitReply, MqttGetConnectorLimitRequest,
+    MqttInferSchemaReply, MqttInferSchemaRequest, MqttListConnectorReply, MqttListConnectorRequest,
+    MqttPauseConnectorReply, MqttPauseConnectorRequest, MqttResumeConnectorReply,
+    MqttResumeConnectorRequest, MqttSetConnectorLimitReply, MqttSetConnectorLimitRequest,
+    MqttTestConnectorRoutingReply, MqttTestConnectorRoutingRequest, MqttUpdateConnectorReply,
+    MqttUpdateConnectorRequest, MqttValidateConnectorReply, MqttValidateConnectorRequest,
+    PurgeDelayMessageReply, PurgeDelayMessageRequest, PurgeExpiredSessionsReply,
+    PurgeExpiredSessionsRequest, RedirectClientReply, RedirectClientRequest, ReplayToClientReply,
+    ReplayToClientRequest, ResetTopicSequenceReply, ResetTopicSequenceRequest,
+    RestoreSnapshotReply, RestoreSnapshotRequest, RestoreSubscribeSnapshotReply,
+    RestoreSubscribeSnapshotRequest, SelfDiagnoseReply, SelfDiagnoseRequest,
+    SetAclDefaultPolicyReply, SetAclDefaultPolicyRequest, SetAutoSubscribeRuleReply,
+    SetAutoSubscribeRuleRequest, SetClientGroupMemberReply, SetClientGroupMemberRequest,
+    SetClientGroupReply, SetClientGroupRequest, SetClientPoolSizeReply, SetClientPoolSizeRequest,
+    SetClusterConfigReply, SetClusterConfigRequest, SetConnectionLimitReply,
+    SetConnectionLimitRequest, SetMessageDedupConfigReply, SetMessageDedupConfigRequest,
+    SetMessageSamplingReply, SetMessageSamplingRequest, SetNodeDrainingReply,
+    SetNodeDrainingRequest, SetRetainDeliverLimitReply, SetRetainDeliverLimitRequest,
+    SetRetainSizeLimitReply, SetRetainSizeLimitRequest, SetRetainTopicPolicyReply,
+    SetRetainTopicPolicyRequest, SetSessionTieringReply, SetSessionTieringRequest,
+    SetSharedDispatchReply, SetSharedDispatchRequest, SetSystemAlarmConfigReply,
+    SetSystemAlarmConfigRequest, SetUserMaxQosReply, SetUserMaxQosRequest,
+    SetUserMessageExpiryReply, SetUserMessageExpiryRequest, SetUserRateLimitReply,
+    SetUserRateLimitRequest, SetUserSingleSessionReply, SetUserSingleSessionRequest,
+    StreamConnectionsReply, StreamConnectionsRequest, TestAclReply, TestAclRequest,
+    TestTopicRewriteReply, TestTopicRewriteRequest, TopicCleanupReply, TopicCleanupRequest,
+    TopicSequenceReply, TopicSequenceRequest, UnbanFlappingDetectClientReply,
+    UnbanFlappingDetectClientRequest,
 };
 use protocol::broker_mqtt::broker_mqtt_admin::{
     CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
@@ -40,6 +91,7 @@
     MqttUnbindSchemaReply, MqttUnbindSchemaRequest, MqttUpdateSchemaReply, MqttUpdateSchemaRequest,
 };
 use tonic::transport::Channel;
+use tonic::Streaming;
 
 use crate::macros::impl_retriable_request;
 
@@ -180,6 +232,22 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_list_connection
 );
 
+impl_retriable_request!(
+    StreamConnectionsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    Streaming<StreamConnectionsReply>,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_stream_connections
+);
+
+impl_retriable_request!(
+    ListTopTalkersRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListTopTalkersReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_top_talkers
+);
+
 impl_retriable_request!(
     EnableFlappingDetectRequest,
     MqttBrokerAdminServiceClient<Channel>,
@@ -208,6 +276,14 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_set_system_alarm_config
 );
 
+impl_retriable_request!(
+    GetSystemAlarmConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetSystemAlarmConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_system_alarm_config
+);
+
 impl_retriable_request!(
     ListSystemAlarmRequest,
     MqttBrokerAdminServiceClient<Channel>,
@@ -216,6 +292,14 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_list_system_alarm
 );
 
+impl_retriable_request!(
+    AckSystemAlarmRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    AckSystemAlarmReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_ack_system_alarm
+);
+
 impl_retriable_request!(
     ListTopicRequest,
     MqttBrokerAdminServiceClient<Channel>,
@@ -361,3 +445,635 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_admin_services_client,
     mqtt_broker_list_session
 );
+
+impl_retriable_request!(
+    ListSubscribeDetailRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListSubscribeDetailReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_subscribe_detail
+);
+
+impl_retriable_request!(
+    GetRetainMessageConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetRetainMessageConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_retain_message_config
+);
+
+impl_retriable_request!(
+    ListDelayMessageRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListDelayMessageReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_delay_message
+);
+
+impl_retriable_request!(
+    PurgeDelayMessageRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    PurgeDelayMessageReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_purge_delay_message
+);
+
+impl_retriable_request!(
+    SetUserMessageExpiryRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetUserMessageExpiryReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_user_message_expiry
+);
+
+impl_retriable_request!(
+    ListUserMessageExpiryRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListUserMessageExpiryReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_user_message_expiry
+);
+
+impl_retriable_request!(
+    SelfDiagnoseRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SelfDiagnoseReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_self_diagnose
+);
+
+impl_retriable_request!(
+    GetDuplicateClientIdConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetDuplicateClientIdConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_duplicate_client_id_config
+);
+
+impl_retriable_request!(
+    ListThreadPoolRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListThreadPoolReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_thread_pool
+);
+
+impl_retriable_request!(
+    TopicCleanupRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    TopicCleanupReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_topic_cleanup
+);
+
+impl_retriable_request!(
+    GcTopicsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GcTopicsReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_gc_topics
+);
+
+impl_retriable_request!(
+    ExportAclRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    Streaming<ExportAclRaw>,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_export_acl
+);
+
+impl_retriable_request!(
+    DeleteAclByPrefixRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteAclByPrefixReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_acl_by_prefix
+);
+
+impl_retriable_request!(
+    ImportAclRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ImportAclReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_import_acl
+);
+
+impl_retriable_request!(
+    ExportClientMetricsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    Streaming<ClientMetricsRaw>,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_export_client_metrics
+);
+
+impl_retriable_request!(
+    GetSubscribeAuthConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetSubscribeAuthConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_subscribe_auth_config
+);
+
+impl_retriable_request!(
+    CreateSnapshotRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    CreateSnapshotReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_create_snapshot
+);
+
+impl_retriable_request!(
+    RestoreSnapshotRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    RestoreSnapshotReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_restore_snapshot
+);
+
+impl_retriable_request!(
+    ListListenerMetricsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListListenerMetricsReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_listener_metrics
+);
+
+impl_retriable_request!(
+    SetSharedDispatchRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetSharedDispatchReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_shared_dispatch
+);
+
+impl_retriable_request!(
+    ListSharedDispatchRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListSharedDispatchReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_shared_dispatch
+);
+
+impl_retriable_request!(
+    TopicSequenceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    TopicSequenceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_topic_sequence
+);
+
+impl_retriable_request!(
+    ResetTopicSequenceRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ResetTopicSequenceReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_reset_topic_sequence
+);
+
+impl_retriable_request!(
+    SetRetainSizeLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetRetainSizeLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_retain_size_limit
+);
+
+impl_retriable_request!(
+    SetRetainTopicPolicyRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetRetainTopicPolicyReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_retain_topic_policy
+);
+
+impl_retriable_request!(
+    ListRetainTopicPolicyRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListRetainTopicPolicyReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_retain_topic_policy
+);
+
+impl_retriable_request!(
+    ListLargestSessionRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListLargestSessionReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_largest_session
+);
+
+impl_retriable_request!(
+    ListExpiredSessionsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListExpiredSessionsReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_expired_sessions
+);
+
+impl_retriable_request!(
+    PurgeExpiredSessionsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    PurgeExpiredSessionsReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_purge_expired_sessions
+);
+
+impl_retriable_request!(
+    RedirectClientRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    RedirectClientReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_redirect_client
+);
+
+impl_retriable_request!(
+    MqttValidateConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    MqttValidateConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_validate_connector
+);
+
+impl_retriable_request!(
+    GetMessageSamplingRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetMessageSamplingReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_message_sampling
+);
+
+impl_retriable_request!(
+    SetMessageSamplingRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetMessageSamplingReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_message_sampling
+);
+
+impl_retriable_request!(
+    ListRateLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListRateLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_rate_limits
+);
+
+impl_retriable_request!(
+    SetUserRateLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetUserRateLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_user_rate_limit
+);
+
+impl_retriable_request!(
+    DeleteUserRateLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteUserRateLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_user_rate_limit
+);
+
+impl_retriable_request!(
+    ListFlappingDetectBanRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListFlappingDetectBanReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_flapping_detect_ban
+);
+
+impl_retriable_request!(
+    UnbanFlappingDetectClientRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    UnbanFlappingDetectClientReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_unban_flapping_detect_client
+);
+
+impl_retriable_request!(
+    GetSessionTieringRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetSessionTieringReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_session_tiering
+);
+
+impl_retriable_request!(
+    SetSessionTieringRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetSessionTieringReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_session_tiering
+);
+
+impl_retriable_request!(
+    BenchmarkMatcherRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    BenchmarkMatcherReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_benchmark_matcher
+);
+
+impl_retriable_request!(
+    GetWillPublishConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetWillPublishConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_will_publish_config
+);
+
+impl_retriable_request!(
+    MqttTestConnectorRoutingRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    MqttTestConnectorRoutingReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_test_connector_routing
+);
+
+impl_retriable_request!(
+    GetAclConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetAclConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_acl_config
+);
+
+impl_retriable_request!(
+    SetAclDefaultPolicyRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetAclDefaultPolicyReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_acl_default_policy
+);
+
+impl_retriable_request!(
+    TestAclRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    TestAclReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_test_acl
+);
+
+impl_retriable_request!(
+    ListInflightQos2Request,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListInflightQos2Reply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_inflight_qos2
+);
+
+impl_retriable_request!(
+    CancelInflightQos2Request,
+    MqttBrokerAdminServiceClient<Channel>,
+    CancelInflightQos2Reply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_cancel_inflight_qos2
+);
+
+impl_retriable_request!(
+    MqttGetConnectorLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    MqttGetConnectorLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_connector_limit
+);
+
+impl_retriable_request!(
+    MqttSetConnectorLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    MqttSetConnectorLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_connector_limit
+);
+
+impl_retriable_request!(
+    SetRetainDeliverLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetRetainDeliverLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_retain_deliver_limit
+);
+
+impl_retriable_request!(
+    EstimateRetainDeliveryRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    EstimateRetainDeliveryReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_estimate_retain_delivery
+);
+
+impl_retriable_request!(
+    ListRetainedMessagesRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListRetainedMessagesReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_retained_messages
+);
+
+impl_retriable_request!(
+    DeleteRetainedMessageRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteRetainedMessageReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_retained_message
+);
+
+impl_retriable_request!(
+    SetClientGroupRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetClientGroupReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_client_group
+);
+
+impl_retriable_request!(
+    DeleteClientGroupRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DeleteClientGroupReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_delete_client_group
+);
+
+impl_retriable_request!(
+    SetClientGroupMemberRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetClientGroupMemberReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_client_group_member
+);
+
+impl_retriable_request!(
+    SetUserMaxQosRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetUserMaxQosReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_user_max_qos
+);
+
+impl_retriable_request!(
+    ListClientGroupRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListClientGroupReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_client_group
+);
+
+impl_retriable_request!(
+    GetClientPoolStatusRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetClientPoolStatusReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_client_pool_status
+);
+
+impl_retriable_request!(
+    SetClientPoolSizeRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetClientPoolSizeReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_client_pool_size
+);
+
+impl_retriable_request!(
+    SetMessageDedupConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetMessageDedupConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_message_dedup_config
+);
+
+impl_retriable_request!(
+    GetMessageDedupConfigRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetMessageDedupConfigReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_message_dedup_config
+);
+
+impl_retriable_request!(
+    ReplayToClientRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ReplayToClientReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_replay_to_client
+);
+
+impl_retriable_request!(
+    SetUserSingleSessionRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetUserSingleSessionReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_user_single_session
+);
+
+impl_retriable_request!(
+    GetUserSingleSessionRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetUserSingleSessionReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_user_single_session
+);
+
+impl_retriable_request!(
+    SetConnectionLimitRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetConnectionLimitReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_connection_limit
+);
+
+impl_retriable_request!(
+    DescribeSubscriptionRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DescribeSubscriptionReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_describe_subscription
+);
+
+impl_retriable_request!(
+    GetSubscribeSnapshotRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    GetSubscribeSnapshotReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_get_subscribe_snapshot
+);
+
+impl_retriable_request!(
+    RestoreSubscribeSnapshotRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    RestoreSubscribeSnapshotReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_restore_subscribe_snapshot
+);
+
+impl_retriable_request!(
+    MqttInferSchemaRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    MqttInferSchemaReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_infer_schema
+);
+
+impl_retriable_request!(
+    DisconnectClientRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    DisconnectClientReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_disconnect_client
+);
+
+impl_retriable_request!(
+    BatchCreateUserRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    BatchCreateUserReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_batch_create_user
+);
+
+impl_retriable_request!(
+    TestTopicRewriteRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    TestTopicRewriteReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_test_topic_rewrite
+);
+
+impl_retriable_request!(
+    MqttPauseConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    MqttPauseConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_pause_connector
+);
+
+impl_retriable_request!(
+    MqttResumeConnectorRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    MqttResumeConnectorReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_resume_connector
+);
+
+impl_retriable_request!(
+    ListSessionSubscriptionsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListSessionSubscriptionsReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_session_subscriptions
+);
+
+impl_retriable_request!(
+    ListSharedSubscriptionsRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    ListSharedSubscriptionsReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_list_shared_subscriptions
+);
+
+impl_retriable_request!(
+    SetNodeDrainingRequest,
+    MqttBrokerAdminServiceClient<Channel>,
+    SetNodeDrainingReply,
+    mqtt_broker_admin_services_client,
+    mqtt_broker_set_node_draining
+);
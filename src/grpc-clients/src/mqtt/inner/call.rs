@@ -14,8 +14,10 @@
 
 use common_base::error::common::CommonError;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateMqttCacheReply, UpdateMqttCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, GetBrokerTimeReply, GetBrokerTimeRequest,
+    GetNodeResourceReply, GetNodeResourceRequest, GetTopicActivityReply, GetTopicActivityRequest,
+    KickUserSessionReply, KickUserSessionRequest, SendLastWillMessageReply,
+    SendLastWillMessageRequest, UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 
 use crate::pool::ClientPool;
@@ -52,3 +54,31 @@ pub async fn $fn_name(
     SendLastWillMessageReply,
     SendLastWillMessage
 );
+
+generate_mqtt_inner_service_call!(
+    broker_mqtt_kick_user_session,
+    KickUserSessionRequest,
+    KickUserSessionReply,
+    KickUserSession
+);
+
+generate_mqtt_inner_service_call!(
+    broker_mqtt_get_node_resource,
+    GetNodeResourceRequest,
+    GetNodeResourceReply,
+    GetNodeResource
+);
+
+generate_mqtt_inner_service_call!(
+    broker_mqtt_get_topic_activity,
+    GetTopicActivityRequest,
+    GetTopicActivityReply,
+    GetTopicActivity
+);
+
+generate_mqtt_inner_service_call!(
+    broker_mqtt_get_broker_time,
+    GetBrokerTimeRequest,
+    GetBrokerTimeReply,
+    GetBrokerTime
+);
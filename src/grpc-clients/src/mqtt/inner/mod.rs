@@ -16,8 +16,10 @@
 use mobc::Manager;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_client::MqttBrokerInnerServiceClient;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateMqttCacheReply, UpdateMqttCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, GetBrokerTimeReply, GetBrokerTimeRequest,
+    GetNodeResourceReply, GetNodeResourceRequest, GetTopicActivityReply, GetTopicActivityRequest,
+    KickUserSessionReply, KickUserSessionRequest, SendLastWillMessageReply,
+    SendLastWillMessageRequest, UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 use tonic::transport::Channel;
 
@@ -83,3 +85,35 @@ async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::
     mqtt_broker_mqtt_services_client,
     send_last_will_message
 );
+
+impl_retriable_request!(
+    KickUserSessionRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    KickUserSessionReply,
+    mqtt_broker_mqtt_services_client,
+    kick_user_session
+);
+
+impl_retriable_request!(
+    GetNodeResourceRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    GetNodeResourceReply,
+    mqtt_broker_mqtt_services_client,
+    get_node_resource
+);
+
+impl_retriable_request!(
+    GetTopicActivityRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    GetTopicActivityReply,
+    mqtt_broker_mqtt_services_client,
+    get_topic_activity
+);
+
+impl_retriable_request!(
+    GetBrokerTimeRequest,
+    MqttBrokerInnerServiceClient<Channel>,
+    GetBrokerTimeReply,
+    mqtt_broker_mqtt_services_client,
+    get_broker_time
+);
@@ -22,16 +22,16 @@
     DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest,
     DeleteSessionReply, DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest,
     DeleteTopicReply, DeleteTopicRequest, DeleteTopicRewriteRuleReply,
-    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply,
-    GetShareSubLeaderRequest, ListAclReply, ListAclRequest, ListAutoSubscribeRuleReply,
-    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectorReply,
-    ListConnectorRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
-    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
-    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, SaveLastWillMessageReply,
-    SaveLastWillMessageRequest, SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest,
-    SetSubscribeReply, SetSubscribeRequest, SetTopicRetainMessageReply,
-    SetTopicRetainMessageRequest, UpdateConnectorReply, UpdateConnectorRequest, UpdateSessionReply,
-    UpdateSessionRequest,
+    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, ExportMetadataReply,
+    ExportMetadataRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest, ListAclReply,
+    ListAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
+    ListBlacklistRequest, ListConnectorReply, ListConnectorRequest, ListSessionReply,
+    ListSessionRequest, ListSubscribeReply, ListSubscribeRequest, ListTopicReply, ListTopicRequest,
+    ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest,
+    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetAutoSubscribeRuleReply,
+    SetAutoSubscribeRuleRequest, SetSubscribeReply, SetSubscribeRequest,
+    SetTopicRetainMessageReply, SetTopicRetainMessageRequest, UpdateConnectorReply,
+    UpdateConnectorRequest, UpdateSessionReply, UpdateSessionRequest,
 };
 
 use crate::pool::ClientPool;
@@ -187,6 +187,13 @@ pub async fn $fn_name(
     ListSubscribe
 );
 
+generate_mqtt_service_call!(
+    placement_export_metadata,
+    ExportMetadataRequest,
+    ExportMetadataReply,
+    ExportMetadata
+);
+
 generate_mqtt_service_call!(
     placement_list_connector,
     ListConnectorRequest,
@@ -12,14 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
 use common_base::error::common::CommonError;
 use dashmap::mapref::one::Ref;
 use dashmap::DashMap;
-use mobc::{Connection, Pool};
+use mobc::{Connection, Manager, Pool};
 use tracing::info;
 
 use crate::journal::admin::JournalAdminServiceManager;
 use crate::journal::inner::JournalInnerServiceManager;
+use crate::metrics::{record_client_pool_request, record_client_pool_wait_ms};
 use crate::mqtt::admin::MqttBrokerAdminServiceManager;
 use crate::mqtt::inner::MqttBrokerPlacementServiceManager;
 use crate::placement::inner::PlacementServiceManager;
@@ -28,9 +33,31 @@
 use crate::placement::mqtt::MqttServiceManager;
 use crate::placement::openraft::OpenRaftServiceManager;
 
+#[derive(Clone, Default)]
+pub struct ClientPoolEndpointStats {
+    pub request_num: u64,
+    pub error_num: u64,
+    pub wait_num: u64,
+    pub wait_total_ms: u64,
+    pub wait_max_ms: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct ClientPoolEndpointState {
+    pub service: String,
+    pub addr: String,
+    pub max_open: u64,
+    pub connections: u64,
+    pub in_use: u64,
+    pub idle: u64,
+    pub stats: ClientPoolEndpointStats,
+}
+
 #[derive(Clone)]
 pub struct ClientPool {
-    max_open_connection: u64,
+    max_open_connection: Arc<AtomicU64>,
+    // per target node: request, error and connection wait statistics
+    endpoint_stats: DashMap<String, ClientPoolEndpointStats>,
     // modules: placement center
     placement_center_inner_pools: DashMap<String, Pool<PlacementServiceManager>>,
     placement_center_journal_service_pools: DashMap<String, Pool<JournalServiceManager>>,
@@ -52,7 +79,8 @@ pub struct ClientPool {
 impl ClientPool {
     pub fn new(max_open_connection: u64) -> Self {
         Self {
-            max_open_connection,
+            max_open_connection: Arc::new(AtomicU64::new(max_open_connection)),
+            endpoint_stats: DashMap::with_capacity(2),
             // modules: placement_center
             placement_center_inner_pools: DashMap::with_capacity(2),
             placement_center_journal_service_pools: DashMap::with_capacity(2),
@@ -77,14 +105,18 @@ pub async fn placement_center_inner_services_client(
         if !self.placement_center_inner_pools.contains_key(addr) {
             let manager = PlacementServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.placement_center_inner_pools
                 .insert(addr.to_owned(), pool);
         }
 
-        if let Some(pool) = self.placement_center_inner_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .placement_center_inner_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => return Ok(conn),
                 Err(e) => {
                     return Err(CommonError::NoAvailableGrpcConnection(
@@ -112,13 +144,17 @@ pub async fn placement_center_journal_services_client(
         {
             let manager = JournalServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.placement_center_journal_service_pools
                 .insert(addr.to_owned(), pool);
         }
-        if let Some(pool) = self.placement_center_journal_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .placement_center_journal_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -145,14 +181,18 @@ pub async fn placement_center_kv_services_client(
         if !self.placement_center_kv_service_pools.contains_key(addr) {
             let manager = KvServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.placement_center_kv_service_pools
                 .insert(addr.to_owned(), pool);
         }
 
-        if let Some(pool) = self.placement_center_kv_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .placement_center_kv_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -182,13 +222,17 @@ pub async fn placement_center_mqtt_services_client(
         if !self.placement_center_mqtt_service_pools.contains_key(addr) {
             let manager = MqttServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.placement_center_mqtt_service_pools
                 .insert(addr.to_owned(), pool);
         }
-        if let Some(pool) = self.placement_center_mqtt_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .placement_center_mqtt_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -220,14 +264,18 @@ pub async fn placement_center_openraft_services_client(
         {
             let manager = OpenRaftServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.placement_center_openraft_service_pools
                 .insert(addr.to_owned(), pool);
         }
 
-        if let Some(pool) = self.placement_center_openraft_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .placement_center_openraft_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -256,14 +304,18 @@ pub async fn mqtt_broker_mqtt_services_client(
         if !self.mqtt_broker_placement_service_pools.contains_key(addr) {
             let manager = MqttBrokerPlacementServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.mqtt_broker_placement_service_pools
                 .insert(addr.to_owned(), pool);
         }
 
-        if let Some(pool) = self.mqtt_broker_placement_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .mqtt_broker_placement_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -292,14 +344,18 @@ pub async fn mqtt_broker_admin_services_client(
         if !self.mqtt_broker_admin_service_pools.contains_key(addr) {
             let manager = MqttBrokerAdminServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.mqtt_broker_admin_service_pools
                 .insert(addr.to_owned(), pool);
         }
 
-        if let Some(pool) = self.mqtt_broker_admin_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .mqtt_broker_admin_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -329,14 +385,18 @@ pub async fn journal_inner_services_client(
         if !self.journal_inner_service_pools.contains_key(addr) {
             let manager = JournalInnerServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.journal_inner_service_pools
                 .insert(addr.to_owned(), pool);
         }
 
-        if let Some(pool) = self.journal_inner_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .journal_inner_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -366,14 +426,18 @@ pub async fn journal_admin_services_client(
         if !self.journal_admin_service_pools.contains_key(addr) {
             let manager = JournalAdminServiceManager::new(addr.to_owned());
             let pool = Pool::builder()
-                .max_open(self.max_open_connection)
+                .max_open(self.max_open_connection())
                 .build(manager);
             self.journal_admin_service_pools
                 .insert(addr.to_owned().to_owned(), pool);
         }
 
-        if let Some(pool) = self.journal_admin_service_pools.get(addr) {
-            match pool.get().await {
+        if let Some(pool) = self
+            .journal_admin_service_pools
+            .get(addr)
+            .map(|pool| pool.clone())
+        {
+            match self.get_connection(addr, &pool).await {
                 Ok(conn) => {
                     return Ok(conn);
                 }
@@ -396,6 +460,202 @@ pub async fn journal_admin_services_client(
         ))
     }
 
+    // ----------modules: pool management -------------
+    pub fn max_open_connection(&self) -> u64 {
+        self.max_open_connection.load(Ordering::Relaxed)
+    }
+
+    /// Changes the maximum number of connections per endpoint. Only the pools whose size
+    /// differs from the new one are replaced by pools built with the new size, so the warm
+    /// connections of the other pools are kept; connections that are checked out keep the
+    /// old pool alive until the in-flight request returns them, so no request is
+    /// interrupted by a resize.
+    pub async fn set_max_open_connection(&self, max_open_connection: u64) {
+        if self
+            .max_open_connection
+            .swap(max_open_connection, Ordering::Relaxed)
+            == max_open_connection
+        {
+            return;
+        }
+
+        let mut rebuilt_num = 0;
+        rebuilt_num += rebuild_pools(
+            &self.placement_center_inner_pools,
+            max_open_connection,
+            PlacementServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.placement_center_journal_service_pools,
+            max_open_connection,
+            JournalServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.placement_center_kv_service_pools,
+            max_open_connection,
+            KvServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.placement_center_mqtt_service_pools,
+            max_open_connection,
+            MqttServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.placement_center_openraft_service_pools,
+            max_open_connection,
+            OpenRaftServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.mqtt_broker_placement_service_pools,
+            max_open_connection,
+            MqttBrokerPlacementServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.mqtt_broker_admin_service_pools,
+            max_open_connection,
+            MqttBrokerAdminServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.journal_admin_service_pools,
+            max_open_connection,
+            JournalAdminServiceManager::new,
+        )
+        .await;
+        rebuilt_num += rebuild_pools(
+            &self.journal_inner_service_pools,
+            max_open_connection,
+            JournalInnerServiceManager::new,
+        )
+        .await;
+        info!(
+            "The maximum number of connections per endpoint in the client pool was changed to {}, {} pools were rebuilt",
+            max_open_connection, rebuilt_num
+        );
+    }
+
+    pub async fn endpoint_states(&self) -> Vec<ClientPoolEndpointState> {
+        let mut results = Vec::new();
+        results.extend(
+            self.collect_states("PlacementService", &self.placement_center_inner_pools)
+                .await,
+        );
+        results.extend(
+            self.collect_states(
+                "JournalService",
+                &self.placement_center_journal_service_pools,
+            )
+            .await,
+        );
+        results.extend(
+            self.collect_states("KvService", &self.placement_center_kv_service_pools)
+                .await,
+        );
+        results.extend(
+            self.collect_states("MqttService", &self.placement_center_mqtt_service_pools)
+                .await,
+        );
+        results.extend(
+            self.collect_states(
+                "OpenRaftService",
+                &self.placement_center_openraft_service_pools,
+            )
+            .await,
+        );
+        results.extend(
+            self.collect_states(
+                "MqttBrokerPlacementService",
+                &self.mqtt_broker_placement_service_pools,
+            )
+            .await,
+        );
+        results.extend(
+            self.collect_states(
+                "MqttBrokerAdminService",
+                &self.mqtt_broker_admin_service_pools,
+            )
+            .await,
+        );
+        results.extend(
+            self.collect_states("JournalAdminService", &self.journal_admin_service_pools)
+                .await,
+        );
+        results.extend(
+            self.collect_states("JournalInnerService", &self.journal_inner_service_pools)
+                .await,
+        );
+        results
+    }
+
+    pub fn endpoint_stats(&self, addr: &str) -> ClientPoolEndpointStats {
+        self.endpoint_stats
+            .get(addr)
+            .map(|stats| stats.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn record_request(&self, addr: &str, success: bool) {
+        let mut stats = self.endpoint_stats.entry(addr.to_owned()).or_default();
+        stats.request_num += 1;
+        if !success {
+            stats.error_num += 1;
+        }
+        record_client_pool_request(addr, success);
+    }
+
+    async fn get_connection<M: Manager>(
+        &self,
+        addr: &str,
+        pool: &Pool<M>,
+    ) -> Result<Connection<M>, mobc::Error<M::Error>> {
+        let start = Instant::now();
+        let result = pool.get().await;
+        let wait_ms = start.elapsed().as_millis() as u64;
+        {
+            let mut stats = self.endpoint_stats.entry(addr.to_owned()).or_default();
+            stats.wait_num += 1;
+            stats.wait_total_ms += wait_ms;
+            stats.wait_max_ms = stats.wait_max_ms.max(wait_ms);
+        }
+        record_client_pool_wait_ms(addr, wait_ms as f64);
+        if result.is_err() {
+            self.record_request(addr, false);
+        }
+        result
+    }
+
+    async fn collect_states<M: Manager>(
+        &self,
+        service: &str,
+        pools: &DashMap<String, Pool<M>>,
+    ) -> Vec<ClientPoolEndpointState> {
+        let pools: Vec<(String, Pool<M>)> = pools
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+
+        let mut results = Vec::with_capacity(pools.len());
+        for (addr, pool) in pools {
+            let state = pool.state().await;
+            results.push(ClientPoolEndpointState {
+                service: service.to_string(),
+                max_open: state.max_open,
+                connections: state.connections,
+                in_use: state.in_use,
+                idle: state.idle,
+                stats: self.endpoint_stats(&addr),
+                addr,
+            });
+        }
+        results
+    }
+
     // other
     pub fn get_leader_addr(&self, addr: &str) -> Option<Ref<'_, String, String>> {
         self.placement_center_leader_addr_caches.get(addr)
@@ -410,3 +670,86 @@ pub fn set_leader_addr(&self, addr: String, leader_addr: String) {
             .insert(addr.to_owned(), leader_addr);
     }
 }
+
+// Replaces the pools whose size differs from max_open_connection and returns how many were
+// replaced. The pools are read before their state is awaited, so no map entry is held across
+// an await.
+async fn rebuild_pools<M: Manager>(
+    pools: &DashMap<String, Pool<M>>,
+    max_open_connection: u64,
+    build_manager: impl Fn(String) -> M,
+) -> u64 {
+    let current: Vec<(String, Pool<M>)> = pools
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect();
+
+    let mut rebuilt_num = 0;
+    for (addr, pool) in current {
+        if pool.state().await.max_open == max_open_connection {
+            continue;
+        }
+        let manager = build_manager(addr.clone());
+        pools.insert(
+            addr,
+            Pool::builder().max_open(max_open_connection).build(manager),
+        );
+        rebuilt_num += 1;
+    }
+    rebuilt_num
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn endpoint_stats_and_resize_test() {
+        let pool = ClientPool::new(3);
+        let addr = "127.0.0.1:1228";
+        pool.record_request(addr, true);
+        pool.record_request(addr, false);
+        pool.record_request(addr, true);
+
+        let stats = pool.endpoint_stats(addr);
+        assert_eq!(stats.request_num, 3);
+        assert_eq!(stats.error_num, 1);
+        assert_eq!(pool.endpoint_stats("127.0.0.1:1229").request_num, 0);
+
+        pool.placement_center_kv_service_pools.insert(
+            addr.to_string(),
+            Pool::builder()
+                .max_open(3)
+                .build(KvServiceManager::new(addr.to_string())),
+        );
+        pool.set_max_open_connection(8).await;
+        assert_eq!(pool.max_open_connection(), 8);
+
+        let states = pool.endpoint_states().await;
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].service, "KvService");
+        assert_eq!(states[0].max_open, 8);
+        assert_eq!(states[0].stats.request_num, 3);
+    }
+
+    #[tokio::test]
+    async fn rebuild_changed_pools_test() {
+        let pools: DashMap<String, Pool<KvServiceManager>> = DashMap::new();
+        for (addr, max_open) in [("127.0.0.1:1228", 3), ("127.0.0.1:1229", 8)] {
+            pools.insert(
+                addr.to_string(),
+                Pool::builder()
+                    .max_open(max_open)
+                    .build(KvServiceManager::new(addr.to_string())),
+            );
+        }
+
+        assert_eq!(rebuild_pools(&pools, 8, KvServiceManager::new).await, 1);
+        let rebuilt: Vec<Pool<KvServiceManager>> =
+            pools.iter().map(|entry| entry.value().clone()).collect();
+        for pool in rebuilt {
+            assert_eq!(pool.state().await.max_open, 8);
+        }
+        assert_eq!(rebuild_pools(&pools, 8, KvServiceManager::new).await, 0);
+    }
+}
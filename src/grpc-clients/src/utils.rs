@@ -82,7 +82,10 @@ pub(crate) async fn retry_call<Req>(
             .map_err(Into::into)?;
 
         match Req::call_once(client.deref_mut(), request.clone()).await {
-            Ok(data) => return Ok(data),
+            Ok(data) => {
+                client_pool.record_request(&target_addr, true);
+                return Ok(data);
+            }
             Err(e) => {
                 let err: CommonError = e.into();
 
@@ -103,14 +106,19 @@ pub(crate) async fn retry_call<Req>(
                                 };
 
                             match Req::call_once(leader_client.deref_mut(), request.clone()).await {
-                                Ok(data) => return Ok(data),
+                                Ok(data) => {
+                                    client_pool.record_request(&leader_addr, true);
+                                    return Ok(data);
+                                }
                                 Err(_) => {
+                                    client_pool.record_request(&leader_addr, false);
                                     tried_addrs.insert(leader_addr);
                                 }
                             }
                         }
                     }
                 } else {
+                    client_pool.record_request(&target_addr, false);
                     return Err(err);
                 }
 
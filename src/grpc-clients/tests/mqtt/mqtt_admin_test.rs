@@ -64,6 +64,8 @@ async fn user_test() {
             username: user_name.clone(),
             password: password.clone(),
             is_superuser: false,
+            if_not_exists: false,
+            upsert: false,
         };
 
         match mqtt_broker_create_user(&client_pool, &addrs, user.clone()).await {
@@ -145,6 +147,7 @@ async fn schema_test() {
             schema_type: "json".to_string(),
             schema: schema_data.clone(),
             desc: "Old schema".to_string(),
+            message_name: "".to_string(),
         };
 
         match mqtt_broker_create_schema(&client_pool, &addrs, create_request).await {
@@ -191,6 +194,8 @@ async fn schema_test() {
             schema_type: "avro".to_string(),
             schema: schema_data.clone(),
             desc: "New schema".to_string(),
+            message_name: "".to_string(),
+            compatibility: "none".to_string(),
         };
 
         match mqtt_broker_update_schema(&client_pool, &addrs, update_request).await {
@@ -257,6 +262,8 @@ async fn connector_test() {
             })
             .unwrap(),
             topic_id: "test-topic-1".to_string(),
+            transforms: String::new(),
+            retry_policy: String::new(),
         };
 
         match mqtt_broker_create_connector(&client_pool, &addrs, create_request).await {
@@ -301,6 +308,7 @@ async fn connector_test() {
             bootstrap_servers: "127.0.0.1:9092".to_string(),
             topic: "test-topic".to_string(),
             key: "test-key".to_string(),
+            ..Default::default()
         })
         .unwrap();
         connector.topic_id = "test-topic-2".to_string();
@@ -339,6 +347,7 @@ async fn connector_test() {
                 bootstrap_servers: "127.0.0.1:9092".to_string(),
                 topic: "test-topic".to_string(),
                 key: "test-key".to_string(),
+                ..Default::default()
             })
             .unwrap()
         );
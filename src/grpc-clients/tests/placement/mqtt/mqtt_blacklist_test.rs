@@ -38,6 +38,7 @@ async fn mqtt_blacklist_test() {
             resource_name: "loboxu".to_string(),
             end_time: now_second() + 100,
             desc: "loboxu test".to_string(),
+            expire_at: None,
         };
 
         let request = CreateBlacklistRequest {
@@ -109,6 +109,7 @@ async fn connector_test() {
             bootstrap_servers: "127.0.0.1:9092".to_string(),
             topic: "test_topic".to_string(),
             key: "test_key".to_string(),
+            ..Default::default()
         })
         .unwrap();
         connector.topic_id = "test_topic-2".to_string();
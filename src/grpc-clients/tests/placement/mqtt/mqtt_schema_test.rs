@@ -61,6 +61,7 @@ async fn schema_test() {
             }"#
             .to_string(),
             desc: "Old schema".to_string(),
+            message_name: "".to_string(),
         };
 
         let create_request = CreateSchemaRequest {
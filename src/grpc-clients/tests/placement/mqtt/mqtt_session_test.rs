@@ -95,6 +95,7 @@ async fn mqtt_session_test() {
             broker_id: mqtt_session.broker_id.unwrap_or(1100),
             reconnect_time: mqtt_session.reconnect_time.unwrap(),
             distinct_time: mqtt_session.distinct_time.unwrap(),
+            takeover: false,
         };
 
         match placement_update_session(&client_pool, &addrs, request).await {
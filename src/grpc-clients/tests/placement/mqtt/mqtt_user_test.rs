@@ -40,12 +40,14 @@ async fn mqtt_user_test() {
             username: user_name.clone(),
             password: password.clone(),
             is_superuser: false,
+            create_time: 0,
         };
 
         let request: CreateUserRequest = CreateUserRequest {
             cluster_name: cluster_name.clone(),
             user_name: mqtt_user.username.clone(),
             content: mqtt_user.encode(),
+            upsert: false,
         };
         match placement_create_user(&client_pool, &addrs, request).await {
             Ok(_) => {}
@@ -13,48 +13,325 @@
 // limitations under the License.
 
 use crate::handler::cache::CacheManager;
+use crate::handler::constant::WILDCARD_RESOURCE;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
+use crate::observability::metrics::auth::{
+    get_subscribe_auth_counter, SUBSCRIBE_AUTH_OUTCOME_DENIED_FILTER,
+    SUBSCRIBE_AUTH_OUTCOME_REJECTED_PACKET,
+};
+use crate::security::acl::auth::check_acl;
 use crate::security::AuthDriver;
+use crate::subscribe::common::decode_sub_path;
+use axum::async_trait;
+use common_base::utils::topic_util::is_valid_topic_filter;
+use common_config::mqtt::config::AclDefaultPolicy;
+use futures::stream::{self, Stream};
 use grpc_clients::pool::ClientPool;
-use metadata_struct::acl::mqtt_acl::MqttAcl;
-use protocol::broker_mqtt::broker_mqtt_admin::{CreateAclRequest, DeleteAclRequest};
+use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction, MqttAclResourceType};
+use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    CreateAclReply, CreateAclRequest, DeleteAclByPrefixReply, DeleteAclByPrefixRequest,
+    DeleteAclRequest, ExportAclRaw, GetAclConfigReply, GetSubscribeAuthConfigReply, ImportAclReply,
+    ImportAclRequest, ListAclRequest, SetAclDefaultPolicyReply, SetAclDefaultPolicyRequest,
+    TestAclReply, TestAclRequest,
+};
+use std::collections::HashSet;
 use std::sync::Arc;
 use tonic::Request;
+use tracing::warn;
 
-// List all ACL entries
+// List the ACL entries a page at a time, ordered by resource name then action so that paging
+// is deterministic. A limit of 0 returns every entry from the offset.
 pub async fn list_acl_by_req(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
-) -> Result<Vec<Vec<u8>>, MqttBrokerError> {
+    request: Request<ListAclRequest>,
+) -> Result<(Vec<Vec<u8>>, usize), MqttBrokerError> {
+    let req = request.into_inner();
     let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
     let data = auth_driver.read_all_acl().await?;
 
-    let mut acls_list = Vec::new();
-    for ele in data {
+    let (page, total_count) = paginate_acls(data, req.limit, req.offset);
+    let mut acls_list = Vec::with_capacity(page.len());
+    for ele in page {
         let acl = ele
             .encode()
             .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
         acls_list.push(acl);
     }
 
-    Ok(acls_list)
+    Ok((acls_list, total_count))
+}
+
+fn paginate_acls(mut acls: Vec<MqttAcl>, limit: u32, offset: u32) -> (Vec<MqttAcl>, usize) {
+    // Entries with the same resource name and action are ordered by the rest of their fields,
+    // the storage does not return them in a fixed order
+    acls.sort_by(|a, b| {
+        a.resource_name
+            .cmp(&b.resource_name)
+            .then_with(|| a.action.to_string().cmp(&b.action.to_string()))
+            .then_with(|| {
+                a.resource_type
+                    .to_string()
+                    .cmp(&b.resource_type.to_string())
+            })
+            .then_with(|| a.topic.cmp(&b.topic))
+            .then_with(|| a.ip.cmp(&b.ip))
+            .then_with(|| a.permission.to_string().cmp(&b.permission.to_string()))
+    });
+
+    let total_count = acls.len();
+    let limit = if limit == 0 {
+        total_count
+    } else {
+        limit as usize
+    };
+    let page = acls.into_iter().skip(offset as usize).take(limit).collect();
+    (page, total_count)
+}
+
+// Export every ACL entry as one JSON object per message, in the order list_acl returns them.
+// Joined with newlines the messages form an NDJSON document that can be imported again.
+pub async fn export_acl_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<impl Stream<Item = ExportAclRaw> + Send + 'static, MqttBrokerError> {
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    let data = auth_driver.read_all_acl().await?;
+    let lines = encode_acl_export(data)?;
+    Ok(stream::iter(
+        lines.into_iter().map(|acl| ExportAclRaw { acl }),
+    ))
+}
+
+fn encode_acl_export(acls: Vec<MqttAcl>) -> Result<Vec<String>, MqttBrokerError> {
+    let (acls, _) = paginate_acls(acls, 0, 0);
+    acls.iter()
+        .map(|acl| {
+            serde_json::to_string(acl).map_err(|e| MqttBrokerError::CommonError(e.to_string()))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum AclImportMode {
+    // Keep the existing rules and only create the ones not stored yet
+    Merge,
+    // Remove every existing rule the document does not contain, once the imported ones exist
+    Replace,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct AclImportPlan {
+    delete: Vec<MqttAcl>,
+    create: Vec<MqttAcl>,
+    skipped_num: u32,
+}
+
+// Import ACL rules from a document written by export_acl. The storage has no transactions, so
+// the whole document is parsed and validated before anything is written: a malformed document
+// changes nothing.
+pub async fn import_acl_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<ImportAclRequest>,
+) -> Result<ImportAclReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let mode = parse_acl_import_mode(&req.mode)?;
+    let imported = decode_acl_document(&req.document)?;
+
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    let existing = auth_driver.read_all_acl().await?;
+    let plan = plan_acl_import(existing, imported, &mode);
+
+    let store = AuthDriverAclStore {
+        cache_manager: cache_manager.clone(),
+        auth_driver,
+    };
+    apply_acl_import(&store, plan, &mode).await
+}
+
+#[async_trait]
+trait AclImportStore {
+    async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError>;
+
+    async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError>;
+}
+
+struct AuthDriverAclStore {
+    cache_manager: Arc<CacheManager>,
+    auth_driver: AuthDriver,
+}
+
+#[async_trait]
+impl AclImportStore for AuthDriverAclStore {
+    async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        // The driver adds the rule to the cache before writing it
+        if let Err(e) = self.auth_driver.save_acl(acl.clone()).await {
+            self.cache_manager.remove_acl(acl);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+        self.auth_driver.delete_acl(acl).await
+    }
+}
+
+// A merge counts the rules that could not be created. A replace only removes the old rules once
+// every imported rule was created: when a write fails the rules created so far are removed
+// again and the stored rules stay as they were.
+async fn apply_acl_import(
+    store: &impl AclImportStore,
+    plan: AclImportPlan,
+    mode: &AclImportMode,
+) -> Result<ImportAclReply, MqttBrokerError> {
+    let mut reply = ImportAclReply {
+        skipped_num: plan.skipped_num,
+        ..Default::default()
+    };
+    let mut created = Vec::new();
+    for acl in plan.create {
+        match store.save_acl(acl.clone()).await {
+            Ok(()) => {
+                reply.created_num += 1;
+                created.push(acl);
+            }
+            Err(e) if *mode == AclImportMode::Replace => {
+                warn!("Failed to import acl {:?}, error message: {}", acl, e);
+                for acl in created {
+                    if let Err(e) = store.delete_acl(acl.clone()).await {
+                        warn!(
+                            "Failed to roll back imported acl {:?}, error message: {}",
+                            acl, e
+                        );
+                    }
+                }
+                return Err(MqttBrokerError::CommonError(format!(
+                    "acl import failed, the existing rules were kept: {}",
+                    e
+                )));
+            }
+            Err(e) => {
+                warn!("Failed to import acl {:?}, error message: {}", acl, e);
+                reply.failed_num += 1;
+            }
+        }
+    }
+
+    // The imported rules are all stored, a rule that cannot be removed is left in place
+    for acl in plan.delete {
+        if let Err(e) = store.delete_acl(acl.clone()).await {
+            warn!(
+                "Failed to remove replaced acl {:?}, error message: {}",
+                acl, e
+            );
+            reply.failed_num += 1;
+        }
+    }
+    Ok(reply)
+}
+
+fn parse_acl_import_mode(mode: &str) -> Result<AclImportMode, MqttBrokerError> {
+    match mode {
+        "" | "merge" => Ok(AclImportMode::Merge),
+        "replace" => Ok(AclImportMode::Replace),
+        _ => Err(MqttBrokerError::CommonError(format!(
+            "unknown acl import mode {}, expected merge or replace",
+            mode
+        ))),
+    }
 }
 
-// Create a new ACL entry
+// The document is either a JSON array of rules or NDJSON with one rule per line
+fn decode_acl_document(document: &str) -> Result<Vec<MqttAcl>, MqttBrokerError> {
+    let document = document.trim();
+    let acls: Vec<MqttAcl> = if document.starts_with('[') {
+        serde_json::from_str(document)
+            .map_err(|e| MqttBrokerError::CommonError(format!("invalid acl document, {}", e)))?
+    } else {
+        let mut acls = Vec::new();
+        for (index, line) in document.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let acl = serde_json::from_str(line).map_err(|e| {
+                MqttBrokerError::CommonError(format!(
+                    "invalid acl document, line {}: {}",
+                    index + 1,
+                    e
+                ))
+            })?;
+            acls.push(acl);
+        }
+        acls
+    };
+
+    for acl in acls.iter() {
+        validate_acl_topic(&acl.topic)?;
+    }
+    Ok(acls)
+}
+
+fn plan_acl_import(
+    existing: Vec<MqttAcl>,
+    imported: Vec<MqttAcl>,
+    mode: &AclImportMode,
+) -> AclImportPlan {
+    let mut plan = AclImportPlan::default();
+    let mut stored: HashSet<MqttAcl> = existing.iter().cloned().collect();
+    if *mode == AclImportMode::Replace {
+        let kept: HashSet<&MqttAcl> = imported.iter().collect();
+        plan.delete = existing
+            .into_iter()
+            .filter(|acl| !kept.contains(acl))
+            .collect();
+    }
+
+    // A rule that is already stored, or repeated in the document, is only created once
+    for acl in imported {
+        if stored.insert(acl.clone()) {
+            plan.create.push(acl);
+        } else {
+            plan.skipped_num += 1;
+        }
+    }
+    plan
+}
+
+// Create a new ACL entry, the reply echoes the entry as it was stored
 pub async fn create_acl_by_req(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
     request: Request<CreateAclRequest>,
-) -> Result<(), MqttBrokerError> {
+) -> Result<CreateAclReply, MqttBrokerError> {
     let req = request.into_inner();
 
     let mqtt_acl =
         MqttAcl::decode(&req.acl).map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    validate_acl_topic(&mqtt_acl.topic)?;
 
+    let acl = mqtt_acl
+        .encode()
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
     let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
     auth_driver.save_acl(mqtt_acl).await?;
 
-    Ok(())
+    Ok(CreateAclReply { acl })
+}
+
+// The topic of a rule is `*` for every topic, or a topic filter that may use `+` and `#`
+fn validate_acl_topic(topic: &str) -> Result<(), MqttBrokerError> {
+    if topic == WILDCARD_RESOURCE || is_valid_topic_filter(topic) {
+        return Ok(());
+    }
+    Err(MqttBrokerError::CommonError(format!(
+        "invalid acl topic {}, `+` and `#` must take a whole level and `#` must be the last level",
+        topic
+    )))
 }
 
 // Delete an existing ACL entry
@@ -72,3 +349,561 @@ pub async fn delete_acl_by_req(
 
     Ok(())
 }
+
+// Delete every ACL entry whose topic starts with a prefix and/or that belongs to a user. The
+// entries are read from the storage once and each match is removed from the storage and cache.
+// A rule that cannot be removed is counted as failed and the remaining ones are still removed.
+pub async fn delete_acl_by_prefix_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<DeleteAclByPrefixRequest>,
+) -> Result<DeleteAclByPrefixReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    let data = auth_driver.read_all_acl().await?;
+    let matched = select_acls_by_prefix(data, &req.topic_prefix, &req.username, req.confirm_all)?;
+
+    // Keep going when a rule cannot be removed, so the reply tells what is left to retry
+    let mut reply = DeleteAclByPrefixReply::default();
+    for acl in matched {
+        match auth_driver.delete_acl(acl.clone()).await {
+            Ok(()) => reply.deleted_num += 1,
+            Err(e) => {
+                warn!("Failed to delete acl {:?}, error message: {}", acl, e);
+                reply.failed_num += 1;
+            }
+        }
+    }
+    Ok(reply)
+}
+
+fn select_acls_by_prefix(
+    acls: Vec<MqttAcl>,
+    topic_prefix: &str,
+    username: &str,
+    confirm_all: bool,
+) -> Result<Vec<MqttAcl>, MqttBrokerError> {
+    // Without a prefix and a username every entry matches
+    if topic_prefix.is_empty() && username.is_empty() && !confirm_all {
+        return Err(MqttBrokerError::CommonError(
+            "an empty topic prefix and username delete every acl, set confirm_all to do so"
+                .to_string(),
+        ));
+    }
+
+    Ok(acls
+        .into_iter()
+        .filter(|acl| acl.topic.starts_with(topic_prefix))
+        .filter(|acl| {
+            username.is_empty()
+                || (acl.resource_type == MqttAclResourceType::User && acl.resource_name == username)
+        })
+        .collect())
+}
+
+// Get the SUBSCRIBE authorization granularity and how often filters and packets were denied
+pub async fn get_subscribe_auth_config_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> Result<GetSubscribeAuthConfigReply, MqttBrokerError> {
+    let config = cache_manager.get_subscribe_auth_config();
+    Ok(GetSubscribeAuthConfigReply {
+        granularity: format!("{:?}", config.granularity),
+        denied_filter_num: get_subscribe_auth_counter(SUBSCRIBE_AUTH_OUTCOME_DENIED_FILTER),
+        rejected_packet_num: get_subscribe_auth_counter(SUBSCRIBE_AUTH_OUTCOME_REJECTED_PACKET),
+    })
+}
+
+pub fn get_acl_config_by_req(cache_manager: &Arc<CacheManager>) -> GetAclConfigReply {
+    GetAclConfigReply {
+        default_policy: acl_default_policy_name(cache_manager.get_acl_default_policy()),
+    }
+}
+
+// Change the policy applied when no ACL rule matches, the next ACL check already uses it
+pub async fn set_acl_default_policy_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<SetAclDefaultPolicyRequest>,
+) -> Result<SetAclDefaultPolicyReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let policy = match req.default_policy.as_str() {
+        "allow" => AclDefaultPolicy::Allow,
+        "deny" => AclDefaultPolicy::Deny,
+        _ => {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unknown acl default policy {}, expected allow or deny",
+                req.default_policy
+            )))
+        }
+    };
+
+    let mut config = cache_manager.get_security_config();
+    config.acl_default_policy = policy;
+    save_cluster_dynamic_config(client_pool, ClusterDynamicConfig::Security, config.encode())
+        .await?;
+    cache_manager.update_security_config(config);
+    Ok(SetAclDefaultPolicyReply {
+        default_policy: acl_default_policy_name(policy),
+    })
+}
+
+// Evaluate the ACL for a hypothetical client the same way a publish or subscribe is checked
+pub fn test_acl_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<TestAclRequest>,
+) -> Result<TestAclReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let action = match req.action.as_str() {
+        "publish" => MqttAclAction::Publish,
+        "subscribe" => MqttAclAction::Subscribe,
+        _ => {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unknown acl action {}, expected publish or subscribe",
+                req.action
+            )))
+        }
+    };
+
+    let mut connection = MQTTConnection::new(ConnectionConfig {
+        connect_id: 0,
+        client_id: req.client_id,
+        receive_maximum: 0,
+        max_packet_size: 0,
+        topic_alias_max: 0,
+        request_problem_info: 0,
+        keep_alive: 0,
+        source_ip_addr: req.source_ip,
+    });
+    connection.login_success(req.username);
+
+    // a subscription is checked with its filter, as on a real SUBSCRIBE
+    let topic_name = if action == MqttAclAction::Subscribe {
+        decode_sub_path(&req.topic_name)
+    } else {
+        req.topic_name
+    };
+    let decision = check_acl(cache_manager, &connection, &topic_name, action, req.retain);
+    Ok(TestAclReply {
+        allowed: decision.is_allowed(),
+        reason: decision.reason().to_string(),
+        default_policy: acl_default_policy_name(cache_manager.get_acl_default_policy()),
+    })
+}
+
+fn acl_default_policy_name(policy: AclDefaultPolicy) -> String {
+    match policy {
+        AclDefaultPolicy::Allow => "allow".to_string(),
+        AclDefaultPolicy::Deny => "deny".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::{
+        apply_acl_import, decode_acl_document, encode_acl_export, paginate_acls,
+        parse_acl_import_mode, plan_acl_import, select_acls_by_prefix, validate_acl_topic,
+        AclImportMode, AclImportStore,
+    };
+    use crate::handler::error::MqttBrokerError;
+    use axum::async_trait;
+    use metadata_struct::acl::mqtt_acl::{
+        MqttAcl, MqttAclAction, MqttAclPermission, MqttAclResourceType,
+    };
+
+    fn acl(resource_name: &str, action: MqttAclAction, topic: &str) -> MqttAcl {
+        MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: resource_name.to_string(),
+            topic: topic.to_string(),
+            ip: "*".to_string(),
+            action,
+            permission: MqttAclPermission::Allow,
+        }
+    }
+
+    fn acls() -> Vec<MqttAcl> {
+        vec![
+            acl("user2", MqttAclAction::Publish, "t1"),
+            acl("user1", MqttAclAction::Subscribe, "t1"),
+            acl("user1", MqttAclAction::Publish, "t2"),
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user3", MqttAclAction::All, "t1"),
+        ]
+    }
+
+    fn keys(acls: &[MqttAcl]) -> Vec<(String, String, String)> {
+        acls.iter()
+            .map(|acl| {
+                (
+                    acl.resource_name.clone(),
+                    acl.action.to_string(),
+                    acl.topic.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paginate_acls_stable_order_test() {
+        let (all, total_count) = paginate_acls(acls(), 0, 0);
+        assert_eq!(total_count, 5);
+        let mut reversed = acls();
+        reversed.reverse();
+        let (all_reversed, _) = paginate_acls(reversed, 0, 0);
+        assert_eq!(keys(&all), keys(&all_reversed));
+        assert_eq!(all[0].resource_name, "user1");
+        assert_eq!(all[4].resource_name, "user3");
+
+        // Consecutive pages cover every entry exactly once
+        let mut paged = Vec::new();
+        for offset in (0..5).step_by(2) {
+            let (page, total_count) = paginate_acls(acls(), 2, offset);
+            assert_eq!(total_count, 5);
+            paged.extend(page);
+        }
+        assert_eq!(keys(&paged), keys(&all));
+    }
+
+    #[test]
+    fn paginate_acls_offset_past_end_test() {
+        let (page, total_count) = paginate_acls(acls(), 10, 5);
+        assert!(page.is_empty());
+        assert_eq!(total_count, 5);
+
+        let (page, total_count) = paginate_acls(acls(), 2, 100);
+        assert!(page.is_empty());
+        assert_eq!(total_count, 5);
+    }
+
+    #[test]
+    fn paginate_acls_zero_limit_test() {
+        let (page, total_count) = paginate_acls(acls(), 0, 3);
+        assert_eq!(page.len(), 2);
+        assert_eq!(total_count, 5);
+
+        let (page, _) = paginate_acls(Vec::new(), 0, 0);
+        assert!(page.is_empty());
+    }
+
+    #[test]
+    fn encode_acl_export_round_trip_test() {
+        let mut exported = acls();
+        exported.push(MqttAcl {
+            resource_type: MqttAclResourceType::ClientId,
+            resource_name: "client-\"quoted\"".to_string(),
+            topic: "sensors/#".to_string(),
+            ip: "10.0.0.1".to_string(),
+            action: MqttAclAction::Subscribe,
+            permission: MqttAclPermission::Deny,
+        });
+
+        let lines = encode_acl_export(exported.clone()).unwrap();
+        assert_eq!(lines.len(), exported.len());
+        let document = lines.join("\n");
+
+        // Every line of the document is a JSON object on its own
+        let imported: Vec<MqttAcl> = document
+            .lines()
+            .map(|line| {
+                let value: serde_json::Value = serde_json::from_str(line).unwrap();
+                assert!(value.is_object());
+                serde_json::from_value(value).unwrap()
+            })
+            .collect();
+        let (expected, _) = paginate_acls(exported, 0, 0);
+        assert_eq!(imported, expected);
+
+        assert!(encode_acl_export(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn plan_acl_import_merge_test() {
+        let existing = vec![
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user2", MqttAclAction::Publish, "t1"),
+        ];
+        let imported = vec![
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user1", MqttAclAction::Subscribe, "t1"),
+            acl("user1", MqttAclAction::Subscribe, "t1"),
+            acl("user3", MqttAclAction::All, "t2"),
+        ];
+
+        let plan = plan_acl_import(existing, imported, &AclImportMode::Merge);
+        assert!(plan.delete.is_empty());
+        assert_eq!(
+            keys(&plan.create),
+            keys(&[
+                acl("user1", MqttAclAction::Subscribe, "t1"),
+                acl("user3", MqttAclAction::All, "t2"),
+            ])
+        );
+        assert_eq!(plan.skipped_num, 2);
+    }
+
+    #[test]
+    fn plan_acl_import_replace_test() {
+        let existing = vec![
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user2", MqttAclAction::Publish, "t1"),
+        ];
+        let imported = vec![
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user3", MqttAclAction::All, "t2"),
+            acl("user3", MqttAclAction::All, "t2"),
+        ];
+
+        // Only the rules the document does not contain are removed, the others are kept
+        let plan = plan_acl_import(existing, imported, &AclImportMode::Replace);
+        assert_eq!(
+            keys(&plan.delete),
+            keys(&[acl("user2", MqttAclAction::Publish, "t1")])
+        );
+        assert_eq!(
+            keys(&plan.create),
+            keys(&[acl("user3", MqttAclAction::All, "t2")])
+        );
+        assert_eq!(plan.skipped_num, 2);
+    }
+
+    #[test]
+    fn plan_acl_import_slash_test() {
+        // Names and topics may contain /, the rules differ even though their fields joined
+        // with / are the same
+        let existing = vec![acl("a/b", MqttAclAction::Publish, "c")];
+        let imported = vec![acl("a", MqttAclAction::Publish, "b/c")];
+
+        let plan = plan_acl_import(existing, imported, &AclImportMode::Replace);
+        assert_eq!(
+            keys(&plan.delete),
+            keys(&[acl("a/b", MqttAclAction::Publish, "c")])
+        );
+        assert_eq!(
+            keys(&plan.create),
+            keys(&[acl("a", MqttAclAction::Publish, "b/c")])
+        );
+        assert_eq!(plan.skipped_num, 0);
+    }
+
+    // Stores the rules in memory, the write of `fail_on` fails
+    struct MemoryAclStore {
+        acls: Mutex<Vec<MqttAcl>>,
+        fail_on: Option<MqttAcl>,
+    }
+
+    impl MemoryAclStore {
+        fn new(acls: Vec<MqttAcl>, fail_on: Option<MqttAcl>) -> Self {
+            MemoryAclStore {
+                acls: Mutex::new(acls),
+                fail_on,
+            }
+        }
+
+        fn keys(&self) -> Vec<(String, String, String)> {
+            let mut acls = self.acls.lock().unwrap().clone();
+            acls.sort_by(|a, b| a.resource_name.cmp(&b.resource_name));
+            keys(&acls)
+        }
+    }
+
+    #[async_trait]
+    impl AclImportStore for MemoryAclStore {
+        async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+            if self.fail_on.as_ref() == Some(&acl) {
+                return Err(MqttBrokerError::CommonError("write failed".to_string()));
+            }
+            self.acls.lock().unwrap().push(acl);
+            Ok(())
+        }
+
+        async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
+            self.acls.lock().unwrap().retain(|raw| *raw != acl);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn apply_acl_import_replace_test() {
+        let existing = vec![
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user2", MqttAclAction::Publish, "t1"),
+        ];
+        let imported = vec![
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user3", MqttAclAction::All, "t2"),
+            acl("user4", MqttAclAction::All, "t2"),
+        ];
+
+        let store = MemoryAclStore::new(existing.clone(), None);
+        let plan = plan_acl_import(existing, imported.clone(), &AclImportMode::Replace);
+        let reply = apply_acl_import(&store, plan, &AclImportMode::Replace)
+            .await
+            .unwrap();
+        assert_eq!(reply.created_num, 2);
+        assert_eq!(reply.skipped_num, 1);
+        assert_eq!(reply.failed_num, 0);
+        assert_eq!(store.keys(), keys(&imported));
+    }
+
+    #[tokio::test]
+    async fn apply_acl_import_replace_write_failure_test() {
+        let existing = vec![
+            acl("user1", MqttAclAction::Publish, "t1"),
+            acl("user2", MqttAclAction::Publish, "t1"),
+        ];
+        let imported = vec![
+            acl("user3", MqttAclAction::All, "t2"),
+            acl("user4", MqttAclAction::All, "t2"),
+            acl("user5", MqttAclAction::All, "t2"),
+        ];
+
+        // The second write fails: the first imported rule is removed again and no rule is lost
+        let store = MemoryAclStore::new(
+            existing.clone(),
+            Some(acl("user4", MqttAclAction::All, "t2")),
+        );
+        let plan = plan_acl_import(existing.clone(), imported.clone(), &AclImportMode::Replace);
+        assert!(apply_acl_import(&store, plan, &AclImportMode::Replace)
+            .await
+            .is_err());
+        assert_eq!(store.keys(), keys(&existing));
+
+        // A merge keeps the rules that were written and counts the failure
+        let store = MemoryAclStore::new(
+            existing.clone(),
+            Some(acl("user4", MqttAclAction::All, "t2")),
+        );
+        let plan = plan_acl_import(existing, imported, &AclImportMode::Merge);
+        let reply = apply_acl_import(&store, plan, &AclImportMode::Merge)
+            .await
+            .unwrap();
+        assert_eq!(reply.created_num, 2);
+        assert_eq!(reply.failed_num, 1);
+        assert_eq!(store.keys().len(), 4);
+    }
+
+    #[test]
+    fn decode_acl_document_test() {
+        let lines = encode_acl_export(acls()).unwrap();
+        let (expected, _) = paginate_acls(acls(), 0, 0);
+
+        // NDJSON as written by the export, blank lines are ignored
+        let document = format!("{}\n\n", lines.join("\n"));
+        assert_eq!(decode_acl_document(&document).unwrap(), expected);
+
+        let document = format!("[{}]", lines.join(","));
+        assert_eq!(decode_acl_document(&document).unwrap(), expected);
+
+        assert!(decode_acl_document("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn decode_acl_document_malformed_test() {
+        let mut lines = encode_acl_export(acls()).unwrap();
+
+        // One bad line rejects the whole document, so nothing is imported
+        let mut malformed = lines.clone();
+        malformed.insert(2, "{\"resource_type\":\"User\"".to_string());
+        let err = decode_acl_document(&malformed.join("\n")).unwrap_err();
+        assert!(err.to_string().contains("line 3"));
+
+        let mut bad_action = lines.clone();
+        bad_action.push(lines[0].replace("\"Publish\"", "\"Write\""));
+        assert!(decode_acl_document(&bad_action.join("\n")).is_err());
+
+        let mut bad_topic = acl("user1", MqttAclAction::Publish, "t1");
+        bad_topic.topic = "sensors/#/temp".to_string();
+        lines.push(serde_json::to_string(&bad_topic).unwrap());
+        assert!(decode_acl_document(&lines.join("\n")).is_err());
+
+        assert!(decode_acl_document("[{}]").is_err());
+    }
+
+    #[test]
+    fn parse_acl_import_mode_test() {
+        assert_eq!(parse_acl_import_mode("").unwrap(), AclImportMode::Merge);
+        assert_eq!(
+            parse_acl_import_mode("merge").unwrap(),
+            AclImportMode::Merge
+        );
+        assert_eq!(
+            parse_acl_import_mode("replace").unwrap(),
+            AclImportMode::Replace
+        );
+        assert!(parse_acl_import_mode("overwrite").is_err());
+    }
+
+    fn tree_acls() -> Vec<MqttAcl> {
+        let mut client_acl = acl("user1", MqttAclAction::Publish, "factory/line2/temp");
+        client_acl.resource_type = MqttAclResourceType::ClientId;
+        vec![
+            acl("user1", MqttAclAction::Publish, "factory/line1/temp"),
+            acl("user1", MqttAclAction::Subscribe, "factory/#"),
+            acl("user2", MqttAclAction::Publish, "factory/line1/pressure"),
+            acl("user2", MqttAclAction::Publish, "office/light"),
+            client_acl,
+        ]
+    }
+
+    #[test]
+    fn select_acls_by_prefix_topic_test() {
+        let matched = select_acls_by_prefix(tree_acls(), "factory/line1/", "", false).unwrap();
+        assert_eq!(
+            keys(&matched),
+            keys(&[
+                acl("user1", MqttAclAction::Publish, "factory/line1/temp"),
+                acl("user2", MqttAclAction::Publish, "factory/line1/pressure"),
+            ])
+        );
+
+        let matched = select_acls_by_prefix(tree_acls(), "factory", "", false).unwrap();
+        assert_eq!(matched.len(), 4);
+
+        assert!(select_acls_by_prefix(tree_acls(), "home/", "", false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn select_acls_by_prefix_username_test() {
+        // A client id with the same name as the user is not matched
+        let matched = select_acls_by_prefix(tree_acls(), "", "user1", false).unwrap();
+        assert_eq!(
+            keys(&matched),
+            keys(&[
+                acl("user1", MqttAclAction::Publish, "factory/line1/temp"),
+                acl("user1", MqttAclAction::Subscribe, "factory/#"),
+            ])
+        );
+
+        let matched = select_acls_by_prefix(tree_acls(), "office/", "user2", false).unwrap();
+        assert_eq!(
+            keys(&matched),
+            keys(&[acl("user2", MqttAclAction::Publish, "office/light")])
+        );
+
+        assert!(select_acls_by_prefix(tree_acls(), "", "user9", false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn select_acls_by_prefix_empty_guard_test() {
+        assert!(select_acls_by_prefix(tree_acls(), "", "", false).is_err());
+
+        let matched = select_acls_by_prefix(tree_acls(), "", "", true).unwrap();
+        assert_eq!(matched.len(), tree_acls().len());
+    }
+
+    #[test]
+    fn validate_acl_topic_test() {
+        for topic in ["*", "tp-1", "sensors/+/temp", "sensors/#", "#", "/a/b"] {
+            assert!(validate_acl_topic(topic).is_ok(), "{}", topic);
+        }
+        for topic in ["", "sensors/+temp", "sensors/#/temp", "sensors#"] {
+            assert!(validate_acl_topic(topic).is_err(), "{}", topic);
+        }
+    }
+}
@@ -15,11 +15,14 @@
 use crate::admin::query::{apply_filters, apply_pagination, apply_sorting, Queryable};
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::security::acl::metadata::build_blacklist_regex;
 use crate::security::AuthDriver;
+use common_base::tools::now_second;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    BlacklistRaw, CreateBlacklistRequest, DeleteBlacklistRequest, ListBlacklistRequest,
+    BlacklistRaw, CreateBlacklistReply, CreateBlacklistRequest, DeleteBlacklistRequest,
+    ListBlacklistRequest,
 };
 use std::sync::Arc;
 use tonic::Request;
@@ -71,6 +74,7 @@ pub async fn delete_blacklist_by_req(
         "ClientIdMatch" => MqttAclBlackListType::ClientIdMatch,
         "UserMatch" => MqttAclBlackListType::UserMatch,
         "IPCIDR" => MqttAclBlackListType::IPCIDR,
+        "IpMatch" => MqttAclBlackListType::IpMatch,
         _ => {
             return Err(MqttBrokerError::CommonError(format!(
                 "Failed BlackList Type: {}",
@@ -84,6 +88,7 @@ pub async fn delete_blacklist_by_req(
         resource_name: req.resource_name,
         end_time: 0,
         desc: "".to_string(),
+        expire_at: None,
     };
 
     let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
@@ -92,20 +97,50 @@ pub async fn delete_blacklist_by_req(
     Ok(())
 }
 
-// Create new blacklist entry
+// Create new blacklist entry, the reply echoes the entry as it was stored
 pub async fn create_blacklist_by_req(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
     request: Request<CreateBlacklistRequest>,
-) -> Result<(), MqttBrokerError> {
+) -> Result<CreateBlacklistReply, MqttBrokerError> {
     let req = request.into_inner();
     let mqtt_blacklist = MqttAclBlackList::decode(&req.blacklist)
         .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    validate_blacklist_pattern(&mqtt_blacklist)?;
+    if let Some(expire_at) = mqtt_blacklist.expire_at {
+        if expire_at <= now_second() {
+            return Err(MqttBrokerError::CommonError(format!(
+                "blacklist expire_at {} is in the past",
+                expire_at
+            )));
+        }
+    }
 
     let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
-    auth_driver.save_blacklist(mqtt_blacklist).await?;
+    auth_driver.save_blacklist(mqtt_blacklist.clone()).await?;
 
-    Ok(())
+    Ok(CreateBlacklistReply {
+        blacklist: Some(BlacklistRaw::from(mqtt_blacklist)),
+    })
+}
+
+// The match kinds hold a regular expression that has to match the whole client id, username
+// or IP address. It is compiled here so that a broken pattern is rejected instead of stored.
+fn validate_blacklist_pattern(blacklist: &MqttAclBlackList) -> Result<(), MqttBrokerError> {
+    match blacklist.blacklist_type {
+        MqttAclBlackListType::ClientIdMatch
+        | MqttAclBlackListType::UserMatch
+        | MqttAclBlackListType::IpMatch => {
+            build_blacklist_regex(&blacklist.resource_name).map_err(|e| {
+                MqttBrokerError::CommonError(format!(
+                    "invalid {} blacklist pattern {}: {}",
+                    blacklist.blacklist_type, blacklist.resource_name, e
+                ))
+            })?;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
 }
 
 impl Queryable for BlacklistRaw {
@@ -119,3 +154,44 @@ fn get_field_str(&self, field: &str) -> Option<String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
+
+    use super::validate_blacklist_pattern;
+
+    fn blacklist(blacklist_type: MqttAclBlackListType, resource_name: &str) -> MqttAclBlackList {
+        MqttAclBlackList {
+            blacklist_type,
+            resource_name: resource_name.to_string(),
+            end_time: 0,
+            desc: "".to_string(),
+            expire_at: None,
+        }
+    }
+
+    #[test]
+    fn validate_blacklist_pattern_test() {
+        let valid = [
+            blacklist(MqttAclBlackListType::ClientIdMatch, "device-[0-9]+"),
+            blacklist(MqttAclBlackListType::UserMatch, "guest.*"),
+            blacklist(MqttAclBlackListType::IpMatch, r"10\.0\.\d+\.\d+"),
+            // exact kinds are not patterns
+            blacklist(MqttAclBlackListType::ClientId, "device-[0-9"),
+        ];
+        for raw in valid {
+            assert!(validate_blacklist_pattern(&raw).is_ok(), "{:?}", raw);
+        }
+
+        let invalid = [
+            blacklist(MqttAclBlackListType::ClientIdMatch, "device-[0-9"),
+            blacklist(MqttAclBlackListType::UserMatch, "guest(.*"),
+            blacklist(MqttAclBlackListType::IpMatch, "10.0.*)"),
+        ];
+        for raw in invalid {
+            let err = validate_blacklist_pattern(&raw).unwrap_err();
+            assert!(err.to_string().contains(&raw.resource_name), "{}", err);
+        }
+    }
+}
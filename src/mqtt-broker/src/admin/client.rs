@@ -14,12 +14,43 @@
 
 use crate::admin::query::{apply_filters, apply_pagination, apply_sorting, Queryable};
 use crate::handler::cache::CacheManager;
+use crate::handler::connection::{kick_connection, redirect_connection};
 use crate::handler::error::MqttBrokerError;
+use crate::observability::metrics::session::{
+    get_duplicate_client_id_counter, get_will_acl_counter, get_will_grace_counter,
+    DUPLICATE_CLIENT_ID_OUTCOME_REJECTED, DUPLICATE_CLIENT_ID_OUTCOME_TAKEOVER,
+    WILL_ACL_OUTCOME_REJECTED, WILL_ACL_OUTCOME_STRIPPED, WILL_GRACE_OUTCOME_REPORTED,
+    WILL_GRACE_OUTCOME_SUPPRESSED,
+};
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::session::SessionStorage;
+use crate::subscribe::manager::SubscribeManager;
+use futures::stream::{self, Stream};
+use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::session::MqttSession;
-use protocol::broker_mqtt::broker_mqtt_admin::{ClientRaw, ListClientRequest};
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    ClientMetricsRaw, ClientRaw, DisconnectClientReply, DisconnectClientRequest,
+    ExportClientMetricsRequest, GetDuplicateClientIdConfigReply, GetWillPublishConfigReply,
+    ListClientRequest, RedirectClientReply, RedirectClientRequest,
+};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tonic::Request;
+use tracing::warn;
+
+const CLIENT_METRICS_FIELDS: [&str; 10] = [
+    "username",
+    "is_online",
+    "source_ip",
+    "connected_at",
+    "subscribe_num",
+    "bytes_received",
+    "bytes_sent",
+    "queue_depth",
+    "inflight_num",
+    "last_seen_time",
+];
 
 // List all clients by request
 pub async fn list_client_by_req(
@@ -35,6 +66,323 @@ pub async fn list_client_by_req(
     Ok(pagination)
 }
 
+// Get the duplicate client id policy and how often each outcome happened
+pub async fn get_duplicate_client_id_config_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> Result<GetDuplicateClientIdConfigReply, MqttBrokerError> {
+    let config = cache_manager.get_duplicate_client_id_config();
+    Ok(GetDuplicateClientIdConfigReply {
+        policy: format!("{:?}", config.policy),
+        grace_period_sec: config.grace_period_sec,
+        takeover_num: get_duplicate_client_id_counter(DUPLICATE_CLIENT_ID_OUTCOME_TAKEOVER),
+        rejected_num: get_duplicate_client_id_counter(DUPLICATE_CLIENT_ID_OUTCOME_REJECTED),
+    })
+}
+
+// Get the will grace period and how many held back wills were suppressed or reported, and
+// how unauthorized will topics are handled and how often that happened
+pub fn get_will_publish_config_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> GetWillPublishConfigReply {
+    let config = cache_manager.get_will_publish_config();
+    GetWillPublishConfigReply {
+        unclean_grace_period_sec: config.unclean_grace_period_sec,
+        suppressed_num: get_will_grace_counter(WILL_GRACE_OUTCOME_SUPPRESSED),
+        reported_num: get_will_grace_counter(WILL_GRACE_OUTCOME_REPORTED),
+        unauthorized_will_action: format!("{:?}", config.unauthorized_will_action),
+        will_acl_rejected_num: get_will_acl_counter(WILL_ACL_OUTCOME_REJECTED),
+        will_acl_stripped_num: get_will_acl_counter(WILL_ACL_OUTCOME_STRIPPED),
+    }
+}
+
+// Export the metrics of every client known to this broker. Rows are built one by one
+// while the stream is consumed, only the client ids are collected up front.
+pub fn export_client_metrics_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    request: Request<ExportClientMetricsRequest>,
+) -> Result<impl Stream<Item = ClientMetricsRaw> + Send + 'static, MqttBrokerError> {
+    let req = request.into_inner();
+    for field in req.fields.iter() {
+        if !CLIENT_METRICS_FIELDS.contains(&field.as_str()) {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unknown client metrics field {}, supported fields: {}",
+                field,
+                CLIENT_METRICS_FIELDS.join(",")
+            )));
+        }
+    }
+
+    let mut subscribe_nums: HashMap<String, u64> = HashMap::new();
+    for entry in subscribe_manager.subscribe_list.iter() {
+        *subscribe_nums.entry(entry.client_id.clone()).or_default() += 1;
+    }
+
+    let mut queue_depths: HashMap<String, u64> = HashMap::new();
+    for progress in subscribe_manager.push_progress.iter() {
+        *queue_depths.entry(progress.client_id.clone()).or_default() +=
+            progress.queued_num(subscribe_manager.get_topic_latest_offset(&progress.topic_id));
+    }
+
+    let client_ids: Vec<String> = cache_manager
+        .session_info
+        .iter()
+        .map(|entry| entry.key().clone())
+        .collect();
+
+    let cache_manager = cache_manager.clone();
+    let connection_manager = connection_manager.clone();
+    let rows = client_ids.into_iter().filter_map(move |client_id| {
+        let session = cache_manager.get_session_info(&client_id)?;
+        let row = build_client_metrics(
+            &cache_manager,
+            &connection_manager,
+            session,
+            subscribe_nums.get(&client_id).copied().unwrap_or_default(),
+            queue_depths.get(&client_id).copied().unwrap_or_default(),
+        );
+
+        if req.last_seen_start_time > 0 && row.last_seen_time < req.last_seen_start_time {
+            return None;
+        }
+        if req.last_seen_end_time > 0 && row.last_seen_time > req.last_seen_end_time {
+            return None;
+        }
+        Some(select_client_metrics_fields(row, &req.fields))
+    });
+    Ok(stream::iter(rows))
+}
+
+fn build_client_metrics(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    session: MqttSession,
+    subscribe_num: u64,
+    queue_depth: u64,
+) -> ClientMetricsRaw {
+    let connection = session
+        .connection_id
+        .and_then(|connect_id| cache_manager.get_connection(connect_id));
+    let network_connection = session
+        .connection_id
+        .and_then(|connect_id| connection_manager.get_connect(connect_id));
+
+    let last_seen_time =
+        if let Some(live_time) = cache_manager.heartbeat_data.get(&session.client_id) {
+            live_time.heartbeat
+        } else if let Some(conn) = connection.as_ref() {
+            conn.create_time
+        } else {
+            session
+                .distinct_time
+                .or(session.reconnect_time)
+                .unwrap_or(session.create_time)
+        };
+
+    let mut row = ClientMetricsRaw {
+        client_id: session.client_id,
+        subscribe_num,
+        queue_depth,
+        last_seen_time,
+        ..Default::default()
+    };
+    if let Some(conn) = connection {
+        row.is_online = true;
+        row.username = conn.login_user.clone();
+        row.source_ip = conn.source_ip_addr.clone();
+        row.connected_at = conn.create_time;
+        row.inflight_num = conn.get_send_qos_message().max(0) as u64;
+    }
+    if let Some(network_connection) = network_connection {
+        row.bytes_received = network_connection.get_bytes_received();
+        row.bytes_sent = network_connection.get_bytes_sent();
+    }
+    row
+}
+
+// Unselected fields are left at their default value, which is not encoded on the wire
+fn select_client_metrics_fields(row: ClientMetricsRaw, fields: &[String]) -> ClientMetricsRaw {
+    if fields.is_empty() {
+        return row;
+    }
+
+    let selected = |name: &str| fields.iter().any(|field| field == name);
+    let mut result = ClientMetricsRaw {
+        client_id: row.client_id,
+        ..Default::default()
+    };
+    if selected("username") {
+        result.username = row.username;
+    }
+    if selected("is_online") {
+        result.is_online = row.is_online;
+    }
+    if selected("source_ip") {
+        result.source_ip = row.source_ip;
+    }
+    if selected("connected_at") {
+        result.connected_at = row.connected_at;
+    }
+    if selected("subscribe_num") {
+        result.subscribe_num = row.subscribe_num;
+    }
+    if selected("bytes_received") {
+        result.bytes_received = row.bytes_received;
+    }
+    if selected("bytes_sent") {
+        result.bytes_sent = row.bytes_sent;
+    }
+    if selected("queue_depth") {
+        result.queue_depth = row.queue_depth;
+    }
+    if selected("inflight_num") {
+        result.inflight_num = row.inflight_num;
+    }
+    if selected("last_seen_time") {
+        result.last_seen_time = row.last_seen_time;
+    }
+    result
+}
+
+// Disconnect the clients matching the filter and point them to another server. MQTT 5 clients
+// receive the server reference in the DISCONNECT, older clients are only disconnected.
+pub async fn redirect_client_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<RedirectClientRequest>,
+) -> Result<RedirectClientReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if req.server_reference.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "server_reference cannot be empty".to_string(),
+        ));
+    }
+
+    if req.client_ids.is_empty() && req.client_id_prefix.is_empty() && req.username.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "at least one of client_ids, client_id_prefix or username must be set".to_string(),
+        ));
+    }
+
+    let mut targets: Vec<(String, u64)> = cache_manager
+        .connection_info
+        .iter()
+        .filter(|entry| is_redirect_target(entry.value(), &req))
+        .map(|entry| (entry.value().client_id.clone(), *entry.key()))
+        .collect();
+    targets.sort();
+    if req.limit > 0 {
+        targets.truncate(req.limit as usize);
+    }
+
+    let mut reply = RedirectClientReply::default();
+    for (client_id, connect_id) in targets {
+        match redirect_connection(
+            &client_id,
+            connect_id,
+            &req.server_reference,
+            cache_manager,
+            client_pool,
+            connection_manager,
+            subscribe_manager,
+        )
+        .await
+        {
+            Ok(true) => reply.redirected_num += 1,
+            Ok(false) => reply.disconnected_num += 1,
+            Err(e) => {
+                warn!(
+                    "Failed to redirect client {} to {}, error message: {}",
+                    client_id, req.server_reference, e
+                );
+                reply.failed_num += 1;
+            }
+        }
+    }
+    Ok(reply)
+}
+
+// Disconnect a client from this broker, optionally only one of its connections. With
+// clean_session the session and its subscriptions are removed too, also when the client
+// is not connected.
+pub async fn disconnect_client_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<DisconnectClientRequest>,
+) -> Result<DisconnectClientReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if req.client_id.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "client_id cannot be empty".to_string(),
+        ));
+    }
+
+    let connect_ids = client_connect_ids(cache_manager, &req.client_id, req.connection_id);
+    let mut closed_num = 0;
+    for connect_id in connect_ids.iter() {
+        kick_connection(
+            &req.client_id,
+            *connect_id,
+            cache_manager,
+            client_pool,
+            connection_manager,
+            subscribe_manager,
+            req.clean_session,
+        )
+        .await?;
+        closed_num += 1;
+    }
+
+    if req.clean_session
+        && connect_ids.is_empty()
+        && req.connection_id.is_none()
+        && cache_manager.get_session_info(&req.client_id).is_some()
+    {
+        let session_storage = SessionStorage::new(client_pool.clone());
+        session_storage
+            .delete_session(req.client_id.clone())
+            .await?;
+        cache_manager.remove_session(&req.client_id);
+        subscribe_manager.remove_client_id(&req.client_id);
+    }
+
+    Ok(DisconnectClientReply { closed_num })
+}
+
+fn client_connect_ids(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    connection_id: Option<u64>,
+) -> Vec<u64> {
+    let mut connect_ids: Vec<u64> = cache_manager
+        .connection_info
+        .iter()
+        .filter(|entry| entry.value().client_id == client_id)
+        .map(|entry| *entry.key())
+        .filter(|connect_id| connection_id.is_none_or(|id| id == *connect_id))
+        .collect();
+    connect_ids.sort();
+    connect_ids
+}
+
+fn is_redirect_target(connection: &MQTTConnection, req: &RedirectClientRequest) -> bool {
+    if !req.client_ids.is_empty() && !req.client_ids.contains(&connection.client_id) {
+        return false;
+    }
+
+    if !req.client_id_prefix.is_empty() && !connection.client_id.starts_with(&req.client_id_prefix)
+    {
+        return false;
+    }
+
+    req.username.is_empty() || connection.login_user == req.username
+}
+
 fn extract_clients(cache_manager: &Arc<CacheManager>) -> Vec<ClientRaw> {
     cache_manager
         .session_info
@@ -85,3 +433,138 @@ fn get_field_str(&self, field: &str) -> Option<String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use protocol::broker_mqtt::broker_mqtt_admin::ExportClientMetricsRequest;
+    use tonic::Request;
+
+    use metadata_struct::mqtt::connection::MQTTConnection;
+    use protocol::broker_mqtt::broker_mqtt_admin::RedirectClientRequest;
+
+    use super::{client_connect_ids, export_client_metrics_by_req, is_redirect_target};
+    use crate::handler::cache::CacheManager;
+    use crate::server::connection_manager::ConnectionManager;
+    use crate::subscribe::manager::SubscribeManager;
+
+    #[tokio::test]
+    async fn export_client_metrics_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+
+        for (client_id, distinct_time) in [("c1", 100), ("c2", 200)] {
+            cache_manager.add_session(
+                client_id,
+                &MqttSession {
+                    client_id: client_id.to_string(),
+                    distinct_time: Some(distinct_time),
+                    ..Default::default()
+                },
+            );
+        }
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: "/a/b".to_string(),
+            ..Default::default()
+        });
+
+        let request = Request::new(ExportClientMetricsRequest {
+            fields: vec!["subscribe_num".to_string()],
+            last_seen_start_time: 0,
+            last_seen_end_time: 150,
+        });
+        let rows: Vec<_> = export_client_metrics_by_req(
+            &cache_manager,
+            &subscribe_manager,
+            &connection_manager,
+            request,
+        )
+        .unwrap()
+        .collect()
+        .await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].client_id, "c1");
+        assert_eq!(rows[0].subscribe_num, 1);
+        // not selected
+        assert_eq!(rows[0].last_seen_time, 0);
+
+        let request = Request::new(ExportClientMetricsRequest {
+            fields: vec!["unknown".to_string()],
+            last_seen_start_time: 0,
+            last_seen_end_time: 0,
+        });
+        assert!(export_client_metrics_by_req(
+            &cache_manager,
+            &subscribe_manager,
+            &connection_manager,
+            request,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn is_redirect_target_test() {
+        let connection = MQTTConnection {
+            client_id: "sensor-1".to_string(),
+            login_user: "admin".to_string(),
+            ..Default::default()
+        };
+
+        let mut req = RedirectClientRequest {
+            client_id_prefix: "sensor-".to_string(),
+            ..Default::default()
+        };
+        assert!(is_redirect_target(&connection, &req));
+
+        req.username = "guest".to_string();
+        assert!(!is_redirect_target(&connection, &req));
+
+        req.username = "admin".to_string();
+        req.client_ids = vec!["sensor-2".to_string()];
+        assert!(!is_redirect_target(&connection, &req));
+
+        req.client_ids.push("sensor-1".to_string());
+        assert!(is_redirect_target(&connection, &req));
+    }
+
+    #[test]
+    fn client_connect_ids_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        for client_id in ["c1", "c2"] {
+            cache_manager.add_session(
+                client_id,
+                &MqttSession {
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        for (connect_id, client_id) in [(1, "c1"), (2, "c2")] {
+            cache_manager.add_connection(
+                connect_id,
+                MQTTConnection {
+                    connect_id,
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(client_connect_ids(&cache_manager, "c1", None), vec![1]);
+        assert_eq!(client_connect_ids(&cache_manager, "c2", Some(2)), vec![2]);
+        assert!(client_connect_ids(&cache_manager, "c2", Some(1)).is_empty());
+        assert!(client_connect_ids(&cache_manager, "c3", None).is_empty());
+
+        cache_manager.remove_connection(1);
+        assert!(client_connect_ids(&cache_manager, "c1", None).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
+use crate::handler::error::MqttBrokerError;
+use crate::subscribe::common::{build_sub_path_regex, is_wildcards};
+use common_config::mqtt::config::{ClientGroup, ClientGroupPolicy};
+use grpc_clients::pool::ClientPool;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    ClientGroupRaw, DeleteClientGroupRequest, ListClientGroupReply, SetClientGroupMemberRequest,
+    SetClientGroupRequest, SetUserMaxQosRequest, UserMaxQosRaw,
+};
+use std::sync::Arc;
+use tonic::Request;
+
+// Create a client group or replace the policy of an existing one, members are kept
+pub async fn set_client_group_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetClientGroupRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.group_name.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "group_name cannot be empty".to_string(),
+        ));
+    }
+    let max_qos = req.max_qos.map(parse_max_qos).transpose()?;
+    for filter in req.allow_topics.iter().chain(req.deny_topics.iter()) {
+        if is_wildcards(filter) {
+            build_sub_path_regex(filter)?;
+        }
+    }
+
+    let mut config = cache_manager.get_client_group_config();
+    config.groups.insert(
+        req.group_name,
+        ClientGroupPolicy {
+            publish_rate: req.publish_rate,
+            message_expiry_interval: req.message_expiry_interval,
+            max_qos,
+            allow_topics: req.allow_topics,
+            deny_topics: req.deny_topics,
+        },
+    );
+    save_client_group(cache_manager, client_pool, config).await
+}
+
+// Delete a client group, its members fall back to their own settings and the cluster defaults
+pub async fn delete_client_group_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<DeleteClientGroupRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    let mut config = cache_manager.get_client_group_config();
+    if config.groups.remove(&req.group_name).is_none() {
+        return Err(MqttBrokerError::CommonError(format!(
+            "client group {} does not exist",
+            req.group_name
+        )));
+    }
+    config
+        .members
+        .retain(|_, group_name| *group_name != req.group_name);
+    save_client_group(cache_manager, client_pool, config).await
+}
+
+// Move a user into a group, an empty group name removes the user from its group
+pub async fn set_client_group_member_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetClientGroupMemberRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.username.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "username cannot be empty".to_string(),
+        ));
+    }
+
+    let mut config = cache_manager.get_client_group_config();
+    if req.group_name.is_empty() {
+        if config.members.remove(&req.username).is_none() {
+            return Ok(());
+        }
+    } else {
+        if !config.groups.contains_key(&req.group_name) {
+            return Err(MqttBrokerError::CommonError(format!(
+                "client group {} does not exist",
+                req.group_name
+            )));
+        }
+        config.members.insert(req.username, req.group_name);
+    }
+    save_client_group(cache_manager, client_pool, config).await
+}
+
+// Set the QoS cap of a single user, replacing the cap of its group. No cap removes the override.
+pub async fn set_user_max_qos_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetUserMaxQosRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.username.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "username cannot be empty".to_string(),
+        ));
+    }
+
+    let mut config = cache_manager.get_client_group_config();
+    match req.max_qos.map(parse_max_qos).transpose()? {
+        Some(max_qos) => {
+            config.user_max_qos.insert(req.username, max_qos);
+        }
+        None => {
+            config.user_max_qos.remove(&req.username);
+        }
+    }
+    save_client_group(cache_manager, client_pool, config).await
+}
+
+pub fn list_client_group_by_req(cache_manager: &Arc<CacheManager>) -> ListClientGroupReply {
+    let config = cache_manager.get_client_group_config();
+
+    let mut groups: Vec<ClientGroupRaw> = config
+        .groups
+        .iter()
+        .map(|(group_name, policy)| {
+            let mut members: Vec<String> = config
+                .members
+                .iter()
+                .filter(|(_, member_group)| *member_group == group_name)
+                .map(|(username, _)| username.clone())
+                .collect();
+            members.sort();
+            ClientGroupRaw {
+                group_name: group_name.clone(),
+                publish_rate: policy.publish_rate,
+                message_expiry_interval: policy.message_expiry_interval,
+                max_qos: policy.max_qos.map(u32::from),
+                allow_topics: policy.allow_topics.clone(),
+                deny_topics: policy.deny_topics.clone(),
+                members,
+            }
+        })
+        .collect();
+    groups.sort_by(|a, b| a.group_name.cmp(&b.group_name));
+
+    let mut user_max_qos: Vec<UserMaxQosRaw> = config
+        .user_max_qos
+        .into_iter()
+        .map(|(username, max_qos)| UserMaxQosRaw {
+            username,
+            max_qos: max_qos as u32,
+        })
+        .collect();
+    user_max_qos.sort_by(|a, b| a.username.cmp(&b.username));
+
+    ListClientGroupReply {
+        groups,
+        user_max_qos,
+    }
+}
+
+fn parse_max_qos(max_qos: u32) -> Result<u8, MqttBrokerError> {
+    if max_qos > 2 {
+        return Err(MqttBrokerError::CommonError(format!(
+            "invalid max qos {}, expected 0, 1 or 2",
+            max_qos
+        )));
+    }
+    Ok(max_qos as u8)
+}
+
+async fn save_client_group(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    config: ClientGroup,
+) -> Result<(), MqttBrokerError> {
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::ClientGroup,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_client_group_config(config);
+    Ok(())
+}
@@ -16,10 +16,16 @@
 use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
 use common_base::enum_type::feature_type::FeatureType;
+use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
-use protocol::broker_mqtt::broker_mqtt_admin::SetClusterConfigRequest;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    ClientPoolEndpointRaw, GetClientPoolStatusReply, SetClientPoolSizeReply,
+    SetClientPoolSizeRequest, SetClusterConfigRequest, SetNodeDrainingReply,
+    SetNodeDrainingRequest,
+};
 use std::str::FromStr;
 use std::sync::Arc;
+use tracing::info;
 
 pub async fn set_cluster_config_by_req(
     cache_manager: &Arc<CacheManager>,
@@ -51,6 +57,18 @@ pub async fn set_cluster_config_by_req(
             .await?;
         }
 
+        Ok(FeatureType::CertUsername) => {
+            let mut config = cache_manager.get_cert_username_config();
+            config.enable = request.is_enable;
+            cache_manager.update_cert_username_config(config.clone());
+            save_cluster_dynamic_config(
+                client_pool,
+                ClusterDynamicConfig::CertUsername,
+                config.encode(),
+            )
+            .await?;
+        }
+
         Err(e) => {
             return Err(MqttBrokerError::CommonError(format!(
                 "Failed to parse feature type: {}",
@@ -60,3 +78,87 @@ pub async fn set_cluster_config_by_req(
     }
     Ok(())
 }
+
+pub async fn get_client_pool_status_by_req(
+    client_pool: &Arc<ClientPool>,
+) -> GetClientPoolStatusReply {
+    let endpoints = client_pool
+        .endpoint_states()
+        .await
+        .into_iter()
+        .map(|state| {
+            let stats = state.stats;
+            let error_rate = if stats.request_num == 0 {
+                0.0
+            } else {
+                stats.error_num as f64 / stats.request_num as f64
+            };
+            let avg_wait_ms = if stats.wait_num == 0 {
+                0.0
+            } else {
+                stats.wait_total_ms as f64 / stats.wait_num as f64
+            };
+            ClientPoolEndpointRaw {
+                service: state.service,
+                addr: state.addr,
+                max_open: state.max_open,
+                connections: state.connections,
+                in_use: state.in_use,
+                idle: state.idle,
+                request_num: stats.request_num,
+                error_num: stats.error_num,
+                error_rate,
+                wait_num: stats.wait_num,
+                avg_wait_ms,
+                max_wait_ms: stats.wait_max_ms,
+            }
+        })
+        .collect();
+
+    GetClientPoolStatusReply {
+        max_open_connection: client_pool.max_open_connection(),
+        endpoints,
+    }
+}
+
+pub async fn set_client_pool_size_by_req(
+    client_pool: &Arc<ClientPool>,
+    request: &SetClientPoolSizeRequest,
+) -> Result<SetClientPoolSizeReply, MqttBrokerError> {
+    if request.max_open_connection == 0 {
+        return Err(MqttBrokerError::CommonError(
+            "max_open_connection must be greater than 0".to_string(),
+        ));
+    }
+
+    let previous_max_open_connection = client_pool.max_open_connection();
+    client_pool
+        .set_max_open_connection(request.max_open_connection)
+        .await;
+
+    Ok(SetClientPoolSizeReply {
+        previous_max_open_connection,
+        max_open_connection: request.max_open_connection,
+    })
+}
+
+// Draining only applies to the node that receives the request and is not persisted, a restarted
+// node accepts connections again
+pub fn set_node_draining_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: &SetNodeDrainingRequest,
+) -> SetNodeDrainingReply {
+    let previous_draining = cache_manager.is_node_draining();
+    cache_manager.set_node_draining(request.draining);
+    if previous_draining != request.draining {
+        info!(
+            "Node {} draining changed to {}",
+            broker_mqtt_conf().broker_id,
+            request.draining
+        );
+    }
+    SetNodeDrainingReply {
+        previous_draining,
+        draining: request.draining,
+    }
+}
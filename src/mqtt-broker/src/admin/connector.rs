@@ -12,30 +12,58 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::bridge::core::encode_bridge_record;
+use crate::bridge::manager::ConnectorManager;
+use crate::bridge::transform::{
+    build_bridge_transforms, transform_bridge_record, validate_connector_transforms,
+};
+use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
+use crate::handler::topic::topic_name_validator;
+use crate::observability::metrics::connector::get_connector_throttled_counter;
 use crate::storage::connector::ConnectorStorage;
+use bytes::Bytes;
 use common_base::tools::now_second;
 use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::ConnectorLimit;
 use grpc_clients::placement::mqtt::call::placement_list_connector;
 use grpc_clients::pool::ClientPool;
-use metadata_struct::mqtt::bridge::config_kafka::KafkaConnectorConfig;
+use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::bridge::config_kafka::{
+    KafkaConnectorConfig, KAFKA_ACKS_VALUES, KAFKA_BATCH_SIZE_MAX, KAFKA_COMPRESSION_VALUES,
+    KAFKA_LINGER_MS_MAX,
+};
 use metadata_struct::mqtt::bridge::config_local_file::LocalFileConnectorConfig;
-use metadata_struct::mqtt::bridge::connector::MQTTConnector;
+use metadata_struct::mqtt::bridge::connector::{
+    ConnectorRetryPolicy, MQTTConnector, CONNECTOR_MAX_BACKOFF_MS_MAX,
+};
 use metadata_struct::mqtt::bridge::connector_type::ConnectorType;
 use metadata_struct::mqtt::bridge::status::MQTTStatus;
+use metadata_struct::mqtt::bridge::transform::ConnectorTransform;
+use metadata_struct::mqtt::message::MqttMessage;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    MqttConnectorType, MqttCreateConnectorRequest, MqttDeleteConnectorRequest,
-    MqttListConnectorRequest, MqttUpdateConnectorRequest,
+    ConnectorConfigErrorRaw, ConnectorEffectiveConfigRaw, ConnectorLimitRaw, ConnectorRoutingRaw,
+    ConnectorStatusRaw, MqttConnectorType, MqttCreateConnectorRequest, MqttDeleteConnectorRequest,
+    MqttGetConnectorLimitReply, MqttListConnectorReply, MqttListConnectorRequest,
+    MqttPauseConnectorRequest, MqttResumeConnectorRequest, MqttSetConnectorLimitReply,
+    MqttSetConnectorLimitRequest, MqttTestConnectorRoutingReply, MqttTestConnectorRoutingRequest,
+    MqttUpdateConnectorRequest, MqttValidateConnectorReply, MqttValidateConnectorRequest,
 };
+use protocol::mqtt::common::Publish;
 use protocol::placement_center::placement_center_mqtt::ListConnectorRequest;
+use serde_json::{Map, Value};
+use std::path::Path;
 use std::sync::Arc;
 use tonic::Request;
 
-// List connectors by request
+// List connectors by request. The runtime status is only known for the connectors that run on
+// this broker.
 pub async fn list_connector_by_req(
     client_pool: &Arc<ClientPool>,
+    connector_manager: &Arc<ConnectorManager>,
     request: Request<MqttListConnectorRequest>,
-) -> Result<Vec<Vec<u8>>, MqttBrokerError> {
+) -> Result<MqttListConnectorReply, MqttBrokerError> {
     let req = request.into_inner();
     let config = broker_mqtt_conf();
     let request = ListConnectorRequest {
@@ -48,7 +76,60 @@ pub async fn list_connector_by_req(
         .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?
         .connectors;
 
-    Ok(connectors)
+    let statuses = connector_status_raws(connector_manager, &connectors);
+    let effective_configs = connector_effective_config_raws(&connectors);
+    Ok(MqttListConnectorReply {
+        connectors,
+        statuses,
+        effective_configs,
+    })
+}
+
+// The config each connector runs with, with the defaults of the options it leaves out
+fn connector_effective_config_raws(connectors: &[Vec<u8>]) -> Vec<ConnectorEffectiveConfigRaw> {
+    connectors
+        .iter()
+        .filter_map(|data| serde_json::from_slice::<MQTTConnector>(data).ok())
+        .filter_map(|connector| {
+            let config = match connector.connector_type {
+                ConnectorType::LocalFile => {
+                    serde_json::from_str::<LocalFileConnectorConfig>(&connector.config)
+                        .and_then(|config| serde_json::to_string(&config))
+                }
+                ConnectorType::Kafka => {
+                    serde_json::from_str::<KafkaConnectorConfig>(&connector.config)
+                        .and_then(|config| serde_json::to_string(&config))
+                }
+            };
+            config.ok().map(|config| ConnectorEffectiveConfigRaw {
+                connector_name: connector.connector_name,
+                config,
+            })
+        })
+        .collect()
+}
+
+fn connector_status_raws(
+    connector_manager: &Arc<ConnectorManager>,
+    connectors: &[Vec<u8>],
+) -> Vec<ConnectorStatusRaw> {
+    connectors
+        .iter()
+        .filter_map(|data| serde_json::from_slice::<MQTTConnector>(data).ok())
+        .filter_map(|connector| {
+            connector_manager
+                .get_connector_status(&connector.connector_name)
+                .map(|status| ConnectorStatusRaw {
+                    connector_name: connector.connector_name,
+                    state: status.state.to_string(),
+                    last_error: status.last_error,
+                    last_success_at: status.last_success_at,
+                    retry_num: status.retry_num,
+                    dead_letter_num: status.dead_letter_num,
+                    dropped_num: status.dropped_num,
+                })
+        })
+        .collect()
 }
 
 // Create a new connector
@@ -59,6 +140,8 @@ pub async fn create_connector_by_req(
     let req = request.into_inner();
     let connector_type = parse_mqtt_connector_type(req.connector_type());
     connector_config_validator(&connector_type, &req.config)?;
+    let transforms = parse_connector_transforms(&connector_type, &req.transforms)?;
+    let retry_policy = parse_connector_retry_policy(&connector_type, &req.retry_policy)?;
 
     let config = broker_mqtt_conf();
     let storage = ConnectorStorage::new(client_pool.clone());
@@ -72,6 +155,9 @@ pub async fn create_connector_by_req(
         broker_id: None,
         create_time: now_second(),
         update_time: now_second(),
+        paused: false,
+        transforms,
+        retry_policy,
     };
 
     storage
@@ -91,8 +177,43 @@ pub async fn update_connector_by_req(
         .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
 
     connector_config_validator(&connector.connector_type, &connector.config)?;
+    connector_transforms_validator(&connector.connector_type, &connector.transforms)?;
+    connector_retry_policy_validator(&connector.connector_type, &connector.retry_policy)?;
+
+    let storage = ConnectorStorage::new(client_pool.clone());
+    storage
+        .update_connector(connector)
+        .await
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+
+    Ok(())
+}
 
+// Pause or resume a connector. The flag is saved with the connector and reaches the broker
+// running it through the connector cache, so the connector keeps its config and offset.
+async fn set_connector_paused(
+    client_pool: &Arc<ClientPool>,
+    connector_name: &str,
+    paused: bool,
+) -> Result<(), MqttBrokerError> {
     let storage = ConnectorStorage::new(client_pool.clone());
+    let Some(mut connector) = storage
+        .list_connector(connector_name)
+        .await?
+        .into_iter()
+        .find(|connector| connector.connector_name == connector_name)
+    else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "connector {} does not exist",
+            connector_name
+        )));
+    };
+
+    if connector.paused == paused {
+        return Ok(());
+    }
+    connector.paused = paused;
+    connector.update_time = now_second();
     storage
         .update_connector(connector)
         .await
@@ -101,6 +222,22 @@ pub async fn update_connector_by_req(
     Ok(())
 }
 
+pub async fn pause_connector_by_req(
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttPauseConnectorRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    set_connector_paused(client_pool, &req.connector_name, true).await
+}
+
+pub async fn resume_connector_by_req(
+    client_pool: &Arc<ClientPool>,
+    request: Request<MqttResumeConnectorRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    set_connector_paused(client_pool, &req.connector_name, false).await
+}
+
 // Delete an existing connector
 pub async fn delete_connector_by_req(
     client_pool: &Arc<ClientPool>,
@@ -118,19 +255,475 @@ pub async fn delete_connector_by_req(
     Ok(())
 }
 
+// Validate a connector config without creating the connector
+pub async fn validate_connector_by_req(
+    request: Request<MqttValidateConnectorRequest>,
+) -> Result<MqttValidateConnectorReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let connector_type = parse_mqtt_connector_type(req.connector_type());
+    let errors = validate_connector_config(&connector_type, &req.config);
+    Ok(MqttValidateConnectorReply {
+        valid: errors.is_empty(),
+        errors,
+    })
+}
+
+// Work out which connectors a message on the topic would reach and what they would write,
+// without publishing anything
+pub fn test_connector_routing_by_req(
+    cache_manager: &Arc<CacheManager>,
+    connector_manager: &Arc<ConnectorManager>,
+    request: Request<MqttTestConnectorRoutingRequest>,
+) -> Result<MqttTestConnectorRoutingReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let topic = cache_manager
+        .get_topic_by_name(&req.topic_name)
+        .ok_or_else(|| MqttBrokerError::TopicDoesNotExist(req.topic_name.clone()))?;
+
+    let publish = Publish {
+        topic: Bytes::from(req.topic_name.clone()),
+        payload: Bytes::from(req.payload),
+        ..Default::default()
+    };
+    let record = MqttMessage::build_record(&req.client_id, &publish, &None, 0);
+
+    let broker_id = broker_mqtt_conf().broker_id;
+    let mut routes: Vec<ConnectorRoutingRaw> = connector_manager
+        .get_all_connector()
+        .into_iter()
+        .filter(|connector| connector.topic_id == topic.topic_id)
+        .map(|connector| {
+            let running = connector_manager
+                .get_connector_thread(&connector.connector_name)
+                .is_some();
+            match connector_routing_output(&connector, &record) {
+                Ok(output) => build_connector_routing(&connector, broker_id, running, &output),
+                Err(e) => {
+                    let mut route = build_connector_routing(&connector, broker_id, running, "");
+                    let detail = format!("transforms cannot be applied: {}", e);
+                    route.detail = if route.detail.is_empty() {
+                        detail
+                    } else {
+                        format!("{}; {}", detail, route.detail)
+                    };
+                    route
+                }
+            }
+        })
+        .collect();
+    routes.sort_by(|a, b| a.connector_name.cmp(&b.connector_name));
+
+    Ok(MqttTestConnectorRoutingReply {
+        topic_id: topic.topic_id,
+        routes,
+    })
+}
+
+pub async fn set_connector_limit_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<MqttSetConnectorLimitRequest>,
+) -> Result<MqttSetConnectorLimitReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let config = ConnectorLimit {
+        max_records_per_sec: req.max_records_per_sec,
+    };
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::ConnectorLimit,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_connector_limit_config(config.clone());
+    Ok(MqttSetConnectorLimitReply {
+        max_records_per_sec: config.max_records_per_sec,
+    })
+}
+
+// The forwarding limit along with how much of it each connector is using on this broker.
+// A connector reads from exactly one topic, so its source breadth is always 1.
+pub fn get_connector_limit_by_req(
+    cache_manager: &Arc<CacheManager>,
+    connector_manager: &Arc<ConnectorManager>,
+) -> MqttGetConnectorLimitReply {
+    let mut connectors: Vec<ConnectorLimitRaw> = connector_manager
+        .get_all_connector()
+        .into_iter()
+        .map(|connector| ConnectorLimitRaw {
+            running: connector_manager
+                .get_connector_thread(&connector.connector_name)
+                .is_some(),
+            source_topic_num: 1,
+            forwarded_in_last_sec: connector_manager.forwarded_in_window(&connector.connector_name),
+            throttled_num: get_connector_throttled_counter(&connector.connector_name),
+            topic_id: connector.topic_id,
+            connector_name: connector.connector_name,
+        })
+        .collect();
+    connectors.sort_by(|a, b| a.connector_name.cmp(&b.connector_name));
+
+    MqttGetConnectorLimitReply {
+        max_records_per_sec: cache_manager
+            .get_connector_limit_config()
+            .max_records_per_sec,
+        connectors,
+    }
+}
+
+// What the connector would write for the record, after its transforms
+fn connector_routing_output(
+    connector: &MQTTConnector,
+    record: &Option<Record>,
+) -> Result<String, MqttBrokerError> {
+    let Some(record) = record else {
+        return Ok(String::new());
+    };
+    if connector.transforms.is_empty() {
+        return encode_bridge_record(record);
+    }
+    let transforms =
+        build_bridge_transforms(&connector.transforms).map_err(MqttBrokerError::CommonError)?;
+    encode_bridge_record(&transform_bridge_record(&transforms, record)?)
+}
+
+fn build_connector_routing(
+    connector: &MQTTConnector,
+    broker_id: u64,
+    running: bool,
+    output: &str,
+) -> ConnectorRoutingRaw {
+    let mut details = Vec::new();
+    let (target, key) = match connector.connector_type {
+        ConnectorType::Kafka => {
+            match serde_json::from_str::<KafkaConnectorConfig>(&connector.config) {
+                Ok(config) => (config.topic, config.key),
+                Err(e) => {
+                    details.push(format!("config cannot be parsed: {}", e));
+                    (String::new(), String::new())
+                }
+            }
+        }
+        ConnectorType::LocalFile => {
+            match serde_json::from_str::<LocalFileConnectorConfig>(&connector.config) {
+                Ok(config) => (config.local_file_path, String::new()),
+                Err(e) => {
+                    details.push(format!("config cannot be parsed: {}", e));
+                    (String::new(), String::new())
+                }
+            }
+        }
+    };
+
+    match connector.broker_id {
+        None => details.push("not assigned to a broker".to_string()),
+        Some(id) if id != broker_id => details.push(format!("runs on broker {}", id)),
+        Some(_) if !running => details.push("no plugin thread on this broker".to_string()),
+        Some(_) => {}
+    }
+
+    ConnectorRoutingRaw {
+        connector_name: connector.connector_name.clone(),
+        connector_type: connector.connector_type.to_string(),
+        status: connector.status.to_string(),
+        broker_id: connector.broker_id,
+        running,
+        target,
+        key,
+        output: output.to_string(),
+        detail: details.join("; "),
+    }
+}
+
 fn connector_config_validator(
     connector_type: &ConnectorType,
     config: &str,
 ) -> Result<(), MqttBrokerError> {
+    let errors = validate_connector_config(connector_type, config);
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    let detail: Vec<String> = errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect();
+    Err(MqttBrokerError::ConnectorConfigInvalid(
+        connector_type.to_string(),
+        detail.join("; "),
+    ))
+}
+
+// Transforms are given as a JSON array, an empty string means none
+fn parse_connector_transforms(
+    connector_type: &ConnectorType,
+    transforms: &str,
+) -> Result<Vec<ConnectorTransform>, MqttBrokerError> {
+    if transforms.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let transforms = serde_json::from_str::<Vec<ConnectorTransform>>(transforms).map_err(|e| {
+        MqttBrokerError::ConnectorConfigInvalid(
+            connector_type.to_string(),
+            format!("transforms: {}", e),
+        )
+    })?;
+    connector_transforms_validator(connector_type, &transforms)?;
+    Ok(transforms)
+}
+
+fn connector_transforms_validator(
+    connector_type: &ConnectorType,
+    transforms: &[ConnectorTransform],
+) -> Result<(), MqttBrokerError> {
+    validate_connector_transforms(transforms).map_err(|e| {
+        MqttBrokerError::ConnectorConfigInvalid(
+            connector_type.to_string(),
+            format!("transforms: {}", e),
+        )
+    })
+}
+
+// The retry policy is given as a JSON object, an empty string or a left out field takes
+// the default
+fn parse_connector_retry_policy(
+    connector_type: &ConnectorType,
+    retry_policy: &str,
+) -> Result<ConnectorRetryPolicy, MqttBrokerError> {
+    if retry_policy.trim().is_empty() {
+        return Ok(ConnectorRetryPolicy::default());
+    }
+    let retry_policy = serde_json::from_str::<ConnectorRetryPolicy>(retry_policy).map_err(|e| {
+        MqttBrokerError::ConnectorConfigInvalid(
+            connector_type.to_string(),
+            format!("retry_policy: {}", e),
+        )
+    })?;
+    connector_retry_policy_validator(connector_type, &retry_policy)?;
+    Ok(retry_policy)
+}
+
+fn connector_retry_policy_validator(
+    connector_type: &ConnectorType,
+    retry_policy: &ConnectorRetryPolicy,
+) -> Result<(), MqttBrokerError> {
+    let detail = if retry_policy.initial_backoff_ms == 0 {
+        Some("initial_backoff_ms must be greater than 0".to_string())
+    } else if retry_policy.max_backoff_ms < retry_policy.initial_backoff_ms {
+        Some("max_backoff_ms must not be less than initial_backoff_ms".to_string())
+    } else if retry_policy.max_backoff_ms > CONNECTOR_MAX_BACKOFF_MS_MAX {
+        Some(format!(
+            "max_backoff_ms must not be greater than {}",
+            CONNECTOR_MAX_BACKOFF_MS_MAX
+        ))
+    } else if retry_policy.dead_letter_topic.is_empty() {
+        None
+    } else if retry_policy.dead_letter_topic.contains(['+', '#']) {
+        Some("dead_letter_topic must not contain wildcards".to_string())
+    } else {
+        topic_name_validator(&retry_policy.dead_letter_topic)
+            .err()
+            .map(|e| format!("dead_letter_topic: {}", e))
+    };
+    match detail {
+        Some(detail) => Err(MqttBrokerError::ConnectorConfigInvalid(
+            connector_type.to_string(),
+            format!("retry_policy: {}", detail),
+        )),
+        None => Ok(()),
+    }
+}
+
+// Check the config of a connector against the fields its sink needs, every problem
+// found is reported with the field it belongs to
+fn validate_connector_config(
+    connector_type: &ConnectorType,
+    config: &str,
+) -> Vec<ConnectorConfigErrorRaw> {
+    let mut errors = Vec::new();
+    let fields = match serde_json::from_str::<Value>(config) {
+        Ok(Value::Object(fields)) => fields,
+        Ok(_) => {
+            push_config_error(&mut errors, "", "config must be a JSON object");
+            return errors;
+        }
+        Err(e) => {
+            push_config_error(&mut errors, "", &format!("config is not valid JSON: {}", e));
+            return errors;
+        }
+    };
+
     match connector_type {
-        ConnectorType::LocalFile => {
-            let _file_config: LocalFileConnectorConfig = serde_json::from_str(config)?;
+        ConnectorType::LocalFile => validate_local_file_config(&fields, &mut errors),
+        ConnectorType::Kafka => validate_kafka_config(&fields, &mut errors),
+    }
+
+    // Catch anything the field checks above missed, such as type mismatches
+    if errors.is_empty() {
+        let res = match connector_type {
+            ConnectorType::LocalFile => {
+                serde_json::from_str::<LocalFileConnectorConfig>(config).map(|_| ())
+            }
+            ConnectorType::Kafka => {
+                serde_json::from_str::<KafkaConnectorConfig>(config).map(|_| ())
+            }
+        };
+        if let Err(e) = res {
+            push_config_error(&mut errors, "", &e.to_string());
         }
-        ConnectorType::Kafka => {
-            let _kafka_config: KafkaConnectorConfig = serde_json::from_str(config)?;
+    }
+    errors
+}
+
+fn validate_local_file_config(
+    fields: &Map<String, Value>,
+    errors: &mut Vec<ConnectorConfigErrorRaw>,
+) {
+    let Some(path) = required_str_field(fields, "local_file_path", errors) else {
+        return;
+    };
+
+    let path = Path::new(path);
+    if path.is_dir() {
+        push_config_error(errors, "local_file_path", "must be a file, not a directory");
+        return;
+    }
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.is_dir() {
+            push_config_error(
+                errors,
+                "local_file_path",
+                &format!("directory {} does not exist", parent.display()),
+            );
         }
     }
-    Ok(())
+}
+
+fn validate_kafka_config(fields: &Map<String, Value>, errors: &mut Vec<ConnectorConfigErrorRaw>) {
+    if let Some(servers) = required_str_field(fields, "bootstrap_servers", errors) {
+        for server in servers.split(',').map(|server| server.trim()) {
+            if !is_valid_broker_address(server) {
+                push_config_error(
+                    errors,
+                    "bootstrap_servers",
+                    &format!("{:?} is not a valid host:port address", server),
+                );
+            }
+        }
+    }
+
+    if let Some(topic) = required_str_field(fields, "topic", errors) {
+        if !is_valid_kafka_topic(topic) {
+            push_config_error(
+                errors,
+                "topic",
+                "must be at most 249 characters of [a-zA-Z0-9._-] and not \".\" or \"..\"",
+            );
+        }
+    }
+
+    required_str_field(fields, "key", errors);
+
+    // The producer options are optional, a missing one takes its default
+    if let Some(acks) = fields.get("acks") {
+        if !acks
+            .as_str()
+            .is_some_and(|acks| KAFKA_ACKS_VALUES.contains(&acks))
+        {
+            push_config_error(
+                errors,
+                "acks",
+                &format!("must be one of {}", KAFKA_ACKS_VALUES.join(", ")),
+            );
+        }
+    }
+
+    if let Some(compression) = fields.get("compression") {
+        if !compression
+            .as_str()
+            .is_some_and(|compression| KAFKA_COMPRESSION_VALUES.contains(&compression))
+        {
+            push_config_error(
+                errors,
+                "compression",
+                &format!("must be one of {}", KAFKA_COMPRESSION_VALUES.join(", ")),
+            );
+        }
+    }
+
+    if let Some(batch_size) = fields.get("batch_size") {
+        if !batch_size
+            .as_u64()
+            .is_some_and(|batch_size| (1..=KAFKA_BATCH_SIZE_MAX).contains(&batch_size))
+        {
+            push_config_error(
+                errors,
+                "batch_size",
+                &format!("must be an integer between 1 and {}", KAFKA_BATCH_SIZE_MAX),
+            );
+        }
+    }
+
+    if let Some(linger_ms) = fields.get("linger_ms") {
+        if !linger_ms
+            .as_u64()
+            .is_some_and(|linger_ms| linger_ms <= KAFKA_LINGER_MS_MAX)
+        {
+            push_config_error(
+                errors,
+                "linger_ms",
+                &format!("must be an integer between 0 and {}", KAFKA_LINGER_MS_MAX),
+            );
+        }
+    }
+}
+
+fn required_str_field<'a>(
+    fields: &'a Map<String, Value>,
+    name: &str,
+    errors: &mut Vec<ConnectorConfigErrorRaw>,
+) -> Option<&'a str> {
+    match fields.get(name) {
+        None => {
+            push_config_error(errors, name, "is required");
+            None
+        }
+        Some(Value::String(value)) => {
+            if value.trim().is_empty() {
+                push_config_error(errors, name, "cannot be empty");
+                return None;
+            }
+            Some(value)
+        }
+        Some(_) => {
+            push_config_error(errors, name, "must be a string");
+            None
+        }
+    }
+}
+
+fn is_valid_broker_address(address: &str) -> bool {
+    let Some((host, port)) = address.rsplit_once(':') else {
+        return false;
+    };
+    !host.is_empty() && port.parse::<u16>().is_ok_and(|port| port > 0)
+}
+
+fn is_valid_kafka_topic(topic: &str) -> bool {
+    topic != "."
+        && topic != ".."
+        && topic.len() <= 249
+        && topic
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+fn push_config_error(errors: &mut Vec<ConnectorConfigErrorRaw>, field: &str, message: &str) {
+    errors.push(ConnectorConfigErrorRaw {
+        field: field.to_string(),
+        message: message.to_string(),
+    });
 }
 
 fn parse_mqtt_connector_type(connector_type: MqttConnectorType) -> ConnectorType {
@@ -139,3 +732,283 @@ fn parse_mqtt_connector_type(connector_type: MqttConnectorType) -> ConnectorType
         MqttConnectorType::Kafka => ConnectorType::Kafka,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::mqtt::bridge::connector_type::ConnectorType;
+
+    use metadata_struct::mqtt::bridge::connector::{ConnectorRetryPolicy, MQTTConnector};
+
+    use bytes::Bytes;
+    use metadata_struct::adapter::record::Record;
+    use metadata_struct::mqtt::bridge::transform::ConnectorTransform;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use protocol::mqtt::common::Publish;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    use super::{
+        build_connector_routing, connector_effective_config_raws, connector_routing_output,
+        connector_status_raws, is_valid_broker_address, parse_connector_retry_policy,
+        parse_connector_transforms, validate_connector_config,
+    };
+    use crate::bridge::manager::ConnectorManager;
+
+    #[test]
+    fn connector_status_raws_test() {
+        let connector_manager = Arc::new(ConnectorManager::new());
+        let connectors: Vec<Vec<u8>> = ["kafka_sink", "file_sink", "remote_sink"]
+            .iter()
+            .map(|name| {
+                MQTTConnector {
+                    connector_name: name.to_string(),
+                    ..Default::default()
+                }
+                .encode()
+            })
+            .collect();
+
+        connector_manager.report_success("kafka_sink");
+        connector_manager.report_error("kafka_sink", "Message production error: QueueFull");
+        connector_manager.report_success("file_sink");
+        connector_manager.record_retry("file_sink");
+        connector_manager.record_dead_letter("file_sink", 10);
+
+        let statuses = connector_status_raws(&connector_manager, &connectors);
+        // remote_sink does not run on this broker
+        assert_eq!(statuses.len(), 2);
+
+        let kafka = &statuses[0];
+        assert_eq!(kafka.connector_name, "kafka_sink");
+        assert_eq!(kafka.state, "Error");
+        assert_eq!(kafka.last_error, "Message production error: QueueFull");
+        assert!(kafka.last_success_at > 0);
+
+        let file = &statuses[1];
+        assert_eq!(file.connector_name, "file_sink");
+        assert_eq!(file.state, "Running");
+        assert!(file.last_error.is_empty());
+        assert_eq!(file.retry_num, 1);
+        assert_eq!(file.dead_letter_num, 10);
+        assert_eq!(file.dropped_num, 0);
+    }
+
+    #[test]
+    fn validate_kafka_config_test() {
+        let config = r#"{"bootstrap_servers":"127.0.0.1:9092,kafka:9093","topic":"t1","key":"k"}"#;
+        assert!(validate_connector_config(&ConnectorType::Kafka, config).is_empty());
+
+        let config = r#"{"bootstrap_servers":"127.0.0.1","topic":"a/b"}"#;
+        let fields: Vec<String> = validate_connector_config(&ConnectorType::Kafka, config)
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(fields, vec!["bootstrap_servers", "topic", "key"]);
+
+        let errors = validate_connector_config(&ConnectorType::Kafka, "[]");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "");
+
+        let config = r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"t1","key":"k","acks":"all","batch_size":16384,"linger_ms":0,"compression":"lz4"}"#;
+        assert!(validate_connector_config(&ConnectorType::Kafka, config).is_empty());
+
+        let config = r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"t1","key":"k","acks":"2","batch_size":0,"linger_ms":900001,"compression":"brotli"}"#;
+        let fields: Vec<String> = validate_connector_config(&ConnectorType::Kafka, config)
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["acks", "compression", "batch_size", "linger_ms"]
+        );
+
+        // Wrong types are rejected as well
+        let config = r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"t1","key":"k","acks":1,"batch_size":"16384"}"#;
+        let fields: Vec<String> = validate_connector_config(&ConnectorType::Kafka, config)
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(fields, vec!["acks", "batch_size"]);
+    }
+
+    #[test]
+    fn connector_effective_config_raws_test() {
+        let connectors: Vec<Vec<u8>> = vec![
+            MQTTConnector {
+                connector_name: "kafka_sink".to_string(),
+                connector_type: ConnectorType::Kafka,
+                config: r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"t1","key":"k1","compression":"gzip"}"#.to_string(),
+                ..Default::default()
+            }
+            .encode(),
+            MQTTConnector {
+                connector_name: "broken_sink".to_string(),
+                connector_type: ConnectorType::Kafka,
+                config: "{}".to_string(),
+                ..Default::default()
+            }
+            .encode(),
+        ];
+
+        let raws = connector_effective_config_raws(&connectors);
+        assert_eq!(raws.len(), 1);
+        assert_eq!(raws[0].connector_name, "kafka_sink");
+        let config: Value = serde_json::from_str(&raws[0].config).unwrap();
+        assert_eq!(config["compression"], "gzip");
+        assert_eq!(config["acks"], "all");
+        assert_eq!(config["batch_size"], 1000000);
+        assert_eq!(config["linger_ms"], 5);
+    }
+
+    #[test]
+    fn validate_local_file_config_test() {
+        let errors = validate_connector_config(&ConnectorType::LocalFile, "{}");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "local_file_path");
+
+        let dir = std::env::temp_dir();
+        let config = format!(
+            r#"{{"local_file_path":"{}"}}"#,
+            dir.join("connector.log").display()
+        );
+        assert!(validate_connector_config(&ConnectorType::LocalFile, &config).is_empty());
+
+        let config = format!(r#"{{"local_file_path":"{}"}}"#, dir.display());
+        assert_eq!(
+            validate_connector_config(&ConnectorType::LocalFile, &config).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn is_valid_broker_address_test() {
+        assert!(is_valid_broker_address("localhost:9092"));
+        assert!(is_valid_broker_address("[::1]:9092"));
+        assert!(!is_valid_broker_address("localhost"));
+        assert!(!is_valid_broker_address(":9092"));
+        assert!(!is_valid_broker_address("localhost:0"));
+        assert!(!is_valid_broker_address("localhost:port"));
+    }
+
+    #[test]
+    fn build_connector_routing_test() {
+        let connector = MQTTConnector {
+            connector_name: "c1".to_string(),
+            connector_type: ConnectorType::Kafka,
+            config: r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"t1","key":"k1"}"#.to_string(),
+            broker_id: Some(1),
+            ..Default::default()
+        };
+        let route = build_connector_routing(&connector, 1, true, "{}");
+        assert_eq!(route.target, "t1");
+        assert_eq!(route.key, "k1");
+        assert_eq!(route.output, "{}");
+        assert!(route.detail.is_empty());
+
+        let route = build_connector_routing(&connector, 1, false, "{}");
+        assert_eq!(route.detail, "no plugin thread on this broker");
+
+        let route = build_connector_routing(&connector, 2, false, "{}");
+        assert_eq!(route.detail, "runs on broker 1");
+
+        let connector = MQTTConnector {
+            connector_type: ConnectorType::LocalFile,
+            config: "{}".to_string(),
+            ..Default::default()
+        };
+        let route = build_connector_routing(&connector, 1, false, "{}");
+        assert!(route.detail.starts_with("config cannot be parsed"));
+        assert!(route.detail.ends_with("not assigned to a broker"));
+    }
+
+    #[test]
+    fn parse_connector_transforms_test() {
+        let connector_type = ConnectorType::LocalFile;
+        assert!(parse_connector_transforms(&connector_type, "")
+            .unwrap()
+            .is_empty());
+
+        let transforms = parse_connector_transforms(
+            &connector_type,
+            r#"[{"type":"extract","path":"$.data"},{"type":"rename","fields":{"t":"temperature"}}]"#,
+        )
+        .unwrap();
+        assert_eq!(transforms.len(), 2);
+
+        // Unknown transform types, malformed JSON and invalid paths are rejected
+        assert!(parse_connector_transforms(&connector_type, r#"[{"type":"drop"}]"#).is_err());
+        assert!(parse_connector_transforms(&connector_type, "[").is_err());
+        assert!(parse_connector_transforms(
+            &connector_type,
+            r#"[{"type":"extract","path":"data"}]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_connector_retry_policy_test() {
+        let connector_type = ConnectorType::Kafka;
+        assert_eq!(
+            parse_connector_retry_policy(&connector_type, "").unwrap(),
+            ConnectorRetryPolicy::default()
+        );
+
+        // Left out fields take their defaults
+        let policy = parse_connector_retry_policy(
+            &connector_type,
+            r#"{"max_retries":5,"dead_letter_topic":"connector/dlq"}"#,
+        )
+        .unwrap();
+        assert_eq!(policy.max_retries, 5);
+        assert_eq!(policy.initial_backoff_ms, 100);
+        assert_eq!(policy.max_backoff_ms, 10000);
+        assert_eq!(policy.dead_letter_topic, "connector/dlq");
+
+        for policy in [
+            r#"{"initial_backoff_ms":0}"#,
+            r#"{"initial_backoff_ms":500,"max_backoff_ms":100}"#,
+            r#"{"max_backoff_ms":600001}"#,
+            r#"{"dead_letter_topic":"dlq/#"}"#,
+            r#"{"dead_letter_topic":"dlq topic"}"#,
+            r#"{"max_retries":-1}"#,
+            "{",
+        ] {
+            assert!(
+                parse_connector_retry_policy(&connector_type, policy).is_err(),
+                "{}",
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn connector_routing_output_transform_test() {
+        let publish = Publish {
+            payload: Bytes::from(r#"{"t":21}"#),
+            ..Default::default()
+        };
+        let record = MqttMessage::build_record("c1", &publish, &None, 0);
+
+        let mut connector = MQTTConnector::default();
+        let output = connector_routing_output(&connector, &record).unwrap();
+        let message: MqttMessage =
+            serde_json::from_slice(&serde_json::from_str::<Record>(&output).unwrap().data).unwrap();
+        assert_eq!(message.payload, Bytes::from(r#"{"t":21}"#));
+
+        connector.transforms = vec![ConnectorTransform::Rename {
+            fields: [("t".to_string(), "temperature".to_string())].into(),
+        }];
+        let output = connector_routing_output(&connector, &record).unwrap();
+        let message: MqttMessage =
+            serde_json::from_slice(&serde_json::from_str::<Record>(&output).unwrap().data).unwrap();
+        assert_eq!(message.payload, Bytes::from(r#"{"temperature":21}"#));
+
+        connector.transforms = vec![ConnectorTransform::Extract {
+            path: "$.missing".to_string(),
+        }];
+        assert!(connector_routing_output(&connector, &record).is_err());
+        assert!(connector_routing_output(&connector, &None)
+            .unwrap()
+            .is_empty());
+    }
+}
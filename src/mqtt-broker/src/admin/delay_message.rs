@@ -0,0 +1,183 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::admin::query::{apply_filters, apply_pagination, apply_sorting, Queryable};
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::subscribe::common::{is_match_sub_and_topic, sub_path_validator};
+use delay_message::DelayMessageManager;
+use metadata_struct::delay_info::DelayMessageInfo;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    DelayMessageRaw, ListDelayMessageRequest, PurgeDelayMessageRequest,
+};
+use std::sync::Arc;
+use storage_adapter::storage::StorageAdapter;
+use tonic::Request;
+
+// List the delay messages that are waiting to be sent
+pub async fn list_delay_message_by_req<S>(
+    cache_manager: &Arc<CacheManager>,
+    delay_message_manager: &Arc<DelayMessageManager<S>>,
+    request: Request<ListDelayMessageRequest>,
+) -> Result<(Vec<DelayMessageRaw>, usize), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let req = request.into_inner();
+    let delay_messages = delay_message_manager
+        .list_delay_message()
+        .into_iter()
+        .map(|delay_info| build_delay_message_raw(cache_manager, delay_info))
+        .collect();
+
+    let filtered = apply_filters(delay_messages, &req.options);
+    let sorted = apply_sorting(filtered, &req.options);
+    let pagination = apply_pagination(sorted, &req.options);
+
+    Ok(pagination)
+}
+
+// Purge the delay messages matching the topic filter and the scheduled time range,
+// returns the number of messages removed, or that would be removed when dry_run is set.
+pub async fn purge_delay_message_by_req<S>(
+    cache_manager: &Arc<CacheManager>,
+    delay_message_manager: &Arc<DelayMessageManager<S>>,
+    request: Request<PurgeDelayMessageRequest>,
+) -> Result<u64, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let req = request.into_inner();
+    validate_purge_request(&req)?;
+
+    let purged_num = delay_message_manager
+        .purge_delay_message(
+            |delay_info| is_match_purge_request(cache_manager, &req, delay_info),
+            req.dry_run,
+        )
+        .await?;
+
+    Ok(purged_num)
+}
+
+fn validate_purge_request(req: &PurgeDelayMessageRequest) -> Result<(), MqttBrokerError> {
+    if req.topic_filter.is_empty() && req.scheduled_before == 0 && req.scheduled_after == 0 {
+        return Err(MqttBrokerError::CommonError(
+            "At least one of topic_filter, scheduled_before and scheduled_after must be set"
+                .to_string(),
+        ));
+    }
+
+    if !req.topic_filter.is_empty() {
+        sub_path_validator(&req.topic_filter)?;
+    }
+
+    if req.scheduled_before > 0 && req.scheduled_after >= req.scheduled_before {
+        return Err(MqttBrokerError::CommonError(format!(
+            "scheduled_after {} must be less than scheduled_before {}",
+            req.scheduled_after, req.scheduled_before
+        )));
+    }
+    Ok(())
+}
+
+fn is_match_purge_request(
+    cache_manager: &Arc<CacheManager>,
+    req: &PurgeDelayMessageRequest,
+    delay_info: &DelayMessageInfo,
+) -> bool {
+    if req.scheduled_before > 0 && delay_info.delay_timestamp >= req.scheduled_before {
+        return false;
+    }
+
+    if req.scheduled_after > 0 && delay_info.delay_timestamp <= req.scheduled_after {
+        return false;
+    }
+
+    if !req.topic_filter.is_empty() {
+        let Some(topic_name) = cache_manager.topic_name_by_id(&delay_info.target_shard_name) else {
+            return false;
+        };
+        return is_match_sub_and_topic(&req.topic_filter, &topic_name).is_ok();
+    }
+
+    true
+}
+
+fn build_delay_message_raw(
+    cache_manager: &Arc<CacheManager>,
+    delay_info: DelayMessageInfo,
+) -> DelayMessageRaw {
+    DelayMessageRaw {
+        target_topic_name: cache_manager
+            .topic_name_by_id(&delay_info.target_shard_name)
+            .unwrap_or_default(),
+        target_topic_id: delay_info.target_shard_name,
+        delay_shard_name: delay_info.delay_shard_name,
+        offset: delay_info.offset,
+        delay_timestamp: delay_info.delay_timestamp,
+    }
+}
+
+impl Queryable for DelayMessageRaw {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "target_topic_name" => Some(self.target_topic_name.clone()),
+            "target_topic_id" => Some(self.target_topic_id.clone()),
+            "delay_shard_name" => Some(self.delay_shard_name.clone()),
+            "offset" => Some(self.offset.to_string()),
+            "delay_timestamp" => Some(self.delay_timestamp.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_purge_request;
+    use protocol::broker_mqtt::broker_mqtt_admin::PurgeDelayMessageRequest;
+
+    #[test]
+    fn validate_purge_request_test() {
+        let req = PurgeDelayMessageRequest::default();
+        assert!(validate_purge_request(&req).is_err());
+
+        let req = PurgeDelayMessageRequest {
+            topic_filter: "/a/+/c".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_purge_request(&req).is_ok());
+
+        let req = PurgeDelayMessageRequest {
+            topic_filter: "/a/b#".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_purge_request(&req).is_err());
+
+        let req = PurgeDelayMessageRequest {
+            scheduled_before: 100,
+            scheduled_after: 200,
+            ..Default::default()
+        };
+        assert!(validate_purge_request(&req).is_err());
+
+        let req = PurgeDelayMessageRequest {
+            scheduled_before: 200,
+            scheduled_after: 100,
+            dry_run: true,
+            ..Default::default()
+        };
+        assert!(validate_purge_request(&req).is_ok());
+    }
+}
@@ -0,0 +1,468 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bridge::manager::ConnectorManager;
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::security::AuthDriver;
+use crate::server::connection::NetworkConnectionType;
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::cluster::ClusterStorage;
+use crate::storage::topic::TopicStorage;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::tools::{now_mills, now_second};
+use common_config::mqtt::broker_mqtt_conf;
+use futures::future::join_all;
+use grpc_clients::mqtt::inner::call::broker_mqtt_get_broker_time;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::placement::node::BrokerNode;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    SelfDiagnoseCheckRaw, SelfDiagnoseReply, SelfDiagnoseRequest,
+};
+use protocol::broker_mqtt::broker_mqtt_inner::GetBrokerTimeRequest;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+use tonic::Request;
+
+const DIAGNOSE_DEFAULT_TIMEOUT_MS: u64 = 3000;
+const DIAGNOSE_MAX_CLOCK_SKEW_SEC: u64 = 5;
+const DIAGNOSE_CONNECTOR_HEARTBEAT_TIMEOUT_SEC: u64 = 30;
+const DIAGNOSE_LISTENERS: [NetworkConnectionType; 5] = [
+    NetworkConnectionType::Tcp,
+    NetworkConnectionType::Tls,
+    NetworkConnectionType::WebSocket,
+    NetworkConnectionType::WebSockets,
+    NetworkConnectionType::Quic,
+];
+
+pub const DIAGNOSE_STATUS_PASS: &str = "pass";
+pub const DIAGNOSE_STATUS_FAIL: &str = "fail";
+pub const DIAGNOSE_STATUS_TIMEOUT: &str = "timeout";
+
+// Run all internal checks of the broker and aggregate them into a single report
+pub async fn self_diagnose_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    connector_manager: &Arc<ConnectorManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    request: Request<SelfDiagnoseRequest>,
+) -> Result<SelfDiagnoseReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let broker_id = broker_mqtt_conf().broker_id;
+    let check_timeout = if req.timeout_ms == 0 {
+        Duration::from_millis(DIAGNOSE_DEFAULT_TIMEOUT_MS)
+    } else {
+        Duration::from_millis(req.timeout_ms)
+    };
+
+    let checks = vec![
+        run_check("store_reachable", check_timeout, check_store(client_pool)).await,
+        run_check(
+            "listeners_bound",
+            check_timeout,
+            check_listeners(connection_manager),
+        )
+        .await,
+        run_check(
+            "cache_consistency",
+            check_timeout,
+            check_cache_consistency(client_pool, cache_manager),
+        )
+        .await,
+        run_check(
+            "clock_skew",
+            check_timeout,
+            check_clock_skew(cache_manager, client_pool, broker_id),
+        )
+        .await,
+        run_check(
+            "connector_health",
+            check_timeout,
+            check_connector_health(connector_manager),
+        )
+        .await,
+        run_check(
+            "subscription_sanity",
+            check_timeout,
+            check_subscription_sanity(cache_manager, subscribe_manager),
+        )
+        .await,
+    ];
+
+    let passed = checks
+        .iter()
+        .all(|check| check.status == DIAGNOSE_STATUS_PASS);
+
+    Ok(SelfDiagnoseReply {
+        broker_id,
+        diagnose_time: now_second(),
+        passed,
+        checks,
+    })
+}
+
+async fn run_check<F>(name: &str, check_timeout: Duration, check: F) -> SelfDiagnoseCheckRaw
+where
+    F: Future<Output = Result<String, MqttBrokerError>>,
+{
+    let start = Instant::now();
+    let (status, detail) = match timeout(check_timeout, check).await {
+        Ok(Ok(detail)) => (DIAGNOSE_STATUS_PASS, detail),
+        Ok(Err(e)) => (DIAGNOSE_STATUS_FAIL, e.to_string()),
+        Err(_) => (
+            DIAGNOSE_STATUS_TIMEOUT,
+            format!(
+                "check did not finish within {}ms",
+                check_timeout.as_millis()
+            ),
+        ),
+    };
+
+    SelfDiagnoseCheckRaw {
+        name: name.to_string(),
+        status: status.to_string(),
+        detail,
+        duration_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+async fn check_store(client_pool: &Arc<ClientPool>) -> Result<String, MqttBrokerError> {
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    cluster_storage.place_cluster_status().await?;
+    let nodes = cluster_storage.node_list().await?;
+    Ok(format!(
+        "placement center reachable, {} broker nodes registered",
+        nodes.len()
+    ))
+}
+
+// Every listener records its address in the listener registry of the connection manager once
+// its socket is bound
+async fn check_listeners(
+    connection_manager: &Arc<ConnectionManager>,
+) -> Result<String, MqttBrokerError> {
+    let unbound = unbound_listeners(connection_manager);
+    if !unbound.is_empty() {
+        return Err(MqttBrokerError::CommonError(format!(
+            "listeners not bound: {}",
+            unbound.join(",")
+        )));
+    }
+    Ok(format!("{} listeners bound", DIAGNOSE_LISTENERS.len()))
+}
+
+fn unbound_listeners(connection_manager: &Arc<ConnectionManager>) -> Vec<String> {
+    DIAGNOSE_LISTENERS
+        .iter()
+        .filter(|network_type| {
+            connection_manager
+                .listener_bound_addr(network_type)
+                .is_none()
+        })
+        .map(|network_type| network_type.to_string())
+        .collect()
+}
+
+async fn check_cache_consistency(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+) -> Result<String, MqttBrokerError> {
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    let store_user_num = auth_driver.read_all_user().await?.len();
+    let cache_user_num = cache_manager.user_info.len();
+
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let store_topic_num = topic_storage.all().await?.len();
+    let cache_topic_num = cache_manager.topic_info.len();
+
+    let detail = format!(
+        "users cache/store {}/{}, topics cache/store {}/{}",
+        cache_user_num, store_user_num, cache_topic_num, store_topic_num
+    );
+
+    if cache_user_num != store_user_num || cache_topic_num != store_topic_num {
+        return Err(MqttBrokerError::CommonError(detail));
+    }
+    Ok(detail)
+}
+
+// Compares the wall clock against every other broker. Without other brokers only jumps of the
+// wall clock since startup can be found, by comparing it against the monotonic clock.
+async fn check_clock_skew(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    local_broker_id: u64,
+) -> Result<String, MqttBrokerError> {
+    let peers: Vec<BrokerNode> = cache_manager
+        .node_list()
+        .into_iter()
+        .filter(|node| node.node_id != local_broker_id)
+        .collect();
+    if peers.is_empty() {
+        let expected = cache_manager.start_time + cache_manager.start_instant.elapsed().as_secs();
+        let skew = now_second().abs_diff(expected);
+        let detail = format!(
+            "no other broker to compare with, wall clock drifted {}s since broker start",
+            skew
+        );
+        if skew > DIAGNOSE_MAX_CLOCK_SKEW_SEC {
+            return Err(MqttBrokerError::CommonError(detail));
+        }
+        return Ok(detail);
+    }
+
+    let offsets = join_all(peers.iter().map(|node| {
+        let request = GetBrokerTimeRequest {
+            cluster_name: cache_manager.cluster_name.clone(),
+        };
+        let addrs = [node.node_inner_addr.clone()];
+        async move {
+            let send_ms = now_mills() as u64;
+            let reply = broker_mqtt_get_broker_time(client_pool, &addrs, request).await?;
+            let receive_ms = now_mills() as u64;
+            Ok::<u64, MqttBrokerError>(peer_clock_offset_ms(send_ms, receive_ms, reply.time_ms))
+        }
+    }))
+    .await;
+
+    let mut skewed = Vec::new();
+    let mut max_offset_ms = 0;
+    for (node, offset) in peers.iter().zip(offsets) {
+        let offset_ms = offset.map_err(|e| {
+            MqttBrokerError::CommonError(format!(
+                "failed to read the clock of broker {}, error message: {}",
+                node.node_id, e
+            ))
+        })?;
+        max_offset_ms = max_offset_ms.max(offset_ms);
+        if offset_ms > DIAGNOSE_MAX_CLOCK_SKEW_SEC * 1000 {
+            skewed.push(format!("{}:{}ms", node.node_id, offset_ms));
+        }
+    }
+
+    if !skewed.is_empty() {
+        return Err(MqttBrokerError::CommonError(format!(
+            "clock differs from brokers {}",
+            skewed.join(",")
+        )));
+    }
+    Ok(format!(
+        "clock within {}ms of {} other brokers",
+        max_offset_ms,
+        peers.len()
+    ))
+}
+
+// The peer read its clock somewhere within the round trip, so only the distance beyond half
+// the round trip from its midpoint is certain
+fn peer_clock_offset_ms(send_ms: u64, receive_ms: u64, peer_ms: u64) -> u64 {
+    let half_round_trip = receive_ms.saturating_sub(send_ms) / 2;
+    peer_ms
+        .abs_diff(send_ms + half_round_trip)
+        .saturating_sub(half_round_trip)
+}
+
+async fn check_connector_health(
+    connector_manager: &Arc<ConnectorManager>,
+) -> Result<String, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let now = now_second();
+
+    let mut total = 0;
+    let mut unhealthy = Vec::new();
+    for connector in connector_manager.get_all_connector() {
+        if connector.broker_id != Some(conf.broker_id) {
+            continue;
+        }
+        total += 1;
+
+        let name = connector.connector_name;
+        if connector_manager.get_connector_thread(&name).is_none() {
+            unhealthy.push(format!("{}(no thread)", name));
+            continue;
+        }
+
+        let last_heartbeat = connector_manager
+            .connector_heartbeat
+            .get(&name)
+            .map(|time| *time)
+            .unwrap_or(0);
+        if now.saturating_sub(last_heartbeat) > DIAGNOSE_CONNECTOR_HEARTBEAT_TIMEOUT_SEC {
+            unhealthy.push(format!("{}(heartbeat timeout)", name));
+        }
+    }
+
+    if !unhealthy.is_empty() {
+        return Err(MqttBrokerError::CommonError(format!(
+            "unhealthy connectors: {}",
+            unhealthy.join(",")
+        )));
+    }
+    Ok(format!("{} connectors healthy", total))
+}
+
+async fn check_subscription_sanity(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<String, MqttBrokerError> {
+    let mut problems = Vec::new();
+
+    for entry in subscribe_manager.subscribe_list.iter() {
+        // Offloaded sessions only live in the store, their subscribes are expected
+        if cache_manager.get_session_info(&entry.client_id).is_none()
+            && !cache_manager.is_session_offloaded(&entry.client_id)
+        {
+            problems.push(format!(
+                "subscribe {} of client {} has no session",
+                entry.path, entry.client_id
+            ));
+        }
+    }
+
+    for entry in subscribe_manager.exclusive_push.iter() {
+        if subscribe_manager
+            .get_subscribe(&entry.client_id, &entry.sub_path)
+            .is_none()
+        {
+            problems.push(format!(
+                "push {} has no matching subscribe {}",
+                entry.key(),
+                entry.sub_path
+            ));
+        }
+
+        if !subscribe_manager
+            .exclusive_push_thread
+            .contains_key(entry.key())
+        {
+            problems.push(format!("push {} has no push thread", entry.key()));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(MqttBrokerError::CommonError(problems.join("; ")));
+    }
+    Ok(format!(
+        "{} subscribes, {} exclusive pushes consistent",
+        subscribe_manager.subscribe_list.len(),
+        subscribe_manager.exclusive_push.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+
+    use super::{
+        check_subscription_sanity, peer_clock_offset_ms, run_check, unbound_listeners,
+        DIAGNOSE_LISTENERS, DIAGNOSE_STATUS_FAIL, DIAGNOSE_STATUS_PASS, DIAGNOSE_STATUS_TIMEOUT,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::server::connection::NetworkConnectionType;
+    use crate::server::connection_manager::ConnectionManager;
+    use crate::subscribe::manager::SubscribeManager;
+
+    #[test]
+    fn peer_clock_offset_test() {
+        // a peer that read its clock within the round trip is not skewed
+        assert_eq!(peer_clock_offset_ms(1000, 1100, 1000), 0);
+        assert_eq!(peer_clock_offset_ms(1000, 1100, 1100), 0);
+        assert_eq!(peer_clock_offset_ms(1000, 1100, 1200), 100);
+        assert_eq!(peer_clock_offset_ms(1000, 1100, 8000), 6900);
+        assert_eq!(peer_clock_offset_ms(10000, 10000, 4000), 6000);
+    }
+
+    #[test]
+    fn unbound_listeners_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager));
+        assert_eq!(
+            unbound_listeners(&connection_manager).len(),
+            DIAGNOSE_LISTENERS.len()
+        );
+
+        for network_type in DIAGNOSE_LISTENERS.iter() {
+            if *network_type != NetworkConnectionType::Quic {
+                connection_manager
+                    .record_listener_bound(network_type, "127.0.0.1:1883".parse().unwrap());
+            }
+        }
+        assert_eq!(
+            unbound_listeners(&connection_manager),
+            vec![NetworkConnectionType::Quic.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn run_check_test() {
+        let res = run_check("ok", Duration::from_millis(100), async {
+            Ok("fine".to_string())
+        })
+        .await;
+        assert_eq!(res.status, DIAGNOSE_STATUS_PASS);
+        assert_eq!(res.detail, "fine");
+
+        let res = run_check("slow", Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok("late".to_string())
+        })
+        .await;
+        assert_eq!(res.status, DIAGNOSE_STATUS_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn check_subscription_sanity_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: "/a/b".to_string(),
+            ..Default::default()
+        });
+
+        let res = run_check(
+            "subscription_sanity",
+            Duration::from_millis(100),
+            check_subscription_sanity(&cache_manager, &subscribe_manager),
+        )
+        .await;
+        assert_eq!(res.status, DIAGNOSE_STATUS_FAIL);
+
+        cache_manager.add_session(
+            "c1",
+            &MqttSession {
+                client_id: "c1".to_string(),
+                ..Default::default()
+            },
+        );
+        let res = run_check(
+            "subscription_sanity",
+            Duration::from_millis(100),
+            check_subscription_sanity(&cache_manager, &subscribe_manager),
+        )
+        .await;
+        assert_eq!(res.status, DIAGNOSE_STATUS_PASS);
+    }
+}
@@ -15,31 +15,58 @@
 pub mod acl;
 pub mod blacklist;
 pub mod client;
+pub mod client_group;
 pub mod cluster;
 pub mod connector;
+pub mod delay_message;
+pub mod diagnose;
 pub mod observability;
 pub mod query;
 pub mod schema;
 pub mod session;
+pub mod snapshot;
 pub mod subscribe;
 pub mod topic;
 pub mod user;
 
 use crate::handler::cache::CacheManager;
-use crate::handler::flapping_detect::enable_flapping_detect;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
+use crate::handler::flapping_detect::{
+    enable_flapping_detect, list_flapping_detect_bans, unban_flapping_detect_client,
+};
+use crate::observability::metrics::resource::{
+    node_resource_source, NodeResourceSnapshot, NodeResourceSource,
+};
+use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::manager::SubscribeManager;
 use crate::{handler::error::MqttBrokerError, storage::cluster::ClusterStorage};
 
 use common_base::tools::serialize_value;
 use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::ConnectionLimit;
+use futures::future::{self, join_all};
+use futures::stream::{self, Stream, StreamExt};
+use grpc_clients::mqtt::admin::call::mqtt_broker_list_connection;
+use grpc_clients::mqtt::inner::call::broker_mqtt_get_node_resource;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::placement::node::BrokerNode;
 use protocol::broker_mqtt::broker_mqtt_admin::{
     BrokerNodeRaw, ClusterStatusReply, EnableFlappingDetectReply, EnableFlappingDetectRequest,
-    ListConnectionRaw, ListConnectionReply,
+    FlappingDetectBanRaw, ListConnectionRaw, ListConnectionReply, ListConnectionRequest,
+    ListFlappingDetectBanReply, SetConnectionLimitReply, SetConnectionLimitRequest,
+    StreamConnectionsReply, StreamConnectionsRequest, UnbanFlappingDetectClientReply,
+    UnbanFlappingDetectClientRequest,
 };
+use protocol::broker_mqtt::broker_mqtt_inner::GetNodeResourceRequest;
+use std::fmt::Display;
+use std::future::Future;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
+use tracing::warn;
+
+const STREAM_CONNECTIONS_DEFAULT_CHUNK_SIZE: u32 = 500;
+const STREAM_CONNECTIONS_MAX_CHUNK_SIZE: u32 = 10000;
 
 pub async fn cluster_status_by_req(
     client_pool: &Arc<ClientPool>,
@@ -57,13 +84,18 @@ pub async fn cluster_status_by_req(
     }
 
     let placement_status = cluster_storage.place_cluster_status().await?;
-    let node_list = cache_manager.node_list();
-    let resp_node_list: Vec<BrokerNodeRaw> =
-        node_list.iter().map(|node| node.clone().into()).collect();
+    let resp_node_list = node_list_with_resource(
+        client_pool,
+        cache_manager.node_list(),
+        node_resource_source(),
+        config.broker_id,
+        cache_manager.is_node_draining(),
+    )
+    .await;
     let reply = ClusterStatusReply {
         cluster_name: config.cluster_name.clone(),
-        message_in_rate: 10,
-        message_out_rate: 3,
+        message_in_rate: connection_manager.message_rate.message_in_rate() as u32,
+        message_out_rate: connection_manager.message_rate.message_out_rate() as u32,
         connection_num: connection_manager.connections.len() as u32,
         session_num: cache_manager.session_info.len() as u32,
         subscribe_num: subscribe_manager.subscribe_list.len() as u32,
@@ -82,11 +114,58 @@ pub async fn cluster_status_by_req(
         websocket_connection_num: connection_manager.websocket_write_list.len() as u32,
         quic_connection_num: connection_manager.quic_write_list.len() as u32,
     };
-    let _ = subscribe_manager.snapshot_info();
 
     Ok(reply)
 }
 
+// The resource usage and draining state of the local node are read directly, the other nodes are
+// asked through the inner service. A node that cannot be reached reports zeros.
+async fn node_list_with_resource(
+    client_pool: &Arc<ClientPool>,
+    nodes: Vec<BrokerNode>,
+    local_source: &dyn NodeResourceSource,
+    local_broker_id: u64,
+    local_draining: bool,
+) -> Vec<BrokerNodeRaw> {
+    let states = join_all(nodes.iter().map(|node| async move {
+        if node.node_id == local_broker_id {
+            return (local_source.snapshot(), local_draining);
+        }
+        let addrs = [node.node_inner_addr.clone()];
+        match broker_mqtt_get_node_resource(client_pool, &addrs, GetNodeResourceRequest {}).await {
+            Ok(reply) => (
+                NodeResourceSnapshot {
+                    cpu_percent: reply.cpu_percent,
+                    memory_bytes: reply.memory_bytes,
+                    open_fd_num: reply.open_fd_num,
+                },
+                reply.draining,
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to get the resource usage of node {}, error message: {}",
+                    node.node_id, e
+                );
+                (NodeResourceSnapshot::default(), false)
+            }
+        }
+    }))
+    .await;
+
+    nodes
+        .into_iter()
+        .zip(states)
+        .map(|(node, (snapshot, draining))| {
+            let mut raw: BrokerNodeRaw = node.into();
+            raw.cpu_percent = snapshot.cpu_percent;
+            raw.memory_bytes = snapshot.memory_bytes;
+            raw.open_fd_num = snapshot.open_fd_num;
+            raw.draining = draining;
+            raw
+        })
+        .collect()
+}
+
 pub async fn enable_flapping_detect_by_req(
     client_pool: &Arc<ClientPool>,
     cache_manager: &Arc<CacheManager>,
@@ -102,28 +181,879 @@ pub async fn enable_flapping_detect_by_req(
     }
 }
 
+// List the clients banned by flapping detect on this broker, ordered by ban expiry
+pub fn list_flapping_detect_ban_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> ListFlappingDetectBanReply {
+    let bans = list_flapping_detect_bans(cache_manager)
+        .into_iter()
+        .map(|ban| FlappingDetectBanRaw {
+            client_id: ban.client_id,
+            ban_start: ban.ban_start,
+            ban_expiry: ban.ban_expiry,
+            connection_num: ban.connection_num,
+        })
+        .collect();
+    ListFlappingDetectBanReply { bans }
+}
+
+pub fn unban_flapping_detect_client_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<UnbanFlappingDetectClientRequest>,
+) -> Result<UnbanFlappingDetectClientReply, MqttBrokerError> {
+    let req = request.into_inner();
+    unban_flapping_detect_client(cache_manager, &req.client_id)?;
+    Ok(UnbanFlappingDetectClientReply {})
+}
+
+// Limit the connections a single source IP may hold on each broker, 0 removes the limit
+pub async fn set_connection_limit_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<SetConnectionLimitRequest>,
+) -> Result<SetConnectionLimitReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let config = ConnectionLimit {
+        max_connections_per_ip: req.max_connections_per_ip,
+    };
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::ConnectionLimit,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_connection_limit_config(config.clone());
+    Ok(SetConnectionLimitReply {
+        max_connections_per_ip: config.max_connections_per_ip,
+    })
+}
+
+// Connections can be narrowed down to an exact client id and to source IPs starting with a prefix.
+// With all_nodes the connections of every broker in the cluster are listed, nodes that do not
+// respond are reported in failed_node_ids.
 pub async fn list_connection_by_req(
+    client_pool: &Arc<ClientPool>,
     connection_manager: &Arc<ConnectionManager>,
     cache_manager: &Arc<CacheManager>,
+    request: Request<ListConnectionRequest>,
 ) -> Result<Response<ListConnectionReply>, Status> {
-    let mut reply = ListConnectionReply::default();
+    let req = request.into_inner();
+    let local = list_local_connection(connection_manager, cache_manager, &req)?;
+    if !req.all_nodes {
+        return Ok(Response::new(ListConnectionReply {
+            list_connection_raw: local,
+            ..Default::default()
+        }));
+    }
+
+    // Peers only list their own connections, otherwise every node would fan out again
+    let peer_request = ListConnectionRequest {
+        all_nodes: false,
+        ..req
+    };
+    let (list_connection_raw, failed_node_ids) = merge_node_connections(
+        cache_manager.node_list(),
+        broker_mqtt_conf().broker_id,
+        local,
+        |node| {
+            let request = peer_request.clone();
+            async move {
+                let addrs = [node.node_inner_addr];
+                mqtt_broker_list_connection(client_pool, &addrs, request)
+                    .await
+                    .map(|reply| reply.list_connection_raw)
+            }
+        },
+    )
+    .await;
+    Ok(Response::new(ListConnectionReply {
+        list_connection_raw,
+        failed_node_ids,
+    }))
+}
+
+// Tags every connection with the node it lives on and collects the ids of the peers whose
+// connections could not be fetched
+async fn merge_node_connections<F, Fut, E>(
+    nodes: Vec<BrokerNode>,
+    local_broker_id: u64,
+    local: Vec<ListConnectionRaw>,
+    fetch: F,
+) -> (Vec<ListConnectionRaw>, Vec<u64>)
+where
+    F: Fn(BrokerNode) -> Fut,
+    Fut: Future<Output = Result<Vec<ListConnectionRaw>, E>>,
+    E: Display,
+{
+    let with_node_id = |raws: Vec<ListConnectionRaw>, node_id: u64| {
+        raws.into_iter().map(move |mut raw| {
+            raw.node_id = node_id;
+            raw
+        })
+    };
+
+    let mut list_connection_raw: Vec<ListConnectionRaw> =
+        with_node_id(local, local_broker_id).collect();
+    let peers: Vec<BrokerNode> = nodes
+        .into_iter()
+        .filter(|node| node.node_id != local_broker_id)
+        .collect();
+    let results = join_all(peers.iter().map(|node| fetch(node.clone()))).await;
+
+    let mut failed_node_ids = Vec::new();
+    for (node, result) in peers.iter().zip(results) {
+        match result {
+            Ok(raws) => list_connection_raw.extend(with_node_id(raws, node.node_id)),
+            Err(e) => {
+                warn!(
+                    "Failed to list the connections of node {}, error message: {}",
+                    node.node_id, e
+                );
+                failed_node_ids.push(node.node_id);
+            }
+        }
+    }
+    (list_connection_raw, failed_node_ids)
+}
+
+fn list_local_connection(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    req: &ListConnectionRequest,
+) -> Result<Vec<ListConnectionRaw>, Status> {
     let mut list_connection_raw: Vec<ListConnectionRaw> = Vec::new();
-    for (key, value) in connection_manager.list_connect() {
-        if let Some(mqtt_value) = cache_manager.get_connection(key) {
-            let mqtt_info = serialize_value(&mqtt_value)?;
-            let raw = ListConnectionRaw {
-                connection_id: value.connection_id,
-                connection_type: value.connection_type.to_string(),
-                protocol: match value.protocol {
-                    Some(protocol) => protocol.into(),
-                    None => "None".to_string(),
-                },
-                source_addr: value.addr.to_string(),
-                info: mqtt_info,
-            };
+    for (_, value) in connection_manager.list_connect() {
+        if let Some(raw) = build_connection_raw(connection_manager, cache_manager, &value, req)? {
+            list_connection_raw.push(raw);
+        }
+    }
+    Ok(list_connection_raw)
+}
+
+// Stream the connections of this broker in chunks of chunk_size. Only the connection ids are
+// collected up front, the rows of a chunk are built when the client asks for it, so memory
+// stays bounded however many connections the broker holds. Connections closed while the
+// stream is consumed are skipped.
+pub fn stream_connections_by_req(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<StreamConnectionsRequest>,
+) -> Result<impl Stream<Item = Result<StreamConnectionsReply, Status>> + Send + 'static, Status> {
+    let req = request.into_inner();
+    if req.chunk_size > STREAM_CONNECTIONS_MAX_CHUNK_SIZE {
+        return Err(Status::invalid_argument(format!(
+            "chunk_size must not exceed {}",
+            STREAM_CONNECTIONS_MAX_CHUNK_SIZE
+        )));
+    }
+    let chunk_size = if req.chunk_size == 0 {
+        STREAM_CONNECTIONS_DEFAULT_CHUNK_SIZE
+    } else {
+        req.chunk_size
+    };
+    let filter = ListConnectionRequest {
+        client_id: req.client_id,
+        source_ip_prefix: req.source_ip_prefix,
+        structured: req.structured,
+        ..Default::default()
+    };
+
+    let connect_ids: Vec<u64> = connection_manager
+        .connections
+        .iter()
+        .map(|entry| *entry.key())
+        .collect();
+
+    let connection_manager = connection_manager.clone();
+    let cache_manager = cache_manager.clone();
+    let chunks = stream::iter(connect_ids)
+        .chunks(chunk_size as usize)
+        .map(move |connect_ids| {
+            build_connections_chunk(&connection_manager, &cache_manager, connect_ids, &filter)
+        })
+        // A chunk whose connections all closed or were filtered out is not sent
+        .filter(|reply| {
+            future::ready(!matches!(reply, Ok(reply) if reply.list_connection_raw.is_empty()))
+        });
+    Ok(chunks)
+}
+
+fn build_connections_chunk(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    connect_ids: Vec<u64>,
+    filter: &ListConnectionRequest,
+) -> Result<StreamConnectionsReply, Status> {
+    let mut list_connection_raw = Vec::with_capacity(connect_ids.len());
+    for connect_id in connect_ids {
+        let Some(value) = connection_manager.get_connect(connect_id) else {
+            continue;
+        };
+        if let Some(raw) = build_connection_raw(connection_manager, cache_manager, &value, filter)?
+        {
             list_connection_raw.push(raw);
         }
     }
-    reply.list_connection_raw = list_connection_raw;
-    Ok(Response::new(reply))
+    Ok(StreamConnectionsReply {
+        list_connection_raw,
+    })
+}
+
+// None when the connection has not been registered yet or does not match the filters
+fn build_connection_raw(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    value: &NetworkConnection,
+    req: &ListConnectionRequest,
+) -> Result<Option<ListConnectionRaw>, Status> {
+    let Some(mqtt_value) = cache_manager.get_connection(value.connection_id) else {
+        return Ok(None);
+    };
+    let client_id = req
+        .client_id
+        .as_ref()
+        .filter(|client_id| !client_id.is_empty());
+    if client_id.is_some_and(|client_id| mqtt_value.client_id != *client_id) {
+        return Ok(None);
+    }
+    let source_ip_prefix = req
+        .source_ip_prefix
+        .as_ref()
+        .filter(|prefix| !prefix.is_empty());
+    if source_ip_prefix
+        .is_some_and(|prefix| !value.addr.ip().to_string().starts_with(prefix.as_str()))
+    {
+        return Ok(None);
+    }
+    let mut raw = ListConnectionRaw {
+        connection_id: value.connection_id,
+        connection_type: value.connection_type.to_string(),
+        // The protocol level byte of the CONNECT, 0 before CONNECT has completed
+        mqtt_protocol_version: value
+            .protocol
+            .clone()
+            .map_or(0, |protocol| u8::from(protocol) as u32),
+        protocol: match value.protocol.clone() {
+            Some(protocol) => protocol.into(),
+            None => "None".to_string(),
+        },
+        source_addr: value.addr.to_string(),
+        original_source_addr: value.original_addr().to_string(),
+        source_ip_connection_num: connection_manager.get_ip_connection_num(&value.addr.ip()),
+        bytes_sent: value.get_bytes_sent(),
+        bytes_received: value.get_bytes_received(),
+        messages_sent: value.get_messages_sent(),
+        messages_received: value.get_messages_received(),
+        connected_duration_ms: value.connected_duration_ms(),
+        ..Default::default()
+    };
+    if let Some(tls_info) = &value.tls_info {
+        raw.tls_version = tls_info.tls_version.clone();
+        raw.cipher_suite = tls_info.cipher_suite.clone();
+        raw.client_cert_subject = tls_info.client_cert_subject.clone();
+    }
+    if let Some(subprotocol) = &value.websocket_subprotocol {
+        raw.websocket_subprotocol = subprotocol.clone();
+    }
+    // The typed fields replace the serialized info, which is kept as the default for
+    // older clients
+    if req.structured {
+        raw.client_id = mqtt_value.client_id.clone();
+        raw.username = mqtt_value.login_user.clone();
+        raw.keep_alive = mqtt_value.keep_alive as u32;
+        raw.connected_at = mqtt_value.create_time;
+        // clean session is true when session_expiry is 0 (MQTT 5.0)
+        raw.clean_session = cache_manager
+            .get_session_info(&mqtt_value.client_id)
+            .is_some_and(|session| session.session_expiry == 0);
+    } else {
+        raw.info = serialize_value(&mqtt_value)?;
+    }
+    Ok(Some(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        list_connection_by_req, merge_node_connections, node_list_with_resource,
+        stream_connections_by_req,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::observability::metrics::resource::{NodeResourceSnapshot, NodeResourceSource};
+    use crate::server::connection::{NetworkConnection, NetworkConnectionType, TlsConnectionInfo};
+    use crate::server::connection_manager::ConnectionManager;
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::MQTTConnection;
+    use metadata_struct::mqtt::session::MqttSession;
+    use metadata_struct::placement::node::BrokerNode;
+    use protocol::broker_mqtt::broker_mqtt_admin::{
+        ListConnectionRaw, ListConnectionRequest, StreamConnectionsRequest,
+    };
+    use protocol::mqtt::codec::{calc_mqtt_packet_size, MqttPacketWrapper};
+    use protocol::mqtt::common::{MqttPacket, MqttProtocol, PingReq, Publish};
+    use std::sync::Arc;
+    use tonic::Request;
+
+    struct MockResourceSource;
+
+    impl NodeResourceSource for MockResourceSource {
+        fn snapshot(&self) -> NodeResourceSnapshot {
+            NodeResourceSnapshot {
+                cpu_percent: 12.5,
+                memory_bytes: 64 * 1024 * 1024,
+                open_fd_num: 42,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn node_list_with_resource_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let nodes = vec![BrokerNode {
+            node_id: 1,
+            node_ip: "127.0.0.1".to_string(),
+            ..Default::default()
+        }];
+
+        let raws =
+            node_list_with_resource(&client_pool, nodes.clone(), &MockResourceSource, 1, false)
+                .await;
+        assert_eq!(raws.len(), 1);
+        assert_eq!(raws[0].node_id, 1);
+        assert_eq!(raws[0].cpu_percent, 12.5);
+        assert_eq!(raws[0].memory_bytes, 64 * 1024 * 1024);
+        assert_eq!(raws[0].open_fd_num, 42);
+        assert!(!raws[0].draining);
+
+        let raws = node_list_with_resource(&client_pool, nodes, &MockResourceSource, 1, true).await;
+        assert!(raws[0].draining);
+    }
+
+    #[tokio::test]
+    async fn merge_node_connections_test() {
+        let nodes: Vec<BrokerNode> = (1..=3)
+            .map(|node_id| BrokerNode {
+                node_id,
+                node_inner_addr: format!("127.0.0.{}:9981", node_id),
+                ..Default::default()
+            })
+            .collect();
+        let raw = |source_addr: &str| ListConnectionRaw {
+            source_addr: source_addr.to_string(),
+            ..Default::default()
+        };
+
+        // Node 2 answers with two connections and node 3 does not respond
+        let (raws, failed_node_ids) =
+            merge_node_connections(nodes, 1, vec![raw("10.0.0.1:1883")], |node| async move {
+                match node.node_id {
+                    2 => Ok(vec![raw("10.0.0.2:1883"), raw("10.0.0.3:1883")]),
+                    _ => Err(format!("{} unreachable", node.node_inner_addr)),
+                }
+            })
+            .await;
+
+        let mut located: Vec<(u64, String)> = raws
+            .into_iter()
+            .map(|raw| (raw.node_id, raw.source_addr))
+            .collect();
+        located.sort();
+        assert_eq!(
+            located,
+            vec![
+                (1, "10.0.0.1:1883".to_string()),
+                (2, "10.0.0.2:1883".to_string()),
+                (2, "10.0.0.3:1883".to_string()),
+            ]
+        );
+        assert_eq!(failed_node_ids, vec![3]);
+    }
+
+    #[tokio::test]
+    async fn stream_connections_by_req_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        let mut connect_ids = Vec::new();
+        for i in 0..7 {
+            let addr = format!("10.0.{}.5:1883", i % 2);
+            let connection =
+                NetworkConnection::new(NetworkConnectionType::Tcp, addr.parse().unwrap(), None);
+            let connect_id = connection_manager.add_connection(connection);
+            cache_manager.add_connection(
+                connect_id,
+                MQTTConnection {
+                    connect_id,
+                    client_id: format!("c{}", i),
+                    ..Default::default()
+                },
+            );
+            connect_ids.push(connect_id);
+        }
+        // Not registered in the cache until CONNECT has completed, so it is never listed
+        connection_manager.add_connection(NetworkConnection::new(
+            NetworkConnectionType::Tcp,
+            "10.0.0.9:1883".parse().unwrap(),
+            None,
+        ));
+
+        let stream = |chunk_size: u32, source_ip_prefix: Option<&str>| {
+            let request = Request::new(StreamConnectionsRequest {
+                chunk_size,
+                source_ip_prefix: source_ip_prefix.map(|s| s.to_string()),
+                structured: true,
+                ..Default::default()
+            });
+            let connection_manager = connection_manager.clone();
+            let cache_manager = cache_manager.clone();
+            async move {
+                stream_connections_by_req(&connection_manager, &cache_manager, request)
+                    .unwrap()
+                    .map(|reply| reply.unwrap().list_connection_raw)
+                    .collect::<Vec<_>>()
+                    .await
+            }
+        };
+
+        let chunks = stream(3, None).await;
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 3));
+        let mut streamed: Vec<u64> = chunks
+            .into_iter()
+            .flatten()
+            .map(|raw| {
+                assert!(raw.client_id.starts_with('c'));
+                raw.connection_id
+            })
+            .collect();
+        streamed.sort();
+        assert_eq!(streamed, connect_ids);
+
+        // The default chunk size holds every connection in a single message
+        let chunks = stream(0, None).await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].len(), 7);
+
+        let chunks = stream(2, Some("10.0.1.")).await;
+        assert_eq!(chunks.into_iter().flatten().count(), 3);
+        assert!(stream(2, Some("192.168.")).await.is_empty());
+
+        let request = Request::new(StreamConnectionsRequest {
+            chunk_size: 10001,
+            ..Default::default()
+        });
+        assert!(stream_connections_by_req(&connection_manager, &cache_manager, request).is_err());
+    }
+
+    #[tokio::test]
+    async fn list_connection_by_req_filter_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        for (client_id, addr) in [
+            ("c1", "10.0.1.5:1883"),
+            ("c2", "10.0.1.6:1883"),
+            ("c3", "192.168.0.7:1883"),
+        ] {
+            let connection =
+                NetworkConnection::new(NetworkConnectionType::Tcp, addr.parse().unwrap(), None);
+            let connect_id = connection_manager.add_connection(connection);
+            cache_manager.add_session(
+                client_id,
+                &MqttSession {
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+            cache_manager.add_connection(
+                connect_id,
+                MQTTConnection {
+                    connect_id,
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let list = |client_id: Option<&str>, source_ip_prefix: Option<&str>| {
+            let request = Request::new(ListConnectionRequest {
+                client_id: client_id.map(|s| s.to_string()),
+                source_ip_prefix: source_ip_prefix.map(|s| s.to_string()),
+                ..Default::default()
+            });
+            let client_pool = client_pool.clone();
+            let connection_manager = connection_manager.clone();
+            let cache_manager = cache_manager.clone();
+            async move {
+                let reply = list_connection_by_req(
+                    &client_pool,
+                    &connection_manager,
+                    &cache_manager,
+                    request,
+                )
+                .await
+                .unwrap()
+                .into_inner();
+                let mut addrs: Vec<String> = reply
+                    .list_connection_raw
+                    .into_iter()
+                    .map(|raw| raw.source_addr)
+                    .collect();
+                addrs.sort();
+                addrs
+            }
+        };
+
+        assert_eq!(list(None, None).await.len(), 3);
+        assert_eq!(list(Some(""), Some("")).await.len(), 3);
+        assert_eq!(list(Some("c2"), None).await, vec!["10.0.1.6:1883"]);
+        assert!(list(Some("c"), None).await.is_empty());
+        assert_eq!(
+            list(None, Some("10.0.1.")).await,
+            vec!["10.0.1.5:1883", "10.0.1.6:1883"]
+        );
+        assert_eq!(
+            list(Some("c3"), Some("192.168")).await,
+            vec!["192.168.0.7:1883"]
+        );
+        assert!(list(Some("c3"), Some("10.")).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_connection_by_req_protocol_version_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        for (client_id, protocol) in [
+            ("c3", Some(MqttProtocol::Mqtt3)),
+            ("c4", Some(MqttProtocol::Mqtt4)),
+            ("c5", Some(MqttProtocol::Mqtt5)),
+            ("c0", None),
+        ] {
+            let mut connection = NetworkConnection::new(
+                NetworkConnectionType::Tcp,
+                "127.0.0.1:1883".parse().unwrap(),
+                None,
+            );
+            connection.protocol = protocol;
+            let connect_id = connection_manager.add_connection(connection);
+            cache_manager.add_session(
+                client_id,
+                &MqttSession {
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+            cache_manager.add_connection(
+                connect_id,
+                MQTTConnection {
+                    connect_id,
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        for (client_id, version, protocol) in [
+            ("c3", 3, "MQTT3"),
+            ("c4", 4, "MQTT4"),
+            ("c5", 5, "MQTT5"),
+            ("c0", 0, "None"),
+        ] {
+            let request = Request::new(ListConnectionRequest {
+                client_id: Some(client_id.to_string()),
+                source_ip_prefix: None,
+                ..Default::default()
+            });
+            let reply =
+                list_connection_by_req(&client_pool, &connection_manager, &cache_manager, request)
+                    .await
+                    .unwrap()
+                    .into_inner();
+            assert_eq!(reply.list_connection_raw.len(), 1);
+            let raw = &reply.list_connection_raw[0];
+            assert_eq!(raw.mqtt_protocol_version, version);
+            assert_eq!(raw.protocol, protocol);
+        }
+    }
+
+    #[tokio::test]
+    async fn list_connection_by_req_traffic_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        let mut connection = NetworkConnection::new(
+            NetworkConnectionType::Tcp,
+            "127.0.0.1:1883".parse().unwrap(),
+            None,
+        );
+        connection.set_protocol(MqttProtocol::Mqtt4);
+        connection.create_time_ms -= 5000;
+        let connect_id = connection_manager.add_connection(connection);
+        cache_manager.add_connection(
+            connect_id,
+            MQTTConnection {
+                connect_id,
+                client_id: "c1".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let connection = connection_manager.get_connect(connect_id).unwrap();
+        let publish = MqttPacket::Publish(
+            Publish {
+                topic: Bytes::from("t1"),
+                payload: Bytes::from("hello"),
+                ..Default::default()
+            },
+            None,
+        );
+        let publish_size = calc_mqtt_packet_size(MqttPacketWrapper {
+            protocol_version: 4,
+            packet: publish.clone(),
+        }) as u64;
+        let ping = MqttPacket::PingReq(PingReq);
+        let ping_size = calc_mqtt_packet_size(MqttPacketWrapper {
+            protocol_version: 4,
+            packet: ping.clone(),
+        }) as u64;
+        for _ in 0..3 {
+            connection.record_packet_received(&publish, publish_size as usize);
+        }
+        connection.record_packet_received(&ping, ping_size as usize);
+        connection.record_bytes_sent(120);
+        connection.record_message_sent();
+
+        let request = Request::new(ListConnectionRequest::default());
+        let reply =
+            list_connection_by_req(&client_pool, &connection_manager, &cache_manager, request)
+                .await
+                .unwrap()
+                .into_inner();
+        assert_eq!(reply.list_connection_raw.len(), 1);
+        let raw = &reply.list_connection_raw[0];
+        assert_eq!(raw.bytes_received, 3 * publish_size + ping_size);
+        assert_eq!(raw.messages_received, 3);
+        assert_eq!(raw.bytes_sent, 120);
+        assert_eq!(raw.messages_sent, 1);
+        assert!(raw.connected_duration_ms >= 5000);
+    }
+
+    #[tokio::test]
+    async fn list_connection_by_req_structured_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        for (client_id, session_expiry) in [("c1", 0), ("c2", 3600)] {
+            let connection = NetworkConnection::new(
+                NetworkConnectionType::Tcp,
+                "127.0.0.1:1883".parse().unwrap(),
+                None,
+            );
+            let connect_id = connection_manager.add_connection(connection);
+            cache_manager.add_session(
+                client_id,
+                &MqttSession {
+                    client_id: client_id.to_string(),
+                    session_expiry,
+                    ..Default::default()
+                },
+            );
+            cache_manager.add_connection(
+                connect_id,
+                MQTTConnection {
+                    connect_id,
+                    client_id: client_id.to_string(),
+                    login_user: format!("user-{}", client_id),
+                    keep_alive: 30,
+                    create_time: 1700000000,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let list = |client_id: &str, structured: bool| {
+            let request = Request::new(ListConnectionRequest {
+                client_id: Some(client_id.to_string()),
+                structured,
+                ..Default::default()
+            });
+            let client_pool = client_pool.clone();
+            let connection_manager = connection_manager.clone();
+            let cache_manager = cache_manager.clone();
+            async move {
+                let mut reply = list_connection_by_req(
+                    &client_pool,
+                    &connection_manager,
+                    &cache_manager,
+                    request,
+                )
+                .await
+                .unwrap()
+                .into_inner();
+                assert_eq!(reply.list_connection_raw.len(), 1);
+                reply.list_connection_raw.remove(0)
+            }
+        };
+
+        for (client_id, clean_session) in [("c1", true), ("c2", false)] {
+            let raw = list(client_id, true).await;
+            assert!(raw.info.is_empty());
+            assert_eq!(raw.client_id, client_id);
+            assert_eq!(raw.username, format!("user-{}", client_id));
+            assert_eq!(raw.keep_alive, 30);
+            assert_eq!(raw.connected_at, 1700000000);
+            assert_eq!(raw.clean_session, clean_session);
+        }
+
+        // Without the flag only the serialized form is returned
+        let raw = list("c1", false).await;
+        assert!(raw.info.contains("\"login_user\":\"user-c1\""));
+        assert!(raw.client_id.is_empty());
+        assert_eq!(raw.keep_alive, 0);
+    }
+
+    #[tokio::test]
+    async fn list_connection_by_req_tls_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+
+        let mut tls_connection = NetworkConnection::new(
+            NetworkConnectionType::Tls,
+            "127.0.0.1:8883".parse().unwrap(),
+            None,
+        );
+        tls_connection.set_tls_info(TlsConnectionInfo {
+            tls_version: "TLSv1_3".to_string(),
+            cipher_suite: "TLS13_AES_256_GCM_SHA384".to_string(),
+            client_cert_subject: "CN=c1, O=RobustMQ".to_string(),
+            client_cert_cn: "c1".to_string(),
+        });
+        let tcp_connection = NetworkConnection::new(
+            NetworkConnectionType::Tcp,
+            "127.0.0.1:1883".parse().unwrap(),
+            None,
+        );
+        let mut websocket_connection = NetworkConnection::new(
+            NetworkConnectionType::WebSocket,
+            "127.0.0.1:8083".parse().unwrap(),
+            None,
+        );
+        websocket_connection.set_websocket_subprotocol("mqttv3.1".to_string());
+        for (client_id, connection) in [
+            ("c1", tls_connection),
+            ("c2", tcp_connection),
+            ("c3", websocket_connection),
+        ] {
+            let connect_id = connection_manager.add_connection(connection);
+            cache_manager.add_connection(
+                connect_id,
+                MQTTConnection {
+                    connect_id,
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let list = |client_id: &str| {
+            let request = Request::new(ListConnectionRequest {
+                client_id: Some(client_id.to_string()),
+                ..Default::default()
+            });
+            let client_pool = client_pool.clone();
+            let connection_manager = connection_manager.clone();
+            let cache_manager = cache_manager.clone();
+            async move {
+                let mut reply = list_connection_by_req(
+                    &client_pool,
+                    &connection_manager,
+                    &cache_manager,
+                    request,
+                )
+                .await
+                .unwrap()
+                .into_inner();
+                assert_eq!(reply.list_connection_raw.len(), 1);
+                reply.list_connection_raw.remove(0)
+            }
+        };
+
+        let raw = list("c1").await;
+        assert_eq!(raw.connection_type, "Tls");
+        assert_eq!(raw.tls_version, "TLSv1_3");
+        assert_eq!(raw.cipher_suite, "TLS13_AES_256_GCM_SHA384");
+        assert_eq!(raw.client_cert_subject, "CN=c1, O=RobustMQ");
+
+        let raw = list("c2").await;
+        assert!(raw.tls_version.is_empty());
+        assert!(raw.cipher_suite.is_empty());
+        assert!(raw.client_cert_subject.is_empty());
+        assert!(raw.websocket_subprotocol.is_empty());
+
+        let raw = list("c3").await;
+        assert_eq!(raw.connection_type, "Websocket");
+        assert_eq!(raw.websocket_subprotocol, "mqttv3.1");
+        assert!(raw.tls_version.is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_connection_by_req_quic_migration_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool.clone(), "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+
+        let connect_id = connection_manager.add_connection(NetworkConnection::new(
+            NetworkConnectionType::Quic,
+            "10.0.0.1:50001".parse().unwrap(),
+            None,
+        ));
+        cache_manager.add_connection(
+            connect_id,
+            MQTTConnection {
+                connect_id,
+                client_id: "c1".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let list = || {
+            let client_pool = client_pool.clone();
+            let connection_manager = connection_manager.clone();
+            let cache_manager = cache_manager.clone();
+            async move {
+                list_connection_by_req(
+                    &client_pool,
+                    &connection_manager,
+                    &cache_manager,
+                    Request::new(ListConnectionRequest::default()),
+                )
+                .await
+                .unwrap()
+                .into_inner()
+                .list_connection_raw
+            }
+        };
+
+        let raws = list().await;
+        assert_eq!(raws.len(), 1);
+        assert_eq!(raws[0].source_addr, "10.0.0.1:50001");
+        assert_eq!(raws[0].original_source_addr, "10.0.0.1:50001");
+
+        // The client changed networks, the connection is still listed once
+        assert!(
+            connection_manager.update_peer_addr(connect_id, "172.16.0.8:41000".parse().unwrap())
+        );
+        let raws = list().await;
+        assert_eq!(raws.len(), 1);
+        assert_eq!(raws[0].connection_id, connect_id);
+        assert_eq!(raws[0].connection_type, "Quic");
+        assert_eq!(raws[0].source_addr, "172.16.0.8:41000");
+        assert_eq!(raws[0].original_source_addr, "10.0.0.1:50001");
+        assert_eq!(raws[0].source_ip_connection_num, 1);
+    }
 }
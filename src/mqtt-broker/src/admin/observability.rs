@@ -13,27 +13,57 @@
 // limitations under the License.
 
 use crate::handler::cache::CacheManager;
-use crate::observability::slow::sub::{read_slow_sub_record, SlowSubData};
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
+use crate::handler::error::MqttBrokerError;
+use crate::observability::alarm_webhook::{
+    ALARM_WEBHOOK_MAX_BACKOFF_MS, ALARM_WEBHOOK_MAX_RETRIES, ALARM_WEBHOOK_MAX_TIMEOUT_MS,
+};
+use crate::observability::slow::sub::{read_slow_sub_record, slow_sub_threshold_ms, SlowSubData};
+use crate::observability::system_topic::sysmon::{
+    alarm_comparator_symbol, alarm_metric_name, parse_alarm_comparator, parse_alarm_metric,
+    threshold_alarm_name, SystemAlarmAck, SystemAlarmEventMessage, SystemAlarmState,
+};
+use crate::server::connection::NetworkConnectionType;
+use crate::server::connection_manager::ConnectionManager;
+use crate::server::top_talkers::{TopTalkerMetric, TOP_TALKERS_WINDOW_SEC};
+use crate::subscribe::manager::SubscribeManager;
 
+use common_base::tools::now_second;
 use common_base::utils::file_utils::get_project_root;
 use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::{
+    AlarmWebhook, MessageSampling, SystemAlarmMetric, SystemAlarmThreshold,
+};
+use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ListSlowSubScribeRaw, ListSlowSubscribeReply, ListSlowSubscribeRequest, ListSystemAlarmRaw,
-    ListSystemAlarmReply, ListSystemAlarmRequest, SetSystemAlarmConfigReply,
-    SetSystemAlarmConfigRequest,
+    AckSystemAlarmReply, AckSystemAlarmRequest, AlarmWebhookRaw, GetMessageSamplingReply,
+    GetSystemAlarmConfigReply, ListListenerMetricsReply, ListSlowSubScribeRaw,
+    ListSlowSubscribeReply, ListSlowSubscribeRequest, ListSystemAlarmRaw, ListSystemAlarmReply,
+    ListSystemAlarmRequest, ListThreadPoolReply, ListTopTalkersReply, ListTopTalkersRequest,
+    ListenerMetricsRaw, SetMessageSamplingReply, SetMessageSamplingRequest,
+    SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest, SystemAlarmThresholdRaw, ThreadPoolRaw,
+    TopTalkerRaw,
 };
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
+// Replaces the webhook secret in replies
+const ALARM_WEBHOOK_SECRET_MASK: &str = "******";
+
 // ---- slow subscribe ----
 pub async fn list_slow_subscribe_by_req(
     cache_manager: &Arc<CacheManager>,
     request: Request<ListSlowSubscribeRequest>,
 ) -> Result<Response<ListSlowSubscribeReply>, Status> {
-    let list_slow_subscribe_request = request.into_inner();
+    let mut list_slow_subscribe_request = request.into_inner();
     let mut list_slow_subscribe_raw: Vec<ListSlowSubScribeRaw> = Vec::new();
     let mqtt_config = broker_mqtt_conf();
-    if cache_manager.get_slow_sub_config().enable {
+    let slow_sub_config = cache_manager.get_slow_sub_config();
+    if slow_sub_config.enable {
+        list_slow_subscribe_request.threshold_ms = slow_sub_threshold_ms(
+            list_slow_subscribe_request.threshold_ms,
+            slow_sub_config.whole_ms,
+        );
         let path = mqtt_config.log.log_path.clone();
         let path_buf = get_project_root()?.join(path.replace("./", "") + "/slow_sub.log");
         let deque = read_slow_sub_record(list_slow_subscribe_request, path_buf)?;
@@ -81,6 +111,26 @@ pub async fn set_system_alarm_config_by_req(
     if let Some(os_cpu_check_interval_ms) = req.os_cpu_check_interval_ms {
         system_monitor_config.os_cpu_check_interval_ms = os_cpu_check_interval_ms;
     }
+    // The thresholds are replaced as a whole, an empty list only clears them when asked to
+    if !req.thresholds.is_empty() || req.clear_thresholds {
+        let thresholds = req
+            .thresholds
+            .iter()
+            .map(parse_alarm_threshold)
+            .collect::<Result<Vec<_>, _>>()?;
+        // Alarms of removed thresholds are no longer checked, so they would never resolve
+        let now = now_second() as i64;
+        for old in &system_monitor_config.thresholds {
+            if !thresholds.contains(old) {
+                cache_manager.remove_alarm_event(&threshold_alarm_name(old), now);
+            }
+        }
+        system_monitor_config.thresholds = thresholds;
+    }
+    if let Some(webhook) = &req.webhook {
+        system_monitor_config.webhook =
+            parse_alarm_webhook(webhook, &system_monitor_config.webhook)?;
+    }
     cache_manager.update_system_monitor_config(system_monitor_config.clone());
     Ok(SetSystemAlarmConfigReply {
         enable: system_monitor_config.enable,
@@ -88,41 +138,464 @@ pub async fn set_system_alarm_config_by_req(
         os_cpu_low_watermark: Some(system_monitor_config.os_cpu_low_watermark),
         os_memory_high_watermark: Some(system_monitor_config.os_memory_high_watermark),
         os_cpu_check_interval_ms: Some(system_monitor_config.os_cpu_check_interval_ms),
+        thresholds: system_alarm_threshold_raws(&system_monitor_config.thresholds),
+        webhook: Some(alarm_webhook_raw(&system_monitor_config.webhook)),
+    })
+}
+
+pub fn get_system_alarm_config_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> GetSystemAlarmConfigReply {
+    let config = cache_manager.get_system_monitor_config();
+    GetSystemAlarmConfigReply {
+        enable: config.enable,
+        os_cpu_high_watermark: config.os_cpu_high_watermark,
+        os_cpu_low_watermark: config.os_cpu_low_watermark,
+        os_memory_high_watermark: config.os_memory_high_watermark,
+        os_cpu_check_interval_ms: config.os_cpu_check_interval_ms,
+        thresholds: system_alarm_threshold_raws(&config.thresholds),
+        webhook: Some(alarm_webhook_raw(&config.webhook)),
+        webhook_delivered_num: cache_manager.alarm_webhook.delivered_num(),
+        webhook_failed_num: cache_manager.alarm_webhook.failed_num(),
+        webhook_retry_num: cache_manager.alarm_webhook.retry_num(),
+    }
+}
+
+fn parse_alarm_webhook(
+    raw: &AlarmWebhookRaw,
+    current: &AlarmWebhook,
+) -> Result<AlarmWebhook, Status> {
+    for url in raw.urls.iter() {
+        let valid = reqwest::Url::parse(url)
+            .is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https");
+        if !valid {
+            return Err(Status::invalid_argument(format!(
+                "alarm webhook url {} is not a valid http or https url",
+                url
+            )));
+        }
+    }
+    if raw.max_retries > ALARM_WEBHOOK_MAX_RETRIES {
+        return Err(Status::invalid_argument(format!(
+            "alarm webhook max_retries must not exceed {}",
+            ALARM_WEBHOOK_MAX_RETRIES
+        )));
+    }
+    if raw.initial_backoff_ms == 0 || raw.initial_backoff_ms > ALARM_WEBHOOK_MAX_BACKOFF_MS {
+        return Err(Status::invalid_argument(format!(
+            "alarm webhook initial_backoff_ms must be between 1 and {}",
+            ALARM_WEBHOOK_MAX_BACKOFF_MS
+        )));
+    }
+    if raw.timeout_ms == 0 || raw.timeout_ms > ALARM_WEBHOOK_MAX_TIMEOUT_MS {
+        return Err(Status::invalid_argument(format!(
+            "alarm webhook timeout_ms must be between 1 and {}",
+            ALARM_WEBHOOK_MAX_TIMEOUT_MS
+        )));
+    }
+    // Replies only carry the mask, so sending it or nothing back keeps the stored secret.
+    // Turning the webhook off with an empty url list also drops the secret.
+    let secret = if raw.urls.is_empty() {
+        String::new()
+    } else if raw.secret.is_empty() || raw.secret == ALARM_WEBHOOK_SECRET_MASK {
+        current.secret.clone()
+    } else {
+        raw.secret.clone()
+    };
+    Ok(AlarmWebhook {
+        urls: raw.urls.clone(),
+        secret,
+        max_retries: raw.max_retries,
+        initial_backoff_ms: raw.initial_backoff_ms,
+        timeout_ms: raw.timeout_ms,
     })
 }
 
+// The secret is never sent back, only whether one is set
+fn alarm_webhook_raw(webhook: &AlarmWebhook) -> AlarmWebhookRaw {
+    AlarmWebhookRaw {
+        urls: webhook.urls.clone(),
+        secret: if webhook.secret.is_empty() {
+            String::new()
+        } else {
+            ALARM_WEBHOOK_SECRET_MASK.to_string()
+        },
+        max_retries: webhook.max_retries,
+        initial_backoff_ms: webhook.initial_backoff_ms,
+        timeout_ms: webhook.timeout_ms,
+    }
+}
+
+fn parse_alarm_threshold(raw: &SystemAlarmThresholdRaw) -> Result<SystemAlarmThreshold, Status> {
+    let Some(metric) = parse_alarm_metric(&raw.metric) else {
+        return Err(Status::invalid_argument(format!(
+            "unknown alarm metric {}, expected cpu_usage, memory_usage, connection_num or session_num",
+            raw.metric
+        )));
+    };
+    let Some(comparator) = parse_alarm_comparator(&raw.comparator) else {
+        return Err(Status::invalid_argument(format!(
+            "unknown alarm comparator {}, expected >, >=, < or <=",
+            raw.comparator
+        )));
+    };
+    let valid = match metric {
+        SystemAlarmMetric::CpuUsage | SystemAlarmMetric::MemoryUsage => {
+            (0.0..=100.0).contains(&raw.threshold)
+        }
+        SystemAlarmMetric::ConnectionNum | SystemAlarmMetric::SessionNum => {
+            raw.threshold.is_finite() && raw.threshold >= 0.0
+        }
+    };
+    if !valid {
+        return Err(Status::invalid_argument(format!(
+            "alarm threshold {} is out of range for {}, percentages are between 0 and 100 and counts are not negative",
+            raw.threshold, raw.metric
+        )));
+    }
+    Ok(SystemAlarmThreshold {
+        metric,
+        comparator,
+        threshold: raw.threshold,
+    })
+}
+
+fn system_alarm_threshold_raws(
+    thresholds: &[SystemAlarmThreshold],
+) -> Vec<SystemAlarmThresholdRaw> {
+    thresholds
+        .iter()
+        .map(|threshold| SystemAlarmThresholdRaw {
+            metric: alarm_metric_name(threshold.metric).to_string(),
+            comparator: alarm_comparator_symbol(threshold.comparator).to_string(),
+            threshold: threshold.threshold,
+        })
+        .collect()
+}
+
 pub async fn list_system_alarm_by_req(
     cache_manager: &Arc<CacheManager>,
-    _req: &ListSystemAlarmRequest,
+    req: &ListSystemAlarmRequest,
 ) -> Result<ListSystemAlarmReply, Status> {
-    let list_system_alarm_raw: Vec<ListSystemAlarmRaw> = cache_manager
-        .alarm_events
-        .iter()
-        .map(|entry| {
-            let system_alarm_message = entry.value();
-            ListSystemAlarmRaw {
-                name: system_alarm_message.name.clone(),
-                message: system_alarm_message.message.clone(),
-                activate_at: system_alarm_message.activate_at,
-                activated: system_alarm_message.activated,
+    // Without a state filter the latest check of every alarm is listed
+    let state = if req.state.is_empty() {
+        None
+    } else {
+        match SystemAlarmState::parse(&req.state) {
+            Some(state) => Some(state),
+            None => {
+                return Err(Status::invalid_argument(format!(
+                    "unknown system alarm state {}, expected active, acknowledged or resolved",
+                    req.state
+                )))
             }
-        })
-        .collect();
+        }
+    };
+
+    let list_system_alarm_raw: Vec<ListSystemAlarmRaw> = match state {
+        Some(SystemAlarmState::Resolved) => cache_manager
+            .list_resolved_alarms()
+            .into_iter()
+            .map(|resolved| {
+                let mut raw = system_alarm_raw(&resolved.event, resolved.ack.as_ref());
+                raw.state = SystemAlarmState::Resolved.as_str().to_string();
+                raw.resolved_at = resolved.resolved_at;
+                raw
+            })
+            .collect(),
+        _ => cache_manager
+            .alarm_events
+            .iter()
+            .map(|entry| {
+                let ack = cache_manager.get_alarm_ack(entry.key());
+                system_alarm_raw(entry.value(), ack.as_ref())
+            })
+            .filter(|raw| state.is_none_or(|state| raw.state == state.as_str()))
+            .collect(),
+    };
 
     Ok(ListSystemAlarmReply {
         list_system_alarm_raw,
     })
 }
 
+fn system_alarm_raw(
+    system_alarm_message: &SystemAlarmEventMessage,
+    ack: Option<&SystemAlarmAck>,
+) -> ListSystemAlarmRaw {
+    let state = match (system_alarm_message.activated, ack) {
+        (true, Some(_)) => SystemAlarmState::Acknowledged.as_str(),
+        (true, None) => SystemAlarmState::Active.as_str(),
+        // The alarm was checked and is not raised
+        (false, _) => "inactive",
+    };
+    ListSystemAlarmRaw {
+        name: system_alarm_message.name.clone(),
+        message: system_alarm_message.message.clone(),
+        activate_at: system_alarm_message.activate_at,
+        activated: system_alarm_message.activated,
+        state: state.to_string(),
+        acknowledged_by: ack
+            .map(|ack| ack.acknowledged_by.clone())
+            .unwrap_or_default(),
+        acknowledged_at: ack.map_or(0, |ack| ack.acknowledged_at),
+        resolved_at: 0,
+    }
+}
+
+pub fn ack_system_alarm_by_req(
+    cache_manager: &Arc<CacheManager>,
+    req: &AckSystemAlarmRequest,
+) -> Result<AckSystemAlarmReply, MqttBrokerError> {
+    if req.acknowledged_by.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "acknowledged_by must not be empty".to_string(),
+        ));
+    }
+    let ack =
+        cache_manager.ack_alarm_event(&req.name, &req.acknowledged_by, now_second() as i64)?;
+    Ok(AckSystemAlarmReply {
+        name: req.name.clone(),
+        acknowledged_by: ack.acknowledged_by,
+        acknowledged_at: ack.acknowledged_at,
+    })
+}
+
+// ---- message sampling ----
+pub async fn set_message_sampling_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<SetMessageSamplingRequest>,
+) -> Result<SetMessageSamplingReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let config = MessageSampling {
+        sample_every: req.sample_every,
+        max_per_second: req.max_per_second,
+    };
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::MessageSampling,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_message_sampling_config(config.clone());
+    Ok(SetMessageSamplingReply {
+        sample_every: config.sample_every,
+        max_per_second: config.max_per_second,
+    })
+}
+
+pub fn get_message_sampling_by_req(cache_manager: &Arc<CacheManager>) -> GetMessageSamplingReply {
+    let sampler = &cache_manager.message_sampler;
+    GetMessageSamplingReply {
+        sample_every: sampler.sample_every(),
+        max_per_second: sampler.max_per_second(),
+        effective_rate: sampler.effective_rate(),
+        seen_num: sampler.seen_num(),
+        sampled_num: sampler.sampled_num(),
+    }
+}
+
+// ---- listener ----
+pub fn list_listener_metrics_by_req(
+    connection_manager: &Arc<ConnectionManager>,
+) -> Result<ListListenerMetricsReply, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    // Secure websocket connections are accepted by the websocket handler and counted there
+    let listeners = [
+        (NetworkConnectionType::Tcp, conf.network_port.tcp_port),
+        (NetworkConnectionType::Tls, conf.network_port.tcps_port),
+        (
+            NetworkConnectionType::WebSocket,
+            conf.network_port.websocket_port,
+        ),
+        (NetworkConnectionType::Quic, conf.network_port.quic_port),
+    ];
+
+    let listeners = listeners
+        .into_iter()
+        .map(|(network_type, port)| {
+            let metrics = connection_manager.get_listener_metrics(&network_type);
+            ListenerMetricsRaw {
+                listener: network_type.to_string(),
+                port,
+                accept_num: metrics.accept_num(),
+                accept_rate: metrics.accept_rate(),
+                tls_handshake_failure_num: metrics.tls_handshake_failure_num(),
+                malformed_connect_num: metrics.malformed_connect_num(),
+                accept_queue_depth: connection_manager.listener_accept_queue_depth(&network_type),
+            }
+        })
+        .collect();
+    Ok(ListListenerMetricsReply { listeners })
+}
+
+// ---- top talkers ----
+pub fn list_top_talkers_by_req(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<CacheManager>,
+    req: &ListTopTalkersRequest,
+) -> Result<ListTopTalkersReply, MqttBrokerError> {
+    let Some(metric) = TopTalkerMetric::parse(&req.metric) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "unknown top talkers metric {}, expected messages or bytes",
+            req.metric
+        )));
+    };
+    if req.n == 0 {
+        return Err(MqttBrokerError::CommonError(
+            "the number of top talkers must be greater than 0".to_string(),
+        ));
+    }
+
+    let top_talkers = connection_manager
+        .talker_sampler
+        .top_talkers(&connection_manager.connections, metric, req.n as usize)
+        .into_iter()
+        .filter_map(|talker| {
+            let connection = connection_manager.get_connect(talker.connection_id)?;
+            // Empty until the CONNECT of the connection has been handled
+            let client_id = cache_manager
+                .get_connection(talker.connection_id)
+                .map(|connection| connection.client_id)
+                .unwrap_or_default();
+            Some(TopTalkerRaw {
+                connection_id: talker.connection_id,
+                client_id,
+                connection_type: connection.connection_type.to_string(),
+                source_addr: connection.addr.to_string(),
+                messages_per_sec: talker.messages_per_sec,
+                bytes_per_sec: talker.bytes_per_sec,
+            })
+        })
+        .collect();
+    Ok(ListTopTalkersReply {
+        top_talkers,
+        window_sec: TOP_TALKERS_WINDOW_SEC,
+    })
+}
+
+// ---- thread pool ----
+pub fn list_thread_pool_by_req(
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<ListThreadPoolReply, MqttBrokerError> {
+    let pools = vec![
+        runtime_pool(),
+        exclusive_push_pool(subscribe_manager),
+        share_leader_push_pool(subscribe_manager),
+        share_follower_resub_pool(subscribe_manager),
+    ];
+    Ok(ListThreadPoolReply { pools })
+}
+
+fn runtime_pool() -> ThreadPoolRaw {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    let size = metrics.num_workers() as u64;
+    let queue_depth = metrics.global_queue_depth() as u64;
+    let recommendation = if queue_depth > size {
+        "tasks are waiting for a runtime worker, consider raising system.runtime_worker_threads"
+    } else {
+        "runtime workers keep up with the spawned tasks"
+    };
+
+    ThreadPoolRaw {
+        name: "runtime".to_string(),
+        size,
+        // alive tasks rather than busy workers, the runtime does not expose the latter
+        active_num: metrics.num_alive_tasks() as u64,
+        idle_num: 0,
+        queue_depth,
+        recommendation: recommendation.to_string(),
+    }
+}
+
+fn exclusive_push_pool(subscribe_manager: &Arc<SubscribeManager>) -> ThreadPoolRaw {
+    let size = subscribe_manager.exclusive_push_thread.len() as u64;
+    let mut active_num = 0;
+    let mut queue_depth = 0;
+    for progress in subscribe_manager.push_progress.iter() {
+        let queued_num =
+            progress.queued_num(subscribe_manager.get_topic_latest_offset(&progress.topic_id));
+        if queued_num > 0 {
+            active_num += 1;
+            queue_depth += queued_num;
+        }
+    }
+    build_push_pool("exclusive_push", size, active_num, queue_depth)
+}
+
+fn share_leader_push_pool(subscribe_manager: &Arc<SubscribeManager>) -> ThreadPoolRaw {
+    let size = subscribe_manager.share_leader_push_thread.len() as u64;
+    let mut active_num = 0;
+    let mut queue_depth = 0;
+    for entry in subscribe_manager.share_leader_push_offset.iter() {
+        let topic_id = match subscribe_manager.share_leader_push.get(entry.key()) {
+            Some(sub_data) => sub_data.topic_id.clone(),
+            None => continue,
+        };
+        let latest = subscribe_manager.get_topic_latest_offset(&topic_id);
+        if let Some(latest) = latest {
+            if latest >= *entry.value() {
+                active_num += 1;
+                queue_depth += latest - *entry.value() + 1;
+            }
+        }
+    }
+    build_push_pool("share_leader_push", size, active_num, queue_depth)
+}
+
+fn share_follower_resub_pool(subscribe_manager: &Arc<SubscribeManager>) -> ThreadPoolRaw {
+    // Resubscribe threads hold a connection to the leader broker for their whole life
+    let size = subscribe_manager.share_follower_resub_thread.len() as u64;
+    ThreadPoolRaw {
+        name: "share_follower_resub".to_string(),
+        size,
+        active_num: size,
+        idle_num: 0,
+        queue_depth: 0,
+        recommendation: "resubscribe threads forward to the leader broker, check the share_leader_push pool there".to_string(),
+    }
+}
+
+// A thread is active when its subscription has messages it has not delivered yet
+fn build_push_pool(name: &str, size: u64, active_num: u64, queue_depth: u64) -> ThreadPoolRaw {
+    let active_num = active_num.min(size);
+    let idle_num = size - active_num;
+    ThreadPoolRaw {
+        name: name.to_string(),
+        size,
+        active_num,
+        idle_num,
+        queue_depth,
+        recommendation: push_pool_recommendation(size, idle_num, queue_depth).to_string(),
+    }
+}
+
+// Push threads are started one per subscription, so the pool can not be resized directly
+fn push_pool_recommendation(size: u64, idle_num: u64, queue_depth: u64) -> &'static str {
+    if size == 0 {
+        return "no push threads are running";
+    }
+    if queue_depth == 0 {
+        return "all subscriptions are caught up";
+    }
+    if idle_num == 0 {
+        return "every push thread has a backlog, delivery is bound by the push threads or storage reads, spread subscriptions over more brokers";
+    }
+    "only some subscriptions have a backlog, the bottleneck is the I/O of those clients, check the slow subscribe list"
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::observability::system_topic::sysmon::SystemAlarmEventMessage;
+    use crate::server::connection::NetworkConnection;
     use crate::storage::message::cluster_name;
 
-    use common_config::mqtt::config::BrokerMqttConfig;
+    use common_config::mqtt::config::{BrokerMqttConfig, SystemAlarmComparator};
     use common_config::mqtt::{default_broker_mqtt, init_broker_mqtt_conf_by_path};
     use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::MQTTConnection;
 
     #[tokio::test]
     pub async fn test_set_system_alarm_config_by_req() {
@@ -141,6 +614,7 @@ pub async fn test_set_system_alarm_config_by_req() {
             os_cpu_low_watermark: Some(20.0),
             os_memory_high_watermark: Some(75.0),
             os_cpu_check_interval_ms: None,
+            ..Default::default()
         };
         let reply = set_system_alarm_config_by_req(&cache_manager, &req)
             .await
@@ -159,6 +633,190 @@ pub async fn test_set_system_alarm_config_by_req() {
         );
     }
 
+    #[tokio::test]
+    pub async fn test_set_system_alarm_webhook_by_req() {
+        let cache_manager = Arc::new(CacheManager::new(
+            Arc::new(ClientPool::new(1)),
+            "test".to_string(),
+        ));
+        cache_manager.set_cluster_config(default_broker_mqtt());
+        let webhook =
+            |urls: Vec<&str>, secret: &str, max_retries: u32, timeout_ms: u64| AlarmWebhookRaw {
+                urls: urls.into_iter().map(|url| url.to_string()).collect(),
+                secret: secret.to_string(),
+                max_retries,
+                initial_backoff_ms: 200,
+                timeout_ms,
+            };
+        let set = |webhook: AlarmWebhookRaw| {
+            let req = SetSystemAlarmConfigRequest {
+                webhook: Some(webhook),
+                ..Default::default()
+            };
+            let cache_manager = cache_manager.clone();
+            async move { set_system_alarm_config_by_req(&cache_manager, &req).await }
+        };
+
+        let reply = set(webhook(
+            vec![
+                "http://127.0.0.1:8080/alarm",
+                "https://ops.example.com/hook",
+            ],
+            "s3cr3t",
+            5,
+            3000,
+        ))
+        .await
+        .unwrap();
+        // The secret is stored but never returned
+        let returned = reply.webhook.unwrap();
+        assert_eq!(returned.secret, ALARM_WEBHOOK_SECRET_MASK);
+        assert_eq!(returned.max_retries, 5);
+        let stored = cache_manager.get_system_monitor_config().webhook;
+        assert_eq!(stored.secret, "s3cr3t");
+        assert_eq!(stored.urls.len(), 2);
+        assert_eq!(stored.timeout_ms, 3000);
+
+        let reply = get_system_alarm_config_by_req(&cache_manager);
+        let returned = reply.webhook.unwrap();
+        assert_eq!(returned.urls, stored.urls);
+        assert_eq!(returned.secret, ALARM_WEBHOOK_SECRET_MASK);
+        assert_eq!(reply.webhook_delivered_num, 0);
+        assert_eq!(reply.webhook_failed_num, 0);
+
+        for invalid in [
+            webhook(vec!["ftp://127.0.0.1/alarm"], "", 3, 3000),
+            webhook(vec!["not a url"], "", 3, 3000),
+            webhook(vec!["http://127.0.0.1/alarm"], "", 11, 3000),
+            webhook(vec!["http://127.0.0.1/alarm"], "", 3, 0),
+            webhook(vec!["http://127.0.0.1/alarm"], "", 3, 60001),
+            AlarmWebhookRaw {
+                initial_backoff_ms: 0,
+                ..webhook(vec!["http://127.0.0.1/alarm"], "", 3, 3000)
+            },
+        ] {
+            assert!(set(invalid).await.is_err());
+        }
+        assert_eq!(cache_manager.get_system_monitor_config().webhook, stored);
+
+        // Sending the masked or an empty secret back keeps the stored one
+        for secret in [ALARM_WEBHOOK_SECRET_MASK, ""] {
+            set(webhook(
+                vec!["http://127.0.0.1:8080/alarm"],
+                secret,
+                3,
+                3000,
+            ))
+            .await
+            .unwrap();
+            assert_eq!(
+                cache_manager.get_system_monitor_config().webhook.secret,
+                "s3cr3t"
+            );
+        }
+        set(webhook(vec!["http://127.0.0.1:8080/alarm"], "n3w", 3, 3000))
+            .await
+            .unwrap();
+        assert_eq!(
+            cache_manager.get_system_monitor_config().webhook.secret,
+            "n3w"
+        );
+
+        // Without a secret nothing is masked, an empty url list turns the webhook off
+        let reply = set(webhook(Vec::new(), "", 0, 1000)).await.unwrap();
+        let returned = reply.webhook.unwrap();
+        assert!(returned.secret.is_empty());
+        assert!(returned.urls.is_empty());
+    }
+
+    #[tokio::test]
+    pub async fn test_set_system_alarm_thresholds_by_req() {
+        let cache_manager = Arc::new(CacheManager::new(
+            Arc::new(ClientPool::new(1)),
+            "test".to_string(),
+        ));
+        cache_manager.set_cluster_config(default_broker_mqtt());
+        let threshold = |metric: &str, comparator: &str, threshold: f64| SystemAlarmThresholdRaw {
+            metric: metric.to_string(),
+            comparator: comparator.to_string(),
+            threshold,
+        };
+        let set = |thresholds: Vec<SystemAlarmThresholdRaw>, clear_thresholds: bool| {
+            let req = SetSystemAlarmConfigRequest {
+                thresholds,
+                clear_thresholds,
+                ..Default::default()
+            };
+            let cache_manager = cache_manager.clone();
+            async move { set_system_alarm_config_by_req(&cache_manager, &req).await }
+        };
+
+        let thresholds = vec![
+            threshold("connection_num", ">", 1000.0),
+            threshold("memory_usage", ">=", 90.0),
+            threshold("cpu_usage", "<", 5.0),
+        ];
+        let reply = set(thresholds.clone(), false).await.unwrap();
+        assert_eq!(reply.thresholds, thresholds);
+        assert_eq!(
+            get_system_alarm_config_by_req(&cache_manager).thresholds,
+            thresholds
+        );
+        assert_eq!(
+            cache_manager.get_system_monitor_config().thresholds[0],
+            SystemAlarmThreshold {
+                metric: SystemAlarmMetric::ConnectionNum,
+                comparator: SystemAlarmComparator::GreaterThan,
+                threshold: 1000.0,
+            }
+        );
+
+        // Invalid tuples are rejected and leave the thresholds as they were
+        for invalid in [
+            threshold("disk_usage", ">", 10.0),
+            threshold("connection_num", "=>", 10.0),
+            threshold("connection_num", "==", 10.0),
+            threshold("memory_usage", ">", 120.0),
+            threshold("cpu_usage", "<", -1.0),
+            threshold("session_num", ">", -5.0),
+            threshold("session_num", ">", f64::NAN),
+        ] {
+            assert!(set(vec![invalid], false).await.is_err());
+        }
+        assert_eq!(
+            get_system_alarm_config_by_req(&cache_manager).thresholds,
+            thresholds
+        );
+
+        // Other settings keep the thresholds, an active alarm of a removed threshold resolves
+        set(Vec::new(), false).await.unwrap();
+        assert_eq!(
+            get_system_alarm_config_by_req(&cache_manager).thresholds,
+            thresholds
+        );
+        cache_manager.add_alarm_event(
+            "connection_num > 1000".to_string(),
+            SystemAlarmEventMessage {
+                name: "connection_num > 1000".to_string(),
+                activate_at: 10,
+                activated: true,
+                ..Default::default()
+            },
+        );
+        let reply = set(vec![thresholds[1].clone()], false).await.unwrap();
+        assert_eq!(reply.thresholds, vec![thresholds[1].clone()]);
+        assert!(cache_manager
+            .get_alarm_event("connection_num > 1000")
+            .is_none());
+        assert_eq!(
+            cache_manager.list_resolved_alarms()[0].event.name,
+            "connection_num > 1000"
+        );
+
+        let reply = set(Vec::new(), true).await.unwrap();
+        assert!(reply.thresholds.is_empty());
+    }
+
     #[tokio::test]
     pub async fn test_list_system_alarm_by_req() {
         let path = format!(
@@ -170,7 +828,7 @@ pub async fn test_list_system_alarm_by_req() {
         let cache_manager = Arc::new(CacheManager::new(cache_client_pool, cluster_name()));
         cache_manager.set_cluster_config(BrokerMqttConfig::default());
 
-        let req = ListSystemAlarmRequest {};
+        let req = ListSystemAlarmRequest::default();
         let test_event = "test_event";
         let message = SystemAlarmEventMessage {
             name: test_event.to_string(),
@@ -194,5 +852,201 @@ pub async fn test_list_system_alarm_by_req() {
         );
         assert_eq!(reply.list_system_alarm_raw[0].activate_at, 0);
         assert!(!reply.list_system_alarm_raw[0].activated);
+        assert_eq!(reply.list_system_alarm_raw[0].state, "inactive");
+    }
+
+    #[tokio::test]
+    pub async fn test_ack_system_alarm_by_req() {
+        let cache_manager = Arc::new(CacheManager::new(
+            Arc::new(ClientPool::new(1)),
+            "test".to_string(),
+        ));
+        let add_event = |name: &str, activate_at: i64, activated: bool| {
+            cache_manager.add_alarm_event(
+                name.to_string(),
+                SystemAlarmEventMessage {
+                    name: name.to_string(),
+                    message: name.to_string(),
+                    activate_at,
+                    activated,
+                },
+            );
+        };
+        add_event("HighCpuUsage", 10, true);
+        add_event("MemoryUsage", 10, true);
+        add_event("LowCpuUsage", 10, false);
+
+        let ack = |name: &str, acknowledged_by: &str| {
+            ack_system_alarm_by_req(
+                &cache_manager,
+                &AckSystemAlarmRequest {
+                    name: name.to_string(),
+                    acknowledged_by: acknowledged_by.to_string(),
+                },
+            )
+        };
+        let reply = ack("MemoryUsage", "ops").unwrap();
+        assert_eq!(reply.name, "MemoryUsage");
+        assert_eq!(reply.acknowledged_by, "ops");
+        assert!(reply.acknowledged_at > 0);
+        // Inactive or unknown alarms and anonymous acknowledgements are rejected
+        assert!(ack("LowCpuUsage", "ops").is_err());
+        assert!(ack("DiskUsage", "ops").is_err());
+        assert!(ack("HighCpuUsage", "").is_err());
+
+        let list = |state: &str| {
+            let req = ListSystemAlarmRequest {
+                state: state.to_string(),
+            };
+            let cache_manager = cache_manager.clone();
+            async move {
+                let mut raws = list_system_alarm_by_req(&cache_manager, &req)
+                    .await
+                    .unwrap()
+                    .list_system_alarm_raw;
+                raws.sort_by(|a, b| a.name.cmp(&b.name));
+                raws
+            }
+        };
+
+        let active = list("active").await;
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].name, "HighCpuUsage");
+        assert_eq!(active[0].state, "active");
+        assert!(active[0].acknowledged_by.is_empty());
+
+        // Acknowledged alarms are still activated but listed apart with who acked them
+        let acknowledged = list("acknowledged").await;
+        assert_eq!(acknowledged.len(), 1);
+        assert_eq!(acknowledged[0].name, "MemoryUsage");
+        assert!(acknowledged[0].activated);
+        assert_eq!(acknowledged[0].acknowledged_by, "ops");
+        assert_eq!(acknowledged[0].acknowledged_at, reply.acknowledged_at);
+
+        assert!(list("resolved").await.is_empty());
+        let states: Vec<String> = list("").await.into_iter().map(|raw| raw.state).collect();
+        assert_eq!(states, vec!["active", "inactive", "acknowledged"]);
+
+        // Resolving the acknowledged alarm moves it to the history
+        add_event("MemoryUsage", 20, false);
+        let resolved = list("RESOLVED").await;
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].name, "MemoryUsage");
+        assert_eq!(resolved[0].state, "resolved");
+        assert_eq!(resolved[0].activate_at, 10);
+        assert_eq!(resolved[0].resolved_at, 20);
+        assert_eq!(resolved[0].acknowledged_by, "ops");
+        assert!(list("acknowledged").await.is_empty());
+
+        assert!(list_system_alarm_by_req(
+            &cache_manager,
+            &ListSystemAlarmRequest {
+                state: "cleared".to_string(),
+            },
+        )
+        .await
+        .is_err());
+    }
+
+    #[test]
+    pub fn test_push_pool_recommendation() {
+        let pool = build_push_pool("exclusive_push", 0, 0, 0);
+        assert_eq!(pool.recommendation, "no push threads are running");
+
+        let pool = build_push_pool("exclusive_push", 4, 0, 0);
+        assert_eq!(pool.idle_num, 4);
+        assert_eq!(pool.recommendation, "all subscriptions are caught up");
+
+        let pool = build_push_pool("exclusive_push", 4, 4, 100);
+        assert_eq!(pool.idle_num, 0);
+        assert!(pool
+            .recommendation
+            .starts_with("every push thread has a backlog"));
+
+        let pool = build_push_pool("exclusive_push", 4, 1, 100);
+        assert_eq!(pool.active_num, 1);
+        assert_eq!(pool.idle_num, 3);
+        assert!(pool
+            .recommendation
+            .starts_with("only some subscriptions have a backlog"));
+    }
+
+    #[tokio::test]
+    pub async fn test_list_top_talkers_by_req() {
+        let cache_manager = Arc::new(CacheManager::new(
+            Arc::new(ClientPool::new(1)),
+            "test".to_string(),
+        ));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        let mut connect_ids = Vec::new();
+        // (client id, messages, bytes per message)
+        for (client_id, messages, size) in [("c1", 5, 100), ("c2", 50, 1), ("c3", 20, 10)] {
+            let connection = NetworkConnection::new(
+                NetworkConnectionType::Tcp,
+                "127.0.0.1:1883".parse().unwrap(),
+                None,
+            );
+            for _ in 0..messages {
+                connection.record_message_sent();
+                connection.record_bytes_sent(size);
+            }
+            let connect_id = connection_manager.add_connection(connection);
+            cache_manager.add_connection(
+                connect_id,
+                MQTTConnection {
+                    connect_id,
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+            connect_ids.push(connect_id);
+        }
+
+        let list = |metric: &str, n: u32| {
+            list_top_talkers_by_req(
+                &connection_manager,
+                &cache_manager,
+                &ListTopTalkersRequest {
+                    n,
+                    metric: metric.to_string(),
+                },
+            )
+        };
+        let reply = list("messages", 2).unwrap();
+        assert_eq!(reply.window_sec, TOP_TALKERS_WINDOW_SEC);
+        let client_ids = |reply: &ListTopTalkersReply| -> Vec<String> {
+            reply
+                .top_talkers
+                .iter()
+                .map(|raw| raw.client_id.clone())
+                .collect()
+        };
+        assert_eq!(client_ids(&reply), vec!["c2", "c3"]);
+        assert_eq!(reply.top_talkers[0].connection_id, connect_ids[1]);
+        assert_eq!(reply.top_talkers[0].connection_type, "Tcp");
+
+        let reply = list("bytes", 3).unwrap();
+        assert_eq!(client_ids(&reply), vec!["c1", "c3", "c2"]);
+        assert!(reply.top_talkers[0].bytes_per_sec > reply.top_talkers[1].bytes_per_sec);
+
+        assert!(list("packets", 3).is_err());
+        assert!(list("messages", 0).is_err());
+    }
+
+    #[tokio::test]
+    pub async fn test_list_thread_pool_by_req() {
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        let reply = list_thread_pool_by_req(&subscribe_manager).unwrap();
+        let names: Vec<String> = reply.pools.iter().map(|pool| pool.name.clone()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "runtime",
+                "exclusive_push",
+                "share_leader_push",
+                "share_follower_resub"
+            ]
+        );
+        assert!(reply.pools[0].size > 0);
     }
 }
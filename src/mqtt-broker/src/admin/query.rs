@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cmp::Ordering;
+
 use protocol::broker_mqtt::broker_mqtt_admin::{MatchMode, OrderDirection, QueryOptions};
 
+use crate::handler::error::MqttBrokerError;
+
 /// A common interface for resource types to support generic querying logic.
 ///
 /// The `Queryable` trait provides a uniform way to retrieve a named field’s
@@ -142,3 +146,635 @@ pub fn apply_pagination<T: Queryable>(
         (items, total_count)
     }
 }
+
+// Nesting allowed in a filter expression, deeper ones are rejected instead of overflowing the stack
+const FILTER_EXPRESSION_MAX_DEPTH: usize = 32;
+
+/// A parsed SQL-like filter expression over the fields of a `Queryable`, e.g.
+/// `is_contain_last_will = false AND (session_expiry > 60 OR client_id LIKE 'sensor-%')`.
+///
+/// Comparisons are `=`, `!=`, `<>`, `>`, `>=`, `<`, `<=`, `[NOT] LIKE` (`%` matches any
+/// run of characters, `_` a single one), `[NOT] IN (...)` and `IS [NOT] NULL`, combined
+/// with `AND`, `OR`, `NOT` and parentheses. Keywords are case insensitive. Values are
+/// quoted strings or bare words such as `60` or `false`. When both sides are integers
+/// they are compared as numbers, otherwise as strings. Like NULL in SQL, a field without
+/// a value fails every comparison except `IS NULL`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: String,
+    },
+    Like {
+        field: String,
+        pattern: String,
+    },
+    In {
+        field: String,
+        values: Vec<String>,
+    },
+    IsNull {
+        field: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+        }
+    }
+}
+
+impl FilterExpr {
+    /// Parse `expression`, accepting only the field names in `fields`.
+    pub fn parse(expression: &str, fields: &[&str]) -> Result<FilterExpr, MqttBrokerError> {
+        let tokens = tokenize(expression)?;
+        let mut parser = FilterParser {
+            tokens,
+            pos: 0,
+            depth: 0,
+            fields,
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(invalid_filter(format!(
+                "unexpected {} at position {}",
+                token.kind, token.offset
+            )));
+        }
+        Ok(expr)
+    }
+
+    pub fn matches<T: Queryable>(&self, item: &T) -> bool {
+        match self {
+            FilterExpr::And(left, right) => left.matches(item) && right.matches(item),
+            FilterExpr::Or(left, right) => left.matches(item) || right.matches(item),
+            FilterExpr::Not(inner) => !inner.matches(item),
+            FilterExpr::Compare { field, op, value } => {
+                let Some(raw) = item.get_field_str(field) else {
+                    return false;
+                };
+                let ordering = compare_values(&raw, value);
+                match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::Ne => ordering != Ordering::Equal,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::Ge => ordering != Ordering::Less,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::Le => ordering != Ordering::Greater,
+                }
+            }
+            FilterExpr::Like { field, pattern } => item
+                .get_field_str(field)
+                .is_some_and(|raw| like_match(&raw, pattern)),
+            FilterExpr::In { field, values } => item.get_field_str(field).is_some_and(|raw| {
+                values
+                    .iter()
+                    .any(|value| compare_values(&raw, value) == Ordering::Equal)
+            }),
+            FilterExpr::IsNull { field } => item.get_field_str(field).is_none(),
+        }
+    }
+}
+
+/// Keep the items matching the filter expression, an empty expression keeps all of them.
+pub fn apply_filter_expression<T: Queryable>(
+    items: Vec<T>,
+    expression: &str,
+    fields: &[&str],
+) -> Result<Vec<T>, MqttBrokerError> {
+    if expression.trim().is_empty() {
+        return Ok(items);
+    }
+    let expr = FilterExpr::parse(expression, fields)?;
+    Ok(items
+        .into_iter()
+        .filter(|item| expr.matches(item))
+        .collect())
+}
+
+fn invalid_filter(reason: String) -> MqttBrokerError {
+    MqttBrokerError::InvalidFilterExpression(reason)
+}
+
+fn compare_values(raw: &str, value: &str) -> Ordering {
+    match (raw.parse::<i128>(), value.parse::<i128>()) {
+        (Ok(raw), Ok(value)) => raw.cmp(&value),
+        _ => raw.cmp(value),
+    }
+}
+
+fn like_match(raw: &str, pattern: &str) -> bool {
+    let raw: Vec<char> = raw.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut r, mut p) = (0, 0);
+    // the last % seen and the character of raw it currently stops before, retried on mismatch
+    let mut backtrack: Option<(usize, usize)> = None;
+    while r < raw.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                backtrack = Some((p, r));
+                p += 1;
+            }
+            Some(c) if *c == '_' || *c == raw[r] => {
+                r += 1;
+                p += 1;
+            }
+            _ => {
+                let Some((percent, start)) = backtrack else {
+                    return false;
+                };
+                backtrack = Some((percent, start + 1));
+                p = percent + 1;
+                r = start + 1;
+            }
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '%')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    // a field name, keyword or unquoted value
+    Word(String),
+    Quoted(String),
+    Op(CompareOp),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Word(word) => write!(f, "'{}'", word),
+            TokenKind::Quoted(value) => write!(f, "string '{}'", value),
+            TokenKind::Op(op) => write!(f, "'{}'", op.symbol()),
+            TokenKind::LeftParen => write!(f, "'('"),
+            TokenKind::RightParen => write!(f, "')'"),
+            TokenKind::Comma => write!(f, "','"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    // character position in the expression, reported in errors
+    offset: usize,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, MqttBrokerError> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let offset = i;
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::LeftParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RightParen
+            }
+            ',' => {
+                i += 1;
+                TokenKind::Comma
+            }
+            '=' => {
+                i += 1;
+                TokenKind::Op(CompareOp::Eq)
+            }
+            '!' if chars.get(i + 1) == Some(&'=') => {
+                i += 2;
+                TokenKind::Op(CompareOp::Ne)
+            }
+            '<' | '>' => {
+                let next = chars.get(i + 1).copied();
+                let (op, len) = match (c, next) {
+                    ('<', Some('>')) => (CompareOp::Ne, 2),
+                    ('<', Some('=')) => (CompareOp::Le, 2),
+                    ('<', _) => (CompareOp::Lt, 1),
+                    ('>', Some('=')) => (CompareOp::Ge, 2),
+                    _ => (CompareOp::Gt, 1),
+                };
+                i += len;
+                TokenKind::Op(op)
+            }
+            '\'' | '"' => {
+                // a quote is written inside a string by doubling it
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(invalid_filter(format!(
+                                "unterminated string starting at position {}",
+                                offset
+                            )))
+                        }
+                        Some(q) if *q == c && chars.get(i + 1) == Some(&c) => {
+                            value.push(c);
+                            i += 2;
+                        }
+                        Some(q) if *q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(other) => {
+                            value.push(*other);
+                            i += 1;
+                        }
+                    }
+                }
+                TokenKind::Quoted(value)
+            }
+            c if is_word_char(c) => {
+                let start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                TokenKind::Word(chars[start..i].iter().collect())
+            }
+            other => {
+                return Err(invalid_filter(format!(
+                    "unexpected character '{}' at position {}",
+                    other, offset
+                )))
+            }
+        };
+        tokens.push(Token { kind, offset });
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '/' | '+' | '#' | '$' | '%')
+}
+
+const KEYWORDS: &[&str] = &["AND", "OR", "NOT", "LIKE", "IN", "IS", "NULL"];
+
+struct FilterParser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+    fields: &'a [&'a str],
+}
+
+impl FilterParser<'_> {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(
+            self.tokens.get(self.pos),
+            Some(Token { kind: TokenKind::Word(word), .. }) if word.eq_ignore_ascii_case(keyword)
+        )
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, MqttBrokerError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+            invalid_filter(format!("expected {} but the expression ended", expected))
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn unexpected(token: &Token, expected: &str) -> MqttBrokerError {
+        invalid_filter(format!(
+            "expected {} but found {} at position {}",
+            expected, token.kind, token.offset
+        ))
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, MqttBrokerError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            let right = self.parse_and()?;
+            left = FilterExpr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, MqttBrokerError> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("AND") {
+            let right = self.parse_not()?;
+            left = FilterExpr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<FilterExpr, MqttBrokerError> {
+        self.depth += 1;
+        if self.depth > FILTER_EXPRESSION_MAX_DEPTH {
+            return Err(invalid_filter(format!(
+                "the expression is nested deeper than {} levels",
+                FILTER_EXPRESSION_MAX_DEPTH
+            )));
+        }
+        let expr = if self.eat_keyword("NOT") {
+            self.parse_not()
+                .map(|inner| FilterExpr::Not(Box::new(inner)))
+        } else {
+            self.parse_primary()
+        };
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_primary(&mut self) -> Result<FilterExpr, MqttBrokerError> {
+        let token = self.next("a field name or '('")?;
+        let field = match token.kind {
+            TokenKind::LeftParen => {
+                let expr = self.parse_or()?;
+                let token = self.next("')'")?;
+                if token.kind != TokenKind::RightParen {
+                    return Err(Self::unexpected(&token, "')'"));
+                }
+                return Ok(expr);
+            }
+            TokenKind::Word(ref word) if !is_keyword(word) => word.clone(),
+            _ => return Err(Self::unexpected(&token, "a field name or '('")),
+        };
+        if !self.fields.contains(&field.as_str()) {
+            return Err(invalid_filter(format!(
+                "unknown field '{}' at position {}, supported fields: {}",
+                field,
+                token.offset,
+                self.fields.join(", ")
+            )));
+        }
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            let token = self.next("NULL")?;
+            if !matches!(&token.kind, TokenKind::Word(word) if word.eq_ignore_ascii_case("NULL")) {
+                return Err(Self::unexpected(&token, "NULL"));
+            }
+            let expr = FilterExpr::IsNull { field };
+            return Ok(negate_if(expr, negated));
+        }
+
+        let negated = self.eat_keyword("NOT");
+        if self.eat_keyword("LIKE") {
+            let pattern = self.parse_value()?;
+            return Ok(negate_if(FilterExpr::Like { field, pattern }, negated));
+        }
+        if self.eat_keyword("IN") {
+            let token = self.next("'('")?;
+            if token.kind != TokenKind::LeftParen {
+                return Err(Self::unexpected(&token, "'('"));
+            }
+            let mut values = vec![self.parse_value()?];
+            loop {
+                let token = self.next("',' or ')'")?;
+                match token.kind {
+                    TokenKind::Comma => values.push(self.parse_value()?),
+                    TokenKind::RightParen => break,
+                    _ => return Err(Self::unexpected(&token, "',' or ')'")),
+                }
+            }
+            return Ok(negate_if(FilterExpr::In { field, values }, negated));
+        }
+        if negated {
+            let token = self.next("LIKE or IN")?;
+            return Err(Self::unexpected(&token, "LIKE or IN"));
+        }
+
+        let token = self.next("an operator")?;
+        let TokenKind::Op(op) = token.kind else {
+            return Err(Self::unexpected(&token, "an operator"));
+        };
+        let value = self.parse_value()?;
+        Ok(FilterExpr::Compare { field, op, value })
+    }
+
+    fn parse_value(&mut self) -> Result<String, MqttBrokerError> {
+        let token = self.next("a value")?;
+        match &token.kind {
+            TokenKind::Quoted(value) => Ok(value.clone()),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("NULL") => {
+                Err(invalid_filter(format!(
+                    "NULL at position {} can only be checked with IS NULL or IS NOT NULL",
+                    token.offset
+                )))
+            }
+            TokenKind::Word(word) if is_keyword(word) => Err(Self::unexpected(&token, "a value")),
+            // booleans are rendered in lower case by the queryable types
+            TokenKind::Word(word)
+                if word.eq_ignore_ascii_case("true") || word.eq_ignore_ascii_case("false") =>
+            {
+                Ok(word.to_lowercase())
+            }
+            TokenKind::Word(word) => Ok(word.clone()),
+            _ => Err(Self::unexpected(&token, "a value")),
+        }
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+fn negate_if(expr: FilterExpr, negated: bool) -> FilterExpr {
+    if negated {
+        FilterExpr::Not(Box::new(expr))
+    } else {
+        expr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_filter_expression, CompareOp, FilterExpr, Queryable};
+    use crate::handler::error::MqttBrokerError;
+
+    const FIELDS: &[&str] = &["name", "count", "enabled", "owner"];
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item {
+        name: &'static str,
+        count: u64,
+        enabled: bool,
+        owner: Option<&'static str>,
+    }
+
+    impl Queryable for Item {
+        fn get_field_str(&self, field: &str) -> Option<String> {
+            match field {
+                "name" => Some(self.name.to_string()),
+                "count" => Some(self.count.to_string()),
+                "enabled" => Some(self.enabled.to_string()),
+                "owner" => self.owner.map(|v| v.to_string()),
+                _ => None,
+            }
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                name: "sensor-1",
+                count: 5,
+                enabled: true,
+                owner: Some("alice"),
+            },
+            Item {
+                name: "sensor-2",
+                count: 100,
+                enabled: false,
+                owner: None,
+            },
+            Item {
+                name: "gateway_1",
+                count: 60,
+                enabled: true,
+                owner: Some("bob"),
+            },
+        ]
+    }
+
+    fn names(expression: &str) -> Vec<&'static str> {
+        apply_filter_expression(items(), expression, FIELDS)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.name)
+            .collect()
+    }
+
+    fn parse_err(expression: &str) -> String {
+        match FilterExpr::parse(expression, FIELDS) {
+            Err(MqttBrokerError::InvalidFilterExpression(reason)) => reason,
+            other => panic!("expression {} parsed to {:?}", expression, other),
+        }
+    }
+
+    #[test]
+    fn filter_expression_compare_test() {
+        assert_eq!(names(""), vec!["sensor-1", "sensor-2", "gateway_1"]);
+        assert_eq!(names("   "), vec!["sensor-1", "sensor-2", "gateway_1"]);
+        assert_eq!(names("count > 60"), vec!["sensor-2"]);
+        assert_eq!(names("count >= 60"), vec!["sensor-2", "gateway_1"]);
+        assert_eq!(names("count < 60"), vec!["sensor-1"]);
+        assert_eq!(names("count <= 60"), vec!["sensor-1", "gateway_1"]);
+        assert_eq!(names("count != 60"), vec!["sensor-1", "sensor-2"]);
+        assert_eq!(names("count <> 60"), vec!["sensor-1", "sensor-2"]);
+        // numbers compare as numbers, not as strings where "100" < "60"
+        assert_eq!(names("count > 9"), vec!["sensor-2", "gateway_1"]);
+        assert_eq!(names("enabled = FALSE"), vec!["sensor-2"]);
+        assert_eq!(names("name = 'sensor-1'"), vec!["sensor-1"]);
+        assert_eq!(names("name = \"gateway_1\""), vec!["gateway_1"]);
+        assert_eq!(names("name = sensor-2"), vec!["sensor-2"]);
+        assert_eq!(names("name > 'r'"), vec!["sensor-1", "sensor-2"]);
+    }
+
+    #[test]
+    fn filter_expression_operators_test() {
+        assert_eq!(names("name LIKE 'sensor-%'"), vec!["sensor-1", "sensor-2"]);
+        assert_eq!(names("name like '%_1'"), vec!["sensor-1", "gateway_1"]);
+        assert_eq!(names("name LIKE 'gateway_1'"), vec!["gateway_1"]);
+        assert_eq!(names("name LIKE 'sensor-_'"), vec!["sensor-1", "sensor-2"]);
+        assert_eq!(names("name LIKE 'sensor'"), Vec::<&str>::new());
+        assert_eq!(names("name NOT LIKE 'sensor%'"), vec!["gateway_1"]);
+        assert_eq!(names("count IN (5, 60)"), vec!["sensor-1", "gateway_1"]);
+        assert_eq!(names("count NOT IN (5, 60)"), vec!["sensor-2"]);
+        assert_eq!(names("owner IS NULL"), vec!["sensor-2"]);
+        assert_eq!(names("owner IS NOT NULL"), vec!["sensor-1", "gateway_1"]);
+        // a field without value fails every comparison, negated or not
+        assert_eq!(names("owner = 'alice'"), vec!["sensor-1"]);
+        assert_eq!(names("owner != 'alice'"), vec!["gateway_1"]);
+    }
+
+    #[test]
+    fn filter_expression_logic_test() {
+        assert_eq!(names("enabled = true AND count > 10"), vec!["gateway_1"]);
+        assert_eq!(
+            names("count = 5 OR count = 100"),
+            vec!["sensor-1", "sensor-2"]
+        );
+        assert_eq!(names("NOT enabled = true"), vec!["sensor-2"]);
+        // AND binds tighter than OR
+        assert_eq!(
+            names("count = 100 OR enabled = true AND count = 60"),
+            vec!["sensor-2", "gateway_1"]
+        );
+        assert_eq!(
+            names("(count = 100 OR enabled = true) AND count = 60"),
+            vec!["gateway_1"]
+        );
+        assert_eq!(
+            FilterExpr::parse("not (count = 1) and owner is null", FIELDS).unwrap(),
+            FilterExpr::And(
+                Box::new(FilterExpr::Not(Box::new(FilterExpr::Compare {
+                    field: "count".to_string(),
+                    op: CompareOp::Eq,
+                    value: "1".to_string(),
+                }))),
+                Box::new(FilterExpr::IsNull {
+                    field: "owner".to_string(),
+                }),
+            )
+        );
+    }
+
+    #[test]
+    fn filter_expression_malformed_test() {
+        assert!(parse_err("clean_session = false").contains(
+            "unknown field 'clean_session' at position 0, supported fields: name, count, enabled, owner"
+        ));
+        assert!(parse_err("count >").contains("expected a value but the expression ended"));
+        assert!(parse_err("count 5").contains("expected an operator but found '5' at position 6"));
+        assert!(parse_err("count = 5 AND").contains("expected a field name or '('"));
+        assert!(parse_err("count = 5 count = 6").contains("unexpected 'count' at position 10"));
+        assert!(parse_err("(count = 5").contains("expected ')'"));
+        assert!(parse_err("count = 5)").contains("unexpected ')' at position 9"));
+        assert!(parse_err("name = 'sensor").contains("unterminated string starting at position 7"));
+        assert!(parse_err("count ; 5").contains("unexpected character ';' at position 6"));
+        assert!(parse_err("owner = NULL").contains("IS NULL"));
+        assert!(parse_err("owner IS 5").contains("expected NULL"));
+        assert!(parse_err("count IN 5").contains("expected '('"));
+        assert!(parse_err("count IN (5 6)").contains("expected ',' or ')'"));
+        assert!(parse_err("name NOT = 'a'").contains("expected LIKE or IN"));
+        assert!(parse_err("count = AND").contains("expected a value but found 'AND'"));
+        assert!(parse_err("AND = 5").contains("expected a field name or '('"));
+        assert!(
+            parse_err(&format!("{}count = 5{}", "(".repeat(64), ")".repeat(64)))
+                .contains("nested deeper than 32 levels")
+        );
+    }
+}
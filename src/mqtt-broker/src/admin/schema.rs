@@ -26,16 +26,26 @@
 use protocol::{
     broker_mqtt::broker_mqtt_admin::{
         MqttBindSchemaRequest, MqttCreateSchemaRequest, MqttDeleteSchemaRequest,
-        MqttListBindSchemaRequest, MqttListSchemaRequest, MqttUnbindSchemaRequest,
-        MqttUpdateSchemaRequest,
+        MqttInferSchemaReply, MqttInferSchemaRequest, MqttListBindSchemaRequest,
+        MqttListSchemaRequest, MqttUnbindSchemaRequest, MqttUpdateSchemaRequest,
     },
     placement_center::placement_center_inner::{
         BindSchemaRequest, CreateSchemaRequest, DeleteSchemaRequest, ListBindSchemaRequest,
         ListSchemaRequest, UnBindSchemaRequest, UpdateSchemaRequest,
     },
 };
+use schema_register::{
+    compatibility::{check_compatibility, CompatibilityMode},
+    protobuf::protobuf_check_schema,
+};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tonic::Request;
+
+const INFER_SCHEMA_MAX_SAMPLES: usize = 1000;
+const INFER_SCHEMA_DEFAULT_RECORD_NAME: &str = "inferred";
+
 // List schemas by request
 pub async fn list_schema_by_req(
     client_pool: &Arc<ClientPool>,
@@ -77,7 +87,9 @@ pub async fn create_schema_by_req(
         schema_type,
         schema: req.schema.clone(),
         desc: req.desc.clone(),
+        message_name: req.message_name.clone(),
     };
+    check_schema_definition(&schema_data)?;
 
     let request = CreateSchemaRequest {
         cluster_name: config.cluster_name.clone(),
@@ -93,6 +105,15 @@ pub async fn create_schema_by_req(
     Ok(())
 }
 
+// Reject a schema that cannot be used to validate payloads before it is stored
+fn check_schema_definition(schema_data: &SchemaData) -> Result<(), MqttBrokerError> {
+    if schema_data.schema_type == SchemaType::PROTOBUF {
+        protobuf_check_schema(schema_data)
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    }
+    Ok(())
+}
+
 // Update an existing schema
 pub async fn update_schema_by_req(
     client_pool: &Arc<ClientPool>,
@@ -114,7 +135,34 @@ pub async fn update_schema_by_req(
         schema_type,
         schema: req.schema.clone(),
         desc: req.desc.clone(),
+        message_name: req.message_name.clone(),
     };
+    check_schema_definition(&schema_data)?;
+
+    let mode = CompatibilityMode::parse(&req.compatibility)
+        .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    if mode != CompatibilityMode::None {
+        let request = ListSchemaRequest {
+            cluster_name: config.cluster_name.clone(),
+            schema_name: req.schema_name.clone(),
+        };
+        let schemas = list_schema(client_pool, &config.placement_center, request)
+            .await
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?
+            .schemas;
+        let Some(current) = schemas
+            .iter()
+            .filter_map(|raw| serde_json::from_slice::<SchemaData>(raw).ok())
+            .find(|schema| schema.name == req.schema_name)
+        else {
+            return Err(MqttBrokerError::CommonError(format!(
+                "schema {} does not exist",
+                req.schema_name
+            )));
+        };
+        check_compatibility(mode, &current, &schema_data)
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+    }
 
     let request = UpdateSchemaRequest {
         cluster_name: config.cluster_name.clone(),
@@ -208,3 +256,375 @@ pub async fn unbind_schema_by_req(
 
     Ok(())
 }
+
+// Infer a draft schema from sample payloads, the draft is returned and not created
+pub fn infer_schema_by_req(
+    request: Request<MqttInferSchemaRequest>,
+) -> Result<MqttInferSchemaReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if req.samples.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "at least one sample payload is required".to_string(),
+        ));
+    }
+    if req.samples.len() > INFER_SCHEMA_MAX_SAMPLES {
+        return Err(MqttBrokerError::CommonError(format!(
+            "at most {} sample payloads can be inferred at once, got {}",
+            INFER_SCHEMA_MAX_SAMPLES,
+            req.samples.len()
+        )));
+    }
+
+    let schema_type = match req.schema_type.as_str() {
+        "" | "json" => SchemaType::JSON,
+        "avro" => SchemaType::AVRO,
+        _ => return Err(MqttBrokerError::InvalidSchemaType(req.schema_type.clone())),
+    };
+
+    let mut inferred: Option<InferredType> = None;
+    for (index, sample) in req.samples.iter().enumerate() {
+        let value: Value = serde_json::from_str(sample).map_err(|e| {
+            MqttBrokerError::CommonError(format!("sample {} is not valid JSON: {}", index, e))
+        })?;
+        let sample_type = InferredType::from_value(&value);
+        inferred = Some(match inferred {
+            Some(inferred) => inferred.merge(sample_type),
+            None => sample_type,
+        });
+    }
+    let inferred = inferred.unwrap();
+
+    let schema = match schema_type {
+        SchemaType::AVRO => {
+            let name = if req.schema_name.is_empty() {
+                INFER_SCHEMA_DEFAULT_RECORD_NAME.to_string()
+            } else {
+                avro_name(&req.schema_name)
+            };
+            inferred.to_avro(&name)?
+        }
+        _ => inferred.to_json_schema(),
+    };
+
+    Ok(MqttInferSchemaReply {
+        schema_type: schema_type.to_string(),
+        schema: serde_json::to_string_pretty(&schema)
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?,
+        sample_num: req.samples.len() as u32,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum InferredType {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    // None when every sample had an empty array
+    Array(Option<Box<InferredType>>),
+    Object(InferredObject),
+    // The samples disagree on the type, at most one member of each kind
+    Union(Vec<InferredType>),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct InferredObject {
+    // Number of samples the object was seen in
+    sample_num: u64,
+    // (field name, (number of samples the field was present in, type of the field))
+    fields: BTreeMap<String, (u64, InferredType)>,
+}
+
+impl InferredType {
+    fn from_value(value: &Value) -> InferredType {
+        match value {
+            Value::Null => InferredType::Null,
+            Value::Bool(_) => InferredType::Boolean,
+            Value::Number(number) => {
+                if number.is_i64() || number.is_u64() {
+                    InferredType::Integer
+                } else {
+                    InferredType::Number
+                }
+            }
+            Value::String(_) => InferredType::String,
+            Value::Array(items) => InferredType::Array(
+                items
+                    .iter()
+                    .map(InferredType::from_value)
+                    .reduce(InferredType::merge)
+                    .map(Box::new),
+            ),
+            Value::Object(map) => InferredType::Object(InferredObject {
+                sample_num: 1,
+                fields: map
+                    .iter()
+                    .map(|(name, value)| (name.clone(), (1, InferredType::from_value(value))))
+                    .collect(),
+            }),
+        }
+    }
+
+    // Integers and numbers are the same kind, an integer widens to a number
+    fn kind(&self) -> u8 {
+        match self {
+            InferredType::Null => 0,
+            InferredType::Boolean => 1,
+            InferredType::Integer | InferredType::Number => 2,
+            InferredType::String => 3,
+            InferredType::Array(_) => 4,
+            InferredType::Object(_) => 5,
+            InferredType::Union(_) => 6,
+        }
+    }
+
+    fn merge(self, other: InferredType) -> InferredType {
+        let mut members = self.into_members();
+        for member in other.into_members() {
+            match members.iter().position(|m| m.kind() == member.kind()) {
+                Some(index) => {
+                    let current = members.remove(index);
+                    members.insert(index, current.merge_same_kind(member));
+                }
+                None => members.push(member),
+            }
+        }
+        if members.len() == 1 {
+            return members.remove(0);
+        }
+        members.sort_by_key(|member| member.kind());
+        InferredType::Union(members)
+    }
+
+    fn merge_same_kind(self, other: InferredType) -> InferredType {
+        match (self, other) {
+            (InferredType::Integer, InferredType::Integer) => InferredType::Integer,
+            (InferredType::Integer | InferredType::Number, _) => InferredType::Number,
+            (InferredType::Array(items), InferredType::Array(other_items)) => {
+                InferredType::Array(match (items, other_items) {
+                    (Some(items), Some(other_items)) => Some(Box::new(items.merge(*other_items))),
+                    (items, other_items) => items.or(other_items),
+                })
+            }
+            (InferredType::Object(mut object), InferredType::Object(other_object)) => {
+                object.sample_num += other_object.sample_num;
+                for (name, (present_num, field_type)) in other_object.fields {
+                    let merged = match object.fields.remove(&name) {
+                        Some((num, current)) => (num + present_num, current.merge(field_type)),
+                        None => (present_num, field_type),
+                    };
+                    object.fields.insert(name, merged);
+                }
+                InferredType::Object(object)
+            }
+            (current, _) => current,
+        }
+    }
+
+    fn into_members(self) -> Vec<InferredType> {
+        match self {
+            InferredType::Union(members) => members,
+            other => vec![other],
+        }
+    }
+
+    fn json_type_name(&self) -> Option<&'static str> {
+        match self {
+            InferredType::Null => Some("null"),
+            InferredType::Boolean => Some("boolean"),
+            InferredType::Integer => Some("integer"),
+            InferredType::Number => Some("number"),
+            InferredType::String => Some("string"),
+            _ => None,
+        }
+    }
+
+    fn to_json_schema(&self) -> Value {
+        match self {
+            InferredType::Array(items) => match items {
+                Some(items) => json!({"type": "array", "items": items.to_json_schema()}),
+                None => json!({"type": "array"}),
+            },
+            InferredType::Object(object) => {
+                let mut properties = Map::new();
+                let mut required = Vec::new();
+                for (name, (present_num, field_type)) in object.fields.iter() {
+                    properties.insert(name.clone(), field_type.to_json_schema());
+                    if *present_num == object.sample_num {
+                        required.push(Value::String(name.clone()));
+                    }
+                }
+                json!({"type": "object", "properties": properties, "required": required})
+            }
+            InferredType::Union(members) => {
+                let names: Option<Vec<&str>> = members
+                    .iter()
+                    .map(|member| member.json_type_name())
+                    .collect();
+                match names {
+                    Some(names) => json!({ "type": names }),
+                    None => {
+                        let any_of: Vec<Value> = members
+                            .iter()
+                            .map(|member| member.to_json_schema())
+                            .collect();
+                        json!({ "anyOf": any_of })
+                    }
+                }
+            }
+            primitive => json!({ "type": primitive.json_type_name() }),
+        }
+    }
+
+    // Records are named after the path to them, since Avro names must be unique in a schema
+    fn to_avro(&self, name: &str) -> Result<Value, MqttBrokerError> {
+        Ok(match self {
+            InferredType::Null => json!("null"),
+            InferredType::Boolean => json!("boolean"),
+            InferredType::Integer => json!("long"),
+            InferredType::Number => json!("double"),
+            InferredType::String => json!("string"),
+            InferredType::Array(items) => {
+                let items = match items {
+                    Some(items) => items.to_avro(&format!("{}_item", name))?,
+                    None => json!("null"),
+                };
+                json!({"type": "array", "items": items})
+            }
+            InferredType::Object(object) => {
+                let mut fields = Vec::new();
+                for (field_name, (present_num, field_type)) in object.fields.iter() {
+                    if avro_name(field_name) != *field_name {
+                        return Err(MqttBrokerError::CommonError(format!(
+                            "field {} is not a valid Avro name, infer a JSON schema instead",
+                            field_name
+                        )));
+                    }
+                    let field_type = field_type.to_avro(&format!("{}_{}", name, field_name))?;
+                    if *present_num == object.sample_num {
+                        fields.push(json!({"name": field_name, "type": field_type}));
+                        continue;
+                    }
+
+                    // An optional field is a union with null, which has to come first for
+                    // the null default
+                    let mut members = vec![json!("null")];
+                    match field_type {
+                        Value::Array(types) => {
+                            members.extend(types.into_iter().filter(|t| *t != json!("null")))
+                        }
+                        field_type if field_type != json!("null") => members.push(field_type),
+                        _ => {}
+                    }
+                    fields.push(json!({"name": field_name, "type": members, "default": null}));
+                }
+                json!({"type": "record", "name": name, "fields": fields})
+            }
+            InferredType::Union(members) => {
+                let mut types = Vec::new();
+                for member in members.iter() {
+                    types.push(member.to_avro(name)?);
+                }
+                Value::Array(types)
+            }
+        })
+    }
+}
+
+// Avro names start with a letter or underscore and only contain letters, digits and underscores
+fn avro_name(name: &str) -> String {
+    let mut avro_name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !avro_name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        avro_name.insert(0, '_');
+    }
+    avro_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{infer_schema_by_req, InferredType};
+    use protocol::broker_mqtt::broker_mqtt_admin::MqttInferSchemaRequest;
+    use serde_json::{json, Value};
+    use tonic::Request;
+
+    fn infer(samples: &[Value]) -> InferredType {
+        samples
+            .iter()
+            .map(InferredType::from_value)
+            .reduce(InferredType::merge)
+            .unwrap()
+    }
+
+    #[test]
+    fn infer_json_schema_test() {
+        let inferred = infer(&[
+            json!({"id": 1, "temp": 20, "tags": ["a"], "meta": {"site": "x"}}),
+            json!({"id": 2, "temp": 20.5, "tags": [], "meta": {"site": "y", "floor": 3}}),
+            json!({"id": 3, "temp": null, "tags": ["b"]}),
+        ]);
+        assert_eq!(
+            inferred.to_json_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "meta": {
+                        "type": "object",
+                        "properties": {
+                            "floor": {"type": "integer"},
+                            "site": {"type": "string"}
+                        },
+                        "required": ["site"]
+                    },
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                    "temp": {"type": ["null", "number"]}
+                },
+                "required": ["id", "tags", "temp"]
+            })
+        );
+    }
+
+    #[test]
+    fn infer_avro_schema_test() {
+        let inferred = infer(&[json!({"id": 1, "name": "a"}), json!({"id": 2})]);
+        assert_eq!(
+            inferred.to_avro("sensor").unwrap(),
+            json!({
+                "type": "record",
+                "name": "sensor",
+                "fields": [
+                    {"name": "id", "type": "long"},
+                    {"name": "name", "type": ["null", "string"], "default": null}
+                ]
+            })
+        );
+
+        let invalid = infer(&[json!({"device-id": 1})]);
+        assert!(invalid.to_avro("sensor").is_err());
+    }
+
+    #[test]
+    fn infer_schema_by_req_test() {
+        let reply = infer_schema_by_req(Request::new(MqttInferSchemaRequest {
+            schema_type: "avro".to_string(),
+            schema_name: "sensor-data".to_string(),
+            samples: vec![r#"{"id": 1}"#.to_string()],
+        }))
+        .unwrap();
+        assert_eq!(reply.schema_type, "avro");
+        assert_eq!(reply.sample_num, 1);
+        let schema: Value = serde_json::from_str(&reply.schema).unwrap();
+        assert_eq!(schema["name"], "sensor_data");
+
+        let res = infer_schema_by_req(Request::new(MqttInferSchemaRequest {
+            schema_type: "json".to_string(),
+            schema_name: "".to_string(),
+            samples: vec!["not json".to_string()],
+        }));
+        assert!(res.is_err());
+    }
+}
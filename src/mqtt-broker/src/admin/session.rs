@@ -12,18 +12,88 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::admin::query::{apply_filters, apply_pagination, apply_sorting, Queryable};
+use crate::admin::query::{
+    apply_filter_expression, apply_filters, apply_pagination, apply_sorting, Queryable,
+};
+use crate::common::pkid_manager::Qos2Direction;
+use crate::common::pkid_storage::pkid_delete;
 use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
-use protocol::broker_mqtt::broker_mqtt_admin::{ListSessionRequest, SessionRaw};
+use crate::observability::metrics::session::{
+    get_session_tiering_counter, SESSION_TIERING_ACTION_OFFLOAD, SESSION_TIERING_ACTION_RELOAD,
+};
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::message::MessageStorage;
+use crate::storage::session::SessionStorage;
+use crate::subscribe::common::{min_qos, Subscriber};
+use crate::subscribe::manager::SubscribeManager;
+use crate::subscribe::push::{build_publish_message, send_publish_packet_to_client};
+use common_base::tools::now_second;
+use common_config::mqtt::config::SessionTiering;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::session::MqttSession;
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    CancelInflightQos2Reply, CancelInflightQos2Request, ExpiredSessionRaw, GetSessionTieringReply,
+    InflightQos2Raw, ListExpiredSessionsReply, ListInflightQos2Request, ListLargestSessionRequest,
+    ListSessionRequest, ListSessionSubscriptionsReply, ListSessionSubscriptionsRequest,
+    PurgeExpiredSessionsReply, ReplayToClientReply, ReplayToClientRequest, SessionMemoryRaw,
+    SessionRaw, SessionSubscriptionRaw, SetSessionTieringReply, SetSessionTieringRequest,
+};
+use protocol::mqtt::common::{qos, QoS};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::sync::Arc;
+use storage_adapter::storage::StorageAdapter;
+use tokio::sync::broadcast;
 use tonic::Request;
 
+const REPLAY_TO_CLIENT_MAX_COUNT: u64 = 1000;
+
+const LARGEST_SESSION_DEFAULT_LIMIT: usize = 10;
+const LARGEST_SESSION_MAX_LIMIT: usize = 1000;
+
+// Rough per-entry overheads of the in-memory structures, on top of the payload bytes
+const SESSION_ENTRY_BYTES: u64 = 512;
+const SUBSCRIBE_ENTRY_BYTES: u64 = 256;
+const MESSAGE_ENTRY_BYTES: u64 = 128;
+
+// An exchange younger than this is most likely still progressing and is not cancelled
+const INFLIGHT_QOS2_CANCEL_MIN_AGE_SEC: u64 = 30;
+
+// Fields of a session that can be used in the filter expression of list_session_by_req.
+// keep_alive and clean_session are joined from the connection of the session.
+pub const SESSION_QUERY_FIELDS: &[&str] = &[
+    "client_id",
+    "session_expiry",
+    "is_contain_last_will",
+    "last_will_delay_interval",
+    "create_time",
+    "connection_id",
+    "broker_id",
+    "reconnect_time",
+    "distinct_time",
+    "takeover_count",
+    "last_takeover_at",
+    "keep_alive",
+    "clean_session",
+];
+
+// A session together with the fields of its connection on this broker, for the filter expression
+struct SessionQueryRow {
+    session: SessionRaw,
+    keep_alive: Option<u16>,
+}
+
 pub async fn list_session_by_req(
     cache_manager: &Arc<CacheManager>,
     request: Request<ListSessionRequest>,
 ) -> Result<(Vec<SessionRaw>, usize), MqttBrokerError> {
-    let sessions = extract_sessions(cache_manager);
+    let rows = extract_session_rows(cache_manager);
+    let sessions = apply_filter_expression(rows, &request.get_ref().filter, SESSION_QUERY_FIELDS)?
+        .into_iter()
+        .map(|row| row.session)
+        .collect();
     let filtered = apply_filters(sessions, &request.get_ref().options);
     let sorted = apply_sorting(filtered, &request.get_ref().options);
     let pagination = apply_pagination(sorted, &request.get_ref().options);
@@ -31,13 +101,459 @@ pub async fn list_session_by_req(
     Ok(pagination)
 }
 
-fn extract_sessions(cache_manager: &Arc<CacheManager>) -> Vec<SessionRaw> {
+pub async fn set_session_tiering_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<SetSessionTieringRequest>,
+) -> Result<SetSessionTieringReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let config = SessionTiering {
+        offload_idle_sec: req.offload_idle_sec,
+        check_interval_sec: req.check_interval_sec,
+    };
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::SessionTiering,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_session_tiering_config(config.clone());
+    Ok(SetSessionTieringReply {
+        offload_idle_sec: config.offload_idle_sec,
+        check_interval_sec: config.check_interval_sec,
+    })
+}
+
+// The tiering policy along with how many sessions of this broker are in each tier
+pub fn get_session_tiering_by_req(cache_manager: &Arc<CacheManager>) -> GetSessionTieringReply {
+    let config = cache_manager.get_session_tiering_config();
+    GetSessionTieringReply {
+        offload_idle_sec: config.offload_idle_sec,
+        check_interval_sec: config.check_interval_sec,
+        memory_session_num: cache_manager.session_info.len() as u64,
+        offloaded_session_num: cache_manager.offloaded_sessions.len() as u64,
+        offload_total: get_session_tiering_counter(SESSION_TIERING_ACTION_OFFLOAD),
+        reload_total: get_session_tiering_counter(SESSION_TIERING_ACTION_RELOAD),
+    }
+}
+
+// QoS 2 exchanges of this broker that are still waiting for the peer, oldest first
+pub fn list_inflight_qos2_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<ListInflightQos2Request>,
+) -> Vec<InflightQos2Raw> {
+    let req = request.into_inner();
+    cache_manager
+        .pkid_metadata
+        .list_inflight_qos2(&req.client_id)
+        .into_iter()
+        .map(|raw| InflightQos2Raw {
+            client_id: raw.client_id,
+            pkid: raw.pkid as u32,
+            direction: raw.direction.as_str().to_string(),
+            state: raw.state.to_string(),
+            age_ms: raw.age_ms,
+        })
+        .collect()
+}
+
+// Drop a stuck QoS 2 exchange so the packet identifier can be used again.
+// Refuses exchanges that are younger than min_age_sec, they may still complete on their own.
+pub async fn cancel_inflight_qos2_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<CancelInflightQos2Request>,
+) -> Result<CancelInflightQos2Reply, MqttBrokerError> {
+    let req = request.into_inner();
+    let direction = match req.direction.as_str() {
+        "inbound" => Qos2Direction::Inbound,
+        "outbound" => Qos2Direction::Outbound,
+        _ => {
+            return Err(MqttBrokerError::CommonError(format!(
+                "invalid direction {}, expected inbound or outbound",
+                req.direction
+            )))
+        }
+    };
+    let pkid = u16::try_from(req.pkid).map_err(|_| {
+        MqttBrokerError::CommonError(format!("invalid packet identifier {}", req.pkid))
+    })?;
+
+    let Some(inflight) =
+        cache_manager
+            .pkid_metadata
+            .get_inflight_qos2(&req.client_id, pkid, &direction)
+    else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "no {} QoS 2 exchange in flight for client {} with packet identifier {}",
+            direction.as_str(),
+            req.client_id,
+            pkid
+        )));
+    };
+
+    let min_age_sec = if req.min_age_sec == 0 {
+        INFLIGHT_QOS2_CANCEL_MIN_AGE_SEC
+    } else {
+        req.min_age_sec
+    };
+    if inflight.age_ms < min_age_sec * 1000 {
+        return Err(MqttBrokerError::CommonError(format!(
+            "QoS 2 exchange of client {} with packet identifier {} is only {}ms old, refusing to cancel before {}s",
+            req.client_id, pkid, inflight.age_ms, min_age_sec
+        )));
+    }
+
+    match direction {
+        Qos2Direction::Inbound => {
+            pkid_delete(cache_manager, client_pool, &req.client_id, pkid).await?;
+        }
+        Qos2Direction::Outbound => {
+            cache_manager
+                .pkid_metadata
+                .remove_ack_packet(&req.client_id, pkid);
+        }
+    }
+
+    Ok(CancelInflightQos2Reply {
+        state: inflight.state.to_string(),
+        age_ms: inflight.age_ms,
+    })
+}
+
+// Push the last `count` messages stored for the topic to one connected client without
+// subscribing it. The newest offset of the topic is only known for topics this broker
+// wrote to since it started, so other topics report that they have no history.
+pub async fn replay_to_client_by_req<S>(
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    message_storage_adapter: &Arc<S>,
+    request: Request<ReplayToClientRequest>,
+) -> Result<ReplayToClientReply, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let req = request.into_inner();
+    if req.count == 0 || req.count > REPLAY_TO_CLIENT_MAX_COUNT {
+        return Err(MqttBrokerError::CommonError(format!(
+            "count must be between 1 and {}",
+            REPLAY_TO_CLIENT_MAX_COUNT
+        )));
+    }
+
+    let Some(topic) = cache_manager.get_topic_by_name(&req.topic_name) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Topic {} does not exist",
+            req.topic_name
+        )));
+    };
+
+    let Some(connect_id) = cache_manager.get_connect_id(&req.client_id) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Client {} is offline, messages can only be replayed to a connected client",
+            req.client_id
+        )));
+    };
+
+    let Some(latest_offset) = subscribe_manager.get_topic_latest_offset(&topic.topic_id) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Topic {} has no message history on this broker",
+            req.topic_name
+        )));
+    };
+
+    let record_num = req.count.min(latest_offset + 1);
+    let start_offset = latest_offset + 1 - record_num;
+    let message_storage = MessageStorage::new(message_storage_adapter.clone());
+    let records = message_storage
+        .read_topic_message(&topic.topic_id, start_offset, record_num)
+        .await?;
+
+    let cluster_qos = cache_manager
+        .get_cluster_config()
+        .mqtt_protocol_config
+        .max_qos;
+    let replay_qos = min_qos(
+        qos(cluster_qos).unwrap(),
+        qos(req.qos as u8).unwrap_or(QoS::AtMostOnce),
+    );
+    let subscriber = Subscriber {
+        protocol: connection_manager
+            .get_connect_protocol(connect_id)
+            .unwrap_or_default(),
+        client_id: req.client_id.clone(),
+        topic_name: topic.topic_name.clone(),
+        topic_id: topic.topic_id.clone(),
+        qos: replay_qos,
+        ..Default::default()
+    };
+
+    let (stop_sx, _) = broadcast::channel(1);
+    let mut delivered_num = 0;
+    let mut skipped_num = 0;
+    for record in records {
+        let Some(mut sub_pub_param) = build_publish_message(
+            cache_manager,
+            connection_manager,
+            &req.client_id,
+            record,
+            "",
+            &replay_qos,
+            &subscriber,
+            &[],
+        )
+        .await?
+        else {
+            // expired, or larger than the client accepts
+            skipped_num += 1;
+            continue;
+        };
+
+        // Replayed messages are old on purpose and must not show up as slow subscriptions
+        sub_pub_param.create_time = 0;
+        if let Err(e) = send_publish_packet_to_client(
+            connection_manager,
+            cache_manager,
+            &sub_pub_param,
+            &replay_qos,
+            &stop_sx,
+        )
+        .await
+        {
+            return Err(MqttBrokerError::CommonError(format!(
+                "Replay to client {} stopped after {} messages: {}",
+                req.client_id, delivered_num, e
+            )));
+        }
+        delivered_num += 1;
+    }
+
+    Ok(ReplayToClientReply {
+        delivered_num,
+        skipped_num,
+        start_offset,
+    })
+}
+
+// The subscriptions a session holds on this broker, sorted by topic filter
+pub fn list_session_subscriptions_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<ListSessionSubscriptionsRequest>,
+) -> Result<ListSessionSubscriptionsReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if cache_manager.get_session_info(&req.client_id).is_none()
+        && !cache_manager.is_session_offloaded(&req.client_id)
+    {
+        return Err(MqttBrokerError::CommonError(format!(
+            "session {} does not exist on this broker",
+            req.client_id
+        )));
+    }
+
+    let mut subscriptions: Vec<SessionSubscriptionRaw> = subscribe_manager
+        .subscribe_list
+        .iter()
+        .filter(|entry| entry.client_id == req.client_id)
+        .map(|entry| SessionSubscriptionRaw {
+            path: entry.path.clone(),
+            qos: entry.filter.qos as u32,
+            no_local: entry.filter.nolocal,
+            preserve_retain: entry.filter.preserve_retain,
+            retain_handling: u8::from(entry.filter.retain_handling.clone()) as u32,
+            subscription_identifier: entry
+                .subscribe_properties
+                .as_ref()
+                .and_then(|properties| properties.subscription_identifier)
+                .map(|id| id as u64),
+        })
+        .collect();
+    subscriptions.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(ListSessionSubscriptionsReply {
+        client_id: req.client_id,
+        subscriptions,
+    })
+}
+
+// Rank the sessions of this broker by estimated memory footprint, keeping only the top N
+pub fn list_largest_session_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<ListLargestSessionRequest>,
+) -> Result<Vec<SessionMemoryRaw>, MqttBrokerError> {
+    let req = request.into_inner();
+    let limit = match req.limit as usize {
+        0 => LARGEST_SESSION_DEFAULT_LIMIT,
+        limit => limit.min(LARGEST_SESSION_MAX_LIMIT),
+    };
+
+    let mut subscribe_nums: HashMap<String, u64> = HashMap::new();
+    for entry in subscribe_manager.subscribe_list.iter() {
+        *subscribe_nums.entry(entry.client_id.clone()).or_default() += 1;
+    }
+
+    // (client_id, (queued messages, queued bytes, sum of the average payload of its topics, topic num))
+    let mut queues: HashMap<String, (u64, u64, u64, u64)> = HashMap::new();
+    for progress in subscribe_manager.push_progress.iter() {
+        let queued_num =
+            progress.queued_num(subscribe_manager.get_topic_latest_offset(&progress.topic_id));
+        let avg_payload_size = cache_manager.get_topic_avg_payload_size(&progress.topic_name);
+        let queue = queues.entry(progress.client_id.clone()).or_default();
+        queue.0 += queued_num;
+        queue.1 += queued_num * (avg_payload_size + MESSAGE_ENTRY_BYTES);
+        queue.2 += avg_payload_size;
+        queue.3 += 1;
+    }
+
+    let sessions = cache_manager.session_info.iter().map(|entry| {
+        let client_id = entry.key().clone();
+        let (queued_num, queue_bytes, payload_size_sum, topic_num) =
+            queues.get(&client_id).copied().unwrap_or_default();
+        let subscribe_num = subscribe_nums.get(&client_id).copied().unwrap_or_default();
+        let inflight_num = cache_manager.pkid_metadata.ack_packet_num(&client_id) as u64;
+        let avg_payload_size = payload_size_sum.checked_div(topic_num).unwrap_or(0);
+        let inflight_bytes = inflight_num * (avg_payload_size + MESSAGE_ENTRY_BYTES);
+
+        SessionMemoryRaw {
+            is_online: entry.connection_id.is_some(),
+            estimated_bytes: SESSION_ENTRY_BYTES
+                + queue_bytes
+                + subscribe_num * SUBSCRIBE_ENTRY_BYTES
+                + inflight_bytes,
+            client_id,
+            queue_bytes,
+            queued_num,
+            subscribe_num,
+            inflight_num,
+            inflight_bytes,
+        }
+    });
+
+    Ok(top_sessions_by_memory(sessions, limit))
+}
+
+// Largest first, only `limit` sessions are held at any time
+fn top_sessions_by_memory(
+    sessions: impl Iterator<Item = SessionMemoryRaw>,
+    limit: usize,
+) -> Vec<SessionMemoryRaw> {
+    let mut heap: BinaryHeap<Reverse<(u64, String)>> = BinaryHeap::with_capacity(limit + 1);
+    let mut rows: HashMap<String, SessionMemoryRaw> = HashMap::with_capacity(limit + 1);
+    for session in sessions {
+        heap.push(Reverse((
+            session.estimated_bytes,
+            session.client_id.clone(),
+        )));
+        rows.insert(session.client_id.clone(), session);
+        if heap.len() > limit {
+            if let Some(Reverse((_, client_id))) = heap.pop() {
+                rows.remove(&client_id);
+            }
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .filter_map(|Reverse((_, client_id))| rows.remove(&client_id))
+        .collect()
+}
+
+// Sessions in the session store whose expiry interval has elapsed since the disconnect.
+// The placement center removes them on its own, anything listed here was missed.
+pub async fn list_expired_sessions_by_req(
+    client_pool: &Arc<ClientPool>,
+) -> Result<ListExpiredSessionsReply, MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    let sessions = session_storage.list_session().await?;
+    Ok(ListExpiredSessionsReply {
+        sessions: collect_expired_sessions(
+            sessions.into_iter().map(|(_, session)| session),
+            now_second(),
+        ),
+    })
+}
+
+// Removes the expired sessions from the session store and from this broker
+pub async fn purge_expired_sessions_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<PurgeExpiredSessionsReply, MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    let sessions = session_storage.list_session().await?;
+    let expired = collect_expired_sessions(
+        sessions.into_iter().map(|(_, session)| session),
+        now_second(),
+    );
+
+    let mut purged_sessions = Vec::with_capacity(expired.len());
+    for session in expired {
+        session_storage
+            .delete_session(session.client_id.clone())
+            .await?;
+        subscribe_manager.remove_client_id(&session.client_id);
+        cache_manager.remove_session(&session.client_id);
+        purged_sessions.push(session);
+    }
+    Ok(PurgeExpiredSessionsReply {
+        purged_num: purged_sessions.len() as u64,
+        sessions: purged_sessions,
+    })
+}
+
+// Same rule as the session expiry of the placement center: only a session that is
+// neither connected nor held by a broker expires
+fn session_expired_at(session: &MqttSession) -> Option<u64> {
+    if session.connection_id.is_some() || session.broker_id.is_some() {
+        return None;
+    }
+    session
+        .distinct_time
+        .map(|distinct_time| distinct_time + session.session_expiry)
+}
+
+// The expired sessions at `now`, longest overdue first
+fn collect_expired_sessions(
+    sessions: impl Iterator<Item = MqttSession>,
+    now: u64,
+) -> Vec<ExpiredSessionRaw> {
+    let mut expired: Vec<ExpiredSessionRaw> = sessions
+        .filter_map(|session| {
+            let expired_at = session_expired_at(&session)?;
+            if now < expired_at {
+                return None;
+            }
+            Some(ExpiredSessionRaw {
+                session_expiry: session.session_expiry,
+                distinct_time: session.distinct_time.unwrap_or_default(),
+                expired_at,
+                overdue_sec: now - expired_at,
+                client_id: session.client_id,
+            })
+        })
+        .collect();
+    expired.sort_by(|a, b| {
+        a.expired_at
+            .cmp(&b.expired_at)
+            .then_with(|| a.client_id.cmp(&b.client_id))
+    });
+    expired
+}
+
+fn extract_session_rows(cache_manager: &Arc<CacheManager>) -> Vec<SessionQueryRow> {
     cache_manager
         .session_info
         .iter()
         .map(|entry| {
             let session = entry.value();
-            SessionRaw {
+            let keep_alive = session
+                .connection_id
+                .and_then(|connect_id| cache_manager.get_connection(connect_id))
+                .map(|connection| connection.keep_alive);
+            let session = SessionRaw {
                 client_id: session.client_id.clone(),
                 session_expiry: session.session_expiry,
                 is_contain_last_will: session.is_contain_last_will,
@@ -47,11 +563,28 @@ fn extract_sessions(cache_manager: &Arc<CacheManager>) -> Vec<SessionRaw> {
                 broker_id: session.broker_id,
                 reconnect_time: session.reconnect_time,
                 distinct_time: session.distinct_time,
+                takeover_count: session.takeover_count,
+                last_takeover_at: session.last_takeover_at,
+            };
+            SessionQueryRow {
+                session,
+                keep_alive,
             }
         })
         .collect()
 }
 
+impl Queryable for SessionQueryRow {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "keep_alive" => self.keep_alive.map(|v| v.to_string()),
+            // clean session is true when session_expiry is 0 (MQTT 5.0)
+            "clean_session" => Some((self.session.session_expiry == 0).to_string()),
+            _ => self.session.get_field_str(field),
+        }
+    }
+}
+
 impl Queryable for SessionRaw {
     fn get_field_str(&self, field: &str) -> Option<String> {
         match field {
@@ -64,7 +597,272 @@ fn get_field_str(&self, field: &str) -> Option<String> {
             "broker_id" => self.broker_id.map(|v| v.to_string()),
             "reconnect_time" => self.reconnect_time.map(|v| v.to_string()),
             "distinct_time" => self.distinct_time.map(|v| v.to_string()),
+            "takeover_count" => Some(self.takeover_count.to_string()),
+            "last_takeover_at" => self.last_takeover_at.map(|v| v.to_string()),
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{
+        collect_expired_sessions, list_session_by_req, list_session_subscriptions_by_req,
+        top_sessions_by_memory,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::handler::error::MqttBrokerError;
+    use crate::subscribe::manager::SubscribeManager;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::MQTTConnection;
+    use metadata_struct::mqtt::session::MqttSession;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use protocol::broker_mqtt::broker_mqtt_admin::{
+        ListSessionRequest, ListSessionSubscriptionsRequest, SessionMemoryRaw,
+    };
+    use protocol::mqtt::common::{Filter, QoS, RetainHandling, SubscribeProperties};
+    use tonic::Request;
+
+    #[tokio::test]
+    async fn list_session_by_filter_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        for (client_id, session_expiry, is_contain_last_will, connection) in [
+            ("sensor-1", 30, false, Some((1, 30))),
+            ("sensor-2", 3600, false, None),
+            ("gateway-1", 7200, true, Some((3, 120))),
+            ("gateway-2", 0, false, Some((4, 120))),
+        ] {
+            cache_manager.add_session(
+                client_id,
+                &MqttSession {
+                    client_id: client_id.to_string(),
+                    session_expiry,
+                    is_contain_last_will,
+                    ..Default::default()
+                },
+            );
+            if let Some((connect_id, keep_alive)) = connection {
+                cache_manager.add_connection(
+                    connect_id,
+                    MQTTConnection {
+                        connect_id,
+                        client_id: client_id.to_string(),
+                        keep_alive,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+
+        let list = |filter: &str| {
+            let cache_manager = cache_manager.clone();
+            let request = Request::new(ListSessionRequest {
+                options: None,
+                filter: filter.to_string(),
+            });
+            async move {
+                list_session_by_req(&cache_manager, request)
+                    .await
+                    .map(|(sessions, count)| {
+                        let mut client_ids: Vec<String> =
+                            sessions.into_iter().map(|s| s.client_id).collect();
+                        client_ids.sort();
+                        (client_ids, count)
+                    })
+            }
+        };
+
+        let (client_ids, count) = list("").await.unwrap();
+        assert_eq!(
+            client_ids,
+            vec!["gateway-1", "gateway-2", "sensor-1", "sensor-2"]
+        );
+        assert_eq!(count, 4);
+
+        let (client_ids, count) = list("is_contain_last_will = false AND session_expiry > 60")
+            .await
+            .unwrap();
+        assert_eq!(client_ids, vec!["sensor-2"]);
+        assert_eq!(count, 1);
+
+        let (client_ids, _) = list("client_id LIKE 'sensor-%' AND connection_id IS NOT NULL")
+            .await
+            .unwrap();
+        assert_eq!(client_ids, vec!["sensor-1"]);
+
+        let (client_ids, _) = list("connection_id IS NULL OR client_id IN ('gateway-1')")
+            .await
+            .unwrap();
+        assert_eq!(client_ids, vec!["gateway-1", "sensor-2"]);
+
+        let (client_ids, _) = list("clean_session = false AND keep_alive > 60")
+            .await
+            .unwrap();
+        assert_eq!(client_ids, vec!["gateway-1"]);
+
+        // an offline session has no keep alive
+        let (client_ids, _) = list("keep_alive IS NULL").await.unwrap();
+        assert_eq!(client_ids, vec!["sensor-2"]);
+
+        let err = list("username = 'admin'").await.unwrap_err();
+        assert!(matches!(err, MqttBrokerError::InvalidFilterExpression(_)));
+        assert!(err.to_string().contains("unknown field 'username'"));
+        assert!(err.to_string().contains("keep_alive"));
+
+        assert!(matches!(
+            list("session_expiry >").await,
+            Err(MqttBrokerError::InvalidFilterExpression(_))
+        ));
+    }
+
+    #[test]
+    fn list_session_subscriptions_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        for client_id in ["c1", "c2"] {
+            cache_manager.add_session(
+                client_id,
+                &MqttSession {
+                    client_id: client_id.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        for (client_id, path, qos, subscription_identifier) in [
+            ("c1", "/b/#", QoS::ExactlyOnce, Some(7)),
+            ("c1", "/a/+", QoS::AtMostOnce, None),
+            ("c2", "/c", QoS::AtLeastOnce, Some(1)),
+        ] {
+            subscribe_manager.add_subscribe(MqttSubscribe {
+                client_id: client_id.to_string(),
+                path: path.to_string(),
+                filter: Filter {
+                    path: path.to_string(),
+                    qos,
+                    nolocal: true,
+                    preserve_retain: false,
+                    retain_handling: RetainHandling::OnNewSubscribe,
+                },
+                subscribe_properties: Some(SubscribeProperties {
+                    subscription_identifier,
+                    user_properties: Vec::new(),
+                }),
+                ..Default::default()
+            });
+        }
+
+        let reply = list_session_subscriptions_by_req(
+            &cache_manager,
+            &subscribe_manager,
+            Request::new(ListSessionSubscriptionsRequest {
+                client_id: "c1".to_string(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(reply.client_id, "c1");
+        let paths: Vec<&str> = reply
+            .subscriptions
+            .iter()
+            .map(|s| s.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["/a/+", "/b/#"]);
+        assert_eq!(reply.subscriptions[0].qos, 0);
+        assert_eq!(reply.subscriptions[0].subscription_identifier, None);
+        assert_eq!(reply.subscriptions[1].qos, 2);
+        assert_eq!(reply.subscriptions[1].subscription_identifier, Some(7));
+        assert!(reply.subscriptions[1].no_local);
+        assert_eq!(reply.subscriptions[1].retain_handling, 1);
+
+        // A session without subscriptions returns an empty list
+        cache_manager.add_session(
+            "c3",
+            &MqttSession {
+                client_id: "c3".to_string(),
+                ..Default::default()
+            },
+        );
+        let reply = list_session_subscriptions_by_req(
+            &cache_manager,
+            &subscribe_manager,
+            Request::new(ListSessionSubscriptionsRequest {
+                client_id: "c3".to_string(),
+            }),
+        )
+        .unwrap();
+        assert!(reply.subscriptions.is_empty());
+
+        let res = list_session_subscriptions_by_req(
+            &cache_manager,
+            &subscribe_manager,
+            Request::new(ListSessionSubscriptionsRequest {
+                client_id: "unknown".to_string(),
+            }),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn top_sessions_by_memory_test() {
+        let sessions = [("c1", 300), ("c2", 100), ("c3", 500), ("c4", 200)]
+            .into_iter()
+            .map(|(client_id, estimated_bytes)| SessionMemoryRaw {
+                client_id: client_id.to_string(),
+                estimated_bytes,
+                ..Default::default()
+            });
+
+        let top = top_sessions_by_memory(sessions, 2);
+        let client_ids: Vec<&str> = top.iter().map(|s| s.client_id.as_str()).collect();
+        assert_eq!(client_ids, vec!["c3", "c1"]);
+    }
+
+    #[test]
+    fn collect_expired_sessions_test() {
+        let now = 10_000;
+        let session = |client_id: &str, session_expiry, distinct_time, connection_id| MqttSession {
+            client_id: client_id.to_string(),
+            session_expiry,
+            distinct_time,
+            connection_id,
+            ..Default::default()
+        };
+        let sessions = vec![
+            // expired 100s ago
+            session("c1", 900, Some(9_000), None),
+            // expired 1000s ago
+            session("c2", 0, Some(9_000), None),
+            // expires right now
+            session("c3", 1_000, Some(9_000), None),
+            // still within its expiry interval
+            session("c4", 3_600, Some(9_000), None),
+            // connected sessions never expire
+            session("c5", 0, Some(1_000), Some(7)),
+            // never disconnected
+            session("c6", 0, None, None),
+            MqttSession {
+                broker_id: Some(1),
+                ..session("c7", 0, Some(1_000), None)
+            },
+        ];
+
+        let expired = collect_expired_sessions(sessions.into_iter(), now);
+        let client_ids: Vec<&str> = expired.iter().map(|s| s.client_id.as_str()).collect();
+        assert_eq!(client_ids, vec!["c2", "c1", "c3"]);
+        assert_eq!(expired[0].expired_at, 9_000);
+        assert_eq!(expired[0].overdue_sec, 1_000);
+        assert_eq!(expired[1].overdue_sec, 100);
+        assert_eq!(expired[2].overdue_sec, 0);
+
+        // Once the clock passes its expiry the live session shows up as well
+        let sessions = vec![session("c4", 3_600, Some(9_000), None)];
+        assert!(collect_expired_sessions(sessions.clone().into_iter(), now).is_empty());
+        let expired = collect_expired_sessions(sessions.into_iter(), 12_600);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].client_id, "c4");
+    }
+}
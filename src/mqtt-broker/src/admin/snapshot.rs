@@ -0,0 +1,505 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::security::AuthDriver;
+use crate::storage::connector::ConnectorStorage;
+use crate::storage::session::SessionStorage;
+use crate::storage::topic::TopicStorage;
+use common_base::tools::now_second;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::placement::inner::call::{bind_schema, create_schema};
+use grpc_clients::placement::mqtt::call::{placement_export_metadata, placement_set_subscribe};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
+use metadata_struct::mqtt::bridge::connector::MQTTConnector;
+use metadata_struct::mqtt::message::MqttMessage;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use metadata_struct::mqtt::topic::MqttTopic;
+use metadata_struct::mqtt::user::MqttUser;
+use metadata_struct::schema::{SchemaData, SchemaResourceBind};
+use protocol::broker_mqtt::broker_mqtt_admin::{
+    CreateSnapshotReply, RestoreSnapshotReply, RestoreSnapshotRequest, SnapshotCategoryRaw,
+};
+use protocol::placement_center::placement_center_inner::{BindSchemaRequest, CreateSchemaRequest};
+use protocol::placement_center::placement_center_mqtt::{
+    ExportMetadataRequest, SetSubscribeRequest,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use storage_adapter::StorageType;
+use tonic::Request;
+
+// Bumped whenever the layout of BrokerSnapshot or of one of the snapshotted
+// structs changes in a way older brokers cannot read
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+pub const SNAPSHOT_STATUS_OK: &str = "ok";
+pub const SNAPSHOT_STATUS_FAILED: &str = "failed";
+pub const SNAPSHOT_STATUS_SKIPPED: &str = "skipped";
+
+const CATEGORY_USER: &str = "user";
+const CATEGORY_ACL: &str = "acl";
+const CATEGORY_BLACKLIST: &str = "blacklist";
+const CATEGORY_SCHEMA: &str = "schema";
+const CATEGORY_CONNECTOR: &str = "connector";
+const CATEGORY_RETAIN_MESSAGE: &str = "retain_message";
+const CATEGORY_SESSION: &str = "session";
+const CATEGORY_SUBSCRIBE: &str = "subscribe";
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct BrokerSnapshot {
+    pub format_version: u32,
+    pub broker_version: String,
+    pub cluster_name: String,
+    pub create_time: u64,
+    pub users: Vec<MqttUser>,
+    pub acls: Vec<MqttAcl>,
+    pub blacklists: Vec<MqttAclBlackList>,
+    pub schemas: Vec<SchemaData>,
+    pub schema_binds: Vec<SchemaResourceBind>,
+    pub connectors: Vec<MQTTConnector>,
+    // Topics that hold a retained message, the message is kept inside the topic
+    pub retain_topics: Vec<MqttTopic>,
+    pub sessions: Vec<MqttSession>,
+    pub subscribes: Vec<MqttSubscribe>,
+}
+
+impl BrokerSnapshot {
+    pub fn encode(&self) -> Result<Vec<u8>, MqttBrokerError> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, MqttBrokerError> {
+        let snapshot = serde_json::from_slice::<BrokerSnapshot>(data)?;
+        if snapshot.format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(MqttBrokerError::CommonError(format!(
+                "snapshot format version {} (broker {}) cannot be restored by this broker, which supports format version {}",
+                snapshot.format_version, snapshot.broker_version, SNAPSHOT_FORMAT_VERSION
+            )));
+        }
+        Ok(snapshot)
+    }
+}
+
+// Capture users, ACLs, blacklist, schemas, connectors, retained messages, sessions and
+// subscriptions. They are exported by the placement center in a single read, so the snapshot
+// is a point-in-time view of the cluster. Users, ACLs and blacklist kept by an external auth
+// storage are read from it separately.
+pub async fn create_snapshot_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<CreateSnapshotReply, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let request = ExportMetadataRequest {
+        cluster_name: conf.cluster_name.clone(),
+    };
+    let metadata = placement_export_metadata(client_pool, &conf.placement_center, request).await?;
+
+    let (users, acls, blacklists) = if is_placement_auth_storage(&conf.auth_storage.storage_type) {
+        (
+            decode_metadata::<MqttUser>(&metadata.users)?,
+            decode_metadata::<MqttAcl>(&metadata.acls)?,
+            decode_metadata::<MqttAclBlackList>(&metadata.blacklists)?,
+        )
+    } else {
+        let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+        let users = auth_driver
+            .read_all_user()
+            .await?
+            .into_iter()
+            .map(|(_, user)| user)
+            .collect();
+        (
+            users,
+            auth_driver.read_all_acl().await?,
+            auth_driver.read_all_blacklist().await?,
+        )
+    };
+
+    let retain_topics: Vec<MqttTopic> = decode_metadata::<MqttTopic>(&metadata.topics)?
+        .into_iter()
+        .filter(|topic| {
+            topic
+                .retain_message
+                .as_ref()
+                .is_some_and(|message| !message.is_empty())
+        })
+        .collect();
+
+    let snapshot = BrokerSnapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        broker_version: env!("CARGO_PKG_VERSION").to_string(),
+        cluster_name: conf.cluster_name.clone(),
+        create_time: now_second(),
+        users,
+        acls,
+        blacklists,
+        schemas: decode_metadata(&metadata.schemas)?,
+        schema_binds: decode_metadata(&metadata.schema_binds)?,
+        connectors: decode_metadata(&metadata.connectors)?,
+        retain_topics,
+        sessions: decode_metadata(&metadata.sessions)?,
+        subscribes: decode_metadata(&metadata.subscribes)?,
+    };
+
+    Ok(CreateSnapshotReply {
+        format_version: snapshot.format_version,
+        broker_version: snapshot.broker_version.clone(),
+        categories: snapshot_categories(&snapshot),
+        snapshot: snapshot.encode()?,
+    })
+}
+
+// Rebuild the broker state from a snapshot. Categories are restored in dependency order,
+// a failed category does not stop the restore, but the categories depending on it are skipped.
+pub async fn restore_snapshot_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<RestoreSnapshotRequest>,
+) -> Result<RestoreSnapshotReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let snapshot = BrokerSnapshot::decode(&req.snapshot)?;
+    check_snapshot_cluster(&snapshot, &cache_manager.cluster_name)?;
+    let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
+    let mut results = Vec::new();
+
+    restore_category(
+        &mut results,
+        CATEGORY_USER,
+        snapshot.users.len(),
+        &[],
+        async {
+            for user in snapshot.users.iter() {
+                // Users created when the broker started, such as the default admin, are kept
+                if cache_manager.user_info.contains_key(&user.username) {
+                    continue;
+                }
+                auth_driver.save_user(user.clone()).await?;
+            }
+            Ok::<(), MqttBrokerError>(())
+        },
+    )
+    .await;
+
+    restore_category(
+        &mut results,
+        CATEGORY_ACL,
+        snapshot.acls.len(),
+        &[CATEGORY_USER],
+        async {
+            for acl in snapshot.acls.iter() {
+                auth_driver.save_acl(acl.clone()).await?;
+            }
+            Ok::<(), MqttBrokerError>(())
+        },
+    )
+    .await;
+
+    restore_category(
+        &mut results,
+        CATEGORY_BLACKLIST,
+        snapshot.blacklists.len(),
+        &[],
+        async {
+            for blacklist in snapshot.blacklists.iter() {
+                auth_driver.save_blacklist(blacklist.clone()).await?;
+            }
+            Ok::<(), MqttBrokerError>(())
+        },
+    )
+    .await;
+
+    restore_category(
+        &mut results,
+        CATEGORY_SCHEMA,
+        snapshot.schemas.len(),
+        &[],
+        restore_schema(client_pool, &snapshot.schemas, &snapshot.schema_binds),
+    )
+    .await;
+
+    restore_category(
+        &mut results,
+        CATEGORY_CONNECTOR,
+        snapshot.connectors.len(),
+        &[],
+        async {
+            let connector_storage = ConnectorStorage::new(client_pool.clone());
+            for connector in snapshot.connectors.iter() {
+                connector_storage
+                    .create_connector(connector.clone())
+                    .await?;
+            }
+            Ok::<(), MqttBrokerError>(())
+        },
+    )
+    .await;
+
+    restore_category(
+        &mut results,
+        CATEGORY_RETAIN_MESSAGE,
+        snapshot.retain_topics.len(),
+        &[],
+        restore_retain_message(cache_manager, client_pool, &snapshot.retain_topics),
+    )
+    .await;
+
+    restore_category(
+        &mut results,
+        CATEGORY_SESSION,
+        snapshot.sessions.len(),
+        &[],
+        async {
+            let session_storage = SessionStorage::new(client_pool.clone());
+            for session in snapshot.sessions.iter() {
+                session_storage
+                    .set_session(session.client_id.clone(), session)
+                    .await?;
+            }
+            Ok::<(), MqttBrokerError>(())
+        },
+    )
+    .await;
+
+    restore_category(
+        &mut results,
+        CATEGORY_SUBSCRIBE,
+        snapshot.subscribes.len(),
+        &[CATEGORY_SESSION],
+        restore_subscribe(client_pool, &snapshot.subscribes),
+    )
+    .await;
+
+    Ok(RestoreSnapshotReply {
+        format_version: snapshot.format_version,
+        broker_version: snapshot.broker_version,
+        success: results
+            .iter()
+            .all(|result| result.status == SNAPSHOT_STATUS_OK),
+        categories: results,
+    })
+}
+
+async fn restore_category<F>(
+    results: &mut Vec<SnapshotCategoryRaw>,
+    category: &str,
+    num: usize,
+    depends_on: &[&str],
+    restore: F,
+) where
+    F: Future<Output = Result<(), MqttBrokerError>>,
+{
+    let failed_dependency = results.iter().find(|result| {
+        depends_on.contains(&result.category.as_str()) && result.status != SNAPSHOT_STATUS_OK
+    });
+
+    let (status, message) = if let Some(dependency) = failed_dependency {
+        (
+            SNAPSHOT_STATUS_SKIPPED,
+            format!("depends on {}, which was not restored", dependency.category),
+        )
+    } else {
+        match restore.await {
+            Ok(()) => (SNAPSHOT_STATUS_OK, String::new()),
+            Err(e) => (SNAPSHOT_STATUS_FAILED, e.to_string()),
+        }
+    };
+
+    results.push(SnapshotCategoryRaw {
+        category: category.to_string(),
+        num: num as u64,
+        status: status.to_string(),
+        message,
+    });
+}
+
+fn snapshot_categories(snapshot: &BrokerSnapshot) -> Vec<SnapshotCategoryRaw> {
+    [
+        (CATEGORY_USER, snapshot.users.len()),
+        (CATEGORY_ACL, snapshot.acls.len()),
+        (CATEGORY_BLACKLIST, snapshot.blacklists.len()),
+        (CATEGORY_SCHEMA, snapshot.schemas.len()),
+        (CATEGORY_CONNECTOR, snapshot.connectors.len()),
+        (CATEGORY_RETAIN_MESSAGE, snapshot.retain_topics.len()),
+        (CATEGORY_SESSION, snapshot.sessions.len()),
+        (CATEGORY_SUBSCRIBE, snapshot.subscribes.len()),
+    ]
+    .into_iter()
+    .map(|(category, num)| SnapshotCategoryRaw {
+        category: category.to_string(),
+        num: num as u64,
+        status: SNAPSHOT_STATUS_OK.to_string(),
+        message: String::new(),
+    })
+    .collect()
+}
+
+// A snapshot carries cluster scoped data such as connectors bound to broker ids, so it is only
+// restored into the cluster it was taken from
+fn check_snapshot_cluster(
+    snapshot: &BrokerSnapshot,
+    cluster_name: &str,
+) -> Result<(), MqttBrokerError> {
+    if snapshot.cluster_name != cluster_name {
+        return Err(MqttBrokerError::CommonError(format!(
+            "snapshot was taken from cluster {}, it cannot be restored into cluster {}",
+            snapshot.cluster_name, cluster_name
+        )));
+    }
+    Ok(())
+}
+
+fn is_placement_auth_storage(storage_type: &str) -> bool {
+    matches!(
+        StorageType::from_str(storage_type),
+        Ok(StorageType::Placement)
+    )
+}
+
+fn decode_metadata<T>(raws: &[Vec<u8>]) -> Result<Vec<T>, MqttBrokerError>
+where
+    T: DeserializeOwned,
+{
+    let mut results = Vec::new();
+    for raw in raws.iter() {
+        results.push(serde_json::from_slice::<T>(raw)?);
+    }
+    Ok(results)
+}
+
+async fn restore_schema(
+    client_pool: &Arc<ClientPool>,
+    schemas: &[SchemaData],
+    schema_binds: &[SchemaResourceBind],
+) -> Result<(), MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    for schema in schemas.iter() {
+        let request = CreateSchemaRequest {
+            cluster_name: conf.cluster_name.clone(),
+            schema_name: schema.name.clone(),
+            schema: serde_json::to_vec(schema)?,
+        };
+        create_schema(client_pool, &conf.placement_center, request).await?;
+    }
+
+    for bind in schema_binds.iter() {
+        let request = BindSchemaRequest {
+            cluster_name: conf.cluster_name.clone(),
+            schema_name: bind.schema_name.clone(),
+            resource_name: bind.resource_name.clone(),
+        };
+        bind_schema(client_pool, &conf.placement_center, request).await?;
+    }
+    Ok(())
+}
+
+async fn restore_retain_message(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    retain_topics: &[MqttTopic],
+) -> Result<(), MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    for topic in retain_topics.iter() {
+        topic_storage.save_topic(topic.clone()).await?;
+        if let Some(retain_message) = topic.retain_message.as_ref() {
+            let message = serde_json::from_slice::<MqttMessage>(retain_message)?;
+            topic_storage
+                .set_retain_message(
+                    topic.topic_name.clone(),
+                    &message,
+                    topic.retain_message_expired_at.unwrap_or_default(),
+                )
+                .await?;
+        }
+        cache_manager.add_topic(&topic.topic_name, topic);
+    }
+    Ok(())
+}
+
+async fn restore_subscribe(
+    client_pool: &Arc<ClientPool>,
+    subscribes: &[MqttSubscribe],
+) -> Result<(), MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    for subscribe in subscribes.iter() {
+        let request = SetSubscribeRequest {
+            cluster_name: conf.cluster_name.clone(),
+            client_id: subscribe.client_id.clone(),
+            path: subscribe.path.clone(),
+            subscribe: subscribe.encode(),
+        };
+        placement_set_subscribe(client_pool, &conf.placement_center, request).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_snapshot_cluster, restore_category, BrokerSnapshot, SNAPSHOT_FORMAT_VERSION,
+        SNAPSHOT_STATUS_FAILED, SNAPSHOT_STATUS_OK, SNAPSHOT_STATUS_SKIPPED,
+    };
+    use crate::handler::error::MqttBrokerError;
+
+    #[test]
+    fn snapshot_version_test() {
+        let snapshot = BrokerSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            ..Default::default()
+        };
+        let data = snapshot.encode().unwrap();
+        assert!(BrokerSnapshot::decode(&data).is_ok());
+
+        let snapshot = BrokerSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION + 1,
+            ..Default::default()
+        };
+        let data = snapshot.encode().unwrap();
+        assert!(BrokerSnapshot::decode(&data).is_err());
+    }
+
+    #[test]
+    fn snapshot_cluster_test() {
+        let snapshot = BrokerSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            cluster_name: "cluster-a".to_string(),
+            ..Default::default()
+        };
+        assert!(check_snapshot_cluster(&snapshot, "cluster-a").is_ok());
+        assert!(check_snapshot_cluster(&snapshot, "cluster-b").is_err());
+    }
+
+    #[tokio::test]
+    async fn restore_category_dependency_test() {
+        let mut results = Vec::new();
+        restore_category(&mut results, "session", 1, &[], async {
+            Err(MqttBrokerError::CommonError(
+                "store unavailable".to_string(),
+            ))
+        })
+        .await;
+        restore_category(&mut results, "subscribe", 1, &["session"], async { Ok(()) }).await;
+        restore_category(&mut results, "user", 1, &[], async { Ok(()) }).await;
+
+        assert_eq!(results[0].status, SNAPSHOT_STATUS_FAILED);
+        assert_eq!(results[1].status, SNAPSHOT_STATUS_SKIPPED);
+        assert_eq!(results[2].status, SNAPSHOT_STATUS_OK);
+    }
+}
@@ -12,20 +12,61 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::admin::query::{apply_filters, apply_pagination, apply_sorting, Queryable};
 use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
+use crate::handler::sub_auto::sort_auto_subscribe_rules;
+use crate::handler::topic_rewrite::convert_sub_path_by_rewrite_rule;
+use crate::observability::metrics::subscribe::get_shared_dispatch_skipped_full_counter;
 use crate::storage::auto_subscribe::AutoSubscribeStorage;
+use crate::storage::message::MessageStorage;
+use crate::storage::session::SessionStorage;
+use crate::storage::subscribe_snapshot::SubscribeSnapshotStorage;
+use crate::subscribe::common::{
+    decode_share_info, decode_sub_path, is_queue_sub, is_share_sub, is_wildcards, min_qos,
+    SHARE_QUEUE_DEFAULT_GROUP_NAME,
+};
+use crate::subscribe::manager::{
+    ShareLeaderSubscribeData, SubscribeManager, SubscribePushProgress,
+};
+use crate::subscribe::snapshot::subscribes_to_restore;
 
+use common_base::tools::now_second;
+use common_base::utils::topic_util::is_exclusive_sub;
 use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::{SharedDispatchMode, SharedGroupDispatch};
+use grpc_clients::placement::mqtt::call::placement_set_subscribe;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::adapter::record::Record;
 use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    DeleteAutoSubscribeRuleRequest, SetAutoSubscribeRuleRequest,
+    BenchmarkMatcherReply, BenchmarkMatcherRequest, DeleteAutoSubscribeRuleRequest,
+    DescribeSubscriptionReply, DescribeSubscriptionRequest, GetSubscribeSnapshotReply,
+    GetSubscribeSnapshotRequest, ListSharedDispatchRequest, ListSharedSubscriptionsRequest,
+    ListSubscribeDetailRequest, RestoreSubscribeSnapshotReply, RestoreSubscribeSnapshotRequest,
+    SetAutoSubscribeRuleRequest, SetSharedDispatchRequest, SharedDispatchRaw,
+    SharedSubscriptionMemberRaw, SharedSubscriptionRaw, SubscribeDetailRaw, SubscriptionTopicRaw,
 };
-use protocol::mqtt::common::{qos, retain_forward_rule, Error};
+use protocol::mqtt::common::{qos, retain_forward_rule, QoS, RetainHandling};
+use protocol::placement_center::placement_center_mqtt::SetSubscribeRequest;
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Instant;
+use storage_adapter::storage::StorageAdapter;
 use tonic::Request;
 
+// The backlog of a subscription is counted up to this many messages
+const SUBSCRIBE_BACKLOG_MAX_READ_NUM: u64 = 1000;
+
+const BENCHMARK_MATCHER_DEFAULT_LOOKUPS: u64 = 1000;
+const BENCHMARK_MATCHER_MAX_LOOKUPS: u64 = 100000;
+const BENCHMARK_MATCHER_SYNTHETIC_LEVEL: &str = "benchmark";
+
+pub const BENCHMARK_TOPIC_SOURCE_REQUEST: &str = "request";
+pub const BENCHMARK_TOPIC_SOURCE_SAMPLED: &str = "sampled";
+pub const BENCHMARK_TOPIC_SOURCE_SYNTHETIC: &str = "synthetic";
+
 pub async fn set_auto_subscribe_rule(
     client_pool: &Arc<ClientPool>,
     cache_manager: &Arc<CacheManager>,
@@ -34,44 +75,31 @@ pub async fn set_auto_subscribe_rule(
     let req = request.into_inner();
     let config = broker_mqtt_conf();
 
-    // Validate and convert QoS
-    let _qos = if req.qos <= u8::MAX as u32 {
-        qos(req.qos as u8)
-    } else {
-        return Err(MqttBrokerError::CommonError(
-            Error::InvalidRemainingLength(req.qos as usize).to_string(),
-        ));
-    };
+    let (qos, retained_handling) = parse_auto_subscribe_options(req.qos, req.retained_handling)?;
 
-    // Validate and convert RetainHandling
-    let _retained_handling = if req.retained_handling <= u8::MAX as u32 {
-        retain_forward_rule(req.retained_handling as u8)
-    } else {
-        return Err(MqttBrokerError::CommonError(
-            Error::InvalidRemainingLength(req.retained_handling as usize).to_string(),
-        ));
-    };
-
-    let auto_subscribe_rule = MqttAutoSubscribeRule {
+    let mut auto_subscribe_rule = MqttAutoSubscribeRule {
         cluster: config.cluster_name.clone(),
         topic: req.topic.clone(),
-        qos: _qos.ok_or_else(|| {
-            MqttBrokerError::CommonError(Error::InvalidQoS(req.qos as u8).to_string())
-        })?,
+        qos,
         no_local: req.no_local,
         retain_as_published: req.retain_as_published,
-        retained_handling: _retained_handling.ok_or_else(|| {
-            MqttBrokerError::CommonError(Error::InvalidQoS(req.retained_handling as u8).to_string())
-        })?,
+        retained_handling,
+        priority: req.priority,
+        create_time: now_second(),
     };
 
+    // Updating a rule keeps its place among rules of the same priority
+    let key = cache_manager.auto_subscribe_rule_key(&config.cluster_name, &req.topic);
+    if let Some(existing) = cache_manager.auto_subscribe_rule.get(&key) {
+        auto_subscribe_rule.create_time = existing.create_time;
+    }
+
     let auto_subscribe_storage = AutoSubscribeStorage::new(client_pool.clone());
     auto_subscribe_storage
         .set_auto_subscribe_rule(auto_subscribe_rule.clone())
         .await
         .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
 
-    let key = cache_manager.auto_subscribe_rule_key(&config.cluster_name, &req.topic);
     cache_manager
         .auto_subscribe_rule
         .insert(key, auto_subscribe_rule);
@@ -79,6 +107,30 @@ pub async fn set_auto_subscribe_rule(
     Ok(())
 }
 
+// The options of a rule are applied as they are to the subscriptions it creates, so they are
+// checked against the values MQTT 5 allows in a SUBSCRIBE
+fn parse_auto_subscribe_options(
+    req_qos: u32,
+    req_retained_handling: u32,
+) -> Result<(QoS, RetainHandling), MqttBrokerError> {
+    let Some(qos) = u8::try_from(req_qos).ok().and_then(qos) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "qos must be 0, 1 or 2, got {}",
+            req_qos
+        )));
+    };
+    let Some(retained_handling) = u8::try_from(req_retained_handling)
+        .ok()
+        .and_then(retain_forward_rule)
+    else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "retained_handling must be 0, 1 or 2, got {}",
+            req_retained_handling
+        )));
+    };
+    Ok((qos, retained_handling))
+}
+
 // Delete auto subscribe rule
 pub async fn delete_auto_subscribe_rule(
     client_pool: &Arc<ClientPool>,
@@ -100,15 +152,882 @@ pub async fn delete_auto_subscribe_rule(
     Ok(())
 }
 
-// List all auto subscribe rules
+// List all auto subscribe rules, in the order they are applied to a connecting client
 pub async fn list_auto_subscribe_rule_by_req(
     cache_manager: &Arc<CacheManager>,
 ) -> Result<Vec<Vec<u8>>, MqttBrokerError> {
-    let rules = cache_manager
+    let mut rules: Vec<MqttAutoSubscribeRule> = cache_manager
         .auto_subscribe_rule
         .iter()
-        .map(|entry| entry.value().clone().encode())
+        .map(|entry| entry.value().clone())
+        .collect();
+    sort_auto_subscribe_rules(&mut rules);
+
+    Ok(rules.iter().map(|rule| rule.encode()).collect())
+}
+
+// List the backlog and delivery lag of each exclusive and shared subscription. Both are read
+// from the storage, so messages published through other brokers are counted as well.
+pub async fn list_subscribe_detail_by_req<S>(
+    subscribe_manager: &Arc<SubscribeManager>,
+    message_storage_adapter: &Arc<S>,
+    request: Request<ListSubscribeDetailRequest>,
+) -> Result<(Vec<SubscribeDetailRaw>, usize), MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let req = request.into_inner();
+    let message_storage = MessageStorage::new(message_storage_adapter.clone());
+
+    let exclusive: Vec<SubscribePushProgress> = subscribe_manager
+        .push_progress
+        .iter()
+        .filter(|entry| req.client_id.is_empty() || entry.value().client_id == req.client_id)
+        .map(|entry| entry.value().clone())
+        .collect();
+    let shared: Vec<ShareLeaderSubscribeData> = subscribe_manager
+        .share_leader_push
+        .iter()
+        .filter(|entry| req.client_id.is_empty() || entry.sub_list.contains_key(&req.client_id))
+        .map(|entry| entry.value().clone())
+        .collect();
+
+    let mut details = Vec::with_capacity(exclusive.len() + shared.len());
+    for progress in exclusive {
+        let backlog =
+            read_subscribe_backlog(&message_storage, &progress.topic_id, progress.next_offset)
+                .await?;
+        details.push(SubscribeDetailRaw {
+            client_id: progress.client_id,
+            sub_path: progress.sub_path,
+            topic_id: progress.topic_id,
+            topic_name: progress.topic_name,
+            next_offset: progress.next_offset,
+            queued_num: backlog.queued_num,
+            delivery_lag_sec: backlog.delivery_lag_sec,
+        });
+    }
+    // A shared subscription has one backlog for the whole group, committed by its leader
+    for group in shared {
+        let next_offset = message_storage.get_group_offset(&group.group_id()).await?;
+        let backlog =
+            read_subscribe_backlog(&message_storage, &group.topic_id, next_offset).await?;
+        let sub_path = group
+            .sub_list
+            .iter()
+            .next()
+            .map(|entry| entry.sub_path.clone())
+            .unwrap_or_default();
+        details.push(SubscribeDetailRaw {
+            client_id: String::new(),
+            sub_path,
+            topic_id: group.topic_id,
+            topic_name: group.topic_name,
+            next_offset,
+            queued_num: backlog.queued_num,
+            delivery_lag_sec: backlog.delivery_lag_sec,
+        });
+    }
+    details.retain(|detail| detail.delivery_lag_sec >= req.min_delivery_lag_sec);
+
+    let filtered = apply_filters(details, &req.options);
+    let sorted = apply_sorting(filtered, &req.options);
+    let pagination = apply_pagination(sorted, &req.options);
+
+    Ok(pagination)
+}
+
+#[derive(Debug, PartialEq)]
+struct SubscribeBacklog {
+    queued_num: u64,
+    delivery_lag_sec: u64,
+}
+
+async fn read_subscribe_backlog<S>(
+    message_storage: &MessageStorage<S>,
+    topic_id: &str,
+    next_offset: u64,
+) -> Result<SubscribeBacklog, MqttBrokerError>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    let records = message_storage
+        .read_topic_message(topic_id, next_offset, SUBSCRIBE_BACKLOG_MAX_READ_NUM)
+        .await?;
+    Ok(subscribe_backlog(&records, now_second()))
+}
+
+// The records are the undelivered messages read from the next offset of the subscription,
+// the first one is the oldest
+fn subscribe_backlog(records: &[Record], now: u64) -> SubscribeBacklog {
+    SubscribeBacklog {
+        queued_num: records.len() as u64,
+        delivery_lag_sec: records
+            .first()
+            .map(|record| now.saturating_sub(record.timestamp))
+            .unwrap_or(0),
+    }
+}
+
+impl Queryable for SubscribeDetailRaw {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "client_id" => Some(self.client_id.clone()),
+            "sub_path" => Some(self.sub_path.clone()),
+            "topic_id" => Some(self.topic_id.clone()),
+            "topic_name" => Some(self.topic_name.clone()),
+            "next_offset" => Some(self.next_offset.to_string()),
+            "queued_num" => Some(self.queued_num.to_string()),
+            "delivery_lag_sec" => Some(self.delivery_lag_sec.to_string()),
+            _ => None,
+        }
+    }
+}
+
+// Configure how the messages of a shared subscription group are dispatched to its consumers
+pub async fn set_shared_dispatch_by_req(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<CacheManager>,
+    request: Request<SetSharedDispatchRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.group_name.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "group_name cannot be empty".to_string(),
+        ));
+    }
+
+    let mode = match req.mode.as_str() {
+        "round_robin" => SharedDispatchMode::RoundRobin,
+        "capacity" => SharedDispatchMode::Capacity,
+        _ => {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unknown shared dispatch mode {}, expected round_robin or capacity",
+                req.mode
+            )))
+        }
+    };
+
+    if let Some((client_id, _)) = req
+        .consumer_weights
+        .iter()
+        .find(|(_, weight)| **weight == 0)
+    {
+        return Err(MqttBrokerError::CommonError(format!(
+            "weight of consumer {} must be greater than 0",
+            client_id
+        )));
+    }
+
+    let group_dispatch = SharedGroupDispatch {
+        mode,
+        consumer_weights: req.consumer_weights.into_iter().collect(),
+    };
+
+    let mut config = cache_manager.get_shared_dispatch_config();
+    if group_dispatch == SharedGroupDispatch::default() {
+        config.groups.remove(&req.group_name);
+    } else {
+        config.groups.insert(req.group_name, group_dispatch);
+    }
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::SharedDispatch,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_shared_dispatch_config(config);
+    Ok(())
+}
+
+// List the groups with a configured dispatch, or a single group when group_name is set
+pub fn list_shared_dispatch_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<ListSharedDispatchRequest>,
+) -> Result<Vec<SharedDispatchRaw>, MqttBrokerError> {
+    let req = request.into_inner();
+    let mut config = cache_manager.get_shared_dispatch_config();
+    if !req.group_name.is_empty() {
+        let group_dispatch = config.groups.remove(&req.group_name).unwrap_or_default();
+        config.groups = [(req.group_name, group_dispatch)].into_iter().collect();
+    }
+
+    let mut groups: Vec<SharedDispatchRaw> = config
+        .groups
+        .into_iter()
+        .map(|(group_name, group_dispatch)| SharedDispatchRaw {
+            skipped_full_num: get_shared_dispatch_skipped_full_counter(&group_name),
+            mode: shared_dispatch_mode_name(&group_dispatch.mode),
+            consumer_weights: group_dispatch.consumer_weights.into_iter().collect(),
+            group_name,
+        })
+        .collect();
+    groups.sort_by(|a, b| a.group_name.cmp(&b.group_name));
+    Ok(groups)
+}
+
+// Shared subscription groups with members on this broker. A member is a leader when this broker
+// pushes the messages of the group, and a follower when it resubscribes from the leader broker.
+pub fn list_shared_subscriptions_by_req(
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<ListSharedSubscriptionsRequest>,
+) -> Result<Vec<SharedSubscriptionRaw>, MqttBrokerError> {
+    let req = request.into_inner();
+
+    // (group name, topic filter) -> (client_id -> role), one entry per member even when the
+    // filter matches several topics
+    let mut groups: BTreeMap<(String, String), BTreeMap<String, String>> = BTreeMap::new();
+    for entry in subscribe_manager.share_leader_push.iter() {
+        let group_name = share_group_name(&entry.group_name, &entry.sub_name);
+        let members = groups
+            .entry((group_name, entry.sub_name.clone()))
+            .or_default();
+        for subscriber in entry.sub_list.iter() {
+            members.insert(subscriber.client_id.clone(), "leader".to_string());
+        }
+    }
+    for entry in subscribe_manager.share_follower_resub.iter() {
+        let group_name = share_group_name(&entry.group_name, &entry.sub_name);
+        groups
+            .entry((group_name, entry.sub_name.clone()))
+            .or_default()
+            .insert(entry.client_id.clone(), "follower".to_string());
+    }
+
+    Ok(groups
+        .into_iter()
+        .filter(|((group_name, _), _)| req.group_name.is_empty() || *group_name == req.group_name)
+        .map(|((group_name, sub_path), members)| SharedSubscriptionRaw {
+            group_name,
+            sub_path,
+            members: members
+                .into_iter()
+                .map(|(client_id, role)| SharedSubscriptionMemberRaw { client_id, role })
+                .collect(),
+        })
+        .collect())
+}
+
+// Shared subscriptions are stored under "{group}_{topic filter}", the group in the
+// subscription path is reported instead
+fn share_group_name(group_name: &str, sub_name: &str) -> String {
+    group_name
+        .strip_suffix(sub_name)
+        .and_then(|name| name.strip_suffix('_'))
+        .unwrap_or(group_name)
+        .to_string()
+}
+
+fn shared_dispatch_mode_name(mode: &SharedDispatchMode) -> String {
+    match mode {
+        SharedDispatchMode::RoundRobin => "round_robin".to_string(),
+        SharedDispatchMode::Capacity => "capacity".to_string(),
+    }
+}
+
+// Describe how the messages of one subscription are delivered to its client on this broker:
+// the granted QoS and the caps applied to it, the subscription options, the shared or exclusive
+// mode and the topics currently matched with their push progress
+pub fn describe_subscription_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<DescribeSubscriptionRequest>,
+) -> Result<DescribeSubscriptionReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let Some(subscribe) = subscribe_manager.get_subscribe(&req.client_id, &req.path) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "client {} has no subscription {} on this broker",
+            req.client_id, req.path
+        )));
+    };
+
+    // The granted QoS was capped when the filter was subscribed, the cap is resolved again
+    // here so that a cap changed since then shows up
+    let username = cache_manager
+        .get_connect_id(&req.client_id)
+        .and_then(|connect_id| cache_manager.get_connection(connect_id))
+        .map(|connection| connection.login_user)
+        .unwrap_or_default();
+    let client_group = cache_manager.get_client_group_config();
+    let (qos_cap, qos_cap_source) = if let Some(max_qos) = client_group.user_max_qos.get(&username)
+    {
+        (Some(*max_qos as u32), "user".to_string())
+    } else if let Some(max_qos) = client_group
+        .group_of(&username)
+        .and_then(|group| group.max_qos)
+    {
+        (Some(max_qos as u32), "group".to_string())
+    } else {
+        (None, "".to_string())
+    };
+    let cluster_max_qos = cache_manager
+        .get_cluster_config()
+        .mqtt_protocol_config
+        .max_qos;
+    let granted_qos = subscribe.filter.qos;
+    let delivery_qos = min_qos(
+        qos(cluster_max_qos).unwrap_or(QoS::ExactlyOnce),
+        granted_qos,
+    );
+
+    let (is_shared, group_name) = if is_share_sub(&subscribe.path) {
+        (true, decode_share_info(&subscribe.path).0)
+    } else if is_queue_sub(&subscribe.path) {
+        (true, SHARE_QUEUE_DEFAULT_GROUP_NAME.to_string())
+    } else {
+        (false, "".to_string())
+    };
+    let (share_role, dispatch_mode, consumer_weight) = if is_shared {
+        let group_dispatch = cache_manager
+            .get_shared_dispatch_config()
+            .groups
+            .remove(&group_name)
+            .unwrap_or_default();
+        (
+            share_role(subscribe_manager, &req.client_id, &group_name),
+            shared_dispatch_mode_name(&group_dispatch.mode),
+            group_dispatch.consumer_weights.get(&req.client_id).copied(),
+        )
+    } else {
+        ("".to_string(), "".to_string(), None)
+    };
+
+    let rewrite_path =
+        convert_sub_path_by_rewrite_rule(cache_manager, &subscribe.path)?.unwrap_or_default();
+
+    Ok(DescribeSubscriptionReply {
+        client_id: subscribe.client_id.clone(),
+        path: subscribe.path.clone(),
+        broker_id: subscribe.broker_id,
+        granted_qos: granted_qos as u32,
+        qos_cap,
+        qos_cap_source,
+        cluster_max_qos: cluster_max_qos as u32,
+        delivery_qos: delivery_qos as u32,
+        no_local: subscribe.filter.nolocal,
+        preserve_retain: subscribe.filter.preserve_retain,
+        retain_handling: u8::from(subscribe.filter.retain_handling.clone()) as u32,
+        subscription_identifier: subscribe
+            .subscribe_properties
+            .as_ref()
+            .and_then(|properties| properties.subscription_identifier)
+            .map(|id| id as u64),
+        is_shared,
+        group_name,
+        share_role,
+        dispatch_mode,
+        consumer_weight,
+        is_exclusive: is_exclusive_sub(&subscribe.path),
+        rewrite_path,
+        topics: subscription_topics(subscribe_manager, &req.client_id, &req.path),
+    })
+}
+
+// A shared subscription is pushed by this broker when it leads the group, otherwise it is
+// resubscribed from the leader of the group
+fn share_role(
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+    group_name: &str,
+) -> String {
+    let is_leader = subscribe_manager
+        .share_leader_push
+        .iter()
+        .any(|entry| entry.group_name == group_name && entry.sub_list.contains_key(client_id));
+    if is_leader {
+        return "leader".to_string();
+    }
+
+    let is_follower = subscribe_manager
+        .share_follower_resub
+        .iter()
+        .any(|entry| entry.client_id == client_id && entry.group_name == group_name);
+    if is_follower {
+        return "follower".to_string();
+    }
+    "".to_string()
+}
+
+fn subscription_topics(
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_id: &str,
+    path: &str,
+) -> Vec<SubscriptionTopicRaw> {
+    let mut topics: Vec<SubscriptionTopicRaw> = subscribe_manager
+        .topic_subscribe_list
+        .iter()
+        .filter(|entry| {
+            entry
+                .value()
+                .iter()
+                .any(|info| info.client_id == client_id && info.path == path)
+        })
+        .map(|entry| {
+            let progress = subscribe_manager.push_progress.iter().find(|progress| {
+                progress.client_id == client_id
+                    && progress.sub_path == path
+                    && progress.topic_name == *entry.key()
+            });
+            match progress {
+                Some(progress) => SubscriptionTopicRaw {
+                    topic_name: entry.key().clone(),
+                    next_offset: Some(progress.next_offset),
+                    queued_num: progress
+                        .queued_num(subscribe_manager.get_topic_latest_offset(&progress.topic_id)),
+                    delivery_lag_sec: progress.delivery_lag_sec(),
+                },
+                None => SubscriptionTopicRaw {
+                    topic_name: entry.key().clone(),
+                    next_offset: None,
+                    queued_num: 0,
+                    delivery_lag_sec: 0,
+                },
+            }
+        })
+        .collect();
+    topics.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+    topics
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct MatcherBenchmarkResult {
+    lookup_num: u64,
+    matched_num: u64,
+    total_us: u64,
+    p50_ns: u64,
+    p90_ns: u64,
+    p99_ns: u64,
+    max_ns: u64,
+}
+
+// Resolve the subscribers of sample topics in the topic subscription index of this broker
+// and report how long the lookups take
+pub async fn benchmark_matcher_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<BenchmarkMatcherRequest>,
+) -> Result<BenchmarkMatcherReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let lookup_num = match req.lookup_num {
+        0 => BENCHMARK_MATCHER_DEFAULT_LOOKUPS,
+        num => num.min(BENCHMARK_MATCHER_MAX_LOOKUPS),
+    };
+
+    let (topics, topic_source) = benchmark_topics(cache_manager, subscribe_manager, req.topics);
+    if topics.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "no topics to benchmark the matcher with, the broker has no topics or subscriptions"
+                .to_string(),
+        ));
+    }
+
+    // The lookups are CPU bound, keep them off the async workers
+    let topic_num = topics.len() as u64;
+    let manager = subscribe_manager.clone();
+    let result =
+        tokio::task::spawn_blocking(move || run_matcher_benchmark(&manager, &topics, lookup_num))
+            .await
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+
+    let throughput = if result.total_us == 0 {
+        0
+    } else {
+        result.lookup_num * 1_000_000 / result.total_us
+    };
+
+    Ok(BenchmarkMatcherReply {
+        topic_source: topic_source.to_string(),
+        topic_num,
+        subscribe_num: subscribe_manager.subscribe_list.len() as u64,
+        lookup_num: result.lookup_num,
+        matched_num: result.matched_num,
+        total_us: result.total_us,
+        lookups_per_second: throughput,
+        p50_ns: result.p50_ns,
+        p90_ns: result.p90_ns,
+        p99_ns: result.p99_ns,
+        max_ns: result.max_ns,
+    })
+}
+
+// Topics given in the request win, then the existing topics of the broker, and
+// finally topics derived from the subscription filters
+fn benchmark_topics(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    requested: Vec<String>,
+) -> (Vec<String>, &'static str) {
+    if !requested.is_empty() {
+        return (requested, BENCHMARK_TOPIC_SOURCE_REQUEST);
+    }
+
+    let sampled: Vec<String> = cache_manager
+        .topic_info
+        .iter()
+        .map(|entry| entry.key().clone())
+        .collect();
+    if !sampled.is_empty() {
+        return (sampled, BENCHMARK_TOPIC_SOURCE_SAMPLED);
+    }
+
+    let synthetic = subscribe_manager
+        .subscribe_list
+        .iter()
+        .map(|entry| synthetic_topic(&entry.path))
         .collect();
+    (synthetic, BENCHMARK_TOPIC_SOURCE_SYNTHETIC)
+}
+
+// Build a topic matched by the subscription filter by filling in its wildcard levels
+fn synthetic_topic(sub_path: &str) -> String {
+    let path = decode_sub_path(sub_path);
+    if !is_wildcards(&path) {
+        return path;
+    }
+    path.split('/')
+        .map(|level| match level {
+            "+" | "#" => BENCHMARK_MATCHER_SYNTHETIC_LEVEL,
+            level => level,
+        })
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+fn run_matcher_benchmark(
+    subscribe_manager: &SubscribeManager,
+    topics: &[String],
+    lookup_num: u64,
+) -> MatcherBenchmarkResult {
+    let mut latencies = Vec::with_capacity(lookup_num as usize);
+    let mut matched_num = 0;
+    let start = Instant::now();
+    for i in 0..lookup_num {
+        let topic = &topics[i as usize % topics.len()];
+        let lookup_start = Instant::now();
+        matched_num += subscribe_manager.topic_subscribe_num(topic) as u64;
+        latencies.push(lookup_start.elapsed().as_nanos() as u64);
+    }
+    let total_us = start.elapsed().as_micros() as u64;
+
+    latencies.sort_unstable();
+    MatcherBenchmarkResult {
+        lookup_num,
+        matched_num,
+        total_us,
+        p50_ns: percentile(&latencies, 50),
+        p90_ns: percentile(&latencies, 90),
+        p99_ns: percentile(&latencies, 99),
+        max_ns: latencies.last().copied().unwrap_or(0),
+    }
+}
+
+// Nearest-rank percentile of an ascending list
+fn percentile(sorted: &[u64], pct: u64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (sorted.len() as u64 * pct).div_ceil(100).max(1);
+    sorted[rank as usize - 1]
+}
+
+// node_id 0 reads the latest snapshot of this broker
+pub async fn get_subscribe_snapshot_by_req(
+    client_pool: &Arc<ClientPool>,
+    request: Request<GetSubscribeSnapshotRequest>,
+) -> Result<GetSubscribeSnapshotReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let node_id = if req.node_id == 0 {
+        broker_mqtt_conf().broker_id
+    } else {
+        req.node_id
+    };
+
+    let storage = SubscribeSnapshotStorage::new(client_pool.clone());
+    let Some(snapshot) = storage.latest(node_id).await? else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "No subscribe snapshot found for broker {}",
+            node_id
+        )));
+    };
+
+    Ok(GetSubscribeSnapshotReply {
+        node_id: snapshot.node_id,
+        create_time: snapshot.create_time,
+        subscribe_num: snapshot.subscribes.len() as u64,
+        snapshot: serde_json::to_string(&snapshot)?,
+    })
+}
+
+// Writes the subscriptions of a snapshot of this broker back, create_time 0 restores the latest
+pub async fn restore_subscribe_snapshot_by_req(
+    client_pool: &Arc<ClientPool>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    request: Request<RestoreSubscribeSnapshotRequest>,
+) -> Result<RestoreSubscribeSnapshotReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let conf = broker_mqtt_conf();
+    let storage = SubscribeSnapshotStorage::new(client_pool.clone());
+    let snapshot = if req.create_time == 0 {
+        storage.latest(conf.broker_id).await?
+    } else {
+        storage.history(conf.broker_id, req.create_time).await?
+    };
+    let Some(snapshot) = snapshot else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "No subscribe snapshot found for broker {} at {}",
+            conf.broker_id, req.create_time
+        )));
+    };
+
+    let sessions = SessionStorage::new(client_pool.clone())
+        .list_session()
+        .await?;
+    let subscribes = subscribes_to_restore(
+        &snapshot,
+        |subscribe| {
+            subscribe_manager
+                .get_subscribe(&subscribe.client_id, &subscribe.path)
+                .is_some()
+        },
+        |client_id| sessions.contains_key(client_id),
+    );
+    for subscribe in subscribes.iter() {
+        let request = SetSubscribeRequest {
+            cluster_name: conf.cluster_name.clone(),
+            client_id: subscribe.client_id.clone(),
+            path: subscribe.path.clone(),
+            subscribe: subscribe.encode(),
+        };
+        placement_set_subscribe(client_pool, &conf.placement_center, request).await?;
+        subscribe_manager.add_subscribe(subscribe.clone());
+    }
+
+    Ok(RestoreSubscribeSnapshotReply {
+        create_time: snapshot.create_time,
+        restored_num: subscribes.len() as u64,
+        skipped_num: (snapshot.subscribes.len() - subscribes.len()) as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        list_shared_subscriptions_by_req, parse_auto_subscribe_options, percentile,
+        run_matcher_benchmark, share_group_name, share_role, subscribe_backlog,
+        subscription_topics, synthetic_topic, SubscribeBacklog,
+    };
+    use crate::subscribe::common::Subscriber;
+    use crate::subscribe::manager::{ShareSubShareSub, SubscribeManager};
+    use metadata_struct::adapter::record::Record;
+    use protocol::broker_mqtt::broker_mqtt_admin::ListSharedSubscriptionsRequest;
+    use protocol::mqtt::common::{QoS, RetainHandling};
+    use std::sync::Arc;
+    use tonic::Request;
+
+    #[test]
+    fn percentile_test() {
+        let latencies: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&latencies, 50), 50);
+        assert_eq!(percentile(&latencies, 99), 99);
+        assert_eq!(percentile(&latencies, 100), 100);
+        assert_eq!(percentile(&[7], 90), 7);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+
+    #[test]
+    fn parse_auto_subscribe_options_test() {
+        assert_eq!(
+            parse_auto_subscribe_options(2, 1).unwrap(),
+            (QoS::ExactlyOnce, RetainHandling::OnNewSubscribe)
+        );
+        assert_eq!(
+            parse_auto_subscribe_options(0, 2).unwrap(),
+            (QoS::AtMostOnce, RetainHandling::Never)
+        );
+
+        let err = parse_auto_subscribe_options(3, 0).unwrap_err();
+        assert!(err.to_string().contains("qos must be 0, 1 or 2, got 3"));
+        let err = parse_auto_subscribe_options(257, 0).unwrap_err();
+        assert!(err.to_string().contains("got 257"));
+        let err = parse_auto_subscribe_options(1, 3).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("retained_handling must be 0, 1 or 2, got 3"));
+    }
+
+    #[test]
+    fn synthetic_topic_test() {
+        assert_eq!(synthetic_topic("/a/+/c"), "/a/benchmark/c");
+        assert_eq!(synthetic_topic("/a/#"), "/a/benchmark");
+        assert_eq!(synthetic_topic("/a/b"), "/a/b");
+    }
+
+    #[test]
+    fn subscribe_backlog_test() {
+        assert_eq!(
+            subscribe_backlog(&[], 100),
+            SubscribeBacklog {
+                queued_num: 0,
+                delivery_lag_sec: 0,
+            }
+        );
+
+        let records: Vec<Record> = [40, 70, 90]
+            .into_iter()
+            .map(|timestamp| {
+                let mut record = Record::build_byte(b"data".to_vec());
+                record.timestamp = timestamp;
+                record
+            })
+            .collect();
+        assert_eq!(
+            subscribe_backlog(&records, 100),
+            SubscribeBacklog {
+                queued_num: 3,
+                delivery_lag_sec: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn run_matcher_benchmark_test() {
+        let subscribe_manager = SubscribeManager::new();
+        subscribe_manager.add_topic_subscribe("/a/b/c", "c1", "/a/+/c");
+        subscribe_manager.add_topic_subscribe("/a/b/c", "c2", "/a/#");
+        subscribe_manager.add_topic_subscribe("/b/c", "c3", "/b/c");
+
+        let topics = vec!["/a/b/c".to_string(), "/b/c".to_string()];
+        let result = run_matcher_benchmark(&subscribe_manager, &topics, 10);
+        assert_eq!(result.lookup_num, 10);
+        // "/a/b/c" matches two filters, "/b/c" one
+        assert_eq!(result.matched_num, 15);
+        assert!(result.p50_ns <= result.p99_ns);
+        assert!(result.p99_ns <= result.max_ns);
+    }
+
+    #[test]
+    fn subscription_topics_test() {
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        subscribe_manager.add_topic_subscribe("/a/b", "c1", "/a/+");
+        subscribe_manager.add_topic_subscribe("/a/c", "c1", "/a/+");
+        subscribe_manager.add_topic_subscribe("/a/b", "c2", "/a/+");
+        let subscriber = Subscriber {
+            client_id: "c1".to_string(),
+            sub_path: "/a/+".to_string(),
+            topic_name: "/a/b".to_string(),
+            topic_id: "t1".to_string(),
+            ..Default::default()
+        };
+        subscribe_manager.add_push_progress("c1_/a/+_t1", &subscriber, 5);
+        subscribe_manager.record_topic_latest_offset("t1", 8);
+
+        let topics = subscription_topics(&subscribe_manager, "c1", "/a/+");
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].topic_name, "/a/b");
+        assert_eq!(topics[0].next_offset, Some(5));
+        assert_eq!(topics[0].queued_num, 4);
+        assert_eq!(topics[1].topic_name, "/a/c");
+        assert_eq!(topics[1].next_offset, None);
+
+        assert!(subscription_topics(&subscribe_manager, "c3", "/a/+").is_empty());
+    }
+
+    #[test]
+    fn share_role_test() {
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        subscribe_manager.add_share_subscribe_leader(
+            "/a/b",
+            Subscriber {
+                client_id: "c1".to_string(),
+                group_name: Some("g1".to_string()),
+                topic_id: "t1".to_string(),
+                ..Default::default()
+            },
+        );
+        assert_eq!(share_role(&subscribe_manager, "c1", "g1"), "leader");
+        assert_eq!(share_role(&subscribe_manager, "c1", "g2"), "");
+        assert_eq!(share_role(&subscribe_manager, "c2", "g1"), "");
+    }
+
+    #[test]
+    fn list_shared_subscriptions_test() {
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        // c1 matches two topics of the group, it is still one member
+        for (client_id, topic_id) in [("c1", "t1"), ("c1", "t2"), ("c2", "t1")] {
+            subscribe_manager.add_share_subscribe_leader(
+                "/a/+",
+                Subscriber {
+                    client_id: client_id.to_string(),
+                    group_name: Some("g1_/a/+".to_string()),
+                    topic_id: topic_id.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        subscribe_manager.add_share_subscribe_follower(
+            "c3",
+            "g2_/b/#",
+            "t3",
+            ShareSubShareSub {
+                client_id: "c3".to_string(),
+                group_name: "g2_/b/#".to_string(),
+                sub_name: "/b/#".to_string(),
+                protocol: Default::default(),
+                topic_name: "/b/c".to_string(),
+                topic_id: "t3".to_string(),
+                packet_identifier: 1,
+                filter: Default::default(),
+                subscription_identifier: None,
+            },
+        );
+
+        let groups = list_shared_subscriptions_by_req(
+            &subscribe_manager,
+            Request::new(ListSharedSubscriptionsRequest {
+                group_name: "".to_string(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].group_name, "g1");
+        assert_eq!(groups[0].sub_path, "/a/+");
+        let members: Vec<(&str, &str)> = groups[0]
+            .members
+            .iter()
+            .map(|m| (m.client_id.as_str(), m.role.as_str()))
+            .collect();
+        assert_eq!(members, vec![("c1", "leader"), ("c2", "leader")]);
+        assert_eq!(groups[1].group_name, "g2");
+        assert_eq!(groups[1].sub_path, "/b/#");
+        assert_eq!(groups[1].members.len(), 1);
+        assert_eq!(groups[1].members[0].client_id, "c3");
+        assert_eq!(groups[1].members[0].role, "follower");
+
+        let groups = list_shared_subscriptions_by_req(
+            &subscribe_manager,
+            Request::new(ListSharedSubscriptionsRequest {
+                group_name: "g2".to_string(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].group_name, "g2");
+
+        let groups = list_shared_subscriptions_by_req(
+            &subscribe_manager,
+            Request::new(ListSharedSubscriptionsRequest {
+                group_name: "g3".to_string(),
+            }),
+        )
+        .unwrap();
+        assert!(groups.is_empty());
+    }
 
-    Ok(rules)
+    #[test]
+    fn share_group_name_test() {
+        assert_eq!(share_group_name("g1_/a/b", "/a/b"), "g1");
+        assert_eq!(share_group_name("my_group_/a/b", "/a/b"), "my_group");
+        assert_eq!(share_group_name("g1", "/a/b"), "g1");
+    }
 }
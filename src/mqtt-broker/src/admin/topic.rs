@@ -14,46 +14,143 @@
 
 use crate::admin::query::{apply_filters, apply_pagination, apply_sorting, Queryable};
 use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
+use crate::handler::retain::exceeded_retain_deliver_limit;
+use crate::handler::topic_cleanup::{
+    gc_topics, list_cluster_gc_topic_candidates, list_cluster_topic_cleanup_candidates,
+    prune_idle_topics,
+};
+use crate::handler::topic_rewrite::{build_topic_rewrite_regex, rewrite_by_rules};
+use crate::observability::metrics::retain::{
+    get_retain_deliver_truncated_counter, get_retain_oversize_counter,
+    get_retain_topic_policy_counter, RETAIN_DELIVER_LIMIT_BYTES, RETAIN_DELIVER_LIMIT_NUM,
+    RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED, RETAIN_OVERSIZE_OUTCOME_REJECTED,
+    RETAIN_TOPIC_POLICY_OUTCOME_REJECTED, RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED,
+};
+use crate::observability::metrics::topic::{
+    get_topic_cleanup_counter, TOPIC_CLEANUP_OUTCOME_PRUNED,
+    TOPIC_CLEANUP_OUTCOME_SKIPPED_IN_FLIGHT,
+};
 use crate::storage::topic::TopicStorage;
+use crate::subscribe::common::{build_sub_path_regex, is_match_sub_and_topic, is_wildcards};
+use crate::subscribe::manager::SubscribeManager;
 use common_base::tools::now_mills;
 use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::{
+    MessageDedup, RetainOversizePolicy, RetainTopicAction, RetainTopicPolicy, RetainTopicSizeLimit,
+};
 use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::message::MqttMessage;
 use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    CreateTopicRewriteRuleRequest, DeleteTopicRewriteRuleRequest, ListTopicRequest, MqttTopicRaw,
-    MqttTopicRewriteRuleRaw,
+    CreateTopicRewriteRuleRequest, DeleteRetainedMessageReply, DeleteRetainedMessageRequest,
+    DeleteTopicRewriteRuleRequest, EstimateRetainDeliveryReply, EstimateRetainDeliveryRequest,
+    GcTopicsReply, GcTopicsRequest, GetMessageDedupConfigReply, GetRetainMessageConfigReply,
+    ListRetainTopicPolicyReply, ListRetainedMessagesReply, ListRetainedMessagesRequest,
+    ListTopicRequest, MessageDedupTopicRaw, MqttTopicRaw, MqttTopicRewriteRuleRaw, OrderDirection,
+    ResetTopicSequenceReply, ResetTopicSequenceRequest, RetainTopicPolicyRaw,
+    RetainTopicSizeLimitRaw, RetainedMessageRaw, SetMessageDedupConfigRequest,
+    SetRetainDeliverLimitRequest, SetRetainSizeLimitRequest, SetRetainTopicPolicyRequest,
+    TestTopicRewriteReply, TestTopicRewriteRequest, TopicCleanupRaw, TopicCleanupReply,
+    TopicCleanupRequest, TopicSequenceRaw, TopicSequenceRequest,
 };
+use std::cmp::Ordering;
 use std::sync::Arc;
 use tonic::Request;
 
-// List all topics by request
+// List all topics by request, with how many messages were published to each topic on this
+// broker, when the last one was, and how many clients subscribe to it
 pub async fn list_topic_by_req(
     cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
     request: Request<ListTopicRequest>,
 ) -> Result<(Vec<MqttTopicRaw>, usize), MqttBrokerError> {
     let req = request.into_inner();
-    let topics = extract_topic(cache_manager)?;
+    let sort_key = TopicSortKey::parse(req.sort_by.as_deref().unwrap_or_default())?;
+    let direction = req
+        .sort_order
+        .and_then(|raw| OrderDirection::try_from(raw).ok())
+        .unwrap_or(OrderDirection::Asc);
 
-    if req.topic_name.as_deref().unwrap_or_default().is_empty() {
-        let topic_count = topics.len();
-        return Ok((topics, topic_count));
+    let mut topics = extract_topic(cache_manager, subscribe_manager)?;
+    if let Some(topic_name) = req.topic_name.as_deref().filter(|name| !name.is_empty()) {
+        topics.retain(|topic| topic.topic_name == topic_name);
     }
+
+    // The cache iterates in no particular order, so the topics are always sorted before they
+    // are paged. Sorting options given in the query options take precedence.
     let filtered = apply_filters(topics, &req.options);
-    let sorted = apply_sorting(filtered, &req.options);
+    let sorted = apply_sorting(sort_topics(filtered, sort_key, direction), &req.options);
     let pagination = apply_pagination(sorted, &req.options);
     Ok(pagination)
 }
 
-fn extract_topic(cache_manager: &Arc<CacheManager>) -> Result<Vec<MqttTopicRaw>, MqttBrokerError> {
+fn extract_topic(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<Vec<MqttTopicRaw>, MqttBrokerError> {
     let mut topics = Vec::new();
     for entry in cache_manager.topic_info.iter() {
         let topic = entry.value();
-        topics.push(MqttTopicRaw::from(topic.clone()));
+        let mut raw = MqttTopicRaw::from(topic.clone());
+        raw.message_count = cache_manager.get_topic_sequence(&topic.topic_name);
+        raw.last_message_at = cache_manager.get_topic_last_message_at(&topic.topic_name);
+        raw.subscriber_count = subscribe_manager.topic_subscriber_num(&topic.topic_name);
+        topics.push(raw);
     }
     Ok(topics)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TopicSortKey {
+    Name,
+    CreatedAt,
+    MessageCount,
+    SubscriberCount,
+    LastMessageAt,
+}
+
+impl TopicSortKey {
+    fn parse(sort_by: &str) -> Result<Self, MqttBrokerError> {
+        match sort_by {
+            "" | "name" => Ok(TopicSortKey::Name),
+            "created_at" => Ok(TopicSortKey::CreatedAt),
+            "message_count" => Ok(TopicSortKey::MessageCount),
+            "subscriber_count" => Ok(TopicSortKey::SubscriberCount),
+            "last_message_at" => Ok(TopicSortKey::LastMessageAt),
+            _ => Err(MqttBrokerError::CommonError(format!(
+                "unsupported sort_by {}, expected one of name, created_at, message_count, subscriber_count, last_message_at",
+                sort_by
+            ))),
+        }
+    }
+}
+
+// Topics with the same key are ordered by name, so the order is the same on every call
+fn sort_topics(
+    mut topics: Vec<MqttTopicRaw>,
+    key: TopicSortKey,
+    direction: OrderDirection,
+) -> Vec<MqttTopicRaw> {
+    topics.sort_by(|a, b| {
+        let ord = match key {
+            TopicSortKey::Name => Ordering::Equal,
+            TopicSortKey::CreatedAt => a.create_time.cmp(&b.create_time),
+            TopicSortKey::MessageCount => a.message_count.cmp(&b.message_count),
+            TopicSortKey::SubscriberCount => a.subscriber_count.cmp(&b.subscriber_count),
+            TopicSortKey::LastMessageAt => a.last_message_at.cmp(&b.last_message_at),
+        }
+        .then_with(|| a.topic_name.cmp(&b.topic_name));
+        if direction == OrderDirection::Desc {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+    topics
+}
+
 // Delete a topic rewrite rule
 pub async fn delete_topic_rewrite_rule_by_req(
     client_pool: &Arc<ClientPool>,
@@ -81,6 +178,8 @@ pub async fn create_topic_rewrite_rule_by_req(
     request: Request<CreateTopicRewriteRuleRequest>,
 ) -> Result<(), MqttBrokerError> {
     let req = request.into_inner();
+    build_topic_rewrite_regex(&req.regex, &req.dest_topic)?;
+
     let config = broker_mqtt_conf();
     let rule = MqttTopicRewriteRule {
         cluster: config.cluster_name.clone(),
@@ -102,6 +201,55 @@ pub async fn create_topic_rewrite_rule_by_req(
     Ok(())
 }
 
+// Run a topic through the rewrite rules without publishing anything. Without a candidate
+// rule the topic is rewritten by the configured rules exactly as a publish would be.
+pub async fn test_topic_rewrite_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<TestTopicRewriteRequest>,
+) -> Result<TestTopicRewriteReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if req.topic.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "topic cannot be empty".to_string(),
+        ));
+    }
+
+    let rewritten = match req.rule {
+        Some(rule) => {
+            // The candidate rule is not cached, it is checked the same way as on creation
+            let regex = build_topic_rewrite_regex(&rule.regex, &rule.dest_topic)?;
+            let rule = MqttTopicRewriteRule {
+                cluster: broker_mqtt_conf().cluster_name.clone(),
+                action: rule.action,
+                source_topic: rule.source_topic,
+                dest_topic: rule.dest_topic,
+                regex: rule.regex,
+                timestamp: now_mills(),
+            };
+            rewrite_by_rules(vec![rule], &req.topic, |_| Ok(regex.clone()))?
+        }
+        None => rewrite_by_rules(
+            cache_manager.get_all_topic_rewrite_rule(),
+            &req.topic,
+            |pattern| cache_manager.get_topic_rewrite_regex(pattern),
+        )?,
+    };
+
+    let reply = match rewritten {
+        Some((rule, rewritten_topic)) => TestTopicRewriteReply {
+            matched: true,
+            rewritten_topic,
+            rule: Some(MqttTopicRewriteRuleRaw::from(rule)),
+        },
+        None => TestTopicRewriteReply {
+            matched: false,
+            rewritten_topic: req.topic,
+            rule: None,
+        },
+    };
+    Ok(reply)
+}
+
 pub async fn get_all_topic_rewrite_rule_by_req(
     cache_manager: &Arc<CacheManager>,
 ) -> Result<Vec<MqttTopicRewriteRuleRaw>, MqttBrokerError> {
@@ -120,7 +268,921 @@ fn get_field_str(&self, field: &str) -> Option<String> {
             "cluster_name" => Some(self.cluster_name.clone()),
             "topic_name" => Some(self.topic_name.clone()),
             "is_contain_retain_message" => Some(self.is_contain_retain_message.to_string()),
+            "create_time" => Some(self.create_time.to_string()),
+            "message_count" => Some(self.message_count.to_string()),
+            "subscriber_count" => Some(self.subscriber_count.to_string()),
+            "last_message_at" => Some(self.last_message_at.to_string()),
             _ => None,
         }
     }
 }
+
+// Get the retained message size policy and how often each outcome happened
+pub async fn get_retain_message_config_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> Result<GetRetainMessageConfigReply, MqttBrokerError> {
+    let config = cache_manager.get_retain_message_config();
+    Ok(GetRetainMessageConfigReply {
+        max_payload_size: config.max_payload_size,
+        oversize_policy: format!("{:?}", config.oversize_policy),
+        rejected_num: get_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_REJECTED),
+        not_retained_num: get_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED),
+        max_deliver_num_per_subscribe: config.max_deliver_num_per_subscribe,
+        max_deliver_bytes_per_subscribe: config.max_deliver_bytes_per_subscribe,
+        truncated_by_num: get_retain_deliver_truncated_counter(RETAIN_DELIVER_LIMIT_NUM),
+        truncated_by_bytes: get_retain_deliver_truncated_counter(RETAIN_DELIVER_LIMIT_BYTES),
+        topic_size_limits: config
+            .topic_size_limits
+            .into_iter()
+            .map(|limit| RetainTopicSizeLimitRaw {
+                topic_filter: limit.topic_filter,
+                max_payload_size: limit.max_payload_size,
+                oversize_policy: format!("{:?}", limit.oversize_policy),
+            })
+            .collect(),
+    })
+}
+
+// Set the retained message size limit. Without a topic filter the cluster-wide limit is set,
+// otherwise the limit of the filter, and an empty oversize policy removes the limit of the filter.
+pub async fn set_retain_size_limit_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetRetainSizeLimitRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    let oversize_policy = match req.oversize_policy.as_str() {
+        "" => None,
+        "reject" => Some(RetainOversizePolicy::Reject),
+        "accept_without_retain" => Some(RetainOversizePolicy::AcceptWithoutRetain),
+        _ => {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unknown retain oversize policy {}, expected reject or accept_without_retain",
+                req.oversize_policy
+            )))
+        }
+    };
+
+    let mut config = cache_manager.get_retain_message_config();
+    if req.topic_filter.is_empty() {
+        let Some(oversize_policy) = oversize_policy else {
+            return Err(MqttBrokerError::CommonError(
+                "oversize_policy cannot be empty for the cluster-wide limit".to_string(),
+            ));
+        };
+        config.max_payload_size = req.max_payload_size;
+        config.oversize_policy = oversize_policy;
+    } else {
+        if is_wildcards(&req.topic_filter) {
+            build_sub_path_regex(&req.topic_filter)?;
+        }
+        let position = config
+            .topic_size_limits
+            .iter()
+            .position(|limit| limit.topic_filter == req.topic_filter);
+        match (oversize_policy, position) {
+            (Some(oversize_policy), Some(index)) => {
+                config.topic_size_limits[index].max_payload_size = req.max_payload_size;
+                config.topic_size_limits[index].oversize_policy = oversize_policy;
+            }
+            (Some(oversize_policy), None) => config.topic_size_limits.push(RetainTopicSizeLimit {
+                topic_filter: req.topic_filter,
+                max_payload_size: req.max_payload_size,
+                oversize_policy,
+            }),
+            (None, Some(index)) => {
+                config.topic_size_limits.remove(index);
+            }
+            (None, None) => return Ok(()),
+        }
+    }
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::RetainMessage,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_retain_message_config(config);
+    Ok(())
+}
+
+pub async fn set_retain_deliver_limit_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetRetainDeliverLimitRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    let mut config = cache_manager.get_retain_message_config();
+    config.max_deliver_num_per_subscribe = req.max_deliver_num_per_subscribe;
+    config.max_deliver_bytes_per_subscribe = req.max_deliver_bytes_per_subscribe;
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::RetainMessage,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_retain_message_config(config);
+    Ok(())
+}
+
+// How many retained messages a subscribe to the filter would receive and how large they are,
+// without subscribing. Topics are taken in name order when applying the delivery limit.
+pub fn estimate_retain_delivery_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<EstimateRetainDeliveryRequest>,
+) -> Result<EstimateRetainDeliveryReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if req.topic_filter.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "topic_filter cannot be empty".to_string(),
+        ));
+    }
+    if is_wildcards(&req.topic_filter) {
+        build_sub_path_regex(&req.topic_filter)?;
+    }
+
+    let mut retained: Vec<(String, u64)> = Vec::new();
+    let mut matched_topic_num = 0;
+    for topic in cache_manager.topic_info.iter() {
+        if is_match_sub_and_topic(&req.topic_filter, &topic.topic_name).is_err() {
+            continue;
+        }
+        matched_topic_num += 1;
+
+        let Some(data) = topic
+            .retain_message
+            .as_ref()
+            .filter(|data| !data.is_empty())
+        else {
+            continue;
+        };
+        let message = serde_json::from_slice::<MqttMessage>(data)?;
+        retained.push((topic.topic_name.clone(), message.payload.len() as u64));
+    }
+    retained.sort();
+
+    let retain_config = cache_manager.get_retain_message_config();
+    let mut reply = EstimateRetainDeliveryReply {
+        matched_topic_num,
+        message_num: retained.len() as u64,
+        total_bytes: retained.iter().map(|(_, size)| size).sum(),
+        ..Default::default()
+    };
+    for (_, payload_size) in retained {
+        if let Some(limit) = exceeded_retain_deliver_limit(
+            &retain_config,
+            reply.deliver_num,
+            reply.deliver_bytes,
+            payload_size,
+        ) {
+            reply.truncated_by = limit.to_string();
+            break;
+        }
+        reply.deliver_num += 1;
+        reply.deliver_bytes += payload_size;
+    }
+    Ok(reply)
+}
+
+// The retained messages of the topics matching the filter, read from the same topic cache the
+// publish path updates. Messages are ordered by topic name so that paging is deterministic, a
+// limit of 0 returns every message from the offset.
+pub fn list_retained_messages_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<ListRetainedMessagesRequest>,
+) -> Result<ListRetainedMessagesReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if req.topic_filter.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "topic_filter cannot be empty".to_string(),
+        ));
+    }
+    if is_wildcards(&req.topic_filter) {
+        build_sub_path_regex(&req.topic_filter)?;
+    }
+
+    let mut retained: Vec<(String, MqttMessage)> = Vec::new();
+    for topic in cache_manager.topic_info.iter() {
+        if is_match_sub_and_topic(&req.topic_filter, &topic.topic_name).is_err() {
+            continue;
+        }
+        let Some(data) = topic
+            .retain_message
+            .as_ref()
+            .filter(|data| !data.is_empty())
+        else {
+            continue;
+        };
+        let message = serde_json::from_slice::<MqttMessage>(data)?;
+        retained.push((topic.topic_name.clone(), message));
+    }
+    retained.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let total_count = retained.len() as u64;
+    let limit = if req.limit == 0 {
+        retained.len()
+    } else {
+        req.limit as usize
+    };
+    let retained_messages = retained
+        .into_iter()
+        .skip(req.offset as usize)
+        .take(limit)
+        .map(|(topic_name, message)| RetainedMessageRaw {
+            topic_name,
+            payload_size: message.payload.len() as u64,
+            qos: message.qos as u32,
+            create_time: message.create_time,
+            payload: if req.include_payload {
+                message.payload.to_vec()
+            } else {
+                Vec::new()
+            },
+        })
+        .collect();
+
+    Ok(ListRetainedMessagesReply {
+        retained_messages,
+        total_count,
+    })
+}
+
+// Clear the retained messages of a topic, or of every topic matching a wildcard filter, from the
+// storage and the cache, as a retained PUBLISH without payload would
+pub async fn delete_retained_message_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<DeleteRetainedMessageRequest>,
+) -> Result<DeleteRetainedMessageReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let topics = select_retained_topics(cache_manager, &req.topic_filter, req.confirm)?;
+
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let mut cleared_num = 0;
+    for topic_name in topics {
+        topic_storage
+            .delete_retain_message(topic_name.clone())
+            .await?;
+        cache_manager.update_topic_retain_message(&topic_name, Some(Vec::new()));
+        cleared_num += 1;
+    }
+    Ok(DeleteRetainedMessageReply { cleared_num })
+}
+
+fn select_retained_topics(
+    cache_manager: &Arc<CacheManager>,
+    topic_filter: &str,
+    confirm: bool,
+) -> Result<Vec<String>, MqttBrokerError> {
+    if topic_filter.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "topic_filter cannot be empty".to_string(),
+        ));
+    }
+    if is_wildcards(topic_filter) {
+        build_sub_path_regex(topic_filter)?;
+        if !confirm {
+            return Err(MqttBrokerError::CommonError(format!(
+                "{} may clear the retained messages of many topics, set confirm to do so",
+                topic_filter
+            )));
+        }
+    }
+
+    let mut topics: Vec<String> = cache_manager
+        .topic_info
+        .iter()
+        .filter(|topic| {
+            topic
+                .retain_message
+                .as_ref()
+                .is_some_and(|data| !data.is_empty())
+        })
+        .filter(|topic| is_match_sub_and_topic(topic_filter, &topic.topic_name).is_ok())
+        .map(|topic| topic.topic_name.clone())
+        .collect();
+    topics.sort();
+    Ok(topics)
+}
+
+// Add, replace or, with an empty action, remove the retain policy of a topic filter
+pub async fn set_retain_topic_policy_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetRetainTopicPolicyRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.topic_filter.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "topic_filter cannot be empty".to_string(),
+        ));
+    }
+    if is_wildcards(&req.topic_filter) {
+        build_sub_path_regex(&req.topic_filter)?;
+    }
+
+    let action = match req.action.as_str() {
+        "" => None,
+        "reject" => Some(RetainTopicAction::Reject),
+        "strip" => Some(RetainTopicAction::Strip),
+        _ => {
+            return Err(MqttBrokerError::CommonError(format!(
+                "unknown retain topic action {}, expected reject or strip",
+                req.action
+            )))
+        }
+    };
+
+    let mut config = cache_manager.get_retain_message_config();
+    let position = config
+        .topic_policies
+        .iter()
+        .position(|policy| policy.topic_filter == req.topic_filter);
+    match (action, position) {
+        (Some(action), Some(index)) => config.topic_policies[index].action = action,
+        (Some(action), None) => config.topic_policies.push(RetainTopicPolicy {
+            topic_filter: req.topic_filter,
+            action,
+        }),
+        (None, Some(index)) => {
+            config.topic_policies.remove(index);
+        }
+        (None, None) => return Ok(()),
+    }
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::RetainMessage,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_retain_message_config(config);
+    Ok(())
+}
+
+pub fn list_retain_topic_policy_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> Result<ListRetainTopicPolicyReply, MqttBrokerError> {
+    let config = cache_manager.get_retain_message_config();
+    Ok(ListRetainTopicPolicyReply {
+        policies: config
+            .topic_policies
+            .into_iter()
+            .map(|policy| RetainTopicPolicyRaw {
+                topic_filter: policy.topic_filter,
+                action: match policy.action {
+                    RetainTopicAction::Reject => "reject".to_string(),
+                    RetainTopicAction::Strip => "strip".to_string(),
+                },
+            })
+            .collect(),
+        rejected_num: get_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_REJECTED),
+        stripped_num: get_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED),
+    })
+}
+
+// Preview or run the idle topic cleanup
+pub async fn topic_cleanup_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<TopicCleanupRequest>,
+) -> Result<TopicCleanupReply, MqttBrokerError> {
+    let req = request.into_inner();
+    let config = cache_manager.get_topic_cleanup_config();
+    let broker_id = broker_mqtt_conf().broker_id;
+    let topics = if req.dry_run {
+        list_cluster_topic_cleanup_candidates(
+            cache_manager,
+            subscribe_manager,
+            client_pool,
+            broker_id,
+        )
+        .await?
+    } else {
+        prune_idle_topics(cache_manager, subscribe_manager, client_pool, broker_id).await?
+    };
+
+    Ok(TopicCleanupReply {
+        enable: config.enable,
+        idle_threshold_sec: config.idle_threshold_sec,
+        topics: topics
+            .into_iter()
+            .map(|candidate| TopicCleanupRaw {
+                topic_name: candidate.topic_name,
+                topic_id: candidate.topic_id,
+                idle_sec: candidate.idle_sec,
+            })
+            .collect(),
+        pruned_num: get_topic_cleanup_counter(TOPIC_CLEANUP_OUTCOME_PRUNED),
+        skipped_in_flight_num: get_topic_cleanup_counter(TOPIC_CLEANUP_OUTCOME_SKIPPED_IN_FLIGHT),
+    })
+}
+
+// Removes topics without subscribers that have not seen a message for idle_sec seconds
+pub async fn gc_topics_by_req(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<GcTopicsRequest>,
+) -> Result<GcTopicsReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if req.idle_sec == 0 {
+        return Err(MqttBrokerError::CommonError(
+            "idle_sec must be greater than 0".to_string(),
+        ));
+    }
+
+    let broker_id = broker_mqtt_conf().broker_id;
+    let topics = if req.dry_run {
+        list_cluster_gc_topic_candidates(
+            cache_manager,
+            subscribe_manager,
+            client_pool,
+            req.idle_sec,
+            req.delete_retained,
+            broker_id,
+        )
+        .await?
+    } else {
+        gc_topics(
+            cache_manager,
+            subscribe_manager,
+            client_pool,
+            req.idle_sec,
+            req.delete_retained,
+            broker_id,
+        )
+        .await?
+    };
+
+    Ok(GcTopicsReply {
+        dry_run: req.dry_run,
+        topics: topics
+            .into_iter()
+            .map(|candidate| TopicCleanupRaw {
+                topic_name: candidate.topic_name,
+                topic_id: candidate.topic_id,
+                idle_sec: candidate.idle_sec,
+            })
+            .collect(),
+    })
+}
+
+// Current sequence of one topic, or of all topics known to this broker. Sequences count the
+// messages published through this broker since it started, they are not shared across brokers.
+pub fn topic_sequence_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<TopicSequenceRequest>,
+) -> Result<Vec<TopicSequenceRaw>, MqttBrokerError> {
+    let req = request.into_inner();
+    if !req.topic_name.is_empty() {
+        if !cache_manager.topic_exists(&req.topic_name) {
+            return Err(MqttBrokerError::TopicDoesNotExist(req.topic_name));
+        }
+        return Ok(vec![TopicSequenceRaw {
+            sequence: cache_manager.get_topic_sequence(&req.topic_name),
+            topic_name: req.topic_name,
+        }]);
+    }
+
+    let mut topics: Vec<TopicSequenceRaw> = cache_manager
+        .topic_info
+        .iter()
+        .map(|entry| TopicSequenceRaw {
+            topic_name: entry.key().clone(),
+            sequence: cache_manager.get_topic_sequence(entry.key()),
+        })
+        .collect();
+    topics.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+    Ok(topics)
+}
+
+// Reset the sequence of a topic. The caller passes the sequence it inspected, the reset
+// is refused when messages were published in the meantime.
+pub fn reset_topic_sequence_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<ResetTopicSequenceRequest>,
+) -> Result<ResetTopicSequenceReply, MqttBrokerError> {
+    let req = request.into_inner();
+    if !cache_manager.topic_exists(&req.topic_name) {
+        return Err(MqttBrokerError::TopicDoesNotExist(req.topic_name));
+    }
+
+    if let Err(current) =
+        cache_manager.reset_topic_sequence(&req.topic_name, req.expected_sequence, req.sequence)
+    {
+        return Err(MqttBrokerError::CommonError(format!(
+            "sequence of topic {} is {}, not the expected {}, inspect it again before resetting",
+            req.topic_name, current, req.expected_sequence
+        )));
+    }
+
+    Ok(ResetTopicSequenceReply {
+        topic_name: req.topic_name,
+        sequence: req.sequence,
+    })
+}
+
+pub async fn set_message_dedup_config_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetMessageDedupConfigRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.enable {
+        if req.property_name.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "property_name cannot be empty".to_string(),
+            ));
+        }
+        if req.window_sec == 0 || req.max_ids_per_topic == 0 {
+            return Err(MqttBrokerError::CommonError(
+                "window_sec and max_ids_per_topic must be greater than 0".to_string(),
+            ));
+        }
+    }
+
+    let config = MessageDedup {
+        enable: req.enable,
+        property_name: req.property_name,
+        topic_filters: req.topic_filters,
+        window_sec: req.window_sec,
+        max_ids_per_topic: req.max_ids_per_topic,
+    };
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::MessageDedup,
+        config.encode(),
+    )
+    .await?;
+
+    // Remembered ids are only useful for the config they were recorded under
+    cache_manager.message_deduplicator.clear();
+    cache_manager.update_message_dedup_config(config);
+    Ok(())
+}
+
+// The message deduplication config and how many messages it suppressed on each topic
+pub fn get_message_dedup_config_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> GetMessageDedupConfigReply {
+    let config = cache_manager.get_message_dedup_config();
+    let mut topics: Vec<MessageDedupTopicRaw> = cache_manager
+        .message_deduplicator
+        .list_suppressed()
+        .into_iter()
+        .map(|(topic_name, suppressed_num)| MessageDedupTopicRaw {
+            remembered_num: cache_manager
+                .message_deduplicator
+                .remembered_num(&topic_name),
+            topic_name,
+            suppressed_num,
+        })
+        .collect();
+    topics.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+
+    GetMessageDedupConfigReply {
+        enable: config.enable,
+        property_name: config.property_name,
+        topic_filters: config.topic_filters,
+        window_sec: config.window_sec,
+        max_ids_per_topic: config.max_ids_per_topic,
+        suppressed_num: topics.iter().map(|topic| topic.suppressed_num).sum(),
+        topics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use protocol::broker_mqtt::broker_mqtt_admin::{
+        ListRetainedMessagesRequest, MqttTopicRaw, OrderDirection, QueryOptions,
+    };
+    use protocol::mqtt::common::QoS;
+    use tonic::Request;
+
+    use super::{list_retained_messages_by_req, select_retained_topics, sort_topics, TopicSortKey};
+    use crate::admin::query::apply_pagination;
+    use crate::handler::cache::CacheManager;
+
+    fn topic(topic_name: &str, create_time: u64, message_count: u64) -> MqttTopicRaw {
+        MqttTopicRaw {
+            topic_name: topic_name.to_string(),
+            create_time,
+            message_count,
+            ..Default::default()
+        }
+    }
+
+    fn topics() -> Vec<MqttTopicRaw> {
+        vec![
+            topic("c", 300, 5),
+            topic("a", 200, 50),
+            topic("e", 100, 5),
+            topic("b", 400, 0),
+            topic("d", 200, 20),
+        ]
+    }
+
+    fn names(topics: &[MqttTopicRaw]) -> Vec<&str> {
+        topics
+            .iter()
+            .map(|topic| topic.topic_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn topic_sort_key_test() {
+        assert_eq!(TopicSortKey::parse("").unwrap(), TopicSortKey::Name);
+        assert_eq!(TopicSortKey::parse("name").unwrap(), TopicSortKey::Name);
+        assert_eq!(
+            TopicSortKey::parse("created_at").unwrap(),
+            TopicSortKey::CreatedAt
+        );
+        assert_eq!(
+            TopicSortKey::parse("message_count").unwrap(),
+            TopicSortKey::MessageCount
+        );
+        assert_eq!(
+            TopicSortKey::parse("subscriber_count").unwrap(),
+            TopicSortKey::SubscriberCount
+        );
+        assert_eq!(
+            TopicSortKey::parse("last_message_at").unwrap(),
+            TopicSortKey::LastMessageAt
+        );
+        assert!(TopicSortKey::parse("size").is_err());
+    }
+
+    #[test]
+    fn sort_topics_test() {
+        let sorted = sort_topics(topics(), TopicSortKey::Name, OrderDirection::Asc);
+        assert_eq!(names(&sorted), vec!["a", "b", "c", "d", "e"]);
+        let sorted = sort_topics(topics(), TopicSortKey::Name, OrderDirection::Desc);
+        assert_eq!(names(&sorted), vec!["e", "d", "c", "b", "a"]);
+
+        // a and d were created at the same time and are ordered by name
+        let sorted = sort_topics(topics(), TopicSortKey::CreatedAt, OrderDirection::Asc);
+        assert_eq!(names(&sorted), vec!["e", "a", "d", "c", "b"]);
+        let sorted = sort_topics(topics(), TopicSortKey::CreatedAt, OrderDirection::Desc);
+        assert_eq!(names(&sorted), vec!["b", "c", "d", "a", "e"]);
+
+        let sorted = sort_topics(topics(), TopicSortKey::MessageCount, OrderDirection::Asc);
+        assert_eq!(names(&sorted), vec!["b", "c", "e", "d", "a"]);
+        let sorted = sort_topics(topics(), TopicSortKey::MessageCount, OrderDirection::Desc);
+        assert_eq!(names(&sorted), vec!["a", "d", "e", "c", "b"]);
+    }
+
+    #[test]
+    fn sort_topics_pagination_test() {
+        for key in [
+            TopicSortKey::Name,
+            TopicSortKey::CreatedAt,
+            TopicSortKey::MessageCount,
+        ] {
+            let sorted = sort_topics(topics(), key, OrderDirection::Asc);
+            let mut paged = Vec::new();
+            for offset in [0, 2, 4, 6] {
+                let mut options = QueryOptions {
+                    pagination: Some(Default::default()),
+                    ..Default::default()
+                };
+                if let Some(pagination) = options.pagination.as_mut() {
+                    pagination.limit = 2;
+                    pagination.offset = offset;
+                }
+                // Every page sorts the unordered topics again, as every request does
+                let page = sort_topics(topics(), key, OrderDirection::Asc);
+                let (page, total_count) = apply_pagination(page, &Some(options));
+                assert_eq!(total_count, 5);
+                paged.extend(page);
+            }
+            assert_eq!(names(&paged), names(&sorted));
+        }
+    }
+
+    fn retained_cache() -> Arc<CacheManager> {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        for (topic_name, payload, qos) in [
+            ("sensor/1/temp", "21.5", QoS::AtLeastOnce),
+            ("sensor/2/temp", "19", QoS::AtMostOnce),
+            ("sensor/2/humidity", "40", QoS::ExactlyOnce),
+            ("sensor/3/temp", "", QoS::AtMostOnce),
+            ("device/1/status", "online", QoS::AtLeastOnce),
+        ] {
+            let mut topic = MqttTopic::new(
+                format!("id-{topic_name}"),
+                "test".to_string(),
+                topic_name.to_string(),
+            );
+            // an empty payload clears the retained message, as a retained PUBLISH without payload does
+            if !payload.is_empty() {
+                let message = MqttMessage {
+                    topic: Bytes::from(topic_name.to_string()),
+                    payload: Bytes::from(payload.to_string()),
+                    qos,
+                    retain: true,
+                    create_time: 100,
+                    ..Default::default()
+                };
+                topic.retain_message = Some(message.encode());
+            }
+            cache_manager.add_topic(topic_name, &topic);
+        }
+        cache_manager
+    }
+
+    fn list(
+        cache_manager: &Arc<CacheManager>,
+        topic_filter: &str,
+        include_payload: bool,
+        limit: u32,
+        offset: u32,
+    ) -> Vec<(String, u64, u32, Vec<u8>)> {
+        list_retained_messages_by_req(
+            cache_manager,
+            Request::new(ListRetainedMessagesRequest {
+                topic_filter: topic_filter.to_string(),
+                include_payload,
+                limit,
+                offset,
+            }),
+        )
+        .unwrap()
+        .retained_messages
+        .into_iter()
+        .map(|raw| (raw.topic_name, raw.payload_size, raw.qos, raw.payload))
+        .collect()
+    }
+
+    #[test]
+    fn list_retained_messages_by_req_test() {
+        let cache_manager = retained_cache();
+
+        let messages = list(&cache_manager, "sensor/+/temp", false, 0, 0);
+        assert_eq!(
+            messages,
+            vec![
+                ("sensor/1/temp".to_string(), 4, 1, Vec::new()),
+                ("sensor/2/temp".to_string(), 2, 0, Vec::new()),
+            ]
+        );
+
+        let messages = list(&cache_manager, "sensor/#", true, 0, 0);
+        let topics: Vec<&str> = messages.iter().map(|m| m.0.as_str()).collect();
+        assert_eq!(
+            topics,
+            vec!["sensor/1/temp", "sensor/2/humidity", "sensor/2/temp"]
+        );
+        assert_eq!(messages[1].3, b"40".to_vec());
+        assert_eq!(messages[1].2, 2);
+
+        assert_eq!(
+            list(&cache_manager, "device/1/status", false, 0, 0).len(),
+            1
+        );
+        assert!(list(&cache_manager, "other/#", false, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn list_retained_messages_by_req_pagination_test() {
+        let cache_manager = retained_cache();
+        let all = list(&cache_manager, "#", false, 0, 0);
+        assert_eq!(all.len(), 4);
+
+        let mut paged = Vec::new();
+        for offset in [0, 3] {
+            paged.extend(list(&cache_manager, "#", false, 3, offset));
+        }
+        assert_eq!(paged, all);
+        assert!(list(&cache_manager, "#", false, 3, 10).is_empty());
+
+        let reply = list_retained_messages_by_req(
+            &cache_manager,
+            Request::new(ListRetainedMessagesRequest {
+                topic_filter: "#".to_string(),
+                limit: 1,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        assert_eq!(reply.total_count, 4);
+        assert_eq!(reply.retained_messages.len(), 1);
+    }
+
+    #[test]
+    fn list_retained_messages_by_req_invalid_filter_test() {
+        let cache_manager = retained_cache();
+        for topic_filter in ["", "sensor/#/temp"] {
+            assert!(list_retained_messages_by_req(
+                &cache_manager,
+                Request::new(ListRetainedMessagesRequest {
+                    topic_filter: topic_filter.to_string(),
+                    ..Default::default()
+                }),
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn select_retained_topics_single_topic_test() {
+        let cache_manager = retained_cache();
+        assert_eq!(
+            select_retained_topics(&cache_manager, "sensor/1/temp", false).unwrap(),
+            vec!["sensor/1/temp"]
+        );
+        // a topic without retained message has nothing to clear
+        assert!(
+            select_retained_topics(&cache_manager, "sensor/3/temp", false)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(select_retained_topics(&cache_manager, "", false).is_err());
+
+        // once cleared the topic is no longer listed
+        cache_manager.update_topic_retain_message("sensor/1/temp", Some(Vec::new()));
+        assert!(
+            select_retained_topics(&cache_manager, "sensor/1/temp", false)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(list(&cache_manager, "sensor/1/temp", false, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn select_retained_topics_wildcard_test() {
+        let cache_manager = retained_cache();
+
+        // a wildcard clear needs confirm
+        assert!(select_retained_topics(&cache_manager, "sensor/+/temp", false).is_err());
+        assert!(select_retained_topics(&cache_manager, "#", false).is_err());
+        assert!(select_retained_topics(&cache_manager, "sensor/#/temp", true).is_err());
+
+        assert_eq!(
+            select_retained_topics(&cache_manager, "sensor/+/temp", true).unwrap(),
+            vec!["sensor/1/temp", "sensor/2/temp"]
+        );
+        assert_eq!(
+            select_retained_topics(&cache_manager, "sensor/#", true).unwrap(),
+            vec!["sensor/1/temp", "sensor/2/humidity", "sensor/2/temp"]
+        );
+        assert_eq!(
+            select_retained_topics(&cache_manager, "#", true)
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn extract_topic_activity_test() {
+        let cache_manager = retained_cache();
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        // two messages on the first topic, one on the second
+        for (topic_name, time) in [
+            ("sensor/1/temp", 100),
+            ("sensor/1/temp", 200),
+            ("sensor/2/temp", 150),
+        ] {
+            cache_manager.next_topic_sequence(topic_name);
+            cache_manager.record_topic_last_message_at(topic_name, time);
+        }
+        subscribe_manager.add_topic_subscribe("sensor/1/temp", "c1", "sensor/+/temp");
+        subscribe_manager.add_topic_subscribe("sensor/1/temp", "c1", "sensor/#");
+        subscribe_manager.add_topic_subscribe("sensor/1/temp", "c2", "sensor/1/temp");
+        subscribe_manager.add_topic_subscribe("sensor/2/temp", "c1", "sensor/+/temp");
+
+        let topics = extract_topic(&cache_manager, &subscribe_manager).unwrap();
+        let activity = |topic_name: &str| {
+            let raw = topics
+                .iter()
+                .find(|raw| raw.topic_name == topic_name)
+                .unwrap();
+            (raw.message_count, raw.subscriber_count, raw.last_message_at)
+        };
+        // a client subscribed with two matching filters counts once
+        assert_eq!(activity("sensor/1/temp"), (2, 2, 200));
+        assert_eq!(activity("sensor/2/temp"), (1, 1, 150));
+        assert_eq!(activity("device/1/status"), (0, 0, 0));
+
+        // unsubscribing is reflected without recounting the subscriptions
+        subscribe_manager.remove_topic_subscribe_by_path("sensor/1/temp", "c2", "sensor/1/temp");
+        let topics = extract_topic(&cache_manager, &subscribe_manager).unwrap();
+        let raw = topics
+            .iter()
+            .find(|raw| raw.topic_name == "sensor/1/temp")
+            .unwrap();
+        assert_eq!(raw.subscriber_count, 1);
+    }
+}
@@ -14,13 +14,27 @@
 
 use crate::admin::query::{apply_filters, apply_pagination, apply_sorting, Queryable};
 use crate::handler::cache::CacheManager;
+use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
+use crate::observability::metrics::session::{
+    get_user_session_kick_counter, USER_SESSION_KICK_SCOPE_CROSS_NODE,
+    USER_SESSION_KICK_SCOPE_LOCAL,
+};
+use crate::observability::metrics::subscribe::get_subscribe_rate_limited_counter;
 use crate::security::AuthDriver;
+use common_base::tools::now_second;
+use common_config::mqtt::config::UserSingleSession;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::user::MqttUser;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    CreateUserRequest, DeleteUserRequest, ListUserRequest, UserRaw,
+    BatchCreateUserRaw, BatchCreateUserReply, BatchCreateUserRequest, CreateUserReply,
+    CreateUserRequest, DeleteUserRateLimitRequest, DeleteUserRequest, GetUserSingleSessionReply,
+    ListRateLimitReply, ListUserMessageExpiryRequest, ListUserRequest, SetUserMessageExpiryRequest,
+    SetUserRateLimitRequest, SetUserSingleSessionRequest, UserMessageExpiryRaw, UserRateLimitRaw,
+    UserRaw,
 };
+use std::collections::{BTreeSet, HashSet};
+use std::future::Future;
 use std::sync::Arc;
 use tonic::Request;
 
@@ -50,23 +64,155 @@ pub async fn list_user_by_req(
     Ok(pagination)
 }
 
-// Create a new user
+// Create a new user, the reply echoes the user as it was stored. By default creating a user
+// that already exists fails, if_not_exists keeps the existing user and upsert overwrites it.
 pub async fn create_user_by_req(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
     request: Request<CreateUserRequest>,
-) -> Result<(), MqttBrokerError> {
+) -> Result<CreateUserReply, MqttBrokerError> {
     let req = request.into_inner();
     let mqtt_user = MqttUser {
         username: req.username,
         password: req.password,
         is_superuser: req.is_superuser,
+        create_time: now_second(),
     };
 
+    let username = mqtt_user.username.clone();
+    let existing = cache_manager
+        .user_info
+        .get(&username)
+        .map(|user| user.clone());
     let auth_driver = AuthDriver::new(cache_manager.clone(), client_pool.clone());
-    auth_driver.save_user(mqtt_user).await?;
+    match create_user_action(existing.as_ref(), mqtt_user, req.if_not_exists, req.upsert)? {
+        CreateUserAction::Create(user) => auth_driver.save_user(user).await?,
+        CreateUserAction::Keep => {}
+        CreateUserAction::Overwrite(user) => auth_driver.update_user(user).await?,
+    }
 
-    Ok(())
+    stored_user_reply(cache_manager, &username)
+}
+
+#[derive(Debug, PartialEq)]
+enum CreateUserAction {
+    Create(MqttUser),
+    Keep,
+    Overwrite(MqttUser),
+}
+
+fn create_user_action(
+    existing: Option<&MqttUser>,
+    user: MqttUser,
+    if_not_exists: bool,
+    upsert: bool,
+) -> Result<CreateUserAction, MqttBrokerError> {
+    if if_not_exists && upsert {
+        return Err(MqttBrokerError::CommonError(
+            "if_not_exists and upsert cannot both be set".to_string(),
+        ));
+    }
+
+    let Some(existing) = existing else {
+        return Ok(CreateUserAction::Create(user));
+    };
+    if if_not_exists {
+        return Ok(CreateUserAction::Keep);
+    }
+    if upsert {
+        // The user keeps the time it was first created at
+        return Ok(CreateUserAction::Overwrite(MqttUser {
+            create_time: existing.create_time,
+            ..user
+        }));
+    }
+    Err(MqttBrokerError::UserAlreadyExist)
+}
+
+fn stored_user_reply(
+    cache_manager: &Arc<CacheManager>,
+    username: &str,
+) -> Result<CreateUserReply, MqttBrokerError> {
+    match cache_manager.user_info.get(username) {
+        Some(user) => Ok(CreateUserReply {
+            username: user.username.clone(),
+            is_superuser: user.is_superuser,
+            create_time: user.create_time,
+        }),
+        None => Err(MqttBrokerError::UserDoesNotExist),
+    }
+}
+
+// Create several users in one request. The auth storage has no transactions, so the users
+// are created one by one and the reply reports the outcome of each of them. A batch with
+// the same username twice is rejected before any user is created.
+pub async fn batch_create_user_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<BatchCreateUserRequest>,
+) -> Result<BatchCreateUserReply, MqttBrokerError> {
+    let req = request.into_inner();
+    batch_create_users(req.users, |user| async move {
+        create_user_by_req(cache_manager, client_pool, Request::new(user))
+            .await
+            .map(|_| ())
+    })
+    .await
+}
+
+async fn batch_create_users<F, Fut>(
+    users: Vec<CreateUserRequest>,
+    create_user: F,
+) -> Result<BatchCreateUserReply, MqttBrokerError>
+where
+    F: Fn(CreateUserRequest) -> Fut,
+    Fut: Future<Output = Result<(), MqttBrokerError>>,
+{
+    if users.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "users cannot be empty".to_string(),
+        ));
+    }
+
+    let mut usernames = HashSet::new();
+    for user in users.iter() {
+        if user.username.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "username cannot be empty".to_string(),
+            ));
+        }
+        if !usernames.insert(user.username.as_str()) {
+            return Err(MqttBrokerError::CommonError(format!(
+                "username {} appears more than once in the batch",
+                user.username
+            )));
+        }
+    }
+
+    let mut reply = BatchCreateUserReply::default();
+    for user in users {
+        let username = user.username.clone();
+        let raw = match create_user(user).await {
+            Ok(()) => {
+                reply.success_num += 1;
+                BatchCreateUserRaw {
+                    username,
+                    success: true,
+                    error: "".to_string(),
+                }
+            }
+            Err(e) => {
+                reply.failure_num += 1;
+                BatchCreateUserRaw {
+                    username,
+                    success: false,
+                    error: e.to_string(),
+                }
+            }
+        };
+        reply.results.push(raw);
+    }
+    Ok(reply)
 }
 
 // Delete an existing user
@@ -83,6 +229,211 @@ pub async fn delete_user_by_req(
     Ok(())
 }
 
+// Set the default message expiry of a user, an interval of 0 removes the default
+pub async fn set_user_message_expiry_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetUserMessageExpiryRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.username.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "username cannot be empty".to_string(),
+        ));
+    }
+
+    let mut config = cache_manager.get_user_message_expiry_config();
+    if req.message_expiry_interval == 0 {
+        config.user_defaults.remove(&req.username);
+        cache_manager.remove_user_default_expiry_applied(&req.username);
+    } else {
+        config
+            .user_defaults
+            .insert(req.username, req.message_expiry_interval);
+    }
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::UserMessageExpiry,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_user_message_expiry_config(config);
+    Ok(())
+}
+
+// List the default message expiry of all users
+pub fn list_user_message_expiry_by_req(
+    cache_manager: &Arc<CacheManager>,
+    request: Request<ListUserMessageExpiryRequest>,
+) -> Result<(Vec<UserMessageExpiryRaw>, usize), MqttBrokerError> {
+    let req = request.into_inner();
+    let config = cache_manager.get_user_message_expiry_config();
+
+    let mut expiries = Vec::new();
+    for (username, message_expiry_interval) in config.user_defaults {
+        expiries.push(UserMessageExpiryRaw {
+            applied_num: cache_manager.get_user_default_expiry_applied_num(&username),
+            username,
+            message_expiry_interval,
+        });
+    }
+
+    let filtered = apply_filters(expiries, &req.options);
+    let sorted = apply_sorting(filtered, &req.options);
+    let pagination = apply_pagination(sorted, &req.options);
+    Ok(pagination)
+}
+
+// Set the publish rate, and the subscribe rate when given, of a user, replacing the cluster defaults
+pub async fn set_user_rate_limit_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetUserRateLimitRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.username.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "username cannot be empty".to_string(),
+        ));
+    }
+
+    let mut config = cache_manager.get_rate_limit_config();
+    if let Some(subscribe_rate) = req.subscribe_rate {
+        config
+            .user_subscribe_overrides
+            .insert(req.username.clone(), subscribe_rate);
+    }
+    config.user_overrides.insert(req.username, req.publish_rate);
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::RateLimit,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_rate_limit_config(config);
+    Ok(())
+}
+
+// Remove the publish and subscribe rate overrides of a user, the user falls back to the cluster defaults
+pub async fn delete_user_rate_limit_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<DeleteUserRateLimitRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    let mut config = cache_manager.get_rate_limit_config();
+    let publish_removed = config.user_overrides.remove(&req.username).is_some();
+    let subscribe_removed = config
+        .user_subscribe_overrides
+        .remove(&req.username)
+        .is_some();
+    if !publish_removed && !subscribe_removed {
+        return Err(MqttBrokerError::CommonError(format!(
+            "user {} has no rate limit override",
+            req.username
+        )));
+    }
+
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::RateLimit,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_rate_limit_config(config);
+    Ok(())
+}
+
+// List the default rates and the overrides of all users, with the rates each user
+// published and subscribed at on this broker in the last second
+pub fn list_rate_limit_by_req(cache_manager: &Arc<CacheManager>) -> ListRateLimitReply {
+    let config = cache_manager.get_rate_limit_config();
+    let now = now_second();
+
+    let usernames: BTreeSet<&String> = config
+        .user_overrides
+        .keys()
+        .chain(config.user_subscribe_overrides.keys())
+        .collect();
+    let overrides: Vec<UserRateLimitRaw> = usernames
+        .into_iter()
+        .map(|username| {
+            let publish_rate = cache_manager.get_user_publish_rate(username);
+            let current_rate = cache_manager
+                .publish_rate_limiter
+                .current_rate(username, now);
+            let subscribe_rate = config.subscribe_rate(username);
+            let current_subscribe_rate = cache_manager
+                .subscribe_rate_limiter
+                .current_rate(username, now);
+            UserRateLimitRaw {
+                username: username.clone(),
+                publish_rate,
+                current_rate,
+                utilization: rate_utilization(current_rate, publish_rate),
+                rejected_num: cache_manager.publish_rate_limiter.rejected_num(username),
+                subscribe_rate,
+                current_subscribe_rate,
+                subscribe_rejected_num: get_subscribe_rate_limited_counter(username),
+            }
+        })
+        .collect();
+
+    ListRateLimitReply {
+        default_publish_rate: config.default_publish_rate,
+        default_subscribe_rate: config.default_subscribe_rate,
+        overrides,
+    }
+}
+
+// Set whether users may hold only one session in the whole cluster
+pub async fn set_user_single_session_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    request: Request<SetUserSingleSessionRequest>,
+) -> Result<(), MqttBrokerError> {
+    let req = request.into_inner();
+    if req.usernames.iter().any(|username| username.is_empty()) {
+        return Err(MqttBrokerError::CommonError(
+            "usernames cannot contain an empty username".to_string(),
+        ));
+    }
+
+    let config = UserSingleSession {
+        enable: req.enable,
+        usernames: req.usernames,
+    };
+    save_cluster_dynamic_config(
+        client_pool,
+        ClusterDynamicConfig::UserSingleSession,
+        config.encode(),
+    )
+    .await?;
+    cache_manager.update_user_single_session_config(config);
+    Ok(())
+}
+
+// The single session per user setting, with how many sessions this broker disconnected for it
+pub fn get_user_single_session_by_req(
+    cache_manager: &Arc<CacheManager>,
+) -> GetUserSingleSessionReply {
+    let config = cache_manager.get_user_single_session_config();
+    GetUserSingleSessionReply {
+        enable: config.enable,
+        usernames: config.usernames,
+        local_kick_num: get_user_session_kick_counter(USER_SESSION_KICK_SCOPE_LOCAL),
+        cross_node_kick_num: get_user_session_kick_counter(USER_SESSION_KICK_SCOPE_CROSS_NODE),
+    }
+}
+
+fn rate_utilization(current_rate: u64, publish_rate: u64) -> f64 {
+    if publish_rate == 0 {
+        return 0.0;
+    }
+    current_rate as f64 / publish_rate as f64
+}
+
 impl Queryable for UserRaw {
     fn get_field_str(&self, field: &str) -> Option<String> {
         match field {
@@ -92,3 +443,201 @@ fn get_field_str(&self, field: &str) -> Option<String> {
         }
     }
 }
+
+impl Queryable for UserMessageExpiryRaw {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "username" => Some(self.username.clone()),
+            "message_expiry_interval" => Some(self.message_expiry_interval.to_string()),
+            "applied_num" => Some(self.applied_num.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use common_base::tools::now_second;
+    use common_config::mqtt::config::RateLimit;
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::user::MqttUser;
+    use protocol::broker_mqtt::broker_mqtt_admin::CreateUserRequest;
+
+    use super::{
+        batch_create_users, create_user_action, list_rate_limit_by_req, stored_user_reply,
+        CreateUserAction,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::handler::error::MqttBrokerError;
+
+    fn user(username: &str) -> CreateUserRequest {
+        CreateUserRequest {
+            username: username.to_string(),
+            password: "pwd".to_string(),
+            is_superuser: false,
+            if_not_exists: false,
+            upsert: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_create_users_success_test() {
+        let created = Mutex::new(Vec::new());
+        let reply = batch_create_users(vec![user("u1"), user("u2"), user("u3")], |user| {
+            created.lock().unwrap().push(user.username);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(reply.success_num, 3);
+        assert_eq!(reply.failure_num, 0);
+        assert!(reply.results.iter().all(|raw| raw.success));
+        assert_eq!(*created.lock().unwrap(), vec!["u1", "u2", "u3"]);
+    }
+
+    #[tokio::test]
+    async fn batch_create_users_partial_failure_test() {
+        let reply = batch_create_users(
+            vec![user("u1"), user("u2"), user("u3")],
+            |user| async move {
+                if user.username == "u2" {
+                    Err(MqttBrokerError::UserAlreadyExist)
+                } else {
+                    Ok(())
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reply.success_num, 2);
+        assert_eq!(reply.failure_num, 1);
+        let failed: Vec<&str> = reply
+            .results
+            .iter()
+            .filter(|raw| !raw.success)
+            .map(|raw| raw.username.as_str())
+            .collect();
+        assert_eq!(failed, vec!["u2"]);
+        assert_eq!(
+            reply.results[1].error,
+            MqttBrokerError::UserAlreadyExist.to_string()
+        );
+        assert!(reply.results[0].error.is_empty());
+    }
+
+    #[tokio::test]
+    async fn batch_create_users_duplicate_test() {
+        let created = Mutex::new(0);
+        let result = batch_create_users(vec![user("u1"), user("u2"), user("u1")], |_| {
+            *created.lock().unwrap() += 1;
+            async { Ok(()) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(*created.lock().unwrap(), 0);
+
+        assert!(batch_create_users(Vec::new(), |_| async { Ok(()) })
+            .await
+            .is_err());
+    }
+
+    fn mqtt_user(password: &str, is_superuser: bool, create_time: u64) -> MqttUser {
+        MqttUser {
+            username: "u1".to_string(),
+            password: password.to_string(),
+            is_superuser,
+            create_time,
+        }
+    }
+
+    #[test]
+    fn create_user_action_test() {
+        let existing = mqtt_user("old", false, 100);
+        let new_user = mqtt_user("new", true, 200);
+
+        // create new
+        assert_eq!(
+            create_user_action(None, new_user.clone(), false, false).unwrap(),
+            CreateUserAction::Create(new_user.clone())
+        );
+        assert_eq!(
+            create_user_action(None, new_user.clone(), true, false).unwrap(),
+            CreateUserAction::Create(new_user.clone())
+        );
+        assert_eq!(
+            create_user_action(None, new_user.clone(), false, true).unwrap(),
+            CreateUserAction::Create(new_user.clone())
+        );
+
+        // create existing, strict
+        assert!(matches!(
+            create_user_action(Some(&existing), new_user.clone(), false, false),
+            Err(MqttBrokerError::UserAlreadyExist)
+        ));
+
+        // if_not_exists keeps the existing user
+        assert_eq!(
+            create_user_action(Some(&existing), new_user.clone(), true, false).unwrap(),
+            CreateUserAction::Keep
+        );
+
+        // upsert overwrites the password and superuser flag but not the create time
+        assert_eq!(
+            create_user_action(Some(&existing), new_user.clone(), false, true).unwrap(),
+            CreateUserAction::Overwrite(mqtt_user("new", true, 100))
+        );
+
+        assert!(create_user_action(Some(&existing), new_user, true, true).is_err());
+    }
+
+    #[test]
+    fn stored_user_reply_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        assert!(stored_user_reply(&cache_manager, "u1").is_err());
+
+        cache_manager.add_user(MqttUser {
+            username: "u1".to_string(),
+            password: "pwd".to_string(),
+            is_superuser: true,
+            create_time: now_second(),
+        });
+        let reply = stored_user_reply(&cache_manager, "u1").unwrap();
+        let cached = cache_manager.user_info.get("u1").unwrap();
+        assert_eq!(reply.username, cached.username);
+        assert_eq!(reply.is_superuser, cached.is_superuser);
+        assert_eq!(reply.create_time, cached.create_time);
+        assert!(reply.create_time > 0);
+    }
+
+    #[test]
+    fn list_rate_limit_by_req_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(Default::default());
+        cache_manager.update_rate_limit_config(RateLimit {
+            default_publish_rate: 100,
+            user_overrides: HashMap::from([("u1".to_string(), 10)]),
+            default_subscribe_rate: 5,
+            user_subscribe_overrides: HashMap::from([("u2".to_string(), 1)]),
+        });
+
+        let reply = list_rate_limit_by_req(&cache_manager);
+        assert_eq!(reply.default_publish_rate, 100);
+        assert_eq!(reply.default_subscribe_rate, 5);
+
+        // users with either override are listed with both effective rates
+        let rates: Vec<(&str, u64, u64)> = reply
+            .overrides
+            .iter()
+            .map(|raw| (raw.username.as_str(), raw.publish_rate, raw.subscribe_rate))
+            .collect();
+        assert_eq!(rates, vec![("u1", 10, 5), ("u2", 100, 1)]);
+    }
+}
@@ -12,20 +12,34 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::observability::metrics::connector::{
+    incr_connector_record_skipped_counter, incr_connector_throttled_counter,
+};
+use crate::storage::message::MessageStorage;
 use axum::async_trait;
 
 use common_config::mqtt::broker_mqtt_conf;
+use metadata_struct::adapter::record::Record;
 use metadata_struct::mqtt::bridge::{
-    config_local_file::LocalFileConnectorConfig, connector::MQTTConnector,
-    connector_type::ConnectorType, status::MQTTStatus,
+    config_kafka::KafkaConnectorConfig,
+    config_local_file::LocalFileConnectorConfig,
+    connector::{ConnectorRetryPolicy, MQTTConnector},
+    connector_type::ConnectorType,
+    status::MQTTStatus,
 };
 use std::{sync::Arc, time::Duration};
 use storage_adapter::storage::StorageAdapter;
 use tokio::{select, sync::broadcast, time::sleep};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use super::{file::FileBridgePlugin, manager::ConnectorManager};
+use super::{
+    file::FileBridgePlugin,
+    kafka::KafkaBridgePlugin,
+    manager::ConnectorManager,
+    transform::{build_bridge_transforms, transform_bridge_record, BridgeTransform},
+};
 
 #[derive(Clone)]
 pub struct BridgePluginReadConfig {
@@ -39,13 +53,185 @@ pub struct BridgePluginThread {
     pub stop_send: broadcast::Sender<bool>,
 }
 
+// The retry policy of a connector, with the dead letter topic resolved to the topic id
+// the rejected batches are written to
+#[derive(Clone, Default)]
+pub struct BridgeRetryConfig {
+    pub policy: ConnectorRetryPolicy,
+    pub dead_letter_topic_id: Option<String>,
+}
+
+pub enum BridgeRetryAction {
+    Retry,
+    GiveUp,
+    // The connector is stopping, the batch is left uncommitted
+    Stop,
+}
+
+// Backoff before the given retry of a batch, counted from 0. It doubles from
+// initial_backoff_ms on and is capped at max_backoff_ms.
+pub fn retry_backoff(policy: &ConnectorRetryPolicy, retry: u32) -> Duration {
+    let factor = 1u64.checked_shl(retry).unwrap_or(u64::MAX);
+    Duration::from_millis(
+        policy
+            .initial_backoff_ms
+            .saturating_mul(factor)
+            .min(policy.max_backoff_ms),
+    )
+}
+
+// The retries of one batch. After each failed write the plugin asks whether to send the batch
+// again, and once the retries are used up hands it to give_up.
+pub struct BridgeRetry<'a> {
+    connector_manager: &'a Arc<ConnectorManager>,
+    connector_name: &'a str,
+    config: &'a BridgeRetryConfig,
+    retries: u32,
+}
+
+impl<'a> BridgeRetry<'a> {
+    pub fn new(
+        connector_manager: &'a Arc<ConnectorManager>,
+        connector_name: &'a str,
+        config: &'a BridgeRetryConfig,
+    ) -> Self {
+        BridgeRetry {
+            connector_manager,
+            connector_name,
+            config,
+            retries: 0,
+        }
+    }
+
+    // Waits out the backoff before the next retry. A stop signal ends the wait.
+    pub async fn retry(
+        &mut self,
+        error: &MqttBrokerError,
+        stop_recv: &mut broadcast::Receiver<bool>,
+    ) -> BridgeRetryAction {
+        self.connector_manager
+            .report_error(self.connector_name, &error.to_string());
+        if self.retries >= self.config.policy.max_retries {
+            return BridgeRetryAction::GiveUp;
+        }
+
+        let backoff = retry_backoff(&self.config.policy, self.retries);
+        self.retries += 1;
+        self.connector_manager.record_retry(self.connector_name);
+        warn!(
+            "Connector {} failed to write a batch, retry {} of {} in {:?}, error message: {}",
+            self.connector_name, self.retries, self.config.policy.max_retries, backoff, error
+        );
+        select! {
+            val = stop_recv.recv() => {
+                if let Ok(true) = val {
+                    return BridgeRetryAction::Stop;
+                }
+                BridgeRetryAction::Retry
+            }
+            _ = sleep(backoff) => BridgeRetryAction::Retry
+        }
+    }
+
+    // Writes a batch the sink kept rejecting to the dead letter topic, or drops it when the
+    // connector has none
+    pub async fn give_up<S>(
+        &self,
+        message_storage: &MessageStorage<S>,
+        records: &[Record],
+    ) -> Result<(), MqttBrokerError>
+    where
+        S: StorageAdapter + Sync + Send + 'static + Clone,
+    {
+        let num = records.len() as u64;
+        let Some(topic_id) = &self.config.dead_letter_topic_id else {
+            error!(
+                "Connector {} dropped {} records after {} retries",
+                self.connector_name, num, self.retries
+            );
+            self.connector_manager
+                .record_dropped(self.connector_name, num);
+            return Ok(());
+        };
+
+        let records = records
+            .iter()
+            .cloned()
+            .map(|mut record| {
+                record.offset = None;
+                record
+            })
+            .collect();
+        message_storage
+            .append_topic_message(topic_id, records)
+            .await?;
+        warn!(
+            "Connector {} wrote {} records to the dead letter topic {} after {} retries",
+            self.connector_name, num, self.config.policy.dead_letter_topic, self.retries
+        );
+        self.connector_manager
+            .record_dead_letter(self.connector_name, num);
+        Ok(())
+    }
+}
+
 #[async_trait]
 pub trait BridgePlugin {
     async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError>;
 }
 
+// The form in which every connector writes a record to its sink
+pub fn encode_bridge_record(record: &Record) -> Result<String, MqttBrokerError> {
+    Ok(serde_json::to_string(record)?)
+}
+
+// Encodes a record after the transforms of the connector. A record the transforms can't be
+// applied to is skipped and counted, so that one malformed message does not stall the connector.
+pub fn encode_transformed_bridge_record(
+    connector_name: &str,
+    transforms: &[BridgeTransform],
+    record: &Record,
+) -> Result<Option<String>, MqttBrokerError> {
+    if transforms.is_empty() {
+        return Ok(Some(encode_bridge_record(record)?));
+    }
+    match transform_bridge_record(transforms, record) {
+        Ok(record) => Ok(Some(encode_bridge_record(&record)?)),
+        Err(e) => {
+            incr_connector_record_skipped_counter(connector_name);
+            warn!(
+                "Connector {} skipped the record at offset {:?}, transform failed: {}",
+                connector_name, record.offset, e
+            );
+            Ok(None)
+        }
+    }
+}
+
+// Number of records the connector may read next. While the forwarding quota of the current
+// second is used up, waits for the next second.
+pub async fn wait_forward_quota(
+    connector_manager: &Arc<ConnectorManager>,
+    connector_name: &str,
+    record_num: u64,
+) -> u64 {
+    let mut throttled = false;
+    loop {
+        let quota = connector_manager.forward_quota(connector_name, record_num);
+        if quota > 0 {
+            return quota;
+        }
+        if !throttled {
+            incr_connector_throttled_counter(connector_name);
+            throttled = true;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
 pub async fn start_connector_thread<S>(
     message_storage: Arc<S>,
+    cache_manager: Arc<CacheManager>,
     connector_manager: Arc<ConnectorManager>,
     stop_send: broadcast::Sender<bool>,
 ) where
@@ -64,6 +250,7 @@ pub async fn start_connector_thread<S>(
             }
             _ = check_connector(
                 &message_storage,
+                &cache_manager,
                 &connector_manager,
             ) => {
                 sleep(Duration::from_secs(1)).await;
@@ -72,11 +259,19 @@ pub async fn start_connector_thread<S>(
     }
 }
 
-async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<ConnectorManager>)
-where
+async fn check_connector<S>(
+    message_storage: &Arc<S>,
+    cache_manager: &Arc<CacheManager>,
+    connector_manager: &Arc<ConnectorManager>,
+) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     let config = broker_mqtt_conf();
+    connector_manager.set_max_records_per_sec(
+        cache_manager
+            .get_connector_limit_config()
+            .max_records_per_sec,
+    );
 
     // Start connector thread
     for raw in connector_manager.get_all_connector() {
@@ -97,6 +292,14 @@ async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<Co
             continue;
         }
 
+        let retry = match bridge_retry_config(cache_manager, &raw) {
+            Ok(retry) => retry,
+            Err(e) => {
+                connector_manager.report_error(&raw.connector_name, &e.to_string());
+                continue;
+            }
+        };
+
         let (stop_send, _) = broadcast::channel::<bool>(1);
         let thread = BridgePluginThread {
             connector_name: raw.connector_name.clone(),
@@ -107,6 +310,7 @@ async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<Co
             connector_manager.clone(),
             message_storage.clone(),
             raw.clone(),
+            retry,
             thread,
         );
     }
@@ -138,15 +342,53 @@ async fn check_connector<S>(message_storage: &Arc<S>, connector_manager: &Arc<Co
     }
 }
 
+// The dead letter topic has to exist before the connector starts, the connector is held
+// back until then
+fn bridge_retry_config(
+    cache_manager: &Arc<CacheManager>,
+    connector: &MQTTConnector,
+) -> Result<BridgeRetryConfig, MqttBrokerError> {
+    let policy = connector.retry_policy.clone();
+    if policy.dead_letter_topic.is_empty() {
+        return Ok(BridgeRetryConfig {
+            policy,
+            dead_letter_topic_id: None,
+        });
+    }
+    let Some(topic) = cache_manager.get_topic_by_name(&policy.dead_letter_topic) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "dead letter topic {} does not exist",
+            policy.dead_letter_topic
+        )));
+    };
+    Ok(BridgeRetryConfig {
+        policy,
+        dead_letter_topic_id: Some(topic.topic_id),
+    })
+}
+
 fn start_thread<S>(
     connector_manager: Arc<ConnectorManager>,
     message_storage: Arc<S>,
     connector: MQTTConnector,
+    retry: BridgeRetryConfig,
     thread: BridgePluginThread,
 ) where
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     tokio::spawn(async move {
+        let transforms = match build_bridge_transforms(&connector.transforms) {
+            Ok(transforms) => transforms,
+            Err(e) => {
+                error!(
+                    "Failed to build the transforms of connector {} with error message :{}",
+                    connector.connector_name, e
+                );
+                connector_manager.report_error(&connector.connector_name, &e);
+                return;
+            }
+        };
+
         match connector.connector_type {
             ConnectorType::LocalFile => {
                 let local_file_config = match serde_json::from_str::<LocalFileConnectorConfig>(
@@ -155,6 +397,7 @@ fn start_thread<S>(
                     Ok(config) => config,
                     Err(e) => {
                         error!("Failed to parse LocalFileConnectorConfig file with error message :{}, configuration contents: {}", e, connector.config);
+                        connector_manager.report_error(&connector.connector_name, &e.to_string());
                         return;
                     }
                 };
@@ -164,6 +407,8 @@ fn start_thread<S>(
                     message_storage.clone(),
                     connector.connector_name.clone(),
                     local_file_config,
+                    transforms,
+                    retry,
                     thread.stop_send.clone(),
                 );
 
@@ -177,13 +422,52 @@ fn start_thread<S>(
                     .await
                 {
                     connector_manager.remove_connector_thread(&connector.connector_name);
+                    connector_manager.report_error(&connector.connector_name, &e.to_string());
                     error!(
                         "Failed to start FileBridgePlugin with error message: {:?}",
                         e
                     );
                 }
             }
-            ConnectorType::Kafka => {}
+            ConnectorType::Kafka => {
+                let kafka_config = match serde_json::from_str::<KafkaConnectorConfig>(
+                    &connector.config,
+                ) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        error!("Failed to parse KafkaConnectorConfig with error message :{}, configuration contents: {}", e, connector.config);
+                        connector_manager.report_error(&connector.connector_name, &e.to_string());
+                        return;
+                    }
+                };
+
+                let bridge = KafkaBridgePlugin::new(
+                    connector_manager.clone(),
+                    message_storage.clone(),
+                    connector.connector_name.clone(),
+                    kafka_config,
+                    transforms,
+                    retry,
+                    thread.stop_send.clone(),
+                );
+
+                connector_manager.add_connector_thread(&connector.connector_name, thread);
+
+                if let Err(e) = bridge
+                    .exec(BridgePluginReadConfig {
+                        topic_id: connector.topic_id,
+                        record_num: 100,
+                    })
+                    .await
+                {
+                    connector_manager.remove_connector_thread(&connector.connector_name);
+                    connector_manager.report_error(&connector.connector_name, &e.to_string());
+                    error!(
+                        "Failed to start KafkaBridgePlugin with error message: {:?}",
+                        e
+                    );
+                }
+            }
         }
     });
 }
@@ -192,3 +476,34 @@ fn stop_thread(thread: BridgePluginThread) -> Result<(), MqttBrokerError> {
     thread.stop_send.send(true)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use metadata_struct::mqtt::bridge::connector::ConnectorRetryPolicy;
+
+    use super::retry_backoff;
+
+    #[test]
+    fn retry_backoff_test() {
+        let policy = ConnectorRetryPolicy {
+            max_retries: 10,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            ..Default::default()
+        };
+        let backoffs: Vec<Duration> = (0..6).map(|retry| retry_backoff(&policy, retry)).collect();
+        assert_eq!(
+            backoffs,
+            [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis)
+        );
+
+        // Large retry counts stay at the cap instead of overflowing
+        assert_eq!(retry_backoff(&policy, 64), Duration::from_millis(1000));
+        assert_eq!(
+            retry_backoff(&policy, u32::MAX),
+            Duration::from_millis(1000)
+        );
+    }
+}
@@ -14,8 +14,12 @@
 
 use std::{sync::Arc, time::Duration};
 
-use super::core::{BridgePlugin, BridgePluginReadConfig};
+use super::core::{
+    encode_transformed_bridge_record, wait_forward_quota, BridgePlugin, BridgePluginReadConfig,
+    BridgeRetry, BridgeRetryAction, BridgeRetryConfig,
+};
 use super::manager::ConnectorManager;
+use super::transform::BridgeTransform;
 use crate::{handler::error::MqttBrokerError, storage::message::MessageStorage};
 use axum::async_trait;
 use metadata_struct::{
@@ -32,6 +36,8 @@ pub struct FileBridgePlugin<S> {
     message_storage: Arc<S>,
     connector_name: String,
     config: LocalFileConnectorConfig,
+    transforms: Vec<BridgeTransform>,
+    retry: BridgeRetryConfig,
     stop_send: broadcast::Sender<bool>,
 }
 
@@ -44,6 +50,8 @@ pub fn new(
         message_storage: Arc<S>,
         connector_name: String,
         config: LocalFileConnectorConfig,
+        transforms: Vec<BridgeTransform>,
+        retry: BridgeRetryConfig,
         stop_send: broadcast::Sender<bool>,
     ) -> Self {
         FileBridgePlugin {
@@ -51,6 +59,8 @@ pub fn new(
             message_storage,
             connector_name,
             config,
+            transforms,
+            retry,
             stop_send,
         }
     }
@@ -61,7 +71,11 @@ pub async fn append(
         writer: &mut BufWriter<File>,
     ) -> Result<(), MqttBrokerError> {
         for record in records {
-            let data = serde_json::to_string(record)?;
+            let Some(data) =
+                encode_transformed_bridge_record(&self.connector_name, &self.transforms, record)?
+            else {
+                continue;
+            };
             writer.write_all(data.as_ref()).await?;
         }
         writer.flush().await?;
@@ -83,9 +97,33 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
             .open(self.config.local_file_path.clone())
             .await?;
         let mut writer = tokio::io::BufWriter::new(file);
+        self.connector_manager.report_running(&self.connector_name);
 
         loop {
+            // A paused connector reads nothing, so the group offset stays where it stopped
+            if self.connector_manager.check_paused(&self.connector_name) {
+                self.connector_manager
+                    .report_heartbeat(&self.connector_name);
+                select! {
+                    val = recv.recv() =>{
+                        if let Ok(flag) = val {
+                            if flag {
+                                break;
+                            }
+                        }
+                    },
+                    _ = sleep(Duration::from_millis(100)) => {}
+                }
+                continue;
+            }
+
             let offset = message_storage.get_group_offset(&group_name).await?;
+            let record_num = wait_forward_quota(
+                &self.connector_manager,
+                &self.connector_name,
+                config.record_num,
+            )
+            .await;
 
             select! {
                 val = recv.recv() =>{
@@ -96,7 +134,7 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
                     }
                 },
 
-                val = message_storage.read_topic_message(&config.topic_id, offset, config.record_num) => {
+                val = message_storage.read_topic_message(&config.topic_id, offset, record_num) => {
                     match val {
                         Ok(data) => {
                             self.connector_manager.report_heartbeat(&self.connector_name);
@@ -105,30 +143,55 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
                                 continue;
                             }
 
-                            if let Err(e) = self.append(&data,&mut writer).await{
+                            let mut retry = BridgeRetry::new(&self.connector_manager, &self.connector_name, &self.retry);
+                            // None once the sink took the batch
+                            let action = loop {
+                                let Err(e) = self.append(&data, &mut writer).await else {
+                                    self.connector_manager.report_success(&self.connector_name);
+                                    break None;
+                                };
                                 error!("Connector {} failed to write data to {}, error message :{}", self.connector_name,self.config.local_file_path, e);
-                                sleep(Duration::from_millis(100)).await;
+                                match retry.retry(&e, &mut recv).await {
+                                    BridgeRetryAction::Retry => continue,
+                                    action => break Some(action),
+                                }
+                            };
+                            match action {
+                                Some(BridgeRetryAction::Stop) => break,
+                                Some(BridgeRetryAction::GiveUp) => {
+                                    if let Err(e) = retry.give_up(&message_storage, &data).await {
+                                        error!("Connector {} failed to route a rejected batch, error message :{}", self.connector_name, e);
+                                        self.connector_manager.report_error(&self.connector_name, &e.to_string());
+                                        sleep(Duration::from_millis(100)).await;
+                                        continue;
+                                    }
+                                }
+                                _ => {}
                             }
+                            self.connector_manager.record_forwarded(&self.connector_name, data.len() as u64);
 
                             // commit offset
                             message_storage.commit_group_offset(&group_name, &config.topic_id, offset + data.len() as u64).await?;
                         },
                         Err(e) => {
                             error!("Connector {} failed to read Topic {} data with error message :{}", self.connector_name,config.topic_id,e);
+                            self.connector_manager.report_error(&self.connector_name, &e.to_string());
                             sleep(Duration::from_millis(100)).await;
                         }
                     }
                 }
             }
         }
+        self.connector_manager.report_stopped(&self.connector_name);
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+    use std::{fs, path::Path, path::PathBuf, sync::Arc, time::Duration};
 
+    use bytes::Bytes;
     use common_base::{
         tools::{now_second, unique_id},
         utils::crc::calc_crc32,
@@ -136,8 +199,15 @@ mod tests {
     use common_config::mqtt::{config::BrokerMqttConfig, init_broker_mqtt_conf_by_config};
     use metadata_struct::{
         adapter::record::{Header, Record},
-        mqtt::bridge::config_local_file::LocalFileConnectorConfig,
+        mqtt::{
+            bridge::{
+                config_local_file::LocalFileConnectorConfig,
+                connector::{ConnectorRetryPolicy, MQTTConnector},
+            },
+            message::MqttMessage,
+        },
     };
+    use serde_json::{json, Value};
     use storage_adapter::{
         memory::MemoryStorageAdapter,
         storage::{ShardInfo, StorageAdapter},
@@ -145,10 +215,13 @@ mod tests {
     use tokio::{fs::File, io::AsyncReadExt, sync::broadcast, time::sleep};
 
     use crate::bridge::{
-        core::{BridgePlugin, BridgePluginReadConfig},
+        core::{BridgePlugin, BridgePluginReadConfig, BridgeRetryConfig},
         file::FileBridgePlugin,
-        manager::ConnectorManager,
+        manager::{ConnectorManager, ConnectorState},
+        transform::BridgeTransform,
     };
+    use crate::observability::metrics::connector::get_connector_record_skipped_counter;
+    use crate::storage::message::MessageStorage;
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -226,6 +299,8 @@ async fn file_bridge_plugin_test() {
             storage_adapter.clone(),
             connector_name.clone(),
             config.clone(),
+            Vec::new(),
+            BridgeRetryConfig::default(),
             stop_send.clone(),
         );
 
@@ -259,4 +334,315 @@ async fn file_bridge_plugin_test() {
 
         assert_eq!(res, expected);
     }
+
+    #[tokio::test]
+    async fn file_bridge_plugin_pause_test() {
+        let namespace = unique_id();
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: namespace.clone(),
+            ..Default::default()
+        });
+
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let shard_name = "test_topic".to_string();
+        storage_adapter
+            .create_shard(ShardInfo {
+                namespace: namespace.clone(),
+                shard_name: shard_name.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let test_data: Vec<Record> = (0..10)
+            .map(|i| {
+                let mut record = Record::build_byte(format!("test_data_{}", i).as_bytes().to_vec());
+                record.offset = Some(i);
+                record
+            })
+            .collect();
+        storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), test_data.clone())
+            .await
+            .unwrap();
+
+        let dir_path = tempdir().unwrap().path().to_str().unwrap().to_string();
+        let config = LocalFileConnectorConfig {
+            local_file_path: PathBuf::from(dir_path.clone())
+                .join("test.txt")
+                .to_str()
+                .unwrap()
+                .to_string(),
+        };
+        fs::create_dir_all(dir_path).unwrap();
+        File::create(config.local_file_path.clone()).await.unwrap();
+
+        // The connector starts paused
+        let connector_name = "test_paused_connector".to_string();
+        let connector_manager = Arc::new(ConnectorManager::new());
+        let mut connector = MQTTConnector {
+            connector_name: connector_name.clone(),
+            topic_id: shard_name.clone(),
+            paused: true,
+            ..Default::default()
+        };
+        connector_manager.add_connector(&connector);
+
+        let (stop_send, _) = broadcast::channel(1);
+        let file_bridge_plugin = FileBridgePlugin::new(
+            connector_manager.clone(),
+            storage_adapter.clone(),
+            connector_name.clone(),
+            config.clone(),
+            Vec::new(),
+            BridgeRetryConfig::default(),
+            stop_send.clone(),
+        );
+        let read_config = BridgePluginReadConfig {
+            topic_id: shard_name.clone(),
+            record_num: 100,
+        };
+        let handle = tokio::spawn(async move {
+            file_bridge_plugin.exec(read_config).await.unwrap();
+        });
+
+        sleep(Duration::from_secs(1)).await;
+        let content = fs::read_to_string(&config.local_file_path).unwrap();
+        assert!(content.is_empty());
+        let status = connector_manager
+            .get_connector_status(&connector_name)
+            .unwrap();
+        assert_eq!(status.state, ConnectorState::Paused);
+        assert_eq!(status.last_success_at, 0);
+
+        // After resuming, the connector forwards the records that arrived while it was paused
+        connector.paused = false;
+        connector_manager.add_connector(&connector);
+        sleep(Duration::from_secs(1)).await;
+
+        stop_send.send(true).unwrap();
+        handle.await.unwrap();
+
+        let expected = test_data.iter().fold(String::new(), |acc, record| {
+            acc + &serde_json::to_string(record).unwrap()
+        });
+        let content = fs::read_to_string(&config.local_file_path).unwrap();
+        assert_eq!(content, expected);
+        assert!(
+            connector_manager
+                .get_connector_status(&connector_name)
+                .unwrap()
+                .last_success_at
+                > 0
+        );
+    }
+
+    #[tokio::test]
+    async fn file_bridge_plugin_transform_test() {
+        let namespace = unique_id();
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: namespace.clone(),
+            ..Default::default()
+        });
+
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let shard_name = "test_topic".to_string();
+        storage_adapter
+            .create_shard(ShardInfo {
+                namespace: namespace.clone(),
+                shard_name: shard_name.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // The record at offset 3 is not JSON and is skipped
+        let test_data: Vec<Record> = (0..5)
+            .map(|i| {
+                let payload = if i == 3 {
+                    "not json".to_string()
+                } else {
+                    format!(r#"{{"t":{},"id":"d1"}}"#, i)
+                };
+                let message = MqttMessage {
+                    client_id: "c1".to_string(),
+                    payload: Bytes::from(payload),
+                    ..Default::default()
+                };
+                let mut record = Record::build_byte(message.encode());
+                record.offset = Some(i);
+                record
+            })
+            .collect();
+        storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), test_data)
+            .await
+            .unwrap();
+
+        let dir_path = tempdir().unwrap().path().to_str().unwrap().to_string();
+        let config = LocalFileConnectorConfig {
+            local_file_path: PathBuf::from(dir_path.clone())
+                .join("test.txt")
+                .to_str()
+                .unwrap()
+                .to_string(),
+        };
+        fs::create_dir_all(dir_path).unwrap();
+        File::create(config.local_file_path.clone()).await.unwrap();
+
+        let connector_name = "test_transform_connector".to_string();
+        let connector_manager = Arc::new(ConnectorManager::new());
+        let (stop_send, _) = broadcast::channel(1);
+        let file_bridge_plugin = FileBridgePlugin::new(
+            connector_manager.clone(),
+            storage_adapter.clone(),
+            connector_name.clone(),
+            config.clone(),
+            vec![BridgeTransform::Rename {
+                fields: [("t".to_string(), "temperature".to_string())].into(),
+            }],
+            BridgeRetryConfig::default(),
+            stop_send.clone(),
+        );
+        let read_config = BridgePluginReadConfig {
+            topic_id: shard_name.clone(),
+            record_num: 100,
+        };
+        let handle = tokio::spawn(async move {
+            file_bridge_plugin.exec(read_config).await.unwrap();
+        });
+
+        sleep(Duration::from_secs(1)).await;
+        stop_send.send(true).unwrap();
+        handle.await.unwrap();
+
+        let content = fs::read_to_string(&config.local_file_path).unwrap();
+        let payloads: Vec<Value> = serde_json::Deserializer::from_str(&content)
+            .into_iter::<Record>()
+            .map(|record| {
+                let message = MqttMessage::decode_record(record.unwrap()).unwrap();
+                serde_json::from_slice(&message.payload).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![
+                json!({"temperature": 0, "id": "d1"}),
+                json!({"temperature": 1, "id": "d1"}),
+                json!({"temperature": 2, "id": "d1"}),
+                json!({"temperature": 4, "id": "d1"}),
+            ]
+        );
+        assert_eq!(get_connector_record_skipped_counter(&connector_name), 1);
+    }
+
+    #[tokio::test]
+    async fn file_bridge_plugin_retry_test() {
+        // Every write to /dev/full fails, so the sink rejects each batch
+        if !Path::new("/dev/full").exists() {
+            return;
+        }
+
+        let namespace = unique_id();
+        init_broker_mqtt_conf_by_config(BrokerMqttConfig {
+            cluster_name: namespace.clone(),
+            ..Default::default()
+        });
+
+        let storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let shard_name = "test_topic".to_string();
+        storage_adapter
+            .create_shard(ShardInfo {
+                namespace: namespace.clone(),
+                shard_name: shard_name.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let test_data: Vec<Record> = (0..5)
+            .map(|i| Record::build_byte(format!("test_data_{}", i).as_bytes().to_vec()))
+            .collect();
+        storage_adapter
+            .batch_write(namespace.clone(), shard_name.clone(), test_data.clone())
+            .await
+            .unwrap();
+
+        let config = LocalFileConnectorConfig {
+            local_file_path: "/dev/full".to_string(),
+        };
+        let policy = ConnectorRetryPolicy {
+            max_retries: 2,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 20,
+            dead_letter_topic: "connector/dlq".to_string(),
+        };
+
+        // The first connector routes the batch to its dead letter topic, the second has none
+        // and drops it
+        for (connector_name, dead_letter_topic_id) in [
+            (
+                "test_dead_letter_connector",
+                Some("dlq_topic_id".to_string()),
+            ),
+            ("test_drop_connector", None),
+        ] {
+            let connector_manager = Arc::new(ConnectorManager::new());
+            let (stop_send, _) = broadcast::channel(1);
+            let file_bridge_plugin = FileBridgePlugin::new(
+                connector_manager.clone(),
+                storage_adapter.clone(),
+                connector_name.to_string(),
+                config.clone(),
+                Vec::new(),
+                BridgeRetryConfig {
+                    policy: policy.clone(),
+                    dead_letter_topic_id: dead_letter_topic_id.clone(),
+                },
+                stop_send.clone(),
+            );
+            let read_config = BridgePluginReadConfig {
+                topic_id: shard_name.clone(),
+                record_num: 100,
+            };
+            let handle = tokio::spawn(async move {
+                file_bridge_plugin.exec(read_config).await.unwrap();
+            });
+
+            sleep(Duration::from_secs(1)).await;
+            stop_send.send(true).unwrap();
+            handle.await.unwrap();
+
+            let status = connector_manager
+                .get_connector_status(connector_name)
+                .unwrap();
+            assert_eq!(status.retry_num, 2);
+            assert_eq!(status.last_success_at, 0);
+            assert!(!status.last_error.is_empty());
+            if dead_letter_topic_id.is_some() {
+                assert_eq!(status.dead_letter_num, 5);
+                assert_eq!(status.dropped_num, 0);
+            } else {
+                assert_eq!(status.dead_letter_num, 0);
+                assert_eq!(status.dropped_num, 5);
+            }
+
+            // The batch is given up once, so the offset moved past it
+            let message_storage = MessageStorage::new(storage_adapter.clone());
+            assert_eq!(
+                message_storage
+                    .get_group_offset(connector_name)
+                    .await
+                    .unwrap(),
+                5
+            );
+        }
+
+        let dead_letters = MessageStorage::new(storage_adapter.clone())
+            .read_topic_message("dlq_topic_id", 0, 100)
+            .await
+            .unwrap();
+        let payloads: Vec<Vec<u8>> = dead_letters.into_iter().map(|record| record.data).collect();
+        let expected: Vec<Vec<u8>> = test_data.into_iter().map(|record| record.data).collect();
+        assert_eq!(payloads, expected);
+    }
 }
@@ -15,8 +15,10 @@
 use std::{sync::Arc, time::Duration};
 
 use axum::async_trait;
+use futures::future::join_all;
 use metadata_struct::{adapter::record::Record, mqtt::bridge::config_kafka::KafkaConnectorConfig};
-use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
 use storage_adapter::storage::StorageAdapter;
 use tokio::{select, sync::broadcast, time::sleep};
 use tracing::{error, info};
@@ -24,8 +26,12 @@
 use crate::{handler::error::MqttBrokerError, storage::message::MessageStorage};
 
 use super::{
-    core::{BridgePlugin, BridgePluginReadConfig},
+    core::{
+        encode_transformed_bridge_record, wait_forward_quota, BridgePlugin, BridgePluginReadConfig,
+        BridgeRetry, BridgeRetryAction, BridgeRetryConfig,
+    },
     manager::ConnectorManager,
+    transform::BridgeTransform,
 };
 
 pub struct KafkaBridgePlugin<S> {
@@ -33,6 +39,8 @@ pub struct KafkaBridgePlugin<S> {
     message_storage: Arc<S>,
     connector_name: String,
     config: KafkaConnectorConfig,
+    transforms: Vec<BridgeTransform>,
+    retry: BridgeRetryConfig,
     stop_send: broadcast::Sender<bool>,
 }
 
@@ -45,6 +53,8 @@ pub fn new(
         message_storage: Arc<S>,
         connector_name: String,
         config: KafkaConnectorConfig,
+        transforms: Vec<BridgeTransform>,
+        retry: BridgeRetryConfig,
         stop_send: broadcast::Sender<bool>,
     ) -> Self {
         KafkaBridgePlugin {
@@ -52,6 +62,8 @@ pub fn new(
             message_storage,
             connector_name,
             config,
+            transforms,
+            retry,
             stop_send,
         }
     }
@@ -61,24 +73,45 @@ pub async fn append(
         records: &Vec<Record>,
         producer: FutureProducer,
     ) -> Result<(), MqttBrokerError> {
+        let mut payloads = Vec::with_capacity(records.len());
         for record in records {
-            let data = serde_json::to_string(record)?;
-            producer
-                .send(
-                    FutureRecord::to(self.config.topic.as_str())
-                        .key(self.config.key.as_str())
-                        .payload(&data),
-                    Duration::from_secs(0),
-                )
-                .await
-                .map_err(|(e, _)| e)?;
+            if let Some(data) =
+                encode_transformed_bridge_record(&self.connector_name, &self.transforms, record)?
+            {
+                payloads.push(data);
+            }
         }
 
-        producer.flush(Duration::from_secs(0))?;
+        // Hand the whole batch to the producer before waiting, so that it can batch and
+        // compress the messages according to batch_size and linger_ms
+        let deliveries = payloads.iter().map(|data| {
+            producer.send(
+                FutureRecord::to(self.config.topic.as_str())
+                    .key(self.config.key.as_str())
+                    .payload(data),
+                Duration::from_secs(0),
+            )
+        });
+        for delivery in join_all(deliveries).await {
+            delivery.map_err(|(e, _)| e)?;
+        }
         Ok(())
     }
 }
 
+// The producer of a connector, tuned by the options of its config
+pub fn build_producer_config(config: &KafkaConnectorConfig) -> ClientConfig {
+    let mut client_config = ClientConfig::new();
+    client_config
+        .set("bootstrap.servers", config.bootstrap_servers.as_str())
+        .set("message.timeout.ms", "5000")
+        .set("acks", config.acks.as_str())
+        .set("batch.size", config.batch_size.to_string())
+        .set("linger.ms", config.linger_ms.to_string())
+        .set("compression.type", config.compression.as_str());
+    client_config
+}
+
 #[async_trait]
 impl<S> BridgePlugin for KafkaBridgePlugin<S>
 where
@@ -87,14 +120,37 @@ impl<S> BridgePlugin for KafkaBridgePlugin<S>
     async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerError> {
         let message_storage = MessageStorage::new(self.message_storage.clone());
         let group_name = self.connector_name.clone();
-        let offset = message_storage.get_group_offset(&group_name).await?;
         let mut recv = self.stop_send.subscribe();
-        let producer: FutureProducer = rdkafka::ClientConfig::new()
-            .set("bootstrap.servers", self.config.bootstrap_servers.as_str())
-            .set("message.timeout.ms", "5000")
-            .create()?;
+        let producer: FutureProducer = build_producer_config(&self.config).create()?;
+        self.connector_manager.report_running(&self.connector_name);
 
         loop {
+            // A paused connector reads nothing, so the group offset stays where it stopped
+            if self.connector_manager.check_paused(&self.connector_name) {
+                self.connector_manager
+                    .report_heartbeat(&self.connector_name);
+                select! {
+                    val = recv.recv() =>{
+                        if let Ok(flag) = val {
+                            if flag {
+                                info!("{}","Connector thread exited successfully");
+                                break;
+                            }
+                        }
+                    },
+                    _ = sleep(Duration::from_millis(100)) => {}
+                }
+                continue;
+            }
+
+            let offset = message_storage.get_group_offset(&group_name).await?;
+            let record_num = wait_forward_quota(
+                &self.connector_manager,
+                &self.connector_name,
+                config.record_num,
+            )
+            .await;
+
             select! {
                 val = recv.recv() =>{
                     if let Ok(flag) = val {
@@ -105,7 +161,7 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
                     }
                 }
 
-                val = message_storage.read_topic_message(&config.topic_id, offset, config.record_num) => {
+                val = message_storage.read_topic_message(&config.topic_id, offset, record_num) => {
                     match val {
                         Ok(data) => {
                             self.connector_manager.report_heartbeat(&self.connector_name);
@@ -114,13 +170,41 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
                                 continue;
                             }
 
-                            if let Err(e) = self.append(&data, producer.clone()).await{
+                            // The offset only moves on once Kafka acknowledged the batch or
+                            // the batch was given up after its retries
+                            let mut retry = BridgeRetry::new(&self.connector_manager, &self.connector_name, &self.retry);
+                            // None once the sink took the batch
+                            let action = loop {
+                                let Err(e) = self.append(&data, producer.clone()).await else {
+                                    self.connector_manager.report_success(&self.connector_name);
+                                    break None;
+                                };
                                 error!("Connector {} failed to write data to kafka topic {}, error message: {}", self.connector_name, self.config.topic, e);
-                                sleep(Duration::from_millis(100)).await;
+                                match retry.retry(&e, &mut recv).await {
+                                    BridgeRetryAction::Retry => continue,
+                                    action => break Some(action),
+                                }
+                            };
+                            match action {
+                                Some(BridgeRetryAction::Stop) => break,
+                                Some(BridgeRetryAction::GiveUp) => {
+                                    if let Err(e) = retry.give_up(&message_storage, &data).await {
+                                        error!("Connector {} failed to route a rejected batch, error message: {}", self.connector_name, e);
+                                        self.connector_manager.report_error(&self.connector_name, &e.to_string());
+                                        sleep(Duration::from_millis(100)).await;
+                                        continue;
+                                    }
+                                }
+                                _ => {}
                             }
+                            self.connector_manager.record_forwarded(&self.connector_name, data.len() as u64);
+
+                            // commit offset
+                            message_storage.commit_group_offset(&group_name, &config.topic_id, offset + data.len() as u64).await?;
                         },
                         Err(e) => {
                             error!("Connector {} failed to read Topic {} data with error message :{}", self.connector_name,config.topic_id,e);
+                            self.connector_manager.report_error(&self.connector_name, &e.to_string());
                             sleep(Duration::from_millis(100)).await;
                         }
                     }
@@ -128,6 +212,51 @@ async fn exec(&self, config: BridgePluginReadConfig) -> Result<(), MqttBrokerErr
             }
         }
 
+        self.connector_manager.report_stopped(&self.connector_name);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::mqtt::bridge::config_kafka::KafkaConnectorConfig;
+
+    use super::build_producer_config;
+
+    #[test]
+    fn build_producer_config_test() {
+        // Options left out of the config take their defaults
+        let config: KafkaConnectorConfig = serde_json::from_str(
+            r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"t1","key":"k1"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            KafkaConnectorConfig {
+                bootstrap_servers: "127.0.0.1:9092".to_string(),
+                topic: "t1".to_string(),
+                key: "k1".to_string(),
+                ..Default::default()
+            }
+        );
+        let client_config = build_producer_config(&config);
+        assert_eq!(client_config.get("acks"), Some("all"));
+        assert_eq!(client_config.get("batch.size"), Some("1000000"));
+        assert_eq!(client_config.get("linger.ms"), Some("5"));
+        assert_eq!(client_config.get("compression.type"), Some("none"));
+
+        let config: KafkaConnectorConfig = serde_json::from_str(
+            r#"{"bootstrap_servers":"127.0.0.1:9092","topic":"t1","key":"k1","acks":"1","batch_size":65536,"linger_ms":50,"compression":"zstd"}"#,
+        )
+        .unwrap();
+        let client_config = build_producer_config(&config);
+        assert_eq!(
+            client_config.get("bootstrap.servers"),
+            Some("127.0.0.1:9092")
+        );
+        assert_eq!(client_config.get("acks"), Some("1"));
+        assert_eq!(client_config.get("batch.size"), Some("65536"));
+        assert_eq!(client_config.get("linger.ms"), Some("50"));
+        assert_eq!(client_config.get("compression.type"), Some("zstd"));
+    }
+}
@@ -12,12 +12,44 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use common_base::tools::now_second;
 use dashmap::DashMap;
 use metadata_struct::mqtt::bridge::connector::MQTTConnector;
 
 use super::core::BridgePluginThread;
 
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ConnectorState {
+    #[default]
+    Stopped,
+    Running,
+    Paused,
+    Error,
+}
+
+impl Display for ConnectorState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// Runtime status of a connector, maintained by the connector task on the broker running it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectorStatus {
+    pub state: ConnectorState,
+    // The last error stays until it is replaced by another one
+    pub last_error: String,
+    pub last_success_at: u64,
+    // Batches sent again after the sink rejected them
+    pub retry_num: u64,
+    // Records written to the dead letter topic, or dropped without one, once retries ran out
+    pub dead_letter_num: u64,
+    pub dropped_num: u64,
+}
+
 #[derive(Default)]
 pub struct ConnectorManager {
     // (connector_name, Connector)
@@ -28,6 +60,15 @@ pub struct ConnectorManager {
 
     // (connector_name, u64)
     pub connector_heartbeat: DashMap<String, u64>,
+
+    // (connector_name, ConnectorStatus)
+    pub connector_status: DashMap<String, ConnectorStatus>,
+
+    // (connector_name, (second, records forwarded in that second))
+    pub connector_forward_window: DashMap<String, (u64, u64)>,
+
+    // Records a connector may forward per second, 0 is unlimited
+    pub max_records_per_sec: AtomicU64,
 }
 
 impl ConnectorManager {
@@ -36,6 +77,9 @@ pub fn new() -> Self {
             connector_list: DashMap::with_capacity(8),
             connector_thread: DashMap::with_capacity(8),
             connector_heartbeat: DashMap::with_capacity(8),
+            connector_status: DashMap::with_capacity(8),
+            connector_forward_window: DashMap::with_capacity(8),
+            max_records_per_sec: AtomicU64::new(0),
         }
     }
 
@@ -63,6 +107,8 @@ pub fn get_all_connector(&self) -> Vec<MQTTConnector> {
 
     pub fn remove_connector(&self, connector_name: &str) {
         self.connector_list.remove(connector_name);
+        self.connector_forward_window.remove(connector_name);
+        self.connector_status.remove(connector_name);
     }
 
     // Connector Thread
@@ -96,4 +142,227 @@ pub fn report_heartbeat(&self, connector_name: &str) {
         self.connector_heartbeat
             .insert(connector_name.to_owned(), now_second());
     }
+
+    // Connector Status
+    pub fn report_running(&self, connector_name: &str) {
+        self.connector_status
+            .entry(connector_name.to_owned())
+            .or_default()
+            .state = ConnectorState::Running;
+    }
+
+    // Records were written to the sink
+    pub fn report_success(&self, connector_name: &str) {
+        let mut status = self
+            .connector_status
+            .entry(connector_name.to_owned())
+            .or_default();
+        status.state = ConnectorState::Running;
+        status.last_success_at = now_second();
+    }
+
+    pub fn report_error(&self, connector_name: &str, error: &str) {
+        let mut status = self
+            .connector_status
+            .entry(connector_name.to_owned())
+            .or_default();
+        status.state = ConnectorState::Error;
+        status.last_error = error.to_owned();
+    }
+
+    pub fn report_stopped(&self, connector_name: &str) {
+        self.connector_status
+            .entry(connector_name.to_owned())
+            .or_default()
+            .state = ConnectorState::Stopped;
+    }
+
+    pub fn record_retry(&self, connector_name: &str) {
+        self.connector_status
+            .entry(connector_name.to_owned())
+            .or_default()
+            .retry_num += 1;
+    }
+
+    pub fn record_dead_letter(&self, connector_name: &str, num: u64) {
+        self.connector_status
+            .entry(connector_name.to_owned())
+            .or_default()
+            .dead_letter_num += num;
+    }
+
+    pub fn record_dropped(&self, connector_name: &str, num: u64) {
+        self.connector_status
+            .entry(connector_name.to_owned())
+            .or_default()
+            .dropped_num += num;
+    }
+
+    // Whether the connector is paused, moving its state in and out of Paused accordingly
+    pub fn check_paused(&self, connector_name: &str) -> bool {
+        let paused = self
+            .get_connector(connector_name)
+            .is_some_and(|connector| connector.paused);
+        if paused {
+            self.connector_status
+                .entry(connector_name.to_owned())
+                .or_default()
+                .state = ConnectorState::Paused;
+        } else if let Some(mut status) = self.connector_status.get_mut(connector_name) {
+            if status.state == ConnectorState::Paused {
+                status.state = ConnectorState::Running;
+            }
+        }
+        paused
+    }
+
+    // None when the connector does not run on this broker
+    pub fn get_connector_status(&self, connector_name: &str) -> Option<ConnectorStatus> {
+        self.connector_status
+            .get(connector_name)
+            .map(|status| status.clone())
+    }
+
+    // Connector forward limit
+    pub fn set_max_records_per_sec(&self, max_records_per_sec: u64) {
+        self.max_records_per_sec
+            .store(max_records_per_sec, Ordering::Relaxed);
+    }
+
+    pub fn get_max_records_per_sec(&self) -> u64 {
+        self.max_records_per_sec.load(Ordering::Relaxed)
+    }
+
+    // How many of `want` records the connector may forward right now, 0 means the quota of
+    // the current second is used up
+    pub fn forward_quota(&self, connector_name: &str, want: u64) -> u64 {
+        let max = self.get_max_records_per_sec();
+        if max == 0 {
+            return want;
+        }
+        let now = now_second();
+        match self.connector_forward_window.get(connector_name) {
+            Some(window) if window.0 == now => want.min(max.saturating_sub(window.1)),
+            _ => want.min(max),
+        }
+    }
+
+    pub fn record_forwarded(&self, connector_name: &str, num: u64) {
+        let now = now_second();
+        let mut window = self
+            .connector_forward_window
+            .entry(connector_name.to_owned())
+            .or_insert((now, 0));
+        if window.0 != now {
+            *window = (now, 0);
+        }
+        window.1 += num;
+    }
+
+    // Records forwarded by the connector in the current second
+    pub fn forwarded_in_window(&self, connector_name: &str) -> u64 {
+        match self.connector_forward_window.get(connector_name) {
+            Some(window) if window.0 == now_second() => window.1,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::mqtt::bridge::connector::MQTTConnector;
+
+    use super::{ConnectorManager, ConnectorState};
+
+    #[test]
+    fn forward_quota_test() {
+        let connector_manager = ConnectorManager::new();
+        assert_eq!(connector_manager.forward_quota("c1", 100), 100);
+
+        connector_manager.set_max_records_per_sec(30);
+        assert_eq!(connector_manager.forward_quota("c1", 100), 30);
+
+        connector_manager.record_forwarded("c1", 20);
+        assert_eq!(connector_manager.forward_quota("c1", 100), 10);
+        assert_eq!(connector_manager.forward_quota("c2", 100), 30);
+
+        connector_manager.record_forwarded("c1", 10);
+        assert_eq!(connector_manager.forward_quota("c1", 100), 0);
+        assert_eq!(connector_manager.forwarded_in_window("c1"), 30);
+    }
+
+    #[test]
+    fn connector_status_test() {
+        let connector_manager = ConnectorManager::new();
+        assert!(connector_manager.get_connector_status("c1").is_none());
+
+        connector_manager.report_running("c1");
+        let status = connector_manager.get_connector_status("c1").unwrap();
+        assert_eq!(status.state, ConnectorState::Running);
+        assert_eq!(status.last_success_at, 0);
+
+        connector_manager.report_success("c1");
+        let last_success_at = connector_manager
+            .get_connector_status("c1")
+            .unwrap()
+            .last_success_at;
+        assert!(last_success_at > 0);
+
+        connector_manager.report_error("c1", "kafka broker unreachable");
+        let status = connector_manager.get_connector_status("c1").unwrap();
+        assert_eq!(status.state, ConnectorState::Error);
+        assert_eq!(status.last_error, "kafka broker unreachable");
+        assert_eq!(status.last_success_at, last_success_at);
+
+        connector_manager.report_success("c1");
+        let status = connector_manager.get_connector_status("c1").unwrap();
+        assert_eq!(status.state, ConnectorState::Running);
+        assert_eq!(status.last_error, "kafka broker unreachable");
+
+        connector_manager.record_retry("c1");
+        connector_manager.record_retry("c1");
+        connector_manager.record_dead_letter("c1", 100);
+        connector_manager.record_dropped("c1", 3);
+
+        connector_manager.report_stopped("c1");
+        let status = connector_manager.get_connector_status("c1").unwrap();
+        assert_eq!(status.state, ConnectorState::Stopped);
+        assert_eq!(status.retry_num, 2);
+        assert_eq!(status.dead_letter_num, 100);
+        assert_eq!(status.dropped_num, 3);
+
+        connector_manager.remove_connector("c1");
+        assert!(connector_manager.get_connector_status("c1").is_none());
+    }
+    #[test]
+    fn check_paused_test() {
+        let connector_manager = ConnectorManager::new();
+        let mut connector = MQTTConnector {
+            connector_name: "c1".to_string(),
+            paused: true,
+            ..Default::default()
+        };
+        connector_manager.add_connector(&connector);
+        connector_manager.report_running("c1");
+
+        assert!(connector_manager.check_paused("c1"));
+        let status = connector_manager.get_connector_status("c1").unwrap();
+        assert_eq!(status.state, ConnectorState::Paused);
+
+        connector.paused = false;
+        connector_manager.add_connector(&connector);
+        assert!(!connector_manager.check_paused("c1"));
+        let status = connector_manager.get_connector_status("c1").unwrap();
+        assert_eq!(status.state, ConnectorState::Running);
+
+        // Resuming does not hide an error reported by the connector
+        connector_manager.report_error("c1", "disk full");
+        assert!(!connector_manager.check_paused("c1"));
+        let status = connector_manager.get_connector_status("c1").unwrap();
+        assert_eq!(status.state, ConnectorState::Error);
+
+        // A connector this broker does not know about is never paused
+        assert!(!connector_manager.check_paused("c2"));
+        assert!(connector_manager.get_connector_status("c2").is_none());
+    }
 }
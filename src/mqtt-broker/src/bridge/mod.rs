@@ -17,3 +17,4 @@
 pub mod heartbeat;
 pub mod kafka;
 pub mod manager;
+pub mod transform;
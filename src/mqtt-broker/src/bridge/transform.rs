@@ -0,0 +1,325 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::{BTreeMap, BTreeSet};
+
+use bytes::Bytes;
+use common_base::utils::crc::calc_crc32;
+use metadata_struct::adapter::record::Record;
+use metadata_struct::mqtt::bridge::transform::ConnectorTransform;
+use metadata_struct::mqtt::message::MqttMessage;
+use serde_json::Value;
+
+use crate::handler::error::MqttBrokerError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonPathSegment {
+    Field(String),
+    Index(usize),
+}
+
+// A transform of a connector with its path already parsed. Built once when the connector
+// starts, so the path is not parsed again for every record.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BridgeTransform {
+    Extract {
+        path: String,
+        segments: Vec<JsonPathSegment>,
+    },
+    Rename {
+        fields: BTreeMap<String, String>,
+    },
+}
+
+// Supports the subset of JSONPath that points at a single value: $, .field and [index]
+fn parse_json_path(path: &str) -> Result<Vec<JsonPathSegment>, String> {
+    let Some(mut rest) = path.strip_prefix('$') else {
+        return Err(format!("path {} must start with $", path));
+    };
+
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(format!("path {} has an empty field name", path));
+            }
+            segments.push(JsonPathSegment::Field(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let Some(end) = after.find(']') else {
+                return Err(format!("path {} has an unclosed [", path));
+            };
+            let index = after[..end]
+                .parse::<usize>()
+                .map_err(|_| format!("path {} has an invalid index {}", path, &after[..end]))?;
+            segments.push(JsonPathSegment::Index(index));
+            rest = &after[end + 1..];
+        } else {
+            return Err(format!(
+                "path {} has an unexpected character at {}",
+                path, rest
+            ));
+        }
+    }
+    Ok(segments)
+}
+
+// Checks the transforms of a connector when it is created or updated, so that a connector
+// never runs with transforms it can't apply
+pub fn validate_connector_transforms(transforms: &[ConnectorTransform]) -> Result<(), String> {
+    build_bridge_transforms(transforms).map(|_| ())
+}
+
+pub fn build_bridge_transforms(
+    transforms: &[ConnectorTransform],
+) -> Result<Vec<BridgeTransform>, String> {
+    transforms
+        .iter()
+        .enumerate()
+        .map(|(i, transform)| {
+            build_bridge_transform(transform).map_err(|e| format!("transform {}: {}", i, e))
+        })
+        .collect()
+}
+
+fn build_bridge_transform(transform: &ConnectorTransform) -> Result<BridgeTransform, String> {
+    match transform {
+        ConnectorTransform::Extract { path } => Ok(BridgeTransform::Extract {
+            path: path.clone(),
+            segments: parse_json_path(path)?,
+        }),
+        ConnectorTransform::Rename { fields } => {
+            validate_rename_fields(fields)?;
+            Ok(BridgeTransform::Rename {
+                fields: fields.clone(),
+            })
+        }
+    }
+}
+
+fn validate_rename_fields(fields: &BTreeMap<String, String>) -> Result<(), String> {
+    if fields.is_empty() {
+        return Err("rename needs at least one field".to_string());
+    }
+    let mut targets = BTreeSet::new();
+    for (from, to) in fields {
+        if from.is_empty() || to.is_empty() {
+            return Err("rename field names must not be empty".to_string());
+        }
+        if !targets.insert(to) {
+            return Err(format!("more than one field is renamed to {}", to));
+        }
+    }
+    Ok(())
+}
+
+fn apply_transform(transform: &BridgeTransform, payload: Value) -> Result<Value, String> {
+    match transform {
+        BridgeTransform::Extract { path, segments } => {
+            let mut value = &payload;
+            for segment in segments {
+                let next = match segment {
+                    JsonPathSegment::Field(name) => value.get(name),
+                    JsonPathSegment::Index(index) => value.get(*index),
+                };
+                value = next.ok_or_else(|| format!("payload has no value at {}", path))?;
+            }
+            Ok(value.clone())
+        }
+        BridgeTransform::Rename { fields } => {
+            let Value::Object(mut object) = payload else {
+                return Err("rename needs a JSON object payload".to_string());
+            };
+            // Take every source field first, so that swapping two names works
+            let renamed: Vec<(String, Value)> = fields
+                .iter()
+                .filter_map(|(from, to)| object.remove(from).map(|value| (to.clone(), value)))
+                .collect();
+            object.extend(renamed);
+            Ok(Value::Object(object))
+        }
+    }
+}
+
+// The record a connector writes to its sink. A record whose payload the transforms can't be
+// applied to is returned as an error, the caller decides whether to skip it.
+pub fn transform_bridge_record(
+    transforms: &[BridgeTransform],
+    record: &Record,
+) -> Result<Record, MqttBrokerError> {
+    let mut message = MqttMessage::decode_record(record.clone())?;
+    let mut payload = serde_json::from_slice::<Value>(&message.payload)
+        .map_err(|e| MqttBrokerError::CommonError(format!("payload is not valid JSON: {}", e)))?;
+    for transform in transforms {
+        payload = apply_transform(transform, payload).map_err(MqttBrokerError::CommonError)?;
+    }
+    message.payload = Bytes::from(serde_json::to_vec(&payload)?);
+
+    let mut record = record.clone();
+    record.data = message.encode();
+    record.crc_num = calc_crc32(&record.data);
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use bytes::Bytes;
+    use metadata_struct::adapter::record::Record;
+    use metadata_struct::mqtt::bridge::transform::ConnectorTransform;
+    use metadata_struct::mqtt::message::MqttMessage;
+    use serde_json::{json, Value};
+
+    use super::{
+        build_bridge_transforms, parse_json_path, transform_bridge_record,
+        validate_connector_transforms, BridgeTransform, JsonPathSegment,
+    };
+    use crate::handler::error::MqttBrokerError;
+
+    fn build_record(payload: &str) -> Record {
+        let message = MqttMessage {
+            client_id: "c1".to_string(),
+            payload: Bytes::from(payload.to_string()),
+            ..Default::default()
+        };
+        Record::build_byte(message.encode())
+    }
+
+    fn record_payload(record: &Record) -> Value {
+        let message = MqttMessage::decode_record(record.clone()).unwrap();
+        serde_json::from_slice(&message.payload).unwrap()
+    }
+
+    fn transform_record(
+        transforms: &[ConnectorTransform],
+        record: &Record,
+    ) -> Result<Record, MqttBrokerError> {
+        transform_bridge_record(&build_bridge_transforms(transforms).unwrap(), record)
+    }
+
+    fn rename(fields: &[(&str, &str)]) -> ConnectorTransform {
+        ConnectorTransform::Rename {
+            fields: fields
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn parse_json_path_test() {
+        assert_eq!(parse_json_path("$").unwrap(), vec![]);
+        assert_eq!(
+            parse_json_path("$.data.items[1].value").unwrap(),
+            vec![
+                JsonPathSegment::Field("data".to_string()),
+                JsonPathSegment::Field("items".to_string()),
+                JsonPathSegment::Index(1),
+                JsonPathSegment::Field("value".to_string()),
+            ]
+        );
+        assert!(parse_json_path("data").is_err());
+        assert!(parse_json_path("$..data").is_err());
+        assert!(parse_json_path("$.items[a]").is_err());
+        assert!(parse_json_path("$.items[0").is_err());
+        assert!(parse_json_path("$x").is_err());
+    }
+
+    #[test]
+    fn validate_connector_transforms_test() {
+        assert!(validate_connector_transforms(&[]).is_ok());
+        assert!(validate_connector_transforms(&[
+            ConnectorTransform::Extract {
+                path: "$.data".to_string()
+            },
+            rename(&[("t", "temperature")]),
+        ])
+        .is_ok());
+
+        let err = validate_connector_transforms(&[
+            rename(&[("t", "temperature")]),
+            ConnectorTransform::Extract {
+                path: "data".to_string(),
+            },
+        ])
+        .unwrap_err();
+        assert!(err.starts_with("transform 1:"));
+
+        assert!(validate_connector_transforms(&[rename(&[])]).is_err());
+        assert!(validate_connector_transforms(&[rename(&[("t", "")])]).is_err());
+        assert!(validate_connector_transforms(&[rename(&[("a", "x"), ("b", "x")])]).is_err());
+    }
+
+    #[test]
+    fn build_bridge_transforms_test() {
+        let transforms = build_bridge_transforms(&[ConnectorTransform::Extract {
+            path: "$.data[0]".to_string(),
+        }])
+        .unwrap();
+        assert_eq!(
+            transforms,
+            vec![BridgeTransform::Extract {
+                path: "$.data[0]".to_string(),
+                segments: vec![
+                    JsonPathSegment::Field("data".to_string()),
+                    JsonPathSegment::Index(0),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn transform_bridge_record_rename_test() {
+        let record = build_record(r#"{"t":21.5,"h":40,"id":"d1"}"#);
+        let transforms = vec![rename(&[("t", "temperature"), ("h", "humidity")])];
+
+        let output = transform_record(&transforms, &record).unwrap();
+        assert_eq!(
+            record_payload(&output),
+            json!({"temperature": 21.5, "humidity": 40, "id": "d1"})
+        );
+        assert!(output.crc32_check());
+
+        // Swapping two names keeps both values
+        let transforms = vec![rename(&[("t", "h"), ("h", "t")])];
+        let output = transform_record(&transforms, &record).unwrap();
+        assert_eq!(
+            record_payload(&output),
+            json!({"t": 40, "h": 21.5, "id": "d1"})
+        );
+    }
+
+    #[test]
+    fn transform_bridge_record_pipeline_test() {
+        let record = build_record(r#"{"data":{"items":[{"t":1},{"t":2}]}}"#);
+        let transforms = vec![
+            ConnectorTransform::Extract {
+                path: "$.data.items[1]".to_string(),
+            },
+            rename(&[("t", "temperature")]),
+        ];
+        let output = transform_record(&transforms, &record).unwrap();
+        assert_eq!(record_payload(&output), json!({"temperature": 2}));
+
+        let transforms = vec![ConnectorTransform::Extract {
+            path: "$.data.missing".to_string(),
+        }];
+        assert!(transform_record(&transforms, &record).is_err());
+
+        let transforms = vec![rename(&[("t", "temperature")])];
+        assert!(transform_record(&transforms, &build_record("not json")).is_err());
+        assert!(transform_record(&transforms, &build_record("[1,2]")).is_err());
+    }
+}
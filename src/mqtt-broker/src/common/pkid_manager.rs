@@ -20,13 +20,39 @@
     time::Duration,
 };
 
-use common_base::tools::now_second;
+use common_base::tools::{now_mills, now_second};
 use dashmap::DashMap;
 use protocol::mqtt::common::QoS;
 use tokio::time::sleep;
 
 use crate::handler::cache::{ClientPkidData, QosAckPacketInfo};
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Qos2Direction {
+    // Publish received from the client, the broker is waiting for PubRel
+    Inbound,
+    // Publish pushed to the client, the broker is waiting for PubRec or PubComp
+    Outbound,
+}
+
+impl Qos2Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Qos2Direction::Inbound => "inbound",
+            Qos2Direction::Outbound => "outbound",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InflightQos2 {
+    pub client_id: String,
+    pub pkid: u16,
+    pub direction: Qos2Direction,
+    pub state: &'static str,
+    pub age_ms: u64,
+}
+
 #[derive(Clone)]
 pub struct PkidManager {
     //(client_id_pkid, u64)
@@ -38,6 +64,9 @@ pub struct PkidManager {
     // (client_id_pkid, QosPkidData)
     pub client_pkid_data: DashMap<String, ClientPkidData>,
 
+    // (client_id, number of entries of the client in qos_ack_packet)
+    ack_packet_num: DashMap<String, u64>,
+
     pub pkid_atomic: Arc<AtomicU64>,
 }
 
@@ -53,22 +82,17 @@ pub fn new() -> Self {
             pkid_cache: DashMap::with_capacity(8),
             qos_ack_packet: DashMap::with_capacity(8),
             client_pkid_data: DashMap::with_capacity(8),
+            ack_packet_num: DashMap::with_capacity(8),
             pkid_atomic: Arc::new(AtomicU64::new(1)),
         }
     }
 
     pub fn remove_by_client_id(&self, client_id: &str) {
-        for (key, _) in self.qos_ack_packet.clone() {
-            if key.starts_with(client_id) {
-                self.qos_ack_packet.remove(&key);
-            }
-        }
-
-        for (key, _) in self.client_pkid_data.clone() {
-            if key.starts_with(client_id) {
-                self.qos_ack_packet.remove(&key);
-            }
-        }
+        self.qos_ack_packet
+            .retain(|key, _| !self.is_client_key(key, client_id));
+        self.client_pkid_data
+            .retain(|key, _| !self.is_client_key(key, client_id));
+        self.ack_packet_num.remove(client_id);
     }
 
     // sub => pub push pkid generate
@@ -99,13 +123,35 @@ pub async fn generate_pkid(&self, client_id: &str, qos: &QoS) -> u16 {
     // ack packet
     pub fn remove_ack_packet(&self, client_id: &str, pkid: u16) {
         let key = self.key(client_id, pkid);
-        self.qos_ack_packet.remove(&key);
+        if self.qos_ack_packet.remove(&key).is_some() {
+            if let Some(mut num) = self.ack_packet_num.get_mut(client_id) {
+                *num = num.saturating_sub(1);
+            }
+            self.ack_packet_num.remove_if(client_id, |_, num| *num == 0);
+        }
         self.pkid_cache.remove(&key);
     }
 
     pub fn add_ack_packet(&self, client_id: &str, pkid: u16, packet: QosAckPacketInfo) {
         let key = self.key(client_id, pkid);
-        self.qos_ack_packet.insert(key, packet);
+        if self.qos_ack_packet.insert(key, packet).is_none() {
+            *self.ack_packet_num.entry(client_id.to_owned()).or_default() += 1;
+        }
+    }
+
+    // Number of QoS 1/2 messages pushed to the client that are still waiting for an ack
+    pub fn ack_packet_num(&self, client_id: &str) -> usize {
+        self.ack_packet_num
+            .get(client_id)
+            .map(|num| *num as usize)
+            .unwrap_or(0)
+    }
+
+    pub fn mark_ack_packet_rec(&self, client_id: &str, pkid: u16) {
+        let key = self.key(client_id, pkid);
+        if let Some(mut data) = self.qos_ack_packet.get_mut(&key) {
+            data.pub_rec_received = true;
+        }
     }
 
     pub fn get_ack_packet(&self, client_id: &str, pkid: u16) -> Option<QosAckPacketInfo> {
@@ -141,7 +187,163 @@ pub fn get_client_pkid(&self, client_id: &str, pkid: u16) -> Option<ClientPkidDa
         None
     }
 
+    // QoS 2 exchanges that have not completed yet, for one client or all clients when client_id is empty
+    pub fn list_inflight_qos2(&self, client_id: &str) -> Vec<InflightQos2> {
+        let now_ms = now_mills();
+        let mut results = Vec::new();
+
+        for entry in self.client_pkid_data.iter() {
+            let Some((id, pkid)) = self.parse_key(entry.key()) else {
+                continue;
+            };
+            if !client_id.is_empty() && id != client_id {
+                continue;
+            }
+            results.push(InflightQos2 {
+                client_id: id.to_owned(),
+                pkid,
+                direction: Qos2Direction::Inbound,
+                state: "awaiting_pubrel",
+                age_ms: now_ms.saturating_sub(entry.create_time as u128 * 1000) as u64,
+            });
+        }
+
+        for entry in self.qos_ack_packet.iter() {
+            if entry.qos != QoS::ExactlyOnce {
+                continue;
+            }
+            let Some((id, pkid)) = self.parse_key(entry.key()) else {
+                continue;
+            };
+            if !client_id.is_empty() && id != client_id {
+                continue;
+            }
+            results.push(InflightQos2 {
+                client_id: id.to_owned(),
+                pkid,
+                direction: Qos2Direction::Outbound,
+                state: if entry.pub_rec_received {
+                    "awaiting_pubcomp"
+                } else {
+                    "awaiting_pubrec"
+                },
+                age_ms: now_ms.saturating_sub(entry.create_time) as u64,
+            });
+        }
+
+        results.sort_by(|a, b| b.age_ms.cmp(&a.age_ms));
+        results
+    }
+
+    pub fn get_inflight_qos2(
+        &self,
+        client_id: &str,
+        pkid: u16,
+        direction: &Qos2Direction,
+    ) -> Option<InflightQos2> {
+        self.list_inflight_qos2(client_id)
+            .into_iter()
+            .find(|raw| raw.pkid == pkid && raw.direction == *direction)
+    }
+
+    fn parse_key<'a>(&self, key: &'a str) -> Option<(&'a str, u16)> {
+        let (client_id, pkid) = key.rsplit_once('_')?;
+        Some((client_id, pkid.parse().ok()?))
+    }
+
+    // Whether the key belongs to the client, a client id that is a prefix of another one does
+    // not match its keys
+    fn is_client_key(&self, key: &str, client_id: &str) -> bool {
+        self.parse_key(key).is_some_and(|(id, _)| id == client_id)
+    }
+
     fn key(&self, client_id: &str, pkid: u16) -> String {
         format!("{}_{}", client_id, pkid)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast;
+
+    fn ack_packet(qos: QoS) -> QosAckPacketInfo {
+        let (sx, _) = broadcast::channel(1);
+        QosAckPacketInfo {
+            sx,
+            create_time: now_mills(),
+            qos,
+            pub_rec_received: false,
+        }
+    }
+
+    #[test]
+    fn list_inflight_qos2_test() {
+        let pkid_manager = PkidManager::new();
+        pkid_manager.add_client_pkid("c_1", 7);
+        pkid_manager.add_client_pkid("c2", 8);
+        pkid_manager.add_ack_packet("c_1", 9, ack_packet(QoS::ExactlyOnce));
+        pkid_manager.add_ack_packet("c_1", 10, ack_packet(QoS::AtLeastOnce));
+
+        let list = pkid_manager.list_inflight_qos2("c_1");
+        assert_eq!(list.len(), 2);
+
+        let inbound = pkid_manager
+            .get_inflight_qos2("c_1", 7, &Qos2Direction::Inbound)
+            .unwrap();
+        assert_eq!(inbound.state, "awaiting_pubrel");
+
+        let outbound = pkid_manager
+            .get_inflight_qos2("c_1", 9, &Qos2Direction::Outbound)
+            .unwrap();
+        assert_eq!(outbound.state, "awaiting_pubrec");
+
+        pkid_manager.mark_ack_packet_rec("c_1", 9);
+        let outbound = pkid_manager
+            .get_inflight_qos2("c_1", 9, &Qos2Direction::Outbound)
+            .unwrap();
+        assert_eq!(outbound.state, "awaiting_pubcomp");
+
+        assert!(pkid_manager
+            .get_inflight_qos2("c_1", 10, &Qos2Direction::Outbound)
+            .is_none());
+        assert_eq!(pkid_manager.list_inflight_qos2("").len(), 3);
+    }
+
+    #[test]
+    fn remove_by_client_id_test() {
+        let pkid_manager = PkidManager::new();
+        pkid_manager.add_client_pkid("c1", 1);
+        pkid_manager.add_client_pkid("c10", 2);
+        pkid_manager.add_ack_packet("c1", 3, ack_packet(QoS::AtLeastOnce));
+        pkid_manager.add_ack_packet("c10", 4, ack_packet(QoS::AtLeastOnce));
+
+        // Both the acks the broker waits for and the QoS 2 packet ids of the client are removed,
+        // the client whose id starts with the removed one keeps its data
+        pkid_manager.remove_by_client_id("c1");
+        assert!(pkid_manager.get_client_pkid("c1", 1).is_none());
+        assert!(pkid_manager.get_ack_packet("c1", 3).is_none());
+        assert!(pkid_manager.get_client_pkid("c10", 2).is_some());
+        assert!(pkid_manager.get_ack_packet("c10", 4).is_some());
+    }
+
+    #[test]
+    fn ack_packet_num_test() {
+        let pkid_manager = PkidManager::new();
+        pkid_manager.add_ack_packet("c1", 1, ack_packet(QoS::AtLeastOnce));
+        pkid_manager.add_ack_packet("c1", 2, ack_packet(QoS::ExactlyOnce));
+        // Replacing the ack of a packet id does not count it twice
+        pkid_manager.add_ack_packet("c1", 2, ack_packet(QoS::ExactlyOnce));
+        pkid_manager.add_ack_packet("c10", 3, ack_packet(QoS::AtLeastOnce));
+        assert_eq!(pkid_manager.ack_packet_num("c1"), 2);
+        assert_eq!(pkid_manager.ack_packet_num("c10"), 1);
+
+        pkid_manager.remove_ack_packet("c1", 1);
+        pkid_manager.remove_ack_packet("c1", 1);
+        assert_eq!(pkid_manager.ack_packet_num("c1"), 1);
+
+        pkid_manager.remove_by_client_id("c1");
+        assert_eq!(pkid_manager.ack_packet_num("c1"), 0);
+        assert_eq!(pkid_manager.ack_packet_num("c10"), 1);
+    }
+}
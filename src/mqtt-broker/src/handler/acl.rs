@@ -15,10 +15,11 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use common_base::tools::now_second;
 use tokio::select;
 use tokio::sync::broadcast;
 use tokio::time::sleep;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::security::AuthDriver;
 
@@ -64,3 +65,47 @@ async fn update_acl_cache(&self) {
         sleep(Duration::from_secs(5)).await;
     }
 }
+
+// How often expired blacklist entries are removed. Expired entries no longer ban anyone
+// before they are removed, this only keeps the cache and the storage small.
+const BLACKLIST_EXPIRY_INTERVAL_SEC: u64 = 10;
+
+pub struct BlacklistExpiryThread {
+    stop_send: broadcast::Sender<bool>,
+    auth_driver: Arc<AuthDriver>,
+}
+
+impl BlacklistExpiryThread {
+    pub fn new(stop_send: broadcast::Sender<bool>, auth_driver: Arc<AuthDriver>) -> Self {
+        BlacklistExpiryThread {
+            stop_send,
+            auth_driver,
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Blacklist expiry thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.purge_expired_blacklist()=>{
+                }
+            }
+        }
+    }
+
+    async fn purge_expired_blacklist(&self) {
+        let purged_num = self.auth_driver.purge_expired_blacklist(now_second()).await;
+        if purged_num > 0 {
+            debug!("Removed {} expired blacklist entries", purged_num);
+        }
+        sleep(Duration::from_secs(BLACKLIST_EXPIRY_INTERVAL_SEC)).await;
+    }
+}
@@ -13,13 +13,24 @@
 // limitations under the License.
 
 use crate::common::pkid_manager::PkidManager;
-use crate::observability::system_topic::sysmon::SystemAlarmEventMessage;
+use crate::handler::error::MqttBrokerError;
+use crate::handler::flow_control::UserRateLimiter;
+use crate::handler::message_dedup::MessageDeduplicator;
+use crate::observability::alarm_webhook::AlarmWebhookNotifier;
+use crate::observability::metrics::session::{
+    incr_session_tiering_counter, SESSION_TIERING_ACTION_OFFLOAD, SESSION_TIERING_ACTION_RELOAD,
+};
+use crate::observability::sampling::MessageSampler;
+use crate::observability::system_topic::sysmon::{
+    ResolvedSystemAlarm, SystemAlarmAck, SystemAlarmEventMessage, SYSTEM_ALARM_HISTORY_CAPACITY,
+};
+use crate::security::acl::decision_cache::AclDecisionCache;
 use crate::security::acl::metadata::AclMetadata;
 use common_base::tools::now_second;
 use common_config::mqtt::config::BrokerMqttConfig;
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
-use metadata_struct::acl::mqtt_acl::MqttAcl;
+use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclResourceType};
 use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
 use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
 use metadata_struct::mqtt::connection::MQTTConnection;
@@ -28,10 +39,13 @@
 use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
 use metadata_struct::mqtt::user::MqttUser;
 use metadata_struct::placement::node::BrokerNode;
-use protocol::mqtt::common::{MqttProtocol, PublishProperties};
+use protocol::mqtt::common::{MqttProtocol, PublishProperties, QoS};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::sync::broadcast::Sender;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -61,10 +75,22 @@ pub struct ConnectionLiveTime {
     pub heartbeat: u64,
 }
 
+#[derive(Clone, Default)]
+pub struct TopicActivity {
+    // Publish requests currently working on the topic
+    pub in_flight: u64,
+    pub last_active_time: u64,
+    // Set while the topic cleanup is deleting the topic, new requests wait until it is done
+    pub pruning: bool,
+}
+
 #[derive(Clone)]
 pub struct QosAckPacketInfo {
     pub sx: Sender<QosAckPackageData>,
     pub create_time: u128,
+    pub qos: QoS,
+    // Set once the client has answered a QoS 2 publish with PubRec
+    pub pub_rec_received: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -91,11 +117,17 @@ pub struct ClientPkidData {
 pub struct CacheManager {
     pub start_time: u64,
 
+    // Monotonic start time, used to detect wall clock jumps
+    pub start_instant: Instant,
+
     pub client_pool: Arc<ClientPool>,
 
     // node list
     pub node_lists: DashMap<u64, BrokerNode>,
 
+    // Whether this node refuses new connections so that it can be shut down
+    pub node_draining: Arc<AtomicBool>,
+
     // cluster_name
     pub cluster_name: String,
 
@@ -108,6 +140,9 @@ pub struct CacheManager {
     // (client_id, Session)
     pub session_info: DashMap<String, MqttSession>,
 
+    // (client_id, offload time), offline sessions kept only in the store
+    pub offloaded_sessions: DashMap<String, u64>,
+
     // (connect_id, Connection)
     pub connection_info: DashMap<u64, MQTTConnection>,
 
@@ -117,44 +152,105 @@ pub struct CacheManager {
     // (topic_id, topic_name)
     pub topic_id_name: DashMap<String, String>,
 
+    // (topic_name, TopicActivity)
+    pub topic_activity: DashMap<String, TopicActivity>,
+
+    // (topic_name, sequence of the last message published to the topic on this broker).
+    // Only kept in memory, so it starts again on restart and other brokers count on their own.
+    pub topic_sequence: DashMap<String, u64>,
+
+    // (topic_name, moving average of the payload size published to the topic on this broker)
+    pub topic_avg_payload_size: DashMap<String, u64>,
+
+    // (topic_name, time of the last message published to the topic on this broker)
+    pub topic_last_message_at: DashMap<String, u64>,
+
+    // (username, messages assigned the default expiry of the user on this broker), only users
+    // with a default of their own are counted
+    pub user_default_expiry_applied: DashMap<String, u64>,
+
+    // sampling of published messages for observability
+    pub message_sampler: MessageSampler,
+
+    // per user publish rate
+    pub publish_rate_limiter: UserRateLimiter,
+
+    // per user subscribe rate
+    pub subscribe_rate_limiter: UserRateLimiter,
+
+    // message ids recently published on each topic
+    pub message_deduplicator: MessageDeduplicator,
+
     // (client_id, HeartbeatShard)
     pub heartbeat_data: DashMap<String, ConnectionLiveTime>,
 
     // acl metadata
     pub acl_metadata: AclMetadata,
 
+    // outcome of the acl rules per user, client id and topic
+    pub acl_decision_cache: AclDecisionCache,
+
     // pkid manager
     pub pkid_metadata: PkidManager,
 
     // All topic rewrite rule
     pub topic_rewrite_rule: DashMap<String, MqttTopicRewriteRule>,
 
+    // (regex, compiled regex) of the topic rewrite rules, so that a message does not compile them
+    pub topic_rewrite_regex: DashMap<String, Regex>,
+
     // All auto subscribe rule
     pub auto_subscribe_rule: DashMap<String, MqttAutoSubscribeRule>,
 
     // Alarm Info
     pub alarm_events: DashMap<String, SystemAlarmEventMessage>,
+
+    // (alarm name, acknowledgement of the current activation of the alarm)
+    pub alarm_acks: DashMap<String, SystemAlarmAck>,
+
+    // Resolved alarms from old to new, at most SYSTEM_ALARM_HISTORY_CAPACITY
+    pub alarm_history: Mutex<VecDeque<ResolvedSystemAlarm>>,
+
+    // Posts alarms to the webhooks of the system monitor config
+    pub alarm_webhook: Arc<AlarmWebhookNotifier>,
 }
 
 impl CacheManager {
     pub fn new(client_pool: Arc<ClientPool>, cluster_name: String) -> Self {
         CacheManager {
             start_time: now_second(),
+            start_instant: Instant::now(),
             client_pool,
             cluster_name,
             node_lists: DashMap::with_capacity(2),
+            node_draining: Arc::new(AtomicBool::new(false)),
             cluster_info: DashMap::with_capacity(1),
             user_info: DashMap::with_capacity(8),
             session_info: DashMap::with_capacity(8),
+            offloaded_sessions: DashMap::with_capacity(8),
             topic_info: DashMap::with_capacity(8),
             topic_id_name: DashMap::with_capacity(8),
+            topic_activity: DashMap::with_capacity(8),
+            topic_sequence: DashMap::with_capacity(8),
+            topic_avg_payload_size: DashMap::with_capacity(8),
+            topic_last_message_at: DashMap::with_capacity(8),
+            user_default_expiry_applied: DashMap::with_capacity(8),
+            message_sampler: MessageSampler::default(),
+            publish_rate_limiter: UserRateLimiter::default(),
+            subscribe_rate_limiter: UserRateLimiter::default(),
+            message_deduplicator: MessageDeduplicator::default(),
             connection_info: DashMap::with_capacity(8),
             heartbeat_data: DashMap::with_capacity(8),
             acl_metadata: AclMetadata::new(),
+            acl_decision_cache: AclDecisionCache::default(),
             pkid_metadata: PkidManager::new(),
             topic_rewrite_rule: DashMap::with_capacity(8),
+            topic_rewrite_regex: DashMap::with_capacity(8),
             auto_subscribe_rule: DashMap::with_capacity(8),
             alarm_events: DashMap::with_capacity(8),
+            alarm_acks: DashMap::with_capacity(8),
+            alarm_history: Mutex::new(VecDeque::with_capacity(SYSTEM_ALARM_HISTORY_CAPACITY)),
+            alarm_webhook: Arc::new(AlarmWebhookNotifier::default()),
         }
     }
 
@@ -174,10 +270,21 @@ pub fn node_list(&self) -> Vec<BrokerNode> {
             .collect()
     }
 
+    pub fn set_node_draining(&self, draining: bool) {
+        self.node_draining.store(draining, Ordering::Relaxed);
+    }
+
+    pub fn is_node_draining(&self) -> bool {
+        self.node_draining.load(Ordering::Relaxed)
+    }
+
     // session
     pub fn add_session(&self, client_id: &str, session: &MqttSession) {
         self.session_info
             .insert(client_id.to_owned(), session.to_owned());
+        if self.offloaded_sessions.remove(client_id).is_some() {
+            incr_session_tiering_counter(SESSION_TIERING_ACTION_RELOAD);
+        }
     }
 
     pub fn get_session_info(&self, client_id: &str) -> Option<MqttSession> {
@@ -198,10 +305,44 @@ pub fn update_session_connect_id(&self, client_id: &str, connect_id: Option<u64>
 
     pub fn remove_session(&self, client_id: &str) {
         self.session_info.remove(client_id);
+        self.offloaded_sessions.remove(client_id);
         self.heartbeat_data.remove(client_id);
         self.pkid_metadata.remove_by_client_id(client_id);
     }
 
+    // Drop the offline sessions idle for at least `idle_sec` from memory. They stay in the
+    // store and are loaded back by the next connect of the client. The pkid state is kept
+    // so that in-flight QoS 2 exchanges can still complete after the reconnect.
+    pub fn offload_idle_sessions(&self, idle_sec: u64, now: u64) -> Vec<String> {
+        let mut offloaded = Vec::new();
+        self.session_info.retain(|client_id, session| {
+            if session.connection_id.is_some() {
+                return true;
+            }
+
+            let last_active = session
+                .create_time
+                .max(session.reconnect_time.unwrap_or(0))
+                .max(session.distinct_time.unwrap_or(0));
+            if now.saturating_sub(last_active) < idle_sec {
+                return true;
+            }
+
+            offloaded.push(client_id.clone());
+            false
+        });
+
+        for client_id in offloaded.iter() {
+            self.offloaded_sessions.insert(client_id.clone(), now);
+            incr_session_tiering_counter(SESSION_TIERING_ACTION_OFFLOAD);
+        }
+        offloaded
+    }
+
+    pub fn is_session_offloaded(&self, client_id: &str) -> bool {
+        self.offloaded_sessions.contains_key(client_id)
+    }
+
     // user
     pub fn add_user(&self, user: MqttUser) {
         self.user_info.insert(user.username.clone(), user);
@@ -259,6 +400,9 @@ pub fn add_topic(&self, topic_name: &str, topic: &MqttTopic) {
     pub fn delete_topic(&self, topic_name: &String, topic: &MqttTopic) {
         self.topic_info.remove(topic_name);
         self.topic_id_name.remove(&topic.topic_id);
+        self.topic_sequence.remove(topic_name);
+        self.topic_avg_payload_size.remove(topic_name);
+        self.topic_last_message_at.remove(topic_name);
     }
 
     pub fn topic_exists(&self, topic: &str) -> bool {
@@ -279,6 +423,123 @@ pub fn get_topic_by_name(&self, topic_name: &str) -> Option<MqttTopic> {
         None
     }
 
+    // topic sequence, scoped to this broker since it started
+    pub fn next_topic_sequence(&self, topic_name: &str) -> u64 {
+        let mut sequence = self
+            .topic_sequence
+            .entry(topic_name.to_owned())
+            .or_default();
+        *sequence += 1;
+        *sequence
+    }
+
+    pub fn get_topic_sequence(&self, topic_name: &str) -> u64 {
+        self.topic_sequence
+            .get(topic_name)
+            .map(|sequence| *sequence)
+            .unwrap_or(0)
+    }
+
+    // Reset only when the sequence is still the one the caller inspected,
+    // otherwise the current sequence is returned as the error
+    pub fn reset_topic_sequence(
+        &self,
+        topic_name: &str,
+        expected_sequence: u64,
+        sequence: u64,
+    ) -> Result<(), u64> {
+        let mut current = self
+            .topic_sequence
+            .entry(topic_name.to_owned())
+            .or_default();
+        if *current != expected_sequence {
+            return Err(*current);
+        }
+        *current = sequence;
+        Ok(())
+    }
+
+    pub fn record_topic_payload_size(&self, topic_name: &str, payload_size: u64) {
+        let mut avg = self
+            .topic_avg_payload_size
+            .entry(topic_name.to_owned())
+            .or_insert(payload_size);
+        // Weight the latest message by 1/8 so the average follows changes of the payload
+        *avg = (*avg * 7 + payload_size) / 8;
+    }
+
+    pub fn get_topic_avg_payload_size(&self, topic_name: &str) -> u64 {
+        self.topic_avg_payload_size
+            .get(topic_name)
+            .map(|size| *size)
+            .unwrap_or(0)
+    }
+
+    pub fn record_topic_last_message_at(&self, topic_name: &str, time: u64) {
+        self.topic_last_message_at
+            .insert(topic_name.to_owned(), time);
+    }
+
+    pub fn get_topic_last_message_at(&self, topic_name: &str) -> u64 {
+        self.topic_last_message_at
+            .get(topic_name)
+            .map(|time| *time)
+            .unwrap_or(0)
+    }
+
+    // topic activity
+    pub fn try_begin_topic_operation(&self, topic_name: &str) -> bool {
+        let mut activity = self
+            .topic_activity
+            .entry(topic_name.to_owned())
+            .or_default();
+        if activity.pruning {
+            return false;
+        }
+        activity.in_flight += 1;
+        activity.last_active_time = now_second();
+        true
+    }
+
+    pub fn end_topic_operation(&self, topic_name: &str) {
+        if let Some(mut activity) = self.topic_activity.get_mut(topic_name) {
+            activity.in_flight = activity.in_flight.saturating_sub(1);
+            activity.last_active_time = now_second();
+        }
+    }
+
+    pub fn get_topic_last_active_time(&self, topic_name: &str) -> Option<u64> {
+        self.topic_activity
+            .get(topic_name)
+            .map(|activity| activity.last_active_time)
+    }
+
+    pub fn is_topic_in_flight(&self, topic_name: &str) -> bool {
+        self.topic_activity
+            .get(topic_name)
+            .is_some_and(|activity| activity.in_flight > 0)
+    }
+
+    pub fn try_mark_topic_pruning(&self, topic_name: &str, idle_before: u64) -> bool {
+        let mut activity = self
+            .topic_activity
+            .entry(topic_name.to_owned())
+            .or_default();
+        if activity.pruning || activity.in_flight > 0 || activity.last_active_time > idle_before {
+            return false;
+        }
+        activity.pruning = true;
+        true
+    }
+
+    pub fn finish_topic_pruning(&self, topic_name: &str, pruned: bool) {
+        if pruned {
+            self.topic_activity.remove(topic_name);
+        } else if let Some(mut activity) = self.topic_activity.get_mut(topic_name) {
+            activity.pruning = false;
+        }
+    }
+
     pub fn update_topic_retain_message(&self, topic_name: &str, retain_message: Option<Vec<u8>>) {
         if let Some(mut topic) = self.topic_info.get_mut(topic_name) {
             topic.retain_message = retain_message;
@@ -292,12 +553,34 @@ pub fn add_topic_rewrite_rule(&self, topic_rewrite_rule: MqttTopicRewriteRule) {
             &topic_rewrite_rule.action,
             &topic_rewrite_rule.source_topic,
         );
+        if let Ok(regex) = Regex::new(&topic_rewrite_rule.regex) {
+            self.topic_rewrite_regex
+                .insert(topic_rewrite_rule.regex.clone(), regex);
+        }
         self.topic_rewrite_rule.insert(key, topic_rewrite_rule);
     }
 
     pub fn delete_topic_rewrite_rule(&self, cluster: &str, action: &str, source_topic: &str) {
         let key = self.topic_rewrite_rule_key(cluster, action, source_topic);
-        self.topic_rewrite_rule.remove(&key);
+        if let Some((_, rule)) = self.topic_rewrite_rule.remove(&key) {
+            let in_use = self
+                .topic_rewrite_rule
+                .iter()
+                .any(|entry| entry.value().regex == rule.regex);
+            if !in_use {
+                self.topic_rewrite_regex.remove(&rule.regex);
+            }
+        }
+    }
+
+    pub fn get_topic_rewrite_regex(&self, pattern: &str) -> Result<Regex, MqttBrokerError> {
+        if let Some(regex) = self.topic_rewrite_regex.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(pattern)?;
+        self.topic_rewrite_regex
+            .insert(pattern.to_string(), regex.clone());
+        Ok(regex)
     }
 
     pub fn get_all_topic_rewrite_rule(&self) -> Vec<MqttTopicRewriteRule> {
@@ -365,20 +648,54 @@ pub fn remove_heartbeat(&self, client_id: &str) {
 
     // acl
     pub fn add_acl(&self, acl: MqttAcl) {
-        self.acl_metadata.parse_mqtt_acl(acl);
+        self.acl_metadata.parse_mqtt_acl(acl.clone());
+        self.invalidate_acl_decisions(&acl);
     }
 
     pub fn remove_acl(&self, acl: MqttAcl) {
-        self.acl_metadata.remove_mqtt_acl(acl);
+        self.acl_metadata.remove_mqtt_acl(acl.clone());
+        self.invalidate_acl_decisions(&acl);
+    }
+
+    // Replace the cached rules with the stored ones. Only the decisions of users and client ids
+    // whose rules changed are invalidated.
+    pub fn sync_acls(&self, acls: Vec<MqttAcl>) {
+        let mut user_acl: HashMap<String, Vec<MqttAcl>> = HashMap::new();
+        let mut client_acl: HashMap<String, Vec<MqttAcl>> = HashMap::new();
+        for acl in acls {
+            match acl.resource_type {
+                MqttAclResourceType::User => &mut user_acl,
+                MqttAclResourceType::ClientId => &mut client_acl,
+            }
+            .entry(acl.resource_name.clone())
+            .or_default()
+            .push(acl);
+        }
+
+        for username in self
+            .acl_metadata
+            .replace_mqtt_acls(MqttAclResourceType::User, user_acl)
+        {
+            self.acl_decision_cache.invalidate_user(&username);
+        }
+        for client_id in self
+            .acl_metadata
+            .replace_mqtt_acls(MqttAclResourceType::ClientId, client_acl)
+        {
+            self.acl_decision_cache.invalidate_client_id(&client_id);
+        }
     }
 
-    pub fn retain_acls(&self, user_acl: HashSet<String>, client_acl: HashSet<String>) {
-        self.acl_metadata
-            .acl_user
-            .retain(|username, _| user_acl.contains(username));
-        self.acl_metadata
-            .acl_client_id
-            .retain(|client_id, _| client_acl.contains(client_id));
+    // The cached decisions the rule may change
+    fn invalidate_acl_decisions(&self, acl: &MqttAcl) {
+        match acl.resource_type {
+            MqttAclResourceType::User => {
+                self.acl_decision_cache.invalidate_user(&acl.resource_name)
+            }
+            MqttAclResourceType::ClientId => self
+                .acl_decision_cache
+                .invalidate_client_id(&acl.resource_name),
+        }
     }
 
     // blacklist
@@ -415,10 +732,79 @@ pub fn delete_auto_subscribe_rule(&self, cluster: &str, topic: &str) {
         self.auto_subscribe_rule.remove(&key);
     }
 
-    pub fn add_alarm_event(&self, alarm_name: String, event: SystemAlarmEventMessage) {
+    pub fn add_alarm_event(&self, alarm_name: String, mut event: SystemAlarmEventMessage) {
+        if let Some(previous) = self.get_alarm_event(&alarm_name) {
+            if previous.activated && event.activated {
+                // Still the same activation, keep the time it started
+                event.activate_at = previous.activate_at;
+            } else if previous.activated {
+                // The acknowledgement belongs to this activation, a new one starts unacknowledged
+                let ack = self.alarm_acks.remove(&alarm_name).map(|(_, ack)| ack);
+                self.add_resolved_alarm(ResolvedSystemAlarm {
+                    event: previous,
+                    ack,
+                    resolved_at: event.activate_at,
+                });
+            }
+        }
         self.alarm_events.insert(alarm_name, event);
     }
 
+    // The alarm is no longer checked, an active one is resolved into the history
+    pub fn remove_alarm_event(&self, alarm_name: &str, resolved_at: i64) {
+        let ack = self.alarm_acks.remove(alarm_name).map(|(_, ack)| ack);
+        if let Some((_, event)) = self.alarm_events.remove(alarm_name) {
+            if event.activated {
+                self.add_resolved_alarm(ResolvedSystemAlarm {
+                    event,
+                    ack,
+                    resolved_at,
+                });
+            }
+        }
+    }
+
+    pub fn ack_alarm_event(
+        &self,
+        alarm_name: &str,
+        acknowledged_by: &str,
+        acknowledged_at: i64,
+    ) -> Result<SystemAlarmAck, MqttBrokerError> {
+        if !self
+            .get_alarm_event(alarm_name)
+            .is_some_and(|event| event.activated)
+        {
+            return Err(MqttBrokerError::CommonError(format!(
+                "system alarm {} is not active",
+                alarm_name
+            )));
+        }
+        let ack = SystemAlarmAck {
+            acknowledged_by: acknowledged_by.to_string(),
+            acknowledged_at,
+        };
+        self.alarm_acks.insert(alarm_name.to_string(), ack.clone());
+        Ok(ack)
+    }
+
+    pub fn get_alarm_ack(&self, alarm_name: &str) -> Option<SystemAlarmAck> {
+        self.alarm_acks.get(alarm_name).map(|ack| ack.clone())
+    }
+
+    fn add_resolved_alarm(&self, resolved: ResolvedSystemAlarm) {
+        let mut history = self.alarm_history.lock().unwrap();
+        if history.len() >= SYSTEM_ALARM_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(resolved);
+    }
+
+    // Resolved alarms, the most recently resolved first
+    pub fn list_resolved_alarms(&self) -> Vec<ResolvedSystemAlarm> {
+        let history = self.alarm_history.lock().unwrap();
+        history.iter().rev().cloned().collect()
+    }
+
     pub fn get_alarm_event(&self, name: &str) -> Option<SystemAlarmEventMessage> {
         if let Some(event) = self.alarm_events.get(name) {
             return Some(event.clone());
@@ -462,4 +848,145 @@ async fn test_add_and_get_alarm_event() {
         assert!(retrieved_event.is_some());
         assert_eq!(event.name, retrieved_event.unwrap().name);
     }
+
+    #[tokio::test]
+    async fn test_alarm_event_ack_and_history() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = CacheManager::new(client_pool, "test_cluster".to_string());
+        let event = |activate_at: i64, activated: bool| SystemAlarmEventMessage {
+            name: "HighCpuUsage".to_string(),
+            message: format!("check at {}", activate_at),
+            activate_at,
+            activated,
+        };
+
+        // Only an active alarm can be acknowledged
+        assert!(cache_manager
+            .ack_alarm_event("HighCpuUsage", "ops", 5)
+            .is_err());
+        cache_manager.add_alarm_event("HighCpuUsage".to_string(), event(10, false));
+        assert!(cache_manager
+            .ack_alarm_event("HighCpuUsage", "ops", 15)
+            .is_err());
+
+        // Checks while the alarm stays active keep the activation time
+        cache_manager.add_alarm_event("HighCpuUsage".to_string(), event(20, true));
+        cache_manager.add_alarm_event("HighCpuUsage".to_string(), event(30, true));
+        assert_eq!(
+            cache_manager
+                .get_alarm_event("HighCpuUsage")
+                .unwrap()
+                .activate_at,
+            20
+        );
+        let ack = cache_manager
+            .ack_alarm_event("HighCpuUsage", "ops", 35)
+            .unwrap();
+        assert_eq!(
+            cache_manager.get_alarm_ack("HighCpuUsage"),
+            Some(ack.clone())
+        );
+        assert!(cache_manager.list_resolved_alarms().is_empty());
+
+        // Resolving moves the activation and its acknowledgement into the history
+        cache_manager.add_alarm_event("HighCpuUsage".to_string(), event(40, false));
+        let resolved = cache_manager.list_resolved_alarms();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].event.activate_at, 20);
+        assert_eq!(resolved[0].event.message, "check at 30");
+        assert_eq!(resolved[0].resolved_at, 40);
+        assert_eq!(resolved[0].ack, Some(ack));
+        assert!(cache_manager.get_alarm_ack("HighCpuUsage").is_none());
+
+        // A new activation starts unacknowledged
+        cache_manager.add_alarm_event("HighCpuUsage".to_string(), event(50, true));
+        assert!(cache_manager.get_alarm_ack("HighCpuUsage").is_none());
+        cache_manager.add_alarm_event("HighCpuUsage".to_string(), event(60, false));
+        let resolved = cache_manager.list_resolved_alarms();
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].event.activate_at, 50);
+        assert!(resolved[0].ack.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_alarm_history_is_bounded() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = CacheManager::new(client_pool, "test_cluster".to_string());
+        for i in 0..SYSTEM_ALARM_HISTORY_CAPACITY as i64 + 10 {
+            for activated in [true, false] {
+                let event = SystemAlarmEventMessage {
+                    name: "MemoryUsage".to_string(),
+                    activate_at: i,
+                    activated,
+                    ..Default::default()
+                };
+                cache_manager.add_alarm_event("MemoryUsage".to_string(), event);
+            }
+        }
+        let resolved = cache_manager.list_resolved_alarms();
+        assert_eq!(resolved.len(), SYSTEM_ALARM_HISTORY_CAPACITY);
+        assert_eq!(
+            resolved[0].event.activate_at,
+            SYSTEM_ALARM_HISTORY_CAPACITY as i64 + 9
+        );
+        assert_eq!(resolved.last().unwrap().event.activate_at, 10);
+    }
+
+    #[tokio::test]
+    async fn topic_sequence_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = CacheManager::new(client_pool, "test_cluster".to_string());
+
+        assert_eq!(cache_manager.get_topic_sequence("t1"), 0);
+        assert_eq!(cache_manager.next_topic_sequence("t1"), 1);
+        assert_eq!(cache_manager.next_topic_sequence("t1"), 2);
+
+        assert_eq!(cache_manager.reset_topic_sequence("t1", 1, 0), Err(2));
+        assert_eq!(cache_manager.get_topic_sequence("t1"), 2);
+
+        assert!(cache_manager.reset_topic_sequence("t1", 2, 0).is_ok());
+        assert_eq!(cache_manager.next_topic_sequence("t1"), 1);
+    }
+
+    #[tokio::test]
+    async fn offload_idle_sessions_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = CacheManager::new(client_pool, "test_cluster".to_string());
+
+        let online = MqttSession {
+            client_id: "online".to_string(),
+            connection_id: Some(1),
+            create_time: 100,
+            ..Default::default()
+        };
+        let idle = MqttSession {
+            client_id: "idle".to_string(),
+            create_time: 100,
+            distinct_time: Some(200),
+            ..Default::default()
+        };
+        let recent = MqttSession {
+            client_id: "recent".to_string(),
+            create_time: 100,
+            distinct_time: Some(900),
+            ..Default::default()
+        };
+        cache_manager.add_session("online", &online);
+        cache_manager.add_session("idle", &idle);
+        cache_manager.add_session("recent", &recent);
+
+        let offloaded = cache_manager.offload_idle_sessions(300, 1000);
+        assert_eq!(offloaded, vec!["idle".to_string()]);
+        assert!(cache_manager.get_session_info("idle").is_none());
+        assert!(cache_manager.is_session_offloaded("idle"));
+        assert_eq!(cache_manager.session_info.len(), 2);
+
+        cache_manager.add_session("idle", &idle);
+        assert!(!cache_manager.is_session_offloaded("idle"));
+        assert!(cache_manager.get_session_info("idle").is_some());
+
+        cache_manager.offload_idle_sessions(300, 1000);
+        cache_manager.remove_session("idle");
+        assert!(!cache_manager.is_session_offloaded("idle"));
+    }
 }
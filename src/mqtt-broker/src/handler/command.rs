@@ -21,6 +21,7 @@
 use crate::handler::response::{
     response_packet_mqtt_connect_fail, response_packet_mqtt_distinct_by_reason,
 };
+use crate::handler::session_restore::{check_session_restore, SessionRestoreCheck};
 use crate::security::AuthDriver;
 use crate::server::connection::NetworkConnection;
 use crate::server::connection_manager::ConnectionManager;
@@ -111,6 +112,15 @@ pub async fn apply(
             is_connect_pkg = true;
         }
 
+        // A PUBLISH the read loop could not hold until the session was ready is rejected
+        if let MqttPacket::Publish(publish, _) = packet {
+            if let SessionRestoreCheck::Rejected(resp) =
+                check_session_restore(connect_manager, tcp_connection.connection_id, publish)
+            {
+                return resp;
+            }
+        }
+
         if !is_connect_pkg && !self.check_login_status(tcp_connection.connection_id).await {
             return Some(response_packet_mqtt_distinct_by_reason(
                 &MqttProtocol::Mqtt5,
@@ -175,6 +185,7 @@ pub async fn apply(
                             .await,
                     )
                 } else {
+                    connect_manager.finish_session_restore(tcp_connection.connection_id);
                     return Some(response_packet_mqtt_connect_fail(
                         &MqttProtocol::Mqtt4,
                         ConnectReturnCode::UnsupportedProtocolVersion,
@@ -186,16 +197,18 @@ pub async fn apply(
                 let ack_pkg = resp_pkg.unwrap();
                 if let MqttPacket::ConnAck(conn_ack, _) = ack_pkg.clone() {
                     if conn_ack.code == ConnectReturnCode::Success {
-                        let username = if let Some(user) = login {
-                            user.username.to_owned()
-                        } else {
-                            "".to_string()
-                        };
+                        // The username CONNECT settled on, which may come from the client certificate
+                        let username = self
+                            .metadata_cache
+                            .get_connection(tcp_connection.connection_id)
+                            .map(|connection| connection.login_user)
+                            .unwrap_or_default();
                         self.metadata_cache
                             .login_success(tcp_connection.connection_id, username);
                         info!("connect [{}] login success", tcp_connection.connection_id);
                     }
                 }
+                connect_manager.finish_session_restore(tcp_connection.connection_id);
                 return Some(ack_pkg);
             }
 
@@ -14,25 +14,42 @@
 
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use common_base::tools::{now_second, unique_id};
-use common_config::mqtt::config::BrokerMqttConfig;
+use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::{BrokerMqttConfig, DuplicateClientIdPolicy};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
-use protocol::mqtt::common::{Connect, ConnectProperties, DisconnectReasonCode, MqttProtocol};
+use metadata_struct::mqtt::session::MqttSession;
+use protocol::mqtt::common::{
+    Connect, ConnectProperties, DisconnectReasonCode, MqttPacket, MqttProtocol,
+};
 
 use super::cache::CacheManager;
 use super::error::MqttBrokerError;
 use super::keep_alive::client_keep_live_time;
+use super::user_session::release_user_session;
 use crate::handler::flow_control::is_connection_rate_exceeded;
-use crate::handler::response::response_packet_mqtt_distinct_by_reason;
+use crate::handler::response::{
+    response_packet_mqtt_distinct_by_reason, response_packet_mqtt_distinct_by_server_reference,
+};
+use crate::observability::metrics::server::incr_connection_ip_limit_rejected_counter;
+use crate::observability::metrics::session::{
+    incr_duplicate_client_id_counter, incr_will_grace_counter,
+    DUPLICATE_CLIENT_ID_OUTCOME_REJECTED, DUPLICATE_CLIENT_ID_OUTCOME_TAKEOVER,
+    WILL_GRACE_OUTCOME_REPORTED, WILL_GRACE_OUTCOME_SUPPRESSED,
+};
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::session::SessionStorage;
 use crate::subscribe::manager::SubscribeManager;
+use axum::extract::ws::Message;
+use bytes::BytesMut;
 use futures_util::SinkExt;
 use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
 use tokio::io::{AsyncWrite, AsyncWriteExt, WriteHalf};
 use tokio::net::TcpStream;
+use tokio::time::sleep;
 use tokio_util::codec::FramedWrite;
 use tracing::{error, warn};
 
@@ -147,11 +164,363 @@ pub async fn disconnect_connection(
             .await?;
     }
 
+    release_connection_user_session(client_id, connect_id, cache_manager, client_pool).await;
     connection_manager.close_connect(connect_id).await;
     cache_manager.remove_connection(connect_id);
     Ok(())
 }
 
+async fn release_connection_user_session(
+    client_id: &str,
+    connect_id: u64,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) {
+    if let Some(connection) = cache_manager.get_connection(connect_id) {
+        release_user_session(
+            &connection.login_user,
+            client_id,
+            cache_manager,
+            client_pool,
+        )
+        .await;
+    }
+}
+
+// Disconnect a client that went away without sending DISCONNECT. The will fires once the
+// store sees the session disconnected, so with a grace period configured the disconnect is
+// only reported after the grace, and not at all if the client reconnected in the meantime,
+// on this broker or on another one.
+pub async fn disconnect_unclean_connection(
+    client_id: &str,
+    connect_id: u64,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<(), MqttBrokerError> {
+    let grace_period_sec = cache_manager
+        .get_will_publish_config()
+        .unclean_grace_period_sec;
+    let has_will = cache_manager
+        .get_session_info(client_id)
+        .is_some_and(|session| session.is_contain_last_will);
+    if grace_period_sec == 0 || !has_will {
+        if !is_session_owned_by_connection(client_pool, client_id, connect_id).await? {
+            close_local_connection(client_id, connect_id, cache_manager, connection_manager).await;
+            return Ok(());
+        }
+        return disconnect_connection(
+            client_id,
+            connect_id,
+            cache_manager,
+            client_pool,
+            connection_manager,
+            subscribe_manager,
+            false,
+        )
+        .await;
+    }
+
+    cache_manager.update_session_connect_id(client_id, None);
+    release_connection_user_session(client_id, connect_id, cache_manager, client_pool).await;
+    connection_manager.close_connect(connect_id).await;
+    cache_manager.remove_connection(connect_id);
+    let disconnect_time = cache_manager
+        .get_session_info(client_id)
+        .and_then(|session| session.distinct_time)
+        .unwrap_or_else(now_second);
+
+    let client_id = client_id.to_owned();
+    let cache_manager = cache_manager.clone();
+    let client_pool = client_pool.clone();
+    tokio::spawn(async move {
+        sleep(Duration::from_secs(grace_period_sec)).await;
+        if is_reconnected_since(&cache_manager, &client_id, disconnect_time) {
+            incr_will_grace_counter(WILL_GRACE_OUTCOME_SUPPRESSED);
+            return;
+        }
+
+        match is_session_owned_by_connection(&client_pool, &client_id, connect_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                incr_will_grace_counter(WILL_GRACE_OUTCOME_SUPPRESSED);
+                return;
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to read the session of client {} after the will grace period, error message: {}",
+                    client_id, e
+                );
+                return;
+            }
+        }
+
+        incr_will_grace_counter(WILL_GRACE_OUTCOME_REPORTED);
+        let session_storage = SessionStorage::new(client_pool);
+        if let Err(e) = session_storage
+            .update_session(client_id.clone(), 0, 0, 0, disconnect_time)
+            .await
+        {
+            warn!(
+                "Failed to report the disconnect of client {} after the will grace period, error message: {}",
+                client_id, e
+            );
+        }
+    });
+    Ok(())
+}
+
+// A session that is connected again, or went through another disconnect, no longer
+// belongs to the disconnect at `disconnect_time`
+fn is_reconnected_since(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+    disconnect_time: u64,
+) -> bool {
+    match cache_manager.get_session_info(client_id) {
+        Some(session) => {
+            session.connection_id.is_some() || session.distinct_time != Some(disconnect_time)
+        }
+        None => false,
+    }
+}
+
+// The stored session names the broker and connection that hold it. Once the client has
+// connected again, possibly to another broker, this connection must not report it disconnected
+async fn is_session_owned_by_connection(
+    client_pool: &Arc<ClientPool>,
+    client_id: &str,
+    connect_id: u64,
+) -> Result<bool, MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    let session = session_storage.get_session(client_id.to_owned()).await?;
+    let broker_id = broker_mqtt_conf().broker_id;
+    Ok(session.is_some_and(|session| is_session_owned_by(&session, broker_id, connect_id)))
+}
+
+fn is_session_owned_by(session: &MqttSession, broker_id: u64, connect_id: u64) -> bool {
+    session.broker_id == Some(broker_id) && session.connection_id == Some(connect_id)
+}
+
+async fn close_local_connection(
+    client_id: &str,
+    connect_id: u64,
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+) {
+    if cache_manager.get_connect_id(client_id) == Some(connect_id) {
+        cache_manager.update_session_connect_id(client_id, None);
+    }
+    connection_manager.close_connect(connect_id).await;
+    cache_manager.remove_connection(connect_id);
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DuplicateClientIdAction {
+    Accept,
+    // Disconnect the old connection before accepting the new one
+    Takeover(u64),
+    Reject,
+}
+
+pub fn check_duplicate_client_id(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> DuplicateClientIdAction {
+    let old_connect_id = match cache_manager.get_connect_id(client_id) {
+        Some(id) => id,
+        None => return DuplicateClientIdAction::Accept,
+    };
+
+    if cache_manager.get_connection(old_connect_id).is_none() {
+        return DuplicateClientIdAction::Accept;
+    }
+
+    let config = cache_manager.get_duplicate_client_id_config();
+    let action = match config.policy {
+        DuplicateClientIdPolicy::Takeover => DuplicateClientIdAction::Takeover(old_connect_id),
+        DuplicateClientIdPolicy::Reject => DuplicateClientIdAction::Reject,
+        DuplicateClientIdPolicy::Grace => {
+            let last_heartbeat = cache_manager
+                .heartbeat_data
+                .get(client_id)
+                .map(|live_time| live_time.heartbeat)
+                .unwrap_or(0);
+            if now_second().saturating_sub(last_heartbeat) > config.grace_period_sec {
+                DuplicateClientIdAction::Takeover(old_connect_id)
+            } else {
+                DuplicateClientIdAction::Reject
+            }
+        }
+    };
+
+    match action {
+        DuplicateClientIdAction::Takeover(_) => {
+            incr_duplicate_client_id_counter(DUPLICATE_CLIENT_ID_OUTCOME_TAKEOVER)
+        }
+        DuplicateClientIdAction::Reject => {
+            incr_duplicate_client_id_counter(DUPLICATE_CLIENT_ID_OUTCOME_REJECTED)
+        }
+        DuplicateClientIdAction::Accept => {}
+    }
+    action
+}
+
+// Kick the old connection of a client id with the Session taken over reason code,
+// the session itself is kept for the new connection.
+pub async fn takeover_connection(
+    client_id: &str,
+    old_connect_id: u64,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<(), MqttBrokerError> {
+    if let Err(e) = send_session_taken_over(old_connect_id, connection_manager).await {
+        warn!(
+            "Failed to notify connection {} of client {} that its session was taken over, error message: {}",
+            old_connect_id, client_id, e
+        );
+    }
+
+    disconnect_connection(
+        client_id,
+        old_connect_id,
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+        false,
+    )
+    .await
+}
+
+async fn send_session_taken_over(
+    connect_id: u64,
+    connection_manager: &Arc<ConnectionManager>,
+) -> Result<(), MqttBrokerError> {
+    send_disconnect_packet(connect_id, connection_manager, |protocol| {
+        response_packet_mqtt_distinct_by_reason(
+            protocol,
+            Some(DisconnectReasonCode::SessionTakenOver),
+        )
+    })
+    .await
+}
+
+// Disconnect a client and point it to another server. Returns whether the client was sent a
+// server reference, which only MQTT 5 clients understand, the others are just disconnected.
+pub async fn redirect_connection(
+    client_id: &str,
+    connect_id: u64,
+    server_reference: &str,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<bool, MqttBrokerError> {
+    let is_mqtt5 = connection_manager
+        .get_connect_protocol(connect_id)
+        .is_some_and(|protocol| protocol.is_mqtt5());
+
+    if let Err(e) = send_disconnect_packet(connect_id, connection_manager, |protocol| {
+        response_packet_mqtt_distinct_by_server_reference(protocol, server_reference)
+    })
+    .await
+    {
+        warn!(
+            "Failed to send the server reference to connection {} of client {}, error message: {}",
+            connect_id, client_id, e
+        );
+    }
+
+    disconnect_connection(
+        client_id,
+        connect_id,
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+        false,
+    )
+    .await?;
+    Ok(is_mqtt5)
+}
+
+// Disconnect a client on request of an operator. MQTT 5 clients are told why with the
+// Administrative action reason code, MQTT 3 and 4 have no server DISCONNECT and only see the
+// connection close.
+pub async fn kick_connection(
+    client_id: &str,
+    connect_id: u64,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    clean_session: bool,
+) -> Result<(), MqttBrokerError> {
+    let is_mqtt5 = connection_manager
+        .get_connect_protocol(connect_id)
+        .is_some_and(|protocol| protocol.is_mqtt5());
+    if is_mqtt5 {
+        if let Err(e) = send_disconnect_packet(connect_id, connection_manager, |protocol| {
+            response_packet_mqtt_distinct_by_reason(
+                protocol,
+                Some(DisconnectReasonCode::AdministrativeAction),
+            )
+        })
+        .await
+        {
+            warn!(
+                "Failed to send DISCONNECT to connection {} of client {}, error message: {}",
+                connect_id, client_id, e
+            );
+        }
+    }
+
+    disconnect_connection(
+        client_id,
+        connect_id,
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+        clean_session,
+    )
+    .await
+}
+
+async fn send_disconnect_packet(
+    connect_id: u64,
+    connection_manager: &Arc<ConnectionManager>,
+    build_packet: impl FnOnce(&MqttProtocol) -> MqttPacket,
+) -> Result<(), MqttBrokerError> {
+    let protocol = match connection_manager.get_connect_protocol(connect_id) {
+        Some(protocol) => protocol,
+        None => return Ok(()),
+    };
+
+    let wrap = MqttPacketWrapper {
+        protocol_version: protocol.clone().into(),
+        packet: build_packet(&protocol),
+    };
+
+    if connection_manager.is_websocket(connect_id) {
+        let mut codec = MqttCodec::new(Some(protocol.into()));
+        let mut buff = BytesMut::new();
+        if let Err(e) = codec.encode_data(wrap.clone(), &mut buff) {
+            return Err(MqttBrokerError::WebsocketEncodePacketFailed(e.to_string()));
+        }
+        connection_manager
+            .write_websocket_frame(connect_id, wrap, Message::Binary(buff.to_vec()))
+            .await?;
+    } else {
+        connection_manager.write_tcp_frame(connect_id, wrap).await?;
+    }
+    Ok(())
+}
+
 pub async fn tcp_establish_connection_check(
     addr: &SocketAddr,
     connection_manager: &Arc<ConnectionManager>,
@@ -163,6 +532,12 @@ pub async fn tcp_establish_connection_check(
         return value;
     }
 
+    if let Some(value) =
+        handle_ip_connection_overflow(addr, connection_manager, write_frame_stream).await
+    {
+        return value;
+    }
+
     if let Some(value) = handle_connection_rate_exceeded(addr, write_frame_stream).await {
         return value;
     }
@@ -183,6 +558,12 @@ pub async fn tcp_tls_establish_connection_check(
         return value;
     }
 
+    if let Some(value) =
+        handle_ip_connection_overflow(addr, connection_manager, write_frame_stream).await
+    {
+        return value;
+    }
+
     if let Some(value) = handle_connection_rate_exceeded(addr, write_frame_stream).await {
         return value;
     }
@@ -215,6 +596,35 @@ async fn handle_tpc_connection_overflow<T>(
     None
 }
 
+async fn handle_ip_connection_overflow<T>(
+    addr: &SocketAddr,
+    connection_manager: &Arc<ConnectionManager>,
+    write_frame_stream: &mut FramedWrite<WriteHalf<T>, MqttCodec>,
+) -> Option<bool>
+where
+    T: AsyncWriteExt + AsyncWrite,
+{
+    if connection_manager.ip_connect_num_check(&addr.ip()) {
+        incr_connection_ip_limit_rejected_counter();
+        let packet_wrapper = MqttPacketWrapper {
+            protocol_version: MqttProtocol::Mqtt5.into(),
+            packet: response_packet_mqtt_distinct_by_reason(
+                &MqttProtocol::Mqtt5,
+                Some(DisconnectReasonCode::QuotaExceeded),
+            ),
+        };
+        if let Err(e) = write_frame_stream.send(packet_wrapper).await {
+            error!("{}", e)
+        }
+        warn!(
+            "Number of connections from source IP {} exceeds the limit, and the connection is closed.",
+            addr.ip()
+        );
+        return Some(false);
+    }
+    None
+}
+
 async fn handle_connection_rate_exceeded<T>(
     addr: &SocketAddr,
     write_frame_stream: &mut FramedWrite<WriteHalf<T>, MqttCodec>,
@@ -242,12 +652,19 @@ async fn handle_connection_rate_exceeded<T>(
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use super::{
-        build_connection, get_client_id, response_information, MQTTConnection,
-        REQUEST_RESPONSE_PREFIX_NAME,
+        build_connection, check_duplicate_client_id, get_client_id, response_information,
+        DuplicateClientIdAction, MQTTConnection, REQUEST_RESPONSE_PREFIX_NAME,
     };
+    use crate::handler::cache::{CacheManager, ConnectionLiveTime};
+    use common_base::tools::now_second;
+    use common_config::mqtt::config::{DuplicateClientId, DuplicateClientIdPolicy};
     use common_config::mqtt::default_broker_mqtt;
-    use protocol::mqtt::common::{Connect, ConnectProperties};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
+    use protocol::mqtt::common::{Connect, ConnectProperties, MqttProtocol};
 
     #[tokio::test]
     pub async fn build_connection_test() {
@@ -345,4 +762,130 @@ pub async fn send_qos_message_num_test() {
         conn.send_qos_message_decr();
         assert_eq!(conn.get_send_qos_message(), 0);
     }
+
+    #[tokio::test]
+    pub async fn check_duplicate_client_id_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(default_broker_mqtt());
+
+        let client_id = "c1";
+        assert_eq!(
+            check_duplicate_client_id(&cache_manager, client_id),
+            DuplicateClientIdAction::Accept
+        );
+
+        cache_manager.add_session(
+            client_id,
+            &MqttSession {
+                client_id: client_id.to_string(),
+                ..Default::default()
+            },
+        );
+        cache_manager.add_connection(
+            1,
+            MQTTConnection {
+                connect_id: 1,
+                client_id: client_id.to_string(),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            check_duplicate_client_id(&cache_manager, client_id),
+            DuplicateClientIdAction::Takeover(1)
+        );
+
+        cache_manager.update_duplicate_client_id_config(DuplicateClientId {
+            policy: DuplicateClientIdPolicy::Reject,
+            grace_period_sec: 30,
+        });
+        assert_eq!(
+            check_duplicate_client_id(&cache_manager, client_id),
+            DuplicateClientIdAction::Reject
+        );
+
+        // the old connection is still alive within the grace period
+        cache_manager.update_duplicate_client_id_config(DuplicateClientId {
+            policy: DuplicateClientIdPolicy::Grace,
+            grace_period_sec: 30,
+        });
+        cache_manager.report_heartbeat(
+            client_id.to_string(),
+            ConnectionLiveTime {
+                protocol: MqttProtocol::Mqtt5,
+                keep_live: 10,
+                heartbeat: now_second(),
+            },
+        );
+        assert_eq!(
+            check_duplicate_client_id(&cache_manager, client_id),
+            DuplicateClientIdAction::Reject
+        );
+
+        // the old connection has gone quiet
+        cache_manager.report_heartbeat(
+            client_id.to_string(),
+            ConnectionLiveTime {
+                protocol: MqttProtocol::Mqtt5,
+                keep_live: 10,
+                heartbeat: now_second() - 60,
+            },
+        );
+        assert_eq!(
+            check_duplicate_client_id(&cache_manager, client_id),
+            DuplicateClientIdAction::Takeover(1)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
+
+    use super::{is_reconnected_since, is_session_owned_by};
+    use crate::handler::cache::CacheManager;
+
+    #[tokio::test]
+    async fn is_reconnected_since_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        assert!(!is_reconnected_since(&cache_manager, "c1", 100));
+
+        let mut session = MqttSession {
+            client_id: "c1".to_string(),
+            distinct_time: Some(100),
+            ..Default::default()
+        };
+        cache_manager.add_session("c1", &session);
+        assert!(!is_reconnected_since(&cache_manager, "c1", 100));
+
+        session.connection_id = Some(2);
+        cache_manager.add_session("c1", &session);
+        assert!(is_reconnected_since(&cache_manager, "c1", 100));
+
+        session.connection_id = None;
+        session.distinct_time = Some(105);
+        cache_manager.add_session("c1", &session);
+        assert!(is_reconnected_since(&cache_manager, "c1", 100));
+    }
+
+    #[test]
+    fn is_session_owned_by_test() {
+        let mut session = MqttSession {
+            client_id: "c1".to_string(),
+            broker_id: Some(1),
+            connection_id: Some(10),
+            ..Default::default()
+        };
+        assert!(is_session_owned_by(&session, 1, 10));
+        assert!(!is_session_owned_by(&session, 1, 11));
+        assert!(!is_session_owned_by(&session, 2, 10));
+
+        session.broker_id = None;
+        session.connection_id = None;
+        assert!(!is_session_owned_by(&session, 1, 10));
+    }
 }
@@ -14,6 +14,7 @@
 
 pub const SUB_RETAIN_MESSAGE_PUSH_FLAG: &str = "retain_push_flag";
 pub const SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE: &str = "true";
+pub const SUB_RETAIN_MESSAGE_TRUNCATED_FLAG: &str = "retain_truncated_by";
 pub const WILDCARD_RESOURCE: &str = "*";
 
 pub const METRICS_KEY_PROTOCOL_NAME: &str = "protocol";
@@ -18,6 +18,8 @@
 pub const DELAY_MESSAGE_FLAG: &str = "delay_message_flag";
 pub const DELAY_MESSAGE_RECV_MS: &str = "delay_message_recv_ms";
 pub const DELAY_MESSAGE_TARGET_MS: &str = "delay_message_save_ms";
+// The largest delay interval in seconds accepted in a delay topic
+pub const DELAY_MESSAGE_MAX_INTERVAL: u64 = 4294967;
 
 #[derive(Debug)]
 pub struct DelayPublishTopic {
@@ -37,6 +39,9 @@ pub fn decode_delay_topic(topic: &str) -> Result<DelayPublishTopic, MqttBrokerEr
     }
 
     let delay_timestamp = str_slice[1].parse::<u64>()?;
+    if delay_timestamp > DELAY_MESSAGE_MAX_INTERVAL {
+        return Err(MqttBrokerError::NotConformDeferredTopic(topic.to_string()));
+    }
     str_slice.remove(0);
     str_slice.remove(0);
     let target_topic_name = format!("/{}", str_slice.join("/"));
@@ -70,5 +75,13 @@ pub fn decode_delay_message_test() {
         let msg = super::decode_delay_topic(topic_name).unwrap();
         assert_eq!(msg.target_topic_name, "/a/b");
         assert_eq!(msg.delay_timestamp, 60);
+
+        let topic_name = "$delayed/4294967/a/b";
+        let msg = super::decode_delay_topic(topic_name).unwrap();
+        assert_eq!(msg.delay_timestamp, super::DELAY_MESSAGE_MAX_INTERVAL);
+
+        assert!(super::decode_delay_topic("$delayed/4294968/a/b").is_err());
+        assert!(super::decode_delay_topic("$delayed/abc/a/b").is_err());
+        assert!(super::decode_delay_topic("$delayed/60").is_err());
     }
 }
@@ -17,10 +17,15 @@
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
 use crate::storage::cluster::ClusterStorage;
+use crate::subscribe::common::is_match_sub_and_topic;
 use common_config::mqtt::broker_mqtt_conf;
 use common_config::mqtt::config::{
-    BrokerMqttConfig, Feature, FlappingDetect, MqttProtocolConfig, NetworkThread, OfflineMessage,
-    Schema, Security, SlowSub, SystemMonitor,
+    AclDefaultPolicy, BrokerMqttConfig, CertUsername, ClientGroup, ClientGroupPolicy,
+    ConnectionLimit, ConnectorLimit, DuplicateClientId, Feature, FlappingDetect, MessageDedup,
+    MessageSampling, MqttProtocolConfig, NetworkThread, OfflineMessage, RateLimit, RetainMessage,
+    Schema, Security, SessionRestore, SessionTiering, SharedDispatch, SharedGroupDispatch, SlowSub,
+    SubscribeAuth, SubscribeSnapshot, SystemMonitor, TopicCleanup, UserMessageExpiry,
+    UserSingleSession, WillPublish,
 };
 use grpc_clients::pool::ClientPool;
 use strum_macros::{Display, EnumString};
@@ -37,6 +42,24 @@ pub enum ClusterDynamicConfig {
     NetworkThread,
     SystemMonitor,
     Schema,
+    RetainMessage,
+    UserMessageExpiry,
+    DuplicateClientId,
+    TopicCleanup,
+    SubscribeAuth,
+    SharedDispatch,
+    MessageSampling,
+    RateLimit,
+    SessionRestore,
+    SessionTiering,
+    WillPublish,
+    ConnectorLimit,
+    ClientGroup,
+    MessageDedup,
+    UserSingleSession,
+    ConnectionLimit,
+    CertUsername,
+    SubscribeSnapshot,
 }
 
 impl CacheManager {
@@ -128,8 +151,310 @@ pub fn get_security_config(&self) -> Security {
         self.get_cluster_config().security
     }
 
+    // Read on every ACL check, so the cluster config is not cloned
+    pub fn get_acl_default_policy(&self) -> AclDefaultPolicy {
+        self.cluster_info
+            .get(&self.cluster_name)
+            .map(|config| config.security.acl_default_policy)
+            .unwrap_or_default()
+    }
+
+    // retain message
+    pub fn update_retain_message_config(&self, retain_message: RetainMessage) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.retain_message = retain_message;
+        }
+    }
+
+    pub fn get_retain_message_config(&self) -> RetainMessage {
+        self.get_cluster_config().retain_message
+    }
+
+    // user message expiry
+    pub fn update_user_message_expiry_config(&self, user_message_expiry: UserMessageExpiry) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.user_message_expiry = user_message_expiry;
+        }
+    }
+
+    pub fn get_user_message_expiry_config(&self) -> UserMessageExpiry {
+        self.get_cluster_config().user_message_expiry
+    }
+
+    pub fn record_user_default_expiry_applied(&self, username: &str) {
+        *self
+            .user_default_expiry_applied
+            .entry(username.to_owned())
+            .or_insert(0) += 1;
+    }
+
+    pub fn get_user_default_expiry_applied_num(&self, username: &str) -> u64 {
+        self.user_default_expiry_applied
+            .get(username)
+            .map(|num| *num)
+            .unwrap_or(0)
+    }
+
+    pub fn remove_user_default_expiry_applied(&self, username: &str) {
+        self.user_default_expiry_applied.remove(username);
+    }
+
+    // duplicate client id
+    pub fn update_duplicate_client_id_config(&self, duplicate_client_id: DuplicateClientId) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.duplicate_client_id = duplicate_client_id;
+        }
+    }
+
+    pub fn get_duplicate_client_id_config(&self) -> DuplicateClientId {
+        self.get_cluster_config().duplicate_client_id
+    }
+
+    // topic cleanup
+    pub fn update_topic_cleanup_config(&self, topic_cleanup: TopicCleanup) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.topic_cleanup = topic_cleanup;
+        }
+    }
+
+    pub fn get_topic_cleanup_config(&self) -> TopicCleanup {
+        self.get_cluster_config().topic_cleanup
+    }
+
+    // subscribe authorization
+    pub fn update_subscribe_auth_config(&self, subscribe_auth: SubscribeAuth) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.subscribe_auth = subscribe_auth;
+        }
+    }
+
+    pub fn get_subscribe_auth_config(&self) -> SubscribeAuth {
+        self.get_cluster_config().subscribe_auth
+    }
+
+    // shared subscription dispatch
+    pub fn update_shared_dispatch_config(&self, shared_dispatch: SharedDispatch) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.shared_dispatch = shared_dispatch;
+        }
+    }
+
+    pub fn get_shared_dispatch_config(&self) -> SharedDispatch {
+        self.get_cluster_config().shared_dispatch
+    }
+
+    pub fn get_shared_group_dispatch_config(&self, group_name: &str) -> SharedGroupDispatch {
+        self.cluster_info
+            .get(&self.cluster_name)
+            .and_then(|config| config.shared_dispatch.groups.get(group_name).cloned())
+            .unwrap_or_default()
+    }
+
+    // message sampling
+    pub fn update_message_sampling_config(&self, message_sampling: MessageSampling) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            self.message_sampler.set_config(&message_sampling);
+            config.message_sampling = message_sampling;
+        }
+    }
+
+    pub fn get_message_sampling_config(&self) -> MessageSampling {
+        self.get_cluster_config().message_sampling
+    }
+
+    // publish and subscribe rate limit
+    pub fn update_rate_limit_config(&self, rate_limit: RateLimit) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.rate_limit = rate_limit;
+        }
+    }
+
+    pub fn get_rate_limit_config(&self) -> RateLimit {
+        self.get_cluster_config().rate_limit
+    }
+
+    // Read on every publish, so only the limit of the user is looked up instead of cloning the config
+    pub fn get_user_publish_rate(&self, username: &str) -> u64 {
+        self.cluster_info
+            .get(&self.cluster_name)
+            .map(|config| {
+                let group_rate = config
+                    .client_group
+                    .group_of(username)
+                    .and_then(|group| group.publish_rate);
+                config.rate_limit.publish_rate(username, group_rate)
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn get_user_subscribe_rate(&self, username: &str) -> u64 {
+        self.cluster_info
+            .get(&self.cluster_name)
+            .map(|config| config.rate_limit.subscribe_rate(username))
+            .unwrap_or(0)
+    }
+
+    // publish during session restore
+    pub fn update_session_restore_config(&self, session_restore: SessionRestore) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.session_restore = session_restore;
+        }
+    }
+
+    pub fn get_session_restore_config(&self) -> SessionRestore {
+        self.get_cluster_config().session_restore
+    }
+
+    // persistent session storage tiering
+    pub fn update_session_tiering_config(&self, session_tiering: SessionTiering) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.session_tiering = session_tiering;
+        }
+    }
+
+    pub fn get_session_tiering_config(&self) -> SessionTiering {
+        self.get_cluster_config().session_tiering
+    }
+
+    // will message publishing
+    pub fn update_will_publish_config(&self, will_publish: WillPublish) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.will_publish = will_publish;
+        }
+    }
+
+    pub fn get_will_publish_config(&self) -> WillPublish {
+        self.get_cluster_config().will_publish
+    }
+
+    // connector forwarding limits
+    pub fn update_connector_limit_config(&self, connector_limit: ConnectorLimit) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.connector_limit = connector_limit;
+        }
+    }
+
+    pub fn get_connector_limit_config(&self) -> ConnectorLimit {
+        self.get_cluster_config().connector_limit
+    }
+
+    // client groups and their policies
+    pub fn update_client_group_config(&self, client_group: ClientGroup) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.client_group = client_group;
+        }
+    }
+
+    pub fn get_client_group_config(&self) -> ClientGroup {
+        self.get_cluster_config().client_group
+    }
+
+    // Only the policy of the group the user belongs to is cloned, not the whole config
+    pub fn get_user_group_policy(&self, username: &str) -> Option<ClientGroupPolicy> {
+        self.cluster_info
+            .get(&self.cluster_name)
+            .and_then(|config| config.client_group.group_of(username).cloned())
+    }
+
+    // Highest QoS the subscriptions of the user are granted, the cluster maximum capped by
+    // the QoS cap of the user or its group
+    pub fn get_user_max_qos(&self, username: &str) -> u8 {
+        self.cluster_info
+            .get(&self.cluster_name)
+            .map(|config| {
+                let cluster_qos = config.mqtt_protocol_config.max_qos;
+                config
+                    .client_group
+                    .max_qos(username)
+                    .map_or(cluster_qos, |max_qos| max_qos.min(cluster_qos))
+            })
+            .unwrap_or(2)
+    }
+
+    // deduplication of messages by a client provided message id
+    pub fn update_message_dedup_config(&self, message_dedup: MessageDedup) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.message_dedup = message_dedup;
+        }
+    }
+
+    pub fn get_message_dedup_config(&self) -> MessageDedup {
+        self.get_cluster_config().message_dedup
+    }
+
+    // Read on every publish, so the config is only cloned when deduplication applies to the topic
+    pub fn get_topic_message_dedup_config(&self, topic_name: &str) -> Option<MessageDedup> {
+        let config = self.cluster_info.get(&self.cluster_name)?;
+        let dedup = &config.message_dedup;
+        if !dedup.enable {
+            return None;
+        }
+        if !dedup.topic_filters.is_empty()
+            && !dedup
+                .topic_filters
+                .iter()
+                .any(|filter| is_match_sub_and_topic(filter, topic_name).is_ok())
+        {
+            return None;
+        }
+        Some(dedup.clone())
+    }
+
+    // at most one session per user across the cluster
+    pub fn update_user_single_session_config(&self, user_single_session: UserSingleSession) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.user_single_session = user_single_session;
+        }
+    }
+
+    pub fn get_user_single_session_config(&self) -> UserSingleSession {
+        self.get_cluster_config().user_single_session
+    }
+
+    // Read on every connect, so only the user is looked up instead of cloning the config
+    pub fn is_user_single_session_enforced(&self, username: &str) -> bool {
+        self.cluster_info
+            .get(&self.cluster_name)
+            .map(|config| config.user_single_session.is_enforced(username))
+            .unwrap_or(false)
+    }
+
+    // connections per source IP
+    pub fn update_connection_limit_config(&self, connection_limit: ConnectionLimit) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.connection_limit = connection_limit;
+        }
+    }
+
+    pub fn get_connection_limit_config(&self) -> ConnectionLimit {
+        self.get_cluster_config().connection_limit
+    }
+
+    // client certificate CN as username
+    pub fn update_cert_username_config(&self, cert_username: CertUsername) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.cert_username = cert_username;
+        }
+    }
+
+    pub fn get_cert_username_config(&self) -> CertUsername {
+        self.get_cluster_config().cert_username
+    }
+
+    // periodic snapshot of the subscribe manager
+    pub fn update_subscribe_snapshot_config(&self, subscribe_snapshot: SubscribeSnapshot) {
+        if let Some(mut config) = self.cluster_info.get_mut(&self.cluster_name) {
+            config.subscribe_snapshot = subscribe_snapshot;
+        }
+    }
+
+    pub fn get_subscribe_snapshot_config(&self) -> SubscribeSnapshot {
+        self.get_cluster_config().subscribe_snapshot
+    }
+
     // cluster config
     pub fn set_cluster_config(&self, cluster: BrokerMqttConfig) {
+        self.message_sampler.set_config(&cluster.message_sampling);
         self.cluster_info.insert(self.cluster_name.clone(), cluster);
     }
 
@@ -178,6 +503,78 @@ pub async fn build_cluster_config(
         conf.system_monitor = data;
     }
 
+    if let Some(data) = get_retain_message(client_pool).await? {
+        conf.retain_message = data;
+    }
+
+    if let Some(data) = get_user_message_expiry(client_pool).await? {
+        conf.user_message_expiry = data;
+    }
+
+    if let Some(data) = get_duplicate_client_id(client_pool).await? {
+        conf.duplicate_client_id = data;
+    }
+
+    if let Some(data) = get_topic_cleanup(client_pool).await? {
+        conf.topic_cleanup = data;
+    }
+
+    if let Some(data) = get_subscribe_auth(client_pool).await? {
+        conf.subscribe_auth = data;
+    }
+
+    if let Some(data) = get_shared_dispatch(client_pool).await? {
+        conf.shared_dispatch = data;
+    }
+
+    if let Some(data) = get_message_sampling(client_pool).await? {
+        conf.message_sampling = data;
+    }
+
+    if let Some(data) = get_rate_limit(client_pool).await? {
+        conf.rate_limit = data;
+    }
+
+    if let Some(data) = get_session_restore(client_pool).await? {
+        conf.session_restore = data;
+    }
+
+    if let Some(data) = get_session_tiering(client_pool).await? {
+        conf.session_tiering = data;
+    }
+
+    if let Some(data) = get_will_publish(client_pool).await? {
+        conf.will_publish = data;
+    }
+
+    if let Some(data) = get_connector_limit(client_pool).await? {
+        conf.connector_limit = data;
+    }
+
+    if let Some(data) = get_client_group(client_pool).await? {
+        conf.client_group = data;
+    }
+
+    if let Some(data) = get_message_dedup(client_pool).await? {
+        conf.message_dedup = data;
+    }
+
+    if let Some(data) = get_user_single_session(client_pool).await? {
+        conf.user_single_session = data;
+    }
+
+    if let Some(data) = get_connection_limit(client_pool).await? {
+        conf.connection_limit = data;
+    }
+
+    if let Some(data) = get_cert_username(client_pool).await? {
+        conf.cert_username = data;
+    }
+
+    if let Some(data) = get_subscribe_snapshot(client_pool).await? {
+        conf.subscribe_snapshot = data;
+    }
+
     Ok(conf)
 }
 
@@ -223,6 +620,78 @@ pub async fn update_cluster_dynamic_config(
             let security_config = serde_json::from_slice(&config)?;
             cache_manager.update_security_config(security_config);
         }
+        ClusterDynamicConfig::RetainMessage => {
+            let retain_message = serde_json::from_slice(&config)?;
+            cache_manager.update_retain_message_config(retain_message);
+        }
+        ClusterDynamicConfig::UserMessageExpiry => {
+            let user_message_expiry = serde_json::from_slice(&config)?;
+            cache_manager.update_user_message_expiry_config(user_message_expiry);
+        }
+        ClusterDynamicConfig::DuplicateClientId => {
+            let duplicate_client_id = serde_json::from_slice(&config)?;
+            cache_manager.update_duplicate_client_id_config(duplicate_client_id);
+        }
+        ClusterDynamicConfig::TopicCleanup => {
+            let topic_cleanup = serde_json::from_slice(&config)?;
+            cache_manager.update_topic_cleanup_config(topic_cleanup);
+        }
+        ClusterDynamicConfig::SubscribeAuth => {
+            let subscribe_auth = serde_json::from_slice(&config)?;
+            cache_manager.update_subscribe_auth_config(subscribe_auth);
+        }
+        ClusterDynamicConfig::SharedDispatch => {
+            let shared_dispatch = serde_json::from_slice(&config)?;
+            cache_manager.update_shared_dispatch_config(shared_dispatch);
+        }
+        ClusterDynamicConfig::MessageSampling => {
+            let message_sampling = serde_json::from_slice(&config)?;
+            cache_manager.update_message_sampling_config(message_sampling);
+        }
+        ClusterDynamicConfig::RateLimit => {
+            let rate_limit = serde_json::from_slice(&config)?;
+            cache_manager.update_rate_limit_config(rate_limit);
+        }
+        ClusterDynamicConfig::SessionRestore => {
+            let session_restore = serde_json::from_slice(&config)?;
+            cache_manager.update_session_restore_config(session_restore);
+        }
+        ClusterDynamicConfig::SessionTiering => {
+            let session_tiering = serde_json::from_slice(&config)?;
+            cache_manager.update_session_tiering_config(session_tiering);
+        }
+        ClusterDynamicConfig::WillPublish => {
+            let will_publish = serde_json::from_slice(&config)?;
+            cache_manager.update_will_publish_config(will_publish);
+        }
+        ClusterDynamicConfig::ConnectorLimit => {
+            let connector_limit = serde_json::from_slice(&config)?;
+            cache_manager.update_connector_limit_config(connector_limit);
+        }
+        ClusterDynamicConfig::ClientGroup => {
+            let client_group = serde_json::from_slice(&config)?;
+            cache_manager.update_client_group_config(client_group);
+        }
+        ClusterDynamicConfig::MessageDedup => {
+            let message_dedup = serde_json::from_slice(&config)?;
+            cache_manager.update_message_dedup_config(message_dedup);
+        }
+        ClusterDynamicConfig::UserSingleSession => {
+            let user_single_session = serde_json::from_slice(&config)?;
+            cache_manager.update_user_single_session_config(user_single_session);
+        }
+        ClusterDynamicConfig::ConnectionLimit => {
+            let connection_limit = serde_json::from_slice(&config)?;
+            cache_manager.update_connection_limit_config(connection_limit);
+        }
+        ClusterDynamicConfig::CertUsername => {
+            let cert_username = serde_json::from_slice(&config)?;
+            cache_manager.update_cert_username_config(cert_username);
+        }
+        ClusterDynamicConfig::SubscribeSnapshot => {
+            let subscribe_snapshot = serde_json::from_slice(&config)?;
+            cache_manager.update_subscribe_snapshot_config(subscribe_snapshot);
+        }
     }
     Ok(())
 }
@@ -397,3 +866,345 @@ async fn get_system_monitor(
 
     Ok(None)
 }
+
+async fn get_retain_message(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<RetainMessage>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::RetainMessage.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<RetainMessage>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_user_message_expiry(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<UserMessageExpiry>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::UserMessageExpiry.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<UserMessageExpiry>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_duplicate_client_id(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<DuplicateClientId>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::DuplicateClientId.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<DuplicateClientId>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_topic_cleanup(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<TopicCleanup>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::TopicCleanup.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<TopicCleanup>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_subscribe_auth(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<SubscribeAuth>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::SubscribeAuth.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<SubscribeAuth>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_shared_dispatch(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<SharedDispatch>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::SharedDispatch.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<SharedDispatch>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_message_sampling(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<MessageSampling>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::MessageSampling.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<MessageSampling>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_rate_limit(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<RateLimit>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::RateLimit.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<RateLimit>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_session_restore(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<SessionRestore>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::SessionRestore.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<SessionRestore>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_session_tiering(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<SessionTiering>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::SessionTiering.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<SessionTiering>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_will_publish(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<WillPublish>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::WillPublish.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<WillPublish>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_connector_limit(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<ConnectorLimit>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::ConnectorLimit.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<ConnectorLimit>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_client_group(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<ClientGroup>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::ClientGroup.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<ClientGroup>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_message_dedup(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<MessageDedup>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::MessageDedup.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<MessageDedup>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_user_single_session(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<UserSingleSession>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::UserSingleSession.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<UserSingleSession>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_connection_limit(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<ConnectionLimit>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::ConnectionLimit.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<ConnectionLimit>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_cert_username(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<CertUsername>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::CertUsername.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<CertUsername>(&data)?));
+    }
+
+    Ok(None)
+}
+
+async fn get_subscribe_snapshot(
+    client_pool: &Arc<ClientPool>,
+) -> Result<Option<SubscribeSnapshot>, MqttBrokerError> {
+    let conf = broker_mqtt_conf();
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
+    let data = cluster_storage
+        .get_dynamic_config(
+            &conf.cluster_name,
+            &ClusterDynamicConfig::SubscribeSnapshot.to_string(),
+        )
+        .await?;
+
+    if !data.is_empty() {
+        return Ok(Some(serde_json::from_slice::<SubscribeSnapshot>(&data)?));
+    }
+
+    Ok(None)
+}
@@ -63,6 +63,9 @@ pub enum MqttBrokerError {
     #[error("{0}")]
     FromProtocolMQTTCommonError(#[from] protocol::mqtt::common::Error),
 
+    #[error("{0}")]
+    JsonWebTokenError(#[from] jsonwebtoken::errors::Error),
+
     #[error("Topic alias is too long. alias is {0}")]
     TopicAliasTooLong(u16),
 
@@ -93,6 +96,11 @@ pub enum MqttBrokerError {
     #[error("Cluster is in self-protection state, please request later")]
     ClusterIsInSelfProtection,
 
+    #[error(
+        "Node is draining and does not accept new connections, please connect to another node"
+    )]
+    NodeIsDraining,
+
     #[error("message is not in UTF8 format")]
     PayloadFormatInvalid,
 
@@ -110,9 +118,18 @@ pub enum MqttBrokerError {
     #[error("user has been existed")]
     UserAlreadyExist,
 
+    #[error("A verified client certificate is required to log in")]
+    ClientCertificateMissing,
+
+    #[error("Username {0} does not match the client certificate CN {1}")]
+    ClientCertificateUsernameMismatch(String, String),
+
     #[error("Session does not exist")]
     SessionDoesNotExist,
 
+    #[error("User {0} connected again from client {1}")]
+    UserSessionSuperseded(String, String),
+
     #[error("Topic [{0}] does not exist")]
     TopicDoesNotExist(String),
 
@@ -175,6 +192,26 @@ pub enum MqttBrokerError {
 
     #[error("Operation timeout, timeout time :{0}, operation: {1}")]
     OperationTimeout(u64, String),
+
+    #[error(
+        "Retained message of topic {0} exceeds the size limit. Payload size :{1}, Limit size :{2}"
+    )]
+    RetainMessageExceedsSizeLimit(String, usize, u64),
+
+    #[error("Retained messages are not allowed on topic {0}, rejected by policy {1}")]
+    RetainMessageNotAllowed(String, String),
+
+    #[error("Invalid {0} connector config: {1}")]
+    ConnectorConfigInvalid(String, String),
+
+    #[error("No key is configured to verify JWTs signed with {0}")]
+    JwtKeyNotFound(String),
+
+    #[error("JWT claim {0} is invalid")]
+    JwtClaimInvalid(String),
+
+    #[error("Invalid filter expression: {0}")]
+    InvalidFilterExpression(String),
 }
 
 impl From<MqttBrokerError> for Status {
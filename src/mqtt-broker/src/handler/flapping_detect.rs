@@ -16,8 +16,8 @@
 use crate::handler::dynamic_config::{save_cluster_dynamic_config, ClusterDynamicConfig};
 use crate::handler::error::MqttBrokerError;
 use crate::observability::metrics::event_metrics;
-use common_base::enum_type::time_unit_enum::TimeUnit;
-use common_base::tools::{convert_seconds, now_second};
+use crate::security::acl::metadata::build_blacklist_regex;
+use common_base::tools::now_second;
 use common_config::mqtt::config::FlappingDetect;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
@@ -41,6 +41,15 @@ pub struct FlappingDetectCondition {
     pub first_request_time: u64,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlappingDetectBan {
+    pub client_id: String,
+    pub ban_start: u64,
+    pub ban_expiry: u64,
+    // connections within the window that triggered the ban
+    pub connection_num: u64,
+}
+
 impl UpdateFlappingDetectCache {
     pub fn new(stop_send: broadcast::Sender<bool>, cache_manager: Arc<CacheManager>) -> Self {
         Self {
@@ -69,8 +78,7 @@ pub async fn start_update(&self) {
 
     async fn update_flapping_detect_cache(&self) {
         let config = self.cache_manager.get_flapping_detect_config().clone();
-        let window_time = config.window_time as u64;
-        let window_time_2_seconds = convert_seconds(window_time, TimeUnit::Minutes);
+        let window_time_2_seconds = config.window_sec();
         match self
             .cache_manager
             .acl_metadata
@@ -89,6 +97,11 @@ async fn update_flapping_detect_cache(&self) {
 }
 
 pub fn check_flapping_detect(client_id: String, cache_manager: &Arc<CacheManager>) {
+    let config = cache_manager.get_flapping_detect_config();
+    if is_flapping_detect_allowlisted(cache_manager, &config.allowlist, &client_id) {
+        return;
+    }
+
     // get metric
     let current_counter = event_metrics::get_client_connection_counter(client_id.clone());
     let current_request_time = now_second();
@@ -115,21 +128,22 @@ pub fn check_flapping_detect(client_id: String, cache_manager: &Arc<CacheManager
     // incr metric
     event_metrics::incr_client_connection_counter(client_id.clone());
 
-    let config = cache_manager.get_flapping_detect_config();
     let current_counter = event_metrics::get_client_connection_counter(client_id.clone());
     debug!("get current_counter : {current_counter} by client_id: {client_id}");
 
     if is_within_window_time(
         current_request_time,
         flapping_detect_condition.first_request_time,
-        config.window_time as u64,
+        config.window_sec(),
     ) && is_exceed_max_client_connections(
         current_counter,
         flapping_detect_condition.before_last_window_connections,
         config.max_client_connections,
     ) {
         debug!("add a new client_id: {client_id} into blacklist.");
-        add_blacklist_4_connection_jitter(cache_manager, config, client_id);
+        let connection_num =
+            current_counter - flapping_detect_condition.before_last_window_connections;
+        add_blacklist_4_connection_jitter(cache_manager, config, client_id, connection_num);
     }
 
     cache_manager
@@ -137,28 +151,103 @@ pub fn check_flapping_detect(client_id: String, cache_manager: &Arc<CacheManager
         .add_flapping_detect_condition(flapping_detect_condition);
 }
 
+// An allowlisted client is left out of flapping detect entirely, its connections are not counted
+fn is_flapping_detect_allowlisted(
+    cache_manager: &Arc<CacheManager>,
+    allowlist: &[String],
+    client_id: &str,
+) -> bool {
+    allowlist.iter().any(|pattern| {
+        pattern == client_id
+            || cache_manager
+                .acl_metadata
+                .get_flapping_detect_allowlist_regex(pattern)
+                .is_some_and(|regex| regex.is_match(client_id))
+    })
+}
+
 fn add_blacklist_4_connection_jitter(
     cache_manager: &Arc<CacheManager>,
     config: FlappingDetect,
     client_id: String,
+    connection_num: u64,
 ) {
+    let ban_start = now_second();
+    let ban_expiry = ban_start + config.ban_sec();
     let client_id_blacklist = MqttAclBlackList {
         blacklist_type: MqttAclBlackListType::ClientId,
-        resource_name: client_id,
-        end_time: now_second() + convert_seconds(config.ban_time as u64, TimeUnit::Minutes),
+        resource_name: client_id.clone(),
+        end_time: ban_expiry,
         desc: "Ban due to connection jitter ".to_string(),
+        expire_at: Some(ban_expiry),
     };
 
     cache_manager.add_blacklist(client_id_blacklist);
+    cache_manager
+        .acl_metadata
+        .add_flapping_detect_ban(FlappingDetectBan {
+            client_id,
+            ban_start,
+            ban_expiry,
+            connection_num,
+        });
+}
+
+// Clients currently banned by flapping detect, the one unbanned next comes first
+pub fn list_flapping_detect_bans(cache_manager: &Arc<CacheManager>) -> Vec<FlappingDetectBan> {
+    let now = now_second();
+    let acl_metadata = &cache_manager.acl_metadata;
+    acl_metadata
+        .flapping_detect_bans
+        .retain(|_, ban| ban.ban_expiry > now);
+
+    let mut bans: Vec<FlappingDetectBan> = acl_metadata
+        .flapping_detect_bans
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect();
+    bans.sort_by(|a, b| {
+        a.ban_expiry
+            .cmp(&b.ban_expiry)
+            .then_with(|| a.client_id.cmp(&b.client_id))
+    });
+    bans
+}
+
+// Lift the flapping detect ban of a client before it expires. The connections counted
+// for the client are dropped as well, so it starts over with a new window.
+pub fn unban_flapping_detect_client(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> Result<FlappingDetectBan, MqttBrokerError> {
+    let acl_metadata = &cache_manager.acl_metadata;
+    let Some(ban) = acl_metadata.remove_flapping_detect_ban(client_id) else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "client {} is not banned by flapping detect",
+            client_id
+        )));
+    };
+
+    // Leave the blacklist alone if the entry was replaced by another ban in the meantime
+    let blacklist = acl_metadata
+        .blacklist_client_id
+        .get(client_id)
+        .map(|entry| entry.value().clone());
+    if let Some(blacklist) = blacklist {
+        if blacklist.end_time == ban.ban_expiry {
+            cache_manager.remove_blacklist(blacklist);
+        }
+    }
+
+    acl_metadata.remove_flapping_detect_condition(client_id);
+    Ok(ban)
 }
 
 fn is_within_window_time(
     current_request_time: u64,
     first_request_time: u64,
-    window_time: u64,
+    window_time_seconds: u64,
 ) -> bool {
-    let window_time_seconds = convert_seconds(window_time, TimeUnit::Minutes);
-
     current_request_time - first_request_time < window_time_seconds
 }
 
@@ -170,17 +259,58 @@ fn is_exceed_max_client_connections(
     current_time - connect_times >= max_client_connections
 }
 
+// Detection needs a window to count in, a ban to apply and at least one connection to count
+fn validate_flapping_detect(config: &FlappingDetect) -> Result<(), MqttBrokerError> {
+    if config.window_sec() == 0 {
+        return Err(MqttBrokerError::CommonError(
+            "flapping detect window must be greater than 0".to_string(),
+        ));
+    }
+    if config.ban_sec() == 0 {
+        return Err(MqttBrokerError::CommonError(
+            "flapping detect ban must be greater than 0".to_string(),
+        ));
+    }
+    if config.max_client_connections < 1 {
+        return Err(MqttBrokerError::CommonError(
+            "flapping detect threshold must be at least 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 pub async fn enable_flapping_detect(
     client_pool: &Arc<ClientPool>,
     cache_manager: &Arc<CacheManager>,
     request: EnableFlappingDetectRequest,
 ) -> Result<(), MqttBrokerError> {
+    // A broken pattern is rejected here instead of silently never matching
+    for pattern in &request.allowlist {
+        build_blacklist_regex(pattern).map_err(|e| {
+            MqttBrokerError::CommonError(format!(
+                "invalid flapping detect allowlist pattern {}: {}",
+                pattern, e
+            ))
+        })?;
+    }
+
     let connection_jitter = FlappingDetect {
         enable: request.is_enable,
         window_time: request.window_time,
-        max_client_connections: request.max_client_connections as u64,
+        // max_disconnects replaces max_client_connections when it is set
+        max_client_connections: if request.max_disconnects > 0 {
+            request.max_disconnects as u64
+        } else {
+            request.max_client_connections as u64
+        },
         ban_time: request.ban_time,
+        window_seconds: request.window_seconds,
+        ban_seconds: request.ban_seconds,
+        allowlist: request.allowlist,
     };
+    if connection_jitter.enable {
+        validate_flapping_detect(&connection_jitter)?;
+    }
 
     save_cluster_dynamic_config(
         client_pool,
@@ -193,3 +323,168 @@ pub async fn enable_flapping_detect(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::unique_id;
+    use common_config::mqtt::config::FlappingDetect;
+    use common_config::mqtt::default_broker_mqtt;
+    use grpc_clients::pool::ClientPool;
+
+    use super::{
+        add_blacklist_4_connection_jitter, check_flapping_detect, list_flapping_detect_bans,
+        unban_flapping_detect_client, validate_flapping_detect, FlappingDetectCondition,
+    };
+    use crate::handler::cache::CacheManager;
+
+    #[test]
+    fn flapping_detect_allowlist_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(default_broker_mqtt());
+
+        let suffix = unique_id();
+        let allowed = format!("allowed-{}", suffix);
+        let allowed_by_pattern = format!("gateway-{}", suffix);
+        let flapping = format!("flapping-{}", suffix);
+        cache_manager.update_flapping_detect_config(FlappingDetect {
+            enable: true,
+            window_time: 1,
+            max_client_connections: 15,
+            ban_time: 5,
+            allowlist: vec![allowed.clone(), "gateway-.*".to_string()],
+            ..Default::default()
+        });
+
+        for _ in 0..20 {
+            for client_id in [&allowed, &allowed_by_pattern, &flapping] {
+                check_flapping_detect(client_id.clone(), &cache_manager);
+            }
+        }
+
+        let acl_metadata = &cache_manager.acl_metadata;
+        assert!(acl_metadata.blacklist_client_id.contains_key(&flapping));
+        assert!(!acl_metadata.blacklist_client_id.contains_key(&allowed));
+        assert!(!acl_metadata
+            .blacklist_client_id
+            .contains_key(&allowed_by_pattern));
+        let banned: Vec<String> = list_flapping_detect_bans(&cache_manager)
+            .into_iter()
+            .map(|ban| ban.client_id)
+            .collect();
+        assert_eq!(banned, vec![flapping]);
+        assert!(acl_metadata
+            .get_flapping_detect_condition(allowed.clone())
+            .is_none());
+    }
+
+    #[test]
+    fn flapping_detect_ban_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let config = |ban_time| FlappingDetect {
+            enable: true,
+            window_time: 1,
+            max_client_connections: 15,
+            ban_time,
+            ..Default::default()
+        };
+
+        add_blacklist_4_connection_jitter(&cache_manager, config(10), "c1".to_string(), 16);
+        add_blacklist_4_connection_jitter(&cache_manager, config(5), "c2".to_string(), 20);
+        cache_manager
+            .acl_metadata
+            .add_flapping_detect_condition(FlappingDetectCondition {
+                client_id: "c2".to_string(),
+                before_last_window_connections: 1,
+                first_request_time: 0,
+            });
+
+        let bans = list_flapping_detect_bans(&cache_manager);
+        let client_ids: Vec<&str> = bans.iter().map(|ban| ban.client_id.as_str()).collect();
+        assert_eq!(client_ids, vec!["c2", "c1"]);
+        assert_eq!(bans[0].connection_num, 20);
+        assert_eq!(bans[0].ban_expiry - bans[0].ban_start, 300);
+
+        let ban = unban_flapping_detect_client(&cache_manager, "c2").unwrap();
+        assert_eq!(ban.client_id, "c2");
+        let acl_metadata = &cache_manager.acl_metadata;
+        assert!(!acl_metadata.blacklist_client_id.contains_key("c2"));
+        assert!(acl_metadata
+            .get_flapping_detect_condition("c2".to_string())
+            .is_none());
+        assert!(acl_metadata.blacklist_client_id.contains_key("c1"));
+
+        assert!(unban_flapping_detect_client(&cache_manager, "c2").is_err());
+        assert_eq!(list_flapping_detect_bans(&cache_manager).len(), 1);
+    }
+
+    #[test]
+    fn flapping_detect_seconds_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(default_broker_mqtt());
+
+        // The second based window and ban take precedence over the minute based ones
+        let config = FlappingDetect {
+            enable: true,
+            window_time: 1,
+            max_client_connections: 3,
+            ban_time: 5,
+            window_seconds: 30,
+            ban_seconds: 45,
+            ..Default::default()
+        };
+        assert_eq!(config.window_sec(), 30);
+        assert_eq!(config.ban_sec(), 45);
+        cache_manager.update_flapping_detect_config(config);
+
+        let client_id = format!("flapping-{}", unique_id());
+        for _ in 0..3 {
+            check_flapping_detect(client_id.clone(), &cache_manager);
+        }
+        assert!(!cache_manager
+            .acl_metadata
+            .blacklist_client_id
+            .contains_key(&client_id));
+
+        check_flapping_detect(client_id.clone(), &cache_manager);
+        let bans = list_flapping_detect_bans(&cache_manager);
+        let ban = bans.iter().find(|ban| ban.client_id == client_id).unwrap();
+        assert_eq!(ban.ban_expiry - ban.ban_start, 45);
+    }
+
+    #[test]
+    fn validate_flapping_detect_test() {
+        let valid = FlappingDetect {
+            enable: true,
+            max_client_connections: 1,
+            window_seconds: 10,
+            ban_seconds: 10,
+            ..Default::default()
+        };
+        assert!(validate_flapping_detect(&valid).is_ok());
+
+        let no_window = FlappingDetect {
+            window_time: 0,
+            window_seconds: 0,
+            ..valid.clone()
+        };
+        assert!(validate_flapping_detect(&no_window).is_err());
+
+        let no_ban = FlappingDetect {
+            ban_time: 0,
+            ban_seconds: 0,
+            ..valid.clone()
+        };
+        assert!(validate_flapping_detect(&no_ban).is_err());
+
+        let no_threshold = FlappingDetect {
+            max_client_connections: 0,
+            ..valid
+        };
+        assert!(validate_flapping_detect(&no_threshold).is_err());
+    }
+}
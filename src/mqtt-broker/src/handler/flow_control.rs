@@ -12,8 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use common_base::tools::now_second;
+use dashmap::DashMap;
 use protocol::mqtt::common::QoS;
 
+use crate::handler::cache::CacheManager;
+use crate::observability::metrics::subscribe::incr_subscribe_rate_limited_counter;
+
 pub fn is_qos_message(qos: QoS) -> bool {
     qos == QoS::AtLeastOnce || qos == QoS::ExactlyOnce
 }
@@ -22,6 +30,172 @@ pub fn is_connection_rate_exceeded() -> bool {
     false
 }
 
-pub fn is_subscribe_rate_exceeded() -> bool {
-    false
+pub fn is_subscribe_rate_exceeded(cache_manager: &Arc<CacheManager>, username: &str) -> bool {
+    let subscribe_rate = cache_manager.get_user_subscribe_rate(username);
+    if cache_manager
+        .subscribe_rate_limiter
+        .try_acquire(username, subscribe_rate, now_second())
+    {
+        return false;
+    }
+    incr_subscribe_rate_limited_counter(username);
+    true
+}
+
+// Windows of users that sent nothing for this long are dropped, together with their counts
+pub const RATE_WINDOW_IDLE_SEC: u64 = 300;
+
+#[derive(Default, Clone, Copy)]
+struct RateWindow {
+    second: u64,
+    num: u64,
+    last_second_num: u64,
+    rejected_num: u64,
+}
+
+// Counts the packets each user sends in the current second
+#[derive(Default)]
+pub struct UserRateLimiter {
+    // (username, RateWindow)
+    windows: DashMap<String, RateWindow>,
+    // Second the idle windows were last dropped in
+    last_evict_second: AtomicU64,
+}
+
+impl UserRateLimiter {
+    // Returns false when the user already sent `limit` packets in this second,
+    // a limit of 0 means the user is not limited
+    pub fn try_acquire(&self, username: &str, limit: u64, now: u64) -> bool {
+        if limit == 0 {
+            return true;
+        }
+        self.evict_idle_windows(now);
+
+        let mut window = self.windows.entry(username.to_owned()).or_default();
+        if window.second != now {
+            window.last_second_num = if window.second + 1 == now {
+                window.num
+            } else {
+                0
+            };
+            window.second = now;
+            window.num = 0;
+        }
+
+        if window.num >= limit {
+            window.rejected_num += 1;
+            return false;
+        }
+        window.num += 1;
+        true
+    }
+
+    // Runs at most once per RATE_WINDOW_IDLE_SEC, so that users who stopped sending do not
+    // keep their window forever
+    fn evict_idle_windows(&self, now: u64) {
+        let last = self.last_evict_second.load(Ordering::Relaxed);
+        if now < last + RATE_WINDOW_IDLE_SEC
+            || self
+                .last_evict_second
+                .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        self.windows
+            .retain(|_, window| window.second + RATE_WINDOW_IDLE_SEC > now);
+    }
+
+    // Packets of the user rejected since its window was created
+    pub fn rejected_num(&self, username: &str) -> u64 {
+        self.windows
+            .get(username)
+            .map(|window| window.rejected_num)
+            .unwrap_or(0)
+    }
+
+    // Packets the user sent in the last complete second
+    pub fn current_rate(&self, username: &str, now: u64) -> u64 {
+        match self.windows.get(username) {
+            Some(window) if window.second == now => window.last_second_num,
+            Some(window) if window.second + 1 == now => window.num,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use common_config::mqtt::config::RateLimit;
+    use grpc_clients::pool::ClientPool;
+
+    use super::{is_subscribe_rate_exceeded, UserRateLimiter, RATE_WINDOW_IDLE_SEC};
+    use crate::handler::cache::CacheManager;
+    use crate::observability::metrics::subscribe::get_subscribe_rate_limited_counter;
+
+    #[test]
+    fn user_rate_limiter_test() {
+        let limiter = UserRateLimiter::default();
+        assert!(limiter.try_acquire("u1", 2, 10));
+        assert!(limiter.try_acquire("u1", 2, 10));
+        assert!(!limiter.try_acquire("u1", 2, 10));
+        assert!(limiter.try_acquire("u2", 2, 10));
+        assert_eq!(limiter.current_rate("u1", 10), 0);
+        assert_eq!(limiter.current_rate("u1", 11), 2);
+
+        assert!(limiter.try_acquire("u1", 2, 11));
+        assert_eq!(limiter.current_rate("u1", 11), 2);
+        assert_eq!(limiter.current_rate("u1", 13), 0);
+
+        // unlimited users are never counted
+        assert!((0..10).all(|_| limiter.try_acquire("u3", 0, 10)));
+        assert_eq!(limiter.current_rate("u3", 11), 0);
+    }
+
+    #[test]
+    fn user_rate_limiter_evict_test() {
+        let limiter = UserRateLimiter::default();
+        let start = RATE_WINDOW_IDLE_SEC;
+        assert!(limiter.try_acquire("u1", 1, start));
+        assert!(!limiter.try_acquire("u1", 1, start));
+        assert_eq!(limiter.rejected_num("u1"), 1);
+
+        // u2 keeps sending, u1 goes idle and its window is dropped
+        let later = start + RATE_WINDOW_IDLE_SEC;
+        assert!(limiter.try_acquire("u2", 1, later - 1));
+        assert!(limiter.try_acquire("u2", 1, later));
+        assert_eq!(limiter.windows.len(), 1);
+        assert_eq!(limiter.rejected_num("u1"), 0);
+        assert_eq!(limiter.current_rate("u2", later), 1);
+    }
+
+    #[test]
+    fn subscribe_rate_exceeded_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(Default::default());
+        cache_manager.update_rate_limit_config(RateLimit {
+            default_subscribe_rate: 0,
+            user_subscribe_overrides: HashMap::from([("sub-limited".to_string(), 2)]),
+            ..Default::default()
+        });
+
+        assert!(!is_subscribe_rate_exceeded(&cache_manager, "sub-limited"));
+        assert!(!is_subscribe_rate_exceeded(&cache_manager, "sub-limited"));
+        assert!(is_subscribe_rate_exceeded(&cache_manager, "sub-limited"));
+        assert_eq!(get_subscribe_rate_limited_counter("sub-limited"), 1);
+
+        // the publish limiter is counted separately
+        assert!(cache_manager
+            .publish_rate_limiter
+            .try_acquire("sub-limited", 1, 0));
+
+        // users without an override fall back to the unlimited default
+        assert!((0..10).all(|_| !is_subscribe_rate_exceeded(&cache_manager, "sub-free")));
+        assert_eq!(get_subscribe_rate_limited_counter("sub-free"), 0);
+    }
 }
@@ -30,7 +30,7 @@
 use tracing::{debug, error, info};
 
 use super::cache::{CacheManager, ConnectionLiveTime};
-use super::connection::disconnect_connection;
+use super::connection::disconnect_unclean_connection;
 use super::response::response_packet_mqtt_distinct_by_reason;
 use crate::handler::error::MqttBrokerError;
 use crate::server::connection::NetworkConnection;
@@ -158,14 +158,13 @@ fn try_send_distinct_packet(
                     .await;
             }
 
-            let _ = disconnect_connection(
+            let _ = disconnect_unclean_connection(
                 &connection.client_id,
                 connect_id,
                 &cache_manager,
                 &client_pool,
                 &connection_manager,
                 &subscribe_manager,
-                false,
             )
             .await;
 
@@ -15,7 +15,10 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
+use common_config::mqtt::config::UnauthorizedWillAction;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::acl::mqtt_acl::MqttAclAction;
+use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::lastwill::LastWillData;
 use metadata_struct::mqtt::message::MqttMessage;
 use protocol::mqtt::common::{LastWill, LastWillProperties, Publish, PublishProperties};
@@ -26,6 +29,10 @@
 use super::message::build_message_expire;
 use super::retain::save_retain_message;
 use super::topic::try_init_topic;
+use crate::observability::metrics::session::{
+    incr_will_acl_counter, WILL_ACL_OUTCOME_REJECTED, WILL_ACL_OUTCOME_STRIPPED,
+};
+use crate::security::acl::auth::is_allow_acl;
 use crate::storage::message::MessageStorage;
 use crate::storage::session::SessionStorage;
 
@@ -58,6 +65,8 @@ pub async fn send_last_will_message<S>(
     )
     .await?;
 
+    let message_expire = build_message_expire(cache_manager, client_id, &publish_properties);
+
     save_retain_message(
         cache_manager,
         client_pool,
@@ -65,13 +74,13 @@ pub async fn send_last_will_message<S>(
         client_id,
         &publish,
         &publish_properties,
+        message_expire,
     )
     .await?;
 
     // Persisting stores message data
     let message_storage = MessageStorage::new(message_storage_adapter.clone());
 
-    let message_expire = build_message_expire(cache_manager, &publish_properties);
     if let Some(record) =
         MqttMessage::build_record(client_id, &publish, &publish_properties, message_expire)
     {
@@ -144,6 +153,50 @@ pub async fn save_last_will_message(
     Ok(())
 }
 
+// What the CONNECT handling does with the will of the client
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LastWillAclOutcome {
+    Accept,
+    Reject,
+    Strip,
+}
+
+// The will is published on behalf of the client after it is gone, so the client must be allowed
+// to publish to the will topic itself. The topic is checked with the rules of a PUBLISH.
+pub fn check_last_will_acl(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    last_will: &Option<LastWill>,
+    unauthorized_will_action: UnauthorizedWillAction,
+) -> LastWillAclOutcome {
+    let Some(will) = last_will else {
+        return LastWillAclOutcome::Accept;
+    };
+
+    let topic_name = String::from_utf8_lossy(&will.topic);
+    if is_allow_acl(
+        cache_manager,
+        connection,
+        &topic_name,
+        MqttAclAction::Publish,
+        will.retain,
+        will.qos,
+    ) {
+        return LastWillAclOutcome::Accept;
+    }
+
+    match unauthorized_will_action {
+        UnauthorizedWillAction::Reject => {
+            incr_will_acl_counter(WILL_ACL_OUTCOME_REJECTED);
+            LastWillAclOutcome::Reject
+        }
+        UnauthorizedWillAction::Strip => {
+            incr_will_acl_counter(WILL_ACL_OUTCOME_STRIPPED);
+            LastWillAclOutcome::Strip
+        }
+    }
+}
+
 pub fn last_will_delay_interval(last_will_properties: &Option<LastWillProperties>) -> Option<u64> {
     let delay_interval = if let Some(properties) = last_will_properties.clone() {
         properties.delay_interval?
@@ -156,10 +209,114 @@ pub fn last_will_delay_interval(last_will_properties: &Option<LastWillProperties
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use bytes::Bytes;
-    use protocol::mqtt::common::{LastWill, LastWillProperties};
+    use common_base::tools::local_hostname;
+    use common_config::mqtt::config::{BrokerMqttConfig, UnauthorizedWillAction};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::acl::mqtt_acl::{
+        MqttAcl, MqttAclAction, MqttAclPermission, MqttAclResourceType,
+    };
+    use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
+    use protocol::mqtt::common::{LastWill, LastWillProperties, QoS};
+
+    use super::{
+        build_publish_message_by_lastwill, check_last_will_acl, last_will_delay_interval,
+        LastWillAclOutcome,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::handler::constant::WILDCARD_RESOURCE;
 
-    use super::{build_publish_message_by_lastwill, last_will_delay_interval};
+    #[tokio::test]
+    pub async fn check_last_will_acl_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(BrokerMqttConfig::default());
+        cache_manager.add_acl(MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: "user-1".to_string(),
+            topic: "devices/+/status".to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: MqttAclAction::Publish,
+            permission: MqttAclPermission::Deny,
+        });
+
+        let mut connection = MQTTConnection::new(ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: local_hostname(),
+        });
+        connection.login_success("user-1".to_string());
+
+        let will = |topic: &'static str| {
+            Some(LastWill {
+                topic: Bytes::from(topic),
+                message: Bytes::from("offline"),
+                qos: QoS::AtLeastOnce,
+                retain: false,
+            })
+        };
+
+        // no will, nothing to check
+        assert_eq!(
+            check_last_will_acl(
+                &cache_manager,
+                &connection,
+                &None,
+                UnauthorizedWillAction::Reject
+            ),
+            LastWillAclOutcome::Accept
+        );
+
+        // a will topic the client may publish to
+        assert_eq!(
+            check_last_will_acl(
+                &cache_manager,
+                &connection,
+                &will("devices/d1/events"),
+                UnauthorizedWillAction::Reject
+            ),
+            LastWillAclOutcome::Accept
+        );
+
+        // a will topic matched by the deny rule through its wildcard
+        assert_eq!(
+            check_last_will_acl(
+                &cache_manager,
+                &connection,
+                &will("devices/d1/status"),
+                UnauthorizedWillAction::Reject
+            ),
+            LastWillAclOutcome::Reject
+        );
+        assert_eq!(
+            check_last_will_acl(
+                &cache_manager,
+                &connection,
+                &will("devices/d1/status"),
+                UnauthorizedWillAction::Strip
+            ),
+            LastWillAclOutcome::Strip
+        );
+
+        // the rule only binds its user
+        connection.login_success("user-2".to_string());
+        assert_eq!(
+            check_last_will_acl(
+                &cache_manager,
+                &connection,
+                &will("devices/d1/status"),
+                UnauthorizedWillAction::Reject
+            ),
+            LastWillAclOutcome::Accept
+        );
+    }
 
     #[tokio::test]
     pub async fn last_will_delay_interval_test() {
@@ -19,6 +19,7 @@
 use protocol::mqtt::common::PublishProperties;
 
 use super::cache::CacheManager;
+use crate::observability::metrics::publish::incr_user_default_message_expiry_counter;
 
 pub fn is_message_expire(message: &MqttMessage) -> bool {
     message.expiry_interval < now_second()
@@ -26,6 +27,7 @@ pub fn is_message_expire(message: &MqttMessage) -> bool {
 
 pub fn build_message_expire(
     cache_manager: &Arc<CacheManager>,
+    client_id: &str,
     publish_properties: &Option<PublishProperties>,
 ) -> u64 {
     if let Some(properties) = publish_properties {
@@ -36,18 +38,53 @@ pub fn build_message_expire(
         }
     }
 
-    let cluster = cache_manager.get_cluster_config();
-    now_second() + cluster.mqtt_protocol_config.max_message_expiry_interval
+    // The client did not set an expiry, fall back to the default of the login user,
+    // which may not go beyond the cluster maximum
+    let max_expire = cache_manager
+        .get_cluster_config()
+        .mqtt_protocol_config
+        .max_message_expiry_interval;
+    if let Some(expire) = get_user_default_message_expiry(cache_manager, client_id) {
+        incr_user_default_message_expiry_counter();
+        return now_second() + expire.min(max_expire);
+    }
+
+    now_second() + max_expire
+}
+
+fn get_user_default_message_expiry(
+    cache_manager: &Arc<CacheManager>,
+    client_id: &str,
+) -> Option<u64> {
+    let connect_id = cache_manager.get_connect_id(client_id)?;
+    let connection = cache_manager.get_connection(connect_id)?;
+
+    // The default of the user wins over the default of its group
+    let user_defaults = cache_manager.get_user_message_expiry_config().user_defaults;
+    if let Some(expire) = user_defaults.get(&connection.login_user) {
+        cache_manager.record_user_default_expiry_applied(&connection.login_user);
+        return Some(*expire);
+    }
+    cache_manager
+        .get_user_group_policy(&connection.login_user)
+        .map(|group| group.message_expiry_interval)
+        .filter(|expire| *expire > 0)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::sync::Arc;
 
     use common_base::tools::now_second;
-    use common_config::mqtt::config::{BrokerMqttConfig, MqttProtocolConfig};
+
+    use common_config::mqtt::config::{
+        BrokerMqttConfig, ClientGroup, ClientGroupPolicy, MqttProtocolConfig, UserMessageExpiry,
+    };
     use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::MQTTConnection;
     use metadata_struct::mqtt::message::MqttMessage;
+    use metadata_struct::mqtt::session::MqttSession;
     use protocol::mqtt::common::PublishProperties;
 
     use crate::handler::cache::CacheManager;
@@ -68,17 +105,112 @@ fn build_message_expire_test() {
         cache_manager.set_cluster_config(cluster);
 
         let publish_properties = None;
-        let res = build_message_expire(&cache_manager, &publish_properties);
+        let res = build_message_expire(&cache_manager, "c1", &publish_properties);
         assert_eq!(res, now_second() + 10);
 
         let publish_properties = PublishProperties {
             message_expiry_interval: Some(3),
             ..Default::default()
         };
-        let res = build_message_expire(&cache_manager, &Some(publish_properties));
+        let res = build_message_expire(&cache_manager, "c1", &Some(publish_properties));
         assert_eq!(res, now_second() + 3);
     }
 
+    #[test]
+    fn build_message_expire_user_default_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cluster_name = "test".to_string();
+        let cache_manager = Arc::new(CacheManager::new(client_pool, cluster_name));
+        let cluster = BrokerMqttConfig {
+            mqtt_protocol_config: MqttProtocolConfig {
+                max_message_expiry_interval: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        cache_manager.set_cluster_config(cluster);
+        cache_manager.update_user_message_expiry_config(UserMessageExpiry {
+            user_defaults: HashMap::from([("u1".to_string(), 60)]),
+        });
+
+        let client_id = "c1";
+        cache_manager.add_session(
+            client_id,
+            &MqttSession {
+                client_id: client_id.to_string(),
+                ..Default::default()
+            },
+        );
+        cache_manager.add_connection(
+            1,
+            MQTTConnection {
+                connect_id: 1,
+                client_id: client_id.to_string(),
+                login_user: "u1".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let res = build_message_expire(&cache_manager, client_id, &None);
+        assert_eq!(res, now_second() + 60);
+        assert_eq!(cache_manager.get_user_default_expiry_applied_num("u1"), 1);
+
+        // an explicit expiry from the client always wins over the user default
+        let publish_properties = PublishProperties {
+            message_expiry_interval: Some(3),
+            ..Default::default()
+        };
+        let res = build_message_expire(&cache_manager, client_id, &Some(publish_properties));
+        assert_eq!(res, now_second() + 3);
+
+        // clients without a matching user keep the cluster default
+        let res = build_message_expire(&cache_manager, "c2", &None);
+        assert_eq!(res, now_second() + 100);
+
+        // users without a default of their own take the default of their group
+        cache_manager.update_client_group_config(ClientGroup {
+            groups: HashMap::from([(
+                "g1".to_string(),
+                ClientGroupPolicy {
+                    message_expiry_interval: 30,
+                    ..Default::default()
+                },
+            )]),
+            members: HashMap::from([
+                ("u1".to_string(), "g1".to_string()),
+                ("u2".to_string(), "g1".to_string()),
+            ]),
+            ..Default::default()
+        });
+        cache_manager.add_session(
+            "c3",
+            &MqttSession {
+                client_id: "c3".to_string(),
+                ..Default::default()
+            },
+        );
+        cache_manager.add_connection(
+            2,
+            MQTTConnection {
+                connect_id: 2,
+                client_id: "c3".to_string(),
+                login_user: "u2".to_string(),
+                ..Default::default()
+            },
+        );
+        let res = build_message_expire(&cache_manager, "c3", &None);
+        assert_eq!(res, now_second() + 30);
+        let res = build_message_expire(&cache_manager, client_id, &None);
+        assert_eq!(res, now_second() + 60);
+
+        // a user default above the cluster maximum is capped to it
+        cache_manager.update_user_message_expiry_config(UserMessageExpiry {
+            user_defaults: HashMap::from([("u1".to_string(), 600)]),
+        });
+        let res = build_message_expire(&cache_manager, client_id, &None);
+        assert_eq!(res, now_second() + 100);
+    }
+
     #[test]
     fn is_message_expire_test() {
         let message = MqttMessage {
@@ -0,0 +1,294 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::now_second;
+use common_config::mqtt::config::MessageDedup;
+use dashmap::DashMap;
+use protocol::mqtt::common::PublishProperties;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::info;
+
+use crate::handler::cache::CacheManager;
+
+// How often expired message ids, and topics without remembered ids, are forgotten
+const MESSAGE_DEDUP_EXPIRY_INTERVAL_SEC: u64 = 10;
+
+#[derive(Default, Clone)]
+struct SeenMessageIds {
+    // (message_id, first seen time in seconds)
+    seen: HashMap<String, u64>,
+    // message ids in the order they were first seen, oldest at the front
+    order: VecDeque<String>,
+    // duplicates suppressed since the topic was first remembered
+    suppressed_num: u64,
+}
+
+impl SeenMessageIds {
+    fn expire(&mut self, window_sec: u64, now: u64) {
+        while let Some(id) = self.order.front() {
+            match self.seen.get(id) {
+                Some(seen_at) if seen_at + window_sec > now => break,
+                _ => {
+                    if let Some(id) = self.order.pop_front() {
+                        self.seen.remove(&id);
+                    }
+                }
+            }
+        }
+    }
+
+    fn evict_to(&mut self, max_ids: u64) {
+        while self.seen.len() as u64 > max_ids {
+            match self.order.pop_front() {
+                Some(id) => {
+                    self.seen.remove(&id);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+// Remembers the message ids recently published on each topic
+#[derive(Default, Clone)]
+pub struct MessageDeduplicator {
+    // (topic_name, SeenMessageIds)
+    topics: DashMap<String, SeenMessageIds>,
+}
+
+impl MessageDeduplicator {
+    // Returns true when the message id was already seen on the topic within the window
+    pub fn is_duplicate(
+        &self,
+        config: &MessageDedup,
+        topic_name: &str,
+        message_id: &str,
+        now: u64,
+    ) -> bool {
+        let Some(mut seen_ids) = self.topics.get_mut(topic_name) else {
+            return false;
+        };
+        seen_ids.expire(config.window_sec, now);
+        if !seen_ids.seen.contains_key(message_id) {
+            return false;
+        }
+        seen_ids.suppressed_num += 1;
+        true
+    }
+
+    // Remembers the id of a message that was stored. Each topic remembers at most
+    // `max_ids_per_topic` ids.
+    pub fn record(&self, config: &MessageDedup, topic_name: &str, message_id: &str, now: u64) {
+        if config.max_ids_per_topic == 0 {
+            return;
+        }
+        let mut seen_ids = self.topics.entry(topic_name.to_owned()).or_default();
+        seen_ids.expire(config.window_sec, now);
+        if seen_ids.seen.contains_key(message_id) {
+            return;
+        }
+        seen_ids.evict_to(config.max_ids_per_topic - 1);
+        seen_ids.seen.insert(message_id.to_owned(), now);
+        seen_ids.order.push_back(message_id.to_owned());
+    }
+
+    // Forget expired ids, and the topics left without any
+    pub fn expire(&self, window_sec: u64, now: u64) {
+        self.topics.retain(|_, seen_ids| {
+            seen_ids.expire(window_sec, now);
+            !seen_ids.seen.is_empty()
+        });
+    }
+
+    pub fn suppressed_num(&self, topic_name: &str) -> u64 {
+        self.topics
+            .get(topic_name)
+            .map(|seen_ids| seen_ids.suppressed_num)
+            .unwrap_or(0)
+    }
+
+    // (topic_name, suppressed message num) of every remembered topic with suppressed messages
+    pub fn list_suppressed(&self) -> Vec<(String, u64)> {
+        self.topics
+            .iter()
+            .filter(|entry| entry.suppressed_num > 0)
+            .map(|entry| (entry.key().clone(), entry.suppressed_num))
+            .collect()
+    }
+
+    pub fn remembered_num(&self, topic_name: &str) -> u64 {
+        self.topics
+            .get(topic_name)
+            .map(|seen_ids| seen_ids.seen.len() as u64)
+            .unwrap_or(0)
+    }
+
+    pub fn clear(&self) {
+        self.topics.clear();
+    }
+}
+
+// Whether the publish repeats a message id already stored on the topic. Off by default, and
+// messages without the configured id property are never treated as duplicates.
+pub fn is_duplicate_message(
+    cache_manager: &Arc<CacheManager>,
+    topic_name: &str,
+    publish_properties: &Option<PublishProperties>,
+) -> bool {
+    let Some(config) = cache_manager.get_topic_message_dedup_config(topic_name) else {
+        return false;
+    };
+    let Some(message_id) = get_message_id(publish_properties, &config.property_name) else {
+        return false;
+    };
+    cache_manager
+        .message_deduplicator
+        .is_duplicate(&config, topic_name, &message_id, now_second())
+}
+
+// Remember the message id once the message was stored, a message that failed to be stored
+// is not a duplicate when the producer resends it
+pub fn record_message_id(
+    cache_manager: &Arc<CacheManager>,
+    topic_name: &str,
+    publish_properties: &Option<PublishProperties>,
+) {
+    let Some(config) = cache_manager.get_topic_message_dedup_config(topic_name) else {
+        return;
+    };
+    let Some(message_id) = get_message_id(publish_properties, &config.property_name) else {
+        return;
+    };
+    cache_manager
+        .message_deduplicator
+        .record(&config, topic_name, &message_id, now_second());
+}
+
+pub struct MessageDedupExpiryThread {
+    stop_send: broadcast::Sender<bool>,
+    cache_manager: Arc<CacheManager>,
+}
+
+impl MessageDedupExpiryThread {
+    pub fn new(stop_send: broadcast::Sender<bool>, cache_manager: Arc<CacheManager>) -> Self {
+        MessageDedupExpiryThread {
+            stop_send,
+            cache_manager,
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Message dedup expiry thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.expire_message_ids()=>{
+                }
+            }
+        }
+    }
+
+    async fn expire_message_ids(&self) {
+        let config = self.cache_manager.get_message_dedup_config();
+        self.cache_manager
+            .message_deduplicator
+            .expire(config.window_sec, now_second());
+        sleep(Duration::from_secs(MESSAGE_DEDUP_EXPIRY_INTERVAL_SEC)).await;
+    }
+}
+
+pub fn get_message_id(
+    publish_properties: &Option<PublishProperties>,
+    property_name: &str,
+) -> Option<String> {
+    publish_properties.as_ref().and_then(|properties| {
+        properties
+            .user_properties
+            .iter()
+            .find(|(key, _)| key == property_name)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageDeduplicator;
+    use common_config::mqtt::config::MessageDedup;
+
+    fn config(window_sec: u64, max_ids_per_topic: u64) -> MessageDedup {
+        MessageDedup {
+            enable: true,
+            property_name: "message-id".to_string(),
+            topic_filters: Vec::new(),
+            window_sec,
+            max_ids_per_topic,
+        }
+    }
+
+    #[test]
+    fn message_deduplicator_test() {
+        let dedup = MessageDeduplicator::default();
+        let config = config(10, 2);
+
+        assert!(!dedup.is_duplicate(&config, "t1", "a", 100));
+        dedup.record(&config, "t1", "a", 100);
+        assert!(dedup.is_duplicate(&config, "t1", "a", 105));
+        // ids are remembered per topic
+        assert!(!dedup.is_duplicate(&config, "t2", "a", 105));
+        assert_eq!(dedup.suppressed_num("t1"), 1);
+        assert_eq!(dedup.suppressed_num("t2"), 0);
+
+        // the window counts from the first time the id was seen
+        assert!(!dedup.is_duplicate(&config, "t1", "a", 110));
+
+        // the oldest id is forgotten once the topic holds the maximum
+        dedup.record(&config, "t1", "b", 111);
+        dedup.record(&config, "t1", "c", 112);
+        assert_eq!(dedup.remembered_num("t1"), 2);
+        assert!(!dedup.is_duplicate(&config, "t1", "a", 113));
+        assert!(dedup.is_duplicate(&config, "t1", "c", 113));
+        assert_eq!(dedup.suppressed_num("t1"), 2);
+    }
+
+    #[test]
+    fn message_deduplicator_record_after_store_test() {
+        let dedup = MessageDeduplicator::default();
+        let config = config(10, 2);
+
+        // a message that was not stored is not remembered, its resend is accepted
+        assert!(!dedup.is_duplicate(&config, "t1", "a", 100));
+        assert!(!dedup.is_duplicate(&config, "t1", "a", 101));
+        assert_eq!(dedup.remembered_num("t1"), 0);
+
+        // topics are forgotten once all their ids expired
+        dedup.record(&config, "t1", "a", 101);
+        dedup.record(&config, "t2", "a", 105);
+        dedup.expire(config.window_sec, 111);
+        assert_eq!(dedup.remembered_num("t1"), 0);
+        assert_eq!(dedup.remembered_num("t2"), 1);
+        assert_eq!(dedup.list_suppressed(), Vec::new());
+    }
+}
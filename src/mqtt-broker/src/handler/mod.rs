@@ -28,18 +28,23 @@
 pub mod keep_alive;
 pub mod lastwill;
 pub mod message;
+pub mod message_dedup;
 pub mod mqtt;
 pub mod offline_message;
 pub mod response;
 pub mod retain;
 pub mod session;
+pub mod session_restore;
+pub mod session_tiering;
 pub mod sub_auto;
 pub mod sub_exclusive;
 pub mod sub_option;
 pub mod sub_parse_topic;
 pub mod subscribe;
 pub mod topic;
-mod topic_rewrite;
+pub mod topic_cleanup;
+pub mod topic_rewrite;
 pub mod unsubscribe;
 pub mod user;
+pub mod user_session;
 pub mod validator;
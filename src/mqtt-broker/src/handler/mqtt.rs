@@ -28,7 +28,7 @@
 };
 use schema_register::schema::SchemaRegisterManager;
 use storage_adapter::storage::StorageAdapter;
-use tracing::{error, warn};
+use tracing::{debug, error, warn};
 
 use super::connection::{disconnect_connection, is_delete_session};
 use super::delay_message::{decode_delay_topic, is_delay_topic};
@@ -36,15 +36,22 @@
 use super::response::build_pub_ack_fail;
 use super::retain::{is_new_sub, try_send_retain_message};
 use super::sub_auto::try_auto_subscribe;
-use super::subscribe::save_subscribe;
+use super::subscribe::{
+    authorize_subscribe_filters, is_subscribe_rejected, save_subscribe,
+    subscribe_not_authorized_code,
+};
 use super::unsubscribe::remove_subscribe;
 use crate::common::pkid_storage::{pkid_delete, pkid_exists, pkid_save};
 use crate::handler::cache::{
     CacheManager, ConnectionLiveTime, QosAckPackageData, QosAckPackageType,
 };
-use crate::handler::connection::{build_connection, get_client_id};
+use crate::handler::connection::{
+    build_connection, check_duplicate_client_id, get_client_id, takeover_connection,
+    DuplicateClientIdAction,
+};
 use crate::handler::flapping_detect::check_flapping_detect;
-use crate::handler::lastwill::save_last_will_message;
+use crate::handler::lastwill::{check_last_will_acl, save_last_will_message, LastWillAclOutcome};
+use crate::handler::message_dedup::{is_duplicate_message, record_message_id};
 use crate::handler::response::{
     build_puback, build_pubrec, response_packet_mqtt_connect_fail,
     response_packet_mqtt_connect_success, response_packet_mqtt_distinct_by_reason,
@@ -54,13 +61,25 @@
 };
 use crate::handler::session::{build_session, save_session};
 use crate::handler::topic::{get_topic_name, try_init_topic};
+use crate::handler::topic_cleanup::begin_topic_operation;
+use crate::handler::user_session::enforce_user_single_session;
 use crate::handler::validator::{
-    connect_validator, publish_validator, subscribe_validator, un_subscribe_validator,
+    connect_validator, node_draining_check, publish_validator, subscribe_validator,
+    un_subscribe_validator,
+};
+use crate::observability::metrics::auth::{
+    incr_subscribe_auth_counter, SUBSCRIBE_AUTH_OUTCOME_DENIED_FILTER,
+    SUBSCRIBE_AUTH_OUTCOME_REJECTED_PACKET,
 };
+use crate::observability::metrics::publish::{
+    incr_message_dedup_suppressed_counter, incr_publish_rate_limited_counter,
+};
+use crate::observability::sampling::record_message_sample;
 use crate::observability::system_topic::event::{
     st_report_connected_event, st_report_disconnected_event, st_report_subscribed_event,
     st_report_unsubscribed_event,
 };
+use crate::security::login::x509::cert_username_login;
 use crate::security::AuthDriver;
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::common::min_qos;
@@ -121,6 +140,13 @@ pub async fn connect(
     ) -> MqttPacket {
         let cluster = self.cache_manager.get_cluster_config();
 
+        // draining check
+        if let Some(res) =
+            node_draining_check(&self.protocol, &self.cache_manager, connect_properties)
+        {
+            return res;
+        }
+
         // connect params validator
         if let Some(res) = connect_validator(
             &self.protocol,
@@ -136,7 +162,7 @@ pub async fn connect(
 
         // blacklist check
         let (client_id, new_client_id) = get_client_id(&connect.client_id);
-        let connection = build_connection(
+        let mut connection = build_connection(
             connect_id,
             client_id.clone(),
             &cluster,
@@ -154,12 +180,42 @@ pub async fn connect(
             );
         }
 
-        // login check
-        match self
-            .auth_driver
-            .check_login_auth(login, connect_properties, addr)
-            .await
+        // client certificate login
+        let tls_info = self
+            .connection_manager
+            .get_connect(connect_id)
+            .and_then(|network_connection| network_connection.tls_info);
+        let cert_login = match cert_username_login(&cluster.cert_username, tls_info.as_ref(), login)
         {
+            Ok(cert_login) => cert_login,
+            Err(e) => {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::NotAuthorized,
+                    connect_properties,
+                    Some(e.to_string()),
+                );
+            }
+        };
+        let login = if cert_login.is_some() {
+            &cert_login
+        } else {
+            login
+        };
+
+        // login check, a verified client certificate replaces the password
+        let login_result = if cert_login.is_some() {
+            Ok(true)
+        } else {
+            let client_cert = tls_info
+                .as_ref()
+                .map(|tls_info| tls_info.client_cert_subject.as_str())
+                .unwrap_or_default();
+            self.auth_driver
+                .check_login_auth(&mut connection, login, client_cert, connect_properties)
+                .await
+        };
+        match login_result {
             Ok(flag) => {
                 if !flag {
                     return response_packet_mqtt_connect_fail(
@@ -180,11 +236,95 @@ pub async fn connect(
             }
         }
 
+        // the username is known once the login passed, JWT auth may have taken it from the token
+        if connection.login_user.is_empty() {
+            connection.login_user = login
+                .as_ref()
+                .map(|user| user.username.clone())
+                .unwrap_or_default();
+        }
+
+        // will topic check, the client may only leave a will on a topic it may publish to
+        let last_will_acl = check_last_will_acl(
+            &self.cache_manager,
+            &connection,
+            last_will,
+            cluster.will_publish.unauthorized_will_action,
+        );
+        if last_will_acl == LastWillAclOutcome::Reject {
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                ConnectReturnCode::NotAuthorized,
+                connect_properties,
+                Some(format!(
+                    "client {} is not authorized to publish to its will topic",
+                    client_id
+                )),
+            );
+        }
+        let (last_will, last_will_properties) = if last_will_acl == LastWillAclOutcome::Strip {
+            (&None, &None)
+        } else {
+            (last_will, last_will_properties)
+        };
+
         // flapping detect check
         if cluster.flapping_detect.enable {
             check_flapping_detect(connect.client_id.clone(), &self.cache_manager);
         }
 
+        // duplicate client id check
+        match check_duplicate_client_id(&self.cache_manager, &client_id) {
+            DuplicateClientIdAction::Accept => {}
+            DuplicateClientIdAction::Takeover(old_connect_id) => {
+                if let Err(e) = takeover_connection(
+                    &client_id,
+                    old_connect_id,
+                    &self.cache_manager,
+                    &self.client_pool,
+                    &self.connection_manager,
+                    &self.subscribe_manager,
+                )
+                .await
+                {
+                    return response_packet_mqtt_connect_fail(
+                        &self.protocol,
+                        ConnectReturnCode::UnspecifiedError,
+                        connect_properties,
+                        Some(e.to_string()),
+                    );
+                }
+            }
+            DuplicateClientIdAction::Reject => {
+                return response_packet_mqtt_connect_fail(
+                    &self.protocol,
+                    ConnectReturnCode::ClientIdentifierNotValid,
+                    connect_properties,
+                    Some(format!("client id {} is already connected", client_id)),
+                );
+            }
+        }
+
+        // single session per user check
+        let username = connection.login_user.clone();
+        if let Err(e) = enforce_user_single_session(
+            &username,
+            &client_id,
+            &self.cache_manager,
+            &self.client_pool,
+            &self.connection_manager,
+            &self.subscribe_manager,
+        )
+        .await
+        {
+            return response_packet_mqtt_connect_fail(
+                &self.protocol,
+                ConnectReturnCode::UnspecifiedError,
+                connect_properties,
+                Some(e.to_string()),
+            );
+        }
+
         let (session, new_session) = match build_session(
             connect_id,
             client_id.clone(),
@@ -393,6 +533,39 @@ pub async fn publish(
             }
         }
 
+        let publish_rate = self
+            .cache_manager
+            .get_user_publish_rate(&connection.login_user);
+        if !self.cache_manager.publish_rate_limiter.try_acquire(
+            &connection.login_user,
+            publish_rate,
+            now_second(),
+        ) {
+            incr_publish_rate_limited_counter();
+            if publish.qos == QoS::AtMostOnce {
+                return None;
+            }
+            if is_puback {
+                return Some(build_puback(
+                    &self.protocol,
+                    &connection,
+                    publish.pkid,
+                    PubAckReason::QuotaExceeded,
+                    None,
+                    Vec::new(),
+                ));
+            }
+            return Some(build_pubrec(
+                &self.protocol,
+                &connection,
+                publish.pkid,
+                PubRecReason::QuotaExceeded,
+                None,
+                Vec::new(),
+            ));
+        }
+
+        let _topic_guard = begin_topic_operation(&self.cache_manager, &topic_name).await;
         let topic = match try_init_topic(
             &topic_name,
             &self.cache_manager,
@@ -420,12 +593,20 @@ pub async fn publish(
         }
 
         if self.schema_manager.is_check_schema(&topic_name) {
-            if let Err(e) = self.schema_manager.validate(&topic_name, &publish.payload) {
+            let reason = match self.schema_manager.validate(&topic_name, &publish.payload) {
+                Ok(true) => None,
+                Ok(false) => Some(format!(
+                    "payload does not match the schema bound to topic {}",
+                    topic_name
+                )),
+                Err(e) => Some(e.to_string()),
+            };
+            if reason.is_some() {
                 return Some(build_pub_ack_fail(
                     &self.protocol,
                     &connection,
                     publish.pkid,
-                    Some(e.to_string()),
+                    reason,
                     is_puback,
                 ));
             }
@@ -433,36 +614,60 @@ pub async fn publish(
 
         let client_id = connection.client_id.clone();
 
-        // Persisting stores message data
-        let offset = match save_message(
-            &self.message_storage_adapter,
-            &self.delay_message_manager,
-            &self.cache_manager,
-            &self.client_pool,
-            publish,
-            publish_properties,
-            &self.subscribe_manager,
-            &client_id,
-            &topic,
-            &delay_info,
-        )
-        .await
-        {
-            Ok(da) => {
-                format!("{:?}", da)
-            }
-            Err(e) => {
-                return Some(build_pub_ack_fail(
-                    &self.protocol,
-                    &connection,
-                    publish.pkid,
-                    Some(e.to_string()),
-                    is_puback,
-                ))
-            }
-        };
-
-        let user_properties: Vec<(String, String)> = vec![("offset".to_string(), offset)];
+        let mut user_properties: Vec<(String, String)> = Vec::new();
+        if is_duplicate_message(&self.cache_manager, &topic_name, publish_properties) {
+            // The message was already accepted once, so it is acknowledged without being stored
+            incr_message_dedup_suppressed_counter();
+            debug!(
+                "Duplicate message suppressed, client_id: {}, topic_name: {}",
+                client_id, topic_name
+            );
+        } else {
+            // Persisting stores message data
+            let offset = match save_message(
+                &self.message_storage_adapter,
+                &self.delay_message_manager,
+                &self.cache_manager,
+                &self.client_pool,
+                publish,
+                publish_properties,
+                &self.subscribe_manager,
+                &client_id,
+                &topic,
+                &delay_info,
+            )
+            .await
+            {
+                Ok(da) => {
+                    record_message_id(&self.cache_manager, &topic_name, publish_properties);
+                    let sequence = self.cache_manager.next_topic_sequence(&topic_name);
+                    self.cache_manager
+                        .record_topic_payload_size(&topic_name, publish.payload.len() as u64);
+                    self.cache_manager
+                        .record_topic_last_message_at(&topic_name, now_second());
+                    if self.cache_manager.message_sampler.should_sample() {
+                        record_message_sample(
+                            &client_id,
+                            &topic_name,
+                            publish.qos.into(),
+                            publish.payload.len(),
+                            sequence,
+                        );
+                    }
+                    format!("{:?}", da)
+                }
+                Err(e) => {
+                    return Some(build_pub_ack_fail(
+                        &self.protocol,
+                        &connection,
+                        publish.pkid,
+                        Some(e.to_string()),
+                        is_puback,
+                    ))
+                }
+            };
+            user_properties.push(("offset".to_string(), offset));
+        }
 
         self.cache_manager
             .add_topic_alias(connect_id, &topic_name, publish_properties);
@@ -550,6 +755,10 @@ pub async fn publish_rec(
                 .pkid_metadata
                 .get_ack_packet(&client_id, pkid)
             {
+                self.cache_manager
+                    .pkid_metadata
+                    .mark_ack_packet_rec(&client_id, pkid);
+
                 if let Err(e) = data.sx.send(QosAckPackageData {
                     ack_type: QosAckPackageType::PubRec,
                     pkid: pub_rec.pkid,
@@ -682,7 +891,6 @@ pub async fn subscribe(
 
         if let Some(packet) = subscribe_validator(
             &self.protocol,
-            &self.auth_driver,
             &self.cache_manager,
             &self.subscribe_manager,
             &connection,
@@ -693,6 +901,42 @@ pub async fn subscribe(
             return packet;
         }
 
+        let not_authorized_code = subscribe_not_authorized_code(&self.protocol);
+        let allowed = authorize_subscribe_filters(&self.auth_driver, &connection, subscribe).await;
+        let granularity = self.cache_manager.get_subscribe_auth_config().granularity;
+        if is_subscribe_rejected(&granularity, &allowed) {
+            incr_subscribe_auth_counter(SUBSCRIBE_AUTH_OUTCOME_REJECTED_PACKET);
+            return response_packet_mqtt_suback(
+                &self.protocol,
+                &connection,
+                subscribe.packet_identifier,
+                vec![not_authorized_code; subscribe.filters.len()],
+                None,
+            );
+        }
+
+        // Only the authorized filters are subscribed, the others are answered with
+        // a Not authorized return code at their position in the SUBACK.
+        // The QoS of each filter is capped by what the user may be granted.
+        let max_qos = qos(self.cache_manager.get_user_max_qos(&connection.login_user))
+            .unwrap_or(QoS::ExactlyOnce);
+        let authorized_subscribe = Subscribe {
+            packet_identifier: subscribe.packet_identifier,
+            filters: subscribe
+                .filters
+                .iter()
+                .zip(allowed.iter())
+                .filter(|(_, allowed)| **allowed)
+                .map(|(filter, _)| {
+                    let mut filter = filter.clone();
+                    filter.qos = min_qos(max_qos, filter.qos);
+                    filter
+                })
+                .collect(),
+        };
+        let all_filters = &subscribe.filters;
+        let subscribe = &authorized_subscribe;
+
         let new_subs = is_new_sub(&connection.client_id, subscribe, &self.subscribe_manager).await;
 
         if let Err(e) = save_subscribe(
@@ -739,13 +983,13 @@ pub async fn subscribe(
         .await;
 
         let mut return_codes: Vec<SubscribeReasonCode> = Vec::new();
-        let cluster_qos = self
-            .cache_manager
-            .get_cluster_config()
-            .mqtt_protocol_config
-            .max_qos;
-        for filter in subscribe.filters.clone() {
-            match min_qos(qos(cluster_qos).unwrap(), filter.qos) {
+        for (filter, allowed) in all_filters.iter().zip(allowed) {
+            if !allowed {
+                incr_subscribe_auth_counter(SUBSCRIBE_AUTH_OUTCOME_DENIED_FILTER);
+                return_codes.push(not_authorized_code);
+                continue;
+            }
+            match min_qos(max_qos, filter.qos) {
                 QoS::AtMostOnce => {
                     return_codes.push(SubscribeReasonCode::QoS0);
                 }
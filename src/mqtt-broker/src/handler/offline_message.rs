@@ -61,7 +61,7 @@ pub async fn save_message<S>(
         return Ok(None);
     }
 
-    let message_expire = build_message_expire(cache_manager, publish_properties);
+    let message_expire = build_message_expire(cache_manager, client_id, publish_properties);
 
     if delay_info.is_some() {
         return save_delay_message(
@@ -83,11 +83,13 @@ pub async fn save_message<S>(
         client_id,
         publish,
         publish_properties,
+        message_expire,
     )
     .await?;
 
     return save_simple_message(
         message_storage_adapter,
+        subscribe_manager,
         publish,
         publish_properties,
         client_id,
@@ -150,6 +152,7 @@ async fn save_delay_message<S>(
 
 async fn save_simple_message<S>(
     message_storage_adapter: &Arc<S>,
+    subscribe_manager: &Arc<SubscribeManager>,
     publish: &Publish,
     publish_properties: &Option<PublishProperties>,
     client_id: &str,
@@ -166,6 +169,9 @@ async fn save_simple_message<S>(
         let offsets = message_storage
             .append_topic_message(&topic.topic_id, vec![record])
             .await?;
+        if let Some(offset) = offsets.iter().max() {
+            subscribe_manager.record_topic_latest_offset(&topic.topic_id, *offset);
+        }
         return Ok(Some(format!("{:?}", offsets)));
     }
 
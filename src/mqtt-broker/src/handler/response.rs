@@ -184,6 +184,26 @@ pub fn response_packet_mqtt_distinct_by_reason(
     )
 }
 
+// Ask an MQTT 5 client to reconnect to another server, older clients get a plain DISCONNECT
+pub fn response_packet_mqtt_distinct_by_server_reference(
+    protocol: &MqttProtocol,
+    server_reference: &str,
+) -> MqttPacket {
+    if !protocol.is_mqtt5() {
+        return MqttPacket::Disconnect(Disconnect { reason_code: None }, None);
+    }
+
+    MqttPacket::Disconnect(
+        Disconnect {
+            reason_code: Some(DisconnectReasonCode::UseAnotherServer),
+        },
+        Some(DisconnectProperties {
+            server_reference: Some(server_reference.to_owned()),
+            ..Default::default()
+        }),
+    )
+}
+
 pub fn build_puback(
     protocol: &MqttProtocol,
     connection: &MQTTConnection,
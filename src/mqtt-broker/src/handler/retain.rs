@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use super::cache::CacheManager;
-use super::constant::{SUB_RETAIN_MESSAGE_PUSH_FLAG, SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE};
+use super::constant::{
+    SUB_RETAIN_MESSAGE_PUSH_FLAG, SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE,
+    SUB_RETAIN_MESSAGE_TRUNCATED_FLAG,
+};
 use super::error::MqttBrokerError;
-use super::message::build_message_expire;
 use crate::handler::sub_option::{
     get_retain_flag_by_retain_as_published, is_send_msg_by_bo_local,
     is_send_retain_msg_by_retain_handling,
@@ -23,19 +25,26 @@
 use crate::observability::metrics::packets::{
     record_retain_recv_metrics, record_retain_sent_metrics,
 };
+use crate::observability::metrics::retain::{
+    incr_retain_deliver_truncated_counter, incr_retain_oversize_counter,
+    incr_retain_topic_policy_counter, RETAIN_DELIVER_LIMIT_BYTES, RETAIN_DELIVER_LIMIT_NUM,
+    RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED, RETAIN_OVERSIZE_OUTCOME_REJECTED,
+    RETAIN_TOPIC_POLICY_OUTCOME_REJECTED, RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED,
+};
 use crate::server::connection_manager::ConnectionManager;
 use crate::storage::topic::TopicStorage;
 use crate::subscribe::common::Subscriber;
-use crate::subscribe::common::{get_sub_topic_id_list, min_qos};
+use crate::subscribe::common::{get_sub_topic_id_list, is_match_sub_and_topic, min_qos};
 use crate::subscribe::common::{is_ignore_push_error, SubPublishParam};
 use crate::subscribe::manager::SubscribeManager;
 use crate::subscribe::push::send_publish_packet_to_client;
 use bytes::Bytes;
+use common_config::mqtt::config::{RetainMessage, RetainOversizePolicy, RetainTopicAction};
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::message::MqttMessage;
 use protocol::mqtt::common::{
-    qos, MqttPacket, MqttProtocol, Publish, PublishProperties, Subscribe, SubscribeProperties,
+    qos, MqttPacket, MqttProtocol, Publish, PublishProperties, QoS, Subscribe, SubscribeProperties,
 };
 use std::sync::Arc;
 use std::time::Duration;
@@ -65,6 +74,7 @@ pub async fn save_retain_message(
     client_id: &str,
     publish: &Publish,
     publish_properties: &Option<PublishProperties>,
+    message_expire: u64,
 ) -> Result<(), MqttBrokerError> {
     if !publish.retain {
         return Ok(());
@@ -78,8 +88,15 @@ pub async fn save_retain_message(
             .await?;
         cache_manager.update_topic_retain_message(&topic_name, Some(Vec::new()));
     } else {
+        let retain_config = cache_manager.get_retain_message_config();
+        if !is_retain_topic_allowed(&retain_config, &topic_name)? {
+            return Ok(());
+        }
+        if !is_retain_payload_allowed(&retain_config, &topic_name, publish.payload.len())? {
+            return Ok(());
+        }
+
         record_retain_recv_metrics(publish.qos);
-        let message_expire = build_message_expire(cache_manager, publish_properties);
         let retain_message =
             MqttMessage::build_message(client_id, publish, publish_properties, message_expire);
         topic_storage
@@ -92,6 +109,105 @@ pub async fn save_retain_message(
     Ok(())
 }
 
+// Returns whether retained messages are allowed on the topic. On a topic matched by a retain
+// topic policy, the publish is either rejected or accepted without being retained.
+fn is_retain_topic_allowed(
+    retain_config: &RetainMessage,
+    topic_name: &str,
+) -> Result<bool, MqttBrokerError> {
+    let Some(policy) = retain_config
+        .topic_policies
+        .iter()
+        .find(|policy| is_match_sub_and_topic(&policy.topic_filter, topic_name).is_ok())
+    else {
+        return Ok(true);
+    };
+
+    match policy.action {
+        RetainTopicAction::Reject => {
+            incr_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_REJECTED);
+            Err(MqttBrokerError::RetainMessageNotAllowed(
+                topic_name.to_owned(),
+                policy.topic_filter.clone(),
+            ))
+        }
+        RetainTopicAction::Strip => {
+            incr_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED);
+            Ok(false)
+        }
+    }
+}
+
+// The size limit and oversize policy of the topic, taken from the first matching topic size
+// limit, or from the cluster-wide setting when no topic size limit matches.
+fn retain_size_limit<'a>(
+    retain_config: &'a RetainMessage,
+    topic_name: &str,
+) -> (u64, &'a RetainOversizePolicy) {
+    retain_config
+        .topic_size_limits
+        .iter()
+        .find(|limit| is_match_sub_and_topic(&limit.topic_filter, topic_name).is_ok())
+        .map(|limit| (limit.max_payload_size, &limit.oversize_policy))
+        .unwrap_or((
+            retain_config.max_payload_size,
+            &retain_config.oversize_policy,
+        ))
+}
+
+// Returns whether the payload can be retained. When it exceeds the limit of the topic,
+// the publish is either rejected or accepted without being retained, depending on the policy.
+fn is_retain_payload_allowed(
+    retain_config: &RetainMessage,
+    topic_name: &str,
+    payload_size: usize,
+) -> Result<bool, MqttBrokerError> {
+    let (max_payload_size, oversize_policy) = retain_size_limit(retain_config, topic_name);
+    if max_payload_size == 0 || payload_size as u64 <= max_payload_size {
+        return Ok(true);
+    }
+
+    match oversize_policy {
+        RetainOversizePolicy::Reject => {
+            incr_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_REJECTED);
+            Err(MqttBrokerError::RetainMessageExceedsSizeLimit(
+                topic_name.to_owned(),
+                payload_size,
+                max_payload_size,
+            ))
+        }
+        RetainOversizePolicy::AcceptWithoutRetain => {
+            incr_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED);
+            warn!(
+                "Retained message of topic {} exceeds the size limit and will not be retained. Payload size: {}, Limit size: {}",
+                topic_name, payload_size, max_payload_size
+            );
+            Ok(false)
+        }
+    }
+}
+
+// Returns the delivery limit of a SUBSCRIBE that one more retained message would exceed,
+// None when it still fits
+pub fn exceeded_retain_deliver_limit(
+    retain_config: &RetainMessage,
+    delivered_num: u64,
+    delivered_bytes: u64,
+    payload_size: u64,
+) -> Option<&'static str> {
+    if retain_config.max_deliver_num_per_subscribe > 0
+        && delivered_num >= retain_config.max_deliver_num_per_subscribe
+    {
+        return Some(RETAIN_DELIVER_LIMIT_NUM);
+    }
+    if retain_config.max_deliver_bytes_per_subscribe > 0
+        && delivered_bytes + payload_size > retain_config.max_deliver_bytes_per_subscribe
+    {
+        return Some(RETAIN_DELIVER_LIMIT_BYTES);
+    }
+    None
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn try_send_retain_message(
     protocol: MqttProtocol,
@@ -152,7 +268,15 @@ async fn send_retain_message(
         }
     }
 
-    for filter in subscribe.filters.iter() {
+    let retain_config = cache_manager.get_retain_message_config();
+    let mut delivered_num = 0;
+    let mut delivered_bytes = 0;
+    let mut truncated_by = None;
+    // The last accepted message is held back until the next one is known to fit, so that a
+    // truncated delivery can be marked on the last message the client receives
+    let mut pending: Option<PendingRetainMessage> = None;
+
+    'filters: for filter in subscribe.filters.iter() {
         if !is_send_retain_msg_by_retain_handling(
             &filter.path,
             &filter.retain_handling,
@@ -184,72 +308,324 @@ async fn send_retain_message(
                 continue;
             }
 
-            let retain = get_retain_flag_by_retain_as_published(filter.preserve_retain, msg.retain);
-            let qos = min_qos(
-                qos(cluster.mqtt_protocol_config.max_qos).unwrap(),
-                filter.qos,
+            let payload_size = msg.payload.len() as u64;
+            truncated_by = exceeded_retain_deliver_limit(
+                &retain_config,
+                delivered_num,
+                delivered_bytes,
+                payload_size,
             );
+            if truncated_by.is_some() {
+                break 'filters;
+            }
 
-            let mut user_properties = msg.user_properties;
-            user_properties.push((
-                SUB_RETAIN_MESSAGE_PUSH_FLAG.to_string(),
-                SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE.to_string(),
-            ));
-
-            let properties = PublishProperties {
-                payload_format_indicator: msg.format_indicator,
-                message_expiry_interval: Some(msg.expiry_interval as u32),
-                topic_alias: None,
-                response_topic: msg.response_topic,
-                correlation_data: msg.correlation_data,
-                user_properties,
-                subscription_identifiers: sub_ids.clone(),
-                content_type: msg.content_type,
-            };
+            if let Some(message) = pending.take() {
+                send_pending_retain_message(
+                    protocol,
+                    client_id,
+                    &sub_ids,
+                    cache_manager,
+                    connection_manager,
+                    stop_sx,
+                    message,
+                    None,
+                )
+                .await?;
+            }
 
-            let pkid = cache_manager
-                .pkid_metadata
-                .generate_pkid(client_id, &qos)
-                .await;
-
-            let publish = Publish {
-                dup: false,
-                qos,
-                pkid,
-                retain,
-                topic: Bytes::from(topic_name.clone()),
-                payload: msg.payload,
-            };
+            pending = Some(PendingRetainMessage {
+                topic_id: topic_id.to_owned(),
+                topic_name,
+                retain: get_retain_flag_by_retain_as_published(filter.preserve_retain, msg.retain),
+                qos: min_qos(
+                    qos(cluster.mqtt_protocol_config.max_qos).unwrap(),
+                    filter.qos,
+                ),
+                msg,
+            });
+            delivered_num += 1;
+            delivered_bytes += payload_size;
+        }
+    }
 
-            let packet = MqttPacket::Publish(publish.clone(), Some(properties));
+    if let Some(message) = pending {
+        send_pending_retain_message(
+            protocol,
+            client_id,
+            &sub_ids,
+            cache_manager,
+            connection_manager,
+            stop_sx,
+            message,
+            truncated_by,
+        )
+        .await?;
+    }
 
-            let sub_pub_param = SubPublishParam::new(
-                Subscriber {
-                    protocol: protocol.to_owned(),
-                    client_id: client_id.to_string(),
-                    ..Default::default()
-                },
-                packet,
-                msg.create_time as u128,
-                "".to_string(),
-                pkid,
-            );
+    if let Some(limit) = truncated_by {
+        incr_retain_deliver_truncated_counter(limit);
+        warn!(
+            "Retained messages delivered to client {} were truncated by the {} delivery limit after {} messages, {} bytes",
+            client_id, limit, delivered_num, delivered_bytes
+        );
+    }
+    Ok(())
+}
 
-            send_publish_packet_to_client(
-                connection_manager,
-                cache_manager,
-                &sub_pub_param,
-                &qos,
-                stop_sx,
-            )
-            .await?;
-            info!(
-                "retain the successful message sending: client_id: {}, topi_id: {}",
-                client_id, topic_id
-            );
+struct PendingRetainMessage {
+    topic_id: String,
+    topic_name: String,
+    retain: bool,
+    qos: QoS,
+    msg: MqttMessage,
+}
 
-            record_retain_sent_metrics(qos);
-        }
+// Sends one retained message. When the delivery was truncated, the message carries the
+// limit that was hit as a user property, so the client can tell it did not get everything.
+#[allow(clippy::too_many_arguments)]
+async fn send_pending_retain_message(
+    protocol: &MqttProtocol,
+    client_id: &str,
+    sub_ids: &[usize],
+    cache_manager: &Arc<CacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    stop_sx: &broadcast::Sender<bool>,
+    message: PendingRetainMessage,
+    truncated_by: Option<&str>,
+) -> Result<(), MqttBrokerError> {
+    let PendingRetainMessage {
+        topic_id,
+        topic_name,
+        retain,
+        qos,
+        msg,
+    } = message;
+
+    let mut user_properties = msg.user_properties;
+    user_properties.push((
+        SUB_RETAIN_MESSAGE_PUSH_FLAG.to_string(),
+        SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE.to_string(),
+    ));
+    if let Some(limit) = truncated_by {
+        user_properties.push((
+            SUB_RETAIN_MESSAGE_TRUNCATED_FLAG.to_string(),
+            limit.to_string(),
+        ));
     }
+
+    let properties = PublishProperties {
+        payload_format_indicator: msg.format_indicator,
+        message_expiry_interval: Some(msg.expiry_interval as u32),
+        topic_alias: None,
+        response_topic: msg.response_topic,
+        correlation_data: msg.correlation_data,
+        user_properties,
+        subscription_identifiers: sub_ids.to_vec(),
+        content_type: msg.content_type,
+    };
+
+    let pkid = cache_manager
+        .pkid_metadata
+        .generate_pkid(client_id, &qos)
+        .await;
+
+    let publish = Publish {
+        dup: false,
+        qos,
+        pkid,
+        retain,
+        topic: Bytes::from(topic_name),
+        payload: msg.payload,
+    };
+
+    let packet = MqttPacket::Publish(publish, Some(properties));
+
+    let sub_pub_param = SubPublishParam::new(
+        Subscriber {
+            protocol: protocol.to_owned(),
+            client_id: client_id.to_string(),
+            ..Default::default()
+        },
+        packet,
+        msg.create_time as u128,
+        "".to_string(),
+        pkid,
+    );
+
+    send_publish_packet_to_client(
+        connection_manager,
+        cache_manager,
+        &sub_pub_param,
+        &qos,
+        stop_sx,
+    )
+    .await?;
+    info!(
+        "retain the successful message sending: client_id: {}, topi_id: {}",
+        client_id, topic_id
+    );
+
+    record_retain_sent_metrics(qos);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        exceeded_retain_deliver_limit, is_retain_payload_allowed, is_retain_topic_allowed,
+    };
+    use crate::observability::metrics::retain::{
+        get_retain_oversize_counter, get_retain_topic_policy_counter, RETAIN_DELIVER_LIMIT_BYTES,
+        RETAIN_DELIVER_LIMIT_NUM, RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED,
+        RETAIN_OVERSIZE_OUTCOME_REJECTED, RETAIN_TOPIC_POLICY_OUTCOME_REJECTED,
+        RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED,
+    };
+    use common_config::mqtt::config::{
+        RetainMessage, RetainOversizePolicy, RetainTopicAction, RetainTopicPolicy,
+        RetainTopicSizeLimit,
+    };
+
+    #[test]
+    fn retain_payload_within_limit_test() {
+        let config = RetainMessage {
+            max_payload_size: 10,
+            oversize_policy: RetainOversizePolicy::Reject,
+            topic_size_limits: Vec::new(),
+            topic_policies: Vec::new(),
+            max_deliver_num_per_subscribe: 0,
+            max_deliver_bytes_per_subscribe: 0,
+        };
+        assert!(is_retain_payload_allowed(&config, "t1", 10).unwrap());
+
+        let config = RetainMessage {
+            max_payload_size: 0,
+            oversize_policy: RetainOversizePolicy::Reject,
+            topic_size_limits: Vec::new(),
+            topic_policies: Vec::new(),
+            max_deliver_num_per_subscribe: 0,
+            max_deliver_bytes_per_subscribe: 0,
+        };
+        assert!(is_retain_payload_allowed(&config, "t1", 1024).unwrap());
+    }
+
+    #[test]
+    fn retain_payload_oversize_test() {
+        let config = RetainMessage {
+            max_payload_size: 10,
+            oversize_policy: RetainOversizePolicy::Reject,
+            topic_size_limits: Vec::new(),
+            topic_policies: Vec::new(),
+            max_deliver_num_per_subscribe: 0,
+            max_deliver_bytes_per_subscribe: 0,
+        };
+        let rejected = get_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_REJECTED);
+        assert!(is_retain_payload_allowed(&config, "t1", 11).is_err());
+        assert_eq!(
+            get_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_REJECTED),
+            rejected + 1
+        );
+
+        let config = RetainMessage {
+            max_payload_size: 10,
+            oversize_policy: RetainOversizePolicy::AcceptWithoutRetain,
+            topic_size_limits: Vec::new(),
+            topic_policies: Vec::new(),
+            max_deliver_num_per_subscribe: 0,
+            max_deliver_bytes_per_subscribe: 0,
+        };
+        let not_retained = get_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED);
+        assert!(!is_retain_payload_allowed(&config, "t1", 11).unwrap());
+        assert_eq!(
+            get_retain_oversize_counter(RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED),
+            not_retained + 1
+        );
+    }
+
+    #[test]
+    fn retain_payload_topic_size_limit_test() {
+        let config = RetainMessage {
+            max_payload_size: 10,
+            oversize_policy: RetainOversizePolicy::Reject,
+            topic_size_limits: vec![
+                RetainTopicSizeLimit {
+                    topic_filter: "firmware/#".to_string(),
+                    max_payload_size: 100,
+                    oversize_policy: RetainOversizePolicy::AcceptWithoutRetain,
+                },
+                RetainTopicSizeLimit {
+                    topic_filter: "firmware/+/manifest".to_string(),
+                    max_payload_size: 0,
+                    oversize_policy: RetainOversizePolicy::Reject,
+                },
+            ],
+            topic_policies: Vec::new(),
+            max_deliver_num_per_subscribe: 0,
+            max_deliver_bytes_per_subscribe: 0,
+        };
+        assert!(is_retain_payload_allowed(&config, "firmware/d1/image", 100).unwrap());
+        assert!(!is_retain_payload_allowed(&config, "firmware/d1/image", 101).unwrap());
+        assert!(!is_retain_payload_allowed(&config, "firmware/d1/manifest", 101).unwrap());
+        assert!(is_retain_payload_allowed(&config, "device/d1/state", 10).unwrap());
+        assert!(is_retain_payload_allowed(&config, "device/d1/state", 11).is_err());
+    }
+
+    #[test]
+    fn retain_topic_policy_test() {
+        let config = RetainMessage {
+            max_payload_size: 0,
+            oversize_policy: RetainOversizePolicy::Reject,
+            topic_size_limits: Vec::new(),
+            topic_policies: vec![
+                RetainTopicPolicy {
+                    topic_filter: "telemetry/#".to_string(),
+                    action: RetainTopicAction::Strip,
+                },
+                RetainTopicPolicy {
+                    topic_filter: "device/+/live".to_string(),
+                    action: RetainTopicAction::Reject,
+                },
+            ],
+            max_deliver_num_per_subscribe: 0,
+            max_deliver_bytes_per_subscribe: 0,
+        };
+        assert!(is_retain_topic_allowed(&config, "device/d1/state").unwrap());
+
+        let stripped = get_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED);
+        assert!(!is_retain_topic_allowed(&config, "telemetry/d1/cpu").unwrap());
+        assert_eq!(
+            get_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED),
+            stripped + 1
+        );
+
+        let rejected = get_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_REJECTED);
+        assert!(is_retain_topic_allowed(&config, "device/d1/live").is_err());
+        assert_eq!(
+            get_retain_topic_policy_counter(RETAIN_TOPIC_POLICY_OUTCOME_REJECTED),
+            rejected + 1
+        );
+    }
+
+    #[test]
+    fn retain_deliver_limit_test() {
+        let mut config = RetainMessage::default();
+        assert_eq!(
+            exceeded_retain_deliver_limit(&config, 10000, 1 << 30, 1024),
+            None
+        );
+
+        config.max_deliver_num_per_subscribe = 2;
+        assert_eq!(exceeded_retain_deliver_limit(&config, 1, 0, 1024), None);
+        assert_eq!(
+            exceeded_retain_deliver_limit(&config, 2, 0, 1024),
+            Some(RETAIN_DELIVER_LIMIT_NUM)
+        );
+
+        config.max_deliver_num_per_subscribe = 0;
+        config.max_deliver_bytes_per_subscribe = 100;
+        assert_eq!(exceeded_retain_deliver_limit(&config, 5, 60, 40), None);
+        assert_eq!(
+            exceeded_retain_deliver_limit(&config, 5, 60, 41),
+            Some(RETAIN_DELIVER_LIMIT_BYTES)
+        );
+    }
+}
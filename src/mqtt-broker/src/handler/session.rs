@@ -19,10 +19,12 @@
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::session::MqttSession;
 use protocol::mqtt::common::{Connect, ConnectProperties, LastWill, LastWillProperties};
+use tracing::info;
 
 use super::cache::CacheManager;
 use super::error::MqttBrokerError;
 use super::lastwill::last_will_delay_interval;
+use crate::observability::metrics::session::incr_session_takeover_counter;
 use crate::storage::session::SessionStorage;
 
 #[allow(clippy::too_many_arguments)]
@@ -73,6 +75,9 @@ pub async fn build_session(
     session.update_connnction_id(Some(connect_id));
     session.update_broker_id(Some(conf.broker_id));
     session.update_reconnect_time();
+    if !new_session {
+        session.record_takeover(session.reconnect_time.unwrap_or_else(now_second));
+    }
     Ok((session, new_session))
 }
 
@@ -90,9 +95,15 @@ pub async fn save_session(
             .set_session(client_id.clone(), &session)
             .await?;
     } else {
+        let takeover_time = session.last_takeover_at.unwrap_or_else(now_second);
         session_storage
-            .update_session(client_id, connect_id, conf.broker_id, now_second(), 0)
+            .takeover_session(client_id.clone(), connect_id, conf.broker_id, takeover_time)
             .await?;
+        incr_session_takeover_counter(&client_id);
+        info!(
+            "Session of client {} was taken over by connection {}, takeover count: {}",
+            client_id, connect_id, session.takeover_count
+        );
     }
 
     Ok(())
@@ -138,6 +149,9 @@ mod test {
 
     use super::session_expiry_interval;
     use crate::handler::cache::CacheManager;
+    use crate::observability::metrics::session::{
+        get_session_takeover_counter, incr_session_takeover_counter,
+    };
 
     #[tokio::test]
     pub async fn build_session_test() {
@@ -152,6 +166,33 @@ pub async fn build_session_test() {
         assert!(session.broker_id.is_none());
         assert!(session.reconnect_time.is_none());
         assert!(session.distinct_time.is_none());
+        assert_eq!(session.takeover_count, 0);
+        assert!(session.last_takeover_at.is_none());
+    }
+
+    #[test]
+    pub fn session_takeover_test() {
+        let client_id = "session_takeover_test";
+        let mut session = MqttSession::new(client_id.to_string(), 10, false, None);
+
+        session.record_takeover(100);
+        assert_eq!(session.takeover_count, 1);
+        assert_eq!(session.last_takeover_at, Some(100));
+
+        session.record_takeover(160);
+        assert_eq!(session.takeover_count, 2);
+        assert_eq!(session.last_takeover_at, Some(160));
+
+        // Sessions stored before the takeover fields existed still decode
+        let stored = r#"{"client_id":"c1","session_expiry":10,"is_contain_last_will":false,"last_will_delay_interval":null,"create_time":1,"connection_id":null,"broker_id":null,"reconnect_time":null,"distinct_time":null}"#;
+        let session = serde_json::from_str::<MqttSession>(stored).unwrap();
+        assert_eq!(session.takeover_count, 0);
+        assert!(session.last_takeover_at.is_none());
+
+        assert_eq!(get_session_takeover_counter(client_id), 0);
+        incr_session_takeover_counter(client_id);
+        incr_session_takeover_counter(client_id);
+        assert_eq!(get_session_takeover_counter(client_id), 2);
     }
 
     #[test]
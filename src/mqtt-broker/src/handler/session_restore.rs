@@ -0,0 +1,221 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use common_config::mqtt::config::{SessionRestore, SessionRestorePolicy};
+use protocol::mqtt::common::{
+    DisconnectReasonCode, MqttPacket, MqttProtocol, PubAck, PubAckReason, PubRec, PubRecReason,
+    Publish, QoS,
+};
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+use super::cache::CacheManager;
+use super::response::response_packet_mqtt_distinct_by_reason;
+use crate::observability::metrics::session::{
+    incr_session_restore_publish_counter, SESSION_RESTORE_OUTCOME_BUFFERED,
+    SESSION_RESTORE_OUTCOME_REJECTED,
+};
+use crate::server::connection_manager::ConnectionManager;
+
+// Open from the moment a CONNECT is read until it has been handled. Packets of a connection
+// are spread over several handler threads, so a PUBLISH sent right behind the CONNECT could
+// otherwise be handled before the session is ready.
+pub struct SessionRestoreGate {
+    ready: watch::Sender<bool>,
+    buffered_num: AtomicU64,
+}
+
+impl Default for SessionRestoreGate {
+    fn default() -> Self {
+        SessionRestoreGate {
+            ready: watch::Sender::new(false),
+            buffered_num: AtomicU64::new(0),
+        }
+    }
+}
+
+impl SessionRestoreGate {
+    pub fn finish(&self) {
+        self.ready.send_replace(true);
+    }
+
+    // Wait until the CONNECT has been handled. Returns false when the buffer of the
+    // connection is full or the session is not ready within max_wait.
+    pub async fn wait_ready(&self, max_buffered: u64, max_wait: Duration) -> bool {
+        let mut ready = self.ready.subscribe();
+        if *ready.borrow() {
+            return true;
+        }
+
+        let reserved = self
+            .buffered_num
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |num| {
+                (num < max_buffered).then_some(num + 1)
+            })
+            .is_ok();
+        if !reserved {
+            return false;
+        }
+
+        let res = timeout(max_wait, ready.wait_for(|ready| *ready)).await;
+        self.buffered_num.fetch_sub(1, Ordering::AcqRel);
+        matches!(res, Ok(Ok(_)))
+    }
+}
+
+pub enum SessionRestoreCheck {
+    // The connection is not restoring a session
+    NotRestoring,
+    // The publish is rejected with the response, if any
+    Rejected(Option<MqttPacket>),
+}
+
+// Hold a PUBLISH in the read loop of its connection until the CONNECT has been handled, so
+// the shared handler threads never wait for a session. A message that can not be held is
+// passed on and rejected by check_session_restore.
+pub async fn wait_session_restore(
+    cache_manager: &CacheManager,
+    connection_manager: &ConnectionManager,
+    connect_id: u64,
+    packet: &MqttPacket,
+) {
+    if !matches!(packet, MqttPacket::Publish(_, _)) {
+        return;
+    }
+    let Some(gate) = connection_manager.get_session_restore(connect_id) else {
+        return;
+    };
+    let config = cache_manager.get_session_restore_config();
+
+    if is_session_restore_buffered(&gate, &config).await {
+        incr_session_restore_publish_counter(SESSION_RESTORE_OUTCOME_BUFFERED);
+    }
+}
+
+// Reject a PUBLISH whose session is still being restored when it is handled
+pub fn check_session_restore(
+    connection_manager: &ConnectionManager,
+    connect_id: u64,
+    publish: &Publish,
+) -> SessionRestoreCheck {
+    if connection_manager.get_session_restore(connect_id).is_none() {
+        return SessionRestoreCheck::NotRestoring;
+    }
+
+    incr_session_restore_publish_counter(SESSION_RESTORE_OUTCOME_REJECTED);
+    let protocol = connection_manager
+        .get_connect_protocol(connect_id)
+        .unwrap_or(MqttProtocol::Mqtt4);
+    SessionRestoreCheck::Rejected(build_session_restore_reject(&protocol, publish))
+}
+
+async fn is_session_restore_buffered(gate: &SessionRestoreGate, config: &SessionRestore) -> bool {
+    if config.policy == SessionRestorePolicy::Reject {
+        return false;
+    }
+    gate.wait_ready(
+        config.max_buffered_messages,
+        Duration::from_millis(config.max_wait_ms),
+    )
+    .await
+}
+
+// MQTT 3.1.1 can not reject a single message, so those clients are disconnected and
+// resend the message once they have reconnected
+fn build_session_restore_reject(protocol: &MqttProtocol, publish: &Publish) -> Option<MqttPacket> {
+    if publish.qos == QoS::AtMostOnce {
+        return None;
+    }
+
+    if !protocol.is_mqtt5() {
+        return Some(response_packet_mqtt_distinct_by_reason(
+            protocol,
+            Some(DisconnectReasonCode::ServerBusy),
+        ));
+    }
+
+    if publish.qos == QoS::AtLeastOnce {
+        return Some(MqttPacket::PubAck(
+            PubAck {
+                pkid: publish.pkid,
+                reason: Some(PubAckReason::ImplementationSpecificError),
+            },
+            None,
+        ));
+    }
+
+    Some(MqttPacket::PubRec(
+        PubRec {
+            pkid: publish.pkid,
+            reason: Some(PubRecReason::ImplementationSpecificError),
+        },
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use protocol::mqtt::common::{MqttPacket, MqttProtocol, Publish, QoS};
+
+    use super::{build_session_restore_reject, SessionRestoreGate};
+
+    #[tokio::test]
+    async fn wait_ready_test() {
+        let gate = Arc::new(SessionRestoreGate::default());
+        assert!(!gate.wait_ready(1, Duration::from_millis(10)).await);
+        assert!(!gate.wait_ready(0, Duration::from_secs(10)).await);
+
+        let raw_gate = gate.clone();
+        let waiter =
+            tokio::spawn(async move { raw_gate.wait_ready(1, Duration::from_secs(10)).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        gate.finish();
+        assert!(waiter.await.unwrap());
+
+        // once ready the buffer limit no longer applies
+        assert!(gate.wait_ready(0, Duration::from_millis(10)).await);
+    }
+
+    #[test]
+    fn build_session_restore_reject_test() {
+        let mut publish = Publish {
+            qos: QoS::AtMostOnce,
+            pkid: 1,
+            ..Default::default()
+        };
+        assert!(build_session_restore_reject(&MqttProtocol::Mqtt5, &publish).is_none());
+
+        publish.qos = QoS::AtLeastOnce;
+        assert!(matches!(
+            build_session_restore_reject(&MqttProtocol::Mqtt5, &publish),
+            Some(MqttPacket::PubAck(_, _))
+        ));
+        assert!(matches!(
+            build_session_restore_reject(&MqttProtocol::Mqtt4, &publish),
+            Some(MqttPacket::Disconnect(_, _))
+        ));
+
+        publish.qos = QoS::ExactlyOnce;
+        assert!(matches!(
+            build_session_restore_reject(&MqttProtocol::Mqtt5, &publish),
+            Some(MqttPacket::PubRec(_, _))
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::handler::cache::CacheManager;
+use common_base::tools::now_second;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::info;
+
+// Periodically moves idle offline sessions out of memory, see CacheManager::offload_idle_sessions
+pub struct SessionTieringThread {
+    stop_send: broadcast::Sender<bool>,
+    cache_manager: Arc<CacheManager>,
+}
+
+impl SessionTieringThread {
+    pub fn new(stop_send: broadcast::Sender<bool>, cache_manager: Arc<CacheManager>) -> Self {
+        Self {
+            stop_send,
+            cache_manager,
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Session tiering thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.offload()=>{
+                }
+            }
+        }
+    }
+
+    async fn offload(&self) {
+        let config = self.cache_manager.get_session_tiering_config();
+        if config.offload_idle_sec > 0 {
+            let offloaded = self
+                .cache_manager
+                .offload_idle_sessions(config.offload_idle_sec, now_second());
+            if !offloaded.is_empty() {
+                info!(
+                    "Session tiering offloaded {} idle offline sessions",
+                    offloaded.len()
+                );
+            }
+        }
+        sleep(Duration::from_secs(config.check_interval_sec.max(1))).await;
+    }
+}
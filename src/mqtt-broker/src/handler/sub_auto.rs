@@ -42,22 +42,7 @@ pub async fn try_auto_subscribe(
         "".to_string()
     };
 
-    let mut filters: Vec<Filter> = Vec::new();
-    for auto_subscribe_rule in auto_subscribe_rules {
-        let mut path: String = auto_subscribe_rule.topic.clone();
-        path = path.replace("${clientid}", &client_id);
-        if !username.is_empty() {
-            path = path.replace("${username}", &username);
-        }
-
-        filters.push(Filter {
-            path,
-            qos: auto_subscribe_rule.qos,
-            nolocal: auto_subscribe_rule.no_local,
-            preserve_retain: auto_subscribe_rule.retain_as_published,
-            retain_handling: auto_subscribe_rule.retained_handling,
-        });
-    }
+    let filters = build_auto_subscribe_filters(auto_subscribe_rules, &client_id, &username);
 
     if !filters.is_empty() {
         let subscribe: Subscribe = Subscribe {
@@ -86,3 +71,150 @@ pub async fn try_auto_subscribe(
     }
     Ok(())
 }
+
+// Highest priority first, ties going to the older rule
+pub fn sort_auto_subscribe_rules(rules: &mut [MqttAutoSubscribeRule]) {
+    rules.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then(a.create_time.cmp(&b.create_time))
+            .then_with(|| a.topic.cmp(&b.topic))
+    });
+}
+
+// Resolve the rules into subscription filters for one client. When several rules resolve to
+// the same topic filter, the first one in priority order decides its options.
+fn build_auto_subscribe_filters(
+    mut auto_subscribe_rules: Vec<MqttAutoSubscribeRule>,
+    client_id: &str,
+    username: &str,
+) -> Vec<Filter> {
+    sort_auto_subscribe_rules(&mut auto_subscribe_rules);
+
+    let mut filters: Vec<Filter> = Vec::new();
+    for auto_subscribe_rule in auto_subscribe_rules {
+        let mut path: String = auto_subscribe_rule.topic.clone();
+        path = path.replace("${clientid}", client_id);
+        if !username.is_empty() {
+            path = path.replace("${username}", username);
+        }
+
+        if filters.iter().any(|filter| filter.path == path) {
+            continue;
+        }
+
+        filters.push(Filter {
+            path,
+            qos: auto_subscribe_rule.qos,
+            nolocal: auto_subscribe_rule.no_local,
+            preserve_retain: auto_subscribe_rule.retain_as_published,
+            retain_handling: auto_subscribe_rule.retained_handling,
+        });
+    }
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::mqtt::auto_subscribe_rule::MqttAutoSubscribeRule;
+    use protocol::mqtt::common::{QoS, RetainHandling};
+
+    use super::build_auto_subscribe_filters;
+
+    fn rule(topic: &str, qos: QoS, priority: i32, create_time: u64) -> MqttAutoSubscribeRule {
+        MqttAutoSubscribeRule {
+            cluster: "test_cluster".to_string(),
+            topic: topic.to_string(),
+            qos,
+            no_local: false,
+            retain_as_published: false,
+            retained_handling: RetainHandling::OnEverySubscribe,
+            priority,
+            create_time,
+        }
+    }
+
+    fn applied(filters: &[protocol::mqtt::common::Filter]) -> Vec<(String, QoS)> {
+        filters
+            .iter()
+            .map(|filter| (filter.path.clone(), filter.qos))
+            .collect()
+    }
+
+    #[test]
+    fn build_auto_subscribe_filters_test() {
+        let rules = vec![
+            rule("device/${clientid}/cmd", QoS::AtMostOnce, 0, 100),
+            rule("device/c1/cmd", QoS::ExactlyOnce, 10, 300),
+            rule("user/${username}/inbox", QoS::AtLeastOnce, 5, 200),
+            rule("broadcast/#", QoS::AtMostOnce, 0, 50),
+        ];
+        let filters = build_auto_subscribe_filters(rules, "c1", "alice");
+
+        // the higher priority rule decides the QoS of device/c1/cmd
+        assert_eq!(
+            applied(&filters),
+            vec![
+                ("device/c1/cmd".to_string(), QoS::ExactlyOnce),
+                ("user/alice/inbox".to_string(), QoS::AtLeastOnce),
+                ("broadcast/#".to_string(), QoS::AtMostOnce),
+            ]
+        );
+
+        // another client is only matched by the placeholder rule
+        let rules = vec![
+            rule("device/${clientid}/cmd", QoS::AtMostOnce, 0, 100),
+            rule("device/c1/cmd", QoS::ExactlyOnce, 10, 300),
+        ];
+        let filters = build_auto_subscribe_filters(rules, "c2", "");
+        assert_eq!(
+            applied(&filters),
+            vec![
+                ("device/c1/cmd".to_string(), QoS::ExactlyOnce),
+                ("device/c2/cmd".to_string(), QoS::AtMostOnce),
+            ]
+        );
+    }
+
+    #[test]
+    fn build_auto_subscribe_filters_options_test() {
+        let mut options_rule = rule("alerts/${clientid}", QoS::ExactlyOnce, 0, 100);
+        options_rule.no_local = true;
+        options_rule.retain_as_published = true;
+        options_rule.retained_handling = RetainHandling::Never;
+
+        let filters = build_auto_subscribe_filters(vec![options_rule], "c1", "");
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].path, "alerts/c1");
+        assert_eq!(filters[0].qos, QoS::ExactlyOnce);
+        assert!(filters[0].nolocal);
+        assert!(filters[0].preserve_retain);
+        assert_eq!(filters[0].retain_handling, RetainHandling::Never);
+    }
+
+    #[test]
+    fn build_auto_subscribe_filters_tie_test() {
+        // with the same priority the older rule wins
+        let rules = vec![
+            rule("${username}/events", QoS::AtLeastOnce, 1, 200),
+            rule("alice/events", QoS::AtMostOnce, 1, 100),
+            rule("${clientid}/events", QoS::ExactlyOnce, 1, 300),
+        ];
+        let filters = build_auto_subscribe_filters(rules, "alice", "alice");
+        assert_eq!(
+            applied(&filters),
+            vec![("alice/events".to_string(), QoS::AtMostOnce)]
+        );
+
+        // a higher priority beats an older rule
+        let rules = vec![
+            rule("alice/events", QoS::AtMostOnce, 1, 100),
+            rule("${clientid}/events", QoS::ExactlyOnce, 2, 300),
+        ];
+        let filters = build_auto_subscribe_filters(rules, "alice", "");
+        assert_eq!(
+            applied(&filters),
+            vec![("alice/events".to_string(), QoS::ExactlyOnce)]
+        );
+    }
+}
@@ -18,16 +18,19 @@
     tools::now_second,
     utils::topic_util::{decode_exclusive_sub_path_to_topic_name, is_exclusive_sub},
 };
-use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::{broker_mqtt_conf, config::SubscribeAuthGranularity};
 use grpc_clients::{placement::mqtt::call::placement_set_subscribe, pool::ClientPool};
-use metadata_struct::mqtt::{subscribe_data::MqttSubscribe, topic::MqttTopic};
+use metadata_struct::mqtt::{
+    connection::MQTTConnection, subscribe_data::MqttSubscribe, topic::MqttTopic,
+};
 use protocol::{
-    mqtt::common::{Filter, MqttProtocol, Subscribe, SubscribeProperties},
+    mqtt::common::{Filter, MqttProtocol, Subscribe, SubscribeProperties, SubscribeReasonCode},
     placement_center::placement_center_mqtt::SetSubscribeRequest,
 };
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
+use crate::security::AuthDriver;
 use crate::subscribe::{
     common::{
         decode_queue_info, decode_share_info, get_share_sub_leader, is_match_sub_and_topic,
@@ -309,11 +312,41 @@ fn add_exclusive_push(
     Ok(())
 }
 
+// Whether each filter of the SUBSCRIBE passes the ACL, in the order of the filters
+pub async fn authorize_subscribe_filters(
+    auth_driver: &Arc<AuthDriver>,
+    connection: &MQTTConnection,
+    subscribe: &Subscribe,
+) -> Vec<bool> {
+    let mut allowed = Vec::with_capacity(subscribe.filters.len());
+    for filter in subscribe.filters.iter() {
+        allowed.push(auth_driver.allow_subscribe_filter(connection, filter).await);
+    }
+    allowed
+}
+
+pub fn is_subscribe_rejected(granularity: &SubscribeAuthGranularity, allowed: &[bool]) -> bool {
+    match granularity {
+        SubscribeAuthGranularity::PerFilter => !allowed.contains(&true),
+        SubscribeAuthGranularity::AllOrNothing => allowed.contains(&false),
+    }
+}
+
+// MQTT 3.1.1 only knows Failure (0x80) as an error return code in SUBACK
+pub fn subscribe_not_authorized_code(protocol: &MqttProtocol) -> SubscribeReasonCode {
+    if protocol.is_mqtt5() {
+        SubscribeReasonCode::NotAuthorized
+    } else {
+        SubscribeReasonCode::Failure
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::add_exclusive_push;
+    use super::{add_exclusive_push, is_subscribe_rejected};
     use crate::subscribe::manager::SubscribeManager;
     use common_base::tools::unique_id;
+    use common_config::mqtt::config::SubscribeAuthGranularity;
     use metadata_struct::mqtt::topic::MqttTopic;
     use protocol::mqtt::common::{Filter, MqttProtocol};
     use std::sync::Arc;
@@ -361,4 +394,15 @@ fn add_exclusive_push_test() {
             }
         }
     }
+
+    #[test]
+    fn is_subscribe_rejected_test() {
+        let per_filter = SubscribeAuthGranularity::PerFilter;
+        let all_or_nothing = SubscribeAuthGranularity::AllOrNothing;
+
+        assert!(!is_subscribe_rejected(&per_filter, &[true, false]));
+        assert!(is_subscribe_rejected(&per_filter, &[false, false]));
+        assert!(is_subscribe_rejected(&all_or_nothing, &[true, false]));
+        assert!(!is_subscribe_rejected(&all_or_nothing, &[true, true]));
+    }
 }
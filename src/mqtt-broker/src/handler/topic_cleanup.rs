@@ -0,0 +1,753 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::observability::metrics::topic::{
+    incr_topic_cleanup_counter, TOPIC_CLEANUP_OUTCOME_PRUNED,
+    TOPIC_CLEANUP_OUTCOME_SKIPPED_IN_FLIGHT,
+};
+use crate::storage::topic::TopicStorage;
+use crate::subscribe::common::is_match_sub_and_topic;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::tools::now_second;
+use common_config::mqtt::broker_mqtt_conf;
+use futures::future::join_all;
+use grpc_clients::mqtt::inner::call::broker_mqtt_get_topic_activity;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::topic::MqttTopic;
+use metadata_struct::placement::node::BrokerNode;
+use protocol::broker_mqtt::broker_mqtt_inner::{GetTopicActivityRequest, TopicActivityRaw};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::{error, info};
+
+// Marks a publish as in flight on a topic, so the topic cleanup leaves it alone
+pub struct TopicOperationGuard {
+    cache_manager: Arc<CacheManager>,
+    topic_name: String,
+}
+
+impl Drop for TopicOperationGuard {
+    fn drop(&mut self) {
+        self.cache_manager.end_topic_operation(&self.topic_name);
+    }
+}
+
+pub async fn begin_topic_operation(
+    cache_manager: &Arc<CacheManager>,
+    topic_name: &str,
+) -> TopicOperationGuard {
+    // If the topic is being pruned, wait for the deletion to finish so that
+    // the request recreates the topic instead of racing with the cleanup.
+    while !cache_manager.try_begin_topic_operation(topic_name) {
+        sleep(Duration::from_millis(10)).await;
+    }
+    TopicOperationGuard {
+        cache_manager: cache_manager.clone(),
+        topic_name: topic_name.to_owned(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopicCleanupCandidate {
+    pub topic_name: String,
+    pub topic_id: String,
+    pub idle_sec: u64,
+}
+
+pub struct TopicCleanupThread {
+    stop_send: broadcast::Sender<bool>,
+    cache_manager: Arc<CacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+    client_pool: Arc<ClientPool>,
+}
+
+impl TopicCleanupThread {
+    pub fn new(
+        stop_send: broadcast::Sender<bool>,
+        cache_manager: Arc<CacheManager>,
+        subscribe_manager: Arc<SubscribeManager>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        Self {
+            stop_send,
+            cache_manager,
+            subscribe_manager,
+            client_pool,
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Topic cleanup thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.cleanup()=>{
+                }
+            }
+        }
+    }
+
+    async fn cleanup(&self) {
+        let config = self.cache_manager.get_topic_cleanup_config();
+        if config.enable {
+            match prune_idle_topics(
+                &self.cache_manager,
+                &self.subscribe_manager,
+                &self.client_pool,
+                broker_mqtt_conf().broker_id,
+            )
+            .await
+            {
+                Ok(pruned) => {
+                    if !pruned.is_empty() {
+                        info!("Topic cleanup pruned {} idle topics", pruned.len());
+                    }
+                }
+                Err(e) => {
+                    error!("Topic cleanup failed, error message: {}", e);
+                }
+            }
+        }
+        sleep(Duration::from_secs(config.check_interval_sec.max(1))).await;
+    }
+}
+
+// Topics idle on this broker. Before they are pruned, they are also checked against the
+// other brokers with list_cluster_topic_cleanup_candidates.
+pub fn list_topic_cleanup_candidates(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Vec<TopicCleanupCandidate> {
+    let config = cache_manager.get_topic_cleanup_config();
+    let now = now_second();
+
+    let mut results = Vec::new();
+    for entry in cache_manager.topic_info.iter() {
+        let topic = entry.value();
+        let idle_sec = now.saturating_sub(topic_last_active_time(cache_manager, topic));
+        if idle_sec < config.idle_threshold_sec {
+            continue;
+        }
+        if !is_topic_unused(subscribe_manager, topic) {
+            continue;
+        }
+        results.push(TopicCleanupCandidate {
+            topic_name: topic.topic_name.clone(),
+            topic_id: topic.topic_id.clone(),
+            idle_sec,
+        });
+    }
+    results
+}
+
+// Topics idle on every broker of the cluster
+pub async fn list_cluster_topic_cleanup_candidates(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_pool: &Arc<ClientPool>,
+    local_broker_id: u64,
+) -> Result<Vec<TopicCleanupCandidate>, MqttBrokerError> {
+    let config = cache_manager.get_topic_cleanup_config();
+    let candidates = list_topic_cleanup_candidates(cache_manager, subscribe_manager);
+    let reports =
+        peer_topic_activity(cache_manager, client_pool, &candidates, local_broker_id).await?;
+    Ok(retain_cluster_idle_topics(
+        candidates,
+        &reports,
+        now_second(),
+        config.idle_threshold_sec,
+        |report| report.last_active_time,
+    ))
+}
+
+pub async fn prune_idle_topics(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_pool: &Arc<ClientPool>,
+    local_broker_id: u64,
+) -> Result<Vec<TopicCleanupCandidate>, MqttBrokerError> {
+    let config = cache_manager.get_topic_cleanup_config();
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let candidates = list_cluster_topic_cleanup_candidates(
+        cache_manager,
+        subscribe_manager,
+        client_pool,
+        local_broker_id,
+    )
+    .await?;
+
+    let mut pruned = Vec::new();
+    for candidate in candidates {
+        let idle_before = now_second().saturating_sub(config.idle_threshold_sec);
+        if !cache_manager.try_mark_topic_pruning(&candidate.topic_name, idle_before) {
+            incr_topic_cleanup_counter(TOPIC_CLEANUP_OUTCOME_SKIPPED_IN_FLIGHT);
+            continue;
+        }
+
+        // A subscribe or retained message may have arrived between listing and marking. A
+        // subscribe that is saved after this check is bound to the topic again once a
+        // publish recreates it.
+        let topic = match cache_manager.get_topic_by_name(&candidate.topic_name) {
+            Some(topic) if is_topic_unused(subscribe_manager, &topic) => topic,
+            _ => {
+                cache_manager.finish_topic_pruning(&candidate.topic_name, false);
+                continue;
+            }
+        };
+
+        if let Err(e) = topic_storage.delete_topic(topic.topic_name.clone()).await {
+            cache_manager.finish_topic_pruning(&candidate.topic_name, false);
+            return Err(e);
+        }
+        cache_manager.delete_topic(&topic.topic_name, &topic);
+        cache_manager.finish_topic_pruning(&candidate.topic_name, true);
+        incr_topic_cleanup_counter(TOPIC_CLEANUP_OUTCOME_PRUNED);
+        pruned.push(candidate);
+    }
+    Ok(pruned)
+}
+
+// Topics with no subscribers and no message on this broker for idle_sec seconds. Topics
+// holding a retained message are only collected when the caller also drops the retained
+// message.
+pub fn list_gc_topic_candidates(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    idle_sec: u64,
+    delete_retained: bool,
+) -> Vec<TopicCleanupCandidate> {
+    let now = now_second();
+
+    let mut results = Vec::new();
+    for entry in cache_manager.topic_info.iter() {
+        let topic = entry.value();
+        let topic_idle_sec = now.saturating_sub(topic_last_message_time(cache_manager, topic));
+        if topic_idle_sec < idle_sec {
+            continue;
+        }
+        if !is_topic_collectable(subscribe_manager, topic, delete_retained) {
+            continue;
+        }
+        results.push(TopicCleanupCandidate {
+            topic_name: topic.topic_name.clone(),
+            topic_id: topic.topic_id.clone(),
+            idle_sec: topic_idle_sec,
+        });
+    }
+    results.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+    results
+}
+
+// Dead topics on every broker of the cluster, the last message of a topic may have been
+// published on any of them
+pub async fn list_cluster_gc_topic_candidates(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_pool: &Arc<ClientPool>,
+    idle_sec: u64,
+    delete_retained: bool,
+    local_broker_id: u64,
+) -> Result<Vec<TopicCleanupCandidate>, MqttBrokerError> {
+    let candidates =
+        list_gc_topic_candidates(cache_manager, subscribe_manager, idle_sec, delete_retained);
+    let reports =
+        peer_topic_activity(cache_manager, client_pool, &candidates, local_broker_id).await?;
+    Ok(retain_cluster_idle_topics(
+        candidates,
+        &reports,
+        now_second(),
+        idle_sec,
+        |report| report.last_message_at,
+    ))
+}
+
+pub async fn gc_topics(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    client_pool: &Arc<ClientPool>,
+    idle_sec: u64,
+    delete_retained: bool,
+    local_broker_id: u64,
+) -> Result<Vec<TopicCleanupCandidate>, MqttBrokerError> {
+    let topic_storage = TopicStorage::new(client_pool.clone());
+    let candidates = list_cluster_gc_topic_candidates(
+        cache_manager,
+        subscribe_manager,
+        client_pool,
+        idle_sec,
+        delete_retained,
+        local_broker_id,
+    )
+    .await?;
+
+    let mut removed = Vec::new();
+    for candidate in candidates {
+        let idle_before = now_second().saturating_sub(idle_sec);
+        if !cache_manager.try_mark_topic_pruning(&candidate.topic_name, idle_before) {
+            incr_topic_cleanup_counter(TOPIC_CLEANUP_OUTCOME_SKIPPED_IN_FLIGHT);
+            continue;
+        }
+
+        // A subscribe or message may have arrived between listing and marking
+        let topic = match cache_manager.get_topic_by_name(&candidate.topic_name) {
+            Some(topic)
+                if is_topic_collectable(subscribe_manager, &topic, delete_retained)
+                    && now_second()
+                        .saturating_sub(topic_last_message_time(cache_manager, &topic))
+                        >= idle_sec =>
+            {
+                topic
+            }
+            _ => {
+                cache_manager.finish_topic_pruning(&candidate.topic_name, false);
+                continue;
+            }
+        };
+
+        if has_retain_message(&topic) {
+            if let Err(e) = topic_storage
+                .delete_retain_message(topic.topic_name.clone())
+                .await
+            {
+                cache_manager.finish_topic_pruning(&candidate.topic_name, false);
+                return Err(e);
+            }
+        }
+        if let Err(e) = topic_storage.delete_topic(topic.topic_name.clone()).await {
+            cache_manager.finish_topic_pruning(&candidate.topic_name, false);
+            return Err(e);
+        }
+        cache_manager.delete_topic(&topic.topic_name, &topic);
+        cache_manager.finish_topic_pruning(&candidate.topic_name, true);
+        incr_topic_cleanup_counter(TOPIC_CLEANUP_OUTCOME_PRUNED);
+        removed.push(candidate);
+    }
+    Ok(removed)
+}
+
+// How this broker sees the topics, asked by the other brokers before they remove a topic.
+// Like the local checks, activity only counts from the start of this broker.
+pub fn local_topic_activity(
+    cache_manager: &Arc<CacheManager>,
+    topic_names: &[String],
+) -> Vec<TopicActivityRaw> {
+    topic_names
+        .iter()
+        .map(|topic_name| TopicActivityRaw {
+            topic_name: topic_name.clone(),
+            last_active_time: cache_manager
+                .get_topic_last_active_time(topic_name)
+                .unwrap_or(0)
+                .max(cache_manager.start_time),
+            last_message_at: cache_manager
+                .get_topic_last_message_at(topic_name)
+                .max(cache_manager.start_time),
+            in_flight: cache_manager.is_topic_in_flight(topic_name),
+        })
+        .collect()
+}
+
+// Publishes and subscribes are only tracked by the broker handling them, so the topics idle on
+// this broker are checked with every other broker. A broker that cannot be reached fails the
+// whole run, no topic is removed on partial data.
+async fn peer_topic_activity(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    candidates: &[TopicCleanupCandidate],
+    local_broker_id: u64,
+) -> Result<Vec<TopicActivityRaw>, MqttBrokerError> {
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let topic_names: Vec<String> = candidates
+        .iter()
+        .map(|candidate| candidate.topic_name.clone())
+        .collect();
+    let peers: Vec<BrokerNode> = cache_manager
+        .node_list()
+        .into_iter()
+        .filter(|node| node.node_id != local_broker_id)
+        .collect();
+    let replies = join_all(peers.iter().map(|node| {
+        let request = GetTopicActivityRequest {
+            cluster_name: cache_manager.cluster_name.clone(),
+            topic_names: topic_names.clone(),
+        };
+        let addrs = [node.node_inner_addr.clone()];
+        async move { broker_mqtt_get_topic_activity(client_pool, &addrs, request).await }
+    }))
+    .await;
+
+    let mut reports = Vec::new();
+    for (node, reply) in peers.iter().zip(replies) {
+        match reply {
+            Ok(reply) => reports.extend(reply.topics),
+            Err(e) => {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "failed to get the topic activity of broker {}, error message: {}",
+                    node.node_id, e
+                )));
+            }
+        }
+    }
+    Ok(reports)
+}
+
+// Keeps the candidates that every other broker also saw idle for idle_sec seconds, with the
+// idle time counted from the most recent activity in the cluster
+fn retain_cluster_idle_topics(
+    candidates: Vec<TopicCleanupCandidate>,
+    reports: &[TopicActivityRaw],
+    now: u64,
+    idle_sec: u64,
+    last_time: impl Fn(&TopicActivityRaw) -> u64,
+) -> Vec<TopicCleanupCandidate> {
+    candidates
+        .into_iter()
+        .filter_map(|mut candidate| {
+            for report in reports
+                .iter()
+                .filter(|report| report.topic_name == candidate.topic_name)
+            {
+                if report.in_flight {
+                    return None;
+                }
+                candidate.idle_sec = candidate
+                    .idle_sec
+                    .min(now.saturating_sub(last_time(report)));
+            }
+            (candidate.idle_sec >= idle_sec).then_some(candidate)
+        })
+        .collect()
+}
+
+fn topic_last_message_time(cache_manager: &Arc<CacheManager>, topic: &MqttTopic) -> u64 {
+    // Like the activity, the last message time is only tracked since this broker started,
+    // the other brokers report theirs before a topic is removed
+    cache_manager
+        .get_topic_last_message_at(&topic.topic_name)
+        .max(topic.create_time)
+        .max(cache_manager.start_time)
+}
+
+fn topic_last_active_time(cache_manager: &Arc<CacheManager>, topic: &MqttTopic) -> u64 {
+    // Activity is only tracked since this broker started
+    cache_manager
+        .get_topic_last_active_time(&topic.topic_name)
+        .unwrap_or(0)
+        .max(topic.create_time)
+        .max(cache_manager.start_time)
+}
+
+fn is_topic_unused(subscribe_manager: &Arc<SubscribeManager>, topic: &MqttTopic) -> bool {
+    // System topics are owned by the broker
+    if topic.topic_name.starts_with('$') {
+        return false;
+    }
+
+    if topic.retain_message.is_some() {
+        return false;
+    }
+
+    !has_topic_subscriber(subscribe_manager, topic)
+}
+
+fn is_topic_collectable(
+    subscribe_manager: &Arc<SubscribeManager>,
+    topic: &MqttTopic,
+    delete_retained: bool,
+) -> bool {
+    if topic.topic_name.starts_with('$') {
+        return false;
+    }
+
+    if !delete_retained && has_retain_message(topic) {
+        return false;
+    }
+
+    !has_topic_subscriber(subscribe_manager, topic)
+}
+
+// A cleared retained message is kept in the cache as an empty payload
+fn has_retain_message(topic: &MqttTopic) -> bool {
+    topic
+        .retain_message
+        .as_ref()
+        .is_some_and(|message| !message.is_empty())
+}
+
+fn has_topic_subscriber(subscribe_manager: &Arc<SubscribeManager>, topic: &MqttTopic) -> bool {
+    if subscribe_manager
+        .subscribe_list
+        .iter()
+        .any(|entry| is_match_sub_and_topic(&entry.path, &topic.topic_name).is_ok())
+    {
+        return true;
+    }
+
+    let topic_id = &topic.topic_id;
+    subscribe_manager
+        .exclusive_push
+        .iter()
+        .any(|entry| entry.topic_id == *topic_id)
+        || subscribe_manager
+            .share_leader_push
+            .iter()
+            .any(|entry| entry.topic_id == *topic_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::tools::now_second;
+    use common_config::mqtt::config::{BrokerMqttConfig, TopicCleanup};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use metadata_struct::mqtt::topic::MqttTopic;
+    use protocol::broker_mqtt::broker_mqtt_inner::TopicActivityRaw;
+
+    use super::{
+        begin_topic_operation, gc_topics, list_gc_topic_candidates, list_topic_cleanup_candidates,
+        local_topic_activity, retain_cluster_idle_topics, TopicCleanupCandidate,
+    };
+    use crate::handler::cache::CacheManager;
+    use crate::subscribe::manager::SubscribeManager;
+
+    fn build_cache_manager() -> Arc<CacheManager> {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(BrokerMqttConfig::default());
+        cache_manager.update_topic_cleanup_config(TopicCleanup {
+            enable: true,
+            idle_threshold_sec: 0,
+            check_interval_sec: 1,
+        });
+        cache_manager
+    }
+
+    fn add_topic(cache_manager: &Arc<CacheManager>, topic_name: &str) -> MqttTopic {
+        let topic = MqttTopic::new(
+            format!("{}_id", topic_name),
+            "test".to_string(),
+            topic_name.to_string(),
+        );
+        cache_manager.add_topic(topic_name, &topic);
+        topic
+    }
+
+    #[tokio::test]
+    async fn list_topic_cleanup_candidates_test() {
+        let cache_manager = build_cache_manager();
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        add_topic(&cache_manager, "/idle");
+        add_topic(&cache_manager, "/subscribed/a");
+        let mut retained = add_topic(&cache_manager, "/retained");
+        retained.retain_message = Some(b"data".to_vec());
+        cache_manager.add_topic("/retained", &retained);
+        add_topic(&cache_manager, "$SYS/brokers");
+
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: "/subscribed/+".to_string(),
+            ..Default::default()
+        });
+
+        let candidates = list_topic_cleanup_candidates(&cache_manager, &subscribe_manager);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].topic_name, "/idle");
+    }
+
+    #[tokio::test]
+    async fn topic_in_flight_not_pruned_test() {
+        let cache_manager = build_cache_manager();
+        add_topic(&cache_manager, "/busy");
+
+        let guard = begin_topic_operation(&cache_manager, "/busy").await;
+        assert!(!cache_manager.try_mark_topic_pruning("/busy", now_second()));
+        drop(guard);
+
+        assert!(cache_manager.try_mark_topic_pruning("/busy", now_second()));
+        assert!(!cache_manager.try_begin_topic_operation("/busy"));
+        cache_manager.finish_topic_pruning("/busy", false);
+        assert!(cache_manager.try_begin_topic_operation("/busy"));
+    }
+
+    #[tokio::test]
+    async fn gc_topics_dry_run_test() {
+        let cache_manager = build_cache_manager();
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        add_topic(&cache_manager, "/dead/b");
+        add_topic(&cache_manager, "/dead/a");
+        let mut retained = add_topic(&cache_manager, "/retained");
+        retained.retain_message = Some(b"data".to_vec());
+        cache_manager.add_topic("/retained", &retained);
+
+        let candidates = list_gc_topic_candidates(&cache_manager, &subscribe_manager, 0, false);
+        let names: Vec<&str> = candidates.iter().map(|c| c.topic_name.as_str()).collect();
+        assert_eq!(names, vec!["/dead/a", "/dead/b"]);
+
+        let candidates = list_gc_topic_candidates(&cache_manager, &subscribe_manager, 0, true);
+        let names: Vec<&str> = candidates.iter().map(|c| c.topic_name.as_str()).collect();
+        assert_eq!(names, vec!["/dead/a", "/dead/b", "/retained"]);
+
+        // Listing leaves the topics in place
+        assert_eq!(cache_manager.topic_info.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gc_topics_protect_active_test() {
+        // Pretend the broker and its topics have been up for an hour
+        let client_pool = Arc::new(ClientPool::new(1));
+        let mut cache_manager = CacheManager::new(client_pool.clone(), "test".to_string());
+        cache_manager.start_time = now_second() - 3600;
+        let cache_manager = Arc::new(cache_manager);
+        cache_manager.set_cluster_config(BrokerMqttConfig::default());
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        for topic_name in ["/stale", "/recent", "/subscribed/a", "$SYS/brokers"] {
+            let mut topic = add_topic(&cache_manager, topic_name);
+            topic.create_time = now_second() - 3600;
+            cache_manager.add_topic(topic_name, &topic);
+        }
+        cache_manager.record_topic_last_message_at("/recent", now_second());
+        subscribe_manager.add_subscribe(MqttSubscribe {
+            client_id: "c1".to_string(),
+            path: "/subscribed/+".to_string(),
+            ..Default::default()
+        });
+
+        let candidates = list_gc_topic_candidates(&cache_manager, &subscribe_manager, 60, true);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].topic_name, "/stale");
+        assert!(candidates[0].idle_sec >= 3600);
+
+        // The last message is recent enough for a longer idle duration
+        assert!(
+            list_gc_topic_candidates(&cache_manager, &subscribe_manager, 7200, true).is_empty()
+        );
+        let removed = gc_topics(
+            &cache_manager,
+            &subscribe_manager,
+            &client_pool,
+            7200,
+            true,
+            1,
+        )
+        .await
+        .unwrap();
+        assert!(removed.is_empty());
+        assert_eq!(cache_manager.topic_info.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn gc_topics_skip_in_flight_test() {
+        let cache_manager = build_cache_manager();
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        let client_pool = Arc::new(ClientPool::new(1));
+        add_topic(&cache_manager, "/busy");
+
+        let guard = begin_topic_operation(&cache_manager, "/busy").await;
+        let removed = gc_topics(
+            &cache_manager,
+            &subscribe_manager,
+            &client_pool,
+            0,
+            false,
+            1,
+        )
+        .await
+        .unwrap();
+        assert!(removed.is_empty());
+        assert!(cache_manager.topic_exists("/busy"));
+        drop(guard);
+    }
+
+    #[test]
+    fn retain_cluster_idle_topics_test() {
+        let candidate = |topic_name: &str| TopicCleanupCandidate {
+            topic_name: topic_name.to_string(),
+            topic_id: format!("{}_id", topic_name),
+            idle_sec: 500,
+        };
+        let report = |topic_name: &str, last_active_time: u64, in_flight: bool| TopicActivityRaw {
+            topic_name: topic_name.to_string(),
+            last_active_time,
+            last_message_at: 0,
+            in_flight,
+        };
+        let reports = vec![
+            report("/idle", 400, false),
+            report("/active", 950, false),
+            report("/busy", 100, true),
+        ];
+
+        let kept = retain_cluster_idle_topics(
+            vec![
+                candidate("/idle"),
+                candidate("/active"),
+                candidate("/busy"),
+                candidate("/unknown"),
+            ],
+            &reports,
+            1000,
+            300,
+            |report| report.last_active_time,
+        );
+        let names: Vec<&str> = kept.iter().map(|c| c.topic_name.as_str()).collect();
+        assert_eq!(names, vec!["/idle", "/unknown"]);
+        // The idle time counts from the most recent activity in the cluster
+        assert_eq!(kept[0].idle_sec, 500);
+        assert_eq!(kept[1].idle_sec, 500);
+
+        let kept = retain_cluster_idle_topics(
+            vec![candidate("/idle")],
+            &[report("/idle", 600, false)],
+            1000,
+            300,
+            |report| report.last_active_time,
+        );
+        assert_eq!(kept[0].idle_sec, 400);
+    }
+
+    #[tokio::test]
+    async fn local_topic_activity_test() {
+        let cache_manager = build_cache_manager();
+        add_topic(&cache_manager, "/busy");
+        let guard = begin_topic_operation(&cache_manager, "/busy").await;
+
+        let topics = vec!["/busy".to_string(), "/unknown".to_string()];
+        let activity = local_topic_activity(&cache_manager, &topics);
+        assert_eq!(activity.len(), 2);
+        assert!(activity[0].in_flight);
+        assert!(!activity[1].in_flight);
+        // Topics without activity report the start of the broker
+        assert_eq!(activity[1].last_active_time, cache_manager.start_time);
+        drop(guard);
+    }
+}
@@ -42,10 +42,33 @@ fn gen_convert_rewrite_name(
     cache_manager: &Arc<CacheManager>,
     name: &str,
 ) -> Result<Option<String>, MqttBrokerError> {
-    let mut rules: Vec<MqttTopicRewriteRule> = cache_manager.get_all_topic_rewrite_rule();
+    let rules = cache_manager.get_all_topic_rewrite_rule();
+    let regex = |pattern: &str| cache_manager.get_topic_rewrite_regex(pattern);
+    let Some((_, new_topic_name)) = rewrite_by_rules(rules, name, regex)? else {
+        return Ok(None);
+    };
+    info!(
+        "topic rewriteconvert topic name: {} to {}",
+        name, new_topic_name
+    );
+    Ok(Some(new_topic_name))
+}
+
+// Rewrite a name with the given rules, returning the rule that decided the result and the new
+// name. The rules are applied in the order they were created and the last rule whose source
+// topic matches wins. Publish, subscribe and the admin dry run all rewrite through here.
+// The compiled regex of a rule is looked up with regex.
+pub fn rewrite_by_rules<F>(
+    mut rules: Vec<MqttTopicRewriteRule>,
+    name: &str,
+    regex: F,
+) -> Result<Option<(MqttTopicRewriteRule, String)>, MqttBrokerError>
+where
+    F: Fn(&str) -> Result<Regex, MqttBrokerError>,
+{
     rules.sort_by_key(|rule| rule.timestamp);
-    let mut new_topic_name = "".to_string();
-    for rule in rules.iter() {
+    let mut rewritten = None;
+    for rule in rules {
         let allow = rule.action != TopicRewriteActionEnum::All.to_string()
             || rule.action != TopicRewriteActionEnum::Publish.to_string();
 
@@ -54,39 +77,79 @@ fn gen_convert_rewrite_name(
         }
 
         if is_match_sub_and_topic(&rule.source_topic, name).is_ok() {
-            new_topic_name = gen_rewrite_topic(name, &rule.regex, &rule.dest_topic)?;
+            let new_topic_name = gen_rewrite_topic(name, &regex(&rule.regex)?, &rule.dest_topic)?;
+            rewritten = Some((rule, new_topic_name));
         }
     }
 
-    if new_topic_name.is_empty() {
-        return Ok(None);
+    Ok(rewritten.filter(|(_, new_topic_name)| !new_topic_name.is_empty()))
+}
+
+// Compile the regex of a rule and check that the destination topic only uses capture groups
+// the regex has, so that a broken rule is rejected when it is created
+pub fn build_topic_rewrite_regex(pattern: &str, template: &str) -> Result<Regex, MqttBrokerError> {
+    let regex = Regex::new(pattern).map_err(|e| {
+        MqttBrokerError::CommonError(format!("invalid topic rewrite regex {}: {}", pattern, e))
+    })?;
+
+    let group_num = regex.captures_len() - 1;
+    for group in template_capture_groups(template) {
+        if group == 0 || group > group_num {
+            return Err(MqttBrokerError::CommonError(format!(
+                "dest_topic {} references ${}, but regex {} has {} capture groups numbered from $1",
+                template, group, pattern, group_num
+            )));
+        }
     }
-    info!(
-        "topic rewriteconvert topic name: {} to {}",
-        name, new_topic_name
-    );
-    Ok(Some(new_topic_name))
+    Ok(regex)
+}
+
+// The $N references of a destination topic
+fn template_capture_groups(template: &str) -> Vec<usize> {
+    let mut groups = Vec::new();
+    let mut rest = template;
+    while let Some(index) = rest.find('$') {
+        rest = &rest[index + 1..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if !digits.is_empty() {
+            groups.push(digits.parse().unwrap_or(usize::MAX));
+        }
+    }
+    groups
 }
 
-fn gen_rewrite_topic(
-    input: &str,
-    pattern: &str,
-    template: &str,
-) -> Result<String, MqttBrokerError> {
-    let prefix = String::new();
+// Replaces every $N of the template with capture group N. The whole number after $ names the
+// group, so $12 is never read as $1 followed by 2, and a group that did not take part in the
+// match is replaced with nothing.
+fn gen_rewrite_topic(input: &str, re: &Regex, template: &str) -> Result<String, MqttBrokerError> {
     let topic = decode_sub_path(input);
-    let re = Regex::new(pattern)?;
-    let mut rewrite_topic = template.to_string();
-    if let Some(captures) = re.captures(topic.as_str()) {
-        for (i, capture) in captures.iter().skip(1).enumerate() {
-            let prefix = format!("${}", (i + 1)).to_string();
-            rewrite_topic = rewrite_topic
-                .replace(&prefix, capture.unwrap().as_str())
-                .clone();
+    let Some(captures) = re.captures(topic.as_str()) else {
+        return Ok(input.to_owned());
+    };
+
+    let mut rewrite_topic = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(index) = rest.find('$') {
+        rewrite_topic.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        let digit_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digit_len == 0 {
+            rewrite_topic.push('$');
+            continue;
         }
-        return Ok(format!("{}{}", prefix, rewrite_topic));
+        let group = rest[..digit_len]
+            .parse::<usize>()
+            .ok()
+            .and_then(|group| captures.get(group));
+        if let Some(group) = group {
+            rewrite_topic.push_str(group.as_str());
+        }
+        rest = &rest[digit_len..];
     }
-    Ok(input.to_owned())
+    rewrite_topic.push_str(rest);
+    Ok(rewrite_topic)
 }
 
 #[cfg(test)]
@@ -157,7 +220,8 @@ async fn gen_rewrite_topic_test() {
             rules.sort_by_key(|rule| rule.timestamp);
             for rule in rules.iter() {
                 if is_match_sub_and_topic(&rule.source_topic, input).is_ok() {
-                    let rewrite_topic = gen_rewrite_topic(input, &rule.regex, &rule.dest_topic);
+                    let regex = Regex::new(&rule.regex).unwrap();
+                    let rewrite_topic = gen_rewrite_topic(input, &regex, &rule.dest_topic);
                     assert!(rewrite_topic.is_ok());
                     t1 = rewrite_topic.unwrap();
                 }
@@ -180,6 +244,148 @@ async fn gen_convert_rewrite_name_test() {
         }
     }
 
+    fn rule(source: &str, destination: &str, regex: &str, timestamp: u128) -> MqttTopicRewriteRule {
+        MqttTopicRewriteRule {
+            cluster: "default".to_string(),
+            action: TopicRewriteActionEnum::All.to_string(),
+            source_topic: source.to_string(),
+            dest_topic: destination.to_string(),
+            regex: regex.to_string(),
+            timestamp,
+        }
+    }
+
+    fn compile(pattern: &str) -> Result<Regex, MqttBrokerError> {
+        Ok(Regex::new(pattern)?)
+    }
+
+    #[tokio::test]
+    async fn rewrite_by_rules_test() {
+        let cache_manager = build_rules().await;
+
+        // a matching rule
+        let (rule, topic) = rewrite_by_rules(
+            cache_manager.get_all_topic_rewrite_rule(),
+            "y/a/z/b",
+            compile,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(rule.source_topic, "y/+/z/#");
+        assert_eq!(topic, "y/z/b");
+
+        // no rule matches, the topic is published unchanged
+        assert!(
+            rewrite_by_rules(cache_manager.get_all_topic_rewrite_rule(), "y/def", compile)
+                .unwrap()
+                .is_none()
+        );
+
+        // x/# and x/y/+ overlap, the rule created last decides
+        let (rule, topic) =
+            rewrite_by_rules(cache_manager.get_all_topic_rewrite_rule(), "x/y/2", compile)
+                .unwrap()
+                .unwrap();
+        assert_eq!(rule.source_topic, "x/y/+");
+        assert_eq!(topic, "z/y/2");
+
+        // the deciding rule does not capture, the earlier rule is not tried again
+        let (rule, topic) =
+            rewrite_by_rules(cache_manager.get_all_topic_rewrite_rule(), "x/y/z", compile)
+                .unwrap()
+                .unwrap();
+        assert_eq!(rule.source_topic, "x/y/+");
+        assert_eq!(topic, "x/y/z");
+
+        // the dry run agrees with the publish path
+        for src_topic in SRC_TOPICS {
+            let rewritten = rewrite_by_rules(
+                cache_manager.get_all_topic_rewrite_rule(),
+                src_topic,
+                compile,
+            )
+            .unwrap()
+            .map(|(_, topic)| topic);
+            assert_eq!(
+                rewritten,
+                convert_publish_topic_by_rewrite_rule(&cache_manager, src_topic).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn rewrite_by_rules_creation_order_test() {
+        // the order of the list does not matter, only the time the rules were created
+        let rules = vec![
+            rule(r"x/y/+", r"z/y/$1", r"^x/y/(\d+)$", 2),
+            rule(r"x/#", r"z/y/x/$1", r"^x/y/(.+)$", 1),
+        ];
+        let (rule, topic) = rewrite_by_rules(rules, "x/y/2", compile).unwrap().unwrap();
+        assert_eq!(rule.source_topic, "x/y/+");
+        assert_eq!(topic, "z/y/2");
+    }
+
+    #[test]
+    fn build_topic_rewrite_regex_test() {
+        // a valid rule
+        let regex = build_topic_rewrite_regex(r"^y/(.+)/z/(.+)$", r"y/z/$2").unwrap();
+        assert!(regex.is_match("y/a/z/b"));
+        assert!(build_topic_rewrite_regex(r"^x/y/(\d+)$", r"z/y/$1").is_ok());
+        assert!(build_topic_rewrite_regex(r"^x/y/z$", r"z/y/x").is_ok());
+
+        // an invalid regex
+        let err = build_topic_rewrite_regex(r"^x/(y/z$", r"z/$1").unwrap_err();
+        assert!(err.to_string().contains("invalid topic rewrite regex"));
+
+        // a template with a dangling capture group
+        let err = build_topic_rewrite_regex(r"^x/y/(\d+)$", r"z/$1/$2").unwrap_err();
+        assert!(err.to_string().contains("$2"));
+        assert!(build_topic_rewrite_regex(r"^x/y/(\d+)$", r"z/$0").is_err());
+    }
+
+    #[test]
+    fn gen_rewrite_topic_groups_test() {
+        let re = Regex::new(r"^(a)/(b)/(c)/(d)/(e)/(f)/(g)/(h)/(i)/(j)/(k)/(l)$").unwrap();
+        assert_eq!(
+            gen_rewrite_topic("a/b/c/d/e/f/g/h/i/j/k/l", &re, "$12/$1/$10").unwrap(),
+            "l/a/j"
+        );
+
+        // an optional group that did not match is replaced with nothing
+        let re = Regex::new(r"^x/(a)?(.+)$").unwrap();
+        assert_eq!(gen_rewrite_topic("x/b", &re, "y/$1$2").unwrap(), "y/b");
+        assert_eq!(gen_rewrite_topic("x/ab", &re, "y/$1/$2").unwrap(), "y/a/b");
+
+        // a $ without a number is kept
+        assert_eq!(gen_rewrite_topic("x/ab", &re, "y/$/$1").unwrap(), "y/$/a");
+
+        // a topic the regex does not match is not rewritten
+        assert_eq!(gen_rewrite_topic("z/ab", &re, "y/$1").unwrap(), "z/ab");
+    }
+
+    #[test]
+    fn template_capture_groups_test() {
+        assert_eq!(template_capture_groups("z/y/x"), Vec::<usize>::new());
+        assert_eq!(template_capture_groups("y/z/$2"), vec![2]);
+        assert_eq!(template_capture_groups("$1/$12/$"), vec![1, 12]);
+    }
+
+    #[test]
+    fn topic_rewrite_regex_cache_test() {
+        let cache_manager = CacheManager::new(Arc::new(ClientPool::new(1)), unique_id());
+        let rule = rule(r"x/y/+", r"z/y/$1", r"^x/y/(\d+)$", 1);
+        cache_manager.add_topic_rewrite_rule(rule.clone());
+        assert!(cache_manager.topic_rewrite_regex.contains_key(&rule.regex));
+        assert!(cache_manager.get_topic_rewrite_regex(&rule.regex).is_ok());
+
+        cache_manager.delete_topic_rewrite_rule(
+            &cache_manager.cluster_name,
+            &rule.action,
+            &rule.source_topic,
+        );
+        assert!(!cache_manager.topic_rewrite_regex.contains_key(&rule.regex));
+    }
+
     async fn build_rules() -> Arc<CacheManager> {
         let rules = vec![
             SimpleRule::new(r"y/+/z/#", r"y/z/$2", r"^y/(.+)/z/(.+)$"),
@@ -15,6 +15,7 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use common_base::tools::now_second;
 use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::user::MqttUser;
@@ -77,6 +78,7 @@ pub async fn init_system_user(cache_manager: &Arc<CacheManager>, client_pool: &A
         username: conf.system.default_user.clone(),
         password: conf.system.default_password.clone(),
         is_superuser: true,
+        create_time: now_second(),
     };
     let user_storage = UserStorage::new(client_pool.clone());
     match user_storage.save_user(system_user_info.clone()).await {
@@ -0,0 +1,276 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::mqtt::inner::call::broker_mqtt_kick_user_session;
+use grpc_clients::pool::ClientPool;
+use protocol::broker_mqtt::broker_mqtt_inner::KickUserSessionRequest;
+use tracing::{info, warn};
+
+use super::cache::CacheManager;
+use super::connection::takeover_connection;
+use super::error::MqttBrokerError;
+use crate::observability::metrics::session::{
+    incr_user_session_kick_counter, USER_SESSION_KICK_SCOPE_CROSS_NODE,
+    USER_SESSION_KICK_SCOPE_LOCAL,
+};
+use crate::server::connection_manager::ConnectionManager;
+use crate::storage::user_session::{UserSessionOwner, UserSessionStorage};
+use crate::subscribe::manager::SubscribeManager;
+
+// Makes the connecting client the only session of its user in the cluster. Sessions of the user
+// on this broker are taken over directly, a session on another broker is taken over by that
+// broker on request. Every connected session of a user is recorded in the placement center, so
+// enabling this adds a write and a prefix read to it, plus one call to every other broker the
+// user was connected to, to every connect of the user.
+pub async fn enforce_user_single_session(
+    username: &str,
+    client_id: &str,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<(), MqttBrokerError> {
+    if !cache_manager.is_user_single_session_enforced(username) {
+        return Ok(());
+    }
+
+    let local_kicked_num = kick_local_user_sessions(
+        username,
+        client_id,
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+    )
+    .await?;
+    for _ in 0..local_kicked_num {
+        incr_user_session_kick_counter(USER_SESSION_KICK_SCOPE_LOCAL);
+    }
+
+    // The session is stored before the others are read. Of two clients of the user connecting
+    // at the same time, at least the one storing last sees the other one, and both agree on
+    // which of them is newer.
+    let broker_id = broker_mqtt_conf().broker_id;
+    let storage = UserSessionStorage::new(client_pool.clone());
+    let session = UserSessionOwner::new(username.to_owned(), client_id.to_owned(), broker_id);
+    storage.save_session(&session).await?;
+
+    if let Err(e) = replace_user_sessions(&storage, &session, cache_manager, client_pool).await {
+        // The connection is refused, its record must not stay behind
+        if let Err(delete_err) = storage.delete_session(&session).await {
+            warn!(
+                "Failed to remove the session of client {} of user {}, error message: {}",
+                client_id, username, delete_err
+            );
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+// Takes over the other sessions of the user, unless one of them is newer
+async fn replace_user_sessions(
+    storage: &UserSessionStorage,
+    session: &UserSessionOwner,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<(), MqttBrokerError> {
+    let stored = storage.list_sessions(&session.username).await?;
+    let superseded = superseded_user_sessions(session, stored).map_err(|newer| {
+        MqttBrokerError::UserSessionSuperseded(session.username.clone(), newer.client_id)
+    })?;
+
+    let mut kicked_brokers = HashSet::new();
+    for other in superseded {
+        if other.broker_id != session.broker_id && kicked_brokers.insert(other.broker_id) {
+            kick_remote_user_session(
+                &session.username,
+                &session.client_id,
+                &other,
+                cache_manager,
+                client_pool,
+            )
+            .await?;
+        }
+        storage.delete_session(&other).await?;
+    }
+    Ok(())
+}
+
+// Forgets the session of a client of the user that disconnected
+pub async fn release_user_session(
+    username: &str,
+    client_id: &str,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) {
+    if username.is_empty() || !cache_manager.is_user_single_session_enforced(username) {
+        return;
+    }
+
+    let session = UserSessionOwner::new(
+        username.to_owned(),
+        client_id.to_owned(),
+        broker_mqtt_conf().broker_id,
+    );
+    let storage = UserSessionStorage::new(client_pool.clone());
+    if let Err(e) = storage.delete_session(&session).await {
+        warn!(
+            "Failed to remove the session of client {} of user {}, error message: {}",
+            client_id, username, e
+        );
+    }
+}
+
+// The stored sessions the connecting session replaces, or the newer session it lost to
+fn superseded_user_sessions(
+    session: &UserSessionOwner,
+    stored: Vec<UserSessionOwner>,
+) -> Result<Vec<UserSessionOwner>, UserSessionOwner> {
+    let mut superseded = Vec::with_capacity(stored.len());
+    for other in stored {
+        if other.broker_id == session.broker_id && other.client_id == session.client_id {
+            continue;
+        }
+        if other.is_newer_than(session) {
+            return Err(other);
+        }
+        superseded.push(other);
+    }
+    Ok(superseded)
+}
+
+async fn kick_remote_user_session(
+    username: &str,
+    client_id: &str,
+    owner: &UserSessionOwner,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+) -> Result<(), MqttBrokerError> {
+    // A broker that left the cluster holds no sessions anymore
+    let Some(node) = cache_manager
+        .node_lists
+        .get(&owner.broker_id)
+        .map(|node| node.clone())
+    else {
+        warn!(
+            "Broker {} holding the session of user {} is no longer in the cluster, skip kicking it",
+            owner.broker_id, username
+        );
+        return Ok(());
+    };
+
+    let request = KickUserSessionRequest {
+        cluster_name: cache_manager.cluster_name.clone(),
+        username: username.to_owned(),
+        except_client_id: client_id.to_owned(),
+    };
+    let reply =
+        broker_mqtt_kick_user_session(client_pool, &[node.node_inner_addr], request).await?;
+
+    for _ in 0..reply.kicked_num {
+        incr_user_session_kick_counter(USER_SESSION_KICK_SCOPE_CROSS_NODE);
+    }
+    if reply.kicked_num > 0 {
+        info!(
+            "Kicked {} sessions of user {} on broker {} for client {}",
+            reply.kicked_num, username, owner.broker_id, client_id
+        );
+    }
+    Ok(())
+}
+
+// Disconnects the connections of the user on this broker, except the one of `except_client_id`
+pub async fn kick_local_user_sessions(
+    username: &str,
+    except_client_id: &str,
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> Result<u64, MqttBrokerError> {
+    let connections: Vec<(String, u64)> = cache_manager
+        .connection_info
+        .iter()
+        .filter(|conn| conn.login_user == username && conn.client_id != except_client_id)
+        .map(|conn| (conn.client_id.clone(), *conn.key()))
+        .collect();
+
+    let mut kicked_num = 0;
+    for (client_id, connect_id) in connections {
+        takeover_connection(
+            &client_id,
+            connect_id,
+            cache_manager,
+            client_pool,
+            connection_manager,
+            subscribe_manager,
+        )
+        .await?;
+        kicked_num += 1;
+    }
+    Ok(kicked_num)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::superseded_user_sessions;
+    use crate::storage::user_session::UserSessionOwner;
+
+    fn session(client_id: &str, broker_id: u64, connect_time: u128) -> UserSessionOwner {
+        UserSessionOwner {
+            username: "user-1".to_string(),
+            client_id: client_id.to_string(),
+            broker_id,
+            connect_time,
+        }
+    }
+
+    #[test]
+    fn superseded_user_sessions_test() {
+        let connecting = session("c2", 1, 200);
+
+        // Older sessions are replaced, the stored record of the connecting session is skipped
+        let superseded = superseded_user_sessions(
+            &connecting,
+            vec![
+                session("c1", 2, 100),
+                connecting.clone(),
+                session("c3", 1, 150),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            superseded,
+            vec![session("c1", 2, 100), session("c3", 1, 150)]
+        );
+
+        // A session that connected later wins, the connecting one gives way
+        assert_eq!(
+            superseded_user_sessions(
+                &connecting,
+                vec![session("c1", 2, 100), session("c4", 2, 300)]
+            ),
+            Err(session("c4", 2, 300))
+        );
+
+        // Sessions connecting in the same millisecond are ordered the same way on every broker
+        let other = session("c1", 2, 200);
+        assert!(superseded_user_sessions(&connecting, vec![other.clone()]).is_err());
+        assert!(superseded_user_sessions(&other, vec![connecting]).is_ok());
+    }
+}
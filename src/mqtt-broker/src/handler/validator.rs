@@ -36,10 +36,27 @@
 use super::topic::topic_name_validator;
 use crate::common::pkid_storage::pkid_exists;
 use crate::handler::response::{build_puback, build_pubrec};
-use crate::security::AuthDriver;
 use crate::subscribe::common::sub_path_validator;
 use crate::subscribe::manager::SubscribeManager;
 
+// A draining node refuses every new CONNECT with a reason the client may retry on another node,
+// the connections it already holds are left alone
+pub fn node_draining_check(
+    protocol: &MqttProtocol,
+    cache_manager: &Arc<CacheManager>,
+    connect_properties: &Option<ConnectProperties>,
+) -> Option<MqttPacket> {
+    if !cache_manager.is_node_draining() {
+        return None;
+    }
+    Some(response_packet_mqtt_connect_fail(
+        protocol,
+        ConnectReturnCode::ServerUnavailable,
+        connect_properties,
+        Some(MqttBrokerError::NodeIsDraining.to_string()),
+    ))
+}
+
 pub fn connect_validator(
     protocol: &MqttProtocol,
     cluster: &BrokerMqttConfig,
@@ -342,7 +359,6 @@ pub async fn publish_validator(
 
 pub async fn subscribe_validator(
     protocol: &MqttProtocol,
-    auth_driver: &Arc<AuthDriver>,
     metadata_cache: &Arc<CacheManager>,
     subscribe_manager: &Arc<SubscribeManager>,
     connection: &MQTTConnection,
@@ -367,7 +383,7 @@ pub async fn subscribe_validator(
         ));
     }
 
-    if is_subscribe_rate_exceeded() {
+    if is_subscribe_rate_exceeded(metadata_cache, &connection.login_user) {
         return Some(response_packet_mqtt_suback(
             protocol,
             connection,
@@ -397,16 +413,6 @@ pub async fn subscribe_validator(
         ));
     }
 
-    if !auth_driver.allow_subscribe(connection, subscribe).await {
-        return Some(response_packet_mqtt_suback(
-            protocol,
-            connection,
-            subscribe.packet_identifier,
-            vec![SubscribeReasonCode::NotAuthorized],
-            None,
-        ));
-    }
-
     None
 }
 
@@ -490,6 +496,50 @@ pub fn password_validator(password: &str) -> bool {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::connection::MQTTConnection;
+    use protocol::mqtt::common::{ConnectReturnCode, MqttPacket, MqttProtocol};
+
+    use super::node_draining_check;
+    use crate::handler::cache::CacheManager;
+
     #[test]
     pub fn topic_name_validator_test() {}
+
+    #[test]
+    pub fn node_draining_check_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.add_connection(
+            1,
+            MQTTConnection {
+                connect_id: 1,
+                client_id: "c1".to_string(),
+                ..Default::default()
+            },
+        );
+        assert!(node_draining_check(&MqttProtocol::Mqtt5, &cache_manager, &None).is_none());
+
+        cache_manager.set_node_draining(true);
+        match node_draining_check(&MqttProtocol::Mqtt5, &cache_manager, &None) {
+            Some(MqttPacket::ConnAck(connack, _)) => {
+                assert_eq!(connack.code, ConnectReturnCode::ServerUnavailable)
+            }
+            _ => panic!("a draining node must refuse the connection"),
+        }
+        match node_draining_check(&MqttProtocol::Mqtt4, &cache_manager, &None) {
+            Some(MqttPacket::ConnAck(connack, _)) => {
+                assert_eq!(connack.code, ConnectReturnCode::ServiceUnavailable)
+            }
+            _ => panic!("a draining node must refuse the connection"),
+        }
+        // the existing connection is kept
+        assert_eq!(cache_manager.connection_info.len(), 1);
+        assert!(cache_manager.get_connection(1).is_some());
+
+        cache_manager.set_node_draining(false);
+        assert!(node_draining_check(&MqttProtocol::Mqtt5, &cache_manager, &None).is_none());
+    }
 }
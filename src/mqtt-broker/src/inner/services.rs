@@ -17,12 +17,19 @@
 use crate::handler::dynamic_cache::update_cache_metadata;
 use crate::handler::error::MqttBrokerError;
 use crate::handler::lastwill::send_last_will_message;
+use crate::handler::topic_cleanup::local_topic_activity;
+use crate::handler::user_session::kick_local_user_sessions;
+use crate::observability::metrics::resource::node_resource_snapshot;
+use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::manager::SubscribeManager;
+use common_base::tools::now_mills;
 use common_config::mqtt::broker_mqtt_conf;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::lastwill::LastWillData;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
+    DeleteSessionReply, DeleteSessionRequest, GetBrokerTimeReply, GetBrokerTimeRequest,
+    GetNodeResourceReply, GetTopicActivityReply, GetTopicActivityRequest, KickUserSessionReply,
+    KickUserSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
     UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 use schema_register::schema::SchemaRegisterManager;
@@ -78,6 +85,69 @@ pub async fn delete_session_by_req(
     Ok(DeleteSessionReply::default())
 }
 
+// Another broker accepted a connection of the user and asks this broker to give up the
+// sessions of the user it holds
+pub async fn kick_user_session_by_req(
+    cache_manager: &Arc<CacheManager>,
+    client_pool: &Arc<ClientPool>,
+    connection_manager: &Arc<ConnectionManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    req: &KickUserSessionRequest,
+) -> Result<KickUserSessionReply, MqttBrokerError> {
+    if cache_manager.cluster_name != req.cluster_name {
+        return Err(MqttBrokerError::ClusterNotMatch(req.cluster_name.clone()));
+    }
+
+    let kicked_num = kick_local_user_sessions(
+        &req.username,
+        &req.except_client_id,
+        cache_manager,
+        client_pool,
+        connection_manager,
+        subscribe_manager,
+    )
+    .await?;
+    Ok(KickUserSessionReply { kicked_num })
+}
+
+pub fn get_node_resource_by_req(cache_manager: &Arc<CacheManager>) -> GetNodeResourceReply {
+    let snapshot = node_resource_snapshot();
+    GetNodeResourceReply {
+        cpu_percent: snapshot.cpu_percent,
+        memory_bytes: snapshot.memory_bytes,
+        open_fd_num: snapshot.open_fd_num,
+        draining: cache_manager.is_node_draining(),
+    }
+}
+
+// Another broker is about to remove idle topics and asks when this broker last saw them
+pub fn get_topic_activity_by_req(
+    cache_manager: &Arc<CacheManager>,
+    req: &GetTopicActivityRequest,
+) -> Result<GetTopicActivityReply, MqttBrokerError> {
+    if cache_manager.cluster_name != req.cluster_name {
+        return Err(MqttBrokerError::ClusterNotMatch(req.cluster_name.clone()));
+    }
+
+    Ok(GetTopicActivityReply {
+        topics: local_topic_activity(cache_manager, &req.topic_names),
+    })
+}
+
+// Another broker compares its wall clock against the one of this broker
+pub fn get_broker_time_by_req(
+    cache_manager: &Arc<CacheManager>,
+    req: &GetBrokerTimeRequest,
+) -> Result<GetBrokerTimeReply, MqttBrokerError> {
+    if cache_manager.cluster_name != req.cluster_name {
+        return Err(MqttBrokerError::ClusterNotMatch(req.cluster_name.clone()));
+    }
+
+    Ok(GetBrokerTimeReply {
+        time_ms: now_mills() as u64,
+    })
+}
+
 pub async fn send_last_will_message_by_req<S>(
     cache_manager: &Arc<CacheManager>,
     client_pool: &Arc<ClientPool>,
@@ -22,13 +22,13 @@
 
 use crate::handler::error::MqttBrokerError;
 use crate::server::server::Server;
-use common_base::metrics::register_prometheus_export;
+use common_base::metrics::{metrics_register_default, register_prometheus_export};
 use common_base::runtime::create_runtime;
 use common_base::tools::now_second;
 use common_config::mqtt::broker_mqtt_conf;
 use delay_message::{start_delay_message_manager, DelayMessageManager};
 use grpc_clients::pool::ClientPool;
-use handler::acl::UpdateAclCache;
+use handler::acl::{BlacklistExpiryThread, UpdateAclCache};
 use handler::cache::CacheManager;
 use handler::dynamic_cache::load_metadata_cache;
 use handler::heartbreat::{register_node, report_heartbeat};
@@ -36,12 +36,15 @@
 use handler::sub_parse_topic::start_parse_subscribe_by_new_topic_thread;
 use handler::user::{init_system_user, UpdateUserCache};
 use lazy_static::lazy_static;
+use observability::metrics::cluster::register_cluster_status_collector;
 use observability::start_opservability;
 use pprof_monitor::pprof_monitor::start_pprof_monitor;
 use schema_register::schema::SchemaRegisterManager;
 use security::AuthDriver;
 use server::connection_manager::ConnectionManager;
+use server::grpc::health::{BrokerHealth, BrokerHealthThread};
 use server::grpc::server::GrpcServer;
+use server::top_talkers::TopTalkersThread;
 use server::websocket::server::{websocket_server, websockets_server, WebSocketServerState};
 use storage::cluster::ClusterStorage;
 use storage_adapter::memory::MemoryStorageAdapter;
@@ -49,7 +52,11 @@
 // use storage_adapter::mysql::MySQLStorageAdapter;
 // use storage_adapter::rocksdb::RocksDBStorageAdapter;
 use crate::handler::flapping_detect::UpdateFlappingDetectCache;
+use crate::handler::message_dedup::MessageDedupExpiryThread;
+use crate::handler::session_tiering::SessionTieringThread;
+use crate::handler::topic_cleanup::TopicCleanupThread;
 use crate::server::quic::server::start_quic_server;
+use crate::subscribe::snapshot::SubscribeSnapshotThread;
 use storage_adapter::storage::StorageAdapter;
 use storage_adapter::StorageType;
 use subscribe::exclusive::ExclusivePush;
@@ -140,6 +147,7 @@ pub struct MqttBroker<S> {
     delay_message_manager: Arc<DelayMessageManager<S>>,
     schema_manager: Arc<SchemaRegisterManager>,
     server: Arc<Server<S>>,
+    broker_health: Arc<BrokerHealth>,
 }
 
 impl<S> MqttBroker<S>
@@ -183,6 +191,7 @@ pub fn new(
             stop_sx,
             auth_driver.clone(),
         ));
+        let broker_health = Arc::new(BrokerHealth::default());
         MqttBroker {
             daemon_runtime,
             connector_runtime,
@@ -199,6 +208,7 @@ pub fn new(
             delay_message_manager,
             schema_manager,
             server,
+            broker_health,
         }
     }
 
@@ -213,6 +223,7 @@ pub fn start(&self, stop_send: broadcast::Sender<bool>) {
         self.start_system_topic_thread(stop_send.clone());
         self.start_prometheus();
         self.start_pprof_monitor();
+        self.start_health_check_thread(stop_send.clone());
 
         // grpc runtime
         self.start_grpc_server();
@@ -228,6 +239,7 @@ pub fn start(&self, stop_send: broadcast::Sender<bool>) {
         // subscribe runtime
         self.start_subscribe_push(stop_send.clone());
 
+        self.broker_health.mark_started();
         self.awaiting_stop(stop_send);
     }
 
@@ -248,6 +260,12 @@ fn start_mqtt_server(&self) {
     fn start_prometheus(&self) {
         let conf = broker_mqtt_conf();
         if conf.prometheus.enable {
+            register_cluster_status_collector(
+                &mut metrics_register_default(),
+                self.connection_manager.clone(),
+                self.cache_manager.clone(),
+                self.subscribe_manager.clone(),
+            );
             self.daemon_runtime.spawn(async move {
                 register_prometheus_export(conf.prometheus.port).await;
             });
@@ -299,6 +317,8 @@ fn start_grpc_server(&self) {
             self.schema_manager.clone(),
             self.client_pool.clone(),
             self.message_storage_adapter.clone(),
+            self.delay_message_manager.clone(),
+            self.broker_health.clone(),
         );
         self.grpc_runtime.spawn(async move {
             if let Err(e) = server.start().await {
@@ -307,6 +327,17 @@ fn start_grpc_server(&self) {
         });
     }
 
+    fn start_health_check_thread(&self, stop_send: broadcast::Sender<bool>) {
+        let health_check = BrokerHealthThread::new(
+            stop_send,
+            self.broker_health.clone(),
+            self.message_storage_adapter.clone(),
+        );
+        self.daemon_runtime.spawn(async move {
+            health_check.start().await;
+        });
+    }
+
     fn start_websocket_server(&self, stop_send: broadcast::Sender<bool>) {
         let ws_state = WebSocketServerState::new(
             self.subscribe_manager.clone(),
@@ -355,9 +386,11 @@ fn start_cluster_heartbeat_report(&self, stop_send: broadcast::Sender<bool>) {
 
     fn start_connector_thread(&self, stop_send: broadcast::Sender<bool>) {
         let message_storage = self.message_storage_adapter.clone();
+        let cache_manager = self.cache_manager.clone();
         let connector_manager = self.connector_manager.clone();
         self.connector_runtime.spawn(async move {
-            start_connector_thread(message_storage, connector_manager, stop_send).await;
+            start_connector_thread(message_storage, cache_manager, connector_manager, stop_send)
+                .await;
         });
     }
 
@@ -454,11 +487,54 @@ fn start_update_cache_thread(&self, stop_send: broadcast::Sender<bool>) {
             update_acl_cache.start_update().await;
         });
 
+        let blacklist_expiry =
+            BlacklistExpiryThread::new(stop_send.clone(), self.auth_driver.clone());
+        self.daemon_runtime.spawn(async move {
+            blacklist_expiry.start().await;
+        });
+
         let update_flapping_detect_cache =
             UpdateFlappingDetectCache::new(stop_send.clone(), self.cache_manager.clone());
         self.daemon_runtime.spawn(async move {
             update_flapping_detect_cache.start_update().await;
         });
+
+        let message_dedup_expiry =
+            MessageDedupExpiryThread::new(stop_send.clone(), self.cache_manager.clone());
+        self.daemon_runtime.spawn(async move {
+            message_dedup_expiry.start().await;
+        });
+
+        let topic_cleanup = TopicCleanupThread::new(
+            stop_send.clone(),
+            self.cache_manager.clone(),
+            self.subscribe_manager.clone(),
+            self.client_pool.clone(),
+        );
+        self.daemon_runtime.spawn(async move {
+            topic_cleanup.start().await;
+        });
+
+        let subscribe_snapshot = SubscribeSnapshotThread::new(
+            stop_send.clone(),
+            self.cache_manager.clone(),
+            self.subscribe_manager.clone(),
+            self.client_pool.clone(),
+        );
+        self.daemon_runtime.spawn(async move {
+            subscribe_snapshot.start().await;
+        });
+
+        let session_tiering =
+            SessionTieringThread::new(stop_send.clone(), self.cache_manager.clone());
+        self.daemon_runtime.spawn(async move {
+            session_tiering.start().await;
+        });
+
+        let top_talkers = TopTalkersThread::new(stop_send.clone(), self.connection_manager.clone());
+        self.daemon_runtime.spawn(async move {
+            top_talkers.start().await;
+        });
     }
 
     fn start_system_topic_thread(&self, stop_send: broadcast::Sender<bool>) {
@@ -490,6 +566,8 @@ pub fn awaiting_stop(&self, stop_send: broadcast::Sender<bool>) {
                 "{}",
                 "When ctrl + c is received, the service starts to stop"
             );
+            // Report NOT_SERVING first so that the broker is taken out of rotation while it stops
+            self.broker_health.mark_stopped().await;
             // Stop the Server first, indicating that it will no longer receive request packets.
             self.server.stop().await;
             match stop_send.send(true) {
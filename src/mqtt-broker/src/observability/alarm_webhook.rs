@@ -0,0 +1,378 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_config::mqtt::config::AlarmWebhook;
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::time::sleep;
+use tracing::{error, warn};
+
+use crate::observability::system_topic::sysmon::SystemAlarmEventMessage;
+
+// Hex HMAC-SHA256 of the request body keyed with the webhook secret, prefixed with "sha256="
+pub const ALARM_WEBHOOK_SIGNATURE_HEADER: &str = "X-RobustMQ-Signature";
+pub const ALARM_WEBHOOK_MAX_BACKOFF_MS: u64 = 30000;
+pub const ALARM_WEBHOOK_MAX_RETRIES: u32 = 10;
+pub const ALARM_WEBHOOK_MAX_TIMEOUT_MS: u64 = 60000;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlarmWebhookPayload {
+    // alarm_fired or alarm_resolved
+    pub event: String,
+    pub cluster_name: String,
+    pub broker_id: u64,
+    pub name: String,
+    pub message: String,
+    pub activate_at: i64,
+    pub activated: bool,
+    // Seconds since the epoch when the alarm changed its state
+    pub timestamp: i64,
+}
+
+impl AlarmWebhookPayload {
+    pub fn new(
+        cluster_name: &str,
+        broker_id: u64,
+        alarm: &SystemAlarmEventMessage,
+        timestamp: i64,
+    ) -> Self {
+        let event = if alarm.activated {
+            "alarm_fired"
+        } else {
+            "alarm_resolved"
+        };
+        AlarmWebhookPayload {
+            event: event.to_string(),
+            cluster_name: cluster_name.to_string(),
+            broker_id,
+            name: alarm.name.clone(),
+            message: alarm.message.clone(),
+            activate_at: alarm.activate_at,
+            activated: alarm.activated,
+            timestamp,
+        }
+    }
+}
+
+pub fn sign_alarm_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes a key of any size");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+// Posts alarms to the configured webhooks. Every url is delivered on its own task, so a slow
+// or failing receiver neither blocks the alarm check nor the other receivers.
+#[derive(Default)]
+pub struct AlarmWebhookNotifier {
+    client: reqwest::Client,
+    delivered_num: AtomicU64,
+    failed_num: AtomicU64,
+    retry_num: AtomicU64,
+}
+
+impl AlarmWebhookNotifier {
+    pub fn notify(self: &Arc<Self>, config: &AlarmWebhook, payload: &AlarmWebhookPayload) {
+        if config.urls.is_empty() {
+            return;
+        }
+        let body = match serde_json::to_vec(payload) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to encode the alarm webhook payload: {}", e);
+                return;
+            }
+        };
+        for url in config.urls.iter() {
+            let notifier = self.clone();
+            let config = config.clone();
+            let url = url.clone();
+            let body = body.clone();
+            tokio::spawn(async move {
+                notifier.deliver(&config, &url, body).await;
+            });
+        }
+    }
+
+    // Returns whether the receiver accepted the alarm with a 2xx status
+    pub async fn deliver(&self, config: &AlarmWebhook, url: &str, body: Vec<u8>) -> bool {
+        let signature = if config.secret.is_empty() {
+            None
+        } else {
+            Some(sign_alarm_payload(&config.secret, &body))
+        };
+        let mut backoff_ms = config.initial_backoff_ms;
+        for attempt in 0..=config.max_retries {
+            if attempt > 0 {
+                self.retry_num.fetch_add(1, Ordering::Relaxed);
+                sleep(Duration::from_millis(backoff_ms)).await;
+                backoff_ms = backoff_ms
+                    .saturating_mul(2)
+                    .min(ALARM_WEBHOOK_MAX_BACKOFF_MS);
+            }
+            let mut request = self
+                .client
+                .post(url)
+                .timeout(Duration::from_millis(config.timeout_ms))
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(ALARM_WEBHOOK_SIGNATURE_HEADER, signature);
+            }
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    self.delivered_num.fetch_add(1, Ordering::Relaxed);
+                    return true;
+                }
+                Ok(response) => {
+                    warn!(
+                        "Alarm webhook {} answered {} on attempt {}",
+                        url,
+                        response.status(),
+                        attempt + 1
+                    );
+                }
+                Err(e) => {
+                    warn!(
+                        "Alarm webhook {} failed on attempt {}: {}",
+                        url,
+                        attempt + 1,
+                        e
+                    );
+                }
+            }
+        }
+        self.failed_num.fetch_add(1, Ordering::Relaxed);
+        error!(
+            "Alarm webhook {} dropped the alarm after {} attempts",
+            url,
+            config.max_retries + 1
+        );
+        false
+    }
+
+    pub fn delivered_num(&self) -> u64 {
+        self.delivered_num.load(Ordering::Relaxed)
+    }
+
+    pub fn failed_num(&self) -> u64 {
+        self.failed_num.load(Ordering::Relaxed)
+    }
+
+    pub fn retry_num(&self) -> u64 {
+        self.retry_num.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        sign_alarm_payload, AlarmWebhookNotifier, AlarmWebhookPayload,
+        ALARM_WEBHOOK_SIGNATURE_HEADER,
+    };
+    use crate::observability::system_topic::sysmon::SystemAlarmEventMessage;
+    use axum::body::Bytes;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use common_config::mqtt::config::AlarmWebhook;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+
+    #[derive(Clone, Default)]
+    struct MockReceiver {
+        // (signature header, body) of every request
+        requests: Arc<Mutex<Vec<(Option<String>, Bytes)>>>,
+        // Requests answered with 500 before the receiver accepts
+        fail_num: Arc<AtomicU32>,
+    }
+
+    async fn receive(
+        State(receiver): State<MockReceiver>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        let signature = headers
+            .get(ALARM_WEBHOOK_SIGNATURE_HEADER)
+            .map(|value| value.to_str().unwrap().to_string());
+        receiver.requests.lock().unwrap().push((signature, body));
+        let failing = receiver
+            .fail_num
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |num| {
+                num.checked_sub(1)
+            })
+            .is_ok();
+        if failing {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    async fn start_receiver(fail_num: u32) -> (MockReceiver, SocketAddr) {
+        let receiver = MockReceiver {
+            fail_num: Arc::new(AtomicU32::new(fail_num)),
+            ..Default::default()
+        };
+        let app = Router::new()
+            .route("/alarm", post(receive))
+            .with_state(receiver.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (receiver, addr)
+    }
+
+    fn webhook(addr: SocketAddr, secret: &str, max_retries: u32) -> AlarmWebhook {
+        AlarmWebhook {
+            urls: vec![format!("http://{}/alarm", addr)],
+            secret: secret.to_string(),
+            max_retries,
+            initial_backoff_ms: 10,
+            timeout_ms: 2000,
+        }
+    }
+
+    fn payload(activated: bool) -> AlarmWebhookPayload {
+        let alarm = SystemAlarmEventMessage {
+            name: "connection_num > 100".to_string(),
+            message: "connection_num is 101, alarm when > 100".to_string(),
+            activate_at: 1700000000,
+            activated,
+        };
+        AlarmWebhookPayload::new("robust-test", 1, &alarm, 1700000010)
+    }
+
+    #[tokio::test]
+    async fn alarm_webhook_payload_and_signature_test() {
+        let (receiver, addr) = start_receiver(0).await;
+        let notifier = AlarmWebhookNotifier::default();
+        let config = webhook(addr, "s3cr3t", 0);
+        let body = serde_json::to_vec(&payload(true)).unwrap();
+        assert!(notifier.deliver(&config, &config.urls[0], body).await);
+        assert_eq!(notifier.delivered_num(), 1);
+
+        let requests = receiver.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 1);
+        let (signature, body) = &requests[0];
+        let value: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(value["event"], "alarm_fired");
+        assert_eq!(value["cluster_name"], "robust-test");
+        assert_eq!(value["broker_id"], 1);
+        assert_eq!(value["name"], "connection_num > 100");
+        assert_eq!(value["message"], "connection_num is 101, alarm when > 100");
+        assert_eq!(value["activate_at"], 1700000000);
+        assert_eq!(value["activated"], true);
+        assert_eq!(value["timestamp"], 1700000010);
+
+        // The receiver recomputes the signature over the raw body with the shared secret
+        let signature = signature.as_ref().unwrap();
+        assert_eq!(*signature, sign_alarm_payload("s3cr3t", body));
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert_ne!(*signature, sign_alarm_payload("other", body));
+
+        // Without a secret the request is not signed
+        let config = webhook(addr, "", 0);
+        let body = serde_json::to_vec(&payload(false)).unwrap();
+        assert!(notifier.deliver(&config, &config.urls[0], body).await);
+        let requests = receiver.requests.lock().unwrap().clone();
+        assert!(requests[1].0.is_none());
+        let value: serde_json::Value = serde_json::from_slice(&requests[1].1).unwrap();
+        assert_eq!(value["event"], "alarm_resolved");
+    }
+
+    #[test]
+    fn sign_alarm_payload_test() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_alarm_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn alarm_webhook_retry_test() {
+        // Two failures are retried, the third attempt is accepted
+        let (receiver, addr) = start_receiver(2).await;
+        let notifier = AlarmWebhookNotifier::default();
+        let config = webhook(addr, "s3cr3t", 3);
+        let body = serde_json::to_vec(&payload(true)).unwrap();
+        assert!(notifier.deliver(&config, &config.urls[0], body).await);
+        assert_eq!(receiver.requests.lock().unwrap().len(), 3);
+        assert_eq!(notifier.retry_num(), 2);
+        assert_eq!(notifier.delivered_num(), 1);
+        assert_eq!(notifier.failed_num(), 0);
+
+        // Failures past the retries are counted and the alarm is dropped
+        let (receiver, addr) = start_receiver(10).await;
+        let config = webhook(addr, "s3cr3t", 1);
+        let body = serde_json::to_vec(&payload(true)).unwrap();
+        assert!(!notifier.deliver(&config, &config.urls[0], body).await);
+        assert_eq!(receiver.requests.lock().unwrap().len(), 2);
+        assert_eq!(notifier.failed_num(), 1);
+
+        // An unreachable receiver fails the same way
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let config = webhook(addr, "", 1);
+        let body = serde_json::to_vec(&payload(true)).unwrap();
+        assert!(!notifier.deliver(&config, &config.urls[0], body).await);
+        assert_eq!(notifier.failed_num(), 2);
+    }
+
+    #[tokio::test]
+    async fn alarm_webhook_notify_test() {
+        let (first, first_addr) = start_receiver(0).await;
+        let (second, second_addr) = start_receiver(0).await;
+        let notifier = Arc::new(AlarmWebhookNotifier::default());
+        let mut config = webhook(first_addr, "s3cr3t", 0);
+        config.urls.push(format!("http://{}/alarm", second_addr));
+        notifier.notify(&config, &payload(true));
+
+        // Each receiver gets the alarm on its own task
+        for _ in 0..200 {
+            if notifier.delivered_num() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(notifier.delivered_num(), 2);
+        assert_eq!(first.requests.lock().unwrap().len(), 1);
+        assert_eq!(second.requests.lock().unwrap().len(), 1);
+
+        // Nothing is posted without a url
+        notifier.notify(&AlarmWebhook::default(), &payload(false));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(notifier.delivered_num(), 2);
+    }
+}
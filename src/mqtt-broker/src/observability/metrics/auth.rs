@@ -11,3 +11,38 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct SubscribeAuthLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    SUBSCRIBE_AUTH_COUNTER,
+    "subscribe_auth_denied",
+    "The number of SUBSCRIBE filters and packets denied by the authorization, by outcome.",
+    SubscribeAuthLabels
+);
+
+// A single filter was answered with Not authorized while the others were subscribed
+pub const SUBSCRIBE_AUTH_OUTCOME_DENIED_FILTER: &str = "denied_filter";
+// The whole SUBSCRIBE packet was rejected
+pub const SUBSCRIBE_AUTH_OUTCOME_REJECTED_PACKET: &str = "rejected_packet";
+
+pub fn incr_subscribe_auth_counter(outcome: &str) {
+    let labels = SubscribeAuthLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(SUBSCRIBE_AUTH_COUNTER, labels)
+}
+
+pub fn get_subscribe_auth_counter(outcome: &str) -> u64 {
+    let labels = SubscribeAuthLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(SUBSCRIBE_AUTH_COUNTER, labels, res);
+    res
+}
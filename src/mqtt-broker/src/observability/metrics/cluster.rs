@@ -0,0 +1,221 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::sync::Arc;
+
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeMetric};
+use prometheus_client::metrics::gauge::ConstGauge;
+use prometheus_client::registry::Registry;
+
+use crate::handler::cache::CacheManager;
+use crate::server::connection_manager::ConnectionManager;
+use crate::subscribe::manager::SubscribeManager;
+
+struct ClusterGauge {
+    name: &'static str,
+    help: &'static str,
+    value: fn(&ClusterStatusCollector) -> i64,
+}
+
+// The counters of cluster_status_by_req. A new gauge only needs an entry here.
+const CLUSTER_GAUGES: &[ClusterGauge] = &[
+    ClusterGauge {
+        name: "broker_connection_num",
+        help: "Number of connections on the broker",
+        value: |c| c.connection_manager.connections.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_tcp_connection_num",
+        help: "Number of TCP connections on the broker",
+        value: |c| c.connection_manager.tcp_write_list.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_tls_connection_num",
+        help: "Number of TLS connections on the broker",
+        value: |c| c.connection_manager.tcp_tls_write_list.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_websocket_connection_num",
+        help: "Number of WebSocket connections on the broker",
+        value: |c| c.connection_manager.websocket_write_list.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_quic_connection_num",
+        help: "Number of QUIC connections on the broker",
+        value: |c| c.connection_manager.quic_write_list.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_session_num",
+        help: "Number of sessions on the broker",
+        value: |c| c.cache_manager.session_info.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_subscribe_num",
+        help: "Number of subscriptions on the broker",
+        value: |c| c.subscribe_manager.subscribe_list.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_exclusive_subscribe_num",
+        help: "Number of exclusive subscriptions pushed by the broker",
+        value: |c| c.subscribe_manager.exclusive_push.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_share_subscribe_leader_num",
+        help: "Number of shared subscriptions the broker pushes as leader",
+        value: |c| c.subscribe_manager.share_leader_push.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_share_subscribe_resub_num",
+        help: "Number of shared subscriptions the broker follows from another node",
+        value: |c| c.subscribe_manager.share_follower_resub.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_topic_num",
+        help: "Number of topics known to the broker",
+        value: |c| c.cache_manager.topic_info.len() as i64,
+    },
+    ClusterGauge {
+        name: "broker_message_in_rate",
+        help: "Inbound PUBLISH packets per second",
+        value: |c| c.connection_manager.message_rate.message_in_rate() as i64,
+    },
+    ClusterGauge {
+        name: "broker_message_out_rate",
+        help: "Outbound PUBLISH packets per second",
+        value: |c| c.connection_manager.message_rate.message_out_rate() as i64,
+    },
+];
+
+// Reads the managers each time the registry is encoded, so a scrape always sees the
+// current state without a task keeping gauges up to date
+pub struct ClusterStatusCollector {
+    connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+}
+
+impl ClusterStatusCollector {
+    pub fn new(
+        connection_manager: Arc<ConnectionManager>,
+        cache_manager: Arc<CacheManager>,
+        subscribe_manager: Arc<SubscribeManager>,
+    ) -> Self {
+        ClusterStatusCollector {
+            connection_manager,
+            cache_manager,
+            subscribe_manager,
+        }
+    }
+}
+
+impl fmt::Debug for ClusterStatusCollector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClusterStatusCollector").finish()
+    }
+}
+
+impl Collector for ClusterStatusCollector {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), fmt::Error> {
+        for gauge in CLUSTER_GAUGES {
+            let metric = ConstGauge::new((gauge.value)(self));
+            let metric_encoder =
+                encoder.encode_descriptor(gauge.name, gauge.help, None, metric.metric_type())?;
+            metric.encode(metric_encoder)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn register_cluster_status_collector(
+    registry: &mut Registry,
+    connection_manager: Arc<ConnectionManager>,
+    cache_manager: Arc<CacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+) {
+    registry.register_collector(Box::new(ClusterStatusCollector::new(
+        connection_manager,
+        cache_manager,
+        subscribe_manager,
+    )));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::mqtt::session::MqttSession;
+    use prometheus_client::encoding::text::encode;
+    use prometheus_client::registry::Registry;
+
+    use super::{register_cluster_status_collector, CLUSTER_GAUGES};
+    use crate::handler::cache::CacheManager;
+    use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+    use crate::server::connection_manager::ConnectionManager;
+    use crate::subscribe::manager::SubscribeManager;
+
+    #[test]
+    fn cluster_status_collector_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let connection_manager = Arc::new(ConnectionManager::new(cache_manager.clone()));
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+
+        let mut registry = Registry::default();
+        register_cluster_status_collector(
+            &mut registry,
+            connection_manager.clone(),
+            cache_manager.clone(),
+            subscribe_manager,
+        );
+
+        let scrape = |registry: &Registry| {
+            let mut buffer = String::new();
+            encode(&mut buffer, registry).unwrap();
+            buffer
+        };
+
+        let body = scrape(&registry);
+        for gauge in CLUSTER_GAUGES {
+            assert!(body.contains(&format!("# HELP {} {}", gauge.name, gauge.help)));
+            assert!(body.contains(&format!("# TYPE {} gauge", gauge.name)));
+        }
+        assert!(body.contains("\nbroker_connection_num 0\n"));
+        assert!(body.ends_with("# EOF\n"));
+
+        // The gauges follow the managers without re-registering
+        for addr in ["127.0.0.1:1883", "127.0.0.1:1884"] {
+            connection_manager.add_connection(NetworkConnection::new(
+                NetworkConnectionType::Tcp,
+                addr.parse().unwrap(),
+                None,
+            ));
+        }
+        cache_manager.add_session(
+            "c1",
+            &MqttSession {
+                client_id: "c1".to_string(),
+                ..Default::default()
+            },
+        );
+        connection_manager.message_rate.record_message_in();
+
+        let body = scrape(&registry);
+        assert!(body.contains("\nbroker_connection_num 2\n"));
+        assert!(body.contains("\nbroker_session_num 1\n"));
+        assert!(body.contains("\nbroker_message_in_rate 1\n"));
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ConnectorThrottledLabels {
+    connector_name: String,
+}
+
+common_base::register_counter_metric!(
+    CONNECTOR_THROTTLED_COUNTER,
+    "connector_throttled",
+    "The number of times a connector used up its forwarding quota and waited before reading more records.",
+    ConnectorThrottledLabels
+);
+
+pub fn incr_connector_throttled_counter(connector_name: &str) {
+    let labels = ConnectorThrottledLabels {
+        connector_name: connector_name.to_string(),
+    };
+    common_base::counter_metric_inc!(CONNECTOR_THROTTLED_COUNTER, labels)
+}
+
+pub fn get_connector_throttled_counter(connector_name: &str) -> u64 {
+    let labels = ConnectorThrottledLabels {
+        connector_name: connector_name.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(CONNECTOR_THROTTLED_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct ConnectorRecordSkippedLabels {
+    connector_name: String,
+}
+
+common_base::register_counter_metric!(
+    CONNECTOR_RECORD_SKIPPED_COUNTER,
+    "connector_record_skipped",
+    "The number of records a connector skipped because its transforms could not be applied to them.",
+    ConnectorRecordSkippedLabels
+);
+
+pub fn incr_connector_record_skipped_counter(connector_name: &str) {
+    let labels = ConnectorRecordSkippedLabels {
+        connector_name: connector_name.to_string(),
+    };
+    common_base::counter_metric_inc!(CONNECTOR_RECORD_SKIPPED_COUNTER, labels)
+}
+
+pub fn get_connector_record_skipped_counter(connector_name: &str) -> u64 {
+    let labels = ConnectorRecordSkippedLabels {
+        connector_name: connector_name.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(CONNECTOR_RECORD_SKIPPED_COUNTER, labels, res);
+    res
+}
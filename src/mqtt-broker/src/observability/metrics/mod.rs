@@ -13,9 +13,15 @@
 // limitations under the License.
 
 pub mod auth;
+pub mod cluster;
+pub mod connector;
 pub mod event_metrics;
 pub mod packets;
 pub mod publish;
+pub mod resource;
+pub mod retain;
 pub mod server;
 pub mod session;
+pub mod subscribe;
 pub mod time;
+pub mod topic;
@@ -317,8 +317,8 @@ pub fn record_received_metrics(
     }
 }
 
-// Record metrics related to messages pushed to the client
-pub fn record_sent_metrics(packet_wrapper: &MqttPacketWrapper, network_type: String) {
+// Record metrics related to messages pushed to the client, returns the size of the packet
+pub fn record_sent_metrics(packet_wrapper: &MqttPacketWrapper, network_type: String) -> usize {
     let qos_str = if let MqttPacket::Publish(publish, _) = packet_wrapper.packet.clone() {
         format!("{}", publish.qos as u8)
     } else {
@@ -367,6 +367,7 @@ pub fn record_sent_metrics(packet_wrapper: &MqttPacketWrapper, network_type: Str
         MqttPacket::Auth(_, _) => common_base::gauge_metric_inc!(PACKETS_CONNACK_SENT, label_qos),
         _ => unreachable!("This branch only matches for packets could not be sent"),
     }
+    payload_size
 }
 
 pub fn record_retain_recv_metrics(qos: QoS) {
@@ -494,9 +495,17 @@ fn calc_mqtt_packet_test() {
         let nc = NetworkConnection {
             connection_type: NetworkConnectionType::Tcp,
             addr: get_addr_by_local_hostname(1883).parse().unwrap(),
+            original_addr: None,
             connection_stop_sx: None,
             connection_id: 100,
             protocol: Some(MqttProtocol::Mqtt3),
+            bytes_received: Default::default(),
+            bytes_sent: Default::default(),
+            messages_received: Default::default(),
+            messages_sent: Default::default(),
+            create_time_ms: 0,
+            tls_info: None,
+            websocket_subprotocol: None,
         };
         let ty = NetworkConnectionType::Tcp;
         record_received_metrics(&nc, &mp, &ty);
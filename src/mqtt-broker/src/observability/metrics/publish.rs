@@ -11,3 +11,51 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+common_base::register_counter_metric!(
+    USER_DEFAULT_MESSAGE_EXPIRY_COUNTER,
+    "user_default_message_expiry",
+    "The number of published messages that were assigned the default expiry of their user or group.",
+    ()
+);
+
+// Not labeled by user, the admin API reports the messages per user
+pub fn incr_user_default_message_expiry_counter() {
+    USER_DEFAULT_MESSAGE_EXPIRY_COUNTER
+        .read()
+        .unwrap()
+        .get_or_create(&())
+        .inc();
+}
+
+common_base::register_counter_metric!(
+    PUBLISH_RATE_LIMITED_COUNTER,
+    "publish_rate_limited_messages",
+    "The number of published messages rejected because their user exceeded the publish rate limit.",
+    ()
+);
+
+// Not labeled by user, the admin API reports the rejected messages per user
+pub fn incr_publish_rate_limited_counter() {
+    PUBLISH_RATE_LIMITED_COUNTER
+        .read()
+        .unwrap()
+        .get_or_create(&())
+        .inc();
+}
+
+common_base::register_counter_metric!(
+    MESSAGE_DEDUP_SUPPRESSED_COUNTER,
+    "message_dedup_suppressed",
+    "The number of published messages dropped because their message id was already seen on the topic.",
+    ()
+);
+
+// Not labeled by topic, the admin API reports the suppressed messages per topic
+pub fn incr_message_dedup_suppressed_counter() {
+    MESSAGE_DEDUP_SUPPRESSED_COUNTER
+        .read()
+        .unwrap()
+        .get_or_create(&())
+        .inc();
+}
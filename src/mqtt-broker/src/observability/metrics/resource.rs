@@ -0,0 +1,107 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Mutex, OnceLock};
+
+use sysinfo::{Pid, ProcessExt, System, SystemExt};
+
+// Resource usage of the broker process on one node
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeResourceSnapshot {
+    // CPU usage averaged over all cores, between 0 and 100
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub open_fd_num: u64,
+}
+
+pub trait NodeResourceSource: Send + Sync {
+    fn snapshot(&self) -> NodeResourceSnapshot;
+}
+
+// Samples the broker process with sysinfo. The CPU usage is measured since the previous
+// snapshot, so the first snapshot after the broker starts reports 0.
+pub struct SysinfoResourceSource {
+    system: Mutex<System>,
+}
+
+impl SysinfoResourceSource {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu();
+        SysinfoResourceSource {
+            system: Mutex::new(system),
+        }
+    }
+}
+
+impl Default for SysinfoResourceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeResourceSource for SysinfoResourceSource {
+    fn snapshot(&self) -> NodeResourceSnapshot {
+        let pid = Pid::from(std::process::id() as usize);
+        let mut system = self.system.lock().unwrap();
+        system.refresh_process(pid);
+
+        let mut snapshot = NodeResourceSnapshot {
+            open_fd_num: open_fd_num(),
+            ..Default::default()
+        };
+        if let Some(process) = system.process(pid) {
+            let cpu_count = system.cpus().len().max(1) as f32;
+            snapshot.cpu_percent = process.cpu_usage() / cpu_count;
+            snapshot.memory_bytes = process.memory();
+        }
+        snapshot
+    }
+}
+
+static NODE_RESOURCE_SOURCE: OnceLock<SysinfoResourceSource> = OnceLock::new();
+
+pub fn node_resource_source() -> &'static SysinfoResourceSource {
+    NODE_RESOURCE_SOURCE.get_or_init(SysinfoResourceSource::new)
+}
+
+pub fn node_resource_snapshot() -> NodeResourceSnapshot {
+    node_resource_source().snapshot()
+}
+
+// sysinfo does not report file descriptors, they are counted from procfs where it exists
+fn open_fd_num() -> u64 {
+    match std::fs::read_dir("/proc/self/fd") {
+        Ok(entries) => entries.count() as u64,
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{node_resource_snapshot, NodeResourceSource, SysinfoResourceSource};
+
+    #[test]
+    fn sysinfo_resource_source_test() {
+        let source = SysinfoResourceSource::new();
+        let snapshot = source.snapshot();
+        assert!(snapshot.cpu_percent >= 0.0);
+        assert!(snapshot.memory_bytes > 0);
+        if cfg!(target_os = "linux") {
+            assert!(snapshot.open_fd_num > 0);
+        }
+
+        assert!(node_resource_snapshot().memory_bytes > 0);
+    }
+}
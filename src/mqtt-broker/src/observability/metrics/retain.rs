@@ -0,0 +1,108 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct RetainOversizeLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    RETAIN_OVERSIZE_COUNTER,
+    "retain_oversize_messages",
+    "The number of retained messages exceeding the size limit, by handling outcome.",
+    RetainOversizeLabels
+);
+
+pub const RETAIN_OVERSIZE_OUTCOME_REJECTED: &str = "rejected";
+pub const RETAIN_OVERSIZE_OUTCOME_NOT_RETAINED: &str = "not_retained";
+
+pub fn incr_retain_oversize_counter(outcome: &str) {
+    let labels = RetainOversizeLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(RETAIN_OVERSIZE_COUNTER, labels)
+}
+
+pub fn get_retain_oversize_counter(outcome: &str) -> u64 {
+    let labels = RetainOversizeLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(RETAIN_OVERSIZE_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct RetainTopicPolicyLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    RETAIN_TOPIC_POLICY_COUNTER,
+    "retain_topic_policy_messages",
+    "The number of retained messages handled by a retain topic policy, by outcome.",
+    RetainTopicPolicyLabels
+);
+
+pub const RETAIN_TOPIC_POLICY_OUTCOME_REJECTED: &str = "rejected";
+pub const RETAIN_TOPIC_POLICY_OUTCOME_STRIPPED: &str = "stripped";
+
+pub fn incr_retain_topic_policy_counter(outcome: &str) {
+    let labels = RetainTopicPolicyLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(RETAIN_TOPIC_POLICY_COUNTER, labels)
+}
+
+pub fn get_retain_topic_policy_counter(outcome: &str) -> u64 {
+    let labels = RetainTopicPolicyLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(RETAIN_TOPIC_POLICY_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct RetainDeliverTruncatedLabels {
+    limit: String,
+}
+
+common_base::register_counter_metric!(
+    RETAIN_DELIVER_TRUNCATED_COUNTER,
+    "retain_deliver_truncated",
+    "The number of subscribes whose retained message delivery was cut short, by the limit that was hit.",
+    RetainDeliverTruncatedLabels
+);
+
+pub const RETAIN_DELIVER_LIMIT_NUM: &str = "num";
+pub const RETAIN_DELIVER_LIMIT_BYTES: &str = "bytes";
+
+pub fn incr_retain_deliver_truncated_counter(limit: &str) {
+    let labels = RetainDeliverTruncatedLabels {
+        limit: limit.to_string(),
+    };
+    common_base::counter_metric_inc!(RETAIN_DELIVER_TRUNCATED_COUNTER, labels)
+}
+
+pub fn get_retain_deliver_truncated_counter(limit: &str) -> u64 {
+    let labels = RetainDeliverTruncatedLabels {
+        limit: limit.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(RETAIN_DELIVER_TRUNCATED_COUNTER, labels, res);
+    res
+}
@@ -189,3 +189,19 @@ pub fn record_broker_thread_num(
     };
     common_base::gauge_metric_inc_by!(BROKER_ACTIVE_THREAD_NUM, accept_label, response as i64);
 }
+
+common_base::register_counter_metric!(
+    CONNECTION_IP_LIMIT_REJECTED_COUNTER,
+    "connection_ip_limit_rejected",
+    "The number of connections rejected because their source IP exceeded the connection limit per IP.",
+    ()
+);
+
+// Not labeled by source IP, the rejected IP is logged instead
+pub fn incr_connection_ip_limit_rejected_counter() {
+    CONNECTION_IP_LIMIT_REJECTED_COUNTER
+        .read()
+        .unwrap()
+        .get_or_create(&())
+        .inc();
+}
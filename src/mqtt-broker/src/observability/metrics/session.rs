@@ -11,3 +11,219 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct DuplicateClientIdLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    DUPLICATE_CLIENT_ID_COUNTER,
+    "duplicate_client_id",
+    "The number of connections using an already connected client id, by handling outcome.",
+    DuplicateClientIdLabels
+);
+
+pub const DUPLICATE_CLIENT_ID_OUTCOME_TAKEOVER: &str = "takeover";
+pub const DUPLICATE_CLIENT_ID_OUTCOME_REJECTED: &str = "rejected";
+
+pub fn incr_duplicate_client_id_counter(outcome: &str) {
+    let labels = DuplicateClientIdLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(DUPLICATE_CLIENT_ID_COUNTER, labels)
+}
+
+pub fn get_duplicate_client_id_counter(outcome: &str) -> u64 {
+    let labels = DuplicateClientIdLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(DUPLICATE_CLIENT_ID_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct UserSessionKickLabels {
+    scope: String,
+}
+
+common_base::register_counter_metric!(
+    USER_SESSION_KICK_COUNTER,
+    "user_session_kick",
+    "The number of sessions disconnected because their user connected again, by where the old session lived.",
+    UserSessionKickLabels
+);
+
+pub const USER_SESSION_KICK_SCOPE_LOCAL: &str = "local";
+pub const USER_SESSION_KICK_SCOPE_CROSS_NODE: &str = "cross_node";
+
+pub fn incr_user_session_kick_counter(scope: &str) {
+    let labels = UserSessionKickLabels {
+        scope: scope.to_string(),
+    };
+    common_base::counter_metric_inc!(USER_SESSION_KICK_COUNTER, labels)
+}
+
+pub fn get_user_session_kick_counter(scope: &str) -> u64 {
+    let labels = UserSessionKickLabels {
+        scope: scope.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(USER_SESSION_KICK_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct SessionRestorePublishLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    SESSION_RESTORE_PUBLISH_COUNTER,
+    "session_restore_publish",
+    "The number of PUBLISH packets received while the session of the connection was being restored, by handling outcome.",
+    SessionRestorePublishLabels
+);
+
+pub const SESSION_RESTORE_OUTCOME_BUFFERED: &str = "buffered";
+pub const SESSION_RESTORE_OUTCOME_REJECTED: &str = "rejected";
+
+pub fn incr_session_restore_publish_counter(outcome: &str) {
+    let labels = SessionRestorePublishLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(SESSION_RESTORE_PUBLISH_COUNTER, labels)
+}
+
+pub fn get_session_restore_publish_counter(outcome: &str) -> u64 {
+    let labels = SessionRestorePublishLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(SESSION_RESTORE_PUBLISH_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct SessionTieringLabels {
+    action: String,
+}
+
+common_base::register_counter_metric!(
+    SESSION_TIERING_COUNTER,
+    "session_tiering",
+    "The number of offline sessions moved between memory and the store, by action.",
+    SessionTieringLabels
+);
+
+pub const SESSION_TIERING_ACTION_OFFLOAD: &str = "offload";
+pub const SESSION_TIERING_ACTION_RELOAD: &str = "reload";
+
+pub fn incr_session_tiering_counter(action: &str) {
+    let labels = SessionTieringLabels {
+        action: action.to_string(),
+    };
+    common_base::counter_metric_inc!(SESSION_TIERING_COUNTER, labels)
+}
+
+pub fn get_session_tiering_counter(action: &str) -> u64 {
+    let labels = SessionTieringLabels {
+        action: action.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(SESSION_TIERING_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct WillGraceLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    WILL_GRACE_COUNTER,
+    "will_grace",
+    "The number of unclean disconnects held back by the will grace period, by outcome.",
+    WillGraceLabels
+);
+
+pub const WILL_GRACE_OUTCOME_SUPPRESSED: &str = "suppressed";
+pub const WILL_GRACE_OUTCOME_REPORTED: &str = "reported";
+
+pub fn incr_will_grace_counter(outcome: &str) {
+    let labels = WillGraceLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(WILL_GRACE_COUNTER, labels)
+}
+
+pub fn get_will_grace_counter(outcome: &str) -> u64 {
+    let labels = WillGraceLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(WILL_GRACE_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct WillAclLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    WILL_ACL_COUNTER,
+    "will_acl",
+    "The number of CONNECT packets with a will topic the client may not publish to, by outcome.",
+    WillAclLabels
+);
+
+pub const WILL_ACL_OUTCOME_REJECTED: &str = "rejected";
+pub const WILL_ACL_OUTCOME_STRIPPED: &str = "stripped";
+
+pub fn incr_will_acl_counter(outcome: &str) {
+    let labels = WillAclLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(WILL_ACL_COUNTER, labels)
+}
+
+pub fn get_will_acl_counter(outcome: &str) -> u64 {
+    let labels = WillAclLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(WILL_ACL_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct SessionTakeoverLabels {
+    client_id: String,
+}
+
+common_base::register_counter_metric!(
+    SESSION_TAKEOVER_COUNTER,
+    "session_takeover",
+    "The number of times a reconnecting client continued its existing session, by client id.",
+    SessionTakeoverLabels
+);
+
+pub fn incr_session_takeover_counter(client_id: &str) {
+    let labels = SessionTakeoverLabels {
+        client_id: client_id.to_string(),
+    };
+    common_base::counter_metric_inc!(SESSION_TAKEOVER_COUNTER, labels)
+}
+
+pub fn get_session_takeover_counter(client_id: &str) -> u64 {
+    let labels = SessionTakeoverLabels {
+        client_id: client_id.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(SESSION_TAKEOVER_COUNTER, labels, res);
+    res
+}
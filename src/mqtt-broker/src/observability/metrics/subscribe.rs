@@ -0,0 +1,70 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct SharedDispatchLabels {
+    group_name: String,
+}
+
+common_base::register_counter_metric!(
+    SHARED_DISPATCH_SKIPPED_FULL_COUNTER,
+    "shared_dispatch_skipped_full",
+    "The number of times a shared subscription consumer was skipped because its inflight window was full.",
+    SharedDispatchLabels
+);
+
+pub fn incr_shared_dispatch_skipped_full_counter(group_name: &str) {
+    let labels = SharedDispatchLabels {
+        group_name: group_name.to_string(),
+    };
+    common_base::counter_metric_inc!(SHARED_DISPATCH_SKIPPED_FULL_COUNTER, labels)
+}
+
+pub fn get_shared_dispatch_skipped_full_counter(group_name: &str) -> u64 {
+    let labels = SharedDispatchLabels {
+        group_name: group_name.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(SHARED_DISPATCH_SKIPPED_FULL_COUNTER, labels, res);
+    res
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct SubscribeRateLimitedLabels {
+    username: String,
+}
+
+common_base::register_counter_metric!(
+    SUBSCRIBE_RATE_LIMITED_COUNTER,
+    "subscribe_rate_limited_packets",
+    "The number of SUBSCRIBE packets rejected because their user exceeded the subscribe rate limit.",
+    SubscribeRateLimitedLabels
+);
+
+pub fn incr_subscribe_rate_limited_counter(username: &str) {
+    let labels = SubscribeRateLimitedLabels {
+        username: username.to_string(),
+    };
+    common_base::counter_metric_inc!(SUBSCRIBE_RATE_LIMITED_COUNTER, labels)
+}
+
+pub fn get_subscribe_rate_limited_counter(username: &str) -> u64 {
+    let labels = SubscribeRateLimitedLabels {
+        username: username.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(SUBSCRIBE_RATE_LIMITED_COUNTER, labels, res);
+    res
+}
@@ -0,0 +1,45 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct TopicCleanupLabels {
+    outcome: String,
+}
+
+common_base::register_counter_metric!(
+    TOPIC_CLEANUP_COUNTER,
+    "topic_cleanup",
+    "The number of idle topics handled by the topic cleanup, by outcome.",
+    TopicCleanupLabels
+);
+
+pub const TOPIC_CLEANUP_OUTCOME_PRUNED: &str = "pruned";
+pub const TOPIC_CLEANUP_OUTCOME_SKIPPED_IN_FLIGHT: &str = "skipped_in_flight";
+
+pub fn incr_topic_cleanup_counter(outcome: &str) {
+    let labels = TopicCleanupLabels {
+        outcome: outcome.to_string(),
+    };
+    common_base::counter_metric_inc!(TOPIC_CLEANUP_COUNTER, labels)
+}
+
+pub fn get_topic_cleanup_counter(outcome: &str) -> u64 {
+    let labels = TopicCleanupLabels {
+        outcome: outcome.to_string(),
+    };
+    let mut res = 0;
+    common_base::counter_metric_get!(TOPIC_CLEANUP_COUNTER, labels, res);
+    res
+}
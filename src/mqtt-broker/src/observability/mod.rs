@@ -21,7 +21,9 @@
 
 use crate::handler::cache::CacheManager;
 
+pub mod alarm_webhook;
 pub mod metrics;
+pub mod sampling;
 pub mod slow;
 pub mod system_topic;
 pub mod warn;
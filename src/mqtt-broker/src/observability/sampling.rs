@@ -0,0 +1,161 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::tools::now_second;
+use common_config::mqtt::config::MessageSampling;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::info;
+
+pub const MESSAGE_SAMPLE_TARGET: &str = "message_sample";
+
+// Decides which published messages are sampled. The config is copied into atomics so the
+// publish path never reads the cluster config, and every check is a few atomic operations.
+#[derive(Default)]
+pub struct MessageSampler {
+    sample_every: AtomicU64,
+    max_per_second: AtomicU64,
+    seen_num: AtomicU64,
+    sampled_num: AtomicU64,
+    window_second: AtomicU64,
+    window_num: AtomicU64,
+}
+
+impl MessageSampler {
+    pub fn set_config(&self, config: &MessageSampling) {
+        self.sample_every
+            .store(config.sample_every, Ordering::Relaxed);
+        self.max_per_second
+            .store(config.max_per_second, Ordering::Relaxed);
+    }
+
+    pub fn should_sample(&self) -> bool {
+        self.should_sample_at(now_second())
+    }
+
+    fn should_sample_at(&self, now: u64) -> bool {
+        let sample_every = self.sample_every.load(Ordering::Relaxed);
+        if sample_every == 0 {
+            return false;
+        }
+
+        // Every N-th message is picked, so the same traffic yields the same sample
+        let seen = self.seen_num.fetch_add(1, Ordering::Relaxed);
+        if seen % sample_every != 0 {
+            return false;
+        }
+
+        let max_per_second = self.max_per_second.load(Ordering::Relaxed);
+        if max_per_second > 0 && !self.acquire_window_slot(now, max_per_second) {
+            return false;
+        }
+
+        self.sampled_num.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    fn acquire_window_slot(&self, now: u64, max_per_second: u64) -> bool {
+        let window = self.window_second.load(Ordering::Acquire);
+        if window != now
+            && self
+                .window_second
+                .compare_exchange(window, now, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            self.window_num.store(0, Ordering::Release);
+        }
+
+        self.window_num
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |num| {
+                (num < max_per_second).then_some(num + 1)
+            })
+            .is_ok()
+    }
+
+    pub fn sample_every(&self) -> u64 {
+        self.sample_every.load(Ordering::Relaxed)
+    }
+
+    pub fn max_per_second(&self) -> u64 {
+        self.max_per_second.load(Ordering::Relaxed)
+    }
+
+    pub fn seen_num(&self) -> u64 {
+        self.seen_num.load(Ordering::Relaxed)
+    }
+
+    pub fn sampled_num(&self) -> u64 {
+        self.sampled_num.load(Ordering::Relaxed)
+    }
+
+    // The share of messages that were actually sampled, which drops below 1/N when the
+    // per second limit kicks in
+    pub fn effective_rate(&self) -> f64 {
+        let seen = self.seen_num();
+        if seen == 0 {
+            return 0.0;
+        }
+        self.sampled_num() as f64 / seen as f64
+    }
+}
+
+pub fn record_message_sample(
+    client_id: &str,
+    topic_name: &str,
+    qos: u8,
+    payload_size: usize,
+    sequence: u64,
+) {
+    info!(
+        target: MESSAGE_SAMPLE_TARGET,
+        client_id, topic_name, qos, payload_size, sequence, "message sampled"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use common_config::mqtt::config::MessageSampling;
+
+    use super::MessageSampler;
+
+    #[test]
+    fn sample_every_test() {
+        let sampler = MessageSampler::default();
+        assert!(!sampler.should_sample_at(1));
+        assert_eq!(sampler.seen_num(), 0);
+
+        sampler.set_config(&MessageSampling {
+            sample_every: 3,
+            max_per_second: 0,
+        });
+        let sampled: Vec<bool> = (0..6).map(|_| sampler.should_sample_at(1)).collect();
+        assert_eq!(sampled, vec![true, false, false, true, false, false]);
+        assert_eq!(sampler.sampled_num(), 2);
+        assert_eq!(sampler.effective_rate(), 2.0 / 6.0);
+    }
+
+    #[test]
+    fn max_per_second_test() {
+        let sampler = MessageSampler::default();
+        sampler.set_config(&MessageSampling {
+            sample_every: 1,
+            max_per_second: 2,
+        });
+
+        let sampled = (0..5).filter(|_| sampler.should_sample_at(10)).count();
+        assert_eq!(sampled, 2);
+
+        assert!(sampler.should_sample_at(11));
+        assert_eq!(sampler.sampled_num(), 3);
+    }
+}
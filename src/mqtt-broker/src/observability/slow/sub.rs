@@ -80,6 +80,28 @@ pub fn connect_regex_pattern(sub_name: String, client_id: String, topic: String)
     pattern
 }
 
+// The threshold of a query, a request without one uses the threshold the cluster records with
+pub fn slow_sub_threshold_ms(threshold_ms: u64, whole_ms: u64) -> u64 {
+    if threshold_ms == 0 {
+        whole_ms
+    } else {
+        threshold_ms
+    }
+}
+
+fn exceeds_threshold(record: &str, threshold_ms: u64) -> bool {
+    if threshold_ms == 0 {
+        return true;
+    }
+    // A record that cannot be parsed is kept, the caller reports it
+    match serde_json::from_str::<SlowSubData>(record) {
+        Ok(data) => data.time_ms > threshold_ms,
+        Err(_) => true,
+    }
+}
+
+// Read the most recent records matching the filters of the request. Only records slower than
+// threshold_ms are returned, so the list limit applies to the records above the threshold.
 pub fn read_slow_sub_record(
     search_options: ListSlowSubscribeRequest,
     path: PathBuf,
@@ -98,6 +120,9 @@ pub fn read_slow_sub_record(
             &file.unwrap(),
             UTF8(|_lnum, line| {
                 let match_byte = matcher.find(line.as_bytes())?.unwrap();
+                if !exceeds_threshold(&line[match_byte], search_options.threshold_ms) {
+                    return Ok(true);
+                }
                 if matches_queue.len() == search_options.list as usize {
                     matches_queue.pop_front();
                     matches_queue.push_back(line[match_byte].to_string())
@@ -116,6 +141,71 @@ pub fn read_slow_sub_record(
 mod tests {
     use super::*;
 
+    fn write_records(name: &str, latencies: &[u64]) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let lines: Vec<String> = latencies
+            .iter()
+            .enumerate()
+            .map(|(i, time_ms)| {
+                let data = SlowSubData {
+                    sub_name: "sub".to_string(),
+                    client_id: format!("client-{}", i),
+                    topic: "t/1".to_string(),
+                    time_ms: *time_ms,
+                    node_info: "RobustMQ-MQTT@127.0.0.1".to_string(),
+                    create_time: 1733488597,
+                };
+                serde_json::to_string(&data).unwrap()
+            })
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    fn read_latencies(path: &PathBuf, list: u64, threshold_ms: u64) -> Vec<u64> {
+        let request = ListSlowSubscribeRequest {
+            list,
+            threshold_ms,
+            ..Default::default()
+        };
+        read_slow_sub_record(request, path.clone())
+            .unwrap()
+            .iter()
+            .map(|line| serde_json::from_str::<SlowSubData>(line).unwrap().time_ms)
+            .collect()
+    }
+
+    #[test]
+    fn test_slow_sub_threshold_ms() {
+        assert_eq!(slow_sub_threshold_ms(0, 500), 500);
+        assert_eq!(slow_sub_threshold_ms(1000, 500), 1000);
+        assert_eq!(slow_sub_threshold_ms(100, 500), 100);
+    }
+
+    #[test]
+    fn test_read_slow_record_threshold() {
+        let path = write_records(
+            "robustmq_slow_sub_threshold_test.log",
+            &[120, 800, 45, 1500, 600, 3000],
+        );
+
+        assert_eq!(
+            read_latencies(&path, 100, 0),
+            vec![120, 800, 45, 1500, 600, 3000]
+        );
+        assert_eq!(
+            read_latencies(&path, 100, 100),
+            vec![120, 800, 1500, 600, 3000]
+        );
+        assert_eq!(read_latencies(&path, 100, 600), vec![800, 1500, 3000]);
+        assert_eq!(read_latencies(&path, 100, 3000), Vec::<u64>::new());
+
+        // The list limit keeps the most recent records above the threshold
+        assert_eq!(read_latencies(&path, 2, 600), vec![1500, 3000]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
     #[test]
     fn test_regex_pattern_param_is_empty() {
         let sub_name = "".to_string();
@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use crate::handler::cache::CacheManager;
+use crate::observability::alarm_webhook::AlarmWebhookPayload;
 use crate::observability::system_topic::{replace_topic_name, write_topic_data};
 
 use common_config::mqtt::broker_mqtt_conf;
+use common_config::mqtt::config::{SystemAlarmComparator, SystemAlarmMetric, SystemAlarmThreshold};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::message::MqttMessage;
 use serde::{Deserialize, Serialize};
@@ -68,6 +70,52 @@ pub struct SystemAlarmEventMessage {
     pub activated: bool,
 }
 
+// Number of resolved alarms kept for the alarm history, the oldest ones are dropped first
+pub const SYSTEM_ALARM_HISTORY_CAPACITY: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SystemAlarmState {
+    // Activated and not acknowledged yet
+    Active,
+    // Activated and acknowledged by an operator, it stays listed until it resolves
+    Acknowledged,
+    // Deactivated after having been activated, kept in the alarm history
+    Resolved,
+}
+
+impl SystemAlarmState {
+    pub fn parse(state: &str) -> Option<Self> {
+        match state.to_lowercase().as_str() {
+            "active" => Some(SystemAlarmState::Active),
+            "acknowledged" => Some(SystemAlarmState::Acknowledged),
+            "resolved" => Some(SystemAlarmState::Resolved),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemAlarmState::Active => "active",
+            SystemAlarmState::Acknowledged => "acknowledged",
+            SystemAlarmState::Resolved => "resolved",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemAlarmAck {
+    pub acknowledged_by: String,
+    pub acknowledged_at: i64,
+}
+
+#[derive(Clone, Default)]
+pub struct ResolvedSystemAlarm {
+    // The alarm as it was last seen activated
+    pub event: SystemAlarmEventMessage,
+    pub ack: Option<SystemAlarmAck>,
+    pub resolved_at: i64,
+}
+
 pub async fn st_check_system_alarm<S>(
     client_pool: &Arc<ClientPool>,
     metadata_cache: &Arc<CacheManager>,
@@ -109,6 +157,120 @@ pub async fn st_check_system_alarm<S>(
         mqtt_conf.system_monitor.os_memory_high_watermark,
     )
     .await;
+
+    let values = SystemAlarmMetricValues {
+        cpu_usage,
+        memory_usage,
+        connection_num: metadata_cache.connection_info.len() as u64,
+        session_num: metadata_cache.session_info.len() as u64,
+    };
+    st_check_threshold_alarms(
+        client_pool,
+        metadata_cache,
+        message_storage_adapter,
+        &values,
+    )
+    .await;
+}
+
+// The current values of the metrics a configured threshold can watch
+#[derive(Clone, Copy, Default)]
+pub struct SystemAlarmMetricValues {
+    pub cpu_usage: f32,
+    pub memory_usage: f32,
+    pub connection_num: u64,
+    pub session_num: u64,
+}
+
+impl SystemAlarmMetricValues {
+    fn value(&self, metric: SystemAlarmMetric) -> f64 {
+        match metric {
+            SystemAlarmMetric::CpuUsage => self.cpu_usage as f64,
+            SystemAlarmMetric::MemoryUsage => self.memory_usage as f64,
+            SystemAlarmMetric::ConnectionNum => self.connection_num as f64,
+            SystemAlarmMetric::SessionNum => self.session_num as f64,
+        }
+    }
+}
+
+pub fn parse_alarm_metric(metric: &str) -> Option<SystemAlarmMetric> {
+    match metric {
+        "cpu_usage" => Some(SystemAlarmMetric::CpuUsage),
+        "memory_usage" => Some(SystemAlarmMetric::MemoryUsage),
+        "connection_num" => Some(SystemAlarmMetric::ConnectionNum),
+        "session_num" => Some(SystemAlarmMetric::SessionNum),
+        _ => None,
+    }
+}
+
+pub fn alarm_metric_name(metric: SystemAlarmMetric) -> &'static str {
+    match metric {
+        SystemAlarmMetric::CpuUsage => "cpu_usage",
+        SystemAlarmMetric::MemoryUsage => "memory_usage",
+        SystemAlarmMetric::ConnectionNum => "connection_num",
+        SystemAlarmMetric::SessionNum => "session_num",
+    }
+}
+
+pub fn parse_alarm_comparator(comparator: &str) -> Option<SystemAlarmComparator> {
+    match comparator {
+        ">" => Some(SystemAlarmComparator::GreaterThan),
+        ">=" => Some(SystemAlarmComparator::GreaterOrEqual),
+        "<" => Some(SystemAlarmComparator::LessThan),
+        "<=" => Some(SystemAlarmComparator::LessOrEqual),
+        _ => None,
+    }
+}
+
+pub fn alarm_comparator_symbol(comparator: SystemAlarmComparator) -> &'static str {
+    match comparator {
+        SystemAlarmComparator::GreaterThan => ">",
+        SystemAlarmComparator::GreaterOrEqual => ">=",
+        SystemAlarmComparator::LessThan => "<",
+        SystemAlarmComparator::LessOrEqual => "<=",
+    }
+}
+
+// The alarm of a threshold is named after its condition, e.g. "connection_num > 10000"
+pub fn threshold_alarm_name(threshold: &SystemAlarmThreshold) -> String {
+    format!(
+        "{} {} {}",
+        alarm_metric_name(threshold.metric),
+        alarm_comparator_symbol(threshold.comparator),
+        threshold.threshold
+    )
+}
+
+pub async fn st_check_threshold_alarms<S>(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+    values: &SystemAlarmMetricValues,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    for threshold in metadata_cache.get_system_monitor_config().thresholds {
+        let value = values.value(threshold.metric);
+        let message = SystemAlarmEventMessage {
+            name: threshold_alarm_name(&threshold),
+            message: format!(
+                "{} is {}, alarm when {} {}",
+                alarm_metric_name(threshold.metric),
+                value,
+                alarm_comparator_symbol(threshold.comparator),
+                threshold.threshold
+            ),
+            activate_at: chrono::Utc::now().timestamp(),
+            activated: threshold.comparator.compare(value, threshold.threshold),
+        };
+        record_system_alarm_event(
+            client_pool,
+            metadata_cache,
+            message_storage_adapter,
+            message,
+        )
+        .await;
+    }
 }
 
 async fn is_send_a_new_system_event<S>(
@@ -139,10 +301,43 @@ async fn is_send_a_new_system_event<S>(
         message.activated = current_usage > config_usage;
     }
 
-    let is_send_message = match metadata_cache.get_alarm_event(alarm_type.as_str()) {
-        None => true,
-        Some(alarm_message) => alarm_message.activated != message.activated,
-    };
+    record_system_alarm_event(
+        client_pool,
+        metadata_cache,
+        message_storage_adapter,
+        message,
+    )
+    .await;
+}
+
+// Publishes the alarm when it is new or its state changed, then keeps it as the latest check.
+// The webhooks are only told when the alarm fires or resolves.
+async fn record_system_alarm_event<S>(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<CacheManager>,
+    message_storage_adapter: &Arc<S>,
+    message: SystemAlarmEventMessage,
+) where
+    S: StorageAdapter + Clone + Send + Sync + 'static,
+{
+    let previous_activated = metadata_cache
+        .get_alarm_event(&message.name)
+        .map(|alarm_message| alarm_message.activated);
+    let is_send_message = previous_activated != Some(message.activated);
+
+    if previous_activated.unwrap_or(false) != message.activated {
+        let conf = broker_mqtt_conf();
+        let payload = AlarmWebhookPayload::new(
+            &conf.cluster_name,
+            conf.broker_id,
+            &message,
+            chrono::Utc::now().timestamp(),
+        );
+        metadata_cache.alarm_webhook.notify(
+            &metadata_cache.get_system_monitor_config().webhook,
+            &payload,
+        );
+    }
 
     if is_send_message {
         st_report_system_alarm_event(
@@ -154,7 +349,7 @@ async fn is_send_a_new_system_event<S>(
         .await;
     }
 
-    metadata_cache.add_alarm_event(alarm_type.to_string(), message);
+    metadata_cache.add_alarm_event(message.name.clone(), message);
 }
 
 pub async fn st_report_system_alarm_event<S>(
@@ -245,7 +440,7 @@ mod tests {
     use crate::storage::message::cluster_name;
 
     use common_base::tools::unique_id;
-    use common_config::mqtt::init_broker_mqtt_conf_by_path;
+    use common_config::mqtt::{default_broker_mqtt, init_broker_mqtt_conf_by_path};
     use metadata_struct::adapter::read_config::ReadConfig;
     use metadata_struct::mqtt::topic::MqttTopic;
     use storage_adapter::memory::MemoryStorageAdapter;
@@ -621,4 +816,197 @@ async fn test_is_send_a_new_system_event_metadata_param_is_different() {
             except_memory_value.activated
         );
     }
+
+    #[tokio::test]
+    async fn test_check_threshold_alarms() {
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+        let client_pool = Arc::new(ClientPool::new(3));
+        let metadata_cache = Arc::new(CacheManager::new(client_pool.clone(), cluster_name()));
+        let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+        let mut topic_ids = Vec::new();
+        for topic in [
+            SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE,
+            SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE,
+        ] {
+            let topic_name = replace_topic_name(topic.to_string());
+            let mqtt_topic = MqttTopic::new(unique_id(), cluster_name(), topic_name.clone());
+            topic_ids.push(mqtt_topic.topic_id.clone());
+            metadata_cache.add_topic(&topic_name, &mqtt_topic);
+        }
+
+        let mut config = default_broker_mqtt();
+        config.system_monitor.thresholds = vec![
+            SystemAlarmThreshold {
+                metric: SystemAlarmMetric::ConnectionNum,
+                comparator: SystemAlarmComparator::GreaterThan,
+                threshold: 100.0,
+            },
+            SystemAlarmThreshold {
+                metric: SystemAlarmMetric::MemoryUsage,
+                comparator: SystemAlarmComparator::GreaterOrEqual,
+                threshold: 90.0,
+            },
+        ];
+        metadata_cache.set_cluster_config(config);
+
+        let check = |connection_num: u64, memory_usage: f32| {
+            let client_pool = client_pool.clone();
+            let metadata_cache = metadata_cache.clone();
+            let message_storage_adapter = message_storage_adapter.clone();
+            async move {
+                let values = SystemAlarmMetricValues {
+                    connection_num,
+                    memory_usage,
+                    ..Default::default()
+                };
+                st_check_threshold_alarms(
+                    &client_pool,
+                    &metadata_cache,
+                    &message_storage_adapter,
+                    &values,
+                )
+                .await;
+            }
+        };
+        let activated = |name: &str| metadata_cache.get_alarm_event(name).unwrap().activated;
+        let published = |topic_id: &String| {
+            let message_storage_adapter = message_storage_adapter.clone();
+            let topic_id = topic_id.clone();
+            async move {
+                let read_config = ReadConfig {
+                    max_record_num: 10,
+                    max_size: 1024 * 1024 * 1024,
+                };
+                message_storage_adapter
+                    .read_by_offset(cluster_name(), topic_id, 0, read_config)
+                    .await
+                    .unwrap()
+                    .len()
+            }
+        };
+
+        // Below every threshold, the alarms are checked but not raised
+        check(100, 50.0).await;
+        assert!(!activated("connection_num > 100"));
+        assert!(!activated("memory_usage >= 90"));
+        assert_eq!(published(&topic_ids[0]).await, 0);
+
+        // Crossing the connection threshold raises only its alarm
+        check(101, 50.0).await;
+        let event = metadata_cache
+            .get_alarm_event("connection_num > 100")
+            .unwrap();
+        assert!(event.activated);
+        assert_eq!(event.message, "connection_num is 101, alarm when > 100");
+        assert!(!activated("memory_usage >= 90"));
+        assert_eq!(published(&topic_ids[0]).await, 1);
+
+        // The comparator decides whether the threshold itself is crossed
+        check(101, 90.0).await;
+        assert!(activated("memory_usage >= 90"));
+        assert_eq!(published(&topic_ids[0]).await, 2);
+
+        // Falling back deactivates the alarm and resolves it
+        check(20, 90.0).await;
+        assert!(!activated("connection_num > 100"));
+        assert!(activated("memory_usage >= 90"));
+        assert_eq!(
+            metadata_cache.list_resolved_alarms()[0].event.name,
+            "connection_num > 100"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_threshold_alarm_webhook() {
+        use axum::body::Bytes;
+        use axum::extract::State;
+        use axum::routing::post;
+        use axum::Router;
+        use std::sync::Mutex;
+        use tokio::net::TcpListener;
+
+        let path = format!(
+            "{}/../../config/mqtt-server.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_broker_mqtt_conf_by_path(&path);
+        let client_pool = Arc::new(ClientPool::new(3));
+        let metadata_cache = Arc::new(CacheManager::new(client_pool.clone(), cluster_name()));
+        let message_storage_adapter = Arc::new(MemoryStorageAdapter::new());
+
+        let received: Arc<Mutex<Vec<serde_json::Value>>> = Arc::new(Mutex::new(Vec::new()));
+        let app =
+            Router::new()
+                .route(
+                    "/alarm",
+                    post(
+                        |State(received): State<Arc<Mutex<Vec<serde_json::Value>>>>,
+                         body: Bytes| async move {
+                            received
+                                .lock()
+                                .unwrap()
+                                .push(serde_json::from_slice(&body).unwrap());
+                        },
+                    ),
+                )
+                .with_state(received.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mut config = default_broker_mqtt();
+        config.system_monitor.thresholds = vec![SystemAlarmThreshold {
+            metric: SystemAlarmMetric::SessionNum,
+            comparator: SystemAlarmComparator::GreaterOrEqual,
+            threshold: 10.0,
+        }];
+        config.system_monitor.webhook.urls = vec![format!("http://{}/alarm", addr)];
+        metadata_cache.set_cluster_config(config);
+
+        let wait_received = |num: usize| {
+            let received = received.clone();
+            let metadata_cache = metadata_cache.clone();
+            async move {
+                for _ in 0..200 {
+                    if metadata_cache.alarm_webhook.delivered_num() >= num as u64 {
+                        break;
+                    }
+                    sleep(Duration::from_millis(10)).await;
+                }
+                received.lock().unwrap().clone()
+            }
+        };
+
+        // Checks that do not change the state of the alarm post nothing
+        for session_num in [5, 9, 10, 12, 3, 1] {
+            let values = SystemAlarmMetricValues {
+                session_num,
+                ..Default::default()
+            };
+            st_check_threshold_alarms(
+                &client_pool,
+                &metadata_cache,
+                &message_storage_adapter,
+                &values,
+            )
+            .await;
+        }
+        // Every post runs on its own task, so the order of arrival is not fixed
+        let mut received = wait_received(2).await;
+        received.sort_by_key(|payload| payload["event"].to_string());
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0]["event"], "alarm_fired");
+        assert_eq!(received[0]["name"], "session_num >= 10");
+        assert_eq!(received[0]["activated"], true);
+        assert_eq!(received[1]["event"], "alarm_resolved");
+        assert_eq!(received[1]["name"], "session_num >= 10");
+        assert_eq!(received[1]["activated"], false);
+        assert_eq!(metadata_cache.alarm_webhook.delivered_num(), 2);
+    }
 }
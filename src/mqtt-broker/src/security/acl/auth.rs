@@ -17,16 +17,56 @@
 use std::sync::Arc;
 
 use common_base::tools::now_second;
+use common_base::utils::topic_util::{topic_filter_covers, topic_filters_overlap};
+use common_config::mqtt::config::AclDefaultPolicy;
 use ipnet::IpNet;
 use metadata_struct::acl::mqtt_acl::{MqttAclAction, MqttAclPermission};
 use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::mqtt::common::QoS;
-use regex::Regex;
 use tracing::info;
 
+use super::decision_cache::AclDecisionKey;
 use crate::handler::cache::CacheManager;
 use crate::handler::constant::WILDCARD_RESOURCE;
 
+// Why an ACL check allowed or denied a request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AclDecision {
+    SuperUser,
+    Blacklist,
+    RuleDeny,
+    RuleAllow,
+    GroupDeny,
+    GroupAllow,
+    DefaultAllow,
+    DefaultDeny,
+}
+
+impl AclDecision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(
+            self,
+            AclDecision::SuperUser
+                | AclDecision::RuleAllow
+                | AclDecision::GroupAllow
+                | AclDecision::DefaultAllow
+        )
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match self {
+            AclDecision::SuperUser => "super_user",
+            AclDecision::Blacklist => "blacklist",
+            AclDecision::RuleDeny => "rule_deny",
+            AclDecision::RuleAllow => "rule_allow",
+            AclDecision::GroupDeny => "group_deny",
+            AclDecision::GroupAllow => "group_allow",
+            AclDecision::DefaultAllow => "default_allow",
+            AclDecision::DefaultDeny => "default_deny",
+        }
+    }
+}
+
 pub fn is_allow_acl(
     cache_manager: &Arc<CacheManager>,
     connection: &MQTTConnection,
@@ -35,27 +75,110 @@ pub fn is_allow_acl(
     retain: bool,
     _: QoS,
 ) -> bool {
+    check_acl(cache_manager, connection, topic_name, action, retain).is_allowed()
+}
+
+pub fn check_acl(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    action: MqttAclAction,
+    retain: bool,
+) -> AclDecision {
     // check super user
     if is_super_user(cache_manager, &connection.login_user) {
-        return true;
+        return AclDecision::SuperUser;
     }
 
     // check blacklist
     if is_blacklist(cache_manager, connection) {
-        return false;
+        return AclDecision::Blacklist;
     }
 
-    // check acl
-    if is_acl_deny(cache_manager, connection, topic_name, action) {
-        return false;
+    // check acl, the outcome of the rules is cached until a rule of the user or client id
+    // changes. Rules assigned to the connection at login are not part of the cache key, so
+    // such connections always match the rules.
+    let decision = if connection.assigned_acls.is_empty() {
+        let key = AclDecisionKey::new(connection, topic_name, action.clone(), retain);
+        cache_manager
+            .acl_decision_cache
+            .get_or_insert_with(key, || {
+                check_acl_rules(
+                    cache_manager,
+                    connection,
+                    topic_name,
+                    action.clone(),
+                    retain,
+                )
+            })
+    } else {
+        check_acl_rules(
+            cache_manager,
+            connection,
+            topic_name,
+            action.clone(),
+            retain,
+        )
+    };
+    if let Some(decision) = decision {
+        return decision;
+    }
+
+    // the topic shortcuts of the client group only apply when no rule of the user matched
+    if action == MqttAclAction::Publish || action == MqttAclAction::Subscribe {
+        if let Some(group) = cache_manager.get_user_group_policy(&connection.login_user) {
+            if group
+                .deny_topics
+                .iter()
+                .any(|filter| topic_filters_overlap(filter, topic_name))
+            {
+                return AclDecision::GroupDeny;
+            }
+            if group
+                .allow_topics
+                .iter()
+                .any(|filter| topic_filter_covers(filter, topic_name))
+            {
+                return AclDecision::GroupAllow;
+            }
+        }
+    }
+
+    // no rule matched
+    match cache_manager.get_acl_default_policy() {
+        AclDefaultPolicy::Allow => AclDecision::DefaultAllow,
+        AclDefaultPolicy::Deny => AclDecision::DefaultDeny,
+    }
+}
+
+// The decision of the user and client id rules, None when no rule matched
+fn check_acl_rules(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    action: MqttAclAction,
+    retain: bool,
+) -> Option<AclDecision> {
+    // a deny rule wins over an allow rule
+    if is_acl_deny(cache_manager, connection, topic_name, action.clone()) {
+        return Some(AclDecision::RuleDeny);
     }
 
     // check retain acl
     if retain && is_acl_deny(cache_manager, connection, topic_name, MqttAclAction::Retain) {
-        return false;
+        return Some(AclDecision::RuleDeny);
     }
 
-    true
+    if is_acl_match(
+        cache_manager,
+        connection,
+        topic_name,
+        action,
+        MqttAclPermission::Allow,
+    ) {
+        return Some(AclDecision::RuleAllow);
+    }
+    None
 }
 
 fn is_super_user(cache_manager: &Arc<CacheManager>, username: &str) -> bool {
@@ -69,6 +192,15 @@ fn is_super_user(cache_manager: &Arc<CacheManager>, username: &str) -> bool {
 }
 
 pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnection) -> bool {
+    is_blacklist_at(cache_manager, connection, now_second())
+}
+
+// Whether the connection is banned at the time `now`, entries that no longer ban are treated as absent
+pub fn is_blacklist_at(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    now: u64,
+) -> bool {
     // todo: I believe this code can be refactored using the Chain of Responsibility pattern.
     // check user blacklist
     if let Some(data) = cache_manager
@@ -76,7 +208,7 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
         .blacklist_user
         .get(&connection.login_user)
     {
-        if data.end_time > now_second() {
+        if data.is_active(now) {
             info!("user blacklist banned,user:{}", &connection.login_user);
             return true;
         }
@@ -84,8 +216,13 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
 
     if let Some(data) = cache_manager.acl_metadata.get_blacklist_user_match() {
         for raw in data {
-            let re = Regex::new(&format!("^{}$", raw.resource_name)).unwrap();
-            if re.is_match(&connection.login_user) && raw.end_time > now_second() {
+            if raw.is_active(now)
+                && is_blacklist_regex_match(
+                    cache_manager,
+                    &raw.resource_name,
+                    &connection.login_user,
+                )
+            {
                 info!(
                     "user blacklist banned by match,user:{}",
                     &connection.login_user
@@ -101,7 +238,7 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
         .blacklist_client_id
         .get(&connection.client_id)
     {
-        if data.end_time > now_second() {
+        if data.is_active(now) {
             info!(
                 "client_id blacklist banned,client_id:{}",
                 &connection.client_id
@@ -112,8 +249,13 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
 
     if let Some(data) = cache_manager.acl_metadata.get_blacklist_client_id_match() {
         for raw in data {
-            let re = Regex::new(&format!("^{}$", raw.resource_name)).unwrap();
-            if re.is_match(&connection.client_id) && raw.end_time > now_second() {
+            if raw.is_active(now)
+                && is_blacklist_regex_match(
+                    cache_manager,
+                    &raw.resource_name,
+                    &connection.client_id,
+                )
+            {
                 info!(
                     "client_id blacklist banned by match,client_id:{}",
                     &connection.client_id
@@ -129,7 +271,7 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
         .blacklist_ip
         .get(&connection.source_ip_addr)
     {
-        if data.end_time < now_second() {
+        if data.is_active(now) {
             info!(
                 "ip blacklist banned,source_ip_addr:{}",
                 &connection.source_ip_addr
@@ -140,9 +282,7 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
 
     if let Some(data) = cache_manager.acl_metadata.get_blacklist_ip_match() {
         for raw in data {
-            if ip_match(&connection.source_ip_addr, &raw.resource_name)
-                && raw.end_time < now_second()
-            {
+            if raw.is_active(now) && ip_match(&connection.source_ip_addr, &raw.resource_name) {
                 info!(
                     "ip blacklist banned by match,source_ip_addr:{}",
                     &connection.source_ip_addr
@@ -152,14 +292,57 @@ pub fn is_blacklist(cache_manager: &Arc<CacheManager>, connection: &MQTTConnecti
         }
     }
 
+    if let Some(data) = cache_manager.acl_metadata.get_blacklist_ip_regex_match() {
+        for raw in data {
+            if raw.is_active(now)
+                && is_blacklist_regex_match(
+                    cache_manager,
+                    &raw.resource_name,
+                    &connection.source_ip_addr,
+                )
+            {
+                info!(
+                    "ip blacklist banned by regex match,source_ip_addr:{}",
+                    &connection.source_ip_addr
+                );
+                return true;
+            }
+        }
+    }
+
     false
 }
 
+// A pattern stored before patterns were validated may not compile, it then matches nothing
+fn is_blacklist_regex_match(cache_manager: &Arc<CacheManager>, pattern: &str, value: &str) -> bool {
+    cache_manager
+        .acl_metadata
+        .get_blacklist_regex(pattern)
+        .is_some_and(|regex| regex.is_match(value))
+}
+
 fn is_acl_deny(
+    cache_manager: &Arc<CacheManager>,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    action: MqttAclAction,
+) -> bool {
+    is_acl_match(
+        cache_manager,
+        connection,
+        topic_name,
+        action,
+        MqttAclPermission::Deny,
+    )
+}
+
+// Whether a user or client id rule with the given permission covers the request
+fn is_acl_match(
     cache_mamanger: &Arc<CacheManager>,
     connection: &MQTTConnection,
     topic_name: &str,
     action: MqttAclAction,
+    permission: MqttAclPermission,
 ) -> bool {
     // check user acl
     if let Some(acl_list) = cache_mamanger
@@ -168,10 +351,10 @@ fn is_acl_deny(
         .get(&connection.login_user)
     {
         for raw in acl_list.clone() {
-            if topic_match(topic_name, &raw.topic)
+            if topic_match(topic_name, &raw.topic, &permission)
                 && ip_match(&connection.source_ip_addr, &raw.ip)
                 && (raw.action == action || raw.action == MqttAclAction::All)
-                && raw.permission == MqttAclPermission::Deny
+                && raw.permission == permission
             {
                 return true;
             }
@@ -185,23 +368,41 @@ fn is_acl_deny(
         .get(&connection.client_id)
     {
         for raw in client_id_list.clone() {
-            if topic_match(topic_name, &raw.topic)
+            if topic_match(topic_name, &raw.topic, &permission)
                 && ip_match(&connection.source_ip_addr, &raw.ip)
                 && (raw.action == action || raw.action == MqttAclAction::All)
-                && raw.permission == MqttAclPermission::Deny
+                && raw.permission == permission
             {
                 return true;
             }
         }
     }
+
+    // check the acl the HTTP auth service assigned to the connection
+    for raw in connection.assigned_acls.iter() {
+        if topic_match(topic_name, &raw.topic, &permission)
+            && ip_match(&connection.source_ip_addr, &raw.ip)
+            && (raw.action == action || raw.action == MqttAclAction::All)
+            && raw.permission == permission
+        {
+            return true;
+        }
+    }
     false
 }
 
-fn topic_match(topic_name: &str, match_topic_name: &str) -> bool {
+// The topic of a rule is a topic filter. On a subscribe the checked topic is the subscribed
+// filter, an allow rule then has to cover every topic of that filter while a deny rule
+// applies as soon as it matches one of them, so a deny cannot be bypassed with a broader
+// filter. For a topic name both come down to the filter matching the name.
+fn topic_match(topic_name: &str, match_topic_name: &str, permission: &MqttAclPermission) -> bool {
     if match_topic_name == WILDCARD_RESOURCE {
         return true;
     }
-    topic_name == match_topic_name
+    match permission {
+        MqttAclPermission::Allow => topic_filter_covers(match_topic_name, topic_name),
+        MqttAclPermission::Deny => topic_filters_overlap(match_topic_name, topic_name),
+    }
 }
 
 fn ip_match(source_ip_addr: &str, ip_role: &str) -> bool {
@@ -232,7 +433,15 @@ mod test {
     use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
     use metadata_struct::mqtt::user::MqttUser;
 
-    use super::{ip_match, is_acl_deny, is_blacklist, is_super_user, topic_match};
+    use common_config::mqtt::config::{
+        AclDefaultPolicy, BrokerMqttConfig, ClientGroup, ClientGroupPolicy,
+    };
+    use std::collections::HashMap;
+
+    use super::{
+        check_acl, ip_match, is_acl_deny, is_blacklist, is_blacklist_at, is_super_user,
+        topic_match, AclDecision,
+    };
     use crate::handler::cache::CacheManager;
     use crate::handler::constant::WILDCARD_RESOURCE;
 
@@ -246,6 +455,7 @@ pub async fn check_super_user_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            create_time: 0,
         };
         cache_manager.add_user(user.clone());
 
@@ -261,6 +471,7 @@ pub async fn check_super_user_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: false,
+            create_time: 0,
         };
         cache_manager.add_user(user.clone());
         assert!(!is_super_user(&cache_manager, &user.username));
@@ -275,6 +486,7 @@ pub async fn check_black_list_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            create_time: 0,
         };
 
         cache_manager.add_user(user.clone());
@@ -300,6 +512,7 @@ pub async fn check_black_list_test() {
             resource_name: user.username.clone(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         cache_manager.add_blacklist(blacklist);
         assert!(is_blacklist(&cache_manager, &connection));
@@ -309,6 +522,7 @@ pub async fn check_black_list_test() {
             resource_name: user.username.clone(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         cache_manager.add_blacklist(blacklist);
         assert!(is_blacklist(&cache_manager, &connection));
@@ -319,6 +533,7 @@ pub async fn check_black_list_test() {
             resource_name: connection.client_id.clone(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         cache_manager.add_blacklist(blacklist);
         assert!(is_blacklist(&cache_manager, &connection));
@@ -328,6 +543,7 @@ pub async fn check_black_list_test() {
             resource_name: connection.client_id.clone(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         cache_manager.add_blacklist(blacklist);
         assert!(is_blacklist(&cache_manager, &connection));
@@ -338,6 +554,7 @@ pub async fn check_black_list_test() {
             resource_name: connection.source_ip_addr.clone(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         cache_manager.add_blacklist(blacklist);
         assert!(is_blacklist(&cache_manager, &connection));
@@ -347,11 +564,144 @@ pub async fn check_black_list_test() {
             resource_name: "127.0.0.0/24".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         cache_manager.add_blacklist(blacklist);
         assert!(is_blacklist(&cache_manager, &connection));
     }
 
+    #[tokio::test]
+    pub async fn check_black_list_regex_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let connection = |client_id: &str, source_ip_addr: &str| {
+            MQTTConnection::new(ConnectionConfig {
+                connect_id: 1,
+                client_id: client_id.to_string(),
+                receive_maximum: 3,
+                max_packet_size: 3,
+                topic_alias_max: 3,
+                request_problem_info: 1,
+                keep_alive: 2,
+                source_ip_addr: source_ip_addr.to_string(),
+            })
+        };
+
+        cache_manager.add_blacklist(MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::ClientIdMatch,
+            resource_name: "(device|sensor)-[0-9]+".to_string(),
+            end_time: now_second() + 100,
+            desc: "".to_string(),
+            expire_at: None,
+        });
+        for client_id in ["device-1", "device-42", "sensor-7"] {
+            assert!(is_blacklist(
+                &cache_manager,
+                &connection(client_id, "192.168.1.1")
+            ));
+        }
+        for client_id in ["device-a", "my-device-1", "sensor-7x"] {
+            assert!(!is_blacklist(
+                &cache_manager,
+                &connection(client_id, "192.168.1.1")
+            ));
+        }
+
+        cache_manager.add_blacklist(MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::IpMatch,
+            resource_name: r"10\.0\.\d+\.\d+".to_string(),
+            end_time: now_second() + 100,
+            desc: "".to_string(),
+            expire_at: None,
+        });
+        assert!(is_blacklist(&cache_manager, &connection("c1", "10.0.3.4")));
+        assert!(!is_blacklist(&cache_manager, &connection("c1", "10.1.3.4")));
+
+        // an expired entry no longer bans
+        cache_manager.add_blacklist(MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::ClientIdMatch,
+            resource_name: "old-.*".to_string(),
+            end_time: now_second() - 1,
+            desc: "".to_string(),
+            expire_at: None,
+        });
+        assert!(!is_blacklist(
+            &cache_manager,
+            &connection("old-1", "192.168.1.1")
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn check_black_list_expiry_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let connection = MQTTConnection::new(ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: "192.168.1.1".to_string(),
+        });
+
+        // banned for one hour
+        let now = now_second();
+        for (blacklist_type, resource_name) in [
+            (MqttAclBlackListType::ClientId, "client_id-1"),
+            (MqttAclBlackListType::Ip, "192.168.1.1"),
+            (MqttAclBlackListType::IPCIDR, "192.168.1.0/24"),
+        ] {
+            cache_manager.add_blacklist(MqttAclBlackList {
+                blacklist_type,
+                resource_name: resource_name.to_string(),
+                end_time: 0,
+                desc: "".to_string(),
+                expire_at: Some(now + 3600),
+            });
+        }
+        assert!(is_blacklist_at(&cache_manager, &connection, now));
+        assert!(is_blacklist_at(&cache_manager, &connection, now + 3599));
+
+        // once the hour is over the entries are treated as absent, also before they are purged
+        assert!(!is_blacklist_at(&cache_manager, &connection, now + 3600));
+        let expired = cache_manager.acl_metadata.expired_blacklist(now + 3600);
+        assert_eq!(expired.len(), 3);
+        for blacklist in expired {
+            cache_manager.remove_blacklist(blacklist);
+        }
+        assert!(cache_manager
+            .acl_metadata
+            .expired_blacklist(now + 3600)
+            .is_empty());
+        assert!(!is_blacklist_at(&cache_manager, &connection, now));
+
+        // entries without expire_at keep banning until end_time, and never with end_time 0
+        cache_manager.add_blacklist(MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::ClientId,
+            resource_name: "client_id-1".to_string(),
+            end_time: 0,
+            desc: "".to_string(),
+            expire_at: None,
+        });
+        assert!(!is_blacklist_at(&cache_manager, &connection, now));
+
+        cache_manager.add_blacklist(MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::ClientId,
+            resource_name: "client_id-1".to_string(),
+            end_time: now + 60,
+            desc: "".to_string(),
+            expire_at: None,
+        });
+        assert!(is_blacklist_at(&cache_manager, &connection, now + 59));
+        assert!(!is_blacklist_at(&cache_manager, &connection, now + 60));
+        assert!(cache_manager
+            .acl_metadata
+            .expired_blacklist(now + 86400)
+            .is_empty());
+    }
+
     #[tokio::test]
     pub async fn check_empty_acl_test() {
         let client_pool = Arc::new(ClientPool::new(1));
@@ -362,6 +712,7 @@ pub async fn check_empty_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            create_time: 0,
         };
 
         cache_manager.add_user(user.clone());
@@ -403,6 +754,7 @@ pub async fn check_user_wildcard_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            create_time: 0,
         };
 
         cache_manager.add_user(user.clone());
@@ -469,6 +821,7 @@ pub async fn check_user_match_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            create_time: 0,
         };
 
         cache_manager.add_user(user.clone());
@@ -535,6 +888,7 @@ pub async fn check_client_id_wildcard_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            create_time: 0,
         };
 
         cache_manager.add_user(user.clone());
@@ -601,6 +955,7 @@ pub async fn check_client_id_match_acl_test() {
             username: "loboxu".to_string(),
             password: "lobo_123".to_string(),
             is_superuser: true,
+            create_time: 0,
         };
 
         cache_manager.add_user(user.clone());
@@ -661,9 +1016,167 @@ pub async fn check_client_id_match_acl_test() {
     pub async fn topic_match_test() {
         let topic_name = "t1";
         let match_topic_name = WILDCARD_RESOURCE.to_string();
-        assert!(topic_match(topic_name, &match_topic_name));
-        assert!(topic_match(topic_name, topic_name));
-        assert!(!topic_match(topic_name, "v1"));
+        let allow = MqttAclPermission::Allow;
+        let deny = MqttAclPermission::Deny;
+        assert!(topic_match(topic_name, &match_topic_name, &allow));
+        assert!(topic_match(topic_name, topic_name, &allow));
+        assert!(!topic_match(topic_name, "v1", &allow));
+
+        // a subscribed filter is allowed only when the rule covers it, but denied when the
+        // rule matches any of its topics
+        assert!(topic_match("sensors/a/temp", "sensors/+/temp", &allow));
+        assert!(topic_match("sensors/+/temp", "sensors/#", &allow));
+        assert!(!topic_match("sensors/#", "sensors/+/temp", &allow));
+        assert!(topic_match("sensors/#", "sensors/+/temp", &deny));
+        assert!(topic_match("sensors/+/temp", "sensors/a/temp", &deny));
+        assert!(!topic_match("sensors/+/humidity", "sensors/a/temp", &deny));
+    }
+
+    #[tokio::test]
+    pub async fn check_acl_wildcard_rule_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(BrokerMqttConfig::default());
+        let mut security = cache_manager.get_security_config();
+        security.acl_default_policy = AclDefaultPolicy::Deny;
+        cache_manager.update_security_config(security);
+
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: local_hostname(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("user-1".to_string());
+
+        let rules = [
+            (
+                "sensors/+/temp",
+                MqttAclAction::All,
+                MqttAclPermission::Allow,
+            ),
+            (
+                "sensors/secret/#",
+                MqttAclAction::All,
+                MqttAclPermission::Deny,
+            ),
+            (
+                "devices/#",
+                MqttAclAction::Publish,
+                MqttAclPermission::Allow,
+            ),
+            (
+                "devices/+/config",
+                MqttAclAction::Retain,
+                MqttAclPermission::Deny,
+            ),
+        ];
+        for (topic, action, permission) in rules {
+            cache_manager.add_acl(MqttAcl {
+                resource_type: MqttAclResourceType::User,
+                resource_name: "user-1".to_string(),
+                topic: topic.to_string(),
+                ip: WILDCARD_RESOURCE.to_string(),
+                action,
+                permission,
+            });
+        }
+
+        let cases = [
+            // (topic or filter, action, retain, expected)
+            (
+                "sensors/a/temp",
+                MqttAclAction::Publish,
+                false,
+                AclDecision::RuleAllow,
+            ),
+            (
+                "sensors/a/b/temp",
+                MqttAclAction::Publish,
+                false,
+                AclDecision::DefaultDeny,
+            ),
+            (
+                "sensors/a/humidity",
+                MqttAclAction::Publish,
+                false,
+                AclDecision::DefaultDeny,
+            ),
+            // deny wins where an allow and a deny rule overlap
+            (
+                "sensors/secret/temp",
+                MqttAclAction::Publish,
+                false,
+                AclDecision::RuleDeny,
+            ),
+            (
+                "sensors/a/temp",
+                MqttAclAction::Subscribe,
+                false,
+                AclDecision::RuleAllow,
+            ),
+            (
+                "sensors/+/temp",
+                MqttAclAction::Subscribe,
+                false,
+                AclDecision::RuleDeny,
+            ),
+            (
+                "sensors/#",
+                MqttAclAction::Subscribe,
+                false,
+                AclDecision::RuleDeny,
+            ),
+            (
+                "sensors/other/+",
+                MqttAclAction::Subscribe,
+                false,
+                AclDecision::DefaultDeny,
+            ),
+            (
+                "devices/d1/state",
+                MqttAclAction::Publish,
+                true,
+                AclDecision::RuleAllow,
+            ),
+            (
+                "devices/d1/config",
+                MqttAclAction::Publish,
+                false,
+                AclDecision::RuleAllow,
+            ),
+            (
+                "devices/d1/config",
+                MqttAclAction::Publish,
+                true,
+                AclDecision::RuleDeny,
+            ),
+            (
+                "devices/d1/state",
+                MqttAclAction::Subscribe,
+                false,
+                AclDecision::DefaultDeny,
+            ),
+            (
+                "$SYS/brokers",
+                MqttAclAction::Subscribe,
+                false,
+                AclDecision::DefaultDeny,
+            ),
+        ];
+        for (topic, action, retain, expected) in cases {
+            assert_eq!(
+                check_acl(&cache_manager, &connection, topic, action, retain),
+                expected,
+                "{}",
+                topic
+            );
+        }
     }
 
     #[tokio::test]
@@ -676,4 +1189,241 @@ pub async fn ip_match_test() {
         assert!(!ip_match(source_ip, "192.1.1.1"));
         assert!(ip_match(source_ip, "127.0.0.1/24"));
     }
+
+    #[tokio::test]
+    pub async fn check_acl_default_policy_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(BrokerMqttConfig::default());
+
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: local_hostname(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("user-1".to_string());
+
+        let decision = check_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            false,
+        );
+        assert_eq!(decision, AclDecision::DefaultAllow);
+
+        let mut security = cache_manager.get_security_config();
+        security.acl_default_policy = AclDefaultPolicy::Deny;
+        cache_manager.update_security_config(security);
+        let decision = check_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            false,
+        );
+        assert_eq!(decision, AclDecision::DefaultDeny);
+        assert!(!decision.is_allowed());
+
+        cache_manager.add_acl(MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: "user-1".to_string(),
+            topic: "tp-1".to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: MqttAclAction::Publish,
+            permission: MqttAclPermission::Allow,
+        });
+        let decision = check_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            false,
+        );
+        assert_eq!(decision, AclDecision::RuleAllow);
+        let decision = check_acl(
+            &cache_manager,
+            &connection,
+            "tp-2",
+            MqttAclAction::Publish,
+            false,
+        );
+        assert_eq!(decision, AclDecision::DefaultDeny);
+
+        cache_manager.add_acl(MqttAcl {
+            resource_type: MqttAclResourceType::ClientId,
+            resource_name: "client_id-1".to_string(),
+            topic: WILDCARD_RESOURCE.to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: MqttAclAction::All,
+            permission: MqttAclPermission::Deny,
+        });
+        let decision = check_acl(
+            &cache_manager,
+            &connection,
+            "tp-1",
+            MqttAclAction::Publish,
+            false,
+        );
+        assert_eq!(decision, AclDecision::RuleDeny);
+    }
+
+    #[tokio::test]
+    pub async fn check_acl_client_group_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(BrokerMqttConfig::default());
+        cache_manager.update_client_group_config(ClientGroup {
+            groups: HashMap::from([(
+                "sensors".to_string(),
+                ClientGroupPolicy {
+                    allow_topics: vec!["sensors/#".to_string()],
+                    deny_topics: vec!["sensors/admin/#".to_string()],
+                    ..Default::default()
+                },
+            )]),
+            members: HashMap::from([("user-1".to_string(), "sensors".to_string())]),
+            ..Default::default()
+        });
+        let mut security = cache_manager.get_security_config();
+        security.acl_default_policy = AclDefaultPolicy::Deny;
+        cache_manager.update_security_config(security);
+
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: local_hostname(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("user-1".to_string());
+
+        let check = |topic: &str| {
+            check_acl(
+                &cache_manager,
+                &connection,
+                topic,
+                MqttAclAction::Publish,
+                false,
+            )
+        };
+        assert_eq!(check("sensors/s1/temp"), AclDecision::GroupAllow);
+        assert_eq!(check("sensors/admin/reset"), AclDecision::GroupDeny);
+        assert_eq!(check("other/s1"), AclDecision::DefaultDeny);
+
+        // a rule of the user wins over the shortcuts of its group
+        cache_manager.add_acl(MqttAcl {
+            resource_type: MqttAclResourceType::User,
+            resource_name: "user-1".to_string(),
+            topic: "sensors/admin/reset".to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: MqttAclAction::Publish,
+            permission: MqttAclPermission::Allow,
+        });
+        assert_eq!(check("sensors/admin/reset"), AclDecision::RuleAllow);
+    }
+
+    #[tokio::test]
+    pub async fn check_acl_decision_cache_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(BrokerMqttConfig::default());
+
+        let config = ConnectionConfig {
+            connect_id: 1,
+            client_id: "client_id-1".to_string(),
+            receive_maximum: 3,
+            max_packet_size: 3,
+            topic_alias_max: 3,
+            request_problem_info: 1,
+            keep_alive: 2,
+            source_ip_addr: local_hostname(),
+        };
+        let mut connection = MQTTConnection::new(config);
+        connection.login_success("user-1".to_string());
+        let check = |topic: &str| {
+            check_acl(
+                &cache_manager,
+                &connection,
+                topic,
+                MqttAclAction::Publish,
+                false,
+            )
+        };
+        let deny_rule = |resource_type: MqttAclResourceType, resource_name: &str| MqttAcl {
+            resource_type,
+            resource_name: resource_name.to_string(),
+            topic: "tp/#".to_string(),
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: MqttAclAction::Publish,
+            permission: MqttAclPermission::Deny,
+        };
+
+        // The outcome of the rules is cached, also when no rule matched
+        assert_eq!(check("tp/1"), AclDecision::DefaultAllow);
+        assert_eq!(check("tp/1"), AclDecision::DefaultAllow);
+        assert_eq!(cache_manager.acl_decision_cache.len(), 1);
+
+        // A rule of another user leaves the decision cached
+        cache_manager.add_acl(deny_rule(MqttAclResourceType::User, "user-2"));
+        assert_eq!(cache_manager.acl_decision_cache.len(), 1);
+
+        // Creating a rule of the user invalidates the decision
+        let user_rule = deny_rule(MqttAclResourceType::User, "user-1");
+        cache_manager.add_acl(user_rule.clone());
+        assert!(cache_manager.acl_decision_cache.is_empty());
+        assert_eq!(check("tp/1"), AclDecision::RuleDeny);
+
+        // Deleting it does so as well
+        cache_manager.remove_acl(user_rule);
+        assert_eq!(check("tp/1"), AclDecision::DefaultAllow);
+
+        // Rules of the client id invalidate the decisions of its connections
+        let client_rule = deny_rule(MqttAclResourceType::ClientId, "client_id-1");
+        cache_manager.add_acl(client_rule.clone());
+        assert_eq!(check("tp/1"), AclDecision::RuleDeny);
+        cache_manager.remove_acl(client_rule);
+        assert_eq!(check("tp/1"), AclDecision::DefaultAllow);
+
+        // A connection with rules assigned at login neither uses nor fills the cache
+        let mut assigned = connection.clone();
+        assigned.assigned_acls = vec![deny_rule(MqttAclResourceType::ClientId, "client_id-1")];
+        let cached = cache_manager.acl_decision_cache.len();
+        assert_eq!(
+            check_acl(
+                &cache_manager,
+                &assigned,
+                "tp/1",
+                MqttAclAction::Publish,
+                false
+            ),
+            AclDecision::RuleDeny
+        );
+        assert_eq!(cache_manager.acl_decision_cache.len(), cached);
+        assert_eq!(check("tp/1"), AclDecision::DefaultAllow);
+
+        // Syncing the stored rules only invalidates the decisions of rules that changed
+        let other_rule = deny_rule(MqttAclResourceType::User, "user-2");
+        cache_manager.sync_acls(vec![other_rule.clone()]);
+        assert_eq!(cache_manager.acl_decision_cache.len(), 1);
+        cache_manager.sync_acls(vec![
+            other_rule,
+            deny_rule(MqttAclResourceType::User, "user-1"),
+        ]);
+        assert!(cache_manager.acl_decision_cache.is_empty());
+        assert_eq!(check("tp/1"), AclDecision::RuleDeny);
+        cache_manager.sync_acls(Vec::new());
+        assert_eq!(check("tp/1"), AclDecision::DefaultAllow);
+        assert!(cache_manager.acl_metadata.acl_user.is_empty());
+    }
 }
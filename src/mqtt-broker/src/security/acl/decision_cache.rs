@@ -0,0 +1,340 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use metadata_struct::acl::mqtt_acl::MqttAclAction;
+use metadata_struct::mqtt::connection::MQTTConnection;
+
+use super::auth::AclDecision;
+
+// Decisions remembered at most, the least recently used one is evicted beyond that
+pub const ACL_DECISION_CACHE_CAPACITY: usize = 10000;
+
+// Decisions are spread over this many locks by user, so checks of different users do not wait
+// for each other
+pub const ACL_DECISION_CACHE_SHARD_NUM: usize = 16;
+
+// Everything the user and client id rules are matched against
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AclDecisionKey {
+    pub login_user: String,
+    pub client_id: String,
+    pub source_ip_addr: String,
+    pub topic_name: String,
+    pub action: MqttAclAction,
+    pub retain: bool,
+}
+
+impl AclDecisionKey {
+    pub fn new(
+        connection: &MQTTConnection,
+        topic_name: &str,
+        action: MqttAclAction,
+        retain: bool,
+    ) -> Self {
+        AclDecisionKey {
+            login_user: connection.login_user.clone(),
+            client_id: connection.client_id.clone(),
+            source_ip_addr: connection.source_ip_addr.clone(),
+            topic_name: topic_name.to_owned(),
+            action,
+            retain,
+        }
+    }
+}
+
+#[derive(Default)]
+struct AclDecisionLru {
+    // (key, (rule decision, last use))
+    entries: HashMap<AclDecisionKey, (Option<AclDecision>, u64)>,
+    // (last use, key), the least recently used key first
+    order: BTreeMap<u64, AclDecisionKey>,
+    tick: u64,
+    // Bumped by every invalidation, a decision matched against older rules is not cached
+    generation: u64,
+}
+
+impl AclDecisionLru {
+    fn touch(&mut self, key: &AclDecisionKey) -> Option<Option<AclDecision>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (decision, last_use) = self.entries.get_mut(key)?;
+        self.order.remove(last_use);
+        *last_use = tick;
+        self.order.insert(tick, key.clone());
+        Some(*decision)
+    }
+
+    fn insert(&mut self, key: AclDecisionKey, decision: Option<AclDecision>, capacity: usize) {
+        self.tick += 1;
+        if let Some((_, last_use)) = self.entries.remove(&key) {
+            self.order.remove(&last_use);
+        }
+        while self.entries.len() >= capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (decision, self.tick));
+    }
+
+    fn remove_where(&mut self, f: impl Fn(&AclDecisionKey) -> bool) {
+        self.generation += 1;
+        let order = &mut self.order;
+        self.entries.retain(|key, (_, last_use)| {
+            if f(key) {
+                order.remove(last_use);
+                return false;
+            }
+            true
+        });
+    }
+}
+
+// The outcome of matching the user and client id rules against a request, None when no rule
+// matched. Checks of the same user and topic skip the scan of the rules until a rule of that
+// user or client id changes.
+pub struct AclDecisionCache {
+    // Capacity of every shard
+    capacity: usize,
+    shards: Vec<Mutex<AclDecisionLru>>,
+}
+
+impl Default for AclDecisionCache {
+    fn default() -> Self {
+        AclDecisionCache::new(ACL_DECISION_CACHE_CAPACITY, ACL_DECISION_CACHE_SHARD_NUM)
+    }
+}
+
+impl AclDecisionCache {
+    pub fn new(capacity: usize, shard_num: usize) -> Self {
+        let shard_num = shard_num.max(1);
+        AclDecisionCache {
+            capacity: capacity.div_ceil(shard_num),
+            shards: (0..shard_num)
+                .map(|_| Mutex::new(AclDecisionLru::default()))
+                .collect(),
+        }
+    }
+
+    // The decisions of a user are kept in one shard
+    fn shard(&self, login_user: &str) -> &Mutex<AclDecisionLru> {
+        let mut hasher = DefaultHasher::new();
+        login_user.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    pub fn get(&self, key: &AclDecisionKey) -> Option<Option<AclDecision>> {
+        self.shard(&key.login_user).lock().unwrap().touch(key)
+    }
+
+    pub fn insert(&self, key: AclDecisionKey, decision: Option<AclDecision>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.shard(&key.login_user)
+            .lock()
+            .unwrap()
+            .insert(key, decision, self.capacity);
+    }
+
+    pub fn get_or_insert_with(
+        &self,
+        key: AclDecisionKey,
+        f: impl FnOnce() -> Option<AclDecision>,
+    ) -> Option<AclDecision> {
+        let shard = self.shard(&key.login_user);
+        let generation = {
+            let mut lru = shard.lock().unwrap();
+            if let Some(decision) = lru.touch(&key) {
+                return decision;
+            }
+            lru.generation
+        };
+
+        // The rules are matched outside the lock. When a rule changed meanwhile the decision
+        // may be stale and is returned without caching it.
+        let decision = f();
+        if self.capacity > 0 {
+            let mut lru = shard.lock().unwrap();
+            if lru.generation == generation {
+                lru.insert(key, decision, self.capacity);
+            }
+        }
+        decision
+    }
+
+    // A rule of the user changed
+    pub fn invalidate_user(&self, login_user: &str) {
+        self.shard(login_user)
+            .lock()
+            .unwrap()
+            .remove_where(|key| key.login_user == login_user);
+    }
+
+    // A rule of the client id changed, the client id may be used by any user
+    pub fn invalidate_client_id(&self, client_id: &str) {
+        for shard in self.shards.iter() {
+            shard
+                .lock()
+                .unwrap()
+                .remove_where(|key| key.client_id == client_id);
+        }
+    }
+
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.lock().unwrap().remove_where(|_| true);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().entries.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata_struct::acl::mqtt_acl::MqttAclAction;
+
+    use super::{AclDecisionCache, AclDecisionKey};
+    use crate::security::acl::auth::AclDecision;
+
+    fn key(login_user: &str, client_id: &str, topic_name: &str) -> AclDecisionKey {
+        AclDecisionKey {
+            login_user: login_user.to_string(),
+            client_id: client_id.to_string(),
+            source_ip_addr: "127.0.0.1".to_string(),
+            topic_name: topic_name.to_string(),
+            action: MqttAclAction::Publish,
+            retain: false,
+        }
+    }
+
+    #[test]
+    fn acl_decision_cache_eviction_test() {
+        let cache = AclDecisionCache::new(3, 1);
+        cache.insert(key("u1", "c1", "t/1"), Some(AclDecision::RuleAllow));
+        cache.insert(key("u1", "c1", "t/2"), None);
+        cache.insert(key("u1", "c1", "t/3"), Some(AclDecision::RuleDeny));
+        assert_eq!(cache.len(), 3);
+
+        // Using t/1 makes t/2 the least recently used entry
+        assert_eq!(
+            cache.get(&key("u1", "c1", "t/1")),
+            Some(Some(AclDecision::RuleAllow))
+        );
+        cache.insert(key("u1", "c1", "t/4"), None);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get(&key("u1", "c1", "t/2")), None);
+        assert!(cache.get(&key("u1", "c1", "t/1")).is_some());
+        assert!(cache.get(&key("u1", "c1", "t/3")).is_some());
+        assert_eq!(cache.get(&key("u1", "c1", "t/4")), Some(None));
+
+        // Replacing an entry does not evict another one
+        cache.insert(key("u1", "c1", "t/4"), Some(AclDecision::RuleDeny));
+        assert_eq!(cache.len(), 3);
+
+        let cache = AclDecisionCache::new(0, 1);
+        cache.insert(key("u1", "c1", "t/1"), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn acl_decision_cache_invalidate_test() {
+        let cache = AclDecisionCache::new(10, 1);
+        cache.insert(key("u1", "c1", "t/1"), None);
+        cache.insert(key("u1", "c2", "t/1"), None);
+        cache.insert(key("u2", "c3", "t/1"), None);
+
+        cache.invalidate_user("u1");
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&key("u2", "c3", "t/1")).is_some());
+
+        cache.insert(key("u1", "c1", "t/1"), None);
+        cache.invalidate_client_id("c3");
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&key("u1", "c1", "t/1")).is_some());
+
+        // Entries left after an invalidation are still evicted in order
+        let cache = AclDecisionCache::new(2, 1);
+        cache.insert(key("u1", "c1", "t/1"), None);
+        cache.insert(key("u2", "c2", "t/1"), None);
+        cache.invalidate_user("u1");
+        cache.insert(key("u3", "c3", "t/1"), None);
+        cache.insert(key("u4", "c4", "t/1"), None);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key("u2", "c2", "t/1")).is_none());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn acl_decision_cache_stale_decision_test() {
+        let cache = AclDecisionCache::new(10, 1);
+        assert_eq!(
+            cache.get_or_insert_with(key("u1", "c1", "t/1"), || Some(AclDecision::RuleAllow)),
+            Some(AclDecision::RuleAllow)
+        );
+        // Cached, the rules are not matched again
+        assert_eq!(
+            cache.get_or_insert_with(key("u1", "c1", "t/1"), || unreachable!()),
+            Some(AclDecision::RuleAllow)
+        );
+
+        // A rule changed while the decision was being matched, it is not cached
+        let decision = cache.get_or_insert_with(key("u1", "c1", "t/2"), || {
+            cache.invalidate_user("u1");
+            Some(AclDecision::RuleAllow)
+        });
+        assert_eq!(decision, Some(AclDecision::RuleAllow));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn acl_decision_cache_shard_test() {
+        let cache = AclDecisionCache::new(64, 4);
+        for i in 0..8 {
+            cache.insert(key(&format!("u{i}"), &format!("c{i}"), "t/1"), None);
+        }
+        cache.insert(key("u1", "c0", "t/2"), None);
+        assert_eq!(cache.len(), 9);
+
+        cache.invalidate_user("u1");
+        assert_eq!(cache.len(), 7);
+        assert!(cache.get(&key("u1", "c1", "t/1")).is_none());
+
+        // A client id is invalidated in every shard
+        cache.invalidate_client_id("c0");
+        assert_eq!(cache.len(), 6);
+        assert!(cache.get(&key("u0", "c0", "t/1")).is_none());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -13,13 +13,14 @@
 // limitations under the License.
 
 use crate::handler::error::MqttBrokerError;
-use crate::handler::flapping_detect::FlappingDetectCondition;
-use common_base::enum_type::time_unit_enum::TimeUnit;
-use common_base::tools::{convert_seconds, now_second};
+use crate::handler::flapping_detect::{FlappingDetectBan, FlappingDetectCondition};
+use common_base::tools::now_second;
 use common_config::mqtt::config::FlappingDetect;
 use dashmap::DashMap;
 use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclResourceType};
 use metadata_struct::acl::mqtt_blacklist::{MqttAclBlackList, MqttAclBlackListType};
+use regex::Regex;
+use std::collections::HashMap;
 
 #[derive(Clone)]
 pub struct AclMetadata {
@@ -30,6 +31,8 @@ pub struct AclMetadata {
     pub blacklist_user_match: DashMap<String, Vec<MqttAclBlackList>>,
     pub blacklist_client_id_match: DashMap<String, Vec<MqttAclBlackList>>,
     pub blacklist_ip_match: DashMap<String, Vec<MqttAclBlackList>>,
+    // compiled patterns of the match kinds, so that a connection does not compile them again
+    pub blacklist_regex: DashMap<String, Regex>,
 
     // acl
     pub acl_user: DashMap<String, Vec<MqttAcl>>,
@@ -37,6 +40,12 @@ pub struct AclMetadata {
 
     // connection jitter (client_id, FlappingDetectCondition)
     pub flapping_detect_map: DashMap<String, FlappingDetectCondition>,
+
+    // clients banned by flapping detect (client_id, FlappingDetectBan)
+    pub flapping_detect_bans: DashMap<String, FlappingDetectBan>,
+
+    // compiled patterns of the flapping detect allowlist
+    pub flapping_detect_allowlist_regex: DashMap<String, Regex>,
 }
 
 impl Default for AclMetadata {
@@ -54,10 +63,13 @@ pub fn new() -> Self {
             blacklist_user_match: DashMap::with_capacity(2),
             blacklist_client_id_match: DashMap::with_capacity(2),
             blacklist_ip_match: DashMap::with_capacity(2),
+            blacklist_regex: DashMap::with_capacity(2),
 
             acl_user: DashMap::with_capacity(2),
             acl_client_id: DashMap::with_capacity(2),
             flapping_detect_map: DashMap::new(),
+            flapping_detect_bans: DashMap::new(),
+            flapping_detect_allowlist_regex: DashMap::new(),
         }
     }
 
@@ -82,6 +94,16 @@ pub fn add_flapping_detect_condition(
         );
     }
 
+    pub fn get_flapping_detect_allowlist_regex(&self, pattern: &str) -> Option<Regex> {
+        if let Some(regex) = self.flapping_detect_allowlist_regex.get(pattern) {
+            return Some(regex.clone());
+        }
+        let regex = build_blacklist_regex(pattern).ok()?;
+        self.flapping_detect_allowlist_regex
+            .insert(pattern.to_string(), regex.clone());
+        Some(regex)
+    }
+
     pub fn remove_flapping_detect_condition(&self, client_id: &str) {
         self.flapping_detect_map.remove(client_id);
     }
@@ -91,7 +113,7 @@ pub async fn remove_flapping_detect_conditions(
         config: FlappingDetect,
     ) -> Result<(), MqttBrokerError> {
         let current_time = now_second();
-        let window_time = convert_seconds(config.window_time as u64, TimeUnit::Minutes);
+        let window_time = config.window_sec();
         self.flapping_detect_map
             .retain(|_, flapping_detect_condition| {
                 // we need retain elements within window_time,
@@ -101,6 +123,16 @@ pub async fn remove_flapping_detect_conditions(
         Ok(())
     }
 
+    pub fn add_flapping_detect_ban(&self, ban: FlappingDetectBan) {
+        self.flapping_detect_bans.insert(ban.client_id.clone(), ban);
+    }
+
+    pub fn remove_flapping_detect_ban(&self, client_id: &str) -> Option<FlappingDetectBan> {
+        self.flapping_detect_bans
+            .remove(client_id)
+            .map(|(_, ban)| ban)
+    }
+
     // ACL
     pub fn parse_mqtt_acl(&self, acl: MqttAcl) {
         match acl.resource_type {
@@ -134,6 +166,35 @@ pub fn remove_mqtt_acl(&self, acl: MqttAcl) {
         }
     }
 
+    // Replace the rules of one resource type, returns the resource names whose rules changed
+    pub fn replace_mqtt_acls(
+        &self,
+        resource_type: MqttAclResourceType,
+        acls: HashMap<String, Vec<MqttAcl>>,
+    ) -> Vec<String> {
+        let cached = match resource_type {
+            MqttAclResourceType::User => &self.acl_user,
+            MqttAclResourceType::ClientId => &self.acl_client_id,
+        };
+
+        let mut changed = Vec::new();
+        cached.retain(|resource_name, _| {
+            if acls.contains_key(resource_name) {
+                return true;
+            }
+            changed.push(resource_name.clone());
+            false
+        });
+        for (resource_name, list) in acls {
+            if cached.get(&resource_name).is_some_and(|raw| *raw == list) {
+                continue;
+            }
+            cached.insert(resource_name.clone(), list);
+            changed.push(resource_name);
+        }
+        changed
+    }
+
     // Blacklist
     pub fn parse_mqtt_blacklist(&self, blacklist: MqttAclBlackList) {
         match blacklist.blacklist_type {
@@ -151,6 +212,7 @@ pub fn parse_mqtt_blacklist(&self, blacklist: MqttAclBlackList) {
             }
             MqttAclBlackListType::ClientIdMatch => {
                 let key = self.get_client_id_match_key();
+                self.add_blacklist_regex(&blacklist.resource_name);
                 if let Some(mut data) = self.blacklist_client_id_match.get_mut(&key) {
                     data.push(blacklist)
                 } else {
@@ -159,6 +221,7 @@ pub fn parse_mqtt_blacklist(&self, blacklist: MqttAclBlackList) {
             }
             MqttAclBlackListType::UserMatch => {
                 let key = self.get_user_match_key();
+                self.add_blacklist_regex(&blacklist.resource_name);
                 if let Some(mut data) = self.blacklist_user_match.get_mut(&key) {
                     data.push(blacklist)
                 } else {
@@ -173,10 +236,24 @@ pub fn parse_mqtt_blacklist(&self, blacklist: MqttAclBlackList) {
                     self.blacklist_ip_match.insert(key, vec![blacklist]);
                 }
             }
+            MqttAclBlackListType::IpMatch => {
+                let key = self.get_ip_regex_match_key();
+                self.add_blacklist_regex(&blacklist.resource_name);
+                if let Some(mut data) = self.blacklist_ip_match.get_mut(&key) {
+                    data.push(blacklist)
+                } else {
+                    self.blacklist_ip_match.insert(key, vec![blacklist]);
+                }
+            }
         }
     }
 
     pub fn remove_mqtt_blacklist(&self, blacklist: MqttAclBlackList) {
+        // a pattern still used by another entry is compiled again on its next use
+        let remove_entry = |list: &mut Vec<MqttAclBlackList>| {
+            list.retain(|raw| raw.resource_name != blacklist.resource_name);
+            self.blacklist_regex.remove(&blacklist.resource_name);
+        };
         match blacklist.blacklist_type {
             MqttAclBlackListType::ClientId => {
                 self.blacklist_client_id.remove(&blacklist.resource_name);
@@ -189,19 +266,82 @@ pub fn remove_mqtt_blacklist(&self, blacklist: MqttAclBlackList) {
             }
             MqttAclBlackListType::ClientIdMatch => {
                 let key = self.get_client_id_match_key();
-                self.blacklist_client_id_match.remove(&key);
+                if let Some(mut data) = self.blacklist_client_id_match.get_mut(&key) {
+                    remove_entry(&mut data);
+                }
             }
             MqttAclBlackListType::UserMatch => {
                 let key = self.get_user_match_key();
-                self.blacklist_user_match.remove(&key);
+                if let Some(mut data) = self.blacklist_user_match.get_mut(&key) {
+                    remove_entry(&mut data);
+                }
             }
             MqttAclBlackListType::IPCIDR => {
                 let key = self.get_ip_cidr_key();
-                self.blacklist_ip_match.remove(&key);
+                if let Some(mut data) = self.blacklist_ip_match.get_mut(&key) {
+                    remove_entry(&mut data);
+                }
+            }
+            MqttAclBlackListType::IpMatch => {
+                let key = self.get_ip_regex_match_key();
+                if let Some(mut data) = self.blacklist_ip_match.get_mut(&key) {
+                    remove_entry(&mut data);
+                }
             }
         }
     }
 
+    // The blacklist entries that expired at the time `now`
+    pub fn expired_blacklist(&self, now: u64) -> Vec<MqttAclBlackList> {
+        let mut expired = Vec::new();
+        for map in [
+            &self.blacklist_user,
+            &self.blacklist_client_id,
+            &self.blacklist_ip,
+        ] {
+            expired.extend(
+                map.iter()
+                    .filter(|entry| entry.value().is_expired(now))
+                    .map(|entry| entry.value().clone()),
+            );
+        }
+        for map in [
+            &self.blacklist_user_match,
+            &self.blacklist_client_id_match,
+            &self.blacklist_ip_match,
+        ] {
+            for entry in map.iter() {
+                expired.extend(
+                    entry
+                        .value()
+                        .iter()
+                        .filter(|raw| raw.is_expired(now))
+                        .cloned(),
+                );
+            }
+        }
+        expired
+    }
+
+    // The compiled pattern of a match entry, None when the pattern does not compile
+    pub fn get_blacklist_regex(&self, pattern: &str) -> Option<Regex> {
+        if let Some(regex) = self.blacklist_regex.get(pattern) {
+            return Some(regex.clone());
+        }
+        self.add_blacklist_regex(pattern)
+    }
+
+    fn add_blacklist_regex(&self, pattern: &str) -> Option<Regex> {
+        match build_blacklist_regex(pattern) {
+            Ok(regex) => {
+                self.blacklist_regex
+                    .insert(pattern.to_string(), regex.clone());
+                Some(regex)
+            }
+            Err(_) => None,
+        }
+    }
+
     pub fn get_blacklist_user_match(&self) -> Option<Vec<MqttAclBlackList>> {
         let key = self.get_user_match_key();
         if let Some(data) = self.blacklist_user_match.get(&key) {
@@ -226,6 +366,14 @@ pub fn get_blacklist_ip_match(&self) -> Option<Vec<MqttAclBlackList>> {
         None
     }
 
+    pub fn get_blacklist_ip_regex_match(&self) -> Option<Vec<MqttAclBlackList>> {
+        let key = self.get_ip_regex_match_key();
+        if let Some(data) = self.blacklist_ip_match.get(&key) {
+            return Some(data.clone());
+        }
+        None
+    }
+
     fn get_client_id_match_key(&self) -> String {
         "ClientIdMatch".to_string()
     }
@@ -237,6 +385,15 @@ fn get_user_match_key(&self) -> String {
     fn get_ip_cidr_key(&self) -> String {
         "IPCIDR".to_string()
     }
+
+    fn get_ip_regex_match_key(&self) -> String {
+        "IpMatch".to_string()
+    }
+}
+
+// A match entry has to match the whole client id, username or IP address
+pub fn build_blacklist_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
 }
 
 #[cfg(test)]
@@ -276,6 +433,8 @@ pub async fn test_mqtt_remove_flapping_detect() {
             window_time: 1,
             max_client_connections: 15,
             ban_time: 5,
+            allowlist: Vec::new(),
+            ..Default::default()
         };
 
         acl_metadata
@@ -350,6 +509,7 @@ pub async fn parse_mqtt_blacklist_test() {
             resource_name: "test_client".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         acl_metadata.parse_mqtt_blacklist(client_id_blacklist);
         assert!(acl_metadata.blacklist_client_id.contains_key("test_client"));
@@ -360,6 +520,7 @@ pub async fn parse_mqtt_blacklist_test() {
             resource_name: "test_user".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         acl_metadata.parse_mqtt_blacklist(user_blacklist);
         assert!(acl_metadata.blacklist_user.contains_key("test_user"));
@@ -370,6 +531,7 @@ pub async fn parse_mqtt_blacklist_test() {
             resource_name: "192.168.1.1".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         acl_metadata.parse_mqtt_blacklist(ip_blacklist);
         assert!(acl_metadata.blacklist_ip.contains_key("192.168.1.1"));
@@ -380,6 +542,7 @@ pub async fn parse_mqtt_blacklist_test() {
             resource_name: "test_client_*".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         acl_metadata.parse_mqtt_blacklist(client_id_match_blacklist);
         let client_id_match_key = acl_metadata.get_client_id_match_key();
@@ -401,6 +564,7 @@ pub async fn parse_mqtt_blacklist_test() {
             resource_name: "test_user_*".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         acl_metadata.parse_mqtt_blacklist(user_match_blacklist);
         let user_match_key = acl_metadata.get_user_match_key();
@@ -422,6 +586,7 @@ pub async fn parse_mqtt_blacklist_test() {
             resource_name: "192.168.1.0/24".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         acl_metadata.parse_mqtt_blacklist(ip_cidr_blacklist);
         let ip_cidr_key = acl_metadata.get_ip_cidr_key();
@@ -441,6 +606,7 @@ pub async fn parse_mqtt_blacklist_test() {
             resource_name: "another_client_*".to_string(),
             end_time: now_second() + 100,
             desc: "".to_string(),
+            expire_at: None,
         };
         acl_metadata.parse_mqtt_blacklist(another_client_id_match_blacklist);
         assert_eq!(
@@ -452,4 +618,80 @@ pub async fn parse_mqtt_blacklist_test() {
             2
         );
     }
+
+    #[tokio::test]
+    pub async fn blacklist_regex_cache_test() {
+        let acl_metadata = AclMetadata::new();
+        for (blacklist_type, resource_name) in [
+            (MqttAclBlackListType::ClientIdMatch, "device-[0-9]+"),
+            (MqttAclBlackListType::ClientIdMatch, "sensor-.*"),
+            (MqttAclBlackListType::IpMatch, r"10\.0\.\d+\.\d+"),
+        ] {
+            acl_metadata.parse_mqtt_blacklist(MqttAclBlackList {
+                blacklist_type,
+                resource_name: resource_name.to_string(),
+                end_time: now_second() + 100,
+                desc: "".to_string(),
+                expire_at: None,
+            });
+        }
+        assert_eq!(acl_metadata.blacklist_regex.len(), 3);
+        assert_eq!(
+            acl_metadata.get_blacklist_ip_regex_match().unwrap().len(),
+            1
+        );
+        assert!(acl_metadata.get_blacklist_ip_match().is_none());
+
+        let regex = acl_metadata.get_blacklist_regex("device-[0-9]+").unwrap();
+        assert!(regex.is_match("device-12"));
+        assert!(!regex.is_match("my-device-12"));
+        assert!(acl_metadata.get_blacklist_regex("device-[0-9").is_none());
+
+        // removing one match entry keeps the other entries of the kind
+        acl_metadata.remove_mqtt_blacklist(MqttAclBlackList {
+            blacklist_type: MqttAclBlackListType::ClientIdMatch,
+            resource_name: "device-[0-9]+".to_string(),
+            end_time: 0,
+            desc: "".to_string(),
+            expire_at: None,
+        });
+        let remaining = acl_metadata.get_blacklist_client_id_match().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].resource_name, "sensor-.*");
+        assert!(!acl_metadata.blacklist_regex.contains_key("device-[0-9]+"));
+    }
+
+    #[tokio::test]
+    pub async fn expired_blacklist_test() {
+        let acl_metadata = AclMetadata::new();
+        let now = now_second();
+        for (blacklist_type, resource_name, expire_at) in [
+            (MqttAclBlackListType::ClientId, "c1", Some(now + 60)),
+            (MqttAclBlackListType::ClientId, "c2", Some(now + 3600)),
+            (MqttAclBlackListType::User, "u1", None),
+            (MqttAclBlackListType::UserMatch, "guest-.*", Some(now + 60)),
+            (MqttAclBlackListType::IPCIDR, "10.0.0.0/8", Some(now + 3600)),
+        ] {
+            acl_metadata.parse_mqtt_blacklist(MqttAclBlackList {
+                blacklist_type,
+                resource_name: resource_name.to_string(),
+                end_time: 0,
+                desc: "".to_string(),
+                expire_at,
+            });
+        }
+
+        assert!(acl_metadata.expired_blacklist(now).is_empty());
+
+        let mut expired: Vec<String> = acl_metadata
+            .expired_blacklist(now + 60)
+            .into_iter()
+            .map(|raw| raw.resource_name)
+            .collect();
+        expired.sort();
+        assert_eq!(expired, vec!["c1", "guest-.*"]);
+
+        // an entry without expire_at is never removed
+        assert_eq!(acl_metadata.expired_blacklist(now + 7200).len(), 4);
+    }
 }
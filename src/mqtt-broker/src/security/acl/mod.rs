@@ -13,4 +13,5 @@
 // limitations under the License.
 
 pub mod auth;
+pub mod decision_cache;
 pub mod metadata;
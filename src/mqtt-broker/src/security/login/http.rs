@@ -11,3 +11,422 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::time::Duration;
+
+use common_base::tools::now_second;
+use common_config::mqtt::config::{AuthHttp, AuthHttpFailureAction};
+use dashmap::DashMap;
+use metadata_struct::acl::mqtt_acl::{
+    MqttAcl, MqttAclAction, MqttAclPermission, MqttAclResourceType,
+};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::handler::constant::WILDCARD_RESOURCE;
+use crate::handler::error::MqttBrokerError;
+
+// Allowed logins remembered at most, expired ones are dropped first when it is full
+pub const AUTH_HTTP_CACHE_CAPACITY: usize = 10000;
+
+// The body POSTed to the auth service
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AuthHttpRequest {
+    pub client_id: String,
+    pub username: String,
+    pub password: String,
+    // Subject of the verified client certificate, empty without one
+    pub client_cert: String,
+    pub source_ip: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthHttpResult {
+    Allow,
+    Deny,
+    // Leave the decision to the built-in user check
+    Ignore,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthHttpAclAction {
+    Publish,
+    Subscribe,
+    All,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthHttpAclPermission {
+    Allow,
+    Deny,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuthHttpAclRule {
+    pub topic: String,
+    pub action: AuthHttpAclAction,
+    pub permission: AuthHttpAclPermission,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuthHttpResponse {
+    pub result: AuthHttpResult,
+    // Rules that apply to the connection on top of the stored ones, only read on allow
+    #[serde(default)]
+    pub acl: Vec<AuthHttpAclRule>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuthHttpOutcome {
+    Allow(Vec<MqttAcl>),
+    Deny,
+    Ignore,
+}
+
+// The login a cached answer belongs to. Only a hash of the password is kept in memory.
+#[derive(PartialEq, Eq, Hash)]
+struct AuthHttpCacheKey {
+    client_id: String,
+    username: String,
+    password_sha256: [u8; 32],
+    client_cert: String,
+    source_ip: String,
+}
+
+impl AuthHttpCacheKey {
+    fn new(request: &AuthHttpRequest) -> Self {
+        AuthHttpCacheKey {
+            client_id: request.client_id.clone(),
+            username: request.username.clone(),
+            password_sha256: Sha256::digest(request.password.as_bytes()).into(),
+            client_cert: request.client_cert.clone(),
+            source_ip: request.source_ip.clone(),
+        }
+    }
+}
+
+struct AuthHttpCacheEntry {
+    acls: Vec<MqttAcl>,
+    expire_at: u64,
+}
+
+// Delegates the CONNECT authentication to an external HTTP service. Only allowed logins are
+// cached, a denied client is asked about again on its next CONNECT.
+#[derive(Default)]
+pub struct AuthHttpBackend {
+    client: reqwest::Client,
+    cache: DashMap<AuthHttpCacheKey, AuthHttpCacheEntry>,
+}
+
+impl AuthHttpBackend {
+    pub async fn authenticate(
+        &self,
+        config: &AuthHttp,
+        request: &AuthHttpRequest,
+    ) -> AuthHttpOutcome {
+        let now = now_second();
+        let key = AuthHttpCacheKey::new(request);
+        if config.cache_ttl_sec > 0 {
+            if let Some(entry) = self.cache.get(&key) {
+                if entry.expire_at > now {
+                    return AuthHttpOutcome::Allow(entry.acls.clone());
+                }
+            }
+        }
+
+        let response = match self.call(config, request).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    "HTTP authentication of client {} failed, error message: {}",
+                    request.client_id, e
+                );
+                return match config.failure_action {
+                    AuthHttpFailureAction::Deny => AuthHttpOutcome::Deny,
+                    AuthHttpFailureAction::Ignore => AuthHttpOutcome::Ignore,
+                };
+            }
+        };
+
+        match response.result {
+            AuthHttpResult::Allow => {
+                let acls = build_assigned_acls(&request.client_id, response.acl);
+                if config.cache_ttl_sec > 0 {
+                    self.cache_allow(key, &acls, now + config.cache_ttl_sec);
+                }
+                AuthHttpOutcome::Allow(acls)
+            }
+            AuthHttpResult::Deny => {
+                self.cache.remove(&key);
+                AuthHttpOutcome::Deny
+            }
+            AuthHttpResult::Ignore => AuthHttpOutcome::Ignore,
+        }
+    }
+
+    async fn call(
+        &self,
+        config: &AuthHttp,
+        request: &AuthHttpRequest,
+    ) -> Result<AuthHttpResponse, MqttBrokerError> {
+        let body = serde_json::to_vec(request)?;
+        let response = self
+            .client
+            .post(&config.url)
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(MqttBrokerError::CommonError(format!(
+                "auth service answered {}",
+                response.status()
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn cache_allow(&self, key: AuthHttpCacheKey, acls: &[MqttAcl], expire_at: u64) {
+        if self.cache.len() >= AUTH_HTTP_CACHE_CAPACITY {
+            let now = now_second();
+            self.cache.retain(|_, entry| entry.expire_at > now);
+            if self.cache.len() >= AUTH_HTTP_CACHE_CAPACITY {
+                return;
+            }
+        }
+        self.cache.insert(
+            key,
+            AuthHttpCacheEntry {
+                acls: acls.to_vec(),
+                expire_at,
+            },
+        );
+    }
+}
+
+pub(crate) fn build_assigned_acls(client_id: &str, rules: Vec<AuthHttpAclRule>) -> Vec<MqttAcl> {
+    rules
+        .into_iter()
+        .map(|rule| MqttAcl {
+            resource_type: MqttAclResourceType::ClientId,
+            resource_name: client_id.to_string(),
+            topic: rule.topic,
+            ip: WILDCARD_RESOURCE.to_string(),
+            action: match rule.action {
+                AuthHttpAclAction::Publish => MqttAclAction::Publish,
+                AuthHttpAclAction::Subscribe => MqttAclAction::Subscribe,
+                AuthHttpAclAction::All => MqttAclAction::All,
+            },
+            permission: match rule.permission {
+                AuthHttpAclPermission::Allow => MqttAclPermission::Allow,
+                AuthHttpAclPermission::Deny => MqttAclPermission::Deny,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AuthHttpAclAction, AuthHttpAclPermission, AuthHttpAclRule, AuthHttpBackend,
+        AuthHttpOutcome, AuthHttpRequest, AuthHttpResponse, AuthHttpResult,
+    };
+    use axum::extract::State;
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use common_config::mqtt::config::{AuthHttp, AuthHttpFailureAction};
+    use metadata_struct::acl::mqtt_acl::{MqttAclAction, MqttAclPermission, MqttAclResourceType};
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::time::sleep;
+
+    // Answers by username: allow-*, deny-*, ignore-*, slow-* answers after the timeout and
+    // anything else fails with 500
+    #[derive(Clone, Default)]
+    struct MockAuthServer {
+        requests: Arc<Mutex<Vec<AuthHttpRequest>>>,
+    }
+
+    async fn auth(
+        State(server): State<MockAuthServer>,
+        Json(request): Json<AuthHttpRequest>,
+    ) -> Response {
+        server.requests.lock().unwrap().push(request.clone());
+        let answer = |result: AuthHttpResult, acl: Vec<AuthHttpAclRule>| {
+            Json(AuthHttpResponse { result, acl }).into_response()
+        };
+        let username = request.username.as_str();
+        if username.starts_with("allow-") {
+            answer(
+                AuthHttpResult::Allow,
+                vec![AuthHttpAclRule {
+                    topic: "devices/+/telemetry".to_string(),
+                    action: AuthHttpAclAction::Publish,
+                    permission: AuthHttpAclPermission::Allow,
+                }],
+            )
+        } else if username.starts_with("deny-") {
+            answer(AuthHttpResult::Deny, Vec::new())
+        } else if username.starts_with("ignore-") {
+            answer(AuthHttpResult::Ignore, Vec::new())
+        } else if username.starts_with("slow-") {
+            sleep(Duration::from_millis(1000)).await;
+            answer(AuthHttpResult::Allow, Vec::new())
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+
+    async fn start_auth_server() -> (MockAuthServer, SocketAddr) {
+        let server = MockAuthServer::default();
+        let app = Router::new()
+            .route("/auth", post(auth))
+            .with_state(server.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (server, addr)
+    }
+
+    fn config(addr: SocketAddr, cache_ttl_sec: u64) -> AuthHttp {
+        AuthHttp {
+            enable: true,
+            url: format!("http://{}/auth", addr),
+            timeout_ms: 200,
+            cache_ttl_sec,
+            failure_action: AuthHttpFailureAction::Deny,
+        }
+    }
+
+    fn request(username: &str) -> AuthHttpRequest {
+        AuthHttpRequest {
+            client_id: "c1".to_string(),
+            username: username.to_string(),
+            password: "pwd".to_string(),
+            client_cert: "CN=c1,O=robustmq".to_string(),
+            source_ip: "127.0.0.1".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn auth_http_allow_test() {
+        let (server, addr) = start_auth_server().await;
+        let backend = AuthHttpBackend::default();
+
+        let AuthHttpOutcome::Allow(acls) = backend
+            .authenticate(&config(addr, 0), &request("allow-user"))
+            .await
+        else {
+            panic!("expected the login to be allowed");
+        };
+        assert_eq!(acls.len(), 1);
+        assert_eq!(acls[0].resource_type, MqttAclResourceType::ClientId);
+        assert_eq!(acls[0].resource_name, "c1");
+        assert_eq!(acls[0].topic, "devices/+/telemetry");
+        assert_eq!(acls[0].action, MqttAclAction::Publish);
+        assert_eq!(acls[0].permission, MqttAclPermission::Allow);
+
+        // The service receives every field of the login
+        let requests = server.requests.lock().unwrap().clone();
+        assert_eq!(requests, vec![request("allow-user")]);
+    }
+
+    #[tokio::test]
+    async fn auth_http_deny_and_ignore_test() {
+        let (_, addr) = start_auth_server().await;
+        let backend = AuthHttpBackend::default();
+
+        assert_eq!(
+            backend
+                .authenticate(&config(addr, 60), &request("deny-user"))
+                .await,
+            AuthHttpOutcome::Deny
+        );
+        assert_eq!(
+            backend
+                .authenticate(&config(addr, 60), &request("ignore-user"))
+                .await,
+            AuthHttpOutcome::Ignore
+        );
+    }
+
+    #[tokio::test]
+    async fn auth_http_failure_test() {
+        let (_, addr) = start_auth_server().await;
+        let backend = AuthHttpBackend::default();
+
+        // A timeout, an error status and an unreachable service fail closed by default
+        let mut config = config(addr, 60);
+        for username in ["slow-user", "broken-user"] {
+            assert_eq!(
+                backend.authenticate(&config, &request(username)).await,
+                AuthHttpOutcome::Deny
+            );
+        }
+        let unreachable = AuthHttp {
+            url: "http://127.0.0.1:1/auth".to_string(),
+            ..config.clone()
+        };
+        assert_eq!(
+            backend
+                .authenticate(&unreachable, &request("allow-user"))
+                .await,
+            AuthHttpOutcome::Deny
+        );
+
+        config.failure_action = AuthHttpFailureAction::Ignore;
+        assert_eq!(
+            backend.authenticate(&config, &request("slow-user")).await,
+            AuthHttpOutcome::Ignore
+        );
+    }
+
+    #[tokio::test]
+    async fn auth_http_cache_test() {
+        let (server, addr) = start_auth_server().await;
+        let backend = AuthHttpBackend::default();
+        let config = config(addr, 60);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                backend.authenticate(&config, &request("allow-user")).await,
+                AuthHttpOutcome::Allow(acls) if acls.len() == 1
+            ));
+        }
+        // A different password is a different login
+        let mut other = request("allow-user");
+        other.password = "other".to_string();
+        backend.authenticate(&config, &other).await;
+        // Denied logins are not cached
+        for _ in 0..2 {
+            backend.authenticate(&config, &request("deny-user")).await;
+        }
+        assert_eq!(server.requests.lock().unwrap().len(), 4);
+
+        // Without a ttl every login asks the service
+        let uncached = AuthHttp {
+            cache_ttl_sec: 0,
+            ..config
+        };
+        backend
+            .authenticate(&uncached, &request("allow-user"))
+            .await;
+        assert_eq!(server.requests.lock().unwrap().len(), 5);
+    }
+}
@@ -11,3 +11,444 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::sync::{Arc, RwLock};
+
+use common_config::mqtt::config::AuthJwt;
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use metadata_struct::acl::mqtt_acl::MqttAcl;
+use serde_json::{Map, Value};
+
+use super::http::{build_assigned_acls, AuthHttpAclRule};
+use crate::handler::error::MqttBrokerError;
+
+// What a verified token says about the client
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JwtIdentity {
+    // Value of the username claim, the client logs in as this user
+    pub username: String,
+    pub acls: Vec<MqttAcl>,
+}
+
+// Keeps the JWKS of the config parsed, it is parsed again only when the configured document
+// changes instead of on every CONNECT
+#[derive(Default)]
+pub struct JwtVerifier {
+    // (configured document, parsed key set)
+    jwks: RwLock<Option<(String, Arc<JwkSet>)>>,
+}
+
+impl JwtVerifier {
+    pub fn verify(
+        &self,
+        config: &AuthJwt,
+        client_id: &str,
+        token: &str,
+    ) -> Result<JwtIdentity, MqttBrokerError> {
+        let jwks = self.jwks(&config.jwks)?;
+        verify_jwt(config, jwks.as_deref(), client_id, token)
+    }
+
+    fn jwks(&self, document: &str) -> Result<Option<Arc<JwkSet>>, MqttBrokerError> {
+        if document.is_empty() {
+            return Ok(None);
+        }
+        if let Some((cached, jwks)) = self.jwks.read().unwrap().as_ref() {
+            if cached == document {
+                return Ok(Some(jwks.clone()));
+            }
+        }
+        let jwks = Arc::new(serde_json::from_str::<JwkSet>(document)?);
+        *self.jwks.write().unwrap() = Some((document.to_string(), jwks.clone()));
+        Ok(Some(jwks))
+    }
+}
+
+// Verifies the signature, expiry, audience and issuer of the token and maps its claims. The
+// username claim is required, otherwise a token would let the client pick any username.
+fn verify_jwt(
+    config: &AuthJwt,
+    jwks: Option<&JwkSet>,
+    client_id: &str,
+    token: &str,
+) -> Result<JwtIdentity, MqttBrokerError> {
+    let header = decode_header(token)?;
+    let key = decoding_key(config, jwks, header.alg, header.kid.as_deref())?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.leeway = config.leeway_sec;
+    validation.validate_nbf = true;
+    let mut required_claims = vec!["exp"];
+    if config.audience.is_empty() {
+        validation.validate_aud = false;
+    } else {
+        validation.set_audience(&config.audience);
+        required_claims.push("aud");
+    }
+    if !config.issuer.is_empty() {
+        validation.set_issuer(&[&config.issuer]);
+        required_claims.push("iss");
+    }
+    validation.set_required_spec_claims(&required_claims);
+
+    let claims = decode::<Map<String, Value>>(token, &key, &validation)?.claims;
+
+    let username = match claims.get(&config.username_claim) {
+        Some(Value::String(username)) if !username.is_empty() => username.clone(),
+        _ => {
+            return Err(MqttBrokerError::JwtClaimInvalid(
+                config.username_claim.clone(),
+            ))
+        }
+    };
+
+    let acls = match claims.get(&config.acl_claim) {
+        None => Vec::new(),
+        Some(value) => {
+            let rules: Vec<AuthHttpAclRule> = serde_json::from_value(value.clone())
+                .map_err(|_| MqttBrokerError::JwtClaimInvalid(config.acl_claim.clone()))?;
+            build_assigned_acls(client_id, rules)
+        }
+    };
+
+    Ok(JwtIdentity { username, acls })
+}
+
+// The key is chosen from the algorithm of the token, so that a token can never make the broker
+// verify it with a key of another kind, e.g. an HMAC signature keyed with the public key.
+fn decoding_key(
+    config: &AuthJwt,
+    jwks: Option<&JwkSet>,
+    alg: Algorithm,
+    kid: Option<&str>,
+) -> Result<DecodingKey, MqttBrokerError> {
+    if let Some(jwks) = jwks {
+        if let Some(jwk) = find_jwk(jwks, alg, kid) {
+            return Ok(DecodingKey::from_jwk(jwk)?);
+        }
+    }
+
+    match alg {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 if !config.secret.is_empty() => {
+            Ok(DecodingKey::from_secret(config.secret.as_bytes()))
+        }
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512
+            if !config.public_key.is_empty() =>
+        {
+            Ok(DecodingKey::from_rsa_pem(config.public_key.as_bytes())?)
+        }
+        Algorithm::ES256 | Algorithm::ES384 if !config.public_key.is_empty() => {
+            Ok(DecodingKey::from_ec_pem(config.public_key.as_bytes())?)
+        }
+        Algorithm::EdDSA if !config.public_key.is_empty() => {
+            Ok(DecodingKey::from_ed_pem(config.public_key.as_bytes())?)
+        }
+        _ => Err(MqttBrokerError::JwtKeyNotFound(format!("{:?}", alg))),
+    }
+}
+
+fn find_jwk<'a>(jwks: &'a JwkSet, alg: Algorithm, kid: Option<&str>) -> Option<&'a Jwk> {
+    let fits = |jwk: &&Jwk| match (&jwk.algorithm, alg) {
+        (
+            AlgorithmParameters::OctetKey(_),
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512,
+        ) => true,
+        (
+            AlgorithmParameters::RSA(_),
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512,
+        ) => true,
+        (AlgorithmParameters::EllipticCurve(_), Algorithm::ES256 | Algorithm::ES384) => true,
+        (AlgorithmParameters::OctetKeyPair(_), Algorithm::EdDSA) => true,
+        _ => false,
+    };
+
+    match kid {
+        Some(kid) => jwks.find(kid).filter(fits),
+        // without a kid the token can only be matched to a set holding a single usable key
+        None => {
+            let mut candidates = jwks.keys.iter().filter(fits);
+            match (candidates.next(), candidates.next()) {
+                (Some(jwk), None) => Some(jwk),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JwtIdentity, JwtVerifier};
+    use crate::handler::error::MqttBrokerError;
+    use common_base::tools::now_second;
+    use std::sync::{Arc, RwLock};
+
+    use common_config::mqtt::config::AuthJwt;
+    use jsonwebtoken::errors::ErrorKind;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use metadata_struct::acl::mqtt_acl::{MqttAclAction, MqttAclPermission, MqttAclResourceType};
+    use serde_json::{json, Value};
+
+    const SECRET: &str = "robustmq-jwt-secret";
+
+    fn config() -> AuthJwt {
+        AuthJwt {
+            enable: true,
+            secret: SECRET.to_string(),
+            audience: vec!["robustmq".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn sign(claims: &Value, secret: &str) -> String {
+        encode(
+            &Header::default(),
+            claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn verify(
+        config: &AuthJwt,
+        client_id: &str,
+        token: &str,
+    ) -> Result<JwtIdentity, MqttBrokerError> {
+        JwtVerifier::default().verify(config, client_id, token)
+    }
+
+    fn error_kind(result: Result<JwtIdentity, MqttBrokerError>) -> ErrorKind {
+        match result {
+            Err(MqttBrokerError::JsonWebTokenError(e)) => e.into_kind(),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn verify_jwt_valid_token_test() {
+        let token = sign(
+            &json!({
+                "exp": now_second() + 300,
+                "aud": "robustmq",
+                "username": "device-1",
+                "acl": [
+                    {"topic": "devices/device-1/#", "action": "all", "permission": "allow"},
+                    {"topic": "admin/#", "action": "subscribe", "permission": "deny"}
+                ]
+            }),
+            SECRET,
+        );
+
+        let identity = verify(&config(), "client-1", &token).unwrap();
+        assert_eq!(identity.username, "device-1");
+        assert_eq!(identity.acls.len(), 2);
+        assert_eq!(
+            identity.acls[0].resource_type,
+            MqttAclResourceType::ClientId
+        );
+        assert_eq!(identity.acls[0].resource_name, "client-1");
+        assert_eq!(identity.acls[0].topic, "devices/device-1/#");
+        assert_eq!(identity.acls[0].action, MqttAclAction::All);
+        assert_eq!(identity.acls[0].permission, MqttAclPermission::Allow);
+        assert_eq!(identity.acls[1].action, MqttAclAction::Subscribe);
+        assert_eq!(identity.acls[1].permission, MqttAclPermission::Deny);
+
+        // the acl claim is optional
+        let token = sign(
+            &json!({"exp": now_second() + 300, "aud": "robustmq", "username": "device-1"}),
+            SECRET,
+        );
+        let identity = verify(&config(), "client-1", &token).unwrap();
+        assert!(identity.acls.is_empty());
+    }
+
+    #[test]
+    fn verify_jwt_missing_username_test() {
+        // without the username claim the client could log in as whatever CONNECT names
+        for claims in [
+            json!({"exp": now_second() + 300, "aud": "robustmq"}),
+            json!({"exp": now_second() + 300, "aud": "robustmq", "username": ""}),
+            json!({"exp": now_second() + 300, "aud": "robustmq", "user": "admin"}),
+        ] {
+            assert!(matches!(
+                verify(&config(), "client-1", &sign(&claims, SECRET)),
+                Err(MqttBrokerError::JwtClaimInvalid(claim)) if claim == "username"
+            ));
+        }
+    }
+
+    #[test]
+    fn verify_jwt_expired_token_test() {
+        let token = sign(
+            &json!({"exp": now_second() - 120, "aud": "robustmq", "username": "device-1"}),
+            SECRET,
+        );
+        assert_eq!(
+            error_kind(verify(&config(), "client-1", &token)),
+            ErrorKind::ExpiredSignature
+        );
+
+        // within the configured leeway the token is still accepted
+        let verifier = JwtVerifier::default();
+        let mut config = config();
+        config.leeway_sec = 300;
+        assert!(verifier.verify(&config, "client-1", &token).is_ok());
+
+        // a token without exp never expires and is rejected
+        let token = sign(&json!({"aud": "robustmq"}), SECRET);
+        assert_eq!(
+            error_kind(verifier.verify(&config, "client-1", &token)),
+            ErrorKind::MissingRequiredClaim("exp".to_string())
+        );
+    }
+
+    #[test]
+    fn verify_jwt_wrong_audience_test() {
+        let token = sign(&json!({"exp": now_second() + 300, "aud": "other"}), SECRET);
+        assert_eq!(
+            error_kind(verify(&config(), "client-1", &token)),
+            ErrorKind::InvalidAudience
+        );
+
+        let token = sign(&json!({"exp": now_second() + 300}), SECRET);
+        assert_eq!(
+            error_kind(verify(&config(), "client-1", &token)),
+            ErrorKind::MissingRequiredClaim("aud".to_string())
+        );
+
+        // without a configured audience the aud claim is not checked
+        let verifier = JwtVerifier::default();
+        let mut config = config();
+        config.audience = Vec::new();
+        let token = sign(&json!({"exp": now_second() + 300, "aud": "other"}), SECRET);
+        assert!(verifier.verify(&config, "client-1", &token).is_ok());
+    }
+
+    #[test]
+    fn verify_jwt_tampered_signature_test() {
+        let token = sign(
+            &json!({"exp": now_second() + 300, "aud": "robustmq", "username": "device-1"}),
+            SECRET,
+        );
+
+        // claims swapped for ones the signature was not made for
+        let forged = sign(
+            &json!({"exp": now_second() + 300, "aud": "robustmq", "username": "admin"}),
+            SECRET,
+        );
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged_parts: Vec<&str> = forged.split('.').collect();
+        let tampered = format!("{}.{}.{}", parts[0], forged_parts[1], parts[2]);
+        assert_eq!(
+            error_kind(verify(&config(), "client-1", &tampered)),
+            ErrorKind::InvalidSignature
+        );
+
+        // signed with another secret
+        let token = sign(
+            &json!({"exp": now_second() + 300, "aud": "robustmq"}),
+            "other",
+        );
+        assert_eq!(
+            error_kind(verify(&config(), "client-1", &token)),
+            ErrorKind::InvalidSignature
+        );
+
+        // not a JWT at all
+        assert!(verify(&config(), "client-1", "password").is_err());
+    }
+
+    #[test]
+    fn verify_jwt_key_selection_test() {
+        let token = sign(
+            &json!({"exp": now_second() + 300, "aud": "robustmq", "username": "device-1"}),
+            SECRET,
+        );
+
+        // an HMAC token is never checked against the public key
+        let verifier = JwtVerifier::default();
+        let mut config = config();
+        config.secret = String::new();
+        config.public_key = "-----BEGIN PUBLIC KEY-----".to_string();
+        assert!(matches!(
+            verifier.verify(&config, "client-1", &token),
+            Err(MqttBrokerError::JwtKeyNotFound(_))
+        ));
+
+        // the key is picked from the JWKS by kid
+        let mut header = Header::default();
+        header.kid = Some("key-2".to_string());
+        let token = encode(
+            &header,
+            &json!({"exp": now_second() + 300, "aud": "robustmq", "username": "device-1"}),
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap();
+        config.jwks = json!({
+            "keys": [
+                {"kty": "oct", "kid": "key-1", "k": "b3RoZXI"},
+                {"kty": "oct", "kid": "key-2", "k": "cm9idXN0bXEtand0LXNlY3JldA"}
+            ]
+        })
+        .to_string();
+        assert!(verifier.verify(&config, "client-1", &token).is_ok());
+
+        let mut header = Header::default();
+        header.kid = Some("key-1".to_string());
+        let token = encode(
+            &header,
+            &json!({"exp": now_second() + 300, "aud": "robustmq", "username": "device-1"}),
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap();
+        assert_eq!(
+            error_kind(verifier.verify(&config, "client-1", &token)),
+            ErrorKind::InvalidSignature
+        );
+
+        // a changed JWKS is picked up by the same verifier
+        config.jwks = json!({
+            "keys": [{"kty": "oct", "kid": "key-1", "k": "cm9idXN0bXEtand0LXNlY3JldA"}]
+        })
+        .to_string();
+        assert!(verifier.verify(&config, "client-1", &token).is_ok());
+
+        config.jwks = "not json".to_string();
+        assert!(verifier.verify(&config, "client-1", &token).is_err());
+    }
+
+    #[test]
+    fn verify_jwt_invalid_claims_test() {
+        let token = sign(
+            &json!({"exp": now_second() + 300, "aud": "robustmq", "username": 1}),
+            SECRET,
+        );
+        assert!(matches!(
+            verify(&config(), "client-1", &token),
+            Err(MqttBrokerError::JwtClaimInvalid(claim)) if claim == "username"
+        ));
+
+        let token = sign(
+            &json!({
+                "exp": now_second() + 300,
+                "aud": "robustmq",
+                "username": "device-1",
+                "acl": [{"topic": "a"}]
+            }),
+            SECRET,
+        );
+        assert!(matches!(
+            verify(&config(), "client-1", &token),
+            Err(MqttBrokerError::JwtClaimInvalid(claim)) if claim == "acl"
+        ));
+    }
+}
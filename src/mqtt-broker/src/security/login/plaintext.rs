@@ -76,6 +76,7 @@ pub async fn plaintext_test() {
             username: username.clone(),
             password: password.clone(),
             is_superuser: true,
+            create_time: 0,
         };
         cache_manager.add_user(user);
 
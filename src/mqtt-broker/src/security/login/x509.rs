@@ -11,3 +11,129 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use crate::handler::error::MqttBrokerError;
+use crate::server::connection::TlsConnectionInfo;
+use common_config::mqtt::config::CertUsername;
+use protocol::mqtt::common::Login;
+
+// With cert_username enabled the CN of the verified client certificate is the username of the
+// connection and the certificate takes the place of the password check. None means the
+// feature is off and the login goes through the username and password check.
+pub fn cert_username_login(
+    config: &CertUsername,
+    tls_info: Option<&TlsConnectionInfo>,
+    login: &Option<Login>,
+) -> Result<Option<Login>, MqttBrokerError> {
+    if !config.enable {
+        return Ok(None);
+    }
+
+    let Some(cn) = tls_info
+        .map(|tls_info| tls_info.client_cert_cn.as_str())
+        .filter(|cn| !cn.is_empty())
+    else {
+        return Err(MqttBrokerError::ClientCertificateMissing);
+    };
+
+    // A client may still send its username, but it has to be the one of its certificate
+    if let Some(login) = login {
+        if !login.username.is_empty() && login.username != cn {
+            return Err(MqttBrokerError::ClientCertificateUsernameMismatch(
+                login.username.clone(),
+                cn.to_string(),
+            ));
+        }
+    }
+
+    Ok(Some(Login {
+        username: cn.to_string(),
+        password: login
+            .as_ref()
+            .map(|login| login.password.clone())
+            .unwrap_or_default(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cert_username_login;
+    use crate::handler::error::MqttBrokerError;
+    use crate::server::connection::TlsConnectionInfo;
+    use common_config::mqtt::config::CertUsername;
+    use protocol::mqtt::common::Login;
+
+    fn tls_info(cn: &str) -> TlsConnectionInfo {
+        TlsConnectionInfo {
+            tls_version: "TLSv1_3".to_string(),
+            cipher_suite: "TLS13_AES_128_GCM_SHA256".to_string(),
+            client_cert_subject: format!("CN={}, O=RobustMQ", cn),
+            client_cert_cn: cn.to_string(),
+        }
+    }
+
+    fn login(username: &str) -> Option<Login> {
+        Some(Login {
+            username: username.to_string(),
+            password: "pwd".to_string(),
+        })
+    }
+
+    #[test]
+    fn cert_username_login_test() {
+        let enabled = CertUsername { enable: true };
+
+        let res = cert_username_login(&enabled, Some(&tls_info("device-01")), &None).unwrap();
+        assert_eq!(res.unwrap().username, "device-01");
+
+        // Disabled, the login is left to the username and password check
+        let res = cert_username_login(
+            &CertUsername { enable: false },
+            Some(&tls_info("device-01")),
+            &login("u1"),
+        )
+        .unwrap();
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn cert_username_missing_cert_test() {
+        let enabled = CertUsername { enable: true };
+
+        // Plain TCP connection
+        let res = cert_username_login(&enabled, None, &login("u1"));
+        assert!(matches!(
+            res,
+            Err(MqttBrokerError::ClientCertificateMissing)
+        ));
+
+        // TLS without a client certificate
+        let res = cert_username_login(&enabled, Some(&tls_info("")), &login("u1"));
+        assert!(matches!(
+            res,
+            Err(MqttBrokerError::ClientCertificateMissing)
+        ));
+    }
+
+    #[test]
+    fn cert_username_explicit_username_test() {
+        let enabled = CertUsername { enable: true };
+
+        let res = cert_username_login(&enabled, Some(&tls_info("device-01")), &login("device-01"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.username, "device-01");
+        assert_eq!(res.password, "pwd");
+
+        let res = cert_username_login(&enabled, Some(&tls_info("device-01")), &login(""))
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.username, "device-01");
+
+        let res = cert_username_login(&enabled, Some(&tls_info("device-01")), &login("admin"));
+        assert!(matches!(
+            res,
+            Err(MqttBrokerError::ClientCertificateUsernameMismatch(_, _))
+        ));
+    }
+}
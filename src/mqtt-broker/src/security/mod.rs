@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use std::collections::HashSet;
-use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -24,21 +23,24 @@
 use common_config::mqtt::config::AuthStorage;
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
+use login::http::{AuthHttpBackend, AuthHttpOutcome, AuthHttpRequest};
+use login::jwt::JwtVerifier;
 use login::plaintext::Plaintext;
 use login::Authentication;
-use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction, MqttAclResourceType};
+use metadata_struct::acl::mqtt_acl::{MqttAcl, MqttAclAction};
 use metadata_struct::acl::mqtt_blacklist::MqttAclBlackList;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use metadata_struct::mqtt::user::MqttUser;
-use protocol::mqtt::common::{ConnectProperties, Login, QoS, Subscribe};
+use protocol::mqtt::common::{ConnectProperties, Filter, Login, QoS, Subscribe};
 use storage::mysql::MySQLAuthStorageAdapter;
 use storage::placement::PlacementAuthStorageAdapter;
 use storage_adapter::StorageType;
+use tracing::{info, warn};
 
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
 use crate::security::acl::auth::is_blacklist;
-use crate::subscribe::common::get_sub_topic_id_list;
+use crate::subscribe::common::decode_sub_path;
 
 pub mod acl;
 pub mod login;
@@ -56,6 +58,8 @@ pub trait AuthStorageAdapter {
 
     async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError>;
 
+    async fn update_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError>;
+
     async fn delete_user(&self, username: String) -> Result<(), MqttBrokerError>;
 
     async fn save_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError>;
@@ -71,6 +75,8 @@ pub struct AuthDriver {
     cache_manager: Arc<CacheManager>,
     client_pool: Arc<ClientPool>,
     driver: Arc<dyn AuthStorageAdapter + Send + 'static + Sync>,
+    auth_http: AuthHttpBackend,
+    jwt: JwtVerifier,
 }
 
 impl AuthDriver {
@@ -87,6 +93,8 @@ pub fn new(cache_manager: Arc<CacheManager>, client_pool: Arc<ClientPool>) -> Au
             cache_manager,
             driver,
             client_pool,
+            auth_http: AuthHttpBackend::default(),
+            jwt: JwtVerifier::default(),
         }
     }
 
@@ -117,6 +125,22 @@ pub async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError
         self.driver.save_user(user_info).await
     }
 
+    // Overwrite the password and superuser flag of an existing user. Both are written to the
+    // storage in one record, the cache is only updated once the write succeeded.
+    pub async fn update_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
+        if self
+            .cache_manager
+            .user_info
+            .get(&user_info.username)
+            .is_none()
+        {
+            return Err(MqttBrokerError::UserDoesNotExist);
+        }
+        self.driver.update_user(user_info.clone()).await?;
+        self.cache_manager.add_user(user_info);
+        Ok(())
+    }
+
     pub async fn delete_user(&self, username: String) -> Result<(), MqttBrokerError> {
         if self.cache_manager.user_info.get(&username).is_none() {
             return Err(MqttBrokerError::UserDoesNotExist);
@@ -141,11 +165,16 @@ pub async fn update_user_cache(&self) -> Result<(), MqttBrokerError> {
         Ok(())
     }
 
+    // With the HTTP auth backend enabled the auth service decides first. When it ignores the
+    // client, or is disabled, a JWT in the password decides if JWT auth is enabled, otherwise
+    // the built-in user check does. The rules assigned by the auth service or the token are
+    // kept on the connection.
     pub async fn check_login_auth(
         &self,
+        connection: &mut MQTTConnection,
         login: &Option<Login>,
+        client_cert: &str,
         _: &Option<ConnectProperties>,
-        _: &SocketAddr,
     ) -> Result<bool, MqttBrokerError> {
         let cluster = self.cache_manager.get_cluster_config();
 
@@ -153,6 +182,57 @@ pub async fn check_login_auth(
             return Ok(true);
         }
 
+        if cluster.auth_http.enable {
+            let request = AuthHttpRequest {
+                client_id: connection.client_id.clone(),
+                username: login
+                    .as_ref()
+                    .map(|info| info.username.clone())
+                    .unwrap_or_default(),
+                password: login
+                    .as_ref()
+                    .map(|info| info.password.clone())
+                    .unwrap_or_default(),
+                client_cert: client_cert.to_string(),
+                source_ip: connection.source_ip_addr.clone(),
+            };
+            match self
+                .auth_http
+                .authenticate(&cluster.auth_http, &request)
+                .await
+            {
+                AuthHttpOutcome::Allow(acls) => {
+                    connection.assigned_acls = acls;
+                    return Ok(true);
+                }
+                AuthHttpOutcome::Deny => return Ok(false),
+                AuthHttpOutcome::Ignore => {}
+            }
+        }
+
+        if cluster.auth_jwt.enable {
+            let Some(info) = login else {
+                return Ok(false);
+            };
+            return match self
+                .jwt
+                .verify(&cluster.auth_jwt, &connection.client_id, &info.password)
+            {
+                Ok(identity) => {
+                    connection.login_user = identity.username;
+                    connection.assigned_acls = identity.acls;
+                    Ok(true)
+                }
+                Err(e) => {
+                    info!(
+                        "JWT authentication of client {} failed: {}",
+                        connection.client_id, e
+                    );
+                    Ok(false)
+                }
+            };
+        }
+
         if let Some(info) = login {
             return self
                 .plaintext_check_login(&info.username, &info.password)
@@ -175,22 +255,7 @@ pub async fn delete_acl(&self, acl: MqttAcl) -> Result<(), MqttBrokerError> {
 
     pub async fn update_acl_cache(&self) -> Result<(), MqttBrokerError> {
         let all_acls: Vec<MqttAcl> = self.driver.read_all_acl().await?;
-
-        for acl in all_acls.iter() {
-            self.cache_manager.add_acl(acl.to_owned());
-        }
-
-        let mut user_acl = HashSet::new();
-        let mut client_acl = HashSet::new();
-
-        for acl in all_acls.clone() {
-            match acl.resource_type {
-                MqttAclResourceType::User => user_acl.insert(acl.resource_name.clone()),
-                MqttAclResourceType::ClientId => client_acl.insert(acl.resource_name.clone()),
-            };
-        }
-        self.cache_manager.retain_acls(user_acl, client_acl);
-
+        self.cache_manager.sync_acls(all_acls);
         Ok(())
     }
 
@@ -208,6 +273,22 @@ pub async fn delete_blacklist(
         Ok(())
     }
 
+    // Remove the blacklist entries that expired at the time `now` from the cache and the
+    // storage. Entries added by flapping detect only exist in the cache.
+    pub async fn purge_expired_blacklist(&self, now: u64) -> usize {
+        let expired = self.cache_manager.acl_metadata.expired_blacklist(now);
+        for blacklist in expired.iter() {
+            self.cache_manager.remove_blacklist(blacklist.clone());
+            if let Err(e) = self.driver.delete_blacklist(blacklist.clone()).await {
+                warn!(
+                    "Failed to delete expired blacklist {} {} from storage, error message: {}",
+                    blacklist.blacklist_type, blacklist.resource_name, e
+                );
+            }
+        }
+        expired.len()
+    }
+
     pub async fn allow_connect(&self, connection: &MQTTConnection) -> bool {
         // check blacklist
         is_blacklist(&self.cache_manager, connection)
@@ -236,23 +317,31 @@ pub async fn allow_subscribe(
         subscribe: &Subscribe,
     ) -> bool {
         for filter in subscribe.filters.iter() {
-            let topic_list = get_sub_topic_id_list(&self.cache_manager, &filter.path).await;
-            for topic in topic_list {
-                if !is_allow_acl(
-                    &self.cache_manager,
-                    connection,
-                    &topic,
-                    MqttAclAction::Subscribe,
-                    false,
-                    filter.qos,
-                ) {
-                    return false;
-                }
+            if !self.allow_subscribe_filter(connection, filter).await {
+                return false;
             }
         }
         true
     }
 
+    pub async fn allow_subscribe_filter(
+        &self,
+        connection: &MQTTConnection,
+        filter: &Filter,
+    ) -> bool {
+        // the rules are checked against the filter itself, without the shared, queue or
+        // exclusive prefix, so that they also apply to topics that do not exist yet
+        let sub_filter = decode_sub_path(&filter.path);
+        is_allow_acl(
+            &self.cache_manager,
+            connection,
+            &sub_filter,
+            MqttAclAction::Subscribe,
+            false,
+            filter.qos,
+        )
+    }
+
     async fn plaintext_check_login(
         &self,
         username: &str,
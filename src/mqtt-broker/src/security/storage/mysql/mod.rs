@@ -66,6 +66,8 @@ async fn read_all_user(&self) -> Result<DashMap<String, MqttUser>, MqttBrokerErr
                 username: raw.0.clone(),
                 password: raw.1.clone(),
                 is_superuser: raw.3 == 1,
+                // the created column of the table is not read
+                create_time: 0,
             };
             results.insert(raw.0.clone(), user);
         }
@@ -129,6 +131,7 @@ async fn get_user(&self, username: String) -> Result<Option<MqttUser>, MqttBroke
                 username: value.0.clone(),
                 password: value.1.clone(),
                 is_superuser: value.3 == 1,
+                create_time: 0,
             }));
         }
         return Ok(None);
@@ -147,6 +150,23 @@ async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
         return Ok(());
     }
 
+    async fn update_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
+        let mut conn = self.pool.get_conn()?;
+        let sql = format!(
+            "update {} set `password` = ?, `is_superuser` = ? where username = ?;",
+            self.table_user(),
+        );
+        conn.exec_drop(
+            sql,
+            (
+                user_info.password,
+                user_info.is_superuser as i32,
+                user_info.username,
+            ),
+        )?;
+        return Ok(());
+    }
+
     async fn delete_user(&self, username: String) -> Result<(), MqttBrokerError> {
         let mut conn = self.pool.get_conn()?;
         let sql = format!(
@@ -14,9 +14,11 @@
 
 use std::fmt;
 use std::net::SocketAddr;
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
-use protocol::mqtt::common::MqttProtocol;
+use common_base::tools::now_mills;
+use protocol::mqtt::common::{MqttPacket, MqttProtocol};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tracing::error;
@@ -47,14 +49,47 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     }
 }
 
+// What a TLS client negotiated in the handshake
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TlsConnectionInfo {
+    pub tls_version: String,
+    pub cipher_suite: String,
+    // Empty unless the client presented a certificate
+    pub client_cert_subject: String,
+    #[serde(default)]
+    pub client_cert_cn: String,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct NetworkConnection {
     pub connection_type: NetworkConnectionType,
     pub connection_id: u64,
     pub protocol: Option<MqttProtocol>,
+    // The current peer address, a QUIC client can migrate to another one
     pub addr: SocketAddr,
+    // The address the connection was accepted from, only set once the peer migrated
+    #[serde(default)]
+    pub original_addr: Option<SocketAddr>,
     #[serde(skip_serializing, skip_deserializing)]
     pub connection_stop_sx: Option<mpsc::Sender<bool>>,
+    // Bytes of the MQTT packets received from and sent to the client on this connection
+    #[serde(skip_serializing, skip_deserializing)]
+    pub bytes_received: Arc<AtomicU64>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub bytes_sent: Arc<AtomicU64>,
+    // PUBLISH packets received from and sent to the client on this connection
+    #[serde(skip_serializing, skip_deserializing)]
+    pub messages_received: Arc<AtomicU64>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub messages_sent: Arc<AtomicU64>,
+    // Milliseconds since the epoch when the connection was accepted
+    pub create_time_ms: u64,
+    // Only set on TLS connections
+    #[serde(default)]
+    pub tls_info: Option<TlsConnectionInfo>,
+    // The MQTT subprotocol negotiated in the upgrade, only set on WebSocket connections
+    #[serde(default)]
+    pub websocket_subprotocol: Option<String>,
 }
 
 impl NetworkConnection {
@@ -63,13 +98,21 @@ pub fn new(
         addr: SocketAddr,
         connection_stop_sx: Option<mpsc::Sender<bool>>,
     ) -> Self {
-        let connection_id = CONNECTION_ID_BUILD.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let connection_id = CONNECTION_ID_BUILD.fetch_add(1, Ordering::Relaxed);
         NetworkConnection {
             connection_type,
             connection_id,
             protocol: None,
             addr,
+            original_addr: None,
             connection_stop_sx,
+            bytes_received: Arc::new(AtomicU64::new(0)),
+            bytes_sent: Arc::new(AtomicU64::new(0)),
+            messages_received: Arc::new(AtomicU64::new(0)),
+            messages_sent: Arc::new(AtomicU64::new(0)),
+            create_time_ms: now_mills() as u64,
+            tls_info: None,
+            websocket_subprotocol: None,
         }
     }
 
@@ -77,6 +120,18 @@ pub fn connection_id(&self) -> u64 {
         self.connection_id
     }
 
+    pub fn set_tls_info(&mut self, tls_info: TlsConnectionInfo) {
+        self.tls_info = Some(tls_info);
+    }
+
+    pub fn original_addr(&self) -> SocketAddr {
+        self.original_addr.unwrap_or(self.addr)
+    }
+
+    pub fn set_websocket_subprotocol(&mut self, subprotocol: String) {
+        self.websocket_subprotocol = Some(subprotocol);
+    }
+
     pub fn set_protocol(&mut self, protocol: MqttProtocol) {
         self.protocol = Some(protocol);
     }
@@ -114,6 +169,45 @@ pub fn get_protocol(&self) -> MqttProtocol {
         MqttProtocol::Mqtt5
     }
 
+    pub fn record_bytes_received(&self, size: u64) {
+        self.bytes_received.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub fn record_packet_received(&self, packet: &MqttPacket, frame_length: usize) {
+        self.record_bytes_received(frame_length as u64);
+        if let MqttPacket::Publish(_, _) = packet {
+            self.messages_received.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_bytes_sent(&self, size: u64) {
+        self.bytes_sent.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub fn record_message_sent(&self) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get_bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    pub fn get_bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    pub fn get_messages_received(&self) -> u64 {
+        self.messages_received.load(Ordering::Relaxed)
+    }
+
+    pub fn get_messages_sent(&self) -> u64 {
+        self.messages_sent.load(Ordering::Relaxed)
+    }
+
+    pub fn connected_duration_ms(&self) -> u64 {
+        (now_mills() as u64).saturating_sub(self.create_time_ms)
+    }
+
     pub fn is_tcp(&self) -> bool {
         self.connection_type == NetworkConnectionType::Tcp
             || self.connection_type == NetworkConnectionType::Tls
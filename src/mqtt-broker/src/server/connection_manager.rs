@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -20,14 +21,18 @@
 use futures::stream::SplitSink;
 use futures::SinkExt;
 use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
-use protocol::mqtt::common::MqttProtocol;
+use protocol::mqtt::common::{MqttPacket, MqttProtocol};
 use tokio::time::sleep;
 use tokio_util::codec::FramedWrite;
 use tracing::{debug, info};
 
 use super::connection::{NetworkConnection, NetworkConnectionType};
+use super::listener_metrics::ListenerMetrics;
+use super::message_rate::MessageRateTracker;
+use super::top_talkers::TalkerSampler;
 use crate::handler::cache::CacheManager;
 use crate::handler::error::MqttBrokerError;
+use crate::handler::session_restore::{self, SessionRestoreGate};
 use crate::observability::metrics::packets::record_sent_metrics;
 use crate::server::quic::quic_stream_wrapper::QuicFramedWriteStream;
 
@@ -44,6 +49,18 @@ pub struct ConnectionManager {
     >,
     pub websocket_write_list: DashMap<u64, SplitSink<WebSocket, Message>>,
     pub quic_write_list: DashMap<u64, QuicFramedWriteStream>,
+    // (network type, listener metrics)
+    pub listener_metrics: DashMap<String, Arc<ListenerMetrics>>,
+    // (network type, address the listener is bound to), recorded once the socket is bound
+    pub bound_listeners: DashMap<String, SocketAddr>,
+    // (connection_id, gate closed until the CONNECT of the connection has been handled)
+    pub session_restore: DashMap<u64, Arc<SessionRestoreGate>>,
+    // Inbound and outbound PUBLISH rate of this broker
+    pub message_rate: MessageRateTracker,
+    // Recent traffic counters of every connection, see TopTalkersThread
+    pub talker_sampler: TalkerSampler,
+    // (source ip, number of connections from the ip)
+    pub ip_connection_num: DashMap<IpAddr, u64>,
     cache_manager: Arc<CacheManager>,
 }
 
@@ -54,6 +71,10 @@ pub fn new(cache_manager: Arc<CacheManager>) -> ConnectionManager {
         let tcp_tls_write_list = DashMap::with_capacity(64);
         let websocket_write_list = DashMap::with_capacity(64);
         let quic_write_list = DashMap::with_capacity(64);
+        let listener_metrics = DashMap::with_capacity(5);
+        let bound_listeners = DashMap::with_capacity(5);
+        let session_restore = DashMap::with_capacity(8);
+        let ip_connection_num = DashMap::with_capacity(64);
         ConnectionManager {
             connections,
             tcp_write_list,
@@ -61,18 +82,113 @@ pub fn new(cache_manager: Arc<CacheManager>) -> ConnectionManager {
             cache_manager,
             websocket_write_list,
             quic_write_list,
+            listener_metrics,
+            bound_listeners,
+            session_restore,
+            message_rate: MessageRateTracker::default(),
+            talker_sampler: TalkerSampler::default(),
+            ip_connection_num,
         }
     }
 
     pub fn add_connection(&self, connection: NetworkConnection) -> u64 {
         let connection_id = connection.connection_id();
-        self.connections.insert(connection_id, connection);
+        let ip = connection.addr.ip();
+        if self.connections.insert(connection_id, connection).is_none() {
+            *self.ip_connection_num.entry(ip).or_default() += 1;
+        }
         connection_id
     }
 
+    // The peer of a QUIC connection migrated to a new address. The connection keeps its entry
+    // and id, only its address and the per ip count follow the peer. Returns whether the
+    // address changed.
+    pub fn update_peer_addr(&self, connection_id: u64, addr: SocketAddr) -> bool {
+        let old_ip = {
+            let Some(mut connection) = self.connections.get_mut(&connection_id) else {
+                return false;
+            };
+            if connection.addr == addr {
+                return false;
+            }
+            if connection.original_addr.is_none() {
+                connection.original_addr = Some(connection.addr);
+            }
+            let old_ip = connection.addr.ip();
+            connection.addr = addr;
+            old_ip
+        };
+        if old_ip != addr.ip() {
+            self.release_ip_connection(&old_ip);
+            *self.ip_connection_num.entry(addr.ip()).or_default() += 1;
+        }
+        true
+    }
+
+    fn release_ip_connection(&self, ip: &IpAddr) {
+        if let Some(mut num) = self.ip_connection_num.get_mut(ip) {
+            *num = num.saturating_sub(1);
+        }
+        self.ip_connection_num.remove_if(ip, |_, num| *num == 0);
+    }
+
+    pub fn get_ip_connection_num(&self, ip: &IpAddr) -> u64 {
+        self.ip_connection_num.get(ip).map_or(0, |num| *num)
+    }
+
     pub fn list_connect(&self) -> DashMap<u64, NetworkConnection> {
         self.connections.clone()
     }
+
+    pub fn get_listener_metrics(
+        &self,
+        network_type: &NetworkConnectionType,
+    ) -> Arc<ListenerMetrics> {
+        self.listener_metrics
+            .entry(network_type.to_string())
+            .or_default()
+            .clone()
+    }
+
+    pub fn record_listener_bound(&self, network_type: &NetworkConnectionType, addr: SocketAddr) {
+        self.bound_listeners.insert(network_type.to_string(), addr);
+    }
+
+    pub fn listener_bound_addr(&self, network_type: &NetworkConnectionType) -> Option<SocketAddr> {
+        self.bound_listeners
+            .get(&network_type.to_string())
+            .map(|addr| *addr)
+    }
+
+    pub fn record_listener_accept(&self, network_type: &NetworkConnectionType) {
+        self.get_listener_metrics(network_type).record_accept();
+    }
+
+    pub fn record_listener_tls_handshake_failure(&self, network_type: &NetworkConnectionType) {
+        self.get_listener_metrics(network_type)
+            .record_tls_handshake_failure();
+    }
+
+    // A packet that fails to decode before CONNECT completed counts as a malformed CONNECT,
+    // later decode errors are only recorded in the packet metrics
+    pub fn record_listener_decode_error(&self, connection_id: u64) {
+        if let Some(connection) = self.connections.get(&connection_id) {
+            if connection.protocol.is_none() {
+                self.get_listener_metrics(&connection.connection_type)
+                    .record_malformed_connect();
+            }
+        }
+    }
+
+    // Connections that have been accepted but have not completed CONNECT yet
+    pub fn listener_accept_queue_depth(&self, network_type: &NetworkConnectionType) -> u64 {
+        self.connections
+            .iter()
+            .filter(|connection| {
+                connection.connection_type == *network_type && connection.protocol.is_none()
+            })
+            .count() as u64
+    }
     pub fn add_tcp_write(
         &self,
         connection_id: u64,
@@ -112,7 +228,10 @@ pub async fn close_all_connect(&self) {
     }
 
     pub async fn close_connect(&self, connection_id: u64) {
+        self.finish_session_restore(connection_id);
+
         if let Some((_, connection)) = self.connections.remove(&connection_id) {
+            self.release_ip_connection(&connection.addr.ip());
             connection.stop_connection().await;
         }
 
@@ -159,14 +278,7 @@ pub async fn write_websocket_frame(
                 dashmap::try_result::TryResult::Present(mut da) => {
                     match da.send(resp.clone()).await {
                         Ok(_) => {
-                            let network_type =
-                                if let Some(connection) = self.get_connect(connection_id) {
-                                    connection.connection_type.to_string()
-                                } else {
-                                    "".to_string()
-                                };
-
-                            record_sent_metrics(&packet_wrapper, network_type);
+                            self.record_sent(connection_id, &packet_wrapper);
                             break;
                         }
                         Err(e) => {
@@ -223,15 +335,7 @@ pub async fn write_tcp_frame(
                 dashmap::try_result::TryResult::Present(mut da) => {
                     match da.send(resp.clone()).await {
                         Ok(_) => {
-                            // write tls stream
-                            let network_type =
-                                if let Some(connection) = self.get_connect(connection_id) {
-                                    connection.connection_type.to_string()
-                                } else {
-                                    "".to_string()
-                                };
-
-                            record_sent_metrics(&resp, network_type);
+                            self.record_sent(connection_id, &resp);
                             break;
                         }
                         Err(e) => {
@@ -278,14 +382,7 @@ async fn write_tcp_tls_frame(
                 dashmap::try_result::TryResult::Present(mut da) => {
                     match da.send(resp.clone()).await {
                         Ok(_) => {
-                            let network_type =
-                                if let Some(connection) = self.get_connect(connection_id) {
-                                    connection.connection_type.to_string()
-                                } else {
-                                    "".to_string()
-                                };
-
-                            record_sent_metrics(&resp, network_type);
+                            self.record_sent(connection_id, &resp);
                             break;
                         }
                         Err(e) => {
@@ -317,6 +414,30 @@ async fn write_tcp_tls_frame(
         Ok(())
     }
 
+    pub fn record_received(&self, packet: &MqttPacket) {
+        if let MqttPacket::Publish(_, _) = packet {
+            self.message_rate.record_message_in();
+        }
+    }
+
+    fn record_sent(&self, connection_id: u64, packet_wrapper: &MqttPacketWrapper) {
+        if let MqttPacket::Publish(_, _) = packet_wrapper.packet {
+            self.message_rate.record_message_out();
+        }
+        let connection = self.get_connect(connection_id);
+        let network_type = connection
+            .as_ref()
+            .map(|connection| connection.connection_type.to_string())
+            .unwrap_or_default();
+        let payload_size = record_sent_metrics(packet_wrapper, network_type);
+        if let Some(connection) = connection {
+            connection.record_bytes_sent(payload_size as u64);
+            if let MqttPacket::Publish(_, _) = packet_wrapper.packet {
+                connection.record_message_sent();
+            }
+        }
+    }
+
     pub fn tcp_connect_num_check(&self) -> bool {
         let cluster = self.cache_manager.get_cluster_config();
         if self.connections.len() >= cluster.network_thread.max_connection_num {
@@ -325,6 +446,12 @@ pub fn tcp_connect_num_check(&self) -> bool {
         false
     }
 
+    pub fn ip_connect_num_check(&self, ip: &IpAddr) -> bool {
+        self.cache_manager
+            .get_connection_limit_config()
+            .is_exceeded(self.get_ip_connection_num(ip))
+    }
+
     pub fn get_connect(&self, connect_id: u64) -> Option<NetworkConnection> {
         if let Some(connect) = self.connections.get(&connect_id) {
             return Some(connect.clone());
@@ -350,6 +477,28 @@ pub fn set_connect_protocol(&self, connect_id: u64, protocol: u8) {
         }
     }
 
+    // session restore
+    pub fn begin_session_restore(&self, connect_id: u64) {
+        self.session_restore
+            .insert(connect_id, Arc::new(SessionRestoreGate::default()));
+    }
+
+    pub fn finish_session_restore(&self, connect_id: u64) {
+        if let Some((_, gate)) = self.session_restore.remove(&connect_id) {
+            gate.finish();
+        }
+    }
+
+    pub fn get_session_restore(&self, connect_id: u64) -> Option<Arc<SessionRestoreGate>> {
+        self.session_restore
+            .get(&connect_id)
+            .map(|gate| gate.clone())
+    }
+
+    pub async fn wait_session_restore(&self, connect_id: u64, packet: &MqttPacket) {
+        session_restore::wait_session_restore(&self.cache_manager, self, connect_id, packet).await
+    }
+
     pub fn is_websocket(&self, connect_id: u64) -> bool {
         if let Some(connect) = self.connections.get(&connect_id) {
             return connect.connection_type == NetworkConnectionType::WebSocket;
@@ -357,3 +506,102 @@ pub fn is_websocket(&self, connect_id: u64) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use common_config::mqtt::config::ConnectionLimit;
+    use grpc_clients::pool::ClientPool;
+
+    use super::ConnectionManager;
+    use crate::handler::cache::CacheManager;
+    use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+
+    #[tokio::test]
+    async fn ip_connect_num_check_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        cache_manager.set_cluster_config(Default::default());
+        let connection_manager = ConnectionManager::new(cache_manager.clone());
+
+        let connect = |addr: &str| {
+            connection_manager.add_connection(NetworkConnection::new(
+                NetworkConnectionType::Tcp,
+                addr.parse().unwrap(),
+                None,
+            ))
+        };
+        let ip = "10.0.0.1".parse().unwrap();
+        let other_ip = "10.0.0.2".parse().unwrap();
+
+        // unlimited by default
+        let first = connect("10.0.0.1:50001");
+        connect("10.0.0.1:50002");
+        assert!(!connection_manager.ip_connect_num_check(&ip));
+
+        cache_manager.update_connection_limit_config(ConnectionLimit {
+            max_connections_per_ip: 3,
+        });
+        assert!(!connection_manager.ip_connect_num_check(&ip));
+        connect("10.0.0.1:50003");
+        assert_eq!(connection_manager.get_ip_connection_num(&ip), 3);
+        assert!(connection_manager.ip_connect_num_check(&ip));
+
+        // other hosts are counted on their own
+        connect("10.0.0.2:50001");
+        assert!(!connection_manager.ip_connect_num_check(&other_ip));
+
+        // closing a connection frees a slot for the ip
+        connection_manager.close_connect(first).await;
+        assert_eq!(connection_manager.get_ip_connection_num(&ip), 2);
+        assert!(!connection_manager.ip_connect_num_check(&ip));
+
+        connection_manager.close_all_connect().await;
+        assert!(connection_manager.ip_connection_num.is_empty());
+    }
+
+    #[tokio::test]
+    async fn update_peer_addr_test() {
+        let client_pool = Arc::new(ClientPool::new(1));
+        let cache_manager = Arc::new(CacheManager::new(client_pool, "test".to_string()));
+        let connection_manager = ConnectionManager::new(cache_manager);
+
+        let original: SocketAddr = "10.0.0.1:50001".parse().unwrap();
+        let connection_id = connection_manager.add_connection(NetworkConnection::new(
+            NetworkConnectionType::Quic,
+            original,
+            None,
+        ));
+        assert!(!connection_manager.update_peer_addr(connection_id, original));
+        assert!(connection_manager
+            .get_connect(connection_id)
+            .unwrap()
+            .original_addr
+            .is_none());
+
+        // The client moved from one network to another
+        let migrated: SocketAddr = "192.168.1.9:40001".parse().unwrap();
+        assert!(connection_manager.update_peer_addr(connection_id, migrated));
+        assert_eq!(connection_manager.connections.len(), 1);
+        let connection = connection_manager.get_connect(connection_id).unwrap();
+        assert_eq!(connection.addr, migrated);
+        assert_eq!(connection.original_addr(), original);
+        assert_eq!(connection_manager.get_ip_connection_num(&original.ip()), 0);
+        assert_eq!(connection_manager.get_ip_connection_num(&migrated.ip()), 1);
+
+        // A second migration keeps the address the connection was accepted from
+        let rebound: SocketAddr = "192.168.1.9:40002".parse().unwrap();
+        assert!(connection_manager.update_peer_addr(connection_id, rebound));
+        let connection = connection_manager.get_connect(connection_id).unwrap();
+        assert_eq!(connection.addr, rebound);
+        assert_eq!(connection.original_addr(), original);
+        assert_eq!(connection_manager.get_ip_connection_num(&rebound.ip()), 1);
+
+        assert!(!connection_manager.update_peer_addr(connection_id + 1, migrated));
+
+        connection_manager.close_connect(connection_id).await;
+        assert!(connection_manager.ip_connection_num.is_empty());
+    }
+}
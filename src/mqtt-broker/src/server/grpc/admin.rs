@@ -12,89 +12,204 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::admin::acl::{create_acl_by_req, delete_acl_by_req, list_acl_by_req};
+use crate::admin::acl::{
+    create_acl_by_req, delete_acl_by_prefix_by_req, delete_acl_by_req, export_acl_by_req,
+    get_acl_config_by_req, get_subscribe_auth_config_by_req, import_acl_by_req, list_acl_by_req,
+    set_acl_default_policy_by_req, test_acl_by_req,
+};
 use crate::admin::blacklist::{
     create_blacklist_by_req, delete_blacklist_by_req, list_blacklist_by_req,
 };
-use crate::admin::client::list_client_by_req;
-use crate::admin::cluster::set_cluster_config_by_req;
+use crate::admin::client::{
+    disconnect_client_by_req, export_client_metrics_by_req, get_duplicate_client_id_config_by_req,
+    get_will_publish_config_by_req, list_client_by_req, redirect_client_by_req,
+};
+use crate::admin::client_group::{
+    delete_client_group_by_req, list_client_group_by_req, set_client_group_by_req,
+    set_client_group_member_by_req, set_user_max_qos_by_req,
+};
+use crate::admin::cluster::{
+    get_client_pool_status_by_req, set_client_pool_size_by_req, set_cluster_config_by_req,
+    set_node_draining_by_req,
+};
 use crate::admin::connector::{
-    create_connector_by_req, delete_connector_by_req, list_connector_by_req,
-    update_connector_by_req,
+    create_connector_by_req, delete_connector_by_req, get_connector_limit_by_req,
+    list_connector_by_req, pause_connector_by_req, resume_connector_by_req,
+    set_connector_limit_by_req, test_connector_routing_by_req, update_connector_by_req,
+    validate_connector_by_req,
 };
+use crate::admin::delay_message::{list_delay_message_by_req, purge_delay_message_by_req};
+use crate::admin::diagnose::self_diagnose_by_req;
 use crate::admin::observability::{
-    list_slow_subscribe_by_req, list_system_alarm_by_req, set_system_alarm_config_by_req,
+    ack_system_alarm_by_req, get_message_sampling_by_req, get_system_alarm_config_by_req,
+    list_listener_metrics_by_req, list_slow_subscribe_by_req, list_system_alarm_by_req,
+    list_thread_pool_by_req, list_top_talkers_by_req, set_message_sampling_by_req,
+    set_system_alarm_config_by_req,
 };
 use crate::admin::schema::{
-    bind_schema_by_req, create_schema_by_req, delete_schema_by_req, list_bind_schema_by_req,
-    list_schema_by_req, unbind_schema_by_req, update_schema_by_req,
+    bind_schema_by_req, create_schema_by_req, delete_schema_by_req, infer_schema_by_req,
+    list_bind_schema_by_req, list_schema_by_req, unbind_schema_by_req, update_schema_by_req,
 };
-use crate::admin::session::list_session_by_req;
+use crate::admin::session::{
+    cancel_inflight_qos2_by_req, get_session_tiering_by_req, list_expired_sessions_by_req,
+    list_inflight_qos2_by_req, list_largest_session_by_req, list_session_by_req,
+    list_session_subscriptions_by_req, purge_expired_sessions_by_req, replay_to_client_by_req,
+    set_session_tiering_by_req,
+};
+use crate::admin::snapshot::{create_snapshot_by_req, restore_snapshot_by_req};
 use crate::admin::subscribe::{
-    delete_auto_subscribe_rule, list_auto_subscribe_rule_by_req, set_auto_subscribe_rule,
+    benchmark_matcher_by_req, delete_auto_subscribe_rule, describe_subscription_by_req,
+    get_subscribe_snapshot_by_req, list_auto_subscribe_rule_by_req, list_shared_dispatch_by_req,
+    list_shared_subscriptions_by_req, list_subscribe_detail_by_req,
+    restore_subscribe_snapshot_by_req, set_auto_subscribe_rule, set_shared_dispatch_by_req,
 };
 use crate::admin::topic::{
-    create_topic_rewrite_rule_by_req, delete_topic_rewrite_rule_by_req,
-    get_all_topic_rewrite_rule_by_req, list_topic_by_req,
+    create_topic_rewrite_rule_by_req, delete_retained_message_by_req,
+    delete_topic_rewrite_rule_by_req, estimate_retain_delivery_by_req, gc_topics_by_req,
+    get_all_topic_rewrite_rule_by_req, get_message_dedup_config_by_req,
+    get_retain_message_config_by_req, list_retain_topic_policy_by_req,
+    list_retained_messages_by_req, list_topic_by_req, reset_topic_sequence_by_req,
+    set_message_dedup_config_by_req, set_retain_deliver_limit_by_req, set_retain_size_limit_by_req,
+    set_retain_topic_policy_by_req, test_topic_rewrite_by_req, topic_cleanup_by_req,
+    topic_sequence_by_req,
+};
+use crate::admin::user::{
+    batch_create_user_by_req, create_user_by_req, delete_user_by_req,
+    delete_user_rate_limit_by_req, get_user_single_session_by_req, list_rate_limit_by_req,
+    list_user_by_req, list_user_message_expiry_by_req, set_user_message_expiry_by_req,
+    set_user_rate_limit_by_req, set_user_single_session_by_req,
 };
-use crate::admin::user::{create_user_by_req, delete_user_by_req, list_user_by_req};
-use crate::admin::{cluster_status_by_req, enable_flapping_detect_by_req, list_connection_by_req};
+use crate::admin::{
+    cluster_status_by_req, enable_flapping_detect_by_req, list_connection_by_req,
+    list_flapping_detect_ban_by_req, set_connection_limit_by_req, stream_connections_by_req,
+    unban_flapping_detect_client_by_req,
+};
+use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::manager::SubscribeManager;
+use delay_message::DelayMessageManager;
+use futures::{Stream, StreamExt};
 use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::MqttBrokerAdminService;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    ClusterStatusReply, ClusterStatusRequest, CreateAclReply, CreateAclRequest,
-    CreateBlacklistReply, CreateBlacklistRequest, CreateTopicRewriteRuleReply,
-    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
-    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
-    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteTopicRewriteRuleReply,
-    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, EnableFlappingDetectReply,
-    EnableFlappingDetectRequest, GetClusterConfigReply, GetClusterConfigRequest, ListAclReply,
-    ListAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
-    ListBlacklistRequest, ListClientReply, ListClientRequest, ListConnectionReply,
-    ListConnectionRequest, ListRewriteTopicRuleReply, ListRewriteTopicRuleRequest,
-    ListSessionReply, ListSessionRequest, ListSlowSubscribeReply, ListSlowSubscribeRequest,
-    ListSystemAlarmReply, ListSystemAlarmRequest, ListTopicReply, ListTopicRequest, ListUserReply,
-    ListUserRequest, MqttBindSchemaReply, MqttBindSchemaRequest, MqttCreateConnectorReply,
+    AckSystemAlarmReply, AckSystemAlarmRequest, BatchCreateUserReply, BatchCreateUserRequest,
+    BenchmarkMatcherReply, BenchmarkMatcherRequest, CancelInflightQos2Reply,
+    CancelInflightQos2Request, ClientMetricsRaw, ClusterStatusReply, ClusterStatusRequest,
+    CreateAclReply, CreateAclRequest, CreateBlacklistReply, CreateBlacklistRequest,
+    CreateSnapshotReply, CreateSnapshotRequest, CreateTopicRewriteRuleReply,
+    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclByPrefixReply,
+    DeleteAclByPrefixRequest, DeleteAclReply, DeleteAclRequest, DeleteAutoSubscribeRuleReply,
+    DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply, DeleteBlacklistRequest,
+    DeleteClientGroupReply, DeleteClientGroupRequest, DeleteRetainedMessageReply,
+    DeleteRetainedMessageRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
+    DeleteUserRateLimitReply, DeleteUserRateLimitRequest, DeleteUserReply, DeleteUserRequest,
+    DescribeSubscriptionReply, DescribeSubscriptionRequest, DisconnectClientReply,
+    DisconnectClientRequest, EnableFlappingDetectReply, EnableFlappingDetectRequest,
+    EstimateRetainDeliveryReply, EstimateRetainDeliveryRequest, ExportAclRaw, ExportAclRequest,
+    ExportClientMetricsRequest, GcTopicsReply, GcTopicsRequest, GetAclConfigReply,
+    GetAclConfigRequest, GetClientPoolStatusReply, GetClientPoolStatusRequest,
+    GetClusterConfigReply, GetClusterConfigRequest, GetDuplicateClientIdConfigReply,
+    GetDuplicateClientIdConfigRequest, GetMessageDedupConfigReply, GetMessageDedupConfigRequest,
+    GetMessageSamplingReply, GetMessageSamplingRequest, GetRetainMessageConfigReply,
+    GetRetainMessageConfigRequest, GetSessionTieringReply, GetSessionTieringRequest,
+    GetSubscribeAuthConfigReply, GetSubscribeAuthConfigRequest, GetSubscribeSnapshotReply,
+    GetSubscribeSnapshotRequest, GetSystemAlarmConfigReply, GetSystemAlarmConfigRequest,
+    GetUserSingleSessionReply, GetUserSingleSessionRequest, GetWillPublishConfigReply,
+    GetWillPublishConfigRequest, ImportAclReply, ImportAclRequest, ListAclReply, ListAclRequest,
+    ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
+    ListBlacklistRequest, ListClientGroupReply, ListClientGroupRequest, ListClientReply,
+    ListClientRequest, ListConnectionReply, ListConnectionRequest, ListDelayMessageReply,
+    ListDelayMessageRequest, ListExpiredSessionsReply, ListExpiredSessionsRequest,
+    ListFlappingDetectBanReply, ListFlappingDetectBanRequest, ListInflightQos2Reply,
+    ListInflightQos2Request, ListLargestSessionReply, ListLargestSessionRequest,
+    ListListenerMetricsReply, ListListenerMetricsRequest, ListRateLimitReply, ListRateLimitRequest,
+    ListRetainTopicPolicyReply, ListRetainTopicPolicyRequest, ListRetainedMessagesReply,
+    ListRetainedMessagesRequest, ListRewriteTopicRuleReply, ListRewriteTopicRuleRequest,
+    ListSessionReply, ListSessionRequest, ListSessionSubscriptionsReply,
+    ListSessionSubscriptionsRequest, ListSharedDispatchReply, ListSharedDispatchRequest,
+    ListSharedSubscriptionsReply, ListSharedSubscriptionsRequest, ListSlowSubscribeReply,
+    ListSlowSubscribeRequest, ListSubscribeDetailReply, ListSubscribeDetailRequest,
+    ListSystemAlarmReply, ListSystemAlarmRequest, ListThreadPoolReply, ListThreadPoolRequest,
+    ListTopTalkersReply, ListTopTalkersRequest, ListTopicReply, ListTopicRequest,
+    ListUserMessageExpiryReply, ListUserMessageExpiryRequest, ListUserReply, ListUserRequest,
+    MqttBindSchemaReply, MqttBindSchemaRequest, MqttCreateConnectorReply,
     MqttCreateConnectorRequest, MqttCreateSchemaReply, MqttCreateSchemaRequest,
     MqttDeleteConnectorReply, MqttDeleteConnectorRequest, MqttDeleteSchemaReply,
-    MqttDeleteSchemaRequest, MqttListBindSchemaReply, MqttListBindSchemaRequest,
-    MqttListConnectorReply, MqttListConnectorRequest, MqttListSchemaReply, MqttListSchemaRequest,
+    MqttDeleteSchemaRequest, MqttGetConnectorLimitReply, MqttGetConnectorLimitRequest,
+    MqttInferSchemaReply, MqttInferSchemaRequest, MqttListBindSchemaReply,
+    MqttListBindSchemaRequest, MqttListConnectorReply, MqttListConnectorRequest,
+    MqttListSchemaReply, MqttListSchemaRequest, MqttPauseConnectorReply, MqttPauseConnectorRequest,
+    MqttResumeConnectorReply, MqttResumeConnectorRequest, MqttSetConnectorLimitReply,
+    MqttSetConnectorLimitRequest, MqttTestConnectorRoutingReply, MqttTestConnectorRoutingRequest,
     MqttUnbindSchemaReply, MqttUnbindSchemaRequest, MqttUpdateConnectorReply,
     MqttUpdateConnectorRequest, MqttUpdateSchemaReply, MqttUpdateSchemaRequest,
-    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetClusterConfigReply,
-    SetClusterConfigRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    MqttValidateConnectorReply, MqttValidateConnectorRequest, PurgeDelayMessageReply,
+    PurgeDelayMessageRequest, PurgeExpiredSessionsReply, PurgeExpiredSessionsRequest,
+    RedirectClientReply, RedirectClientRequest, ReplayToClientReply, ReplayToClientRequest,
+    ResetTopicSequenceReply, ResetTopicSequenceRequest, RestoreSnapshotReply,
+    RestoreSnapshotRequest, RestoreSubscribeSnapshotReply, RestoreSubscribeSnapshotRequest,
+    SelfDiagnoseReply, SelfDiagnoseRequest, SetAclDefaultPolicyReply, SetAclDefaultPolicyRequest,
+    SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest, SetClientGroupMemberReply,
+    SetClientGroupMemberRequest, SetClientGroupReply, SetClientGroupRequest,
+    SetClientPoolSizeReply, SetClientPoolSizeRequest, SetClusterConfigReply,
+    SetClusterConfigRequest, SetConnectionLimitReply, SetConnectionLimitRequest,
+    SetMessageDedupConfigReply, SetMessageDedupConfigRequest, SetMessageSamplingReply,
+    SetMessageSamplingRequest, SetNodeDrainingReply, SetNodeDrainingRequest,
+    SetRetainDeliverLimitReply, SetRetainDeliverLimitRequest, SetRetainSizeLimitReply,
+    SetRetainSizeLimitRequest, SetRetainTopicPolicyReply, SetRetainTopicPolicyRequest,
+    SetSessionTieringReply, SetSessionTieringRequest, SetSharedDispatchReply,
+    SetSharedDispatchRequest, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    SetUserMaxQosReply, SetUserMaxQosRequest, SetUserMessageExpiryReply,
+    SetUserMessageExpiryRequest, SetUserRateLimitReply, SetUserRateLimitRequest,
+    SetUserSingleSessionReply, SetUserSingleSessionRequest, StreamConnectionsReply,
+    StreamConnectionsRequest, TestAclReply, TestAclRequest, TestTopicRewriteReply,
+    TestTopicRewriteRequest, TopicCleanupReply, TopicCleanupRequest, TopicSequenceReply,
+    TopicSequenceRequest, UnbanFlappingDetectClientReply, UnbanFlappingDetectClientRequest,
 };
+use std::pin::Pin;
 use std::sync::Arc;
+use storage_adapter::storage::StorageAdapter;
 use tonic::{Request, Response, Status};
 
-pub struct GrpcAdminServices {
+pub struct GrpcAdminServices<S> {
     client_pool: Arc<ClientPool>,
     cache_manager: Arc<CacheManager>,
     connection_manager: Arc<ConnectionManager>,
     subscribe_manager: Arc<SubscribeManager>,
+    connector_manager: Arc<ConnectorManager>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
+    message_storage_adapter: Arc<S>,
 }
 
-impl GrpcAdminServices {
+impl<S> GrpcAdminServices<S> {
     pub fn new(
         client_pool: Arc<ClientPool>,
         cache_manager: Arc<CacheManager>,
         connection_manager: Arc<ConnectionManager>,
         subscribe_manager: Arc<SubscribeManager>,
+        connector_manager: Arc<ConnectorManager>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
+        message_storage_adapter: Arc<S>,
     ) -> Self {
         GrpcAdminServices {
             client_pool,
             cache_manager,
             connection_manager,
             subscribe_manager,
+            connector_manager,
+            delay_message_manager,
+            message_storage_adapter,
         }
     }
 }
 
 #[tonic::async_trait]
-impl MqttBrokerAdminService for GrpcAdminServices {
+impl<S> MqttBrokerAdminService for GrpcAdminServices<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
     async fn mqtt_broker_set_cluster_config(
         &self,
         request: Request<SetClusterConfigRequest>,
@@ -121,6 +236,35 @@ async fn mqtt_broker_get_cluster_config(
         }))
     }
 
+    async fn mqtt_broker_get_client_pool_status(
+        &self,
+        _request: Request<GetClientPoolStatusRequest>,
+    ) -> Result<Response<GetClientPoolStatusReply>, Status> {
+        Ok(Response::new(
+            get_client_pool_status_by_req(&self.client_pool).await,
+        ))
+    }
+
+    async fn mqtt_broker_set_client_pool_size(
+        &self,
+        request: Request<SetClientPoolSizeRequest>,
+    ) -> Result<Response<SetClientPoolSizeReply>, Status> {
+        set_client_pool_size_by_req(&self.client_pool, &request.into_inner())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_set_node_draining(
+        &self,
+        request: Request<SetNodeDrainingRequest>,
+    ) -> Result<Response<SetNodeDrainingReply>, Status> {
+        Ok(Response::new(set_node_draining_by_req(
+            &self.cache_manager,
+            &request.into_inner(),
+        )))
+    }
+
     // --- cluster ---
     async fn cluster_status(
         &self,
@@ -139,6 +283,24 @@ async fn cluster_status(
         }
     }
 
+    async fn mqtt_broker_self_diagnose(
+        &self,
+        request: Request<SelfDiagnoseRequest>,
+    ) -> Result<Response<SelfDiagnoseReply>, Status> {
+        let reply = self_diagnose_by_req(
+            &self.client_pool,
+            &self.cache_manager,
+            &self.subscribe_manager,
+            &self.connector_manager,
+            &self.connection_manager,
+            request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(reply))
+    }
+
     // --- user ---
     async fn mqtt_broker_create_user(
         &self,
@@ -146,9 +308,18 @@ async fn mqtt_broker_create_user(
     ) -> Result<Response<CreateUserReply>, Status> {
         create_user_by_req(&self.cache_manager, &self.client_pool, request)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
 
-        Ok(Response::new(CreateUserReply {}))
+    async fn mqtt_broker_batch_create_user(
+        &self,
+        request: Request<BatchCreateUserRequest>,
+    ) -> Result<Response<BatchCreateUserReply>, Status> {
+        batch_create_user_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
     }
 
     async fn mqtt_broker_delete_user(
@@ -176,6 +347,131 @@ async fn mqtt_broker_list_user(
         }))
     }
 
+    async fn mqtt_broker_set_user_message_expiry(
+        &self,
+        request: Request<SetUserMessageExpiryRequest>,
+    ) -> Result<Response<SetUserMessageExpiryReply>, Status> {
+        set_user_message_expiry_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetUserMessageExpiryReply {}))
+    }
+
+    async fn mqtt_broker_list_user_message_expiry(
+        &self,
+        request: Request<ListUserMessageExpiryRequest>,
+    ) -> Result<Response<ListUserMessageExpiryReply>, Status> {
+        let (user_message_expiries, count) =
+            list_user_message_expiry_by_req(&self.cache_manager, request)
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListUserMessageExpiryReply {
+            user_message_expiries,
+            total_count: count as u32,
+        }))
+    }
+
+    async fn mqtt_broker_set_user_single_session(
+        &self,
+        request: Request<SetUserSingleSessionRequest>,
+    ) -> Result<Response<SetUserSingleSessionReply>, Status> {
+        set_user_single_session_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetUserSingleSessionReply {}))
+    }
+
+    async fn mqtt_broker_get_user_single_session(
+        &self,
+        _request: Request<GetUserSingleSessionRequest>,
+    ) -> Result<Response<GetUserSingleSessionReply>, Status> {
+        Ok(Response::new(get_user_single_session_by_req(
+            &self.cache_manager,
+        )))
+    }
+
+    async fn mqtt_broker_list_rate_limits(
+        &self,
+        _request: Request<ListRateLimitRequest>,
+    ) -> Result<Response<ListRateLimitReply>, Status> {
+        Ok(Response::new(list_rate_limit_by_req(&self.cache_manager)))
+    }
+
+    async fn mqtt_broker_set_user_rate_limit(
+        &self,
+        request: Request<SetUserRateLimitRequest>,
+    ) -> Result<Response<SetUserRateLimitReply>, Status> {
+        set_user_rate_limit_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetUserRateLimitReply {}))
+    }
+
+    async fn mqtt_broker_delete_user_rate_limit(
+        &self,
+        request: Request<DeleteUserRateLimitRequest>,
+    ) -> Result<Response<DeleteUserRateLimitReply>, Status> {
+        delete_user_rate_limit_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(DeleteUserRateLimitReply {}))
+    }
+
+    async fn mqtt_broker_set_client_group(
+        &self,
+        request: Request<SetClientGroupRequest>,
+    ) -> Result<Response<SetClientGroupReply>, Status> {
+        set_client_group_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetClientGroupReply {}))
+    }
+
+    async fn mqtt_broker_delete_client_group(
+        &self,
+        request: Request<DeleteClientGroupRequest>,
+    ) -> Result<Response<DeleteClientGroupReply>, Status> {
+        delete_client_group_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(DeleteClientGroupReply {}))
+    }
+
+    async fn mqtt_broker_set_client_group_member(
+        &self,
+        request: Request<SetClientGroupMemberRequest>,
+    ) -> Result<Response<SetClientGroupMemberReply>, Status> {
+        set_client_group_member_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetClientGroupMemberReply {}))
+    }
+
+    async fn mqtt_broker_set_user_max_qos(
+        &self,
+        request: Request<SetUserMaxQosRequest>,
+    ) -> Result<Response<SetUserMaxQosReply>, Status> {
+        set_user_max_qos_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetUserMaxQosReply {}))
+    }
+
+    async fn mqtt_broker_list_client_group(
+        &self,
+        _request: Request<ListClientGroupRequest>,
+    ) -> Result<Response<ListClientGroupReply>, Status> {
+        Ok(Response::new(list_client_group_by_req(&self.cache_manager)))
+    }
+
     async fn mqtt_broker_list_client(
         &self,
         request: Request<ListClientRequest>,
@@ -190,13 +486,87 @@ async fn mqtt_broker_list_client(
         }))
     }
 
+    type MqttBrokerExportClientMetricsStream =
+        Pin<Box<dyn Stream<Item = Result<ClientMetricsRaw, Status>> + Send>>;
+
+    async fn mqtt_broker_export_client_metrics(
+        &self,
+        request: Request<ExportClientMetricsRequest>,
+    ) -> Result<Response<Self::MqttBrokerExportClientMetricsStream>, Status> {
+        let rows = export_client_metrics_by_req(
+            &self.cache_manager,
+            &self.subscribe_manager,
+            &self.connection_manager,
+            request,
+        )
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        Ok(Response::new(Box::pin(rows.map(Ok))))
+    }
+
+    async fn mqtt_broker_get_duplicate_client_id_config(
+        &self,
+        _request: Request<GetDuplicateClientIdConfigRequest>,
+    ) -> Result<Response<GetDuplicateClientIdConfigReply>, Status> {
+        get_duplicate_client_id_config_by_req(&self.cache_manager)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_will_publish_config(
+        &self,
+        _request: Request<GetWillPublishConfigRequest>,
+    ) -> Result<Response<GetWillPublishConfigReply>, Status> {
+        Ok(Response::new(get_will_publish_config_by_req(
+            &self.cache_manager,
+        )))
+    }
+
+    async fn mqtt_broker_redirect_client(
+        &self,
+        request: Request<RedirectClientRequest>,
+    ) -> Result<Response<RedirectClientReply>, Status> {
+        redirect_client_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
+    async fn mqtt_broker_disconnect_client(
+        &self,
+        request: Request<DisconnectClientRequest>,
+    ) -> Result<Response<DisconnectClientReply>, Status> {
+        disconnect_client_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
     async fn mqtt_broker_list_session(
         &self,
         request: Request<ListSessionRequest>,
     ) -> Result<Response<ListSessionReply>, Status> {
         let (sessions, count) = list_session_by_req(&self.cache_manager, request)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(|e| match e {
+                MqttBrokerError::InvalidFilterExpression(_) => {
+                    Status::invalid_argument(e.to_string())
+                }
+                _ => Status::internal(e.to_string()),
+            })?;
 
         Ok(Response::new(ListSessionReply {
             sessions,
@@ -204,29 +574,149 @@ async fn mqtt_broker_list_session(
         }))
     }
 
+    async fn mqtt_broker_list_session_subscriptions(
+        &self,
+        request: Request<ListSessionSubscriptionsRequest>,
+    ) -> Result<Response<ListSessionSubscriptionsReply>, Status> {
+        list_session_subscriptions_by_req(&self.cache_manager, &self.subscribe_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_largest_session(
+        &self,
+        request: Request<ListLargestSessionRequest>,
+    ) -> Result<Response<ListLargestSessionReply>, Status> {
+        let sessions =
+            list_largest_session_by_req(&self.cache_manager, &self.subscribe_manager, request)
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListLargestSessionReply { sessions }))
+    }
+
+    async fn mqtt_broker_list_expired_sessions(
+        &self,
+        _request: Request<ListExpiredSessionsRequest>,
+    ) -> Result<Response<ListExpiredSessionsReply>, Status> {
+        list_expired_sessions_by_req(&self.client_pool)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_purge_expired_sessions(
+        &self,
+        _request: Request<PurgeExpiredSessionsRequest>,
+    ) -> Result<Response<PurgeExpiredSessionsReply>, Status> {
+        purge_expired_sessions_by_req(
+            &self.cache_manager,
+            &self.subscribe_manager,
+            &self.client_pool,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_session_tiering(
+        &self,
+        _request: Request<GetSessionTieringRequest>,
+    ) -> Result<Response<GetSessionTieringReply>, Status> {
+        Ok(Response::new(get_session_tiering_by_req(
+            &self.cache_manager,
+        )))
+    }
+
+    async fn mqtt_broker_set_session_tiering(
+        &self,
+        request: Request<SetSessionTieringRequest>,
+    ) -> Result<Response<SetSessionTieringReply>, Status> {
+        set_session_tiering_by_req(&self.client_pool, &self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_inflight_qos2(
+        &self,
+        request: Request<ListInflightQos2Request>,
+    ) -> Result<Response<ListInflightQos2Reply>, Status> {
+        let exchanges = list_inflight_qos2_by_req(&self.cache_manager, request);
+        Ok(Response::new(ListInflightQos2Reply { exchanges }))
+    }
+
+    async fn mqtt_broker_cancel_inflight_qos2(
+        &self,
+        request: Request<CancelInflightQos2Request>,
+    ) -> Result<Response<CancelInflightQos2Reply>, Status> {
+        cancel_inflight_qos2_by_req(&self.client_pool, &self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_replay_to_client(
+        &self,
+        request: Request<ReplayToClientRequest>,
+    ) -> Result<Response<ReplayToClientReply>, Status> {
+        replay_to_client_by_req(
+            &self.cache_manager,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            &self.message_storage_adapter,
+            request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
     async fn mqtt_broker_list_acl(
         &self,
-        _: Request<ListAclRequest>,
+        request: Request<ListAclRequest>,
     ) -> Result<Response<ListAclReply>, Status> {
-        let acls = list_acl_by_req(&self.cache_manager, &self.client_pool)
+        let (acls, count) = list_acl_by_req(&self.cache_manager, &self.client_pool, request)
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
         Ok(Response::new(ListAclReply {
-            acls: acls.clone(),
-            total_count: acls.len() as u32,
+            acls,
+            total_count: count as u32,
         }))
     }
 
+    type MqttBrokerExportAclStream =
+        Pin<Box<dyn Stream<Item = Result<ExportAclRaw, Status>> + Send>>;
+
+    async fn mqtt_broker_export_acl(
+        &self,
+        _request: Request<ExportAclRequest>,
+    ) -> Result<Response<Self::MqttBrokerExportAclStream>, Status> {
+        let acls = export_acl_by_req(&self.cache_manager, &self.client_pool)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(Box::pin(acls.map(Ok))))
+    }
+
+    async fn mqtt_broker_import_acl(
+        &self,
+        request: Request<ImportAclRequest>,
+    ) -> Result<Response<ImportAclReply>, Status> {
+        import_acl_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     async fn mqtt_broker_create_acl(
         &self,
         request: Request<CreateAclRequest>,
     ) -> Result<Response<CreateAclReply>, Status> {
         create_acl_by_req(&self.cache_manager, &self.client_pool, request)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        Ok(Response::new(CreateAclReply {}))
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
     }
 
     async fn mqtt_broker_delete_acl(
@@ -240,6 +730,52 @@ async fn mqtt_broker_delete_acl(
         Ok(Response::new(DeleteAclReply {}))
     }
 
+    async fn mqtt_broker_delete_acl_by_prefix(
+        &self,
+        request: Request<DeleteAclByPrefixRequest>,
+    ) -> Result<Response<DeleteAclByPrefixReply>, Status> {
+        delete_acl_by_prefix_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_acl_config(
+        &self,
+        _request: Request<GetAclConfigRequest>,
+    ) -> Result<Response<GetAclConfigReply>, Status> {
+        Ok(Response::new(get_acl_config_by_req(&self.cache_manager)))
+    }
+
+    async fn mqtt_broker_set_acl_default_policy(
+        &self,
+        request: Request<SetAclDefaultPolicyRequest>,
+    ) -> Result<Response<SetAclDefaultPolicyReply>, Status> {
+        set_acl_default_policy_by_req(&self.client_pool, &self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_test_acl(
+        &self,
+        request: Request<TestAclRequest>,
+    ) -> Result<Response<TestAclReply>, Status> {
+        test_acl_by_req(&self.cache_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_subscribe_auth_config(
+        &self,
+        _request: Request<GetSubscribeAuthConfigRequest>,
+    ) -> Result<Response<GetSubscribeAuthConfigReply>, Status> {
+        get_subscribe_auth_config_by_req(&self.cache_manager)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     async fn mqtt_broker_list_blacklist(
         &self,
         request: Request<ListBlacklistRequest>,
@@ -272,9 +808,8 @@ async fn mqtt_broker_create_blacklist(
     ) -> Result<Response<CreateBlacklistReply>, Status> {
         create_blacklist_by_req(&self.cache_manager, &self.client_pool, request)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        Ok(Response::new(CreateBlacklistReply {}))
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
     }
 
     async fn mqtt_broker_enable_flapping_detect(
@@ -284,6 +819,24 @@ async fn mqtt_broker_enable_flapping_detect(
         enable_flapping_detect_by_req(&self.client_pool, &self.cache_manager, request).await
     }
 
+    async fn mqtt_broker_list_flapping_detect_ban(
+        &self,
+        _request: Request<ListFlappingDetectBanRequest>,
+    ) -> Result<Response<ListFlappingDetectBanReply>, Status> {
+        Ok(Response::new(list_flapping_detect_ban_by_req(
+            &self.cache_manager,
+        )))
+    }
+
+    async fn mqtt_broker_unban_flapping_detect_client(
+        &self,
+        request: Request<UnbanFlappingDetectClientRequest>,
+    ) -> Result<Response<UnbanFlappingDetectClientReply>, Status> {
+        unban_flapping_detect_client_by_req(&self.cache_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     async fn mqtt_broker_set_system_alarm_config(
         &self,
         request: Request<SetSystemAlarmConfigRequest>,
@@ -295,6 +848,15 @@ async fn mqtt_broker_set_system_alarm_config(
             .map(Response::new)
     }
 
+    async fn mqtt_broker_get_system_alarm_config(
+        &self,
+        _request: Request<GetSystemAlarmConfigRequest>,
+    ) -> Result<Response<GetSystemAlarmConfigReply>, Status> {
+        Ok(Response::new(get_system_alarm_config_by_req(
+            &self.cache_manager,
+        )))
+    }
+
     async fn mqtt_broker_list_system_alarm(
         &self,
         request: Request<ListSystemAlarmRequest>,
@@ -306,12 +868,98 @@ async fn mqtt_broker_list_system_alarm(
             .map(Response::new)
     }
 
+    async fn mqtt_broker_ack_system_alarm(
+        &self,
+        request: Request<AckSystemAlarmRequest>,
+    ) -> Result<Response<AckSystemAlarmReply>, Status> {
+        let req = request.into_inner();
+        ack_system_alarm_by_req(&self.cache_manager, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_listener_metrics(
+        &self,
+        _request: Request<ListListenerMetricsRequest>,
+    ) -> Result<Response<ListListenerMetricsReply>, Status> {
+        list_listener_metrics_by_req(&self.connection_manager)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_top_talkers(
+        &self,
+        request: Request<ListTopTalkersRequest>,
+    ) -> Result<Response<ListTopTalkersReply>, Status> {
+        let req = request.into_inner();
+        list_top_talkers_by_req(&self.connection_manager, &self.cache_manager, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_thread_pool(
+        &self,
+        _request: Request<ListThreadPoolRequest>,
+    ) -> Result<Response<ListThreadPoolReply>, Status> {
+        list_thread_pool_by_req(&self.subscribe_manager)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_message_sampling(
+        &self,
+        _request: Request<GetMessageSamplingRequest>,
+    ) -> Result<Response<GetMessageSamplingReply>, Status> {
+        Ok(Response::new(get_message_sampling_by_req(
+            &self.cache_manager,
+        )))
+    }
+
+    async fn mqtt_broker_set_message_sampling(
+        &self,
+        request: Request<SetMessageSamplingRequest>,
+    ) -> Result<Response<SetMessageSamplingReply>, Status> {
+        set_message_sampling_by_req(&self.client_pool, &self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     // --- connection ---
     async fn mqtt_broker_list_connection(
         &self,
-        _: Request<ListConnectionRequest>,
+        request: Request<ListConnectionRequest>,
     ) -> Result<Response<ListConnectionReply>, Status> {
-        list_connection_by_req(&self.connection_manager, &self.cache_manager).await
+        list_connection_by_req(
+            &self.client_pool,
+            &self.connection_manager,
+            &self.cache_manager,
+            request,
+        )
+        .await
+    }
+
+    type MqttBrokerStreamConnectionsStream =
+        Pin<Box<dyn Stream<Item = Result<StreamConnectionsReply, Status>> + Send>>;
+
+    async fn mqtt_broker_stream_connections(
+        &self,
+        request: Request<StreamConnectionsRequest>,
+    ) -> Result<Response<Self::MqttBrokerStreamConnectionsStream>, Status> {
+        let chunks =
+            stream_connections_by_req(&self.connection_manager, &self.cache_manager, request)?;
+
+        Ok(Response::new(Box::pin(chunks)))
+    }
+
+    async fn mqtt_broker_set_connection_limit(
+        &self,
+        request: Request<SetConnectionLimitRequest>,
+    ) -> Result<Response<SetConnectionLimitReply>, Status> {
+        set_connection_limit_by_req(&self.client_pool, &self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
     }
 
     async fn mqtt_broker_list_slow_subscribe(
@@ -325,9 +973,10 @@ async fn mqtt_broker_list_topic(
         &self,
         request: Request<ListTopicRequest>,
     ) -> Result<Response<ListTopicReply>, Status> {
-        let (topics, count) = list_topic_by_req(&self.cache_manager, request)
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+        let (topics, count) =
+            list_topic_by_req(&self.cache_manager, &self.subscribe_manager, request)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
 
         Ok(Response::new(ListTopicReply {
             topics,
@@ -357,6 +1006,16 @@ async fn mqtt_broker_create_topic_rewrite_rule(
         Ok(Response::new(CreateTopicRewriteRuleReply {}))
     }
 
+    async fn mqtt_broker_test_topic_rewrite(
+        &self,
+        request: Request<TestTopicRewriteRequest>,
+    ) -> Result<Response<TestTopicRewriteReply>, Status> {
+        test_topic_rewrite_by_req(&self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     async fn mqtt_broker_get_all_topic_rewrite_rule(
         &self,
         _request: Request<ListRewriteTopicRuleRequest>,
@@ -375,11 +1034,10 @@ async fn mqtt_broker_list_connector(
         &self,
         request: Request<MqttListConnectorRequest>,
     ) -> Result<Response<MqttListConnectorReply>, Status> {
-        let connectors = list_connector_by_req(&self.client_pool, request)
+        list_connector_by_req(&self.client_pool, &self.connector_manager, request)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
-
-        Ok(Response::new(MqttListConnectorReply { connectors }))
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
     }
 
     async fn mqtt_broker_create_connector(
@@ -415,6 +1073,67 @@ async fn mqtt_broker_update_connector(
         Ok(Response::new(MqttUpdateConnectorReply {}))
     }
 
+    async fn mqtt_broker_pause_connector(
+        &self,
+        request: Request<MqttPauseConnectorRequest>,
+    ) -> Result<Response<MqttPauseConnectorReply>, Status> {
+        pause_connector_by_req(&self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(MqttPauseConnectorReply {}))
+    }
+
+    async fn mqtt_broker_resume_connector(
+        &self,
+        request: Request<MqttResumeConnectorRequest>,
+    ) -> Result<Response<MqttResumeConnectorReply>, Status> {
+        resume_connector_by_req(&self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(MqttResumeConnectorReply {}))
+    }
+
+    async fn mqtt_broker_validate_connector(
+        &self,
+        request: Request<MqttValidateConnectorRequest>,
+    ) -> Result<Response<MqttValidateConnectorReply>, Status> {
+        validate_connector_by_req(request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_test_connector_routing(
+        &self,
+        request: Request<MqttTestConnectorRoutingRequest>,
+    ) -> Result<Response<MqttTestConnectorRoutingReply>, Status> {
+        test_connector_routing_by_req(&self.cache_manager, &self.connector_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_connector_limit(
+        &self,
+        _request: Request<MqttGetConnectorLimitRequest>,
+    ) -> Result<Response<MqttGetConnectorLimitReply>, Status> {
+        Ok(Response::new(get_connector_limit_by_req(
+            &self.cache_manager,
+            &self.connector_manager,
+        )))
+    }
+
+    async fn mqtt_broker_set_connector_limit(
+        &self,
+        request: Request<MqttSetConnectorLimitRequest>,
+    ) -> Result<Response<MqttSetConnectorLimitReply>, Status> {
+        set_connector_limit_by_req(&self.client_pool, &self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     // --- schema ---
     async fn mqtt_broker_list_schema(
         &self,
@@ -460,6 +1179,15 @@ async fn mqtt_broker_delete_schema(
         Ok(Response::new(MqttDeleteSchemaReply {}))
     }
 
+    async fn mqtt_broker_infer_schema(
+        &self,
+        request: Request<MqttInferSchemaRequest>,
+    ) -> Result<Response<MqttInferSchemaReply>, Status> {
+        infer_schema_by_req(request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
     async fn mqtt_broker_list_bind_schema(
         &self,
         request: Request<MqttListBindSchemaRequest>,
@@ -527,4 +1255,290 @@ async fn mqtt_broker_list_auto_subscribe_rule(
             auto_subscribe_rules,
         }))
     }
+
+    async fn mqtt_broker_set_shared_dispatch(
+        &self,
+        request: Request<SetSharedDispatchRequest>,
+    ) -> Result<Response<SetSharedDispatchReply>, Status> {
+        set_shared_dispatch_by_req(&self.client_pool, &self.cache_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetSharedDispatchReply {}))
+    }
+
+    async fn mqtt_broker_list_shared_dispatch(
+        &self,
+        request: Request<ListSharedDispatchRequest>,
+    ) -> Result<Response<ListSharedDispatchReply>, Status> {
+        let groups = list_shared_dispatch_by_req(&self.cache_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListSharedDispatchReply { groups }))
+    }
+
+    async fn mqtt_broker_list_shared_subscriptions(
+        &self,
+        request: Request<ListSharedSubscriptionsRequest>,
+    ) -> Result<Response<ListSharedSubscriptionsReply>, Status> {
+        let groups = list_shared_subscriptions_by_req(&self.subscribe_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListSharedSubscriptionsReply { groups }))
+    }
+
+    async fn mqtt_broker_list_subscribe_detail(
+        &self,
+        request: Request<ListSubscribeDetailRequest>,
+    ) -> Result<Response<ListSubscribeDetailReply>, Status> {
+        let (subscriptions, count) = list_subscribe_detail_by_req(
+            &self.subscribe_manager,
+            &self.message_storage_adapter,
+            request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListSubscribeDetailReply {
+            subscriptions,
+            total_count: count as u32,
+        }))
+    }
+
+    async fn mqtt_broker_describe_subscription(
+        &self,
+        request: Request<DescribeSubscriptionRequest>,
+    ) -> Result<Response<DescribeSubscriptionReply>, Status> {
+        describe_subscription_by_req(&self.cache_manager, &self.subscribe_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_subscribe_snapshot(
+        &self,
+        request: Request<GetSubscribeSnapshotRequest>,
+    ) -> Result<Response<GetSubscribeSnapshotReply>, Status> {
+        get_subscribe_snapshot_by_req(&self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_restore_subscribe_snapshot(
+        &self,
+        request: Request<RestoreSubscribeSnapshotRequest>,
+    ) -> Result<Response<RestoreSubscribeSnapshotReply>, Status> {
+        restore_subscribe_snapshot_by_req(&self.client_pool, &self.subscribe_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_benchmark_matcher(
+        &self,
+        request: Request<BenchmarkMatcherRequest>,
+    ) -> Result<Response<BenchmarkMatcherReply>, Status> {
+        benchmark_matcher_by_req(&self.cache_manager, &self.subscribe_manager, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_get_retain_message_config(
+        &self,
+        _request: Request<GetRetainMessageConfigRequest>,
+    ) -> Result<Response<GetRetainMessageConfigReply>, Status> {
+        get_retain_message_config_by_req(&self.cache_manager)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_set_retain_size_limit(
+        &self,
+        request: Request<SetRetainSizeLimitRequest>,
+    ) -> Result<Response<SetRetainSizeLimitReply>, Status> {
+        set_retain_size_limit_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetRetainSizeLimitReply {}))
+    }
+
+    async fn mqtt_broker_set_retain_topic_policy(
+        &self,
+        request: Request<SetRetainTopicPolicyRequest>,
+    ) -> Result<Response<SetRetainTopicPolicyReply>, Status> {
+        set_retain_topic_policy_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetRetainTopicPolicyReply {}))
+    }
+
+    async fn mqtt_broker_list_retain_topic_policy(
+        &self,
+        _request: Request<ListRetainTopicPolicyRequest>,
+    ) -> Result<Response<ListRetainTopicPolicyReply>, Status> {
+        list_retain_topic_policy_by_req(&self.cache_manager)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_set_retain_deliver_limit(
+        &self,
+        request: Request<SetRetainDeliverLimitRequest>,
+    ) -> Result<Response<SetRetainDeliverLimitReply>, Status> {
+        set_retain_deliver_limit_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetRetainDeliverLimitReply {}))
+    }
+
+    async fn mqtt_broker_estimate_retain_delivery(
+        &self,
+        request: Request<EstimateRetainDeliveryRequest>,
+    ) -> Result<Response<EstimateRetainDeliveryReply>, Status> {
+        estimate_retain_delivery_by_req(&self.cache_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_list_retained_messages(
+        &self,
+        request: Request<ListRetainedMessagesRequest>,
+    ) -> Result<Response<ListRetainedMessagesReply>, Status> {
+        list_retained_messages_by_req(&self.cache_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_delete_retained_message(
+        &self,
+        request: Request<DeleteRetainedMessageRequest>,
+    ) -> Result<Response<DeleteRetainedMessageReply>, Status> {
+        delete_retained_message_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_set_message_dedup_config(
+        &self,
+        request: Request<SetMessageDedupConfigRequest>,
+    ) -> Result<Response<SetMessageDedupConfigReply>, Status> {
+        set_message_dedup_config_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(SetMessageDedupConfigReply {}))
+    }
+
+    async fn mqtt_broker_get_message_dedup_config(
+        &self,
+        _request: Request<GetMessageDedupConfigRequest>,
+    ) -> Result<Response<GetMessageDedupConfigReply>, Status> {
+        Ok(Response::new(get_message_dedup_config_by_req(
+            &self.cache_manager,
+        )))
+    }
+
+    async fn mqtt_broker_topic_cleanup(
+        &self,
+        request: Request<TopicCleanupRequest>,
+    ) -> Result<Response<TopicCleanupReply>, Status> {
+        topic_cleanup_by_req(
+            &self.cache_manager,
+            &self.subscribe_manager,
+            &self.client_pool,
+            request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
+    async fn mqtt_broker_gc_topics(
+        &self,
+        request: Request<GcTopicsRequest>,
+    ) -> Result<Response<GcTopicsReply>, Status> {
+        gc_topics_by_req(
+            &self.cache_manager,
+            &self.subscribe_manager,
+            &self.client_pool,
+            request,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
+    async fn mqtt_broker_topic_sequence(
+        &self,
+        request: Request<TopicSequenceRequest>,
+    ) -> Result<Response<TopicSequenceReply>, Status> {
+        let topics = topic_sequence_by_req(&self.cache_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(TopicSequenceReply { topics }))
+    }
+
+    async fn mqtt_broker_reset_topic_sequence(
+        &self,
+        request: Request<ResetTopicSequenceRequest>,
+    ) -> Result<Response<ResetTopicSequenceReply>, Status> {
+        reset_topic_sequence_by_req(&self.cache_manager, request)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    // --- delay message ---
+    async fn mqtt_broker_list_delay_message(
+        &self,
+        request: Request<ListDelayMessageRequest>,
+    ) -> Result<Response<ListDelayMessageReply>, Status> {
+        let (delay_messages, count) =
+            list_delay_message_by_req(&self.cache_manager, &self.delay_message_manager, request)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListDelayMessageReply {
+            delay_messages,
+            total_count: count as u32,
+        }))
+    }
+
+    async fn mqtt_broker_purge_delay_message(
+        &self,
+        request: Request<PurgeDelayMessageRequest>,
+    ) -> Result<Response<PurgeDelayMessageReply>, Status> {
+        let purged_num =
+            purge_delay_message_by_req(&self.cache_manager, &self.delay_message_manager, request)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(PurgeDelayMessageReply { purged_num }))
+    }
+
+    // --- snapshot ---
+    async fn mqtt_broker_create_snapshot(
+        &self,
+        _request: Request<CreateSnapshotRequest>,
+    ) -> Result<Response<CreateSnapshotReply>, Status> {
+        create_snapshot_by_req(&self.cache_manager, &self.client_pool)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn mqtt_broker_restore_snapshot(
+        &self,
+        request: Request<RestoreSnapshotRequest>,
+    ) -> Result<Response<RestoreSnapshotReply>, Status> {
+        restore_snapshot_by_req(&self.cache_manager, &self.client_pool, request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
 }
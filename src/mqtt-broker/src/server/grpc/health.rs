@@ -0,0 +1,215 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::SERVICE_NAME;
+use storage_adapter::storage::StorageAdapter;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout};
+use tonic_health::server::{HealthReporter, HealthService};
+use tonic_health::ServingStatus;
+use tracing::{info, warn};
+
+pub const BROKER_HEALTH_CHECK_INTERVAL_MS: u64 = 5000;
+const BROKER_HEALTH_STORAGE_TIMEOUT_MS: u64 = 3000;
+// Offsets are looked up for a group nobody consumes with, which is a round trip to the
+// storage for every adapter without touching any data
+const BROKER_HEALTH_PROBE_GROUP: &str = "__robustmq_health_check";
+
+// Backs the standard grpc.health.v1.Health service. The status is reported for the broker as a
+// whole, the empty service name, and for the admin service. The broker is SERVING once it has
+// started and while its message storage answers.
+pub struct BrokerHealth {
+    reporter: HealthReporter,
+    started: AtomicBool,
+}
+
+impl Default for BrokerHealth {
+    fn default() -> Self {
+        BrokerHealth {
+            reporter: HealthReporter::new(),
+            started: AtomicBool::new(false),
+        }
+    }
+}
+
+impl BrokerHealth {
+    pub fn health_service(&self) -> HealthService {
+        HealthService::from_health_reporter(self.reporter.clone())
+    }
+
+    pub fn mark_started(&self) {
+        self.started.store(true, Ordering::Release);
+    }
+
+    // Called when the broker begins to stop, so that it is taken out of rotation right away
+    pub async fn mark_stopped(&self) {
+        self.started.store(false, Ordering::Release);
+        self.set_status(ServingStatus::NotServing).await;
+    }
+
+    pub async fn refresh<S>(&self, message_storage_adapter: &Arc<S>) -> ServingStatus
+    where
+        S: StorageAdapter + Sync + Send + 'static + Clone,
+    {
+        let status = if self.started.load(Ordering::Acquire)
+            && is_storage_available(message_storage_adapter).await
+        {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+        self.set_status(status).await;
+        status
+    }
+
+    async fn set_status(&self, status: ServingStatus) {
+        // The reporter only notifies watchers when the status changes
+        let mut reporter = self.reporter.clone();
+        reporter.set_service_status("", status).await;
+        reporter.set_service_status(SERVICE_NAME, status).await;
+    }
+}
+
+pub async fn is_storage_available<S>(message_storage_adapter: &Arc<S>) -> bool
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    match timeout(
+        Duration::from_millis(BROKER_HEALTH_STORAGE_TIMEOUT_MS),
+        message_storage_adapter.get_offset_by_group(BROKER_HEALTH_PROBE_GROUP.to_string()),
+    )
+    .await
+    {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            warn!("Health check could not reach the message storage: {}", e);
+            false
+        }
+        Err(_) => {
+            warn!(
+                "Health check timed out reaching the message storage after {}ms",
+                BROKER_HEALTH_STORAGE_TIMEOUT_MS
+            );
+            false
+        }
+    }
+}
+
+// Re-checks the readiness of the broker for the health service
+pub struct BrokerHealthThread<S> {
+    stop_send: broadcast::Sender<bool>,
+    broker_health: Arc<BrokerHealth>,
+    message_storage_adapter: Arc<S>,
+}
+
+impl<S> BrokerHealthThread<S>
+where
+    S: StorageAdapter + Sync + Send + 'static + Clone,
+{
+    pub fn new(
+        stop_send: broadcast::Sender<bool>,
+        broker_health: Arc<BrokerHealth>,
+        message_storage_adapter: Arc<S>,
+    ) -> Self {
+        Self {
+            stop_send,
+            broker_health,
+            message_storage_adapter,
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Broker health check thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.check()=>{
+                }
+            }
+        }
+    }
+
+    async fn check(&self) {
+        self.broker_health
+            .refresh(&self.message_storage_adapter)
+            .await;
+        sleep(Duration::from_millis(BROKER_HEALTH_CHECK_INTERVAL_MS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::SERVICE_NAME;
+    use storage_adapter::memory::MemoryStorageAdapter;
+    use tonic::Request;
+    use tonic_health::pb::health_check_response::ServingStatus as PbServingStatus;
+    use tonic_health::pb::health_server::Health;
+    use tonic_health::pb::HealthCheckRequest;
+    use tonic_health::ServingStatus;
+
+    use super::BrokerHealth;
+
+    async fn check(health: &BrokerHealth, service: &str) -> i32 {
+        health
+            .health_service()
+            .check(Request::new(HealthCheckRequest {
+                service: service.to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .status
+    }
+
+    #[tokio::test]
+    async fn broker_health_test() {
+        let storage = Arc::new(MemoryStorageAdapter::new());
+        let health = BrokerHealth::default();
+
+        // Not ready before the broker has started
+        assert_eq!(health.refresh(&storage).await, ServingStatus::NotServing);
+        for service in ["", SERVICE_NAME] {
+            assert_eq!(
+                check(&health, service).await,
+                PbServingStatus::NotServing as i32
+            );
+        }
+
+        health.mark_started();
+        assert_eq!(health.refresh(&storage).await, ServingStatus::Serving);
+        for service in ["", SERVICE_NAME] {
+            assert_eq!(
+                check(&health, service).await,
+                PbServingStatus::Serving as i32
+            );
+        }
+
+        health.mark_stopped().await;
+        assert_eq!(check(&health, "").await, PbServingStatus::NotServing as i32);
+        assert_eq!(health.refresh(&storage).await, ServingStatus::NotServing);
+    }
+}
@@ -17,8 +17,10 @@
 use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_server::MqttBrokerInnerService;
 use protocol::broker_mqtt::broker_mqtt_inner::{
-    DeleteSessionReply, DeleteSessionRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
-    UpdateMqttCacheReply, UpdateMqttCacheRequest,
+    DeleteSessionReply, DeleteSessionRequest, GetBrokerTimeReply, GetBrokerTimeRequest,
+    GetNodeResourceReply, GetNodeResourceRequest, GetTopicActivityReply, GetTopicActivityRequest,
+    KickUserSessionReply, KickUserSessionRequest, SendLastWillMessageReply,
+    SendLastWillMessageRequest, UpdateMqttCacheReply, UpdateMqttCacheRequest,
 };
 use schema_register::schema::SchemaRegisterManager;
 use storage_adapter::storage::StorageAdapter;
@@ -27,8 +29,11 @@
 use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
 use crate::inner::services::{
-    delete_session_by_req, send_last_will_message_by_req, update_cache_by_req,
+    delete_session_by_req, get_broker_time_by_req, get_node_resource_by_req,
+    get_topic_activity_by_req, kick_user_session_by_req, send_last_will_message_by_req,
+    update_cache_by_req,
 };
+use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::manager::SubscribeManager;
 
 pub struct GrpcInnerServices<S> {
@@ -37,6 +42,7 @@ pub struct GrpcInnerServices<S> {
     subscribe_manager: Arc<SubscribeManager>,
     schema_manager: Arc<SchemaRegisterManager>,
     client_pool: Arc<ClientPool>,
+    connection_manager: Arc<ConnectionManager>,
     message_storage_adapter: Arc<S>,
 }
 
@@ -47,6 +53,7 @@ pub fn new(
         connector_manager: Arc<ConnectorManager>,
         schema_manager: Arc<SchemaRegisterManager>,
         client_pool: Arc<ClientPool>,
+        connection_manager: Arc<ConnectionManager>,
         message_storage_adapter: Arc<S>,
     ) -> Self {
         GrpcInnerServices {
@@ -54,6 +61,7 @@ pub fn new(
             subscribe_manager,
             connector_manager,
             client_pool,
+            connection_manager,
             message_storage_adapter,
             schema_manager,
         }
@@ -108,4 +116,48 @@ async fn send_last_will_message(
         .map_err(|e| Status::internal(e.to_string()))
         .map(Response::new)
     }
+
+    async fn kick_user_session(
+        &self,
+        request: Request<KickUserSessionRequest>,
+    ) -> Result<Response<KickUserSessionReply>, Status> {
+        let req = request.into_inner();
+        kick_user_session_by_req(
+            &self.cache_manager,
+            &self.client_pool,
+            &self.connection_manager,
+            &self.subscribe_manager,
+            &req,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))
+        .map(Response::new)
+    }
+
+    async fn get_node_resource(
+        &self,
+        _: Request<GetNodeResourceRequest>,
+    ) -> Result<Response<GetNodeResourceReply>, Status> {
+        Ok(Response::new(get_node_resource_by_req(&self.cache_manager)))
+    }
+
+    async fn get_topic_activity(
+        &self,
+        request: Request<GetTopicActivityRequest>,
+    ) -> Result<Response<GetTopicActivityReply>, Status> {
+        let req = request.into_inner();
+        get_topic_activity_by_req(&self.cache_manager, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
+
+    async fn get_broker_time(
+        &self,
+        request: Request<GetBrokerTimeRequest>,
+    ) -> Result<Response<GetBrokerTimeReply>, Status> {
+        let req = request.into_inner();
+        get_broker_time_by_req(&self.cache_manager, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
 }
@@ -13,5 +13,6 @@
 // limitations under the License.
 
 mod admin;
+pub mod health;
 mod inner;
 pub mod server;
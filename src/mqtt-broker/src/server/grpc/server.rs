@@ -15,14 +15,18 @@
 use std::sync::Arc;
 
 use common_base::error::common::CommonError;
+use delay_message::DelayMessageManager;
 use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_admin::mqtt_broker_admin_service_server::MqttBrokerAdminServiceServer;
+use protocol::broker_mqtt::broker_mqtt_admin::FILE_DESCRIPTOR_SET;
 use protocol::broker_mqtt::broker_mqtt_inner::mqtt_broker_inner_service_server::MqttBrokerInnerServiceServer;
 use schema_register::schema::SchemaRegisterManager;
 use storage_adapter::storage::StorageAdapter;
 use tonic::transport::Server;
+use tonic_health::pb::health_server::HealthServer;
 use tracing::info;
 
+use super::health::BrokerHealth;
 use super::inner::GrpcInnerServices;
 use crate::bridge::manager::ConnectorManager;
 use crate::handler::cache::CacheManager;
@@ -39,6 +43,8 @@ pub struct GrpcServer<S> {
     schema_manager: Arc<SchemaRegisterManager>,
     client_pool: Arc<ClientPool>,
     message_storage_adapter: Arc<S>,
+    delay_message_manager: Arc<DelayMessageManager<S>>,
+    broker_health: Arc<BrokerHealth>,
 }
 
 impl<S> GrpcServer<S>
@@ -55,6 +61,8 @@ pub fn new(
         schema_manager: Arc<SchemaRegisterManager>,
         client_pool: Arc<ClientPool>,
         message_storage_adapter: Arc<S>,
+        delay_message_manager: Arc<DelayMessageManager<S>>,
+        broker_health: Arc<BrokerHealth>,
     ) -> Self {
         Self {
             port,
@@ -65,6 +73,8 @@ pub fn new(
             client_pool,
             message_storage_adapter,
             schema_manager,
+            delay_message_manager,
+            broker_health,
         }
     }
     pub async fn start(&self) -> Result<(), CommonError> {
@@ -76,6 +86,7 @@ pub async fn start(&self) -> Result<(), CommonError> {
             self.connector_manager.clone(),
             self.schema_manager.clone(),
             self.client_pool.clone(),
+            self.connection_manager.clone(),
             self.message_storage_adapter.clone(),
         );
         let admin_handler = GrpcAdminServices::new(
@@ -83,13 +94,27 @@ pub async fn start(&self) -> Result<(), CommonError> {
             self.metadata_cache.clone(),
             self.connection_manager.clone(),
             self.subscribe_manager.clone(),
+            self.connector_manager.clone(),
+            self.delay_message_manager.clone(),
+            self.message_storage_adapter.clone(),
         );
+        // Report the real status before the first health check can be answered
+        self.broker_health
+            .refresh(&self.message_storage_adapter)
+            .await;
+        let reflection_service = tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+            .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+            .build_v1()
+            .map_err(|e| CommonError::CommonError(e.to_string()))?;
         Server::builder()
             .accept_http1(true)
             .layer(tower_http::cors::CorsLayer::very_permissive())
             .layer(tonic_web::GrpcWebLayer::new())
             .add_service(MqttBrokerInnerServiceServer::new(inner_handler))
             .add_service(MqttBrokerAdminServiceServer::new(admin_handler))
+            .add_service(HealthServer::new(self.broker_health.health_service()))
+            .add_service(reflection_service)
             .serve(addr)
             .await?;
         Ok(())
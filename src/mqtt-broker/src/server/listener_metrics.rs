@@ -0,0 +1,115 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use common_base::tools::now_second;
+
+// Accept rate is averaged over this many seconds
+pub const ACCEPT_RATE_WINDOW_SEC: u64 = 60;
+
+// Operational counters of one listener, maintained in the accept path
+#[derive(Default)]
+pub struct ListenerMetrics {
+    pub accept_num: AtomicU64,
+    // The TLS (or QUIC) handshake failed before the connection was established
+    pub tls_handshake_failure_num: AtomicU64,
+    // The connection sent bytes that could not be decoded before CONNECT completed
+    pub malformed_connect_num: AtomicU64,
+    // Accepts per second, oldest first
+    accept_window: Mutex<VecDeque<(u64, u64)>>,
+}
+
+impl ListenerMetrics {
+    pub fn record_accept(&self) {
+        self.record_accept_at(now_second());
+    }
+
+    pub fn record_tls_handshake_failure(&self) {
+        self.tls_handshake_failure_num
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_malformed_connect(&self) {
+        self.malformed_connect_num.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn accept_num(&self) -> u64 {
+        self.accept_num.load(Ordering::Relaxed)
+    }
+
+    pub fn tls_handshake_failure_num(&self) -> u64 {
+        self.tls_handshake_failure_num.load(Ordering::Relaxed)
+    }
+
+    pub fn malformed_connect_num(&self) -> u64 {
+        self.malformed_connect_num.load(Ordering::Relaxed)
+    }
+
+    // Average number of accepted connections per second over the last ACCEPT_RATE_WINDOW_SEC seconds
+    pub fn accept_rate(&self) -> f64 {
+        self.accept_rate_at(now_second())
+    }
+
+    fn record_accept_at(&self, now: u64) {
+        self.accept_num.fetch_add(1, Ordering::Relaxed);
+
+        let mut window = self.accept_window.lock().unwrap();
+        match window.back_mut() {
+            Some((second, num)) if *second == now => *num += 1,
+            _ => window.push_back((now, 1)),
+        }
+        Self::evict(&mut window, now);
+    }
+
+    fn accept_rate_at(&self, now: u64) -> f64 {
+        let mut window = self.accept_window.lock().unwrap();
+        Self::evict(&mut window, now);
+        let total: u64 = window.iter().map(|(_, num)| num).sum();
+        total as f64 / ACCEPT_RATE_WINDOW_SEC as f64
+    }
+
+    fn evict(window: &mut VecDeque<(u64, u64)>, now: u64) {
+        while let Some((second, _)) = window.front() {
+            if now.saturating_sub(*second) < ACCEPT_RATE_WINDOW_SEC {
+                break;
+            }
+            window.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ListenerMetrics, ACCEPT_RATE_WINDOW_SEC};
+
+    #[test]
+    fn accept_rate_test() {
+        let metrics = ListenerMetrics::default();
+        for _ in 0..30 {
+            metrics.record_accept_at(100);
+        }
+        for _ in 0..30 {
+            metrics.record_accept_at(110);
+        }
+        assert_eq!(metrics.accept_num(), 60);
+        assert_eq!(metrics.accept_rate_at(110), 1.0);
+
+        // The first second drops out of the window
+        assert_eq!(metrics.accept_rate_at(100 + ACCEPT_RATE_WINDOW_SEC), 0.5);
+        assert_eq!(metrics.accept_rate_at(110 + ACCEPT_RATE_WINDOW_SEC), 0.0);
+        assert_eq!(metrics.accept_num(), 60);
+    }
+}
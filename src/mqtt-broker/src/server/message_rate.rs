@@ -0,0 +1,125 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use common_base::tools::now_mills;
+
+// The rate is counted over a sliding one second window made of buckets of this length
+pub const MESSAGE_RATE_BUCKET_MS: u64 = 100;
+const MESSAGE_RATE_BUCKET_NUM: usize = 10;
+
+// Inbound and outbound PUBLISH packets per second of this broker
+#[derive(Default)]
+pub struct MessageRateTracker {
+    message_in: RateWindow,
+    message_out: RateWindow,
+}
+
+impl MessageRateTracker {
+    pub fn record_message_in(&self) {
+        self.message_in.record_at(now_ms());
+    }
+
+    pub fn record_message_out(&self) {
+        self.message_out.record_at(now_ms());
+    }
+
+    pub fn message_in_rate(&self) -> u64 {
+        self.message_in.rate_at(now_ms())
+    }
+
+    pub fn message_out_rate(&self) -> u64 {
+        self.message_out.rate_at(now_ms())
+    }
+}
+
+// A ring of buckets indexed by bucket id, so that recording a message only takes atomic
+// operations on the publish path
+#[derive(Default)]
+struct RateWindow {
+    // (bucket id, number of messages in the bucket)
+    buckets: [(AtomicU64, AtomicU64); MESSAGE_RATE_BUCKET_NUM],
+}
+
+impl RateWindow {
+    fn record_at(&self, now_ms: u64) {
+        let bucket_id = now_ms / MESSAGE_RATE_BUCKET_MS;
+        let (id, num) = &self.buckets[bucket_id as usize % MESSAGE_RATE_BUCKET_NUM];
+        // The first message of a new bucket resets it. A message recorded by another
+        // thread while the bucket is reset may be lost, which is fine for a rate.
+        if id.load(Ordering::Relaxed) != bucket_id
+            && id.swap(bucket_id, Ordering::Relaxed) != bucket_id
+        {
+            num.store(0, Ordering::Relaxed);
+        }
+        num.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Messages in the current bucket and the buckets before it within the window
+    fn rate_at(&self, now_ms: u64) -> u64 {
+        let bucket_id = now_ms / MESSAGE_RATE_BUCKET_MS;
+        self.buckets
+            .iter()
+            .filter(|(id, _)| {
+                let id = id.load(Ordering::Relaxed);
+                id <= bucket_id && bucket_id - id < MESSAGE_RATE_BUCKET_NUM as u64
+            })
+            .map(|(_, num)| num.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+fn now_ms() -> u64 {
+    now_mills() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessageRateTracker, RateWindow, MESSAGE_RATE_BUCKET_MS};
+
+    #[test]
+    fn rate_window_test() {
+        let window = RateWindow::default();
+        let start = 1_000_000;
+        // 500 messages spread evenly over one second
+        for i in 0..500 {
+            window.record_at(start + i * 2);
+        }
+        let rate = window.rate_at(start + 999);
+        assert!((450..=500).contains(&rate), "rate {}", rate);
+
+        // Half a second later only the second half of the messages is in the window
+        let rate = window.rate_at(start + 1499);
+        assert!((200..=300).contains(&rate), "rate {}", rate);
+
+        // Buckets that are reused after the window passed do not keep their old count
+        window.record_at(start + 10 * MESSAGE_RATE_BUCKET_MS);
+        assert_eq!(window.rate_at(start + 1999), 1);
+        assert_eq!(window.rate_at(start + 5000), 0);
+    }
+
+    #[test]
+    fn message_rate_tracker_test() {
+        let tracker = MessageRateTracker::default();
+        for _ in 0..100 {
+            tracker.record_message_in();
+        }
+        for _ in 0..40 {
+            tracker.record_message_out();
+        }
+        // Recording takes far less than the window, so every message is still counted
+        assert_eq!(tracker.message_in_rate(), 100);
+        assert_eq!(tracker.message_out_rate(), 40);
+    }
+}
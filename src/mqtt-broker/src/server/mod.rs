@@ -15,10 +15,13 @@
 pub mod connection;
 pub mod connection_manager;
 pub mod grpc;
+pub mod listener_metrics;
+pub mod message_rate;
 mod metric;
 pub mod packet;
 pub mod quic;
 #[allow(clippy::module_inception)]
 pub mod server;
 pub mod tcp;
+pub mod top_talkers;
 pub mod websocket;
@@ -22,7 +22,9 @@
 use crate::server::packet::RequestPackage;
 use crate::server::quic::quic_stream_wrapper::{QuicFramedReadStream, QuicFramedWriteStream};
 use protocol::mqtt::codec::MqttCodec;
-use quinn::Endpoint;
+use protocol::mqtt::common::MqttPacket;
+use quinn::{Connection, Endpoint};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::select;
 use tokio::sync::broadcast;
@@ -62,10 +64,12 @@ pub(crate) async fn acceptor_process(
                     val = endpoint.accept()=> {
                         match val {
                             Some(incoming) => {
+                                connection_manager.record_listener_accept(&network_type);
                                 match incoming.await {
                                 Ok(connection) => {
                                         info!("accept quic connection:{:?}",connection.remote_address());
                                         let client_addr = connection.remote_address();
+                                        let quic_connection = connection.clone();
                                         match connection.accept_bi().await {
                                             Ok((w_stream, r_stream)) => {
                                                     let codec = MqttCodec::new(None);
@@ -81,7 +85,7 @@ pub(crate) async fn acceptor_process(
                                                 );
                                                 connection_manager.add_connection(connection.clone());
                                                 connection_manager.add_quic_write(connection.connection_id, quic_framed_write_stream);
-                                                read_frame_process(quic_framed_read_stream, connection.clone(), raw_request_queue_sx.clone(),connection_stop_rx, network_type.clone(), cache_manager.clone())
+                                                read_frame_process(quic_framed_read_stream, quic_connection, connection.clone(), raw_request_queue_sx.clone(),connection_stop_rx, network_type.clone(), cache_manager.clone(), connection_manager.clone())
                                             },
                                             Err(e) => {
                                                 error!("Quic accept failed to create connection with error message :{:?}",e);
@@ -89,6 +93,7 @@ pub(crate) async fn acceptor_process(
                                         }
                                 },
                                 Err(e) => {
+                                        connection_manager.record_listener_tls_handshake_failure(&network_type);
                                         error!("Quic accept failed to create connection with error message :{:?}",e);
                                     }
                                 }
@@ -105,13 +110,38 @@ pub(crate) async fn acceptor_process(
     }
 }
 
+// A QUIC connection outlives a change of the client address, such as a phone moving from
+// Wi-Fi to a cellular network. The connection entry follows the peer to its new address.
+fn track_peer_migration(
+    connection_manager: &Arc<ConnectionManager>,
+    connection: &mut NetworkConnection,
+    peer_addr: SocketAddr,
+) {
+    if peer_addr == connection.addr {
+        return;
+    }
+    if connection_manager.update_peer_addr(connection.connection_id, peer_addr) {
+        info!(
+            "Quic connection {} migrated from {} to {}",
+            connection.connection_id, connection.addr, peer_addr
+        );
+    }
+    if connection.original_addr.is_none() {
+        connection.original_addr = Some(connection.addr);
+    }
+    connection.addr = peer_addr;
+}
+
+#[allow(clippy::too_many_arguments)]
 fn read_frame_process(
     mut read_frame_stream: QuicFramedReadStream,
-    connection: NetworkConnection,
+    quic_connection: Connection,
+    mut connection: NetworkConnection,
     request_queue_sx: Sender<RequestPackage>,
     mut connection_stop_rx: Receiver<bool>,
     network_type: NetworkConnectionType,
     cache_manager: Arc<CacheManager>,
+    connection_manager: Arc<ConnectionManager>,
 ) {
     tokio::spawn(async move {
         loop {
@@ -128,7 +158,14 @@ fn read_frame_process(
                       match val {
 
                             Ok(packet) => {
+                                    track_peer_migration(&connection_manager, &mut connection, quic_connection.remote_address());
                                     record_received_metrics(&connection, &packet, &network_type);
+                                    connection.record_packet_received(&packet, read_frame_stream.last_frame_length());
+                                    connection_manager.record_received(&packet);
+                                    if let MqttPacket::Connect(_, _, _, _, _, _) = packet {
+                                        connection_manager.begin_session_restore(connection.connection_id);
+                                    }
+                                    connection_manager.wait_session_restore(connection.connection_id, &packet).await;
 
                                     info!("revc quic packet:{:?}", packet);
                                     let package =
@@ -143,6 +180,7 @@ fn read_frame_process(
                                 },
                             Err(e) => {
                                 record_received_error_metrics(network_type.clone());
+                                connection_manager.record_listener_decode_error(connection.connection_id);
                                 debug!("Quic connection parsing packet format error message :{:?}",e)
                             }
                     }
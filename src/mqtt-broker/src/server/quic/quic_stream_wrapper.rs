@@ -83,6 +83,10 @@ impl QuicFramedReadStream {
     pub fn new(read_stream: RecvStream, codec: MqttCodec) -> Self {
         Self { read_stream, codec }
     }
+
+    pub fn last_frame_length(&self) -> usize {
+        self.codec.last_frame_length()
+    }
     pub async fn receive(&mut self) -> Result<MqttPacket, MqttBrokerError> {
         let mut decode_bytes = BytesMut::with_capacity(0);
         match self.read_stream.read_to_end(1024).await {
@@ -74,6 +74,7 @@ pub async fn start_quic_server<S>(
         conf.network_port.quic_port as u16,
     ));
     server.start();
+    connection_manager.record_listener_bound(&NetworkConnectionType::Quic, server.local_addr());
 
     let quic_endpoint = server.get_endpoint();
 
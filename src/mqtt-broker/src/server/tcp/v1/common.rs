@@ -22,6 +22,7 @@
     observability::metrics::packets::{record_received_error_metrics, record_received_metrics},
     server::{
         connection::{NetworkConnection, NetworkConnectionType},
+        connection_manager::ConnectionManager,
         packet::RequestPackage,
         tcp::v1::channel::RequestChannel,
     },
@@ -30,8 +31,10 @@
 pub async fn read_packet(
     package: Option<Result<MqttPacket, Error>>,
     request_channel: &RequestChannel,
+    connection_manager: &ConnectionManager,
     connection: &NetworkConnection,
     network_type: &NetworkConnectionType,
+    frame_length: usize,
 ) {
     if let Some(pkg) = package {
         match pkg {
@@ -41,12 +44,21 @@ pub async fn read_packet(
                     network_type, pack, connection.connection_id
                 );
                 record_received_metrics(connection, &pack, network_type);
+                connection.record_packet_received(&pack, frame_length);
+                connection_manager.record_received(&pack);
+                if let MqttPacket::Connect(_, _, _, _, _, _) = pack {
+                    connection_manager.begin_session_restore(connection.connection_id);
+                }
+                connection_manager
+                    .wait_session_restore(connection.connection_id, &pack)
+                    .await;
 
                 let package = RequestPackage::new(connection.connection_id, connection.addr, pack);
                 request_channel.send_request_channel(package.clone()).await;
             }
             Err(e) => {
                 record_received_error_metrics(network_type.clone());
+                connection_manager.record_listener_decode_error(connection.connection_id);
                 debug!(
                     "{} connection parsing packet format error message :{:?}",
                     network_type, e
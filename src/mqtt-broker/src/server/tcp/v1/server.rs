@@ -100,6 +100,8 @@ pub async fn start(&self, tls: bool) -> Result<(), MqttBrokerError> {
         };
 
         let listener = TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+        self.connection_manager
+            .record_listener_bound(&self.network_type, listener.local_addr()?);
         let arc_listener = Arc::new(listener);
         let request_recv_channel = self.request_channel.create_request_channel();
         let response_recv_channel = self.request_channel.create_response_channel();
@@ -74,6 +74,7 @@ pub(crate) async fn acceptor_process(
                         match val{
                             Ok((stream, addr)) => {
                                 info!("Accept {} connection:{:?}", network_type, addr);
+                                connection_manager.record_listener_accept(&network_type);
 
                                 let (r_stream, w_stream) = io::split(stream);
                                 let codec = MqttCodec::new(None);
@@ -94,7 +95,7 @@ pub(crate) async fn acceptor_process(
                                 connection_manager.add_connection(connection.clone());
                                 connection_manager.add_tcp_write(connection.connection_id, write_frame_stream);
 
-                                read_frame_process(read_frame_stream,connection, request_channel.clone(), connection_stop_rx, network_type.clone());
+                                read_frame_process(read_frame_stream,connection, request_channel.clone(), connection_manager.clone(), connection_stop_rx, network_type.clone());
                             }
                             Err(e) => {
                                 error!("{} accept failed to create connection with error message :{:?}", network_type, e);
@@ -112,6 +113,7 @@ fn read_frame_process(
     mut read_frame_stream: FramedRead<io::ReadHalf<tokio::net::TcpStream>, MqttCodec>,
     connection: NetworkConnection,
     request_channel: Arc<RequestChannel>,
+    connection_manager: Arc<ConnectionManager>,
     mut connection_stop_rx: Receiver<bool>,
     network_type: NetworkConnectionType,
 ) {
@@ -128,7 +130,8 @@ fn read_frame_process(
                 }
 
                 package = read_frame_stream.next()=>{
-                   read_packet(package, &request_channel, &connection, &network_type).await;
+                   let frame_length = read_frame_stream.decoder().last_frame_length();
+                   read_packet(package, &request_channel, &connection_manager, &connection, &network_type, frame_length).await;
                 }
             }
         }
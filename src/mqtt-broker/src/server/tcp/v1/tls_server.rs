@@ -14,7 +14,7 @@
 
 use crate::handler::connection::tcp_tls_establish_connection_check;
 use crate::handler::error::MqttBrokerError;
-use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+use crate::server::connection::{NetworkConnection, NetworkConnectionType, TlsConnectionInfo};
 use crate::server::connection_manager::ConnectionManager;
 use crate::server::tcp::v1::channel::RequestChannel;
 use crate::server::tcp::v1::common::read_packet;
@@ -31,10 +31,16 @@
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::{broadcast, mpsc};
 use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
-use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig, ServerConnection};
 use tokio_rustls::TlsAcceptor;
 use tokio_util::codec::{FramedRead, FramedWrite};
 use tracing::{debug, error, info};
+use x509_parser::oid_registry::{
+    Oid, OID_X509_COMMON_NAME, OID_X509_COUNTRY_NAME, OID_X509_LOCALITY_NAME,
+    OID_X509_ORGANIZATIONAL_UNIT, OID_X509_ORGANIZATION_NAME, OID_X509_STATE_OR_PROVINCE_NAME,
+};
+use x509_parser::parse_x509_certificate;
 
 pub(crate) fn load_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
     certs(&mut BufReader::new(File::open(path)?)).collect()
@@ -82,14 +88,17 @@ pub(crate) async fn acceptor_tls_process(
                         match val{
                             Ok((stream, addr)) => {
                                 info!("Accept {} tls connection:{:?}", network_type, addr);
+                                connection_manager.record_listener_accept(&network_type);
                                 let stream = match raw_tls_acceptor.accept(stream).await{
                                     Ok(da) => da,
                                     Err(e) => {
+                                        connection_manager.record_listener_tls_handshake_failure(&network_type);
                                         error!("{} Accepter failed to read Stream with error message :{e:?}", network_type);
                                         continue;
                                     }
                                 };
 
+                                let tls_info = tls_connection_info(stream.get_ref().1);
                                 let (r_stream, w_stream) = tokio::io::split(stream);
                                 let codec = MqttCodec::new(None);
                                 let read_frame_stream = FramedRead::new(r_stream, codec.clone());
@@ -100,15 +109,16 @@ pub(crate) async fn acceptor_tls_process(
                                 }
 
                                 let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
-                                let connection = NetworkConnection::new(
+                                let mut connection = NetworkConnection::new(
                                     crate::server::connection::NetworkConnectionType::Tls,
                                     addr,
                                     Some(connection_stop_sx.clone())
                                 );
+                                connection.set_tls_info(tls_info);
                                 connection_manager.add_connection(connection.clone());
                                 connection_manager.add_tcp_tls_write(connection.connection_id, write_frame_stream);
 
-                                read_tls_frame_process(read_frame_stream, connection, request_channel.clone(), connection_stop_rx, network_type.clone());
+                                read_tls_frame_process(read_frame_stream, connection, request_channel.clone(), connection_manager.clone(), connection_stop_rx, network_type.clone());
                             }
                             Err(e) => {
                                 error!("{} accept failed to create connection with error message :{:?}", network_type, e);
@@ -130,6 +140,7 @@ pub(crate) fn read_tls_frame_process(
     >,
     connection: NetworkConnection,
     request_channel: Arc<RequestChannel>,
+    connection_manager: Arc<ConnectionManager>,
     mut connection_stop_rx: Receiver<bool>,
     network_type: NetworkConnectionType,
 ) {
@@ -145,19 +156,146 @@ pub(crate) fn read_tls_frame_process(
                     }
                 }
                 package = read_frame_stream.next()=>{
-                    read_packet(package, &request_channel, &connection, &network_type).await;
+                    let frame_length = read_frame_stream.decoder().last_frame_length();
+                    read_packet(package, &request_channel, &connection_manager, &connection, &network_type, frame_length).await;
                 }
             }
         }
     });
 }
 
+pub(crate) fn tls_connection_info(connection: &ServerConnection) -> TlsConnectionInfo {
+    let mut tls_info = TlsConnectionInfo {
+        tls_version: connection
+            .protocol_version()
+            .map(|version| format!("{:?}", version))
+            .unwrap_or_default(),
+        cipher_suite: connection
+            .negotiated_cipher_suite()
+            .map(|suite| format!("{:?}", suite.suite()))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    // Only present when the listener verifies client certificates
+    let subject = connection
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .and_then(|cert| certificate_subject(cert.as_ref()))
+        .unwrap_or_default();
+    tls_info.client_cert_cn = subject
+        .iter()
+        .find(|(name, _)| name == "CN")
+        .map(|(_, value)| value.clone())
+        .unwrap_or_default();
+    tls_info.client_cert_subject = subject
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<String>>()
+        .join(", ");
+    tls_info
+}
+
+// The attributes of the subject of a DER encoded X.509 certificate in the order they appear,
+// e.g. [("CN", "client01"), ("O", "RobustMQ")]
+fn certificate_subject(der: &[u8]) -> Option<Vec<(String, String)>> {
+    let (_, certificate) = parse_x509_certificate(der).ok()?;
+    let attributes = certificate
+        .subject()
+        .iter_attributes()
+        .map(|attribute| {
+            let value = match attribute.as_str() {
+                Ok(value) => value.to_string(),
+                Err(_) => String::from_utf8_lossy(attribute.attr_value().data).to_string(),
+            };
+            (attribute_type_name(attribute.attr_type()), value)
+        })
+        .collect();
+    Some(attributes)
+}
+
+const ATTRIBUTE_TYPE_NAMES: [(Oid<'static>, &str); 6] = [
+    (OID_X509_COMMON_NAME, "CN"),
+    (OID_X509_COUNTRY_NAME, "C"),
+    (OID_X509_LOCALITY_NAME, "L"),
+    (OID_X509_STATE_OR_PROVINCE_NAME, "ST"),
+    (OID_X509_ORGANIZATION_NAME, "O"),
+    (OID_X509_ORGANIZATIONAL_UNIT, "OU"),
+];
+
+// Other attribute types are shown by their dotted OID
+fn attribute_type_name(oid: &Oid) -> String {
+    ATTRIBUTE_TYPE_NAMES
+        .iter()
+        .find(|(known, _)| known == oid)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| oid.to_id_string())
+}
+
 fn create_tls_accept() -> Result<TlsAcceptor, MqttBrokerError> {
     let conf = broker_mqtt_conf();
     let certs = load_certs(Path::new(&conf.network_port.tls_cert))?;
     let key = load_key(Path::new(&conf.network_port.tls_key))?;
-    let config = ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)?;
+    let builder = ServerConfig::builder();
+    let config = if conf.network_port.tls_client_ca.is_empty() {
+        builder.with_no_client_auth().with_single_cert(certs, key)?
+    } else {
+        // Clients without a certificate are still accepted, whether they may log in
+        // without one is decided when CONNECT is handled
+        let mut roots = RootCertStore::empty();
+        for cert in load_certs(Path::new(&conf.network_port.tls_client_ca))? {
+            roots.add(cert)?;
+        }
+        let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+            .allow_unauthenticated()
+            .build()
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+        builder
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(certs, key)?
+    };
     Ok(TlsAcceptor::from(Arc::new(config)))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{certificate_subject, load_certs};
+
+    fn subject(path: &str) -> Vec<String> {
+        let path = format!("{}/../../{}", env!("CARGO_MANIFEST_DIR"), path);
+        let certs = load_certs(Path::new(&path)).unwrap();
+        certificate_subject(certs[0].as_ref())
+            .unwrap()
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect()
+    }
+
+    #[test]
+    fn certificate_subject_test() {
+        assert_eq!(
+            subject("config/example/certs/ca.pem"),
+            vec![
+                "O=mkcert development CA",
+                "OU=root@OpenEuler-22.03-SP1",
+                "CN=mkcert root@OpenEuler-22.03-SP1"
+            ]
+        );
+        assert_eq!(
+            subject("config/example/certs/cert.pem"),
+            vec![
+                "O=mkcert development certificate",
+                "OU=root@OpenEuler-22.03-SP1"
+            ]
+        );
+
+        let path = format!(
+            "{}/../../config/example/certs/ca.pem",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let certs = load_certs(Path::new(&path)).unwrap();
+        assert!(certificate_subject(&certs[0].as_ref()[..20]).is_none());
+    }
+}
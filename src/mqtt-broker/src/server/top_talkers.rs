@@ -0,0 +1,344 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::now_mills;
+use dashmap::DashMap;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::info;
+
+use super::connection::NetworkConnection;
+use super::connection_manager::ConnectionManager;
+
+// Rates are the traffic of a connection over the last window, sampled once per interval
+pub const TOP_TALKERS_WINDOW_SEC: u64 = 10;
+const TOP_TALKERS_SAMPLE_INTERVAL_MS: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopTalkerMetric {
+    Messages,
+    Bytes,
+}
+
+impl TopTalkerMetric {
+    pub fn parse(metric: &str) -> Option<Self> {
+        match metric.to_lowercase().as_str() {
+            "messages" => Some(TopTalkerMetric::Messages),
+            "bytes" => Some(TopTalkerMetric::Bytes),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TopTalker {
+    pub connection_id: u64,
+    pub messages_per_sec: u64,
+    pub bytes_per_sec: u64,
+}
+
+// Counters of a connection at the time of a sample, received and sent together
+#[derive(Clone, Copy, Debug, Default)]
+struct TalkerSample {
+    time_ms: u64,
+    messages: u64,
+    bytes: u64,
+}
+
+impl TalkerSample {
+    fn of(connection: &NetworkConnection, time_ms: u64) -> Self {
+        TalkerSample {
+            time_ms,
+            messages: connection.get_messages_received() + connection.get_messages_sent(),
+            bytes: connection.get_bytes_received() + connection.get_bytes_sent(),
+        }
+    }
+}
+
+// Keeps the recent counters of every connection, the rate is the difference between the
+// live counters and the oldest sample still in the window
+#[derive(Default)]
+pub struct TalkerSampler {
+    // (connection_id, samples from old to new)
+    samples: DashMap<u64, VecDeque<TalkerSample>>,
+}
+
+impl TalkerSampler {
+    pub fn record_at(&self, connections: &DashMap<u64, NetworkConnection>, now_ms: u64) {
+        let window_ms = TOP_TALKERS_WINDOW_SEC * 1000;
+        for connection in connections.iter() {
+            let mut samples = self.samples.entry(connection.connection_id).or_default();
+            samples.push_back(TalkerSample::of(&connection, now_ms));
+            while samples
+                .front()
+                .is_some_and(|sample| now_ms.saturating_sub(sample.time_ms) > window_ms)
+            {
+                samples.pop_front();
+            }
+        }
+        self.samples
+            .retain(|connection_id, _| connections.contains_key(connection_id));
+    }
+
+    // The n busiest connections by the metric, ties are ordered by connection id
+    pub fn top_talkers_at(
+        &self,
+        connections: &DashMap<u64, NetworkConnection>,
+        metric: TopTalkerMetric,
+        n: usize,
+        now_ms: u64,
+    ) -> Vec<TopTalker> {
+        let window_ms = TOP_TALKERS_WINDOW_SEC * 1000;
+        let mut talkers: Vec<TopTalker> = connections
+            .iter()
+            .map(|connection| {
+                let current = TalkerSample::of(&connection, now_ms);
+                // A connection accepted after the last sample is measured from zero since it
+                // was accepted
+                let base = self
+                    .samples
+                    .get(&connection.connection_id)
+                    .and_then(|samples| {
+                        samples
+                            .iter()
+                            .find(|sample| now_ms.saturating_sub(sample.time_ms) <= window_ms)
+                            .copied()
+                    })
+                    .unwrap_or(TalkerSample {
+                        time_ms: connection.create_time_ms,
+                        ..Default::default()
+                    });
+                // Less than a second of traffic is not extrapolated to a full second
+                let elapsed_ms = now_ms.saturating_sub(base.time_ms).max(1000);
+                TopTalker {
+                    connection_id: connection.connection_id,
+                    messages_per_sec: current.messages.saturating_sub(base.messages) * 1000
+                        / elapsed_ms,
+                    bytes_per_sec: current.bytes.saturating_sub(base.bytes) * 1000 / elapsed_ms,
+                }
+            })
+            .collect();
+        let rate = |talker: &TopTalker| match metric {
+            TopTalkerMetric::Messages => talker.messages_per_sec,
+            TopTalkerMetric::Bytes => talker.bytes_per_sec,
+        };
+        talkers.sort_by(|a, b| {
+            rate(b)
+                .cmp(&rate(a))
+                .then(a.connection_id.cmp(&b.connection_id))
+        });
+        talkers.truncate(n);
+        talkers
+    }
+
+    pub fn top_talkers(
+        &self,
+        connections: &DashMap<u64, NetworkConnection>,
+        metric: TopTalkerMetric,
+        n: usize,
+    ) -> Vec<TopTalker> {
+        self.top_talkers_at(connections, metric, n, now_mills() as u64)
+    }
+}
+
+// Samples the counters of every connection for the top talkers ranking
+pub struct TopTalkersThread {
+    stop_send: broadcast::Sender<bool>,
+    connection_manager: Arc<ConnectionManager>,
+}
+
+impl TopTalkersThread {
+    pub fn new(
+        stop_send: broadcast::Sender<bool>,
+        connection_manager: Arc<ConnectionManager>,
+    ) -> Self {
+        Self {
+            stop_send,
+            connection_manager,
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Top talkers thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.sample()=>{
+                }
+            }
+        }
+    }
+
+    async fn sample(&self) {
+        self.connection_manager
+            .talker_sampler
+            .record_at(&self.connection_manager.connections, now_mills() as u64);
+        sleep(Duration::from_millis(TOP_TALKERS_SAMPLE_INTERVAL_MS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TalkerSampler, TopTalkerMetric, TOP_TALKERS_WINDOW_SEC};
+    use crate::server::connection::{NetworkConnection, NetworkConnectionType};
+    use dashmap::DashMap;
+
+    fn connection(create_time_ms: u64) -> NetworkConnection {
+        let mut connection = NetworkConnection::new(
+            NetworkConnectionType::Tcp,
+            "127.0.0.1:1883".parse().unwrap(),
+            None,
+        );
+        connection.create_time_ms = create_time_ms;
+        connection
+    }
+
+    fn send(connection: &NetworkConnection, messages: u64, bytes_per_message: u64) {
+        for _ in 0..messages {
+            connection.record_message_sent();
+            connection.record_bytes_sent(bytes_per_message);
+        }
+    }
+
+    fn ranking(
+        sampler: &TalkerSampler,
+        connections: &DashMap<u64, NetworkConnection>,
+        metric: TopTalkerMetric,
+        n: usize,
+        now_ms: u64,
+    ) -> Vec<(u64, u64, u64)> {
+        sampler
+            .top_talkers_at(connections, metric, n, now_ms)
+            .into_iter()
+            .map(|talker| {
+                (
+                    talker.connection_id,
+                    talker.messages_per_sec,
+                    talker.bytes_per_sec,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn top_talkers_ranking_test() {
+        let start = 1_000_000;
+        let connections = DashMap::new();
+        let sampler = TalkerSampler::default();
+        // Many small messages, few large messages and a quiet connection
+        let chatty = connection(start);
+        let bulky = connection(start);
+        let quiet = connection(start);
+        let ids = (
+            chatty.connection_id,
+            bulky.connection_id,
+            quiet.connection_id,
+        );
+        for connection in [&chatty, &bulky, &quiet] {
+            connections.insert(connection.connection_id, connection.clone());
+        }
+        sampler.record_at(&connections, start);
+
+        send(&chatty, 100, 10);
+        send(&bulky, 20, 1000);
+        send(&quiet, 2, 10);
+        let now = start + 2000;
+
+        assert_eq!(
+            ranking(&sampler, &connections, TopTalkerMetric::Messages, 10, now),
+            vec![(ids.0, 50, 500), (ids.1, 10, 10000), (ids.2, 1, 10)]
+        );
+        assert_eq!(
+            ranking(&sampler, &connections, TopTalkerMetric::Bytes, 10, now),
+            vec![(ids.1, 10, 10000), (ids.0, 50, 500), (ids.2, 1, 10)]
+        );
+        assert_eq!(
+            ranking(&sampler, &connections, TopTalkerMetric::Messages, 1, now),
+            vec![(ids.0, 50, 500)]
+        );
+        assert!(ranking(&sampler, &connections, TopTalkerMetric::Bytes, 0, now).is_empty());
+    }
+
+    #[test]
+    fn top_talkers_window_test() {
+        let start = 1_000_000;
+        let window_ms = TOP_TALKERS_WINDOW_SEC * 1000;
+        let connections = DashMap::new();
+        let sampler = TalkerSampler::default();
+        let early = connection(start);
+        let late = connection(start);
+        for connection in [&early, &late] {
+            connections.insert(connection.connection_id, connection.clone());
+        }
+
+        // The early burst drops out of the window, only recent traffic is ranked
+        sampler.record_at(&connections, start);
+        send(&early, 1000, 1);
+        for second in 1..=window_ms / 1000 {
+            sampler.record_at(&connections, start + second * 1000);
+        }
+        send(&late, 100, 1);
+        let now = start + window_ms + 500;
+        let top = ranking(&sampler, &connections, TopTalkerMetric::Messages, 2, now);
+        assert_eq!(top[0].0, late.connection_id);
+        // Measured from the oldest sample in the window, taken 500ms into it
+        assert_eq!(top[0].1, 100 * 1000 / (window_ms - 500));
+        assert_eq!(top[1], (early.connection_id, 0, 0));
+
+        // Closed connections are forgotten on the next sample
+        connections.remove(&early.connection_id);
+        sampler.record_at(&connections, now);
+        assert!(!sampler.samples.contains_key(&early.connection_id));
+    }
+
+    #[test]
+    fn top_talkers_new_connection_test() {
+        let start = 1_000_000;
+        let connections = DashMap::new();
+        let sampler = TalkerSampler::default();
+        let old = connection(start);
+        connections.insert(old.connection_id, old.clone());
+        sampler.record_at(&connections, start);
+        send(&old, 30, 1);
+
+        // Accepted after the last sample, measured from its accept time
+        let new = connection(start + 1000);
+        connections.insert(new.connection_id, new.clone());
+        send(&new, 60, 1);
+
+        let now = start + 3000;
+        assert_eq!(
+            ranking(&sampler, &connections, TopTalkerMetric::Messages, 2, now),
+            vec![(new.connection_id, 30, 30), (old.connection_id, 10, 10)]
+        );
+
+        // Less than a second old, the traffic is not extrapolated
+        let newest = connection(now - 100);
+        connections.insert(newest.connection_id, newest.clone());
+        send(&newest, 5, 1);
+        let top = ranking(&sampler, &connections, TopTalkerMetric::Messages, 3, now);
+        assert_eq!(top[2], (newest.connection_id, 5, 5));
+    }
+}
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::borrow::Cow;
+use std::future::Future;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -21,10 +23,10 @@
 use crate::handler::error::MqttBrokerError;
 use crate::observability::metrics::server::record_ws_request_duration;
 use crate::security::AuthDriver;
-use crate::server::connection::NetworkConnection;
+use crate::server::connection::{NetworkConnection, NetworkConnectionType};
 use crate::server::connection_manager::ConnectionManager;
 use crate::subscribe::manager::SubscribeManager;
-use axum::extract::ws::{Message, WebSocket};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
 use axum::extract::{ConnectInfo, State, WebSocketUpgrade};
 use axum::response::Response;
 use axum::routing::get;
@@ -32,6 +34,7 @@
 use axum_extra::headers::UserAgent;
 use axum_extra::TypedHeader;
 use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
 use bytes::{BufMut, BytesMut};
 
 use common_base::tools::now_mills;
@@ -49,6 +52,12 @@
 
 pub const ROUTE_ROOT: &str = "/mqtt";
 
+// The subprotocols of MQTT over WebSocket, "mqttv3.1" is offered by MQTT 3.1 clients
+pub const MQTT_WEBSOCKET_SUBPROTOCOLS: [&str; 2] = ["mqtt", "mqttv3.1"];
+
+const UNSUPPORTED_SUBPROTOCOL_REASON: &str =
+    "no supported MQTT subprotocol offered, expected mqtt or mqttv3.1";
+
 #[derive(Clone)]
 pub struct WebSocketServerState<S> {
     sucscribe_manager: Arc<SubscribeManager>,
@@ -100,12 +109,19 @@ pub async fn websocket_server<S>(state: WebSocketServerState<S>)
     let ip: SocketAddr = format!("0.0.0.0:{}", config.network_port.websocket_port)
         .parse()
         .unwrap();
+    let handle = Handle::new();
+    record_listener_bound_when_listening(
+        handle.clone(),
+        state.connection_manager.clone(),
+        NetworkConnectionType::WebSocket,
+    );
     let app = routes_v1(state);
     info!(
         "Broker WebSocket Server start success. port:{}",
         config.network_port.websocket_port
     );
     match axum_server::bind(ip)
+        .handle(handle)
         .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
     {
@@ -122,6 +138,12 @@ pub async fn websockets_server<S>(state: WebSocketServerState<S>)
     let ip: SocketAddr = format!("0.0.0.0:{}", config.network_port.websockets_port)
         .parse()
         .unwrap();
+    let handle = Handle::new();
+    record_listener_bound_when_listening(
+        handle.clone(),
+        state.connection_manager.clone(),
+        NetworkConnectionType::WebSockets,
+    );
     let app = routes_v1(state);
 
     let tls_config = match RustlsConfig::from_pem_file(
@@ -141,6 +163,7 @@ pub async fn websockets_server<S>(state: WebSocketServerState<S>)
         config.network_port.websockets_port
     );
     match axum_server::bind_rustls(ip, tls_config)
+        .handle(handle)
         .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
     {
@@ -149,6 +172,19 @@ pub async fn websockets_server<S>(state: WebSocketServerState<S>)
     }
 }
 
+// axum_server binds the socket inside serve, the handle reports when it is listening
+fn record_listener_bound_when_listening(
+    handle: Handle,
+    connection_manager: Arc<ConnectionManager>,
+    network_type: NetworkConnectionType,
+) {
+    tokio::spawn(async move {
+        if let Some(addr) = handle.listening().await {
+            connection_manager.record_listener_bound(&network_type, addr);
+        }
+    });
+}
+
 fn routes_v1<S>(state: WebSocketServerState<S>) -> Router
 where
     S: StorageAdapter + Sync + Send + 'static + Clone,
@@ -185,22 +221,53 @@ async fn ws_handler<S>(
         state.auth_driver.clone(),
     );
     let codec = MqttCodec::new(None);
-    ws.protocols(["mqtt", "mqttv3.1"])
-        .on_upgrade(move |socket| {
-            handle_socket(
-                socket,
-                addr,
-                command,
-                codec,
-                state.connection_manager.clone(),
-                state.stop_sx.clone(),
-            )
+    upgrade_mqtt_websocket(ws, addr, move |socket, subprotocol| {
+        handle_socket(
+            socket,
+            addr,
+            subprotocol,
+            command,
+            codec,
+            state.connection_manager.clone(),
+            state.stop_sx.clone(),
+        )
+    })
+}
+
+// Upgrades the connection and hands the socket to `handle` with the MQTT subprotocol the
+// client negotiated. A client that offered none of them, such as a browser client created
+// without the "mqtt" protocol, is closed with the protocol error code.
+fn upgrade_mqtt_websocket<F, Fut>(ws: WebSocketUpgrade, addr: SocketAddr, handle: F) -> Response
+where
+    F: FnOnce(WebSocket, String) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    ws.protocols(MQTT_WEBSOCKET_SUBPROTOCOLS)
+        .on_upgrade(move |mut socket| async move {
+            let subprotocol = socket
+                .protocol()
+                .and_then(|protocol| protocol.to_str().ok())
+                .map(|protocol| protocol.to_owned());
+            match subprotocol {
+                Some(subprotocol) => handle(socket, subprotocol).await,
+                None => {
+                    warn!("websocket at {addr} offered no supported MQTT subprotocol, closing");
+                    let close = Message::Close(Some(CloseFrame {
+                        code: close_code::PROTOCOL,
+                        reason: Cow::from(UNSUPPORTED_SUBPROTOCOL_REASON),
+                    }));
+                    if let Err(e) = socket.send(close).await {
+                        warn!("websocket failed to close {addr} with error message :{e:?}");
+                    }
+                }
+            }
         })
 }
 
 async fn handle_socket<S>(
     socket: WebSocket,
     addr: SocketAddr,
+    subprotocol: String,
     mut command: Command<S>,
     mut codec: MqttCodec,
     connection_manager: Arc<ConnectionManager>,
@@ -209,12 +276,10 @@ async fn handle_socket<S>(
     S: StorageAdapter + Sync + Send + 'static + Clone,
 {
     let (sender, mut receiver) = socket.split();
-    let mut tcp_connection = NetworkConnection::new(
-        crate::server::connection::NetworkConnectionType::WebSocket,
-        addr,
-        None,
-    );
+    let mut tcp_connection = NetworkConnection::new(NetworkConnectionType::WebSocket, addr, None);
+    tcp_connection.set_websocket_subprotocol(subprotocol);
 
+    connection_manager.record_listener_accept(&tcp_connection.connection_type);
     connection_manager.add_websocket_write(tcp_connection.connection_id, sender);
     connection_manager.add_connection(tcp_connection.clone());
     let mut stop_rx = stop_sx.subscribe();
@@ -233,6 +298,7 @@ async fn handle_socket<S>(
                     match msg {
                         Ok(Message::Binary(data)) => {
                             if let Err(e) = process_socket_packet_by_binary(&connection_manager,&mut codec,&mut command,&mut tcp_connection,&addr,data).await{
+                                connection_manager.record_listener_decode_error(tcp_connection.connection_id);
                                 error!("Websocket failed to parse MQTT protocol packet with error message :{e:?}");
                             }
                         }
@@ -290,6 +356,8 @@ async fn process_socket_packet_by_binary<S>(
     buf.put(data.as_slice());
     if let Some(packet) = codec.decode_data(&mut buf)? {
         info!("recv websocket packet:{packet:?}");
+        connection_manager.record_received(&packet);
+        tcp_connection.record_packet_received(&packet, codec.last_frame_length());
 
         if let Some(resp_pkg) = command
             .apply(connection_manager, tcp_connection, addr, &packet)
@@ -330,3 +398,108 @@ async fn process_socket_packet_by_binary<S>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use axum::extract::ws::Message;
+    use axum::extract::{ConnectInfo, WebSocketUpgrade};
+    use axum::routing::get;
+    use axum::Router;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::{upgrade_mqtt_websocket, ROUTE_ROOT, UNSUPPORTED_SUBPROTOCOL_REASON};
+
+    // A server that answers every negotiated socket with the subprotocol in a text frame
+    async fn start_server() -> SocketAddr {
+        let app = Router::new().route(
+            ROUTE_ROOT,
+            get(
+                |ws: WebSocketUpgrade, ConnectInfo(addr): ConnectInfo<SocketAddr>| async move {
+                    upgrade_mqtt_websocket(ws, addr, |mut socket, subprotocol| async move {
+                        socket.send(Message::Text(subprotocol)).await.unwrap();
+                    })
+                },
+            ),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+        addr
+    }
+
+    // Upgrades with the given Sec-WebSocket-Protocol and returns the response head and the
+    // first frame the server sent
+    async fn upgrade(addr: SocketAddr, protocols: Option<&str>) -> (String, u8, Vec<u8>) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let protocol_header = protocols
+            .map(|protocols| format!("Sec-WebSocket-Protocol: {}\r\n", protocols))
+            .unwrap_or_default();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n{}\r\n",
+            ROUTE_ROOT, addr, protocol_header
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut buf = Vec::new();
+        loop {
+            let mut chunk = [0u8; 1024];
+            let n = stream.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "connection closed early");
+            buf.extend_from_slice(&chunk[..n]);
+
+            let Some(head_end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+                continue;
+            };
+            // Frames from the server are unmasked and short, so the length is in one byte
+            let frame = &buf[head_end + 4..];
+            if frame.len() < 2 || frame.len() < 2 + frame[1] as usize {
+                continue;
+            }
+            let head = String::from_utf8_lossy(&buf[..head_end]).to_lowercase();
+            return (head, frame[0], frame[2..2 + frame[1] as usize].to_vec());
+        }
+    }
+
+    #[tokio::test]
+    async fn websocket_subprotocol_negotiation_test() {
+        let addr = start_server().await;
+
+        for (offered, negotiated) in [
+            ("mqtt", "mqtt"),
+            ("mqttv3.1", "mqttv3.1"),
+            ("wamp, mqttv3.1", "mqttv3.1"),
+        ] {
+            let (head, opcode, payload) = upgrade(addr, Some(offered)).await;
+            assert!(head.starts_with("http/1.1 101"), "{}", head);
+            assert!(head.contains(&format!("sec-websocket-protocol: {}", negotiated)));
+            // A FIN text frame
+            assert_eq!(opcode, 0x81);
+            assert_eq!(payload, negotiated.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn websocket_subprotocol_rejection_test() {
+        let addr = start_server().await;
+
+        for offered in [None, Some("wamp"), Some("MQTT5")] {
+            let (head, opcode, payload) = upgrade(addr, offered).await;
+            assert!(!head.contains("sec-websocket-protocol"), "{}", head);
+            // A FIN close frame with the protocol error code 1002 and the reason
+            assert_eq!(opcode, 0x88);
+            assert_eq!(u16::from_be_bytes([payload[0], payload[1]]), 1002);
+            assert_eq!(&payload[2..], UNSUPPORTED_SUBPROTOCOL_REASON.as_bytes());
+        }
+    }
+}
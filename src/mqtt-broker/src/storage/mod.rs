@@ -19,5 +19,7 @@
 pub mod connector;
 pub mod message;
 pub mod session;
+pub mod subscribe_snapshot;
 pub mod topic;
 pub mod user;
+pub mod user_session;
@@ -71,6 +71,31 @@ pub async fn update_session(
             broker_id,
             reconnect_time,
             distinct_time,
+            takeover: false,
+        };
+        match placement_update_session(&self.client_pool, &config.placement_center, request).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Attach a reconnecting client to its existing session and count the takeover
+    pub async fn takeover_session(
+        &self,
+        client_id: String,
+        connection_id: u64,
+        broker_id: u64,
+        reconnect_time: u64,
+    ) -> Result<(), CommonError> {
+        let config = broker_mqtt_conf();
+        let request = UpdateSessionRequest {
+            cluster_name: config.cluster_name.clone(),
+            client_id,
+            connection_id,
+            broker_id,
+            reconnect_time,
+            distinct_time: 0,
+            takeover: true,
         };
         match placement_update_session(&self.client_pool, &config.placement_center, request).await {
             Ok(_) => Ok(()),
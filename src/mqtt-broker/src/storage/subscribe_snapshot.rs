@@ -0,0 +1,173 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{placement_delete, placement_get, placement_set};
+use grpc_clients::pool::ClientPool;
+use protocol::placement_center::placement_center_kv::{DeleteRequest, GetRequest, SetRequest};
+
+use crate::handler::error::MqttBrokerError;
+use crate::subscribe::snapshot::SubscribeSnapshotData;
+
+// Placement center writes above the default gRPC message size would fail
+pub const SUBSCRIBE_SNAPSHOT_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+// Each broker keeps its latest snapshot under a fixed key, the history under keys ordered
+// by creation time and the creation times of the history in an index record
+pub struct SubscribeSnapshotStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl SubscribeSnapshotStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        SubscribeSnapshotStorage { client_pool }
+    }
+
+    pub async fn save(&self, snapshot: &SubscribeSnapshotData) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let value = String::from_utf8(snapshot.encode()?)
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+        if value.len() > SUBSCRIBE_SNAPSHOT_MAX_BYTES {
+            return Err(MqttBrokerError::CommonError(format!(
+                "subscribe snapshot of {} bytes exceeds the limit of {} bytes",
+                value.len(),
+                SUBSCRIBE_SNAPSHOT_MAX_BYTES
+            )));
+        }
+        for key in [
+            history_key(&config.cluster_name, snapshot.node_id, snapshot.create_time),
+            latest_key(&config.cluster_name, snapshot.node_id),
+        ] {
+            let request = SetRequest {
+                key,
+                value: value.clone(),
+            };
+            placement_set(&self.client_pool, &config.placement_center, request).await?;
+        }
+
+        let mut times = self.history_times(snapshot.node_id).await?;
+        if !times.contains(&snapshot.create_time) {
+            times.push(snapshot.create_time);
+            times.sort();
+        }
+        self.save_history_times(snapshot.node_id, &times).await
+    }
+
+    pub async fn latest(
+        &self,
+        node_id: u64,
+    ) -> Result<Option<SubscribeSnapshotData>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = GetRequest {
+            key: latest_key(&config.cluster_name, node_id),
+        };
+        let reply = placement_get(&self.client_pool, &config.placement_center, request).await?;
+        if reply.value.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(SubscribeSnapshotData::decode(reply.value.as_bytes())?))
+    }
+
+    pub async fn history(
+        &self,
+        node_id: u64,
+        create_time: u64,
+    ) -> Result<Option<SubscribeSnapshotData>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = GetRequest {
+            key: history_key(&config.cluster_name, node_id, create_time),
+        };
+        let reply = placement_get(&self.client_pool, &config.placement_center, request).await?;
+        if reply.value.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(SubscribeSnapshotData::decode(reply.value.as_bytes())?))
+    }
+
+    // Creation times of the snapshots in the history of a broker, oldest first, read from the
+    // index so that the snapshots themselves are not fetched
+    pub async fn history_times(&self, node_id: u64) -> Result<Vec<u64>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = GetRequest {
+            key: history_index_key(&config.cluster_name, node_id),
+        };
+        let reply = placement_get(&self.client_pool, &config.placement_center, request).await?;
+        if reply.value.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(serde_json::from_str(&reply.value)?)
+    }
+
+    pub async fn delete_history(
+        &self,
+        node_id: u64,
+        create_times: &[u64],
+    ) -> Result<(), MqttBrokerError> {
+        if create_times.is_empty() {
+            return Ok(());
+        }
+        let config = broker_mqtt_conf();
+        for create_time in create_times {
+            let request = DeleteRequest {
+                key: history_key(&config.cluster_name, node_id, *create_time),
+            };
+            placement_delete(&self.client_pool, &config.placement_center, request).await?;
+        }
+
+        let mut times = self.history_times(node_id).await?;
+        times.retain(|time| !create_times.contains(time));
+        self.save_history_times(node_id, &times).await
+    }
+
+    async fn save_history_times(&self, node_id: u64, times: &[u64]) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = SetRequest {
+            key: history_index_key(&config.cluster_name, node_id),
+            value: serde_json::to_string(times)?,
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+}
+
+fn latest_key(cluster_name: &str, node_id: u64) -> String {
+    format!(
+        "/mqtt/subscribe_snapshot/{}/{}/latest",
+        cluster_name, node_id
+    )
+}
+
+fn history_prefix(cluster_name: &str, node_id: u64) -> String {
+    format!(
+        "/mqtt/subscribe_snapshot/{}/{}/history/",
+        cluster_name, node_id
+    )
+}
+
+fn history_index_key(cluster_name: &str, node_id: u64) -> String {
+    format!(
+        "/mqtt/subscribe_snapshot/{}/{}/history_index",
+        cluster_name, node_id
+    )
+}
+
+fn history_key(cluster_name: &str, node_id: u64, create_time: u64) -> String {
+    // Zero padded so the keys sort by creation time
+    format!(
+        "{}{:020}",
+        history_prefix(cluster_name, node_id),
+        create_time
+    )
+}
@@ -41,6 +41,20 @@ pub async fn save_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError
             cluster_name: config.cluster_name.clone(),
             user_name: user_info.username.clone(),
             content: user_info.encode(),
+            upsert: false,
+        };
+        placement_create_user(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    // Replace the stored record of an existing user in a single write
+    pub async fn update_user(&self, user_info: MqttUser) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = CreateUserRequest {
+            cluster_name: config.cluster_name.clone(),
+            user_name: user_info.username.clone(),
+            content: user_info.encode(),
+            upsert: true,
         };
         placement_create_user(&self.client_pool, &config.placement_center, request).await?;
         Ok(())
@@ -0,0 +1,117 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Arc;
+
+use common_base::tools::now_mills;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::placement::kv::call::{placement_delete, placement_get_prefix, placement_set};
+use grpc_clients::pool::ClientPool;
+use protocol::placement_center::placement_center_kv::{
+    DeleteRequest, GetPrefixRequest, SetRequest,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::handler::error::MqttBrokerError;
+
+// A connected session of a user. Every session is stored under its own key, so connecting
+// clients never overwrite each other and each of them sees the sessions stored before it.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct UserSessionOwner {
+    pub username: String,
+    pub client_id: String,
+    pub broker_id: u64,
+    // milliseconds
+    pub connect_time: u128,
+}
+
+impl UserSessionOwner {
+    pub fn new(username: String, client_id: String, broker_id: u64) -> Self {
+        UserSessionOwner {
+            username,
+            client_id,
+            broker_id,
+            connect_time: now_mills(),
+        }
+    }
+
+    // The session that connected last is kept, ties are broken by broker and client id so
+    // that every broker picks the same one
+    pub fn is_newer_than(&self, other: &UserSessionOwner) -> bool {
+        (self.connect_time, self.broker_id, &self.client_id)
+            > (other.connect_time, other.broker_id, &other.client_id)
+    }
+}
+
+pub struct UserSessionStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl UserSessionStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        UserSessionStorage { client_pool }
+    }
+
+    pub async fn list_sessions(
+        &self,
+        username: &str,
+    ) -> Result<Vec<UserSessionOwner>, MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = GetPrefixRequest {
+            prefix: user_session_prefix(&config.cluster_name, username),
+        };
+        let reply =
+            placement_get_prefix(&self.client_pool, &config.placement_center, request).await?;
+        let mut sessions = Vec::with_capacity(reply.values.len());
+        for value in reply.values {
+            let session = serde_json::from_str::<UserSessionOwner>(&value)?;
+            // The prefix of a user is also the prefix of usernames that extend it with a "/"
+            if session.username == username {
+                sessions.push(session);
+            }
+        }
+        Ok(sessions)
+    }
+
+    pub async fn save_session(&self, session: &UserSessionOwner) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = SetRequest {
+            key: user_session_key(&config.cluster_name, session),
+            value: serde_json::to_string(session)?,
+        };
+        placement_set(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+
+    pub async fn delete_session(&self, session: &UserSessionOwner) -> Result<(), MqttBrokerError> {
+        let config = broker_mqtt_conf();
+        let request = DeleteRequest {
+            key: user_session_key(&config.cluster_name, session),
+        };
+        placement_delete(&self.client_pool, &config.placement_center, request).await?;
+        Ok(())
+    }
+}
+
+fn user_session_prefix(cluster_name: &str, username: &str) -> String {
+    format!("/mqtt/user_session/{}/{}/", cluster_name, username)
+}
+
+fn user_session_key(cluster_name: &str, session: &UserSessionOwner) -> String {
+    format!(
+        "{}{}/{}",
+        user_session_prefix(cluster_name, &session.username),
+        session.broker_id,
+        session.client_id
+    )
+}
@@ -86,6 +86,7 @@ async fn try_thread_gc(&self) {
                 self.subscribe_manager
                     .exclusive_push_thread
                     .remove(&exclusive_key);
+                self.subscribe_manager.remove_push_progress(&exclusive_key);
             }
         }
     }
@@ -132,6 +133,7 @@ async fn start_push_thread(&self) {
                         return;
                     }
                 };
+                subscribe_manager.add_push_progress(&exclusive_key, &subscriber, offset);
 
                 loop {
                     select! {
@@ -146,6 +148,7 @@ async fn start_push_thread(&self) {
                                     );
 
                                     subscribe_manager.exclusive_push_thread.remove(&exclusive_key);
+                                    subscribe_manager.remove_push_progress(&exclusive_key);
                                     break;
                                 }
                             }
@@ -154,6 +157,8 @@ async fn start_push_thread(&self) {
                                 &connection_manager,
                                 &message_storage,
                                 &cache_manager,
+                                &subscribe_manager,
+                                &exclusive_key,
                                 &subscriber,
                                 &group_id,
                                 &qos,
@@ -192,6 +197,8 @@ async fn pub_message<S>(
     connection_manager: &Arc<ConnectionManager>,
     message_storage: &MessageStorage<S>,
     cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    exclusive_key: &str,
     subscriber: &Subscriber,
     group_id: &str,
     qos: &QoS,
@@ -208,9 +215,19 @@ async fn pub_message<S>(
         .await?;
 
     if results.is_empty() {
+        subscribe_manager.mark_push_caught_up(exclusive_key, offset);
         return Ok(None);
     }
 
+    subscribe_manager.record_topic_read_offsets(&subscriber.topic_id, &results);
+
+    // The head of the batch is the oldest message this subscription has not received yet
+    subscribe_manager.update_push_progress(
+        exclusive_key,
+        offset,
+        results.first().map(|record| record.timestamp),
+    );
+
     let push_fn = async || -> Result<(), MqttBrokerError> {
         for record in results.iter() {
             let record_offset = if let Some(offset) = record.offset {
@@ -267,6 +284,7 @@ async fn pub_message<S>(
         }
     }
 
+    subscribe_manager.update_push_progress(exclusive_key, last_offset + 1, None);
     Ok(Some(last_offset))
 }
 
@@ -13,11 +13,13 @@
 // limitations under the License.
 
 use crate::subscribe::common::Subscriber;
+use common_base::tools::now_second;
 use dashmap::DashMap;
+use metadata_struct::adapter::record::Record;
 use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
 use protocol::mqtt::common::{Filter, MqttProtocol};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tokio::sync::broadcast::Sender;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -47,12 +49,52 @@ pub struct ShareLeaderSubscribeData {
     pub sub_list: DashMap<String, Subscriber>,
 }
 
+impl ShareLeaderSubscribeData {
+    // Consumer group the leader commits the offset of the shared subscription under
+    pub fn group_id(&self) -> String {
+        format!(
+            "system_sub_{}_{}_{}",
+            self.group_name, self.sub_name, self.topic_id
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TopicSubscribeInfo {
     pub client_id: String,
     pub path: String,
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct SubscribePushProgress {
+    pub client_id: String,
+    pub sub_path: String,
+    pub topic_id: String,
+    pub topic_name: String,
+    // Offset of the next message the push thread will deliver
+    pub next_offset: u64,
+    // Storage time (in seconds) of the oldest message that has been read but not yet delivered
+    pub oldest_undelivered_time: Option<u64>,
+}
+
+impl SubscribePushProgress {
+    pub fn queued_num(&self, latest_offset: Option<u64>) -> u64 {
+        if let Some(latest) = latest_offset {
+            if latest >= self.next_offset {
+                return latest - self.next_offset + 1;
+            }
+        }
+        0
+    }
+
+    pub fn delivery_lag_sec(&self) -> u64 {
+        if let Some(time) = self.oldest_undelivered_time {
+            return now_second().saturating_sub(time);
+        }
+        0
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct SubscribeManager {
     //(client_id_path: MqttSubscribe)
@@ -78,6 +120,15 @@ pub struct SubscribeManager {
 
     //(topic_id, Vec<TopicSubscribeInfo>)
     pub topic_subscribe_list: DashMap<String, Vec<TopicSubscribeInfo>>,
+
+    // (client_id_sub_name_topic_id, SubscribePushProgress)
+    pub push_progress: DashMap<String, SubscribePushProgress>,
+
+    // (topic_id, latest written offset)
+    pub topic_latest_offset: DashMap<String, u64>,
+
+    // (group_name_topic_id, next offset of the share leader push thread)
+    pub share_leader_push_offset: DashMap<String, u64>,
 }
 
 impl SubscribeManager {
@@ -91,6 +142,9 @@ pub fn new() -> Self {
             share_leader_push_thread: DashMap::with_capacity(8),
             share_follower_resub_thread: DashMap::with_capacity(8),
             topic_subscribe_list: DashMap::with_capacity(8),
+            push_progress: DashMap::with_capacity(8),
+            topic_latest_offset: DashMap::with_capacity(8),
+            share_leader_push_offset: DashMap::with_capacity(8),
         }
     }
 
@@ -217,6 +271,19 @@ pub fn add_topic_subscribe(&self, topic_name: &str, client_id: &str, path: &str)
         }
     }
 
+    // Clients subscribed to the topic, a client matching the topic with several filters counts once
+    pub fn topic_subscriber_num(&self, topic_name: &str) -> u64 {
+        self.topic_subscribe_list
+            .get(topic_name)
+            .map(|list| {
+                list.iter()
+                    .map(|info| info.client_id.as_str())
+                    .collect::<HashSet<&str>>()
+                    .len() as u64
+            })
+            .unwrap_or(0)
+    }
+
     pub fn contain_topic_subscribe(&self, topic_name: &str) -> bool {
         if let Some(list) = self.topic_subscribe_list.get(topic_name) {
             return !list.is_empty();
@@ -224,6 +291,14 @@ pub fn contain_topic_subscribe(&self, topic_name: &str) -> bool {
         false
     }
 
+    // Subscriptions indexed for the topic, the lookup publishes are resolved with
+    pub fn topic_subscribe_num(&self, topic_name: &str) -> usize {
+        self.topic_subscribe_list
+            .get(topic_name)
+            .map(|list| list.len())
+            .unwrap_or(0)
+    }
+
     pub fn is_exclusive_subscribe(&self, topic_name: &str) -> bool {
         if let Some(list) = self.topic_subscribe_list.get(topic_name) {
             for raw in list.iter() {
@@ -254,6 +329,78 @@ pub fn remove_client_id(&self, client_id: &str) {
         self.remove_subscriber_by_client_id(client_id);
     }
 
+    // push progress
+    pub fn add_push_progress(&self, exclusive_key: &str, subscriber: &Subscriber, offset: u64) {
+        self.push_progress.insert(
+            exclusive_key.to_owned(),
+            SubscribePushProgress {
+                client_id: subscriber.client_id.clone(),
+                sub_path: subscriber.sub_path.clone(),
+                topic_id: subscriber.topic_id.clone(),
+                topic_name: subscriber.topic_name.clone(),
+                next_offset: offset,
+                oldest_undelivered_time: None,
+            },
+        );
+    }
+
+    pub fn update_push_progress(
+        &self,
+        exclusive_key: &str,
+        next_offset: u64,
+        oldest_undelivered_time: Option<u64>,
+    ) {
+        if let Some(mut progress) = self.push_progress.get_mut(exclusive_key) {
+            progress.next_offset = next_offset;
+            // Without a newer batch the last known oldest message still counts
+            if oldest_undelivered_time.is_some() {
+                progress.oldest_undelivered_time = oldest_undelivered_time;
+            }
+        }
+    }
+
+    // The push thread found no message after next_offset
+    pub fn mark_push_caught_up(&self, exclusive_key: &str, next_offset: u64) {
+        if let Some(mut progress) = self.push_progress.get_mut(exclusive_key) {
+            progress.next_offset = next_offset;
+            progress.oldest_undelivered_time = None;
+        }
+    }
+
+    pub fn remove_push_progress(&self, exclusive_key: &str) {
+        self.push_progress.remove(exclusive_key);
+    }
+
+    pub fn record_topic_latest_offset(&self, topic_id: &str, offset: u64) {
+        if let Some(mut latest) = self.topic_latest_offset.get_mut(topic_id) {
+            if offset > *latest {
+                *latest = offset;
+            }
+            return;
+        }
+        self.topic_latest_offset.insert(topic_id.to_owned(), offset);
+    }
+
+    // Messages read from the storage may have been written through other brokers
+    pub fn record_topic_read_offsets(&self, topic_id: &str, records: &[Record]) {
+        if let Some(offset) = records.iter().filter_map(|record| record.offset).max() {
+            self.record_topic_latest_offset(topic_id, offset);
+        }
+    }
+
+    pub fn get_topic_latest_offset(&self, topic_id: &str) -> Option<u64> {
+        self.topic_latest_offset.get(topic_id).map(|raw| *raw)
+    }
+
+    pub fn update_share_leader_push_offset(&self, share_leader_key: &str, next_offset: u64) {
+        self.share_leader_push_offset
+            .insert(share_leader_key.to_owned(), next_offset);
+    }
+
+    pub fn remove_share_leader_push_offset(&self, share_leader_key: &str) {
+        self.share_leader_push_offset.remove(share_leader_key);
+    }
+
     // info
     pub fn snapshot_info(&self) -> HashMap<String, Vec<String>> {
         let exclusive_push_key: Vec<String> = self
@@ -420,4 +567,49 @@ fn share_subscribe_followe_test() {
         subscribe_manager.remove_share_subscribe_follower_by_client_id(&share_sub.client_id);
         assert_eq!(subscribe_manager.share_follower_resub.len(), 0);
     }
+
+    #[test]
+    fn push_progress_test() {
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        let sub = Subscriber {
+            client_id: "client_id_1".to_string(),
+            topic_name: "t_name_1".to_string(),
+            topic_id: "t_id_1".to_string(),
+            sub_path: "/var/111".to_string(),
+            ..Default::default()
+        };
+        let key = "client_id_1_/var/111_t_id_1";
+        subscribe_manager.add_push_progress(key, &sub, 3);
+
+        let progress = subscribe_manager.push_progress.get(key).unwrap().clone();
+        assert_eq!(progress.queued_num(None), 0);
+        assert_eq!(progress.delivery_lag_sec(), 0);
+
+        subscribe_manager.record_topic_latest_offset(&sub.topic_id, 7);
+        subscribe_manager.record_topic_latest_offset(&sub.topic_id, 5);
+        assert_eq!(
+            subscribe_manager.get_topic_latest_offset(&sub.topic_id),
+            Some(7)
+        );
+
+        subscribe_manager.update_push_progress(key, 3, Some(now_second() - 10));
+        let progress = subscribe_manager.push_progress.get(key).unwrap().clone();
+        let latest = subscribe_manager.get_topic_latest_offset(&sub.topic_id);
+        assert_eq!(progress.queued_num(latest), 5);
+        assert!(progress.delivery_lag_sec() >= 10);
+
+        // A batch without a new head keeps the last known lag
+        subscribe_manager.update_push_progress(key, 8, None);
+        let progress = subscribe_manager.push_progress.get(key).unwrap().clone();
+        assert_eq!(progress.queued_num(latest), 0);
+        assert!(progress.delivery_lag_sec() >= 10);
+
+        subscribe_manager.mark_push_caught_up(key, 8);
+        let progress = subscribe_manager.push_progress.get(key).unwrap().clone();
+        assert_eq!(progress.next_offset, 8);
+        assert_eq!(progress.delivery_lag_sec(), 0);
+
+        subscribe_manager.remove_push_progress(key);
+        assert!(subscribe_manager.push_progress.is_empty());
+    }
 }
@@ -17,3 +17,4 @@
 pub mod manager;
 pub mod push;
 pub mod share;
+pub mod snapshot;
@@ -159,6 +159,8 @@ pub async fn send_publish_packet_to_client(
                 QosAckPacketInfo {
                     sx: wait_puback_sx.clone(),
                     create_time: now_mills(),
+                    qos: QoS::AtLeastOnce,
+                    pub_rec_received: false,
                 },
             );
 
@@ -186,6 +188,8 @@ pub async fn send_publish_packet_to_client(
                 QosAckPacketInfo {
                     sx: wait_ack_sx.clone(),
                     create_time: now_mills(),
+                    qos: QoS::ExactlyOnce,
+                    pub_rec_received: false,
                 },
             );
 
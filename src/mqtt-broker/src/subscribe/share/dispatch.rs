@@ -0,0 +1,239 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::handler::cache::CacheManager;
+use crate::observability::metrics::subscribe::incr_shared_dispatch_skipped_full_counter;
+use crate::subscribe::common::Subscriber;
+use crate::subscribe::manager::SubscribeManager;
+use common_config::mqtt::config::{SharedDispatchMode, SharedGroupDispatch};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub enum ShareDispatchTarget {
+    Consumer(Subscriber),
+    // Every consumer of the group has a full inflight window
+    AllFull,
+    NoConsumer,
+}
+
+pub struct ConsumerCapacity {
+    pub client_id: String,
+    pub weight: u64,
+    pub inflight: u64,
+}
+
+// Smooth weighted round-robin over the consumers of one shared subscription. Consumers are
+// picked in proportion to their weight, and skipped while their inflight window is full.
+#[derive(Default)]
+pub struct CapacityDispatcher {
+    // (client_id, current weight)
+    current_weight: HashMap<String, i64>,
+}
+
+impl CapacityDispatcher {
+    // Returns the picked consumer and the number of consumers skipped because they were full
+    pub fn select(&mut self, consumers: &[ConsumerCapacity]) -> (Option<String>, u64) {
+        self.current_weight
+            .retain(|client_id, _| consumers.iter().any(|c| c.client_id == *client_id));
+
+        let mut skipped_full = 0;
+        let mut total_weight = 0;
+        let mut picked: Option<(&str, i64)> = None;
+        for consumer in consumers.iter() {
+            if consumer.weight == 0 {
+                continue;
+            }
+            if consumer.inflight >= consumer.weight {
+                skipped_full += 1;
+                continue;
+            }
+
+            let current = self
+                .current_weight
+                .entry(consumer.client_id.clone())
+                .or_insert(0);
+            *current += consumer.weight as i64;
+            total_weight += consumer.weight as i64;
+
+            if picked.is_none_or(|(_, weight)| *current > weight) {
+                picked = Some((&consumer.client_id, *current));
+            }
+        }
+
+        let picked = picked.map(|(client_id, _)| client_id.to_string());
+        if let Some(client_id) = picked.as_ref() {
+            if let Some(mut current) = self.current_weight.get_mut(client_id) {
+                *current -= total_weight;
+            }
+        }
+        (picked, skipped_full)
+    }
+}
+
+pub fn select_share_consumer(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    share_leader_key: &str,
+    group_name: &str,
+    group_dispatch: &SharedGroupDispatch,
+    seq: u64,
+    dispatcher: &mut CapacityDispatcher,
+) -> ShareDispatchTarget {
+    match group_dispatch.mode {
+        SharedDispatchMode::RoundRobin => {
+            match get_subscribe_by_random(subscribe_manager, share_leader_key, seq) {
+                Some(subscriber) => ShareDispatchTarget::Consumer(subscriber),
+                None => ShareDispatchTarget::NoConsumer,
+            }
+        }
+        SharedDispatchMode::Capacity => get_subscribe_by_capacity(
+            cache_manager,
+            subscribe_manager,
+            share_leader_key,
+            group_name,
+            group_dispatch,
+            dispatcher,
+        ),
+    }
+}
+
+fn get_subscribe_by_capacity(
+    cache_manager: &Arc<CacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    share_leader_key: &str,
+    group_name: &str,
+    group_dispatch: &SharedGroupDispatch,
+    dispatcher: &mut CapacityDispatcher,
+) -> ShareDispatchTarget {
+    let Some(sub_list) = subscribe_manager.share_leader_push.get(share_leader_key) else {
+        return ShareDispatchTarget::NoConsumer;
+    };
+
+    let consumers: Vec<ConsumerCapacity> = sub_list
+        .sub_list
+        .iter()
+        .map(|entry| ConsumerCapacity {
+            client_id: entry.key().clone(),
+            weight: consumer_weight(cache_manager, group_dispatch, entry.key()),
+            inflight: cache_manager.pkid_metadata.ack_packet_num(entry.key()) as u64,
+        })
+        .collect();
+
+    let (picked, skipped_full) = dispatcher.select(&consumers);
+    for _ in 0..skipped_full {
+        incr_shared_dispatch_skipped_full_counter(group_name);
+    }
+
+    if let Some(client_id) = picked {
+        if let Some(subscriber) = sub_list.sub_list.get(&client_id) {
+            return ShareDispatchTarget::Consumer(subscriber.clone());
+        }
+    }
+
+    if skipped_full > 0 {
+        return ShareDispatchTarget::AllFull;
+    }
+    ShareDispatchTarget::NoConsumer
+}
+
+// A configured weight wins over the receive maximum advertised by the consumer in CONNECT,
+// consumers without a live connection get no messages
+fn consumer_weight(
+    cache_manager: &Arc<CacheManager>,
+    group_dispatch: &SharedGroupDispatch,
+    client_id: &str,
+) -> u64 {
+    if let Some(weight) = group_dispatch.consumer_weights.get(client_id) {
+        return *weight as u64;
+    }
+
+    cache_manager
+        .get_session_info(client_id)
+        .and_then(|session| session.connection_id)
+        .and_then(|connect_id| cache_manager.get_connection(connect_id))
+        .map(|connection| connection.client_max_receive_maximum as u64)
+        .unwrap_or(0)
+}
+
+fn get_subscribe_by_random(
+    subscribe_manager: &Arc<SubscribeManager>,
+    share_leader_key: &str,
+    seq: u64,
+) -> Option<Subscriber> {
+    if let Some(sub_list) = subscribe_manager.share_leader_push.get(share_leader_key) {
+        let index = seq % (sub_list.sub_list.len() as u64);
+        let keys: Vec<String> = sub_list
+            .sub_list
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        if let Some(key) = keys.get(index as usize) {
+            if let Some(subscribe) = sub_list.sub_list.get(key) {
+                return Some(subscribe.clone());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CapacityDispatcher, ConsumerCapacity};
+    use std::collections::HashMap;
+
+    fn consumer(client_id: &str, weight: u64, inflight: u64) -> ConsumerCapacity {
+        ConsumerCapacity {
+            client_id: client_id.to_string(),
+            weight,
+            inflight,
+        }
+    }
+
+    #[test]
+    fn capacity_dispatch_proportional_test() {
+        let mut dispatcher = CapacityDispatcher::default();
+        let consumers = vec![consumer("c1", 3, 0), consumer("c2", 1, 0)];
+
+        let mut picked: HashMap<String, u32> = HashMap::new();
+        for _ in 0..8 {
+            let (client_id, skipped_full) = dispatcher.select(&consumers);
+            assert_eq!(skipped_full, 0);
+            *picked.entry(client_id.unwrap()).or_default() += 1;
+        }
+        assert_eq!(picked.get("c1"), Some(&6));
+        assert_eq!(picked.get("c2"), Some(&2));
+    }
+
+    #[test]
+    fn capacity_dispatch_skip_full_test() {
+        let mut dispatcher = CapacityDispatcher::default();
+
+        let consumers = vec![consumer("c1", 3, 3), consumer("c2", 1, 0)];
+        let (client_id, skipped_full) = dispatcher.select(&consumers);
+        assert_eq!(client_id.unwrap(), "c2");
+        assert_eq!(skipped_full, 1);
+
+        let consumers = vec![consumer("c1", 3, 3), consumer("c2", 1, 1)];
+        let (client_id, skipped_full) = dispatcher.select(&consumers);
+        assert!(client_id.is_none());
+        assert_eq!(skipped_full, 2);
+
+        // Offline consumers are neither picked nor counted as full
+        let consumers = vec![consumer("c1", 0, 0)];
+        let (client_id, skipped_full) = dispatcher.select(&consumers);
+        assert!(client_id.is_none());
+        assert_eq!(skipped_full, 0);
+    }
+}
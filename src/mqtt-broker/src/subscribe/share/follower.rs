@@ -415,6 +415,8 @@ async fn process_publish_packet(
                 QosAckPacketInfo {
                     sx: wait_puback_sx.clone(),
                     create_time: now_mills(),
+                    qos: protocol::mqtt::common::QoS::AtLeastOnce,
+                    pub_rec_received: false,
                 },
             );
 
@@ -442,6 +444,8 @@ async fn process_publish_packet(
                 QosAckPacketInfo {
                     sx: wait_client_ack_sx.clone(),
                     create_time: now_mills(),
+                    qos: protocol::mqtt::common::QoS::ExactlyOnce,
+                    pub_rec_received: false,
                 },
             );
 
@@ -452,6 +456,8 @@ async fn process_publish_packet(
                 QosAckPacketInfo {
                     sx: wait_leader_ack_sx.clone(),
                     create_time: now_mills(),
+                    qos: protocol::mqtt::common::QoS::ExactlyOnce,
+                    pub_rec_received: false,
                 },
             );
 
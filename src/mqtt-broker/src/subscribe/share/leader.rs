@@ -18,11 +18,13 @@
 use crate::storage::message::MessageStorage;
 use crate::subscribe::common::is_ignore_push_error;
 use crate::subscribe::common::loop_commit_offset;
-use crate::subscribe::common::Subscriber;
 use crate::subscribe::manager::{ShareLeaderSubscribeData, SubscribeManager};
 use crate::subscribe::push::{
     build_pub_qos, build_publish_message, build_sub_ids, send_publish_packet_to_client,
 };
+use crate::subscribe::share::dispatch::{
+    select_share_consumer, CapacityDispatcher, ShareDispatchTarget,
+};
 use std::sync::Arc;
 use std::time::Duration;
 use storage_adapter::storage::StorageAdapter;
@@ -132,10 +134,7 @@ async fn push_by_round_robin(
         sub_data: ShareLeaderSubscribeData,
     ) -> Result<(), MqttBrokerError> {
         let (sub_thread_stop_sx, mut sub_thread_stop_rx) = broadcast::channel(1);
-        let group_id = format!(
-            "system_sub_{}_{}_{}",
-            sub_data.group_name, sub_data.sub_name, sub_data.topic_id
-        );
+        let group_id = sub_data.group_id();
 
         // get current offset by group
         let message_storage = MessageStorage::new(self.message_storage.clone());
@@ -145,6 +144,8 @@ async fn push_by_round_robin(
         self.subscribe_manager
             .share_leader_push_thread
             .insert(share_leader_key.clone(), sub_thread_stop_sx.clone());
+        self.subscribe_manager
+            .update_share_leader_push_offset(&share_leader_key, offset);
 
         let connection_manager = self.connection_manager.clone();
         let cache_manager = self.cache_manager.clone();
@@ -157,6 +158,7 @@ async fn push_by_round_robin(
             );
 
             let mut seq = 1;
+            let mut dispatcher = CapacityDispatcher::default();
             loop {
                 select! {
                     val = sub_thread_stop_rx.recv() =>{
@@ -180,6 +182,7 @@ async fn push_by_round_robin(
                         &group_id,
                         offset,
                         seq,
+                        &mut dispatcher,
                         &sub_thread_stop_sx,
                     ) =>{
                         match res {
@@ -187,6 +190,7 @@ async fn push_by_round_robin(
                                 if let Some(offset_cur) = data{
                                     offset = offset_cur + 1;
                                     seq = seq_num;
+                                    subscribe_manager.update_share_leader_push_offset(&share_leader_key, offset);
                                 }else{
                                     sleep(Duration::from_millis(100)).await;
                                 }
@@ -212,6 +216,7 @@ async fn push_by_round_robin(
             subscribe_manager
                 .share_leader_push_thread
                 .remove(&share_leader_key);
+            subscribe_manager.remove_share_leader_push_offset(&share_leader_key);
         });
         Ok(())
     }
@@ -228,6 +233,7 @@ async fn read_message_process<S>(
     group_id: &str,
     offset: u64,
     mut seq: u64,
+    dispatcher: &mut CapacityDispatcher,
     stop_sx: &Sender<bool>,
 ) -> Result<(Option<u64>, u64), MqttBrokerError>
 where
@@ -240,7 +246,11 @@ async fn read_message_process<S>(
     if results.is_empty() {
         return Ok((None, seq));
     }
+    subscribe_manager.record_topic_read_offsets(&sub_data.topic_id, &results);
 
+    // Read once per batch, the group config is not cloned for every message
+    let group_dispatch = cache_manager.get_shared_group_dispatch_config(&sub_data.group_name);
+    let mut committed_offset = None;
     for record in results.iter() {
         let record_offset = if let Some(offset) = record.offset {
             offset
@@ -256,14 +266,37 @@ async fn read_message_process<S>(
                 warn!("Shared subscription failed to send messages {} times and the messages were discarded", times);
                 break;
             }
-            let subscriber = if let Some(subscrbie) =
-                get_subscribe_by_random(subscribe_manager, share_leader_key, seq)
-            {
-                subscrbie
-            } else {
-                warn!("No available subscribers were obtained. Continue looking for the next one");
-                sleep(Duration::from_secs(1)).await;
-                continue;
+            let subscriber = match select_share_consumer(
+                cache_manager,
+                subscribe_manager,
+                share_leader_key,
+                &sub_data.group_name,
+                &group_dispatch,
+                seq,
+                dispatcher,
+            ) {
+                ShareDispatchTarget::Consumer(subscriber) => subscriber,
+                ShareDispatchTarget::AllFull => {
+                    // Waiting for a consumer to ack is not a failed delivery
+                    times -= 1;
+                    let mut stop_rx = stop_sx.subscribe();
+                    select! {
+                        val = stop_rx.recv() => {
+                            if let Ok(true) = val {
+                                return Ok((committed_offset, seq));
+                            }
+                        }
+                        _ = sleep(Duration::from_millis(10)) => {}
+                    }
+                    continue;
+                }
+                ShareDispatchTarget::NoConsumer => {
+                    warn!(
+                        "No available subscribers were obtained. Continue looking for the next one"
+                    );
+                    sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
             };
 
             let qos = build_pub_qos(cache_manager, &subscriber);
@@ -314,32 +347,10 @@ async fn read_message_process<S>(
 
         // commit offset
         loop_commit_offset(message_storage, &sub_data.topic_id, group_id, record_offset).await?;
+        committed_offset = Some(record_offset);
     }
     Ok((results.last().unwrap().offset, seq))
 }
 
-fn get_subscribe_by_random(
-    subscribe_manager: &Arc<SubscribeManager>,
-    share_leader_key: &str,
-    seq: u64,
-) -> Option<Subscriber> {
-    if let Some(sub_list) = subscribe_manager.share_leader_push.get(share_leader_key) {
-        let index = seq % (sub_list.sub_list.len() as u64);
-        let keys: Vec<String> = sub_list
-            .sub_list
-            .iter()
-            .map(|entry| entry.key().clone())
-            .collect();
-
-        if let Some(key) = keys.get(index as usize) {
-            if let Some(subscribe) = sub_list.sub_list.get(key) {
-                return Some(subscribe.clone());
-            }
-        }
-    }
-
-    None
-}
-
 #[cfg(test)]
 mod tests {}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod dispatch;
 pub mod follower;
 pub mod leader;
 pub mod write;
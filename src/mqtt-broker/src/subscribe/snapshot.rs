@@ -0,0 +1,256 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_base::tools::now_second;
+use common_config::mqtt::broker_mqtt_conf;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+use serde::{Deserialize, Serialize};
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::{error, info};
+
+use super::manager::SubscribeManager;
+use crate::handler::cache::CacheManager;
+use crate::handler::error::MqttBrokerError;
+use crate::storage::subscribe_snapshot::SubscribeSnapshotStorage;
+
+// The subscriptions of a broker and the keys of its push threads at a point in time
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct SubscribeSnapshotData {
+    pub node_id: u64,
+    pub create_time: u64,
+    pub subscribes: Vec<MqttSubscribe>,
+    pub push_keys: HashMap<String, Vec<String>>,
+}
+
+impl SubscribeSnapshotData {
+    pub fn build(subscribe_manager: &Arc<SubscribeManager>, node_id: u64, now: u64) -> Self {
+        let mut subscribes: Vec<MqttSubscribe> = subscribe_manager
+            .subscribe_list
+            .iter()
+            .map(|raw| raw.value().clone())
+            .collect();
+        subscribes.sort_by(|a, b| (&a.client_id, &a.path).cmp(&(&b.client_id, &b.path)));
+
+        let mut push_keys = subscribe_manager.snapshot_info();
+        for keys in push_keys.values_mut() {
+            keys.sort();
+        }
+
+        SubscribeSnapshotData {
+            node_id,
+            create_time: now,
+            subscribes,
+            push_keys,
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, MqttBrokerError> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, MqttBrokerError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+pub fn is_snapshot_due(last_time: u64, now: u64, interval_sec: u64) -> bool {
+    last_time == 0 || now.saturating_sub(last_time) >= interval_sec.max(1)
+}
+
+// Creation times beyond the newest retain_num snapshots, given oldest first
+pub fn expired_snapshot_times(history_times: &[u64], retain_num: u64) -> Vec<u64> {
+    let retain_num = retain_num.max(1) as usize;
+    if history_times.len() <= retain_num {
+        return Vec::new();
+    }
+    history_times[..history_times.len() - retain_num].to_vec()
+}
+
+// Subscriptions of the snapshot the broker no longer holds, only for clients that still have
+// a session, so that subscriptions of sessions that ended are not brought back
+pub fn subscribes_to_restore(
+    snapshot: &SubscribeSnapshotData,
+    is_subscribed: impl Fn(&MqttSubscribe) -> bool,
+    has_session: impl Fn(&str) -> bool,
+) -> Vec<MqttSubscribe> {
+    snapshot
+        .subscribes
+        .iter()
+        .filter(|subscribe| !is_subscribed(subscribe) && has_session(&subscribe.client_id))
+        .cloned()
+        .collect()
+}
+
+pub struct SubscribeSnapshotThread {
+    stop_send: broadcast::Sender<bool>,
+    cache_manager: Arc<CacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+    client_pool: Arc<ClientPool>,
+    last_snapshot_time: AtomicU64,
+}
+
+impl SubscribeSnapshotThread {
+    pub fn new(
+        stop_send: broadcast::Sender<bool>,
+        cache_manager: Arc<CacheManager>,
+        subscribe_manager: Arc<SubscribeManager>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        Self {
+            stop_send,
+            cache_manager,
+            subscribe_manager,
+            client_pool,
+            last_snapshot_time: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn start(&self) {
+        loop {
+            let mut stop_rx = self.stop_send.subscribe();
+            select! {
+                val = stop_rx.recv() =>{
+                    if let Ok(flag) = val {
+                        if flag {
+                            info!("{}","Subscribe snapshot thread stopped successfully.");
+                            break;
+                        }
+                    }
+                }
+                _ = self.snapshot()=>{
+                }
+            }
+        }
+    }
+
+    async fn snapshot(&self) {
+        let config = self.cache_manager.get_subscribe_snapshot_config();
+        let now = now_second();
+        if config.enable
+            && is_snapshot_due(
+                self.last_snapshot_time.load(Ordering::Relaxed),
+                now,
+                config.interval_sec,
+            )
+        {
+            // A failed snapshot is retried on the next interval rather than every second
+            self.last_snapshot_time.store(now, Ordering::Relaxed);
+            if let Err(e) = self.save_snapshot(now, config.retain_num).await {
+                error!("Subscribe snapshot failed, error message: {}", e);
+            }
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    async fn save_snapshot(&self, now: u64, retain_num: u64) -> Result<(), MqttBrokerError> {
+        let node_id = broker_mqtt_conf().broker_id;
+        let snapshot = SubscribeSnapshotData::build(&self.subscribe_manager, node_id, now);
+        let storage = SubscribeSnapshotStorage::new(self.client_pool.clone());
+        storage.save(&snapshot).await?;
+
+        let history_times = storage.history_times(node_id).await?;
+        storage
+            .delete_history(node_id, &expired_snapshot_times(&history_times, retain_num))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use metadata_struct::mqtt::subscribe_data::MqttSubscribe;
+    use tokio::sync::broadcast;
+
+    use super::{
+        expired_snapshot_times, is_snapshot_due, subscribes_to_restore, SubscribeSnapshotData,
+    };
+    use crate::subscribe::manager::SubscribeManager;
+
+    #[test]
+    fn subscribe_snapshot_round_trip_test() {
+        let subscribe_manager = Arc::new(SubscribeManager::new());
+        for (client_id, path) in [("c2", "/a/#"), ("c1", "/b/+")] {
+            subscribe_manager.add_subscribe(MqttSubscribe {
+                client_id: client_id.to_string(),
+                path: path.to_string(),
+                ..Default::default()
+            });
+        }
+        subscribe_manager
+            .exclusive_push_thread
+            .insert("c1_/b/+".to_string(), broadcast::channel(1).0);
+
+        let snapshot = SubscribeSnapshotData::build(&subscribe_manager, 3, 1000);
+        assert_eq!(snapshot.node_id, 3);
+        assert_eq!(snapshot.create_time, 1000);
+        assert_eq!(snapshot.subscribes.len(), 2);
+        assert_eq!(snapshot.subscribes[0].client_id, "c1");
+
+        let data = snapshot.encode().unwrap();
+        let decoded = SubscribeSnapshotData::decode(&data).unwrap();
+        assert_eq!(decoded, snapshot);
+
+        assert!(SubscribeSnapshotData::decode(b"not json").is_err());
+    }
+
+    #[test]
+    fn subscribe_snapshot_schedule_test() {
+        // The first snapshot is taken right away
+        assert!(is_snapshot_due(0, 1000, 300));
+        assert!(!is_snapshot_due(1000, 1299, 300));
+        assert!(is_snapshot_due(1000, 1300, 300));
+        // A zero interval still waits one second between snapshots
+        assert!(!is_snapshot_due(1000, 1000, 0));
+        assert!(is_snapshot_due(1000, 1001, 0));
+
+        assert!(expired_snapshot_times(&[1, 2, 3], 3).is_empty());
+        assert_eq!(expired_snapshot_times(&[1, 2, 3, 4, 5], 2), vec![1, 2, 3]);
+        assert_eq!(expired_snapshot_times(&[1, 2], 0), vec![1]);
+    }
+
+    #[test]
+    fn subscribes_to_restore_test() {
+        let subscribe = |client_id: &str, path: &str| MqttSubscribe {
+            client_id: client_id.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let snapshot = SubscribeSnapshotData {
+            node_id: 1,
+            create_time: 1000,
+            subscribes: vec![
+                subscribe("c1", "/a"),
+                subscribe("c1", "/b"),
+                subscribe("c2", "/a"),
+            ],
+            ..Default::default()
+        };
+
+        // c1 still holds /a, c2 has no session anymore
+        let restored = subscribes_to_restore(
+            &snapshot,
+            |sub| sub.client_id == "c1" && sub.path == "/a",
+            |client_id| client_id == "c1",
+        );
+        assert_eq!(restored, vec![subscribe("c1", "/b")]);
+    }
+}
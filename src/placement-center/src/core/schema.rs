@@ -187,39 +187,24 @@ pub async fn list_bind_schema_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListBindSchemaRequest,
 ) -> Result<Vec<Vec<u8>>, PlacementCenterError> {
-    let schema_storage = SchemaStorage::new(rocksdb_engine_handler.clone());
-
-    // get schema bind
-    if !req.cluster_name.is_empty() && !req.schema_name.is_empty() && !req.resource_name.is_empty()
-    {
-        if let Some(res) =
-            schema_storage.get_bind(&req.cluster_name, &req.schema_name, &req.resource_name)?
-        {
-            return Ok(vec![res.encode()]);
-        } else {
-            return Ok(Vec::new());
-        }
-    }
-
-    // get schema bind by cluster_name
-    if !req.cluster_name.is_empty() && req.schema_name.is_empty() && req.resource_name.is_empty() {
-        let mut results = Vec::new();
-        for raw in schema_storage.list_bind_by_cluster(&req.cluster_name)? {
-            results.push(raw.encode());
-        }
-        return Ok(results);
+    if req.cluster_name.is_empty() {
+        return Ok(Vec::new());
     }
 
-    // get schema bind by resource
-    if !req.cluster_name.is_empty() && req.schema_name.is_empty() && !req.resource_name.is_empty() {
-        let mut results = Vec::new();
-        for raw in schema_storage.list_bind_by_resource(&req.cluster_name, &req.resource_name)? {
-            results.push(raw.encode());
-        }
-        return Ok(results);
-    }
+    // resource_name and schema_name are optional exact filters, an empty one matches all
+    let schema_storage = SchemaStorage::new(rocksdb_engine_handler.clone());
+    let binds = if req.resource_name.is_empty() {
+        schema_storage.list_bind_by_cluster(&req.cluster_name)?
+    } else {
+        schema_storage.list_bind_by_resource(&req.cluster_name, &req.resource_name)?
+    };
 
-    Ok(Vec::new())
+    Ok(binds
+        .into_iter()
+        .filter(|bind| req.resource_name.is_empty() || bind.resource_name == req.resource_name)
+        .filter(|bind| req.schema_name.is_empty() || bind.schema_name == req.schema_name)
+        .map(|bind| bind.encode())
+        .collect())
 }
 
 pub async fn bind_schema_req(
@@ -299,3 +284,80 @@ pub async fn un_bind_schema_req(
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use metadata_struct::schema::SchemaResourceBind;
+    use protocol::placement_center::placement_center_inner::ListBindSchemaRequest;
+    use tempfile::tempdir;
+
+    use super::list_bind_schema_req;
+    use crate::storage::placement::schema::SchemaStorage;
+    use crate::storage::rocksdb::RocksDBEngine;
+
+    #[tokio::test]
+    async fn list_bind_schema_filter_test() {
+        let rocksdb_engine = Arc::new(RocksDBEngine::new(
+            tempdir().unwrap().path().to_str().unwrap(),
+            100,
+            vec!["cluster".to_string()],
+        ));
+        let schema_storage = SchemaStorage::new(rocksdb_engine.clone());
+        let cluster_name = "test_cluster".to_string();
+
+        // t10 shares a prefix with t1 and must not be returned for t1
+        for (resource_name, schema_name) in
+            [("t1", "s1"), ("t1", "s2"), ("t10", "s1"), ("t2", "s2")]
+        {
+            let bind = SchemaResourceBind {
+                cluster_name: cluster_name.clone(),
+                schema_name: schema_name.to_string(),
+                resource_name: resource_name.to_string(),
+            };
+            schema_storage.save_bind(&cluster_name, &bind).unwrap();
+        }
+
+        let list = |resource_name: &str, schema_name: &str| {
+            let req = ListBindSchemaRequest {
+                cluster_name: cluster_name.clone(),
+                schema_name: schema_name.to_string(),
+                resource_name: resource_name.to_string(),
+            };
+            let rocksdb_engine = rocksdb_engine.clone();
+            async move {
+                let mut binds: Vec<(String, String)> = list_bind_schema_req(&rocksdb_engine, &req)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|raw| {
+                        let bind = serde_json::from_slice::<SchemaResourceBind>(raw).unwrap();
+                        (bind.resource_name, bind.schema_name)
+                    })
+                    .collect();
+                binds.sort();
+                binds
+            }
+        };
+        let pairs = |list: &[(&str, &str)]| -> Vec<(String, String)> {
+            list.iter()
+                .map(|(r, s)| (r.to_string(), s.to_string()))
+                .collect()
+        };
+
+        // neither
+        assert_eq!(
+            list("", "").await,
+            pairs(&[("t1", "s1"), ("t1", "s2"), ("t10", "s1"), ("t2", "s2")])
+        );
+        // resource only
+        assert_eq!(list("t1", "").await, pairs(&[("t1", "s1"), ("t1", "s2")]));
+        // schema only
+        assert_eq!(list("", "s1").await, pairs(&[("t1", "s1"), ("t10", "s1")]));
+        // both
+        assert_eq!(list("t1", "s2").await, pairs(&[("t1", "s2")]));
+        assert!(list("t2", "s1").await.is_empty());
+        assert!(list("t3", "").await.is_empty());
+    }
+}
@@ -16,6 +16,7 @@
 pub mod connector;
 pub mod session;
 pub mod share_sub;
+pub mod snapshot;
 pub mod subscribe;
 pub mod topic;
 pub mod user;
@@ -0,0 +1,55 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    core::error::PlacementCenterError,
+    storage::mqtt::snapshot::{
+        MqttSnapshotStorage, METADATA_CATEGORY_ACL, METADATA_CATEGORY_BLACKLIST,
+        METADATA_CATEGORY_CONNECTOR, METADATA_CATEGORY_SCHEMA, METADATA_CATEGORY_SCHEMA_BIND,
+        METADATA_CATEGORY_SESSION, METADATA_CATEGORY_SUBSCRIBE, METADATA_CATEGORY_TOPIC,
+        METADATA_CATEGORY_USER,
+    },
+};
+use protocol::placement_center::placement_center_mqtt::{
+    ExportMetadataReply, ExportMetadataRequest,
+};
+use rocksdb_engine::RocksDBEngine;
+use std::sync::Arc;
+
+pub fn export_metadata_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &ExportMetadataRequest,
+) -> Result<ExportMetadataReply, PlacementCenterError> {
+    if req.cluster_name.is_empty() {
+        return Err(PlacementCenterError::RequestParamsNotEmpty(
+            "cluster_name".to_string(),
+        ));
+    }
+
+    let storage = MqttSnapshotStorage::new(rocksdb_engine_handler.clone());
+    let mut metadata = storage.export(&req.cluster_name)?;
+    let mut take = |category: &str| metadata.remove(category).unwrap_or_default();
+
+    Ok(ExportMetadataReply {
+        users: take(METADATA_CATEGORY_USER),
+        acls: take(METADATA_CATEGORY_ACL),
+        blacklists: take(METADATA_CATEGORY_BLACKLIST),
+        schemas: take(METADATA_CATEGORY_SCHEMA),
+        schema_binds: take(METADATA_CATEGORY_SCHEMA_BIND),
+        connectors: take(METADATA_CATEGORY_CONNECTOR),
+        topics: take(METADATA_CATEGORY_TOPIC),
+        sessions: take(METADATA_CATEGORY_SESSION),
+        subscribes: take(METADATA_CATEGORY_SUBSCRIBE),
+    })
+}
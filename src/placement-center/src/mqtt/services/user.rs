@@ -63,8 +63,9 @@ pub async fn create_user_by_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &CreateUserRequest,
 ) -> Result<CreateUserReply, PlacementCenterError> {
+    // An upsert replaces the existing record with the single raft write below
     let storage = MqttUserStorage::new(rocksdb_engine_handler.clone());
-    if storage.get(&req.cluster_name, &req.user_name)?.is_some() {
+    if !req.upsert && storage.get(&req.cluster_name, &req.user_name)?.is_some() {
         return Err(PlacementCenterError::UserAlreadyExist(
             req.user_name.clone(),
         ));
@@ -147,6 +147,10 @@ pub fn update_session(&self, value: Vec<u8>) -> Result<(), PlacementCenterError>
                 session.distinct_time = None;
             }
 
+            if req.takeover {
+                session.record_takeover(req.reconnect_time);
+            }
+
             storage.save(&req.cluster_name, &req.client_id, session)?;
         }
 
@@ -291,6 +295,8 @@ pub fn set_auto_subscribe_rule(&self, value: Vec<u8>) -> Result<(), PlacementCen
             retained_handling: _retained_handling.ok_or(PlacementCenterError::CommonError(
                 Error::InvalidRetainForwardRule(req.retained_handling as u8).to_string(),
             ))?,
+            priority: req.priority,
+            create_time: req.create_time,
         };
         storage.save_auto_subscribe_rule(&req.cluster_name, &req.topic, auto_subscribe_rule)
     }
@@ -27,6 +27,7 @@
     create_session_by_req, delete_session_by_req, list_session_by_req, update_session_by_req,
 };
 use crate::mqtt::services::share_sub::get_share_sub_leader_by_req;
+use crate::mqtt::services::snapshot::export_metadata_by_req;
 use crate::mqtt::services::subscribe::{
     delete_auto_subscribe_rule_by_req, delete_subscribe_by_req, list_auto_subscribe_rule_by_req,
     list_subscribe_by_req, set_auto_subscribe_rule_by_req, set_subscribe_by_req,
@@ -51,16 +52,16 @@
     DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest,
     DeleteSessionReply, DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest,
     DeleteTopicReply, DeleteTopicRequest, DeleteTopicRewriteRuleReply,
-    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, GetShareSubLeaderReply,
-    GetShareSubLeaderRequest, ListAclReply, ListAclRequest, ListAutoSubscribeRuleReply,
-    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectorReply,
-    ListConnectorRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
-    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
-    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, SaveLastWillMessageReply,
-    SaveLastWillMessageRequest, SetAutoSubscribeRuleReply, SetAutoSubscribeRuleRequest,
-    SetSubscribeReply, SetSubscribeRequest, SetTopicRetainMessageReply,
-    SetTopicRetainMessageRequest, UpdateConnectorReply, UpdateConnectorRequest, UpdateSessionReply,
-    UpdateSessionRequest,
+    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, ExportMetadataReply,
+    ExportMetadataRequest, GetShareSubLeaderReply, GetShareSubLeaderRequest, ListAclReply,
+    ListAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
+    ListBlacklistRequest, ListConnectorReply, ListConnectorRequest, ListSessionReply,
+    ListSessionRequest, ListSubscribeReply, ListSubscribeRequest, ListTopicReply, ListTopicRequest,
+    ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest,
+    SaveLastWillMessageReply, SaveLastWillMessageRequest, SetAutoSubscribeRuleReply,
+    SetAutoSubscribeRuleRequest, SetSubscribeReply, SetSubscribeRequest,
+    SetTopicRetainMessageReply, SetTopicRetainMessageRequest, UpdateConnectorReply,
+    UpdateConnectorRequest, UpdateSessionReply, UpdateSessionRequest,
 };
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
@@ -563,4 +564,16 @@ async fn list_auto_subscribe_rule(
             .map_err(|e| Status::internal(e.to_string()))
             .map(Response::new)
     }
+
+    // Snapshot
+    async fn export_metadata(
+        &self,
+        request: Request<ExportMetadataRequest>,
+    ) -> Result<Response<ExportMetadataReply>, Status> {
+        let req = request.into_inner();
+
+        export_metadata_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
 }
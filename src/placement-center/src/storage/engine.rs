@@ -14,9 +14,10 @@
 
 use super::rocksdb::{RocksDBEngine, DB_COLUMN_FAMILY_CLUSTER};
 use common_base::error::common::CommonError;
+use dashmap::DashMap;
 use rocksdb_engine::engine::{
     rocksdb_engine_delete, rocksdb_engine_exists, rocksdb_engine_get, rocksdb_engine_prefix_list,
-    rocksdb_engine_save,
+    rocksdb_engine_prefix_map, rocksdb_engine_save,
 };
 use rocksdb_engine::warp::StorageDataWrap;
 use serde::Serialize;
@@ -69,3 +70,14 @@ pub fn engine_prefix_list_by_cluster(
         prefix_key_name,
     )
 }
+
+pub fn engine_prefix_map_by_cluster(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    prefix_key_name: String,
+) -> Result<DashMap<String, StorageDataWrap>, CommonError> {
+    rocksdb_engine_prefix_map(
+        rocksdb_engine_handler,
+        DB_COLUMN_FAMILY_CLUSTER,
+        prefix_key_name,
+    )
+}
@@ -143,6 +143,10 @@ pub fn key_segment_metadata_shard_prefix(
 }
 
 /** ===========MQTT========== */
+pub fn storage_key_mqtt_prefix() -> String {
+    "/mqtt/".to_string()
+}
+
 pub fn storage_key_mqtt_user(cluster_name: &str, user_name: &str) -> String {
     format!("/mqtt/user/{}/{}", cluster_name, user_name)
 }
@@ -92,12 +92,14 @@ async fn blacklist_storage_test() {
             resource_name: "resource1".to_string(),
             end_time: 171456001,
             desc: "user1".to_string(),
+            expire_at: None,
         };
         let blacklist2 = MqttAclBlackList {
             blacklist_type: MqttAclBlackListType::User,
             resource_name: "resource2".to_string(),
             end_time: 171456002,
             desc: "user2".to_string(),
+            expire_at: None,
         };
 
         blacklist_storage
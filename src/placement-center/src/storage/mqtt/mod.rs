@@ -29,6 +29,7 @@
 pub mod connector;
 pub mod lastwill;
 pub mod session;
+pub mod snapshot;
 pub mod subscribe;
 pub mod topic;
 pub mod user;
@@ -0,0 +1,134 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+
+use crate::storage::engine::engine_prefix_map_by_cluster;
+use crate::storage::keys::storage_key_mqtt_prefix;
+use crate::storage::rocksdb::RocksDBEngine;
+
+// Categories are the second segment of the mqtt keys, /mqtt/{category}/{cluster_name}/...
+pub const METADATA_CATEGORY_USER: &str = "user";
+pub const METADATA_CATEGORY_ACL: &str = "acl";
+pub const METADATA_CATEGORY_BLACKLIST: &str = "blacklist";
+pub const METADATA_CATEGORY_SCHEMA: &str = "schema";
+pub const METADATA_CATEGORY_SCHEMA_BIND: &str = "schema_bind";
+pub const METADATA_CATEGORY_CONNECTOR: &str = "connector";
+pub const METADATA_CATEGORY_TOPIC: &str = "topic";
+pub const METADATA_CATEGORY_SESSION: &str = "session";
+pub const METADATA_CATEGORY_SUBSCRIBE: &str = "subscribe";
+
+pub struct MqttSnapshotStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttSnapshotStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttSnapshotStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    // All mqtt metadata of a cluster grouped by category. It is read through a single
+    // iterator, so the result is a point-in-time view of the store.
+    pub fn export(&self, cluster_name: &str) -> Result<HashMap<String, Vec<Vec<u8>>>, CommonError> {
+        let data = engine_prefix_map_by_cluster(
+            self.rocksdb_engine_handler.clone(),
+            storage_key_mqtt_prefix(),
+        )?;
+
+        let mut results: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
+        for (key, raw) in data {
+            if let Some(category) = metadata_category(&key, cluster_name) {
+                results
+                    .entry(category.to_string())
+                    .or_default()
+                    .push(raw.data.into_bytes());
+            }
+        }
+        Ok(results)
+    }
+}
+
+fn metadata_category<'a>(key: &'a str, cluster_name: &str) -> Option<&'a str> {
+    let mut segments = key
+        .strip_prefix(storage_key_mqtt_prefix().as_str())?
+        .splitn(3, '/');
+    let category = segments.next()?;
+    let cluster = segments.next()?;
+    (cluster == cluster_name).then_some(category)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_base::utils::file_utils::test_temp_dir;
+    use common_config::place::config::placement_center_test_conf;
+    use metadata_struct::mqtt::user::MqttUser;
+
+    use super::{metadata_category, METADATA_CATEGORY_USER};
+    use crate::storage::mqtt::snapshot::MqttSnapshotStorage;
+    use crate::storage::mqtt::user::MqttUserStorage;
+    use crate::storage::rocksdb::{column_family_list, RocksDBEngine};
+
+    #[test]
+    fn metadata_category_test() {
+        assert_eq!(metadata_category("/mqtt/user/c1/lobo", "c1"), Some("user"));
+        assert_eq!(
+            metadata_category("/mqtt/schema_bind/c1/resource/schema", "c1"),
+            Some("schema_bind")
+        );
+        assert_eq!(metadata_category("/mqtt/user/c12/lobo", "c1"), None);
+        assert_eq!(
+            metadata_category("/mqtt/sub_group_leader/c1", "c1"),
+            Some("sub_group_leader")
+        );
+        assert_eq!(metadata_category("/clusters/node/c1/1", "c1"), None);
+    }
+
+    #[tokio::test]
+    async fn snapshot_export_test() {
+        let config = placement_center_test_conf();
+
+        let rs = Arc::new(RocksDBEngine::new(
+            &test_temp_dir(),
+            config.rocksdb.max_open_files.unwrap(),
+            column_family_list(),
+        ));
+        let user_storage = MqttUserStorage::new(rs.clone());
+        for (cluster_name, username) in [("c1", "lobo1"), ("c1", "lobo2"), ("c2", "lobo3")] {
+            let user = MqttUser {
+                username: username.to_string(),
+                password: "pwd123".to_string(),
+                is_superuser: false,
+                create_time: 0,
+            };
+            user_storage.save(cluster_name, username, user).unwrap();
+        }
+
+        let snapshot_storage = MqttSnapshotStorage::new(rs);
+        let res = snapshot_storage.export("c1").unwrap();
+        assert_eq!(res.len(), 1);
+        let users = res.get(METADATA_CATEGORY_USER).unwrap();
+        assert_eq!(users.len(), 2);
+        for raw in users {
+            let user = serde_json::from_slice::<MqttUser>(raw).unwrap();
+            assert_ne!(user.username, "lobo3");
+        }
+    }
+}
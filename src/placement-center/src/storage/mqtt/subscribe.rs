@@ -279,6 +279,8 @@ async fn auto_subscribe_rule_storage_basic_ops() {
             no_local: true,
             retain_as_published: false,
             retained_handling: RetainHandling::OnEverySubscribe,
+            priority: 10,
+            create_time: 1000,
         };
 
         storage
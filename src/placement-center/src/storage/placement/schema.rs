@@ -186,6 +186,7 @@ async fn schema_storage_test() {
             schema_type: SchemaType::JSON,
             desc: desc.to_string(),
             schema: schema.to_string(),
+            message_name: "".to_string(),
         };
 
         //test func save()
@@ -12,7 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     robustmq_proto_build::setup()?;
+
+    // The admin service is served by gRPC reflection from this descriptor set. A proto build
+    // that does not write it leaves an empty set, so the admin service is then not described
+    // by reflection but the crate still builds.
+    let descriptor = PathBuf::from(env::var("OUT_DIR")?).join("broker_mqtt_admin_descriptor.bin");
+    if !descriptor.exists() {
+        println!(
+            "cargo:warning={} was not generated by robustmq-proto-build, gRPC reflection will not describe the admin service",
+            descriptor.display()
+        );
+        fs::write(&descriptor, b"")?;
+    }
     Ok(())
 }
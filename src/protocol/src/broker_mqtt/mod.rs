@@ -17,6 +17,10 @@
 
 pub mod broker_mqtt_admin {
     tonic::include_proto!("broker.mqtt.admin");
+
+    // Served by gRPC reflection so that tools like grpcurl can discover the admin service
+    pub const FILE_DESCRIPTOR_SET: &[u8] =
+        tonic::include_file_descriptor_set!("broker_mqtt_admin_descriptor");
 }
 
 pub mod broker_mqtt_inner {
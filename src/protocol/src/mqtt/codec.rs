@@ -27,11 +27,20 @@ pub struct MqttPacketWrapper {
 #[derive(Clone, Debug)]
 pub struct MqttCodec {
     pub protocol_version: Option<u8>,
+    last_frame_length: usize,
 }
 
 impl MqttCodec {
     pub fn new(protocol_version: Option<u8>) -> MqttCodec {
-        MqttCodec { protocol_version }
+        MqttCodec {
+            protocol_version,
+            last_frame_length: 0,
+        }
+    }
+
+    // Length on the wire of the last packet returned by decode_data
+    pub fn last_frame_length(&self) -> usize {
+        self.last_frame_length
     }
 }
 
@@ -43,6 +52,7 @@ pub fn decode_data(
         let fixed_header = check(stream.iter(), 1000000)?;
         // Test with a stream with exactly the size to check border panics
         let packet = stream.split_to(fixed_header.frame_length());
+        self.last_frame_length = fixed_header.frame_length();
         let packet_type = fixed_header.packet_type()?;
         let packet = packet.freeze();
 
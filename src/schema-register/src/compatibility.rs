@@ -0,0 +1,494 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use apache_avro::{schema_compatibility::SchemaCompatibility, Schema};
+use common_base::error::common::CommonError;
+use metadata_struct::schema::{SchemaData, SchemaType};
+use serde_json::Value;
+
+// How a new version of a schema has to relate to the current one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompatibilityMode {
+    // No check
+    None,
+    // Data valid under the current schema is valid under the new one
+    Backward,
+    // Data valid under the new schema is valid under the current one
+    Forward,
+    // Both Backward and Forward
+    Full,
+}
+
+impl CompatibilityMode {
+    pub fn parse(mode: &str) -> Result<Self, CommonError> {
+        match mode.to_lowercase().as_str() {
+            "" | "none" => Ok(CompatibilityMode::None),
+            "backward" => Ok(CompatibilityMode::Backward),
+            "forward" => Ok(CompatibilityMode::Forward),
+            "full" => Ok(CompatibilityMode::Full),
+            _ => Err(CommonError::CommonError(format!(
+                "unknown schema compatibility mode {}, expected one of BACKWARD, FORWARD, FULL, NONE",
+                mode
+            ))),
+        }
+    }
+}
+
+// Check that `new` can replace `current` under the given mode. All incompatibilities are
+// collected into the error message, one per line.
+pub fn check_compatibility(
+    mode: CompatibilityMode,
+    current: &SchemaData,
+    new: &SchemaData,
+) -> Result<(), CommonError> {
+    if mode == CompatibilityMode::None {
+        return Ok(());
+    }
+
+    let mut diffs = Vec::new();
+    if current.schema_type != new.schema_type {
+        diffs.push(format!(
+            "schema type changed from {} to {}",
+            current.schema_type, new.schema_type
+        ));
+    } else {
+        let check_backward = matches!(mode, CompatibilityMode::Backward | CompatibilityMode::Full);
+        let check_forward = matches!(mode, CompatibilityMode::Forward | CompatibilityMode::Full);
+        match new.schema_type {
+            SchemaType::JSON => {
+                let current_schema = parse_json_schema(&current.schema)?;
+                let new_schema = parse_json_schema(&new.schema)?;
+                if check_backward {
+                    json_accepts(&new_schema, &current_schema, "$", "backward", &mut diffs);
+                }
+                if check_forward {
+                    json_accepts(&current_schema, &new_schema, "$", "forward", &mut diffs);
+                }
+            }
+            SchemaType::AVRO => {
+                let current_schema = Schema::parse_str(&current.schema)?;
+                let new_schema = Schema::parse_str(&new.schema)?;
+                if check_backward {
+                    if let Err(e) = SchemaCompatibility::can_read(&current_schema, &new_schema) {
+                        diffs.push(format!(
+                            "backward: the new schema cannot read data written with the current schema: {}",
+                            e
+                        ));
+                    }
+                }
+                if check_forward {
+                    if let Err(e) = SchemaCompatibility::can_read(&new_schema, &current_schema) {
+                        diffs.push(format!(
+                            "forward: the current schema cannot read data written with the new schema: {}",
+                            e
+                        ));
+                    }
+                }
+            }
+            SchemaType::PROTOBUF => {
+                return Err(CommonError::CommonError(
+                    "compatibility checks are not supported for protobuf schemas, use NONE"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+
+    if diffs.is_empty() {
+        return Ok(());
+    }
+    Err(CommonError::CommonError(format!(
+        "schema {} is not {:?} compatible with its current version:\n{}",
+        new.name,
+        mode,
+        diffs.join("\n")
+    )))
+}
+
+fn parse_json_schema(schema: &str) -> Result<Value, CommonError> {
+    Ok(serde_json::from_str(schema)?)
+}
+
+// Record every way in which `reader` rejects data that `writer` accepts. Only the keywords
+// below are compared, properties `reader` does not describe are not checked.
+fn json_accepts(
+    reader: &Value,
+    writer: &Value,
+    path: &str,
+    direction: &str,
+    diffs: &mut Vec<String>,
+) {
+    let (Some(reader), Some(writer)) = (reader.as_object(), writer.as_object()) else {
+        return;
+    };
+
+    // type
+    if let Some(reader_types) = reader.get("type").map(json_types) {
+        match writer.get("type").map(json_types) {
+            Some(writer_types) => {
+                for writer_type in writer_types {
+                    let accepted = reader_types.contains(&writer_type)
+                        || (writer_type == "integer"
+                            && reader_types.contains(&"number".to_string()));
+                    if !accepted {
+                        diffs.push(format!(
+                            "{}: {} type {} is no longer accepted, allowed types are {:?}",
+                            direction, path, writer_type, reader_types
+                        ));
+                    }
+                }
+            }
+            None => diffs.push(format!(
+                "{}: {} is restricted to types {:?}",
+                direction, path, reader_types
+            )),
+        }
+    }
+
+    // enum
+    if let Some(reader_enum) = reader.get("enum").and_then(|v| v.as_array()) {
+        match writer.get("enum").and_then(|v| v.as_array()) {
+            Some(writer_enum) => {
+                for value in writer_enum {
+                    if !reader_enum.contains(value) {
+                        diffs.push(format!(
+                            "{}: {} enum value {} is no longer accepted",
+                            direction, path, value
+                        ));
+                    }
+                }
+            }
+            None => diffs.push(format!(
+                "{}: {} is restricted to enum values {}",
+                direction,
+                path,
+                Value::Array(reader_enum.clone())
+            )),
+        }
+    }
+
+    // bounds, a reader bound is only fine if the writer has an equal or tighter one
+    for (keyword, reader_is_lower) in [
+        ("minimum", true),
+        ("maximum", false),
+        ("minLength", true),
+        ("maxLength", false),
+        ("minItems", true),
+        ("maxItems", false),
+    ] {
+        let Some(reader_bound) = reader.get(keyword).and_then(|v| v.as_f64()) else {
+            continue;
+        };
+        let covered = writer
+            .get(keyword)
+            .and_then(|v| v.as_f64())
+            .is_some_and(|writer_bound| {
+                if reader_is_lower {
+                    writer_bound >= reader_bound
+                } else {
+                    writer_bound <= reader_bound
+                }
+            });
+        if !covered {
+            diffs.push(format!(
+                "{}: {} {} is tightened to {}",
+                direction,
+                path,
+                keyword,
+                reader.get(keyword).unwrap()
+            ));
+        }
+    }
+
+    // required
+    let writer_required = string_list(writer.get("required"));
+    for field in string_list(reader.get("required")) {
+        if !writer_required.contains(&field) {
+            diffs.push(format!(
+                "{}: {}.{} is required but may be missing",
+                direction, path, field
+            ));
+        }
+    }
+
+    // properties
+    let empty = serde_json::Map::new();
+    let reader_properties = reader
+        .get("properties")
+        .and_then(|v| v.as_object())
+        .unwrap_or(&empty);
+    let writer_properties = writer
+        .get("properties")
+        .and_then(|v| v.as_object())
+        .unwrap_or(&empty);
+    let reader_closed = reader.get("additionalProperties") == Some(&Value::Bool(false));
+    for (name, writer_property) in writer_properties {
+        let property_path = format!("{}.{}", path, name);
+        match reader_properties.get(name) {
+            Some(reader_property) => json_accepts(
+                reader_property,
+                writer_property,
+                &property_path,
+                direction,
+                diffs,
+            ),
+            None if reader_closed => diffs.push(format!(
+                "{}: {} is not allowed because additionalProperties is false",
+                direction, property_path
+            )),
+            None => {}
+        }
+    }
+    let writer_closed = writer.get("additionalProperties") == Some(&Value::Bool(false));
+    if reader_closed && !writer_closed {
+        diffs.push(format!(
+            "{}: {} no longer allows additional properties",
+            direction, path
+        ));
+    }
+
+    // items
+    if let (Some(reader_items), Some(writer_items)) = (reader.get("items"), writer.get("items")) {
+        json_accepts(
+            reader_items,
+            writer_items,
+            &format!("{}[]", path),
+            direction,
+            diffs,
+        );
+    }
+}
+
+fn json_types(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(t) => vec![t.clone()],
+        Value::Array(_) => string_list(Some(value)),
+        _ => Vec::new(),
+    }
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|list| {
+            list.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use metadata_struct::schema::{SchemaData, SchemaType};
+
+    use super::{check_compatibility, CompatibilityMode};
+
+    fn schema(schema_type: SchemaType, schema: &str) -> SchemaData {
+        SchemaData {
+            cluster_name: "test_cluster".to_string(),
+            name: "schema1".to_string(),
+            schema_type,
+            desc: "".to_string(),
+            schema: schema.to_string(),
+            message_name: "".to_string(),
+        }
+    }
+
+    const JSON_V1: &str = r#"{
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "age": { "type": "integer", "minimum": 0 }
+        },
+        "required": ["name", "age"]
+    }"#;
+
+    // age becomes optional and any number, a new optional field is added
+    const JSON_RELAXED: &str = r#"{
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "age": { "type": "number" },
+            "email": { "type": "string" }
+        },
+        "required": ["name"]
+    }"#;
+
+    // email becomes required and age must be at least 18
+    const JSON_TIGHTENED: &str = r#"{
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "age": { "type": "integer", "minimum": 18 },
+            "email": { "type": "string" }
+        },
+        "required": ["name", "age", "email"]
+    }"#;
+
+    #[test]
+    fn compatibility_mode_parse_test() {
+        assert_eq!(
+            CompatibilityMode::parse("").unwrap(),
+            CompatibilityMode::None
+        );
+        assert_eq!(
+            CompatibilityMode::parse("BACKWARD").unwrap(),
+            CompatibilityMode::Backward
+        );
+        assert_eq!(
+            CompatibilityMode::parse("forward").unwrap(),
+            CompatibilityMode::Forward
+        );
+        assert_eq!(
+            CompatibilityMode::parse("Full").unwrap(),
+            CompatibilityMode::Full
+        );
+        assert!(CompatibilityMode::parse("transitive").is_err());
+    }
+
+    #[test]
+    fn json_compatibility_test() {
+        let v1 = schema(SchemaType::JSON, JSON_V1);
+        let relaxed = schema(SchemaType::JSON, JSON_RELAXED);
+        let tightened = schema(SchemaType::JSON, JSON_TIGHTENED);
+
+        // NONE accepts anything
+        assert!(check_compatibility(CompatibilityMode::None, &v1, &tightened).is_ok());
+
+        // BACKWARD: relaxing is fine, tightening rejects data that used to be valid
+        assert!(check_compatibility(CompatibilityMode::Backward, &v1, &relaxed).is_ok());
+        let err = check_compatibility(CompatibilityMode::Backward, &v1, &tightened)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("$.email is required but may be missing"),
+            "{}",
+            err
+        );
+        assert!(err.contains("$.age minimum is tightened to 18"), "{}", err);
+
+        // FORWARD: the other way round
+        assert!(check_compatibility(CompatibilityMode::Forward, &v1, &tightened).is_ok());
+        let err = check_compatibility(CompatibilityMode::Forward, &v1, &relaxed)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("$.age is required but may be missing"),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains("$.age type number is no longer accepted"),
+            "{}",
+            err
+        );
+
+        // FULL: only changes that keep both directions valid
+        let described = schema(
+            SchemaType::JSON,
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "full name" },
+                    "age": { "type": "integer", "minimum": 0 }
+                },
+                "required": ["name", "age"]
+            }"#,
+        );
+        assert!(check_compatibility(CompatibilityMode::Full, &v1, &described).is_ok());
+        assert!(check_compatibility(CompatibilityMode::Full, &v1, &relaxed).is_err());
+        assert!(check_compatibility(CompatibilityMode::Full, &v1, &tightened).is_err());
+
+        // changing the schema type is never compatible
+        let avro = schema(SchemaType::AVRO, r#"{"type": "string"}"#);
+        let err = check_compatibility(CompatibilityMode::Backward, &v1, &avro)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("schema type changed from json to avro"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn avro_compatibility_test() {
+        let v1 = schema(
+            SchemaType::AVRO,
+            r#"{
+                "type": "record",
+                "name": "test",
+                "fields": [
+                    {"name": "a", "type": "long"},
+                    {"name": "b", "type": "string"}
+                ]
+            }"#,
+        );
+        // a new field with a default can be read from old data
+        let with_default = schema(
+            SchemaType::AVRO,
+            r#"{
+                "type": "record",
+                "name": "test",
+                "fields": [
+                    {"name": "a", "type": "long"},
+                    {"name": "b", "type": "string"},
+                    {"name": "c", "type": "string", "default": ""}
+                ]
+            }"#,
+        );
+        // a new field without a default cannot
+        let without_default = schema(
+            SchemaType::AVRO,
+            r#"{
+                "type": "record",
+                "name": "test",
+                "fields": [
+                    {"name": "a", "type": "long"},
+                    {"name": "b", "type": "string"},
+                    {"name": "c", "type": "string"}
+                ]
+            }"#,
+        );
+
+        assert!(check_compatibility(CompatibilityMode::None, &v1, &without_default).is_ok());
+
+        assert!(check_compatibility(CompatibilityMode::Backward, &v1, &with_default).is_ok());
+        let err = check_compatibility(CompatibilityMode::Backward, &v1, &without_default)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("backward"), "{}", err);
+
+        // old readers ignore the new field
+        assert!(check_compatibility(CompatibilityMode::Forward, &v1, &without_default).is_ok());
+        let dropped = schema(
+            SchemaType::AVRO,
+            r#"{
+                "type": "record",
+                "name": "test",
+                "fields": [
+                    {"name": "a", "type": "long"}
+                ]
+            }"#,
+        );
+        let err = check_compatibility(CompatibilityMode::Forward, &v1, &dropped)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("forward"), "{}", err);
+
+        assert!(check_compatibility(CompatibilityMode::Full, &v1, &with_default).is_ok());
+        assert!(check_compatibility(CompatibilityMode::Full, &v1, &without_default).is_err());
+    }
+}
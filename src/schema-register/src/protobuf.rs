@@ -16,28 +16,61 @@
 use metadata_struct::schema::SchemaData;
 use protofish::{decode::Value, prelude::Context};
 
-pub fn protobuf_validate(
-    schema_data: &SchemaData,
-    data: &[u8],
-    message_name: &str,
-) -> Result<bool, CommonError> {
-    let context = Context::parse([schema_data.schema.as_str()]).map_err(|err| {
+// Check that a protobuf schema parses and defines the message type payloads are decoded as,
+// so that a broken definition is rejected when the schema is created
+pub fn protobuf_check_schema(schema_data: &SchemaData) -> Result<(), CommonError> {
+    if schema_data.message_name.is_empty() {
+        return Err(CommonError::CommonError(format!(
+            "Protobuf schema {} requires a message name",
+            schema_data.name.as_str()
+        )));
+    }
+    let context = protobuf_parse_schema(schema_data)?;
+    if context.get_message(&schema_data.message_name).is_none() {
+        return Err(CommonError::CommonError(format!(
+            "Message {} not found in schema {}",
+            schema_data.message_name,
+            schema_data.name.as_str()
+        )));
+    }
+    Ok(())
+}
+
+// Parse the definition of a protobuf schema. The parsed context is kept by the schema manager
+// so payloads are decoded without parsing the definition again.
+pub fn protobuf_parse_schema(schema_data: &SchemaData) -> Result<Context, CommonError> {
+    Context::parse([schema_data.schema.as_str()]).map_err(|err| {
         CommonError::CommonError(format!(
             "Failed to parse schema {}: {}",
             schema_data.name.as_str(),
             err
         ))
-    })?;
+    })
+}
+
+pub fn protobuf_validate(
+    schema_data: &SchemaData,
+    data: &[u8],
+    message_name: &str,
+) -> Result<bool, CommonError> {
+    let context = protobuf_parse_schema(schema_data)?;
+    protobuf_validate_with_context(&context, &schema_data.name, data, message_name)
+}
 
+pub fn protobuf_validate_with_context(
+    context: &Context,
+    schema_name: &str,
+    data: &[u8],
+    message_name: &str,
+) -> Result<bool, CommonError> {
     let message = context.get_message(message_name).ok_or_else(|| {
         CommonError::CommonError(format!(
             "Message {} not found in schema {}",
-            message_name,
-            schema_data.name.as_str()
+            message_name, schema_name
         ))
     })?;
 
-    let decoded = message.decode(data, &context);
+    let decoded = message.decode(data, context);
 
     // Check if there are any unknown or incomplete fields
     for field in decoded.fields {
@@ -54,7 +87,7 @@ pub fn protobuf_validate(
 
 #[cfg(test)]
 mod test {
-    use crate::protobuf::protobuf_validate;
+    use crate::protobuf::{protobuf_check_schema, protobuf_validate};
     use metadata_struct::schema::{SchemaData, SchemaType};
 
     #[test]
@@ -81,6 +114,7 @@ pub fn protofish_example_test() {
             schema_type: SchemaType::PROTOBUF,
             desc: "".to_string(),
             schema: schema.to_string(),
+            message_name: "Proto.Request".to_string(),
         };
 
         let res = protobuf_validate(&schema_data, b"\x0a\x05Perch", "Proto.Request");
@@ -126,6 +160,7 @@ pub fn protofish_schema_test() {
             schema_type: SchemaType::PROTOBUF,
             desc: "".to_string(),
             schema: schema.to_string(),
+            message_name: "MyPackage.Person".to_string(),
         };
 
         // ----- Experience -----
@@ -160,4 +195,46 @@ pub fn protofish_schema_test() {
         assert!(res.is_ok());
         assert!(res.unwrap());
     }
+
+    #[test]
+    pub fn protobuf_check_schema_test() {
+        let mut schema_data = SchemaData {
+            cluster_name: "test_cluster".to_string(),
+            name: "Proto".to_string(),
+            schema_type: SchemaType::PROTOBUF,
+            desc: "".to_string(),
+            schema: r#"
+                syntax = "proto3";
+                package Proto;
+
+                message Request { string kind = 1; }
+            "#
+            .to_string(),
+            message_name: "Proto.Request".to_string(),
+        };
+        assert!(protobuf_check_schema(&schema_data).is_ok());
+
+        // the message type has to be named and defined in the schema
+        schema_data.message_name = "".to_string();
+        let err = protobuf_check_schema(&schema_data).unwrap_err();
+        assert!(err.to_string().contains("requires a message name"));
+
+        schema_data.message_name = "Proto.Response".to_string();
+        let err = protobuf_check_schema(&schema_data).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Message Proto.Response not found in schema Proto"));
+
+        // a definition that does not parse
+        schema_data.message_name = "Proto.Request".to_string();
+        schema_data.schema = r#"
+            syntax = "proto3";
+            package Proto;
+
+            message Request { string kind = ; }
+        "#
+        .to_string();
+        let err = protobuf_check_schema(&schema_data).unwrap_err();
+        assert!(err.to_string().contains("Failed to parse schema Proto"));
+    }
 }
@@ -13,12 +13,17 @@
 // limitations under the License.
 
 use std::str::from_utf8;
+use std::sync::Arc;
 
 use common_base::error::common::CommonError;
 use dashmap::DashMap;
 use metadata_struct::schema::{SchemaData, SchemaResourceBind, SchemaType};
+use protofish::prelude::Context;
+use tracing::warn;
 
-use crate::{avro::avro_validate, json::json_validate};
+use crate::avro::avro_validate;
+use crate::json::json_validate;
+use crate::protobuf::{protobuf_parse_schema, protobuf_validate, protobuf_validate_with_context};
 
 #[derive(Default)]
 pub struct SchemaRegisterManager {
@@ -26,6 +31,8 @@ pub struct SchemaRegisterManager {
     schema_list: DashMap<String, SchemaData>,
     // (Resource, Vec<SchemaName>)
     schema_resource_list: DashMap<String, Vec<String>>,
+    // (SchemaName, parsed definition of a protobuf schema)
+    protobuf_context_list: DashMap<String, Arc<Context>>,
 }
 
 impl SchemaRegisterManager {
@@ -33,6 +40,7 @@ pub fn new() -> Self {
         SchemaRegisterManager {
             schema_list: DashMap::with_capacity(2),
             schema_resource_list: DashMap::with_capacity(2),
+            protobuf_context_list: DashMap::with_capacity(2),
         }
     }
 
@@ -52,7 +60,22 @@ pub fn validate(&self, resource: &str, data: &[u8]) -> Result<bool, CommonError>
                             let raw = from_utf8(data).unwrap();
                             return json_validate(&schema.schema, raw);
                         }
-                        SchemaType::PROTOBUF => {}
+                        SchemaType::PROTOBUF => {
+                            let Some(context) = self
+                                .protobuf_context_list
+                                .get(schema_name)
+                                .map(|context| context.clone())
+                            else {
+                                // The definition did not parse when the schema was added
+                                return protobuf_validate(&schema, data, &schema.message_name);
+                            };
+                            return protobuf_validate_with_context(
+                                &context,
+                                &schema.name,
+                                data,
+                                &schema.message_name,
+                            );
+                        }
                         SchemaType::AVRO => {
                             return avro_validate(&schema.schema, data);
                         }
@@ -65,11 +88,22 @@ pub fn validate(&self, resource: &str, data: &[u8]) -> Result<bool, CommonError>
 
     // Schema
     pub fn add_schema(&self, schema: SchemaData) {
+        self.protobuf_context_list.remove(&schema.name);
+        if schema.schema_type == SchemaType::PROTOBUF {
+            match protobuf_parse_schema(&schema) {
+                Ok(context) => {
+                    self.protobuf_context_list
+                        .insert(schema.name.clone(), Arc::new(context));
+                }
+                Err(e) => warn!("{}", e),
+            }
+        }
         self.schema_list.insert(schema.name.clone(), schema);
     }
 
     pub fn remove_schema(&self, schema_name: &str) {
         self.schema_list.remove(schema_name);
+        self.protobuf_context_list.remove(schema_name);
     }
 
     pub fn get_schema(&self, schema_name: &str) -> Option<SchemaData> {
@@ -154,6 +188,7 @@ pub fn json_schema_test() {
             schema: schema_json_content.to_string(),
             schema_type: SchemaType::JSON,
             desc: "test".to_string(),
+            message_name: "".to_string(),
         });
 
         let topic_name = "t1".to_string();
@@ -225,6 +260,7 @@ pub fn avro_schema_test() {
             schema: schema_avro_content.to_string(),
             schema_type: SchemaType::AVRO,
             desc: "test".to_string(),
+            message_name: "".to_string(),
         });
 
         let topic_name = "t1".to_string();
@@ -281,4 +317,53 @@ pub fn avro_schema_test() {
         println!("{:?}", result);
         assert!(result.is_err());
     }
+
+    #[test]
+    pub fn protobuf_schema_test() {
+        let schema_manager = SchemaRegisterManager::new();
+        let cluster_name = "test1".to_string();
+        let schema_name = "schema1".to_string();
+        let schema_proto_content = r#"
+            syntax = "proto3";
+            package Proto;
+
+            message Request { string kind = 1; }
+        "#;
+
+        schema_manager.add_schema(SchemaData {
+            cluster_name: cluster_name.clone(),
+            name: schema_name.clone(),
+            schema: schema_proto_content.to_string(),
+            schema_type: SchemaType::PROTOBUF,
+            desc: "test".to_string(),
+            message_name: "Proto.Request".to_string(),
+        });
+
+        let topic_name = "t1".to_string();
+        schema_manager.add_schema_resource(&SchemaResourceBind {
+            cluster_name: cluster_name.clone(),
+            resource_name: topic_name.clone(),
+            schema_name: schema_name.clone(),
+        });
+        assert!(schema_manager.is_check_schema(&topic_name));
+
+        // Request { kind: "Perch" }
+        let result = schema_manager.validate(&topic_name, b"\x0a\x05Perch");
+        assert!(result.is_ok());
+        assert!(result.unwrap());
+
+        // field 2 is not part of Request
+        let result = schema_manager.validate(&topic_name, b"\x12\x07Unknown\x0a\x0fAtlantic ");
+        assert!(result.is_ok());
+        assert!(!result.unwrap());
+
+        // the definition is parsed once when the schema is added
+        assert!(schema_manager
+            .protobuf_context_list
+            .contains_key(&schema_name));
+        schema_manager.remove_schema(&schema_name);
+        assert!(!schema_manager
+            .protobuf_context_list
+            .contains_key(&schema_name));
+    }
 }
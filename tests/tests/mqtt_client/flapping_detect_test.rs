@@ -40,6 +40,10 @@ async fn test_enable_flapping_detect() {
             window_time: 1,
             max_client_connections: 15,
             ban_time: 1,
+            window_seconds: 0,
+            max_disconnects: 0,
+            ban_seconds: 0,
+            allowlist: Vec::new(),
         };
 
         // result
@@ -63,6 +67,10 @@ async fn test_enable_flapping_detect() {
             window_time: 1,
             max_client_connections: 15,
             ban_time: 1,
+            window_seconds: 0,
+            max_disconnects: 0,
+            ban_seconds: 0,
+            allowlist: Vec::new(),
         };
 
         // result
@@ -58,6 +58,7 @@ async fn test_enable_slow_subscribe() {
             topic: "".to_string(),
             client_id: "".to_string(),
             sort: "asc".to_string(),
+            threshold_ms: 0,
         };
 
         match mqtt_broker_list_slow_subscribe(&client_pool, &grpc_addr, request).await {
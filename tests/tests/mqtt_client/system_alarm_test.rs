@@ -17,7 +17,7 @@
 use grpc_clients::mqtt::admin::call::mqtt_broker_set_system_alarm_config;
 use grpc_clients::pool::ClientPool;
 use protocol::broker_mqtt::broker_mqtt_admin::{
-    SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
+    AlarmWebhookRaw, SetSystemAlarmConfigReply, SetSystemAlarmConfigRequest,
 };
 use std::sync::Arc;
 
@@ -38,6 +38,7 @@ pub async fn set_cluster_system_alarm_config() {
             os_cpu_low_watermark: Some(40.0),
             os_memory_high_watermark: None,
             os_cpu_check_interval_ms: None,
+            ..Default::default()
         };
 
         let expect_reply = SetSystemAlarmConfigReply {
@@ -62,6 +63,14 @@ pub async fn set_cluster_system_alarm_config() {
                     .os_cpu_check_interval_ms
                     .unwrap_or(mqtt_broker_conf.system_monitor.os_cpu_check_interval_ms),
             ),
+            thresholds: Vec::new(),
+            webhook: Some(AlarmWebhookRaw {
+                urls: Vec::new(),
+                secret: String::new(),
+                max_retries: 3,
+                initial_backoff_ms: 500,
+                timeout_ms: 5000,
+            }),
         };
 
         match mqtt_broker_set_system_alarm_config(&client_pool, &grpc_addr, valid_request).await {
@@ -82,7 +91,7 @@ pub async fn list_cluster_system_alarm() {
     let client_pool = Arc::new(ClientPool::new(3));
     let grpc_addr = vec![broker_grpc_addr()];
 
-    let request = protocol::broker_mqtt::broker_mqtt_admin::ListSystemAlarmRequest {};
+    let request = protocol::broker_mqtt::broker_mqtt_admin::ListSystemAlarmRequest::default();
 
     match grpc_clients::mqtt::admin::call::mqtt_broker_list_system_alarm(
         &client_pool,
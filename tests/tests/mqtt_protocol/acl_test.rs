@@ -342,6 +342,8 @@ async fn create_user(
             username,
             password,
             is_superuser: false,
+            if_not_exists: false,
+            upsert: false,
         };
         let res = mqtt_broker_create_user(&client_pool, &grpc_addr, user.clone()).await;
         assert!(res.is_ok());
@@ -50,6 +50,7 @@ async fn blacklist_storage_test() {
             resource_name: user.to_string(),
             end_time: 10000,
             desc: password.to_string(),
+            expire_at: None,
         };
 
         create_blacklist(
@@ -122,6 +123,7 @@ async fn blacklist_user_auth_test() {
             resource_name: user.to_string(),
             end_time: now_second() + 10000,
             desc: password.to_string(),
+            expire_at: None,
         };
 
         create_blacklist(
@@ -243,6 +245,8 @@ async fn create_user(
             username: username.to_owned(),
             password: password.to_owned(),
             is_superuser: false,
+            if_not_exists: false,
+            upsert: false,
         };
 
         let res = mqtt_broker_create_user(client_pool, addrs, user.clone()).await;
@@ -0,0 +1,79 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use crate::mqtt_protocol::common::{broker_addr, broker_grpc_addr, connect_server};
+    use crate::mqtt_protocol::ClientTestProperties;
+    use common_base::tools::unique_id;
+    use grpc_clients::mqtt::admin::call::{
+        mqtt_broker_disconnect_client, mqtt_broker_list_connection,
+    };
+    use grpc_clients::pool::ClientPool;
+    use protocol::broker_mqtt::broker_mqtt_admin::{
+        DisconnectClientRequest, ListConnectionRequest,
+    };
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    #[ignore = "reason"]
+    #[tokio::test]
+    async fn disconnect_client_test() {
+        for mqtt_version in [4, 5] {
+            let client_id = unique_id();
+            let client_test_properties = ClientTestProperties {
+                mqtt_version,
+                client_id: client_id.clone(),
+                addr: broker_addr(),
+                ws: false,
+                ssl: false,
+                ..Default::default()
+            };
+            let cli = connect_server(&client_test_properties);
+            assert!(cli.is_connected());
+            assert_eq!(connection_num(&client_id).await, 1);
+
+            let client_pool = Arc::new(ClientPool::new(3));
+            let grpc_addr = vec![broker_grpc_addr()];
+            let request = DisconnectClientRequest {
+                client_id: client_id.clone(),
+                connection_id: None,
+                clean_session: true,
+            };
+            let reply = mqtt_broker_disconnect_client(&client_pool, &grpc_addr, request)
+                .await
+                .unwrap();
+            assert_eq!(reply.closed_num, 1);
+
+            sleep(Duration::from_secs(1)).await;
+            assert!(!cli.is_connected());
+            assert_eq!(connection_num(&client_id).await, 0);
+        }
+    }
+
+    async fn connection_num(client_id: &str) -> usize {
+        let client_pool = Arc::new(ClientPool::new(3));
+        let grpc_addr = vec![broker_grpc_addr()];
+        let request = ListConnectionRequest {
+            client_id: Some(client_id.to_string()),
+            ..Default::default()
+        };
+        mqtt_broker_list_connection(&client_pool, &grpc_addr, request)
+            .await
+            .unwrap()
+            .list_connection_raw
+            .len()
+    }
+}
@@ -29,7 +29,7 @@ mod tests {
     #[ignore = "reason"]
     #[tokio::test]
     async fn client_flapping_detect_test() {
-        open_flapping_detect().await;
+        open_flapping_detect(Vec::new()).await;
 
         let client_test_properties = ClientTestProperties {
             mqtt_version: 3,
@@ -50,6 +50,37 @@ async fn client_flapping_detect_test() {
         close_flapping_detect().await;
     }
 
+    #[ignore = "reason"]
+    #[tokio::test]
+    async fn client_flapping_detect_allowlist_test() {
+        let allowed_client_id = format!("allowed_{}", unique_id());
+        open_flapping_detect(vec!["allowed_.*".to_string()]).await;
+
+        let allowed = ClientTestProperties {
+            mqtt_version: 3,
+            client_id: allowed_client_id,
+            addr: broker_addr(),
+            ..Default::default()
+        };
+        let flapping = ClientTestProperties {
+            mqtt_version: 3,
+            client_id: unique_id(),
+            addr: broker_addr(),
+            ..Default::default()
+        };
+
+        for _i in 0..21 {
+            test_correct_connect(&allowed);
+            test_correct_connect(&flapping);
+        }
+
+        // only the client that is not allowlisted gets banned
+        test_correct_connect(&allowed);
+        test_fail_connect(&flapping);
+
+        close_flapping_detect().await;
+    }
+
     fn test_fail_connect(client_test_properties: &ClientTestProperties) {
         let create_opts = build_create_conn_pros(
             &client_test_properties.client_id,
@@ -81,7 +112,7 @@ fn test_correct_connect(client_test_properties: &ClientTestProperties) {
         distinct_conn(cli);
     }
 
-    async fn open_flapping_detect() {
+    async fn open_flapping_detect(allowlist: Vec<String>) {
         let client_pool = Arc::new(ClientPool::new(3));
         let grpc_addr = vec![broker_grpc_addr()];
 
@@ -90,6 +121,10 @@ async fn open_flapping_detect() {
             window_time: 60,
             max_client_connections: 20,
             ban_time: 1,
+            window_seconds: 0,
+            max_disconnects: 0,
+            ban_seconds: 0,
+            allowlist,
         };
 
         let reply = mqtt_broker_enable_flapping_detect(&client_pool, &grpc_addr, request).await;
@@ -105,6 +140,10 @@ async fn close_flapping_detect() {
             window_time: 60,
             max_client_connections: 20,
             ban_time: 1,
+            window_seconds: 0,
+            max_disconnects: 0,
+            ban_seconds: 0,
+            allowlist: Vec::new(),
         };
 
         let reply = mqtt_broker_enable_flapping_detect(&client_pool, &grpc_addr, request).await;
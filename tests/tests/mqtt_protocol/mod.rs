@@ -38,9 +38,11 @@ pub struct ClientTestProperties {
 pub mod connect_test;
 pub mod content_type_test;
 pub mod delay_publish_test;
+pub mod disconnect_client_test;
 mod flapping_detect_test;
 pub mod keep_alive_test;
 pub mod lastwill_message_test;
+pub mod node_draining_test;
 pub mod qos_test;
 pub mod req_resp_test;
 pub mod retain_message_test;
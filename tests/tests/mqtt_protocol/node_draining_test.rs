@@ -0,0 +1,106 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use crate::mqtt_protocol::common::{
+        broker_addr, broker_grpc_addr, connect_server, distinct_conn,
+    };
+    use crate::mqtt_protocol::ClientTestProperties;
+    use common_base::tools::unique_id;
+    use grpc_clients::mqtt::admin::call::{
+        mqtt_broker_cluster_status, mqtt_broker_list_connection, mqtt_broker_set_node_draining,
+    };
+    use grpc_clients::pool::ClientPool;
+    use protocol::broker_mqtt::broker_mqtt_admin::{
+        ClusterStatusRequest, ListConnectionRequest, SetNodeDrainingRequest,
+    };
+    use std::sync::Arc;
+
+    #[ignore = "reason"]
+    #[tokio::test]
+    async fn node_draining_test() {
+        let client_pool = Arc::new(ClientPool::new(3));
+        let grpc_addr = vec![broker_grpc_addr()];
+
+        let client_id = unique_id();
+        let cli = connect_server(&ClientTestProperties {
+            mqtt_version: 5,
+            client_id: client_id.clone(),
+            addr: broker_addr(),
+            ..Default::default()
+        });
+        assert_eq!(connection_num(&client_id).await, 1);
+
+        let reply = mqtt_broker_set_node_draining(
+            &client_pool,
+            &grpc_addr,
+            SetNodeDrainingRequest { draining: true },
+        )
+        .await
+        .unwrap();
+        assert!(!reply.previous_draining);
+        assert!(reply.draining);
+
+        let status = mqtt_broker_cluster_status(&client_pool, &grpc_addr, ClusterStatusRequest {})
+            .await
+            .unwrap();
+        assert!(status.nodes.iter().any(|node| node.draining));
+
+        // new connections are refused
+        for mqtt_version in [4, 5] {
+            connect_server(&ClientTestProperties {
+                mqtt_version,
+                client_id: unique_id(),
+                addr: broker_addr(),
+                conn_is_err: true,
+                ..Default::default()
+            });
+        }
+
+        // the existing connection keeps working
+        assert!(cli.is_connected());
+        assert_eq!(connection_num(&client_id).await, 1);
+
+        mqtt_broker_set_node_draining(
+            &client_pool,
+            &grpc_addr,
+            SetNodeDrainingRequest { draining: false },
+        )
+        .await
+        .unwrap();
+        let new_cli = connect_server(&ClientTestProperties {
+            mqtt_version: 5,
+            client_id: unique_id(),
+            addr: broker_addr(),
+            ..Default::default()
+        });
+        distinct_conn(new_cli);
+        distinct_conn(cli);
+    }
+
+    async fn connection_num(client_id: &str) -> usize {
+        let client_pool = Arc::new(ClientPool::new(3));
+        let grpc_addr = vec![broker_grpc_addr()];
+        let request = ListConnectionRequest {
+            client_id: Some(client_id.to_string()),
+            ..Default::default()
+        };
+        mqtt_broker_list_connection(&client_pool, &grpc_addr, request)
+            .await
+            .unwrap()
+            .list_connection_raw
+            .len()
+    }
+}
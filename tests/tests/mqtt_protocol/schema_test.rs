@@ -192,6 +192,7 @@ async fn create_schema(
             schema_type,
             schema,
             desc: "".to_string(),
+            message_name: "".to_string(),
         };
         let res = mqtt_broker_create_schema(&client_pool, &addrs, user.clone()).await;
         assert!(res.is_ok());
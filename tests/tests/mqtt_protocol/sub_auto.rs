@@ -19,13 +19,14 @@ mod tests {
     use common_base::tools::unique_id;
     use grpc_clients::{
         mqtt::admin::call::{
-            mqtt_broker_delete_auto_subscribe_rule, mqtt_broker_set_auto_subscribe_rule,
+            mqtt_broker_delete_auto_subscribe_rule, mqtt_broker_describe_subscription,
+            mqtt_broker_set_auto_subscribe_rule,
         },
         pool::ClientPool,
     };
     use paho_mqtt::{Message, QOS_1};
     use protocol::broker_mqtt::broker_mqtt_admin::{
-        DeleteAutoSubscribeRuleRequest, SetAutoSubscribeRuleRequest,
+        DeleteAutoSubscribeRuleRequest, DescribeSubscriptionRequest, SetAutoSubscribeRuleRequest,
     };
 
     use crate::mqtt_protocol::{
@@ -85,6 +86,76 @@ async fn sub_auto_test() {
         delete_auto_subscribe_rule(&client_pool, grpc_addr.clone(), &topic).await;
     }
 
+    #[tokio::test]
+    async fn sub_auto_options_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let grpc_addr = vec![broker_grpc_addr()];
+
+        let topic = format!("/tests/options/{}", unique_id());
+        let request = SetAutoSubscribeRuleRequest {
+            topic: topic.clone(),
+            qos: 2,
+            no_local: true,
+            retain_as_published: true,
+            retained_handling: 2,
+            priority: 0,
+        };
+        let res = mqtt_broker_set_auto_subscribe_rule(&client_pool, &grpc_addr, request).await;
+        assert!(res.is_ok());
+
+        // QoS and retained handling are limited to 0, 1 and 2
+        let request = SetAutoSubscribeRuleRequest {
+            topic: topic.clone(),
+            qos: 3,
+            ..Default::default()
+        };
+        let res = mqtt_broker_set_auto_subscribe_rule(&client_pool, &grpc_addr, request).await;
+        assert!(res.is_err());
+        let request = SetAutoSubscribeRuleRequest {
+            topic: topic.clone(),
+            retained_handling: 3,
+            ..Default::default()
+        };
+        let res = mqtt_broker_set_auto_subscribe_rule(&client_pool, &grpc_addr, request).await;
+        assert!(res.is_err());
+
+        let network = "tcp";
+        let client_id = build_client_id(format!("sub_auto_options_test_{}", network).as_str());
+        let client_properties = ClientTestProperties {
+            mqtt_version: 5,
+            client_id: client_id.to_string(),
+            addr: broker_addr_by_type(network),
+            ws: ws_by_type(network),
+            ssl: ssl_by_type(network),
+            ..Default::default()
+        };
+        let cli = connect_server(&client_properties);
+
+        // the subscription carries the options of the rule as they were set
+        let request = DescribeSubscriptionRequest {
+            client_id: client_id.clone(),
+            path: topic.clone(),
+        };
+        let mut reply = None;
+        for _ in 0..50 {
+            if let Ok(res) =
+                mqtt_broker_describe_subscription(&client_pool, &grpc_addr, request.clone()).await
+            {
+                reply = Some(res);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let reply = reply.expect("auto subscription was not created");
+        assert_eq!(reply.granted_qos, 2);
+        assert!(reply.no_local);
+        assert!(reply.preserve_retain);
+        assert_eq!(reply.retain_handling, 2);
+
+        distinct_conn(cli);
+        delete_auto_subscribe_rule(&client_pool, grpc_addr.clone(), &topic).await;
+    }
+
     async fn create_auto_subscribe_rule(
         client_pool: &Arc<ClientPool>,
         grpc_addr: Vec<String>,
@@ -96,6 +167,7 @@ async fn create_auto_subscribe_rule(
             no_local: false,
             retain_as_published: false,
             retained_handling: 0,
+            priority: 0,
         };
         let res = mqtt_broker_set_auto_subscribe_rule(client_pool, &grpc_addr, request).await;
         assert!(res.is_ok());
@@ -29,6 +29,7 @@ fn test_read_slow_record_path_is_empty() {
             topic: "".to_string(),
             list: 5,
             sort: "asc".to_string(),
+            threshold_ms: 0,
         };
         let result = read_slow_sub_record(record, path_buf).unwrap();
         assert_eq!(0, result.clone().len())
@@ -44,6 +45,7 @@ fn test_read_slow_record_param_is_one_1() {
             topic: "".to_string(),
             list: 5,
             sort: "asc".to_string(),
+            threshold_ms: 0,
         };
 
         let result = read_slow_sub_record(record, path_buf).unwrap();
@@ -81,6 +83,7 @@ fn test_read_slow_record_param_is_one_2() {
             topic: "".to_string(),
             list: 5,
             sort: "asc".to_string(),
+            threshold_ms: 0,
         };
 
         let result = read_slow_sub_record(record, path_buf).unwrap();
@@ -118,6 +121,7 @@ fn test_read_slow_record_param_is_one_3() {
             topic: "/request/131edb8526804e80b32b387fa2340d35".to_string(),
             list: 5,
             sort: "asc".to_string(),
+            threshold_ms: 0,
         };
 
         let result = read_slow_sub_record(record, path_buf).unwrap();
@@ -151,6 +155,7 @@ fn test_read_slow_record_param_is_two() {
             topic: "".to_string(),
             list: 5,
             sort: "asc".to_string(),
+            threshold_ms: 0,
         };
 
         let result = read_slow_sub_record(record, path_buf).unwrap();
@@ -188,6 +193,7 @@ fn test_read_slow_record_param_is_three() {
             topic: "/packet_tcp_ssl/7fce56aa49ef4cea90dc4be77d6a775e".to_string(),
             list: 5,
             sort: "asc".to_string(),
+            threshold_ms: 0,
         };
 
         let result = read_slow_sub_record(record, path_buf).unwrap();
@@ -64,6 +64,35 @@ async fn session_test() {
             .unwrap();
         assert_eq!(result.broker_id.unwrap(), 3);
         assert_eq!(result.connection_id.unwrap(), 3);
+        assert_eq!(result.takeover_count, 0);
+        assert!(result.last_takeover_at.is_none());
+
+        let first_takeover = now_second();
+        session_storage
+            .takeover_session(client_id.clone(), 4, 3, first_takeover)
+            .await
+            .unwrap();
+        let result = session_storage
+            .get_session(client_id.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.connection_id.unwrap(), 4);
+        assert_eq!(result.takeover_count, 1);
+        assert_eq!(result.last_takeover_at, Some(first_takeover));
+
+        session_storage
+            .takeover_session(client_id.clone(), 5, 3, first_takeover + 10)
+            .await
+            .unwrap();
+        let result = session_storage
+            .get_session(client_id.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.connection_id.unwrap(), 5);
+        assert_eq!(result.takeover_count, 2);
+        assert_eq!(result.last_takeover_at, Some(first_takeover + 10));
 
         let result = session_storage.list_session().await.unwrap();
         let prefix_len = result.len();
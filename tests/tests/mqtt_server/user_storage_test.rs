@@ -34,6 +34,7 @@ async fn user_test() {
             username: username.clone(),
             password: password.clone(),
             is_superuser,
+            create_time: 0,
         };
         user_storage.save_user(user_info).await.unwrap();
 